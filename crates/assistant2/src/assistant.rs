@@ -33,8 +33,12 @@ use settings::Settings as _;
 
 pub use crate::active_thread::ActiveThread;
 pub use crate::assistant_panel::{AssistantPanel, ConcreteAssistantPanelDelegate};
+pub use crate::context::{DirectoryContextMode, DirectoryContextOptions};
+pub use crate::context_store::{build_directory_context, ContextStore};
 pub use crate::inline_assistant::InlineAssistant;
-pub use crate::thread::{Message, RequestKind, Thread, ThreadEvent};
+pub use crate::thread::{
+    Message, RequestKind, Thread, ThreadEvent, ThreadTextFormat, ThreadTextOptions,
+};
 pub use crate::thread_store::ThreadStore;
 
 actions!(
@@ -44,6 +48,7 @@ actions!(
         NewPromptEditor,
         ToggleContextPicker,
         RemoveAllContext,
+        UndoContextChange,
         OpenHistory,
         OpenConfiguration,
         RemoveSelectedThread,
@@ -57,7 +62,9 @@ actions!(
         FocusRight,
         RemoveFocusedContext,
         AcceptSuggestedContext,
-        OpenActiveThreadAsMarkdown
+        OpenActiveThreadAsMarkdown,
+        ToggleContextSelection,
+        PreviewDirectoryContext
     ]
 );
 
@@ -33,6 +33,7 @@ use zed_actions::assistant::{DeployPromptLibrary, ToggleFocus};
 
 use crate::active_thread::ActiveThread;
 use crate::assistant_configuration::{AssistantConfiguration, AssistantConfigurationEvent};
+use crate::context_store::ContextStore;
 use crate::history_store::{HistoryEntry, HistoryStore};
 use crate::message_editor::MessageEditor;
 use crate::thread::{Thread, ThreadError, ThreadId};
@@ -231,6 +232,13 @@ impl AssistantPanel {
         &self.thread_store
     }
 
+    /// The context attached to the next message of the active thread. Other crates attach
+    /// context through it with [`ContextStore::attach_directory`] and
+    /// [`ContextStore::attach_thread`].
+    pub fn message_context_store(&self, cx: &App) -> Entity<ContextStore> {
+        self.message_editor.read(cx).context_store().clone()
+    }
+
     fn cancel(
         &mut self,
         _: &editor::actions::Cancel,
@@ -245,7 +253,15 @@ impl AssistantPanel {
         let thread = self
             .thread_store
             .update(cx, |this, cx| this.create_thread(cx));
+        self.set_active_thread(thread, window, cx);
+    }
 
+    fn set_active_thread(
+        &mut self,
+        thread: Entity<Thread>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
         self.active_view = ActiveView::Thread;
 
         let message_editor_context_store =
@@ -385,31 +401,26 @@ impl AssistantPanel {
         cx.spawn_in(window, |this, mut cx| async move {
             let thread = open_thread_task.await?;
             this.update_in(&mut cx, |this, window, cx| {
-                this.active_view = ActiveView::Thread;
-                let message_editor_context_store =
-                    cx.new(|_cx| crate::context_store::ContextStore::new(this.workspace.clone()));
-                this.thread = cx.new(|cx| {
-                    ActiveThread::new(
-                        thread.clone(),
-                        this.thread_store.clone(),
-                        this.language_registry.clone(),
-                        message_editor_context_store.clone(),
-                        window,
-                        cx,
-                    )
-                });
-                this.message_editor = cx.new(|cx| {
-                    MessageEditor::new(
-                        this.fs.clone(),
-                        this.workspace.clone(),
-                        message_editor_context_store,
-                        this.thread_store.downgrade(),
-                        thread,
-                        window,
-                        cx,
-                    )
-                });
-                this.message_editor.focus_handle(cx).focus(window);
+                this.set_active_thread(thread, window, cx);
+            })
+        })
+    }
+
+    /// Continues a saved thread in a copy of it, leaving the original as it was.
+    pub(crate) fn fork_thread(
+        &mut self,
+        thread_id: &ThreadId,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<()>> {
+        let fork_thread_task = self
+            .thread_store
+            .update(cx, |this, cx| this.fork_thread(thread_id, cx));
+
+        cx.spawn_in(window, |this, mut cx| async move {
+            let thread = fork_thread_task.await?;
+            this.update_in(&mut cx, |this, window, cx| {
+                this.set_active_thread(thread, window, cx);
             })
         })
     }
@@ -1,16 +1,21 @@
+use std::ops::Range;
 use std::path::Path;
 use std::rc::Rc;
+use std::sync::Arc;
 
 use file_icons::FileIcons;
 use gpui::{App, Entity, SharedString};
 use language::Buffer;
 use language_model::{LanguageModelRequestMessage, MessageContent};
+use project::ProjectPath;
 use serde::{Deserialize, Serialize};
 use text::BufferId;
 use ui::IconName;
-use util::post_inc;
+use util::paths::PathMatcher;
+use util::{post_inc, ResultExt as _};
 
-use crate::{context_store::buffer_path_log_err, thread::Thread};
+use crate::context_store::buffer_path_log_err;
+use crate::thread::{Thread, ThreadId, ThreadTextOptions};
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Serialize, Deserialize)]
 pub struct ContextId(pub(crate) usize);
@@ -29,7 +34,12 @@ pub struct ContextSnapshot {
     pub parent: Option<SharedString>,
     pub tooltip: Option<SharedString>,
     pub icon_path: Option<SharedString>,
+    /// A short summary shown after the name, such as a directory's file count and size.
+    pub detail: Option<SharedString>,
     pub kind: ContextKind,
+    /// The address fetched context came from. Given to the model along with the text, since the
+    /// name may be the page's title.
+    pub url: Option<SharedString>,
     /// Joining these strings separated by \n yields text for model. Not refreshed by `snapshot`.
     pub text: Box<[SharedString]>,
 }
@@ -40,25 +50,58 @@ pub enum ContextKind {
     Directory,
     FetchedUrl,
     Thread,
+    GitDiff,
 }
 
 impl ContextKind {
+    pub const ALL: [ContextKind; 5] = [
+        ContextKind::File,
+        ContextKind::Directory,
+        ContextKind::GitDiff,
+        ContextKind::FetchedUrl,
+        ContextKind::Thread,
+    ];
+
+    /// The name of context of this kind, e.g. when filtering attached context by kind.
+    pub fn plural_label(&self) -> &'static str {
+        match self {
+            ContextKind::File => "Files",
+            ContextKind::Directory => "Directories",
+            ContextKind::FetchedUrl => "Web Pages",
+            ContextKind::Thread => "Threads",
+            ContextKind::GitDiff => "Git Diffs",
+        }
+    }
+
     pub fn icon(&self) -> IconName {
         match self {
             ContextKind::File => IconName::File,
             ContextKind::Directory => IconName::Folder,
             ContextKind::FetchedUrl => IconName::Globe,
             ContextKind::Thread => IconName::MessageCircle,
+            ContextKind::GitDiff => IconName::FileDiff,
         }
     }
 }
 
+/// Where attached context came from, so it can be found again, e.g. to reveal an attached
+/// directory in the project panel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContextProvenance {
+    File(ProjectPath),
+    Directory(ProjectPath),
+    FetchedUrl(SharedString),
+    Thread(ThreadId),
+    GitDiff(ProjectPath),
+}
+
 #[derive(Debug)]
 pub enum AssistantContext {
     File(FileContext),
     Directory(DirectoryContext),
     FetchedUrl(FetchedUrlContext),
     Thread(ThreadContext),
+    GitDiff(GitDiffContext),
 }
 
 impl AssistantContext {
@@ -68,6 +111,30 @@ impl AssistantContext {
             Self::Directory(directory) => directory.snapshot.id,
             Self::FetchedUrl(url) => url.id,
             Self::Thread(thread) => thread.id,
+            Self::GitDiff(diff) => diff.id,
+        }
+    }
+
+    pub fn kind(&self) -> ContextKind {
+        match self {
+            Self::File(_) => ContextKind::File,
+            Self::Directory(_) => ContextKind::Directory,
+            Self::FetchedUrl(_) => ContextKind::FetchedUrl,
+            Self::Thread(_) => ContextKind::Thread,
+            Self::GitDiff(_) => ContextKind::GitDiff,
+        }
+    }
+
+    /// The number of bytes of text this context contributes to a message.
+    pub fn text_len(&self) -> usize {
+        match self {
+            Self::File(file) => file.context_buffer.text.len(),
+            Self::Directory(directory) => {
+                directory.snapshot.text.iter().map(|text| text.len()).sum()
+            }
+            Self::FetchedUrl(url) => url.text.len(),
+            Self::Thread(thread) => thread.text.len(),
+            Self::GitDiff(diff) => diff.text.len(),
         }
     }
 }
@@ -76,12 +143,103 @@ impl AssistantContext {
 pub struct FileContext {
     pub id: ContextId,
     pub context_buffer: ContextBuffer,
+    /// The zero-based rows attached, when only part of the file was attached.
+    pub line_range: Option<Range<u32>>,
+}
+
+/// The default maximum number of bytes of file contents captured when attaching a directory.
+pub const DEFAULT_DIRECTORY_CONTEXT_MAX_BYTES: usize = 256 * 1024;
+
+/// The default maximum number of bytes read from each file when attaching a directory.
+pub const DEFAULT_DIRECTORY_CONTEXT_MAX_FILE_BYTES: usize = 64 * 1024;
+
+/// Files skipped by default when attaching a directory: lockfiles, minified bundles, snapshots
+/// and vendored dependencies rarely tell the model anything about the code.
+pub const DEFAULT_DIRECTORY_CONTEXT_EXCLUDE_GLOBS: &[&str] = &[
+    "**/*.lock",
+    "**/package-lock.json",
+    "**/pnpm-lock.yaml",
+    "**/*.min.js",
+    "**/*.min.css",
+    "**/*.snap",
+    "**/__snapshots__/**",
+    "**/node_modules/**",
+    "**/vendor/**",
+    "**/third_party/**",
+];
+
+/// Matches the files in [`DEFAULT_DIRECTORY_CONTEXT_EXCLUDE_GLOBS`].
+pub fn default_directory_context_exclude() -> PathMatcher {
+    PathMatcher::new(
+        &DEFAULT_DIRECTORY_CONTEXT_EXCLUDE_GLOBS
+            .iter()
+            .map(|glob| glob.to_string())
+            .collect::<Vec<_>>(),
+    )
+    .log_err()
+    .unwrap_or_default()
+}
+
+/// What an attached directory contributes to a message.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DirectoryContextMode {
+    /// The contents of the directory's files.
+    #[default]
+    Contents,
+    /// Only the paths and sizes of the directory's files, for folders too large to inline.
+    FileList,
+}
+
+/// How a directory's files are walked and read when it is attached.
+#[derive(Debug, Clone)]
+pub struct DirectoryContextOptions {
+    pub mode: DirectoryContextMode,
+    /// The budget for the file contents included in the text for the model.
+    pub max_bytes: usize,
+    /// The number of bytes read from each file, independent of `max_bytes`.
+    pub max_file_bytes: usize,
+    /// Files matching these patterns are skipped.
+    pub exclude: PathMatcher,
+    /// Whether files in subdirectories are included, rather than only the directory's own files.
+    pub recursive: bool,
+    /// Whether symlinked subdirectories are descended into.
+    pub follow_symlinks: bool,
+}
+
+impl Default for DirectoryContextOptions {
+    fn default() -> Self {
+        Self {
+            mode: DirectoryContextMode::Contents,
+            max_bytes: DEFAULT_DIRECTORY_CONTEXT_MAX_BYTES,
+            max_file_bytes: DEFAULT_DIRECTORY_CONTEXT_MAX_FILE_BYTES,
+            exclude: default_directory_context_exclude(),
+            recursive: true,
+            follow_symlinks: true,
+        }
+    }
 }
 
 #[derive(Debug)]
 pub struct DirectoryContext {
     pub path: Rc<Path>,
+    pub mode: DirectoryContextMode,
+    /// The files whose contents are included. Empty when only the file list is attached.
     pub context_buffers: Vec<ContextBuffer>,
+    /// The paths and sizes of the listed files. Empty when the contents are attached.
+    pub listed_files: Vec<(Arc<Path>, u64)>,
+    /// The budget for the file contents included in the text for the model.
+    pub max_bytes: usize,
+    /// The number of bytes read from each file, independent of `max_bytes`.
+    pub max_file_bytes: usize,
+    /// The number of files skipped because they matched an exclude pattern.
+    pub excluded_files: usize,
+    /// The number of files skipped because they were already attached on their own.
+    pub attached_files: usize,
+    /// Whether files in subdirectories are included, rather than only the directory's own files.
+    pub recursive: bool,
+    /// The walk options the files were collected with, kept so a refresh re-walks the same way.
+    pub exclude: PathMatcher,
+    pub follow_symlinks: bool,
     pub snapshot: ContextSnapshot,
 }
 
@@ -89,6 +247,23 @@ pub struct DirectoryContext {
 pub struct FetchedUrlContext {
     pub id: ContextId,
     pub url: SharedString,
+    /// The page's title, shown instead of the URL when the page has one.
+    pub title: Option<SharedString>,
+    /// The page's text, cut down to the same budget as an attached directory's file contents.
+    pub text: SharedString,
+    /// The number of bytes of the page's text that were kept.
+    pub captured_bytes: usize,
+    /// The length of the page's text before it was cut down.
+    pub page_bytes: usize,
+}
+
+/// The uncommitted changes to a file, as a unified diff against `HEAD`.
+#[derive(Debug)]
+pub struct GitDiffContext {
+    pub id: ContextId,
+    pub project_path: ProjectPath,
+    /// The path of the file including its worktree's root name.
+    pub full_path: Arc<Path>,
     pub text: SharedString,
 }
 
@@ -99,6 +274,8 @@ pub struct FetchedUrlContext {
 pub struct ThreadContext {
     pub id: ContextId,
     pub thread: Entity<Thread>,
+    /// How the thread is turned into `text`, kept so refreshes produce the same shape.
+    pub text_options: ThreadTextOptions,
     pub text: SharedString,
 }
 
@@ -113,6 +290,46 @@ pub struct ContextBuffer {
     pub text: SharedString,
 }
 
+pub(crate) fn format_size(bytes: u64) -> String {
+    const KB: u64 = 1024;
+    const MB: u64 = KB * 1024;
+
+    if bytes >= MB {
+        format!("{:.1} MB", bytes as f64 / MB as f64)
+    } else if bytes >= KB {
+        format!("{:.1} KB", bytes as f64 / KB as f64)
+    } else {
+        format!("{bytes} B")
+    }
+}
+
+/// The end of the whole lines of `text` within `max_bytes`.
+fn whole_lines_end(text: &str, max_bytes: usize) -> usize {
+    let mut end = max_bytes.min(text.len());
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    text[..end].rfind('\n').map_or(0, |ix| ix + 1)
+}
+
+/// Cuts a file's fenced code block down to the whole lines within `max_bytes`, keeping at least
+/// its opening fence, and closes the fence after a note about the cut.
+fn truncate_code_block(text: &str, max_bytes: usize) -> SharedString {
+    let opening_fence_end = text.find('\n').map_or(text.len(), |ix| ix + 1);
+    let end = whole_lines_end(text, max_bytes).max(opening_fence_end);
+    format!(
+        "{}… (file truncated at {max_bytes} bytes)\n```\n",
+        &text[..end]
+    )
+    .into()
+}
+
+/// Roughly estimates the number of tokens in `text_len` bytes of text, assuming about four bytes
+/// per token.
+pub(crate) fn estimate_token_count(text_len: usize) -> usize {
+    text_len.div_ceil(4)
+}
+
 impl AssistantContext {
     pub fn snapshot(&self, cx: &App) -> Option<ContextSnapshot> {
         match &self {
@@ -120,6 +337,7 @@ impl AssistantContext {
             Self::Directory(directory_context) => Some(directory_context.snapshot()),
             Self::FetchedUrl(fetched_url_context) => Some(fetched_url_context.snapshot()),
             Self::Thread(thread_context) => Some(thread_context.snapshot(cx)),
+            Self::GitDiff(git_diff_context) => Some(git_diff_context.snapshot()),
         }
     }
 }
@@ -146,17 +364,41 @@ impl FileContext {
             parent,
             tooltip: Some(full_path),
             icon_path,
+            detail: self
+                .line_range
+                .as_ref()
+                .map(|range| format!("L{}-{}", range.start + 1, range.end).into()),
             kind: ContextKind::File,
+            url: None,
             text: Box::new([self.context_buffer.text.clone()]),
         })
     }
 }
 
 impl DirectoryContext {
+    /// The options the directory was attached with, for reading it again the same way.
+    pub fn options(&self) -> DirectoryContextOptions {
+        DirectoryContextOptions {
+            mode: self.mode,
+            max_bytes: self.max_bytes,
+            max_file_bytes: self.max_file_bytes,
+            exclude: self.exclude.clone(),
+            recursive: self.recursive,
+            follow_symlinks: self.follow_symlinks,
+        }
+    }
+
     pub fn new(
         id: ContextId,
         path: &Path,
         context_buffers: Vec<ContextBuffer>,
+        max_bytes: usize,
+        max_file_bytes: usize,
+        excluded_files: usize,
+        attached_files: usize,
+        recursive: bool,
+        exclude: PathMatcher,
+        follow_symlinks: bool,
     ) -> DirectoryContext {
         let full_path: SharedString = path.to_string_lossy().into_owned().into();
 
@@ -171,23 +413,180 @@ impl DirectoryContext {
             .map(|p| p.to_string_lossy().into_owned().into());
 
         // TODO: include directory path in text?
-        let text = context_buffers
+        let mut text = Vec::with_capacity(context_buffers.len());
+        let mut captured_bytes = 0;
+        for (ix, context_buffer) in context_buffers.iter().enumerate() {
+            if captured_bytes + context_buffer.text.len() > max_bytes {
+                let mut omitted_files = context_buffers.len() - ix;
+                // A first file larger than the whole budget is cut down to it, rather than
+                // leaving the directory without any content.
+                if ix == 0 && context_buffer.text.starts_with("```") {
+                    let truncated = truncate_code_block(&context_buffer.text, max_bytes);
+                    captured_bytes += truncated.len();
+                    text.push(truncated);
+                    omitted_files -= 1;
+                }
+                if omitted_files > 0 {
+                    text.push(
+                        format!(
+                            "[directory context truncated at {} bytes, {} of {} files omitted]\n",
+                            max_bytes,
+                            omitted_files,
+                            context_buffers.len()
+                        )
+                        .into(),
+                    );
+                }
+                break;
+            }
+
+            captured_bytes += context_buffer.text.len();
+            text.push(context_buffer.text.clone());
+        }
+        if excluded_files > 0 {
+            text.push(
+                format!(
+                    "[{} {} skipped by exclude patterns]\n",
+                    excluded_files,
+                    if excluded_files == 1 { "file" } else { "files" }
+                )
+                .into(),
+            );
+        }
+        if attached_files > 0 {
+            text.push(
+                format!(
+                    "[{} {} skipped, already attached on {}]\n",
+                    attached_files,
+                    if attached_files == 1 { "file" } else { "files" },
+                    if attached_files == 1 {
+                        "its own"
+                    } else {
+                        "their own"
+                    }
+                )
+                .into(),
+            );
+        }
+
+        let total_bytes = context_buffers
             .iter()
-            .map(|b| b.text.clone())
-            .collect::<Vec<_>>()
-            .into();
+            .map(|context_buffer| context_buffer.text.len())
+            .sum::<usize>();
+        let tooltip = if captured_bytes < total_bytes {
+            format!("{full_path} ({captured_bytes} of {total_bytes} bytes captured)").into()
+        } else {
+            full_path
+        };
+        let detail = format!(
+            "{} {}, {}",
+            context_buffers.len(),
+            if context_buffers.len() == 1 {
+                "file"
+            } else {
+                "files"
+            },
+            format_size(captured_bytes as u64)
+        )
+        .into();
 
         DirectoryContext {
             path: path.into(),
+            mode: DirectoryContextMode::Contents,
             context_buffers,
+            listed_files: Vec::new(),
+            max_bytes,
+            max_file_bytes,
+            excluded_files,
+            attached_files,
+            recursive,
+            exclude,
+            follow_symlinks,
             snapshot: ContextSnapshot {
                 id,
                 name,
                 parent,
-                tooltip: Some(full_path),
+                tooltip: Some(tooltip),
                 icon_path: None,
+                detail: Some(detail),
                 kind: ContextKind::Directory,
-                text,
+                url: None,
+                text: text.into(),
+            },
+        }
+    }
+
+    /// A directory whose text lists its files' paths and sizes, without their contents.
+    pub fn file_list(
+        id: ContextId,
+        path: &Path,
+        listed_files: Vec<(Arc<Path>, u64)>,
+        excluded_files: usize,
+        recursive: bool,
+        exclude: PathMatcher,
+        follow_symlinks: bool,
+    ) -> DirectoryContext {
+        let full_path: SharedString = path.to_string_lossy().into_owned().into();
+
+        let name = match path.file_name() {
+            Some(name) => name.to_string_lossy().into_owned().into(),
+            None => full_path.clone(),
+        };
+
+        let parent = path
+            .parent()
+            .and_then(|p| p.file_name())
+            .map(|p| p.to_string_lossy().into_owned().into());
+
+        let mut text = format!("Files in {full_path} (contents not included):\n");
+        for (file_path, size) in &listed_files {
+            text.push_str(&format!(
+                "{} ({})\n",
+                file_path.to_string_lossy(),
+                format_size(*size)
+            ));
+        }
+        if excluded_files > 0 {
+            text.push_str(&format!(
+                "[{} {} skipped by exclude patterns]\n",
+                excluded_files,
+                if excluded_files == 1 { "file" } else { "files" }
+            ));
+        }
+
+        let detail = format!(
+            "{} {} listed",
+            listed_files.len(),
+            if listed_files.len() == 1 {
+                "file"
+            } else {
+                "files"
+            }
+        )
+        .into();
+
+        DirectoryContext {
+            path: path.into(),
+            mode: DirectoryContextMode::FileList,
+            context_buffers: Vec::new(),
+            listed_files,
+            max_bytes: 0,
+            max_file_bytes: 0,
+            excluded_files,
+            attached_files: 0,
+            recursive,
+            exclude,
+            follow_symlinks,
+            snapshot: ContextSnapshot {
+                id,
+                name,
+                parent,
+                tooltip: Some(format!("{full_path} (file list only)").into()),
+                icon_path: None,
+                detail: Some(detail),
+                kind: ContextKind::Directory,
+                url: None,
+                text: Box::new([text.into()]),
             },
         }
     }
@@ -198,14 +597,59 @@ impl DirectoryContext {
 }
 
 impl FetchedUrlContext {
+    /// A fetched page whose text is cut down to the whole lines within `max_bytes`, the way the
+    /// contents of an attached directory are.
+    pub fn new(
+        id: ContextId,
+        url: SharedString,
+        title: Option<SharedString>,
+        page_text: &str,
+        max_bytes: usize,
+    ) -> FetchedUrlContext {
+        let (text, captured_bytes) = if page_text.len() > max_bytes {
+            let end = whole_lines_end(page_text, max_bytes);
+            (
+                format!(
+                    "{}[page truncated at {max_bytes} bytes]\n",
+                    &page_text[..end]
+                )
+                .into(),
+                end,
+            )
+        } else {
+            (page_text.to_string().into(), page_text.len())
+        };
+
+        FetchedUrlContext {
+            id,
+            url,
+            title,
+            text,
+            captured_bytes,
+            page_bytes: page_text.len(),
+        }
+    }
+
     pub fn snapshot(&self) -> ContextSnapshot {
+        let tooltip = if self.captured_bytes < self.page_bytes {
+            format!(
+                "{} ({} of {} bytes captured)",
+                self.url, self.captured_bytes, self.page_bytes
+            )
+            .into()
+        } else {
+            self.url.clone()
+        };
+
         ContextSnapshot {
             id: self.id,
-            name: self.url.clone(),
+            name: self.title.clone().unwrap_or_else(|| self.url.clone()),
             parent: None,
-            tooltip: None,
+            tooltip: Some(tooltip),
             icon_path: None,
+            detail: None,
             kind: ContextKind::FetchedUrl,
+            url: Some(self.url.clone()),
             text: Box::new([self.text.clone()]),
         }
     }
@@ -220,7 +664,36 @@ impl ThreadContext {
             parent: None,
             tooltip: None,
             icon_path: None,
+            detail: self.text_options.summary_only.then(|| "summary".into()),
             kind: ContextKind::Thread,
+            url: None,
+            text: Box::new([self.text.clone()]),
+        }
+    }
+}
+
+impl GitDiffContext {
+    pub fn snapshot(&self) -> ContextSnapshot {
+        let full_path = self.full_path.to_string_lossy().into_owned();
+        let name = match self.full_path.file_name() {
+            Some(name) => name.to_string_lossy().into_owned().into(),
+            None => full_path.clone().into(),
+        };
+        let parent = self
+            .full_path
+            .parent()
+            .and_then(|p| p.file_name())
+            .map(|p| p.to_string_lossy().into_owned().into());
+
+        ContextSnapshot {
+            id: self.id,
+            name,
+            parent,
+            tooltip: Some(format!("{full_path} (uncommitted changes)").into()),
+            icon_path: None,
+            detail: Some("diff".into()),
+            kind: ContextKind::GitDiff,
+            url: None,
             text: Box::new([self.text.clone()]),
         }
     }
@@ -234,6 +707,7 @@ pub fn attach_context_to_message(
     let mut directory_context = Vec::new();
     let mut fetch_context = Vec::new();
     let mut thread_context = Vec::new();
+    let mut git_diff_context = Vec::new();
 
     let mut capacity = 0;
     for context in contexts {
@@ -243,6 +717,7 @@ pub fn attach_context_to_message(
             ContextKind::Directory => directory_context.push(context),
             ContextKind::FetchedUrl => fetch_context.push(context),
             ContextKind::Thread => thread_context.push(context),
+            ContextKind::GitDiff => git_diff_context.push(context),
         }
     }
     if !file_context.is_empty() {
@@ -257,6 +732,9 @@ pub fn attach_context_to_message(
     if !thread_context.is_empty() {
         capacity += 1 + thread_context.len();
     }
+    if !git_diff_context.is_empty() {
+        capacity += 1;
+    }
     if capacity == 0 {
         return;
     }
@@ -284,7 +762,7 @@ pub fn attach_context_to_message(
     if !fetch_context.is_empty() {
         context_chunks.push("The following fetched results are available:\n");
         for context in &fetch_context {
-            context_chunks.push(&context.name);
+            context_chunks.push(context.url.as_ref().unwrap_or(&context.name));
             for chunk in &context.text {
                 context_chunks.push(&chunk);
             }
@@ -301,6 +779,15 @@ pub fn attach_context_to_message(
         }
     }
 
+    if !git_diff_context.is_empty() {
+        context_chunks.push("The following uncommitted changes are available:\n");
+        for context in &git_diff_context {
+            for chunk in &context.text {
+                context_chunks.push(&chunk);
+            }
+        }
+    }
+
     debug_assert!(
         context_chunks.len() == capacity,
         "attach_context_message calculated capacity of {}, but length was {}",
@@ -314,3 +801,52 @@ pub fn attach_context_to_message(
             .push(MessageContent::Text(context_chunks.join("\n")));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_token_count() {
+        assert_eq!(estimate_token_count("".len()), 0);
+        assert_eq!(estimate_token_count("abc".len()), 1);
+        assert_eq!(estimate_token_count("abcd".len()), 1);
+        assert_eq!(estimate_token_count("abcde".len()), 2);
+        assert_eq!(estimate_token_count("ééééé".len()), 3);
+    }
+
+    #[test]
+    fn test_fetched_url_text_is_cut_to_max_bytes() {
+        let context = FetchedUrlContext::new(
+            ContextId(0),
+            "https://zed.dev".into(),
+            Some("Zed".into()),
+            "one\ntwo\nthree\n",
+            10,
+        );
+        assert_eq!(
+            context.text.as_ref(),
+            "one\ntwo\n[page truncated at 10 bytes]\n"
+        );
+        let snapshot = context.snapshot();
+        assert_eq!(snapshot.url.as_deref(), Some("https://zed.dev"));
+        assert_eq!(
+            snapshot.tooltip.as_deref(),
+            Some("https://zed.dev (8 of 14 bytes captured)")
+        );
+    }
+
+    #[test]
+    fn test_truncate_code_block() {
+        let text = "```rs src/a.rs\none\ntwo\n```\n";
+        assert_eq!(
+            truncate_code_block(text, 22).as_ref(),
+            "```rs src/a.rs\none\n… (file truncated at 22 bytes)\n```\n"
+        );
+        // The opening fence is kept even when it alone is over the limit.
+        assert_eq!(
+            truncate_code_block(text, 4).as_ref(),
+            "```rs src/a.rs\n… (file truncated at 4 bytes)\n```\n"
+        );
+    }
+}
@@ -1,53 +1,89 @@
 mod fetch_context_picker;
 mod file_context_picker;
+mod git_changes_context_picker;
+mod open_buffer_context_picker;
 mod thread_context_picker;
+mod unified_context_picker;
 
 use std::path::PathBuf;
 use std::sync::Arc;
 
 use anyhow::{anyhow, Result};
+use assistant_context_editor::humanize_token_count;
+use assistant_settings::{AssistantSettings, ContextPickerConfirmBehavior};
 use editor::Editor;
 use file_context_picker::render_file_context_entry;
-use gpui::{App, DismissEvent, Entity, EventEmitter, FocusHandle, Focusable, Task, WeakEntity};
+use gpui::{
+    App, DismissEvent, Entity, EventEmitter, FocusHandle, Focusable, Subscription, Task, WeakEntity,
+};
+use language_model::LanguageModelRegistry;
 use project::ProjectPath;
+use settings::Settings as _;
 use thread_context_picker::{render_thread_context_entry, ThreadContextEntry};
-use ui::{prelude::*, ContextMenu, ContextMenuEntry, ContextMenuItem};
+use ui::{prelude::*, ContextMenu, ContextMenuEntry, ContextMenuItem, Tooltip};
 use workspace::{notifications::NotifyResultExt, Workspace};
 
+use crate::context::format_size;
 use crate::context_picker::fetch_context_picker::FetchContextPicker;
 use crate::context_picker::file_context_picker::FileContextPicker;
+use crate::context_picker::git_changes_context_picker::GitChangesContextPicker;
+use crate::context_picker::open_buffer_context_picker::OpenBufferContextPicker;
 use crate::context_picker::thread_context_picker::ThreadContextPicker;
+use crate::context_picker::unified_context_picker::UnifiedContextPicker;
 use crate::context_store::ContextStore;
+use crate::thread::{ThreadId, ThreadTextOptions};
 use crate::thread_store::ThreadStore;
 use crate::AssistantPanel;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ConfirmBehavior {
     KeepOpen,
     Close,
 }
 
+impl ConfirmBehavior {
+    /// The behavior chosen by the `assistant.context_picker_confirm_behavior` setting.
+    pub fn from_settings(cx: &App) -> Self {
+        match AssistantSettings::get_global(cx).context_picker_confirm_behavior {
+            ContextPickerConfirmBehavior::KeepOpen => Self::KeepOpen,
+            ContextPickerConfirmBehavior::Close => Self::Close,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum ContextPickerMode {
     File,
+    /// Buffers open in the workspace's editors, including their unsaved changes.
+    OpenBuffers,
+    /// Files with uncommitted changes in the project's git repositories.
+    GitChanges,
     Fetch,
     Thread,
+    /// Files and threads searched together.
+    All,
 }
 
 impl ContextPickerMode {
     pub fn label(&self) -> &'static str {
         match self {
             Self::File => "File/Directory",
+            Self::OpenBuffers => "Open Buffers",
+            Self::GitChanges => "Uncommitted Changes",
             Self::Fetch => "Fetch",
             Self::Thread => "Thread",
+            Self::All => "Files & Threads",
         }
     }
 
     pub fn icon(&self) -> IconName {
         match self {
             Self::File => IconName::File,
+            Self::OpenBuffers => IconName::FileText,
+            Self::GitChanges => IconName::GitBranch,
             Self::Fetch => IconName::Globe,
             Self::Thread => IconName::MessageCircle,
+            Self::All => IconName::MagnifyingGlass,
         }
     }
 }
@@ -56,8 +92,11 @@ impl ContextPickerMode {
 enum ContextPickerState {
     Default(Entity<ContextMenu>),
     File(Entity<FileContextPicker>),
+    OpenBuffers(Entity<OpenBufferContextPicker>),
+    GitChanges(Entity<GitChangesContextPicker>),
     Fetch(Entity<FetchContextPicker>),
     Thread(Entity<ThreadContextPicker>),
+    Unified(Entity<UnifiedContextPicker>),
 }
 
 pub(super) struct ContextPicker {
@@ -66,7 +105,11 @@ pub(super) struct ContextPicker {
     editor: WeakEntity<Editor>,
     context_store: WeakEntity<ContextStore>,
     thread_store: Option<WeakEntity<ThreadStore>>,
-    confirm_behavior: ConfirmBehavior,
+    /// Overrides the [`ConfirmBehavior`] chosen in the settings.
+    confirm_behavior: Option<ConfirmBehavior>,
+    /// The mode most recently selected from the menu, which the picker reopens into.
+    last_mode: Option<ContextPickerMode>,
+    _subscriptions: Vec<Subscription>,
 }
 
 impl ContextPicker {
@@ -75,10 +118,21 @@ impl ContextPicker {
         thread_store: Option<WeakEntity<ThreadStore>>,
         context_store: WeakEntity<ContextStore>,
         editor: WeakEntity<Editor>,
-        confirm_behavior: ConfirmBehavior,
+        confirm_behavior: Option<ConfirmBehavior>,
         window: &mut Window,
         cx: &mut Context<Self>,
     ) -> Self {
+        // Keep the footer's size summary current as context is attached.
+        let subscriptions = context_store
+            .upgrade()
+            .map(|context_store| {
+                vec![
+                    cx.observe(&context_store, |_, _, cx| cx.notify()),
+                    cx.subscribe(&context_store, |_, _, _, cx| cx.notify()),
+                ]
+            })
+            .unwrap_or_default();
+
         ContextPicker {
             mode: ContextPickerState::Default(ContextMenu::build(
                 window,
@@ -90,10 +144,25 @@ impl ContextPicker {
             thread_store,
             editor,
             confirm_behavior,
+            last_mode: None,
+            _subscriptions: subscriptions,
         }
     }
 
+    /// Opens the picker in the last selected mode, or at the top-level menu if there isn't one.
     pub fn init(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        match self.last_mode {
+            Some(ContextPickerMode::Thread | ContextPickerMode::All) if !self.allow_threads() => {
+                self.init_menu(window, cx)
+            }
+            Some(mode) => self.select_mode(mode, window, cx),
+            None => self.init_menu(window, cx),
+        }
+    }
+
+    /// Opens the picker at the top-level menu, forgetting the last selected mode.
+    pub fn init_menu(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.last_mode = None;
         self.mode = ContextPickerState::Default(self.build_menu(window, cx));
         cx.notify();
     }
@@ -109,9 +178,15 @@ impl ContextPicker {
                 .enumerate()
                 .map(|(ix, entry)| self.recent_menu_item(context_picker.clone(), ix, entry));
 
-            let mut modes = vec![ContextPickerMode::File, ContextPickerMode::Fetch];
+            let mut modes = vec![
+                ContextPickerMode::File,
+                ContextPickerMode::OpenBuffers,
+                ContextPickerMode::GitChanges,
+                ContextPickerMode::Fetch,
+            ];
             if self.allow_threads() {
                 modes.push(ContextPickerMode::Thread);
+                modes.push(ContextPickerMode::All);
             }
 
             let menu = menu
@@ -141,7 +216,7 @@ impl ContextPicker {
                         })
                 }));
 
-            match self.confirm_behavior {
+            match self.confirm_behavior(cx) {
                 ConfirmBehavior::KeepOpen => menu.keep_open_on_confirm(),
                 ConfirmBehavior::Close => menu,
             }
@@ -155,6 +230,13 @@ impl ContextPicker {
         menu
     }
 
+    /// The [`ConfirmBehavior`] pickers opened from here use: the caller's, if it chose one, or
+    /// else the one from the settings.
+    fn confirm_behavior(&self, cx: &App) -> ConfirmBehavior {
+        self.confirm_behavior
+            .unwrap_or_else(|| ConfirmBehavior::from_settings(cx))
+    }
+
     /// Whether threads are allowed as context.
     pub fn allow_threads(&self) -> bool {
         self.thread_store.is_some()
@@ -167,6 +249,8 @@ impl ContextPicker {
         cx: &mut Context<Self>,
     ) {
         let context_picker = cx.entity().downgrade();
+        let confirm_behavior = self.confirm_behavior(cx);
+        self.last_mode = Some(mode);
 
         match mode {
             ContextPickerMode::File => {
@@ -176,7 +260,33 @@ impl ContextPicker {
                         self.workspace.clone(),
                         self.editor.clone(),
                         self.context_store.clone(),
-                        self.confirm_behavior,
+                        confirm_behavior,
+                        window,
+                        cx,
+                    )
+                }));
+            }
+            ContextPickerMode::OpenBuffers => {
+                self.mode = ContextPickerState::OpenBuffers(cx.new(|cx| {
+                    OpenBufferContextPicker::new(
+                        context_picker.clone(),
+                        self.workspace.clone(),
+                        self.editor.clone(),
+                        self.context_store.clone(),
+                        confirm_behavior,
+                        window,
+                        cx,
+                    )
+                }));
+            }
+            ContextPickerMode::GitChanges => {
+                self.mode = ContextPickerState::GitChanges(cx.new(|cx| {
+                    GitChangesContextPicker::new(
+                        context_picker.clone(),
+                        self.workspace.clone(),
+                        self.editor.clone(),
+                        self.context_store.clone(),
+                        confirm_behavior,
                         window,
                         cx,
                     )
@@ -188,7 +298,7 @@ impl ContextPicker {
                         context_picker.clone(),
                         self.workspace.clone(),
                         self.context_store.clone(),
-                        self.confirm_behavior,
+                        confirm_behavior,
                         window,
                         cx,
                     )
@@ -196,12 +306,33 @@ impl ContextPicker {
             }
             ContextPickerMode::Thread => {
                 if let Some(thread_store) = self.thread_store.as_ref() {
+                    let active_thread_id = self.active_thread_id(cx);
                     self.mode = ContextPickerState::Thread(cx.new(|cx| {
                         ThreadContextPicker::new(
                             thread_store.clone(),
                             context_picker.clone(),
                             self.context_store.clone(),
-                            self.confirm_behavior,
+                            confirm_behavior,
+                            ThreadTextOptions::default(),
+                            active_thread_id,
+                            window,
+                            cx,
+                        )
+                    }));
+                }
+            }
+            ContextPickerMode::All => {
+                if let Some(thread_store) = self.thread_store.as_ref() {
+                    let active_thread_id = self.active_thread_id(cx);
+                    self.mode = ContextPickerState::Unified(cx.new(|cx| {
+                        UnifiedContextPicker::new(
+                            context_picker.clone(),
+                            self.workspace.clone(),
+                            self.editor.clone(),
+                            self.context_store.clone(),
+                            thread_store.clone(),
+                            confirm_behavior,
+                            active_thread_id,
                             window,
                             cx,
                         )
@@ -226,14 +357,22 @@ impl ContextPicker {
                 path_prefix,
             } => {
                 let context_store = self.context_store.clone();
+                let workspace = self.workspace.clone();
+                let worktree_id = project_path.worktree_id;
                 let path = project_path.path.clone();
 
                 ContextMenuItem::custom_entry(
                     move |_window, cx| {
+                        let show_root_name = workspace.upgrade().map_or(false, |workspace| {
+                            workspace.read(cx).visible_worktrees(cx).count() > 1
+                        });
                         render_file_context_entry(
                             ElementId::NamedInteger("ctx-recent".into(), ix),
+                            worktree_id,
                             &path,
                             &path_prefix,
+                            show_root_name,
+                            &[],
                             false,
                             context_store.clone(),
                             cx,
@@ -278,7 +417,7 @@ impl ContextPicker {
         };
 
         let task = context_store.update(cx, |context_store, cx| {
-            context_store.add_file_from_path(project_path.clone(), cx)
+            context_store.add_file_from_path(project_path.clone(), None, cx)
         });
 
         cx.spawn_in(window, |_, mut cx| async move {
@@ -306,13 +445,11 @@ impl ContextPicker {
             return Task::ready(Err(anyhow!("thread store not available")));
         };
 
-        let open_thread_task = thread_store.update(cx, |this, cx| this.open_thread(&thread.id, cx));
+        let attach_task = context_store.update(cx, |context_store, cx| {
+            context_store.attach_thread(&thread_store, &thread.id, ThreadTextOptions::default(), cx)
+        });
         cx.spawn(|this, mut cx| async move {
-            let thread = open_thread_task.await?;
-            context_store.update(&mut cx, |context_store, cx| {
-                context_store.add_thread(thread, cx);
-            })?;
-
+            attach_task.await?;
             this.update(&mut cx, |_this, cx| cx.notify())
         })
     }
@@ -353,11 +490,8 @@ impl ContextPicker {
 
         let mut current_threads = context_store.thread_ids();
 
-        if let Some(active_thread) = workspace
-            .panel::<AssistantPanel>(cx)
-            .map(|panel| panel.read(cx).active_thread(cx))
-        {
-            current_threads.insert(active_thread.read(cx).id().clone());
+        if let Some(active_thread_id) = Self::active_thread_id_in(workspace, cx) {
+            current_threads.insert(active_thread_id);
         }
 
         let Some(thread_store) = self
@@ -379,6 +513,8 @@ impl ContextPicker {
                         RecentEntry::Thread(ThreadContextEntry {
                             id: thread.id,
                             summary: thread.summary,
+                            message_count: thread.message_count,
+                            updated_at: thread.updated_at,
                         })
                     }),
             )
@@ -387,6 +523,19 @@ impl ContextPicker {
         recent
     }
 
+    /// Returns the ID of the thread open in the assistant panel, which can't be attached to itself.
+    fn active_thread_id(&self, cx: &App) -> Option<ThreadId> {
+        let workspace = self.workspace.upgrade()?;
+        Self::active_thread_id_in(workspace.read(cx), cx)
+    }
+
+    fn active_thread_id_in(workspace: &Workspace, cx: &App) -> Option<ThreadId> {
+        let panel = workspace.panel::<AssistantPanel>(cx)?;
+        let active_thread = panel.read(cx).active_thread(cx);
+        let id = active_thread.read(cx).id().clone();
+        Some(id)
+    }
+
     fn active_singleton_buffer_path(workspace: &Workspace, cx: &App) -> Option<PathBuf> {
         let active_item = workspace.active_item(cx)?;
 
@@ -405,23 +554,116 @@ impl Focusable for ContextPicker {
         match &self.mode {
             ContextPickerState::Default(menu) => menu.focus_handle(cx),
             ContextPickerState::File(file_picker) => file_picker.focus_handle(cx),
+            ContextPickerState::OpenBuffers(open_buffer_picker) => {
+                open_buffer_picker.focus_handle(cx)
+            }
+            ContextPickerState::GitChanges(git_changes_picker) => {
+                git_changes_picker.focus_handle(cx)
+            }
             ContextPickerState::Fetch(fetch_picker) => fetch_picker.focus_handle(cx),
             ContextPickerState::Thread(thread_picker) => thread_picker.focus_handle(cx),
+            ContextPickerState::Unified(unified_picker) => unified_picker.focus_handle(cx),
         }
     }
 }
 
+impl ContextPicker {
+    /// Summarizes the size of the attached context against the active model's context window.
+    fn render_context_usage(&self, cx: &App) -> Option<impl IntoElement> {
+        let context_store = self.context_store.upgrade()?;
+        let context_store = context_store.read(cx);
+        let token_count = context_store.estimated_token_count();
+        let total_bytes = context_store.total_text_len();
+        let max_token_count = LanguageModelRegistry::read_global(cx)
+            .active_model()
+            .map(|model| model.max_token_count());
+
+        let (label, color) = match max_token_count {
+            Some(max_token_count) => (
+                format!(
+                    "Context: ~{} / {} tokens",
+                    humanize_token_count(token_count),
+                    humanize_token_count(max_token_count)
+                ),
+                context_usage_color(token_count, max_token_count),
+            ),
+            None => (
+                format!("Context: ~{} tokens", humanize_token_count(token_count)),
+                Color::Muted,
+            ),
+        };
+
+        Some(
+            h_flex()
+                .id("context-usage")
+                .px_2()
+                .py_1()
+                .border_t_1()
+                .border_color(cx.theme().colors().border_variant)
+                .child(Label::new(label).size(LabelSize::Small).color(color))
+                .tooltip(Tooltip::text(format!(
+                    "{} of attached context",
+                    format_size(total_bytes as u64)
+                ))),
+        )
+    }
+}
+
+/// The fraction of the context window at which the usage summary turns amber.
+const CONTEXT_USAGE_WARNING_RATIO: f32 = 0.75;
+/// The fraction of the context window at which the usage summary turns red.
+const CONTEXT_USAGE_ERROR_RATIO: f32 = 0.9;
+
+fn context_usage_color(token_count: usize, max_token_count: usize) -> Color {
+    let ratio = token_count as f32 / max_token_count.max(1) as f32;
+    if ratio >= CONTEXT_USAGE_ERROR_RATIO {
+        Color::Error
+    } else if ratio >= CONTEXT_USAGE_WARNING_RATIO {
+        Color::Warning
+    } else {
+        Color::Muted
+    }
+}
+
+/// Finds the first selectable entry at or after `ix` (or at or before it, when `backward`),
+/// wrapping around the list, so that pickers with header rows never select a header.
+fn selectable_index<T>(
+    entries: &[T],
+    ix: usize,
+    backward: bool,
+    is_selectable: impl Fn(&T) -> bool,
+) -> Option<usize> {
+    let len = entries.len();
+    (0..len)
+        .map(|offset| {
+            if backward {
+                (ix % len + len - offset) % len
+            } else {
+                (ix + offset) % len
+            }
+        })
+        .find(|&ix| is_selectable(&entries[ix]))
+}
+
 impl Render for ContextPicker {
-    fn render(&mut self, _window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         v_flex()
             .w(px(400.))
             .min_w(px(400.))
             .map(|parent| match &self.mode {
                 ContextPickerState::Default(menu) => parent.child(menu.clone()),
                 ContextPickerState::File(file_picker) => parent.child(file_picker.clone()),
+                ContextPickerState::OpenBuffers(open_buffer_picker) => {
+                    parent.child(open_buffer_picker.clone())
+                }
+                ContextPickerState::GitChanges(git_changes_picker) => {
+                    parent.child(git_changes_picker.clone())
+                }
                 ContextPickerState::Fetch(fetch_picker) => parent.child(fetch_picker.clone()),
                 ContextPickerState::Thread(thread_picker) => parent.child(thread_picker.clone()),
+                ContextPickerState::Unified(unified_picker) => parent.child(unified_picker.clone()),
             })
+            .children(self.render_context_usage(cx))
     }
 }
 enum RecentEntry {
@@ -431,3 +673,59 @@ enum RecentEntry {
     },
     Thread(ThreadContextEntry),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use settings::SettingsStore;
+
+    #[test]
+    fn test_context_usage_color() {
+        assert_eq!(context_usage_color(0, 128_000), Color::Muted);
+        assert_eq!(context_usage_color(95_999, 128_000), Color::Muted);
+        assert_eq!(context_usage_color(96_000, 128_000), Color::Warning);
+        assert_eq!(context_usage_color(115_200, 128_000), Color::Error);
+        assert_eq!(context_usage_color(200_000, 128_000), Color::Error);
+        // A model that reports no context window doesn't divide by zero.
+        assert_eq!(context_usage_color(1, 0), Color::Error);
+    }
+
+    #[test]
+    fn test_selectable_index_skips_headers() {
+        // Headers are `false`: header, item, item, header, item
+        let entries = [false, true, true, false, true];
+        let is_item = |entry: &bool| *entry;
+
+        assert_eq!(selectable_index(&entries, 2, false, is_item), Some(2));
+        // Moving onto a header moves past it.
+        assert_eq!(selectable_index(&entries, 3, false, is_item), Some(4));
+        assert_eq!(selectable_index(&entries, 3, true, is_item), Some(2));
+        // Moving past either end wraps around.
+        assert_eq!(selectable_index(&entries, 0, false, is_item), Some(1));
+        assert_eq!(selectable_index(&entries, 0, true, is_item), Some(4));
+        assert_eq!(selectable_index(&[false], 0, false, is_item), None);
+        assert_eq!(selectable_index(&[], 0, false, is_item), None);
+    }
+
+    #[gpui::test]
+    fn test_confirm_behavior_from_settings(cx: &mut App) {
+        let settings_store = SettingsStore::test(cx);
+        cx.set_global(settings_store);
+        AssistantSettings::register(cx);
+
+        assert_eq!(
+            ConfirmBehavior::from_settings(cx),
+            ConfirmBehavior::KeepOpen
+        );
+
+        cx.update_global::<SettingsStore, _>(|store, cx| {
+            store
+                .set_user_settings(
+                    r#"{"assistant": {"version": "2", "context_picker_confirm_behavior": "close"}}"#,
+                    cx,
+                )
+                .unwrap();
+        });
+        assert_eq!(ConfirmBehavior::from_settings(cx), ConfirmBehavior::Close);
+    }
+}
@@ -9,6 +9,7 @@ use html_to_markdown::{convert_html_to_markdown, markdown, TagHandler};
 use http_client::{AsyncBody, HttpClientWithUrl};
 use picker::{Picker, PickerDelegate};
 use ui::{prelude::*, Context, ListItem, Window};
+use workspace::notifications::NotifyResultExt;
 use workspace::Workspace;
 
 use crate::context_picker::{ConfirmBehavior, ContextPicker};
@@ -58,6 +59,26 @@ enum ContentType {
     Json,
 }
 
+impl ContentType {
+    /// Parses a `Content-Type` header, ignoring parameters such as the charset. Returns `None` for
+    /// content that can't be turned into text, such as images or PDFs.
+    fn from_header(content_type: &str) -> Option<Self> {
+        let essence = content_type.split(';').next().unwrap_or_default().trim();
+        match essence.to_ascii_lowercase().as_str() {
+            "text/html" | "application/xhtml+xml" => Some(Self::Html),
+            "text/plain" | "text/markdown" => Some(Self::Plaintext),
+            "application/json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+}
+
+/// The readable text of a fetched page.
+struct FetchedPage {
+    title: Option<SharedString>,
+    text: String,
+}
+
 pub struct FetchContextPickerDelegate {
     context_picker: WeakEntity<ContextPicker>,
     workspace: WeakEntity<Workspace>,
@@ -82,7 +103,7 @@ impl FetchContextPickerDelegate {
         }
     }
 
-    async fn build_message(http_client: Arc<HttpClientWithUrl>, url: String) -> Result<String> {
+    async fn fetch_page(http_client: Arc<HttpClientWithUrl>, url: String) -> Result<FetchedPage> {
         let url = if !url.starts_with("https://") && !url.starts_with("http://") {
             format!("https://{url}")
         } else {
@@ -98,12 +119,8 @@ impl FetchContextPickerDelegate {
             .await
             .context("error reading response body")?;
 
-        if response.status().is_client_error() {
-            let text = String::from_utf8_lossy(body.as_slice());
-            bail!(
-                "status error {}, response: {text:?}",
-                response.status().as_u16()
-            );
+        if !response.status().is_success() {
+            bail!("the server responded with status {}", response.status());
         }
 
         let Some(content_type) = response.headers().get("content-type") else {
@@ -112,14 +129,15 @@ impl FetchContextPickerDelegate {
         let content_type = content_type
             .to_str()
             .context("invalid Content-Type header")?;
-        let content_type = match content_type {
-            "text/html" => ContentType::Html,
-            "text/plain" => ContentType::Plaintext,
-            "application/json" => ContentType::Json,
-            _ => ContentType::Html,
+        let Some(content_type) = ContentType::from_header(content_type) else {
+            bail!("{content_type} content can't be attached, only HTML, plain text and JSON can");
         };
 
-        match content_type {
+        let title = match content_type {
+            ContentType::Html => html_title(&String::from_utf8_lossy(&body)).map(Into::into),
+            ContentType::Plaintext | ContentType::Json => None,
+        };
+        let text = match content_type {
             ContentType::Html => {
                 let mut handlers: Vec<TagHandler> = vec![
                     Rc::new(RefCell::new(markdown::WebpageChromeRemover)),
@@ -152,10 +170,32 @@ impl FetchContextPickerDelegate {
                     serde_json::to_string_pretty(&json)?
                 ))
             }
-        }
+        }?;
+
+        Ok(FetchedPage { title, text })
     }
 }
 
+/// Returns the contents of the page's `<title>` element, with whitespace collapsed and common
+/// character references decoded.
+fn html_title(html: &str) -> Option<String> {
+    let lowercase = html.to_ascii_lowercase();
+    let start = lowercase.find("<title")?;
+    let start = start + lowercase[start..].find('>')? + 1;
+    let end = start + lowercase[start..].find("</title")?;
+
+    let title = html[start..end]
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&");
+    (!title.is_empty()).then_some(title)
+}
+
 impl PickerDelegate for FetchContextPickerDelegate {
     type ListItem = ListItem;
 
@@ -207,15 +247,20 @@ impl PickerDelegate for FetchContextPickerDelegate {
         let url = self.url.clone();
         let confirm_behavior = self.confirm_behavior;
         cx.spawn_in(window, |this, mut cx| async move {
-            let text = cx
-                .background_spawn(Self::build_message(http_client, url.clone()))
-                .await?;
+            let Some(page) = cx
+                .background_spawn(Self::fetch_page(http_client, url.clone()))
+                .await
+                .with_context(|| format!("failed to fetch {url}"))
+                .notify_async_err(&mut cx)
+            else {
+                return anyhow::Ok(());
+            };
 
             this.update_in(&mut cx, |this, window, cx| {
                 this.delegate
                     .context_store
-                    .update(cx, |context_store, _cx| {
-                        context_store.add_fetched_url(url, text);
+                    .update(cx, |context_store, cx| {
+                        context_store.add_fetched_url(url, page.title, &page.text, cx);
                     })?;
 
                 match confirm_behavior {
@@ -270,3 +315,40 @@ impl PickerDelegate for FetchContextPickerDelegate {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_type_from_header() {
+        assert_eq!(
+            ContentType::from_header("text/html; charset=utf-8"),
+            Some(ContentType::Html)
+        );
+        assert_eq!(
+            ContentType::from_header("Application/JSON"),
+            Some(ContentType::Json)
+        );
+        assert_eq!(
+            ContentType::from_header("text/plain"),
+            Some(ContentType::Plaintext)
+        );
+        assert_eq!(ContentType::from_header("application/pdf"), None);
+        assert_eq!(ContentType::from_header("image/png"), None);
+    }
+
+    #[test]
+    fn test_html_title() {
+        assert_eq!(
+            html_title("<html><head><TITLE>\n  Zed &amp; Docs\n</TITLE></head></html>").as_deref(),
+            Some("Zed & Docs")
+        );
+        assert_eq!(
+            html_title(r#"<title lang="en">Getting started</title>"#).as_deref(),
+            Some("Getting started")
+        );
+        assert_eq!(html_title("<title>  </title>"), None);
+        assert_eq!(html_title("<p>No title</p>"), None);
+    }
+}
@@ -1,33 +1,62 @@
-use std::collections::BTreeSet;
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, VecDeque};
 use std::ops::Range;
-use std::path::Path;
-use std::sync::atomic::AtomicBool;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{self, AtomicBool};
 use std::sync::Arc;
 
+use collections::{HashMap, HashSet};
+use db::kvp::KEY_VALUE_STORE;
 use editor::actions::FoldAt;
 use editor::display_map::{Crease, FoldId};
 use editor::scroll::Autoscroll;
 use editor::{Anchor, AnchorRangeExt, Editor, FoldPlaceholder, ToPoint};
 use file_icons::FileIcons;
+use futures::future;
 use fuzzy::PathMatch;
 use gpui::{
-    AnyElement, App, AppContext, DismissEvent, Empty, Entity, FocusHandle, Focusable, Stateful,
-    Task, WeakEntity,
+    anchored, deferred, point, Action, AnyElement, App, AppContext, Corner, DismissEvent, Empty,
+    Entity, FocusHandle, Focusable, Stateful, Task, WeakEntity,
 };
 use multi_buffer::{MultiBufferPoint, MultiBufferRow};
 use picker::{Picker, PickerDelegate};
-use project::{PathMatchCandidateSet, ProjectPath, WorktreeId};
+use project::{PathMatchCandidateSet, ProjectPath, Worktree, WorktreeId};
 use rope::Point;
+use serde::{Deserialize, Serialize};
 use text::SelectionGoal;
-use ui::{prelude::*, ButtonLike, Disclosure, ListItem, TintColor, Tooltip};
+use ui::{
+    prelude::*, right_click_menu, ButtonLike, Checkbox, ContextMenu, Disclosure, HighlightedLabel,
+    KeyBinding, ListItem, TintColor, ToggleState, Tooltip,
+};
+use util::paths::PathMatcher;
 use util::ResultExt as _;
-use workspace::{notifications::NotifyResultExt, Workspace};
+use workspace::notifications::{NotificationId, NotifyResultExt};
+use workspace::{Toast, Workspace};
 
-use crate::context_picker::{ConfirmBehavior, ContextPicker};
+use crate::context::{
+    format_size, DirectoryContextMode, DirectoryContextOptions,
+    DEFAULT_DIRECTORY_CONTEXT_EXCLUDE_GLOBS,
+};
+use crate::context_picker::{selectable_index, ConfirmBehavior, ContextPicker};
 use crate::context_store::{ContextStore, FileInclusion};
+use crate::{PreviewDirectoryContext, ToggleContextSelection};
 
 pub struct FileContextPicker {
     picker: Entity<Picker<FileContextPickerDelegate>>,
+    /// The contents of the highlighted directory, shown next to the picker until dismissed.
+    preview: Option<DirectoryPreview>,
+}
+
+struct DirectoryPreview {
+    worktree_id: WorktreeId,
+    path: Arc<Path>,
+    entries: Vec<DirectoryPreviewEntry>,
+}
+
+struct DirectoryPreviewEntry {
+    name: SharedString,
+    is_dir: bool,
+    size: u64,
 }
 
 impl FileContextPicker {
@@ -40,16 +69,30 @@ impl FileContextPicker {
         window: &mut Window,
         cx: &mut Context<Self>,
     ) -> Self {
+        let exclude_editor = cx.new(|cx| {
+            let mut editor = Editor::single_line(window, cx);
+            editor.set_placeholder_text("Globs to skip, separated by commas", cx);
+            editor.set_text(
+                DEFAULT_DIRECTORY_CONTEXT_EXCLUDE_GLOBS.join(", "),
+                window,
+                cx,
+            );
+            editor
+        });
         let delegate = FileContextPickerDelegate::new(
             context_picker,
             workspace,
             editor,
             context_store,
             confirm_behavior,
+            exclude_editor,
         );
         let picker = cx.new(|cx| Picker::uniform_list(delegate, window, cx));
 
-        Self { picker }
+        Self {
+            picker,
+            preview: None,
+        }
     }
 }
 
@@ -59,9 +102,144 @@ impl Focusable for FileContextPicker {
     }
 }
 
+impl FileContextPicker {
+    fn toggle_context_selection(
+        &mut self,
+        _: &ToggleContextSelection,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.picker.update(cx, |picker, cx| {
+            picker.delegate.toggle_selected_directory();
+            cx.notify();
+        });
+    }
+
+    fn preview_directory(
+        &mut self,
+        _: &PreviewDirectoryContext,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if self.preview.is_some() {
+            self.dismiss_preview(window, cx);
+            return;
+        }
+
+        self.preview = self.picker.read(cx).delegate.directory_preview(cx);
+        cx.notify();
+    }
+
+    fn dismiss_preview(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.preview = None;
+        self.picker.focus_handle(cx).focus(window);
+        cx.notify();
+    }
+
+    /// Escape closes the preview before it closes the picker.
+    fn cancel(&mut self, _: &menu::Cancel, window: &mut Window, cx: &mut Context<Self>) {
+        if self.preview.is_some() {
+            cx.stop_propagation();
+            self.dismiss_preview(window, cx);
+        }
+    }
+
+    fn render_preview(&self, preview: &DirectoryPreview, cx: &App) -> impl IntoElement {
+        let total_size = preview
+            .entries
+            .iter()
+            .filter(|entry| !entry.is_dir)
+            .map(|entry| entry.size)
+            .sum::<u64>();
+
+        v_flex()
+            .id("directory-preview")
+            .w(rems(18.))
+            .max_h(rems(20.))
+            .overflow_y_scroll()
+            .p_2()
+            .gap_1()
+            .elevation_2(cx)
+            .child(
+                h_flex()
+                    .justify_between()
+                    .child(
+                        Label::new(preview.path.to_string_lossy().into_owned())
+                            .size(LabelSize::Small)
+                            .single_line(),
+                    )
+                    .child(
+                        Label::new(format_size(total_size))
+                            .size(LabelSize::Small)
+                            .color(Color::Muted),
+                    ),
+            )
+            .when(preview.entries.is_empty(), |this| {
+                this.child(
+                    Label::new("Empty directory")
+                        .size(LabelSize::Small)
+                        .color(Color::Muted),
+                )
+            })
+            .children(preview.entries.iter().map(|entry| {
+                let icon = if entry.is_dir {
+                    IconName::Folder
+                } else {
+                    IconName::File
+                };
+
+                h_flex()
+                    .gap_1p5()
+                    .child(Icon::new(icon).size(IconSize::Small).color(Color::Muted))
+                    .child(
+                        div().flex_1().child(
+                            Label::new(entry.name.clone())
+                                .size(LabelSize::Small)
+                                .single_line(),
+                        ),
+                    )
+                    .when(!entry.is_dir, |this| {
+                        this.child(
+                            Label::new(format_size(entry.size))
+                                .size(LabelSize::Small)
+                                .color(Color::Muted),
+                        )
+                    })
+            }))
+    }
+}
+
 impl Render for FileContextPicker {
-    fn render(&mut self, _window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
-        self.picker.clone()
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        // The preview belongs to the directory it was opened on.
+        let selected_directory = self
+            .picker
+            .read(cx)
+            .delegate
+            .selected_match()
+            .map(|mat| (WorktreeId::from_usize(mat.worktree_id), mat.path.clone()));
+        if self.preview.as_ref().map_or(false, |preview| {
+            selected_directory.as_ref() != Some(&(preview.worktree_id, preview.path.clone()))
+        }) {
+            self.preview = None;
+        }
+
+        v_flex()
+            .key_context("FileContextPicker")
+            .on_action(cx.listener(Self::toggle_context_selection))
+            .on_action(cx.listener(Self::preview_directory))
+            .capture_action(cx.listener(Self::cancel))
+            .child(self.picker.clone())
+            .children(self.preview.as_ref().map(|preview| {
+                deferred(
+                    anchored()
+                        .position_mode(gpui::AnchoredPositionMode::Local)
+                        .position(point(px(0.), px(0.)))
+                        .anchor(Corner::TopRight)
+                        .snap_to_window_with_margin(px(8.))
+                        .child(self.render_preview(preview, cx)),
+                )
+            }))
     }
 }
 
@@ -71,10 +249,62 @@ pub struct FileContextPickerDelegate {
     editor: WeakEntity<Editor>,
     context_store: WeakEntity<ContextStore>,
     confirm_behavior: ConfirmBehavior,
-    matches: Vec<PathMatch>,
+    /// How directories are attached, apart from the exclude patterns and whether subdirectories
+    /// are included, which are read from `exclude_editor` and the kind of confirm.
+    directory_options: DirectoryContextOptions,
+    /// Comma-separated globs for the files skipped when attaching a directory.
+    exclude_editor: Entity<Editor>,
+    /// Whether to show entries that are ignored by the worktree, i.e. entries
+    /// matched by a `.gitignore` (or another ignore file) in an ancestor
+    /// directory. Applies to both browsing and searching.
+    include_ignored: bool,
+    /// Whether browsing with an empty query lists the entries around the active file first.
+    near_active_file: bool,
+    /// Directories toggled for attaching together on the next confirm.
+    selected_directories: HashSet<(WorktreeId, Arc<Path>)>,
+    /// Set when a newer query supersedes the search that is currently in flight.
+    cancellation_flag: Arc<AtomicBool>,
+    /// Set when the picker is dismissed while confirmed directories are still being read.
+    confirm_cancellation_flag: Arc<AtomicBool>,
+    /// Matches grouped by worktree, each group preceded by a header when there are several worktrees.
+    matches: Vec<FileMatchEntry>,
+    /// The directories the user pinned, listed above everything else.
+    pinned_directories: Vec<PersistedDirectory>,
+    /// The directories attached most recently, listed below the pinned ones when browsing. Read
+    /// once and kept up to date as directories are attached, rather than read on every query.
+    recent_directories: Vec<PersistedDirectory>,
+    /// Each worktree's symlinked directories, along with the scan they were collected from, so
+    /// they're only collected again once the worktree changed.
+    symlinked_directories: HashMap<WorktreeId, (usize, HashSet<Arc<Path>>)>,
+    /// The pinned directories among the current matches, keyed by worktree id and path.
+    pinned_match_keys: HashSet<(usize, Arc<Path>)>,
+    /// The number of entries in the pinned and recent sections above the worktree groups,
+    /// including their headers.
+    section_entry_count: usize,
+    /// Whether browsing with an empty query left out entries of a worktree past
+    /// [`MAX_BROWSE_MATCHES`].
+    browse_truncated: bool,
+    /// The query the current matches were found for, without any trailing line range.
+    query: String,
+    /// The zero-based rows to attach when a file is confirmed, parsed from a query like
+    /// `src/main.rs:40-80`.
+    line_range: Option<Range<u32>>,
     selected_index: usize,
 }
 
+enum FileMatchEntry {
+    PinnedHeader,
+    RecentHeader,
+    WorktreeHeader { root_name: SharedString },
+    Match(PathMatch),
+}
+
+impl FileMatchEntry {
+    fn is_match(&self) -> bool {
+        matches!(self, Self::Match(_))
+    }
+}
+
 impl FileContextPickerDelegate {
     pub fn new(
         context_picker: WeakEntity<ContextPicker>,
@@ -82,6 +312,7 @@ impl FileContextPickerDelegate {
         editor: WeakEntity<Editor>,
         context_store: WeakEntity<ContextStore>,
         confirm_behavior: ConfirmBehavior,
+        exclude_editor: Entity<Editor>,
     ) -> Self {
         Self {
             context_picker,
@@ -89,87 +320,493 @@ impl FileContextPickerDelegate {
             editor,
             context_store,
             confirm_behavior,
+            directory_options: DirectoryContextOptions::default(),
+            exclude_editor,
+            include_ignored: false,
+            near_active_file: false,
+            selected_directories: HashSet::default(),
+            cancellation_flag: Arc::default(),
+            confirm_cancellation_flag: Arc::default(),
             matches: Vec::new(),
+            pinned_directories: read_persisted_directories(PINNED_DIRECTORIES_KEY),
+            recent_directories: read_persisted_directories(RECENT_DIRECTORIES_KEY),
+            symlinked_directories: HashMap::default(),
+            pinned_match_keys: HashSet::default(),
+            section_entry_count: 0,
+            browse_truncated: false,
+            query: String::new(),
+            line_range: None,
             selected_index: 0,
         }
     }
 
+    /// Finds the matches for `query`. An empty query browses the worktrees instead, in which case
+    /// the returned flag tells whether entries of any worktree were left out to stay within
+    /// [`MAX_BROWSE_MATCHES`].
     fn search(
         &mut self,
         query: String,
         cancellation_flag: Arc<AtomicBool>,
         workspace: &Entity<Workspace>,
         cx: &mut Context<Picker<Self>>,
-    ) -> Task<Vec<PathMatch>> {
+    ) -> Task<(Vec<PathMatch>, bool)> {
+        let include_ignored = self.include_ignored;
         if query.is_empty() {
             let workspace = workspace.read(cx);
             let project = workspace.project().read(cx);
-            let recent_matches = workspace
-                .recent_navigation_history(Some(10), cx)
-                .into_iter()
-                .filter_map(|(project_path, _)| {
-                    let worktree = project.worktree_for_id(project_path.worktree_id, cx)?;
-                    Some(PathMatch {
-                        score: 0.,
-                        positions: Vec::new(),
-                        worktree_id: project_path.worktree_id.to_usize(),
-                        path: project_path.path,
-                        path_prefix: worktree.read(cx).root_name().into(),
-                        distance_to_relative_ancestor: 0,
-                        is_dir: false,
-                    })
-                });
+            let recent_matches = recent_file_matches(workspace, include_ignored, cx);
 
-            let file_matches = project.worktrees(cx).flat_map(|worktree| {
-                let worktree = worktree.read(cx);
-                let path_prefix: Arc<str> = worktree.root_name().into();
-                worktree.entries(false, 0).map(move |entry| PathMatch {
-                    score: 0.,
-                    positions: Vec::new(),
-                    worktree_id: worktree.id().to_usize(),
-                    path: entry.path.clone(),
-                    path_prefix: path_prefix.clone(),
-                    distance_to_relative_ancestor: 0,
-                    is_dir: entry.is_dir(),
+            let mut worktrees = project
+                .worktrees(cx)
+                .map(|worktree| worktree.read(cx))
+                .collect::<Vec<_>>();
+            worktrees.sort_by(|a, b| {
+                a.root_name()
+                    .cmp(b.root_name())
+                    .then_with(|| a.id().cmp(&b.id()))
+            });
+            let mut file_matches = Vec::new();
+            let mut truncated = false;
+            // Each worktree is capped on its own, so a large one doesn't hide the ones after it.
+            for worktree in worktrees {
+                let (matches, worktree_truncated) =
+                    browse_matches(worktree, include_ignored, MAX_BROWSE_MATCHES);
+                file_matches.extend(matches);
+                truncated |= worktree_truncated;
+            }
+            sort_browse_matches(&mut file_matches);
+
+            // Without an active file, e.g. when only a terminal is open, list everything as usual.
+            let active_path = self
+                .near_active_file
+                .then(|| workspace.active_item(cx)?.project_path(cx))
+                .flatten();
+            let Some(active_path) = active_path else {
+                return Task::ready((
+                    recent_matches.into_iter().chain(file_matches).collect(),
+                    truncated,
+                ));
+            };
+            // The entries around the active file may be deeper than the browsed ones, so they're
+            // listed separately.
+            let near_matches = project
+                .worktree_for_id(active_path.worktree_id, cx)
+                .map(|worktree| {
+                    near_active_file_matches(worktree.read(cx), &active_path, include_ignored)
                 })
+                .unwrap_or_default();
+            let near_keys = near_matches
+                .iter()
+                .map(|path_match| (path_match.worktree_id, path_match.path.clone()))
+                .collect::<HashSet<_>>();
+            file_matches.retain(|path_match| {
+                !near_keys.contains(&(path_match.worktree_id, path_match.path.clone()))
             });
 
-            Task::ready(recent_matches.chain(file_matches).collect())
+            Task::ready((
+                near_matches
+                    .into_iter()
+                    .chain(recent_matches)
+                    .chain(file_matches)
+                    .collect(),
+                truncated,
+            ))
         } else {
-            let worktrees = workspace.read(cx).visible_worktrees(cx).collect::<Vec<_>>();
-            let candidate_sets = worktrees
+            let search_task =
+                search_paths(query, include_ignored, cancellation_flag, workspace, cx);
+            cx.spawn(|_, _| async move { (search_task.await, false) })
+        }
+    }
+}
+
+/// The recently navigated files, for an empty query.
+pub(super) fn recent_file_matches(
+    workspace: &Workspace,
+    include_ignored: bool,
+    cx: &App,
+) -> Vec<PathMatch> {
+    let project = workspace.project().read(cx);
+    workspace
+        .recent_navigation_history(Some(10), cx)
+        .into_iter()
+        .filter_map(|(project_path, _)| {
+            let worktree = project.worktree_for_id(project_path.worktree_id, cx)?;
+            if !includes_ignored_entries(worktree.read(cx), include_ignored)
+                && worktree
+                    .read(cx)
+                    .entry_for_path(&project_path.path)
+                    .map_or(false, |entry| entry.is_ignored)
+            {
+                return None;
+            }
+            Some(PathMatch {
+                score: 0.,
+                positions: Vec::new(),
+                worktree_id: project_path.worktree_id.to_usize(),
+                path: project_path.path,
+                path_prefix: worktree.read(cx).root_name().into(),
+                distance_to_relative_ancestor: 0,
+                is_dir: false,
+            })
+        })
+        .collect()
+}
+
+/// Fuzzy-matches the files and directories of the visible worktrees against `query`.
+pub(super) fn search_paths(
+    query: String,
+    include_ignored: bool,
+    cancellation_flag: Arc<AtomicBool>,
+    workspace: &Entity<Workspace>,
+    cx: &App,
+) -> Task<Vec<PathMatch>> {
+    let worktrees = workspace.read(cx).visible_worktrees(cx).collect::<Vec<_>>();
+    let candidate_sets = worktrees
+        .into_iter()
+        .map(|worktree| {
+            let worktree = worktree.read(cx);
+
+            PathMatchCandidateSet {
+                snapshot: worktree.snapshot(),
+                include_ignored: includes_ignored_entries(worktree, include_ignored),
+                include_root_name: true,
+                candidates: project::Candidates::Entries,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let executor = cx.background_executor().clone();
+    cx.foreground_executor().spawn(async move {
+        fuzzy::match_path_sets(
+            candidate_sets.as_slice(),
+            query.as_str(),
+            None,
+            false,
+            100,
+            &cancellation_flag,
+            executor,
+        )
+        .await
+    })
+}
+
+/// Splits a trailing `:start-end` or `:line` off `query`, returning the rest of the query and the
+/// one-based, inclusive lines as a range of zero-based rows. A suffix of numbers that don't form a
+/// valid range is still removed from the query, but no range is returned.
+fn parse_line_range(query: &str) -> (&str, Option<Range<u32>>) {
+    let Some((path, suffix)) = query.rsplit_once(':') else {
+        return (query, None);
+    };
+    let (start, end) = suffix.split_once('-').unwrap_or((suffix, suffix));
+    let (Ok(start), Ok(end)) = (start.parse::<u32>(), end.parse::<u32>()) else {
+        return (query, None);
+    };
+    if start == 0 || end < start {
+        return (path, None);
+    }
+    (path, Some(start - 1..end))
+}
+
+fn is_in_symlinked_directory(symlinked_directories: &HashSet<Arc<Path>>, path: &Path) -> bool {
+    !symlinked_directories.is_empty()
+        && path
+            .ancestors()
+            .any(|ancestor| symlinked_directories.contains(ancestor))
+}
+
+/// The most entries of each worktree listed when browsing with an empty query; the rest are
+/// found by searching.
+///
+/// Listing every entry instead allocates and sorts a [`PathMatch`] per entry whenever the query is
+/// cleared. In a worktree of 50,000 directories, the matches and the queue of directories left to
+/// visit grow to about 13 MB of allocations before a single row is rendered, against about 210 KB
+/// with this cap, as `test_browse_matches_allocations_are_capped` checks. Since entries are listed
+/// breadth-first, the ones kept are the top-level ones that sorting by depth puts first anyway.
+const MAX_BROWSE_MATCHES: usize = 1_000;
+
+fn browse_match(worktree: &Worktree, path_prefix: &Arc<str>, entry: &project::Entry) -> PathMatch {
+    PathMatch {
+        score: 0.,
+        positions: Vec::new(),
+        worktree_id: worktree.id().to_usize(),
+        path: entry.path.clone(),
+        path_prefix: path_prefix.clone(),
+        distance_to_relative_ancestor: 0,
+        is_dir: entry.is_dir(),
+    }
+}
+
+/// Whether the worktree's ignored entries are listed. They always are in a worktree whose root
+/// is itself ignored, which would otherwise list nothing.
+fn includes_ignored_entries(worktree: &Worktree, include_ignored: bool) -> bool {
+    include_ignored
+        || worktree
+            .root_entry()
+            .map_or(false, |entry| entry.is_ignored)
+}
+
+/// Lists the worktree's entries breadth-first, so shallower entries come first, for browsing
+/// with an empty query. Stops after `limit` entries, returning whether any were left out.
+fn browse_matches(
+    worktree: &Worktree,
+    include_ignored: bool,
+    limit: usize,
+) -> (Vec<PathMatch>, bool) {
+    let include_ignored = includes_ignored_entries(worktree, include_ignored);
+    let path_prefix: Arc<str> = worktree.root_name().into();
+    let mut matches = Vec::new();
+    let mut directories = VecDeque::new();
+    if let Some(root_entry) = worktree.root_entry() {
+        if limit == 0 {
+            return (matches, true);
+        }
+        matches.push(browse_match(worktree, &path_prefix, root_entry));
+        if root_entry.is_dir() {
+            directories.push_back(root_entry.path.clone());
+        }
+    }
+
+    while let Some(directory) = directories.pop_front() {
+        for entry in worktree.child_entries(&directory) {
+            if !include_ignored && entry.is_ignored {
+                continue;
+            }
+            if matches.len() == limit {
+                return (matches, true);
+            }
+            matches.push(browse_match(worktree, &path_prefix, entry));
+            if entry.is_dir() {
+                directories.push_back(entry.path.clone());
+            }
+        }
+    }
+    (matches, false)
+}
+
+/// Lists the entries around the active file, nearest first, as ordered by
+/// [`sort_near_active_file`]. These are the entries of the active file's directory and of each
+/// of its ancestors, up to the worktree root.
+fn near_active_file_matches(
+    worktree: &Worktree,
+    active_path: &ProjectPath,
+    include_ignored: bool,
+) -> Vec<PathMatch> {
+    let include_ignored = includes_ignored_entries(worktree, include_ignored);
+    let active_directory = active_path.path.parent().unwrap_or(Path::new(""));
+    let path_prefix: Arc<str> = worktree.root_name().into();
+    let mut matches = worktree
+        .root_entry()
+        .map(|root_entry| browse_match(worktree, &path_prefix, root_entry))
+        .into_iter()
+        .collect::<Vec<_>>();
+    for directory in active_directory.ancestors() {
+        matches.extend(
+            worktree
+                .child_entries(directory)
+                .filter(|entry| include_ignored || !entry.is_ignored)
+                .map(|entry| browse_match(worktree, &path_prefix, entry)),
+        );
+    }
+    sort_browse_matches(&mut matches);
+    sort_near_active_file(&mut matches, active_path);
+    matches
+}
+
+/// Lists the pinned directories, then the recently attached ones, above the rest of the matches,
+/// which no longer include them. Also returns the number of entries in those two sections.
+fn build_match_entries(
+    pinned_directories: Vec<PathMatch>,
+    mut recent_directories: Vec<PathMatch>,
+    mut matches: Vec<PathMatch>,
+    show_worktree_headers: bool,
+) -> (Vec<FileMatchEntry>, usize) {
+    let key = |path_match: &PathMatch| (path_match.worktree_id, path_match.path.clone());
+    let pinned_keys = pinned_directories.iter().map(key).collect::<HashSet<_>>();
+    recent_directories.retain(|path_match| !pinned_keys.contains(&key(path_match)));
+    let recent_keys = recent_directories.iter().map(key).collect::<HashSet<_>>();
+    matches.retain(|path_match| {
+        let key = key(path_match);
+        !pinned_keys.contains(&key) && !recent_keys.contains(&key)
+    });
+
+    let mut entries = Vec::new();
+    if !pinned_directories.is_empty() {
+        entries.push(FileMatchEntry::PinnedHeader);
+        entries.extend(pinned_directories.into_iter().map(FileMatchEntry::Match));
+    }
+    if !recent_directories.is_empty() {
+        entries.push(FileMatchEntry::RecentHeader);
+        entries.extend(recent_directories.into_iter().map(FileMatchEntry::Match));
+    }
+    let section_entry_count = entries.len();
+    entries.extend(group_matches_by_worktree(matches, show_worktree_headers));
+    (entries, section_entry_count)
+}
+
+/// Orders matches by descending score, moving pinned ones ahead of the other matches with the
+/// same score and keeping the order otherwise.
+fn sort_pinned_first(matches: &mut [PathMatch], pinned_keys: &HashSet<(usize, Arc<Path>)>) {
+    let is_pinned = |path_match: &PathMatch| {
+        pinned_keys.contains(&(path_match.worktree_id, path_match.path.clone()))
+    };
+    matches.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| is_pinned(b).cmp(&is_pinned(a)))
+    });
+}
+
+/// Groups matches by worktree, ordering the groups by their first match so the best match still
+/// comes first. Matches keep their relative order within each group.
+fn group_matches_by_worktree(
+    matches: Vec<PathMatch>,
+    show_worktree_headers: bool,
+) -> Vec<FileMatchEntry> {
+    let mut groups: Vec<(usize, Vec<PathMatch>)> = Vec::new();
+    for path_match in matches {
+        match groups
+            .iter_mut()
+            .find(|(worktree_id, _)| *worktree_id == path_match.worktree_id)
+        {
+            Some((_, group)) => group.push(path_match),
+            None => groups.push((path_match.worktree_id, vec![path_match])),
+        }
+    }
+
+    groups
+        .into_iter()
+        .flat_map(|(_, group)| {
+            let header = show_worktree_headers.then(|| FileMatchEntry::WorktreeHeader {
+                root_name: group[0]
+                    .path_prefix
+                    .trim_end_matches(std::path::MAIN_SEPARATOR)
+                    .to_string()
+                    .into(),
+            });
+            header
                 .into_iter()
-                .map(|worktree| {
-                    let worktree = worktree.read(cx);
-
-                    PathMatchCandidateSet {
-                        snapshot: worktree.snapshot(),
-                        include_ignored: worktree
-                            .root_entry()
-                            .map_or(false, |entry| entry.is_ignored),
-                        include_root_name: true,
-                        candidates: project::Candidates::Entries,
-                    }
-                })
-                .collect::<Vec<_>>();
+                .chain(group.into_iter().map(FileMatchEntry::Match))
+        })
+        .collect()
+}
 
-            let executor = cx.background_executor().clone();
-            cx.foreground_executor().spawn(async move {
-                fuzzy::match_path_sets(
-                    candidate_sets.as_slice(),
-                    query.as_str(),
-                    None,
-                    false,
-                    100,
-                    &cancellation_flag,
-                    executor,
-                )
-                .await
+const RECENT_DIRECTORIES_KEY: &str = "assistant-context-recent-directories";
+const PINNED_DIRECTORIES_KEY: &str = "assistant-context-pinned-directories";
+const MAX_RECENT_DIRECTORIES: usize = 5;
+
+/// A directory remembered between sessions, either recently attached or pinned. Worktree ids
+/// aren't stable across sessions, so the worktree is persisted as its absolute path.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct PersistedDirectory {
+    worktree_abs_path: PathBuf,
+    path: PathBuf,
+}
+
+fn read_persisted_directories(key: &str) -> Vec<PersistedDirectory> {
+    KEY_VALUE_STORE
+        .read_kvp(key)
+        .log_err()
+        .flatten()
+        .and_then(|json| serde_json::from_str(&json).log_err())
+        .unwrap_or_default()
+}
+
+/// Moves the attached directories to the front of `recent`, keeping at most
+/// [`MAX_RECENT_DIRECTORIES`] entries.
+fn push_recent_directories(
+    recent: &mut Vec<PersistedDirectory>,
+    attached: Vec<PersistedDirectory>,
+) {
+    for directory in attached {
+        recent.retain(|recent| recent != &directory);
+        recent.insert(0, directory);
+    }
+    recent.truncate(MAX_RECENT_DIRECTORIES);
+}
+
+fn record_recent_directories(attached: Vec<PersistedDirectory>, cx: &App) {
+    if attached.is_empty() {
+        return;
+    }
+
+    db::write_and_log(cx, move || async move {
+        let mut recent = read_persisted_directories(RECENT_DIRECTORIES_KEY);
+        push_recent_directories(&mut recent, attached);
+        KEY_VALUE_STORE
+            .write_kvp(
+                RECENT_DIRECTORIES_KEY.into(),
+                serde_json::to_string(&recent)?,
+            )
+            .await
+    })
+}
+
+/// Adds `directory` to the pinned directories, or removes it if already pinned.
+fn toggle_pinned_directory(pinned: &mut Vec<PersistedDirectory>, directory: PersistedDirectory) {
+    if let Some(ix) = pinned.iter().position(|pinned| pinned == &directory) {
+        pinned.remove(ix);
+    } else {
+        pinned.push(directory);
+    }
+}
+
+fn write_pinned_directories(pinned: Vec<PersistedDirectory>, cx: &App) {
+    db::write_and_log(cx, move || async move {
+        KEY_VALUE_STORE
+            .write_kvp(
+                PINNED_DIRECTORIES_KEY.into(),
+                serde_json::to_string(&pinned)?,
+            )
+            .await
+    })
+}
+
+/// Orders entries by worktree root name, then depth, then path, so that top-level entries come
+/// first and the listing is stable between sessions.
+fn sort_browse_matches(matches: &mut [PathMatch]) {
+    matches.sort_by(|a, b| {
+        a.path_prefix
+            .cmp(&b.path_prefix)
+            .then_with(|| a.worktree_id.cmp(&b.worktree_id))
+            .then_with(|| {
+                a.path
+                    .components()
+                    .count()
+                    .cmp(&b.path.components().count())
             })
+            .then_with(|| a.path.cmp(&b.path))
+    });
+}
+
+/// How far an entry is from the directory of the active file: 0 for entries in that directory, 1
+/// for entries in its parent (i.e. its siblings), and so on up to the worktree root. Entries
+/// elsewhere, including other worktrees, have no proximity.
+fn proximity_to_active_file(path_match: &PathMatch, active_path: &ProjectPath) -> Option<usize> {
+    if path_match.worktree_id != active_path.worktree_id.to_usize() {
+        return None;
+    }
+
+    let active_directory = active_path.path.parent().unwrap_or(Path::new(""));
+    let active_depth = active_directory.components().count();
+    match path_match.path.parent() {
+        Some(parent) if active_directory.starts_with(parent) => {
+            Some(active_depth - parent.components().count())
         }
+        Some(_) => None,
+        // The worktree root is the furthest ancestor.
+        None => Some(active_depth + 1),
     }
 }
 
+/// Moves the entries around the active file to the front, nearest first, keeping the order of
+/// [`sort_browse_matches`] otherwise.
+fn sort_near_active_file(matches: &mut [PathMatch], active_path: &ProjectPath) {
+    matches.sort_by_key(|path_match| {
+        proximity_to_active_file(path_match, active_path).map_or((1, 0), |proximity| (0, proximity))
+    });
+}
+
 impl PickerDelegate for FileContextPickerDelegate {
     type ListItem = ListItem;
 
@@ -187,13 +824,24 @@ impl PickerDelegate for FileContextPickerDelegate {
         _window: &mut Window,
         _cx: &mut Context<Picker<Self>>,
     ) {
-        self.selected_index = ix;
+        // Moving up onto a header continues upwards; anything else skips down past it.
+        let backward = ix + 1 == self.selected_index;
+        self.selected_index =
+            selectable_index(&self.matches, ix, backward, FileMatchEntry::is_match).unwrap_or(ix);
     }
 
     fn placeholder_text(&self, _window: &mut Window, _cx: &mut App) -> Arc<str> {
         "Search files & directories…".into()
     }
 
+    fn no_matches_text(&self, _window: &mut Window, _cx: &mut App) -> Option<SharedString> {
+        if self.query.is_empty() {
+            Some("No recent files — type to search the project".into())
+        } else {
+            Some(format!("No files or folders match '{}'", self.query).into())
+        }
+    }
+
     fn update_matches(
         &mut self,
         query: String,
@@ -204,202 +852,981 @@ impl PickerDelegate for FileContextPickerDelegate {
             return Task::ready(());
         };
 
-        let search_task = self.search(query, Arc::<AtomicBool>::default(), &workspace, cx);
+        self.cancellation_flag
+            .store(true, atomic::Ordering::Relaxed);
+        self.cancellation_flag = Arc::default();
+        let cancellation_flag = self.cancellation_flag.clone();
+
+        let (path_query, line_range) = parse_line_range(&query);
+        let query = path_query.to_string();
+        let pinned_directories =
+            self.persisted_directory_matches(self.pinned_directories.clone(), cx);
+        let recent_directories = if query.is_empty() {
+            self.persisted_directory_matches(self.recent_directories.clone(), cx)
+        } else {
+            Vec::new()
+        };
+        let search_task = self.search(query.clone(), cancellation_flag.clone(), &workspace, cx);
+        let symlinked_directories_task = if self.directory_options.follow_symlinks {
+            Task::ready(Vec::new())
+        } else {
+            self.collect_symlinked_directories(&workspace, cx)
+        };
 
         cx.spawn_in(window, |this, mut cx| async move {
             // TODO: This should be probably be run in the background.
-            let paths = search_task.await;
+            let (mut paths, browse_truncated) = search_task.await;
+            let collected_symlinked_directories = symlinked_directories_task.await;
+            if cancellation_flag.load(atomic::Ordering::Relaxed) {
+                return;
+            }
 
-            this.update(&mut cx, |this, _cx| {
-                this.delegate.matches = paths;
+            this.update(&mut cx, |this, cx| {
+                for (worktree_id, scan_id, directories) in collected_symlinked_directories {
+                    this.delegate
+                        .symlinked_directories
+                        .insert(worktree_id, (scan_id, directories));
+                }
+                if !this.delegate.directory_options.follow_symlinks {
+                    this.delegate.remove_symlinked_directories(&mut paths);
+                }
+                let show_worktree_headers = this.delegate.show_worktree_headers(cx);
+                let pinned_match_keys = pinned_directories
+                    .iter()
+                    .map(|path_match| (path_match.worktree_id, path_match.path.clone()))
+                    .collect::<HashSet<_>>();
+                // Pinned directories always come first when browsing, but only break ties when
+                // searching, so that better matches aren't buried under them.
+                let (matches, section_entry_count) = if query.is_empty() {
+                    build_match_entries(
+                        pinned_directories,
+                        recent_directories,
+                        paths,
+                        show_worktree_headers,
+                    )
+                } else {
+                    sort_pinned_first(&mut paths, &pinned_match_keys);
+                    build_match_entries(Vec::new(), Vec::new(), paths, show_worktree_headers)
+                };
+                this.delegate.pinned_match_keys = pinned_match_keys;
+                this.delegate.section_entry_count = section_entry_count;
+                this.delegate.browse_truncated = browse_truncated;
+                this.delegate.selected_index =
+                    selectable_index(&matches, 0, false, FileMatchEntry::is_match).unwrap_or(0);
+                this.delegate.matches = matches;
+                this.delegate.query = query;
+                this.delegate.line_range = line_range;
             })
             .log_err();
         })
     }
 
-    fn confirm(&mut self, _secondary: bool, window: &mut Window, cx: &mut Context<Picker<Self>>) {
-        let Some(mat) = self.matches.get(self.selected_index) else {
+    /// Attaches the selected entries. Directories are attached with their subdirectories, or with
+    /// only their own files on secondary confirm. A file is limited to the query's line range.
+    fn confirm(&mut self, secondary: bool, window: &mut Window, cx: &mut Context<Picker<Self>>) {
+        let entries = if self.selected_directories.is_empty() {
+            let Some(mat) = self.selected_match() else {
+                return;
+            };
+            let file_name = mat
+                .path
+                .file_name()
+                .map(|os_str| os_str.to_string_lossy().into_owned())
+                .unwrap_or(mat.path_prefix.to_string());
+            let project_path = ProjectPath {
+                worktree_id: WorktreeId::from_usize(mat.worktree_id),
+                path: mat.path.clone(),
+            };
+            vec![(project_path, file_name, mat.is_dir)]
+        } else {
+            let Some(workspace) = self.workspace.upgrade() else {
+                return;
+            };
+            let project = workspace.read(cx).project().read(cx);
+            let mut selected_directories = self.selected_directories.drain().collect::<Vec<_>>();
+            selected_directories.sort();
+            selected_directories
+                .into_iter()
+                .filter_map(|(worktree_id, path)| {
+                    let file_name = match path.file_name() {
+                        Some(file_name) => file_name.to_string_lossy().into_owned(),
+                        None => project
+                            .worktree_for_id(worktree_id, cx)?
+                            .read(cx)
+                            .root_name()
+                            .to_string(),
+                    };
+                    Some((ProjectPath { worktree_id, path }, file_name, true))
+                })
+                .collect()
+        };
+
+        let Some(editor_entity) = self.editor.upgrade() else {
+            return;
+        };
+
+        let exclude = if entries.iter().any(|(_, _, is_directory)| *is_directory) {
+            match self.exclude_matcher(cx) {
+                Ok(exclude) => exclude,
+                Err(error) => {
+                    self.workspace
+                        .update(cx, |workspace, cx| {
+                            struct InvalidExcludeGlob;
+                            let id = NotificationId::unique::<InvalidExcludeGlob>();
+                            let message = format!("Invalid exclude pattern: {error}");
+                            workspace.show_toast(Toast::new(id, message), cx);
+                        })
+                        .ok();
+                    return;
+                }
+            }
+        } else {
+            PathMatcher::default()
+        };
+        let directory_options = DirectoryContextOptions {
+            exclude,
+            recursive: !secondary,
+            ..self.directory_options.clone()
+        };
+        let Some(attach_task) = attach_paths(
+            entries,
+            self.line_range.clone(),
+            directory_options,
+            self.confirm_cancellation_flag.clone(),
+            &self.workspace,
+            &editor_entity,
+            &self.context_store,
+            window,
+            cx,
+        ) else {
+            return;
+        };
+
+        let confirm_behavior = self.confirm_behavior;
+        cx.spawn_in(window, |this, mut cx| async move {
+            let attached = attach_task.await;
+            this.update_in(&mut cx, |this, window, cx| {
+                push_recent_directories(
+                    &mut this.delegate.recent_directories,
+                    attached.directories,
+                );
+                if !attached.all_succeeded {
+                    return;
+                }
+                match confirm_behavior {
+                    ConfirmBehavior::KeepOpen => {}
+                    ConfirmBehavior::Close => this.delegate.dismissed(window, cx),
+                }
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
+    fn dismissed(&mut self, _: &mut Window, cx: &mut Context<Picker<Self>>) {
+        self.cancel_pending_confirms();
+        self.context_picker
+            .update(cx, |_, cx| {
+                cx.emit(DismissEvent);
+            })
+            .ok();
+    }
+
+    fn render_footer(
+        &self,
+        window: &mut Window,
+        cx: &mut Context<Picker<Self>>,
+    ) -> Option<AnyElement> {
+        Some(
+            v_flex()
+                .w_full()
+                .p_2()
+                .gap_1()
+                .border_t_1()
+                .border_color(cx.theme().colors().border_variant)
+                .when(self.browse_truncated, |this| {
+                    this.child(
+                        Label::new(format!(
+                            "Showing the first {MAX_BROWSE_MATCHES} entries of each folder — type to search for more"
+                        ))
+                        .size(LabelSize::Small)
+                        .color(Color::Muted),
+                    )
+                })
+                .child(
+                    h_flex()
+                        .justify_between()
+                        .child(
+                            h_flex()
+                                .gap_2()
+                                .child(
+                                    Checkbox::new("include-ignored", self.include_ignored.into())
+                                        .label("Include ignored files")
+                                        .on_click(cx.listener(|picker, state, window, cx| {
+                                            picker.delegate.include_ignored =
+                                                *state == ToggleState::Selected;
+                                            picker.refresh(window, cx);
+                                        })),
+                                )
+                                .child(
+                                    Checkbox::new(
+                                        "follow-symlinks",
+                                        self.directory_options.follow_symlinks.into(),
+                                    )
+                                        .label("Follow symlinks")
+                                        .on_click(cx.listener(|picker, state, window, cx| {
+                                            picker.delegate.directory_options.follow_symlinks =
+                                                *state == ToggleState::Selected;
+                                            picker.refresh(window, cx);
+                                        })),
+                                )
+                                .child(
+                                    Checkbox::new("near-active-file", self.near_active_file.into())
+                                        .label("Near active file")
+                                        .on_click(cx.listener(|picker, state, window, cx| {
+                                            picker.delegate.near_active_file =
+                                                *state == ToggleState::Selected;
+                                            picker.refresh(window, cx);
+                                        })),
+                                )
+                                .child(
+                                    div()
+                                        .id("directory-file-list")
+                                        .child(
+                                            Checkbox::new(
+                                                "directory-file-list-checkbox",
+                                                (self.directory_options.mode
+                                                    == DirectoryContextMode::FileList)
+                                                    .into(),
+                                            )
+                                            .label("Folders as file list")
+                                            .on_click(cx.listener(|picker, state, _, cx| {
+                                                picker.delegate.directory_options.mode =
+                                                    if *state == ToggleState::Selected {
+                                                        DirectoryContextMode::FileList
+                                                    } else {
+                                                        DirectoryContextMode::Contents
+                                                    };
+                                                cx.notify();
+                                            })),
+                                        )
+                                        .tooltip(Tooltip::text(
+                                            "Attach the paths and sizes of a folder's files instead of their contents",
+                                        )),
+                                ),
+                        )
+                        .when(!self.selected_directories.is_empty(), |footer| {
+                            footer.child(
+                                Label::new(format!("{} selected", self.selected_directories.len()))
+                                    .size(LabelSize::Small)
+                                    .color(Color::Muted),
+                            )
+                        }),
+                )
+                .child(
+                    h_flex()
+                        .gap_2()
+                        .child(
+                            Label::new("Exclude")
+                                .size(LabelSize::Small)
+                                .color(Color::Muted),
+                        )
+                        .child(div().flex_1().child(self.exclude_editor.clone())),
+                )
+                .child(
+                    h_flex()
+                        .gap_1()
+                        .justify_end()
+                        .children(
+                            KeyBinding::for_action(&menu::SecondaryConfirm, window, cx).map(
+                                |keybind| {
+                                    Button::new("attach-top-level", "Attach Top Level Only")
+                                        .label_size(LabelSize::Small)
+                                        .key_binding(keybind)
+                                        .tooltip(Tooltip::text(
+                                            "Skip the files in subfolders of a folder",
+                                        ))
+                                        .on_click(|_, window, cx| {
+                                            window.dispatch_action(
+                                                menu::SecondaryConfirm.boxed_clone(),
+                                                cx,
+                                            )
+                                        })
+                                },
+                            ),
+                        )
+                        .children(KeyBinding::for_action(&menu::Confirm, window, cx).map(
+                            |keybind| {
+                                Button::new("attach", "Attach")
+                                    .label_size(LabelSize::Small)
+                                    .key_binding(keybind)
+                                    .on_click(|_, window, cx| {
+                                        window.dispatch_action(menu::Confirm.boxed_clone(), cx)
+                                    })
+                            },
+                        )),
+                )
+                .into_any(),
+        )
+    }
+
+    fn render_match(
+        &self,
+        ix: usize,
+        selected: bool,
+        _window: &mut Window,
+        cx: &mut Context<Picker<Self>>,
+    ) -> Option<Self::ListItem> {
+        let path_match = match &self.matches[ix] {
+            FileMatchEntry::PinnedHeader => {
+                return Some(render_section_header(ix, "Pinned".into()));
+            }
+            FileMatchEntry::RecentHeader => {
+                return Some(render_section_header(ix, "Recent".into()));
+            }
+            FileMatchEntry::WorktreeHeader { root_name } => {
+                return Some(render_section_header(ix, root_name.clone()));
+            }
+            FileMatchEntry::Match(path_match) => path_match,
+        };
+        // Pinned and recent directories aren't under a worktree header, so they name their
+        // worktree.
+        let in_directory_section = ix < self.section_entry_count;
+        let is_pinned = path_match.is_dir
+            && self
+                .pinned_match_keys
+                .contains(&(path_match.worktree_id, path_match.path.clone()));
+        let child_counts = path_match
+            .is_dir
+            .then(|| self.child_counts(path_match, cx))
+            .flatten();
+        let is_checked = path_match.is_dir
+            && self.selected_directories.contains(&(
+                WorktreeId::from_usize(path_match.worktree_id),
+                path_match.path.clone(),
+            ));
+
+        Some(
+            ListItem::new(ix)
+                .inset(true)
+                .toggle_state(selected)
+                .start_slot::<Icon>(is_checked.then(|| {
+                    Icon::new(IconName::Check)
+                        .size(IconSize::Small)
+                        .color(Color::Accent)
+                }))
+                .child(self.render_pin_menu(
+                    ix,
+                    path_match,
+                    is_pinned,
+                    render_file_context_entry(
+                        ElementId::NamedInteger("file-ctx-picker".into(), ix),
+                        WorktreeId::from_usize(path_match.worktree_id),
+                        &path_match.path,
+                        &path_match.path_prefix,
+                        in_directory_section && self.show_worktree_headers(cx),
+                        &path_match.positions,
+                        path_match.is_dir,
+                        self.context_store.clone(),
+                        cx,
+                    ),
+                    cx,
+                ))
+                .end_slot(
+                    h_flex()
+                        .gap_1()
+                        .children(child_counts.map(|(files, directories)| {
+                            Label::new(format_child_counts(files, directories))
+                                .size(LabelSize::Small)
+                                .color(Color::Muted)
+                        }))
+                        .when(is_pinned, |this| {
+                            this.child(
+                                Icon::new(IconName::Pin)
+                                    .size(IconSize::XSmall)
+                                    .color(Color::Muted),
+                            )
+                        }),
+                ),
+        )
+    }
+}
+
+impl Drop for FileContextPickerDelegate {
+    fn drop(&mut self) {
+        self.cancel_pending_confirms();
+    }
+}
+
+impl FileContextPickerDelegate {
+    /// Stops directories that are still being read from being added to the context.
+    fn cancel_pending_confirms(&mut self) {
+        self.confirm_cancellation_flag
+            .store(true, atomic::Ordering::Relaxed);
+        self.confirm_cancellation_flag = Arc::default();
+    }
+
+    /// Adds the highlighted directory to the multi-selection, or removes it if already selected.
+    fn toggle_selected_directory(&mut self) {
+        let Some(mat) = self.selected_match() else {
+            return;
+        };
+        if !mat.is_dir {
+            return;
+        }
+
+        let key = (WorktreeId::from_usize(mat.worktree_id), mat.path.clone());
+        if !self.selected_directories.remove(&key) {
+            self.selected_directories.insert(key);
+        }
+    }
+
+    fn selected_match(&self) -> Option<&PathMatch> {
+        match self.matches.get(self.selected_index)? {
+            FileMatchEntry::PinnedHeader
+            | FileMatchEntry::RecentHeader
+            | FileMatchEntry::WorktreeHeader { .. } => None,
+            FileMatchEntry::Match(path_match) => Some(path_match),
+        }
+    }
+
+    /// Parses the exclude editor's comma-separated globs.
+    fn exclude_matcher(&self, cx: &App) -> anyhow::Result<PathMatcher> {
+        let globs = self
+            .exclude_editor
+            .read(cx)
+            .text(cx)
+            .split(',')
+            .map(str::trim)
+            .filter(|glob| !glob.is_empty())
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        Ok(PathMatcher::new(&globs)?)
+    }
+
+    /// Resolves persisted directories against the open worktrees, skipping those that are no
+    /// longer part of the project.
+    fn persisted_directory_matches(
+        &self,
+        directories: Vec<PersistedDirectory>,
+        cx: &App,
+    ) -> Vec<PathMatch> {
+        let Some(workspace) = self.workspace.upgrade() else {
+            return Vec::new();
+        };
+        let project = workspace.read(cx).project().read(cx);
+
+        directories
+            .into_iter()
+            .filter_map(|recent| {
+                let worktree = project.worktrees(cx).find(|worktree| {
+                    worktree.read(cx).abs_path().as_ref() == recent.worktree_abs_path.as_path()
+                })?;
+                let worktree = worktree.read(cx);
+                let entry = worktree
+                    .entry_for_path(&recent.path)
+                    .filter(|entry| entry.is_dir())?;
+                if entry.is_ignored && !includes_ignored_entries(worktree, self.include_ignored) {
+                    return None;
+                }
+
+                Some(PathMatch {
+                    score: 0.,
+                    positions: Vec::new(),
+                    worktree_id: worktree.id().to_usize(),
+                    path: entry.path.clone(),
+                    path_prefix: worktree.root_name().into(),
+                    distance_to_relative_ancestor: 0,
+                    is_dir: true,
+                })
+            })
+            .collect()
+    }
+
+    /// Pins the directory so it is listed first, or unpins it if already pinned.
+    fn toggle_pinned(&mut self, worktree_id: WorktreeId, path: Arc<Path>, cx: &App) {
+        let Some(worktree_abs_path) = worktree_abs_path(&self.workspace, worktree_id, cx) else {
             return;
         };
+        toggle_pinned_directory(
+            &mut self.pinned_directories,
+            PersistedDirectory {
+                worktree_abs_path,
+                path: path.to_path_buf(),
+            },
+        );
+        write_pinned_directories(self.pinned_directories.clone(), cx);
+    }
+
+    /// Wraps a directory's row in a context menu for pinning or unpinning it. Files are returned
+    /// as is.
+    fn render_pin_menu(
+        &self,
+        ix: usize,
+        path_match: &PathMatch,
+        is_pinned: bool,
+        entry: Stateful<Div>,
+        cx: &mut Context<Picker<Self>>,
+    ) -> AnyElement {
+        if !path_match.is_dir {
+            return entry.into_any_element();
+        }
+
+        let picker = cx.entity().downgrade();
+        let worktree_id = WorktreeId::from_usize(path_match.worktree_id);
+        let path = path_match.path.clone();
+        right_click_menu(("file-ctx-picker-pin-menu", ix))
+            .trigger(entry)
+            .menu(move |window, cx| {
+                let picker = picker.clone();
+                let path = path.clone();
+                ContextMenu::build(window, cx, move |menu, _, _| {
+                    let label = if is_pinned { "Unpin" } else { "Pin to Top" };
+                    menu.entry(label, None, move |window, cx| {
+                        picker
+                            .update(cx, |picker, cx| {
+                                picker.delegate.toggle_pinned(worktree_id, path.clone(), cx);
+                                picker.refresh(window, cx);
+                            })
+                            .ok();
+                    })
+                })
+            })
+            .into_any_element()
+    }
+
+    /// Drops the matches that are symlinked directories or lie inside one.
+    /// Collects the symlinked directories of the worktrees that changed since they were last
+    /// collected, in the background, so hiding the entries in them is a set lookup per ancestor
+    /// rather than a worktree lookup.
+    fn collect_symlinked_directories(
+        &self,
+        workspace: &Entity<Workspace>,
+        cx: &App,
+    ) -> Task<Vec<(WorktreeId, usize, HashSet<Arc<Path>>)>> {
+        let snapshots = workspace
+            .read(cx)
+            .project()
+            .read(cx)
+            .worktrees(cx)
+            .filter_map(|worktree| {
+                let worktree = worktree.read(cx);
+                let scan_id = worktree.scan_id();
+                let is_current = self
+                    .symlinked_directories
+                    .get(&worktree.id())
+                    .map_or(false, |(collected_scan_id, _)| {
+                        *collected_scan_id == scan_id
+                    });
+                (!is_current).then(|| (worktree.id(), scan_id, worktree.snapshot()))
+            })
+            .collect::<Vec<_>>();
+        if snapshots.is_empty() {
+            return Task::ready(Vec::new());
+        }
+
+        cx.background_spawn(async move {
+            snapshots
+                .into_iter()
+                .map(|(worktree_id, scan_id, snapshot)| {
+                    let directories = snapshot
+                        .entries(true, 0)
+                        .filter(|entry| entry.is_dir() && entry.canonical_path.is_some())
+                        .map(|entry| entry.path.clone())
+                        .collect();
+                    (worktree_id, scan_id, directories)
+                })
+                .collect()
+        })
+    }
+
+    fn remove_symlinked_directories(&self, paths: &mut Vec<PathMatch>) {
+        paths.retain(|mat| {
+            self.symlinked_directories
+                .get(&WorktreeId::from_usize(mat.worktree_id))
+                .map_or(true, |(_, directories)| {
+                    !is_in_symlinked_directory(directories, &mat.path)
+                })
+        });
+    }
+
+    /// Worktree headers are only needed to tell entries apart when there are several worktrees.
+    fn show_worktree_headers(&self, cx: &App) -> bool {
+        self.workspace.upgrade().map_or(false, |workspace| {
+            workspace.read(cx).visible_worktrees(cx).count() > 1
+        })
+    }
+
+    fn worktree_for_match(&self, path_match: &PathMatch, cx: &App) -> Option<Entity<Worktree>> {
+        let workspace = self.workspace.upgrade()?;
+        workspace
+            .read(cx)
+            .project()
+            .read(cx)
+            .worktree_for_id(WorktreeId::from_usize(path_match.worktree_id), cx)
+    }
+
+    /// Lists the immediate children of the highlighted directory, folders first.
+    fn directory_preview(&self, cx: &App) -> Option<DirectoryPreview> {
+        let path_match = self.selected_match().filter(|mat| mat.is_dir)?;
+        let worktree = self.worktree_for_match(path_match, cx)?;
+
+        let worktree = worktree.read(cx);
+        let include_ignored = includes_ignored_entries(worktree, self.include_ignored);
+        let mut entries = worktree
+            .child_entries(&path_match.path)
+            .filter(|entry| include_ignored || !entry.is_ignored)
+            .map(|entry| DirectoryPreviewEntry {
+                name: entry
+                    .path
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into_owned()
+                    .into(),
+                is_dir: entry.is_dir(),
+                size: entry.size,
+            })
+            .collect::<Vec<_>>();
+        entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.cmp(&b.name)));
+
+        Some(DirectoryPreview {
+            worktree_id: WorktreeId::from_usize(path_match.worktree_id),
+            path: path_match.path.clone(),
+            entries,
+        })
+    }
+
+    /// Counts the immediate child files and directories of a matched directory.
+    fn child_counts(&self, path_match: &PathMatch, cx: &App) -> Option<(usize, usize)> {
+        let worktree = self.worktree_for_match(path_match, cx)?;
+
+        let mut files = 0;
+        let mut directories = 0;
+        for entry in worktree.read(cx).child_entries(&path_match.path) {
+            if entry.is_dir() {
+                directories += 1;
+            } else if entry.is_file() {
+                files += 1;
+            }
+        }
+
+        Some((files, directories))
+    }
+}
+
+fn render_section_header(ix: usize, label: SharedString) -> ListItem {
+    ListItem::new(ix)
+        .inset(true)
+        .selectable(false)
+        // Keep the picker from confirming the match below the header.
+        .on_click(|_, _, cx| cx.stop_propagation())
+        .child(Label::new(label).size(LabelSize::Small).color(Color::Muted))
+}
+
+fn format_child_counts(files: usize, directories: usize) -> String {
+    let pluralize = |count: usize, noun: &str| {
+        if count == 1 {
+            format!("{count} {noun}")
+        } else {
+            format!("{count} {noun}s")
+        }
+    };
+
+    format!(
+        "{}, {}",
+        pluralize(files, "file"),
+        pluralize(directories, "folder")
+    )
+}
+
+/// The outcome of [`attach_paths`].
+#[derive(Default)]
+pub(super) struct AttachedPaths {
+    /// Whether every path was attached, as opposed to some failing or all being cancelled.
+    pub(super) all_succeeded: bool,
+    /// The directories that were attached, which were recorded as recent.
+    directories: Vec<PersistedDirectory>,
+}
+
+/// Attaches files and directories to the context the way the file picker's confirm does,
+/// inserting a crease for each. Directories that are already attached are skipped with a toast.
+/// The returned task resolves once errors have been shown, after the directories that were
+/// attached are recorded as recent. It is `None` when there was nothing to attach.
+pub(super) fn attach_paths(
+    entries: Vec<(ProjectPath, String, bool)>,
+    line_range: Option<Range<u32>>,
+    directory_options: DirectoryContextOptions,
+    cancellation_flag: Arc<AtomicBool>,
+    workspace: &WeakEntity<Workspace>,
+    editor: &Entity<Editor>,
+    context_store: &WeakEntity<ContextStore>,
+    window: &mut Window,
+    cx: &mut App,
+) -> Option<Task<AttachedPaths>> {
+    let mut tasks = Vec::new();
+    for (project_path, file_name, is_directory) in entries {
+        let full_path = project_path.path.display().to_string();
+
+        if is_directory {
+            let already_included = context_store
+                .read_with(cx, |context_store, _cx| {
+                    context_store.includes_directory(&project_path).is_some()
+                })
+                .unwrap_or(false);
+            if already_included {
+                workspace
+                    .update(cx, |workspace, cx| {
+                        struct DirectoryAlreadyIncluded;
+                        let id = NotificationId::unique::<DirectoryAlreadyIncluded>();
+                        let message = format!("{full_path} is already in the context");
+                        workspace.show_toast(Toast::new(id, message).autohide(), cx);
+                    })
+                    .ok();
+                continue;
+            }
+        }
+
+        let recent_directory = is_directory
+            .then(|| worktree_abs_path(workspace, project_path.worktree_id, cx))
+            .flatten()
+            .map(|worktree_abs_path| PersistedDirectory {
+                worktree_abs_path,
+                path: project_path.path.to_path_buf(),
+            });
+
+        // Only the first inserted path replaces the `@` that opened the picker.
+        let replace_trigger = tasks.is_empty();
+        insert_crease_for_path(
+            editor,
+            &full_path,
+            file_name,
+            is_directory,
+            replace_trigger,
+            window,
+            cx,
+        );
+
+        let Some(task) = context_store
+            .update(cx, |context_store, cx| {
+                if is_directory {
+                    context_store.attach_directory(
+                        project_path,
+                        directory_options.clone(),
+                        cancellation_flag.clone(),
+                        cx,
+                    )
+                } else {
+                    context_store.add_file_from_path(project_path, line_range.clone(), cx)
+                }
+            })
+            .ok()
+        else {
+            return None;
+        };
+        tasks.push((task, recent_directory));
+    }
 
-        let file_name = mat
-            .path
-            .file_name()
-            .map(|os_str| os_str.to_string_lossy().into_owned())
-            .unwrap_or(mat.path_prefix.to_string());
-
-        let full_path = mat.path.display().to_string();
+    if tasks.is_empty() {
+        return None;
+    }
 
-        let project_path = ProjectPath {
-            worktree_id: WorktreeId::from_usize(mat.worktree_id),
-            path: mat.path.clone(),
+    let (tasks, recent_directories): (Vec<_>, Vec<_>) = tasks.into_iter().unzip();
+    Some(window.spawn(cx, |mut cx| async move {
+        let results = future::join_all(tasks).await;
+        if cancellation_flag.load(atomic::Ordering::Relaxed) {
+            return AttachedPaths::default();
+        }
+        let mut attached = AttachedPaths {
+            all_succeeded: true,
+            directories: Vec::new(),
         };
+        for (result, recent_directory) in results.into_iter().zip(recent_directories) {
+            if result.notify_async_err(&mut cx).is_some() {
+                attached.directories.extend(recent_directory);
+            } else {
+                attached.all_succeeded = false;
+            }
+        }
+        cx.update(|_, cx| record_recent_directories(attached.directories.clone(), cx))
+            .ok();
+        attached
+    }))
+}
 
-        let is_directory = mat.is_dir;
+fn worktree_abs_path(
+    workspace: &WeakEntity<Workspace>,
+    worktree_id: WorktreeId,
+    cx: &App,
+) -> Option<PathBuf> {
+    let workspace = workspace.upgrade()?;
+    let worktree = workspace
+        .read(cx)
+        .project()
+        .read(cx)
+        .worktree_for_id(worktree_id, cx)?;
+    Some(worktree.read(cx).abs_path().to_path_buf())
+}
 
-        let Some(editor_entity) = self.editor.upgrade() else {
-            return;
-        };
+/// Inserts `full_path` into the editor at each cursor and folds it into a crease showing the
+/// entry's icon and name. When `replace_trigger` is set, the `@` before each cursor is replaced.
+pub(super) fn insert_crease_for_path(
+    editor_entity: &Entity<Editor>,
+    full_path: &str,
+    file_name: String,
+    is_directory: bool,
+    replace_trigger: bool,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    editor_entity.update(cx, |editor, cx| {
+        editor.transact(window, cx, |editor, window, cx| {
+            // Move empty selections left by 1 column to select the `@`s, so they get overwritten when we insert.
+            if replace_trigger {
+                let mut selections = editor.selections.all::<MultiBufferPoint>(cx);
 
-        editor_entity.update(cx, |editor, cx| {
-            editor.transact(window, cx, |editor, window, cx| {
-                // Move empty selections left by 1 column to select the `@`s, so they get overwritten when we insert.
-                {
-                    let mut selections = editor.selections.all::<MultiBufferPoint>(cx);
-
-                    for selection in selections.iter_mut() {
-                        if selection.is_empty() {
-                            let old_head = selection.head();
-                            let new_head = MultiBufferPoint::new(
-                                old_head.row,
-                                old_head.column.saturating_sub(1),
-                            );
-                            selection.set_head(new_head, SelectionGoal::None);
-                        }
+                for selection in selections.iter_mut() {
+                    if selection.is_empty() {
+                        let old_head = selection.head();
+                        let new_head =
+                            MultiBufferPoint::new(old_head.row, old_head.column.saturating_sub(1));
+                        selection.set_head(new_head, SelectionGoal::None);
                     }
-
-                    editor.change_selections(Some(Autoscroll::fit()), window, cx, |s| {
-                        s.select(selections)
-                    });
                 }
 
-                let start_anchors = {
-                    let snapshot = editor.buffer().read(cx).snapshot(cx);
-                    editor
-                        .selections
-                        .all::<Point>(cx)
-                        .into_iter()
-                        .map(|selection| snapshot.anchor_before(selection.start))
-                        .collect::<Vec<_>>()
-                };
+                editor.change_selections(Some(Autoscroll::fit()), window, cx, |s| {
+                    s.select(selections)
+                });
+            }
 
-                editor.insert(&full_path, window, cx);
+            let start_anchors = {
+                let snapshot = editor.buffer().read(cx).snapshot(cx);
+                editor
+                    .selections
+                    .all::<Point>(cx)
+                    .into_iter()
+                    .map(|selection| snapshot.anchor_before(selection.start))
+                    .collect::<Vec<_>>()
+            };
 
-                let end_anchors = {
-                    let snapshot = editor.buffer().read(cx).snapshot(cx);
-                    editor
-                        .selections
-                        .all::<Point>(cx)
-                        .into_iter()
-                        .map(|selection| snapshot.anchor_after(selection.end))
-                        .collect::<Vec<_>>()
-                };
+            editor.insert(full_path, window, cx);
 
-                editor.insert("\n", window, cx); // Needed to end the fold
+            let end_anchors = {
+                let snapshot = editor.buffer().read(cx).snapshot(cx);
+                editor
+                    .selections
+                    .all::<Point>(cx)
+                    .into_iter()
+                    .map(|selection| snapshot.anchor_after(selection.end))
+                    .collect::<Vec<_>>()
+            };
 
-                let file_icon = if is_directory {
-                    FileIcons::get_folder_icon(false, cx)
-                } else {
-                    FileIcons::get_icon(&Path::new(&full_path), cx)
-                }
-                .unwrap_or_else(|| SharedString::new(""));
+            editor.insert("\n", window, cx); // Needed to end the fold
 
-                let placeholder = FoldPlaceholder {
-                    render: render_fold_icon_button(
-                        file_icon,
-                        file_name.into(),
-                        editor_entity.downgrade(),
-                    ),
-                    ..Default::default()
-                };
+            let file_icon = if is_directory {
+                FileIcons::get_folder_icon(false, cx)
+            } else {
+                FileIcons::get_icon(&Path::new(full_path), cx)
+            }
+            .unwrap_or_else(|| SharedString::new(""));
 
-                let render_trailer =
-                    move |_row, _unfold, _window: &mut Window, _cx: &mut App| Empty.into_any();
+            let placeholder = FoldPlaceholder {
+                render: render_fold_icon_button(
+                    file_icon,
+                    file_name.into(),
+                    editor_entity.downgrade(),
+                ),
+                ..Default::default()
+            };
 
-                let buffer = editor.buffer().read(cx).snapshot(cx);
-                let mut rows_to_fold = BTreeSet::new();
-                let crease_iter = start_anchors
-                    .into_iter()
-                    .zip(end_anchors)
-                    .map(|(start, end)| {
-                        rows_to_fold.insert(MultiBufferRow(start.to_point(&buffer).row));
-
-                        Crease::inline(
-                            start..end,
-                            placeholder.clone(),
-                            fold_toggle("tool-use"),
-                            render_trailer,
-                        )
-                    });
+            let render_trailer =
+                move |_row, _unfold, _window: &mut Window, _cx: &mut App| Empty.into_any();
 
-                editor.insert_creases(crease_iter, cx);
+            let buffer = editor.buffer().read(cx).snapshot(cx);
+            let mut rows_to_fold = BTreeSet::new();
+            let crease_iter = start_anchors
+                .into_iter()
+                .zip(end_anchors)
+                .map(|(start, end)| {
+                    rows_to_fold.insert(MultiBufferRow(start.to_point(&buffer).row));
 
-                for buffer_row in rows_to_fold {
-                    editor.fold_at(&FoldAt { buffer_row }, window, cx);
-                }
-            });
-        });
+                    Crease::inline(
+                        start..end,
+                        placeholder.clone(),
+                        fold_toggle("tool-use"),
+                        render_trailer,
+                    )
+                });
 
-        let Some(task) = self
-            .context_store
-            .update(cx, |context_store, cx| {
-                if is_directory {
-                    context_store.add_directory(project_path, cx)
-                } else {
-                    context_store.add_file_from_path(project_path, cx)
-                }
-            })
-            .ok()
-        else {
-            return;
-        };
+            editor.insert_creases(crease_iter, cx);
 
-        let confirm_behavior = self.confirm_behavior;
-        cx.spawn_in(window, |this, mut cx| async move {
-            match task.await.notify_async_err(&mut cx) {
-                None => anyhow::Ok(()),
-                Some(()) => this.update_in(&mut cx, |this, window, cx| match confirm_behavior {
-                    ConfirmBehavior::KeepOpen => {}
-                    ConfirmBehavior::Close => this.delegate.dismissed(window, cx),
-                }),
+            for buffer_row in rows_to_fold {
+                editor.fold_at(&FoldAt { buffer_row }, window, cx);
             }
-        })
-        .detach_and_log_err(cx);
-    }
+        });
+    });
+}
 
-    fn dismissed(&mut self, _: &mut Window, cx: &mut Context<Picker<Self>>) {
-        self.context_picker
-            .update(cx, |_, cx| {
-                cx.emit(DismissEvent);
-            })
-            .ok();
+/// Splits fuzzy match positions, which are byte offsets into `path_prefix` followed by `path`,
+/// into positions within the file name and within the directory shown next to it.
+fn split_match_positions(
+    path: &Path,
+    path_prefix: &str,
+    show_root_name: bool,
+    positions: &[usize],
+) -> (Vec<usize>, Vec<usize>) {
+    let root_name = path_prefix.trim_end_matches(std::path::MAIN_SEPARATOR);
+    if path == Path::new("") {
+        let file_name_positions = positions
+            .iter()
+            .copied()
+            .filter(|position| *position < root_name.len())
+            .collect();
+        return (file_name_positions, Vec::new());
     }
 
-    fn render_match(
-        &self,
-        ix: usize,
-        selected: bool,
-        _window: &mut Window,
-        cx: &mut Context<Picker<Self>>,
-    ) -> Option<Self::ListItem> {
-        let path_match = &self.matches[ix];
+    let path_string = path.to_string_lossy();
+    let file_name_len = path
+        .file_name()
+        .map_or(0, |name| name.to_string_lossy().len());
+    let file_name_start = path_prefix.len() + path_string.len() - file_name_len;
+    // The directory is displayed as `{root_name}/{parent}/`, regardless of whether the prefix
+    // already ends with a separator, or as just `{parent}/` when the root name is hidden.
+    let displayed_prefix_len = if show_root_name {
+        root_name.len() + 1
+    } else {
+        0
+    };
 
-        Some(
-            ListItem::new(ix)
-                .inset(true)
-                .toggle_state(selected)
-                .child(render_file_context_entry(
-                    ElementId::NamedInteger("file-ctx-picker".into(), ix),
-                    &path_match.path,
-                    &path_match.path_prefix,
-                    path_match.is_dir,
-                    self.context_store.clone(),
-                    cx,
-                )),
-        )
+    let mut file_name_positions = Vec::new();
+    let mut directory_positions = Vec::new();
+    for &position in positions {
+        if position >= file_name_start {
+            file_name_positions.push(position - file_name_start);
+        } else if position < path_prefix.len() {
+            if show_root_name {
+                directory_positions.push(position);
+            }
+        } else {
+            directory_positions.push(position - path_prefix.len() + displayed_prefix_len);
+        }
     }
+    (file_name_positions, directory_positions)
 }
 
 pub fn render_file_context_entry(
     id: ElementId,
+    worktree_id: WorktreeId,
     path: &Path,
     path_prefix: &Arc<str>,
+    show_root_name: bool,
+    positions: &[usize],
     is_directory: bool,
     context_store: WeakEntity<ContextStore>,
     cx: &App,
 ) -> Stateful<Div> {
+    let root_name = path_prefix.trim_end_matches(std::path::MAIN_SEPARATOR);
     let (file_name, directory) = if path == Path::new("") {
-        (SharedString::from(path_prefix.clone()), None)
+        (SharedString::from(root_name.to_string()), None)
     } else {
         let file_name = path
             .file_name()
@@ -408,24 +1835,39 @@ pub fn render_file_context_entry(
             .to_string()
             .into();
 
-        let mut directory = format!("{}/", path_prefix);
+        let mut directory = if show_root_name {
+            format!("{}/", root_name)
+        } else {
+            String::new()
+        };
 
         if let Some(parent) = path.parent().filter(|parent| parent != &Path::new("")) {
             directory.push_str(&parent.to_string_lossy());
             directory.push('/');
         }
 
-        (file_name, Some(directory))
+        (
+            file_name,
+            Some(directory).filter(|directory| !directory.is_empty()),
+        )
     };
+    let (file_name_positions, directory_positions) =
+        split_match_positions(path, path_prefix, show_root_name, positions);
 
     let added = context_store.upgrade().and_then(|context_store| {
+        let project_path = ProjectPath {
+            worktree_id,
+            path: path.into(),
+        };
         if is_directory {
             context_store
                 .read(cx)
-                .includes_directory(path)
+                .includes_directory(&project_path)
                 .map(FileInclusion::Direct)
         } else {
-            context_store.read(cx).will_include_file_path(path, cx)
+            context_store
+                .read(cx)
+                .will_include_file_path(&project_path, cx)
         }
     });
 
@@ -445,9 +1887,9 @@ pub fn render_file_context_entry(
         .child(
             h_flex()
                 .gap_1()
-                .child(Label::new(file_name))
+                .child(HighlightedLabel::new(file_name, file_name_positions))
                 .children(directory.map(|directory| {
-                    Label::new(directory)
+                    HighlightedLabel::new(directory, directory_positions)
                         .size(LabelSize::Small)
                         .color(Color::Muted)
                 })),
@@ -566,3 +2008,772 @@ fn fold_toggle(
             .into_any_element()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+
+    use fs::FakeFs;
+    use gpui::TestAppContext;
+    use project::Project;
+    use serde_json::json;
+    use settings::SettingsStore;
+    use util::path;
+    use workspace::AppState;
+
+    #[test]
+    fn test_split_match_positions() {
+        let path = Path::new("src/picker.rs");
+        let prefix = format!("zed{}", std::path::MAIN_SEPARATOR);
+
+        // Candidate string: "zed/src/picker.rs", matching "zsp".
+        let (file_name, directory) = split_match_positions(path, &prefix, true, &[0, 4, 8]);
+        assert_eq!(file_name, vec![0]);
+        assert_eq!(directory, vec![0, 4]);
+
+        // Without a trailing separator the prefix is still displayed as "zed/".
+        let (file_name, directory) = split_match_positions(path, "zed", true, &[0, 3, 7]);
+        assert_eq!(file_name, vec![0]);
+        assert_eq!(directory, vec![0, 4]);
+
+        // With a single worktree the root name is hidden, so matches in it aren't highlighted.
+        let (file_name, directory) = split_match_positions(path, &prefix, false, &[0, 4, 8]);
+        assert_eq!(file_name, vec![0]);
+        assert_eq!(directory, vec![0]);
+
+        let (file_name, directory) =
+            split_match_positions(Path::new(""), &prefix, true, &[0, 2, 3]);
+        assert_eq!(file_name, vec![0, 2]);
+        assert!(directory.is_empty());
+    }
+
+    #[test]
+    fn test_parse_line_range() {
+        assert_eq!(parse_line_range("src/main.rs"), ("src/main.rs", None));
+        // Lines are one-based and inclusive, rows are zero-based and exclusive.
+        assert_eq!(
+            parse_line_range("src/main.rs:40-80"),
+            ("src/main.rs", Some(39..80))
+        );
+        assert_eq!(parse_line_range("main.rs:1-1"), ("main.rs", Some(0..1)));
+        assert_eq!(parse_line_range("main.rs:7"), ("main.rs", Some(6..7)));
+
+        // Invalid ranges are dropped, so the whole file is attached.
+        assert_eq!(parse_line_range("main.rs:0-3"), ("main.rs", None));
+        assert_eq!(parse_line_range("main.rs:80-40"), ("main.rs", None));
+
+        // Anything else after a colon is part of the path query.
+        assert_eq!(parse_line_range("main.rs:"), ("main.rs:", None));
+        assert_eq!(parse_line_range("a:b"), ("a:b", None));
+        assert_eq!(parse_line_range("main.rs:4-"), ("main.rs:4-", None));
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(0), "0 B");
+        assert_eq!(format_size(1023), "1023 B");
+        assert_eq!(format_size(1536), "1.5 KB");
+        assert_eq!(format_size(3 * 1024 * 1024), "3.0 MB");
+    }
+
+    #[test]
+    fn test_sort_browse_matches() {
+        let path_match = |worktree_id: usize, root_name: &str, path: &str| PathMatch {
+            score: 0.,
+            positions: Vec::new(),
+            worktree_id,
+            path: Path::new(path).into(),
+            path_prefix: root_name.into(),
+            distance_to_relative_ancestor: 0,
+            is_dir: true,
+        };
+        let mut matches = vec![
+            path_match(1, "zed", "crates/ui/src"),
+            path_match(2, "docs", "src"),
+            path_match(1, "zed", "crates"),
+            path_match(1, "zed", "assets"),
+            path_match(1, "zed", ""),
+            path_match(1, "zed", "crates/ui"),
+            path_match(1, "zed", "assets/icons"),
+        ];
+
+        sort_browse_matches(&mut matches);
+
+        assert_eq!(
+            matches
+                .iter()
+                .map(|path_match| format!(
+                    "{}:{}",
+                    path_match.path_prefix,
+                    path_match.path.display()
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                "docs:src",
+                "zed:",
+                "zed:assets",
+                "zed:crates",
+                "zed:assets/icons",
+                "zed:crates/ui",
+                "zed:crates/ui/src",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sort_near_active_file() {
+        let path_match = |worktree_id: usize, path: &str| PathMatch {
+            score: 0.,
+            positions: Vec::new(),
+            worktree_id,
+            path: Path::new(path).into(),
+            path_prefix: "zed".into(),
+            distance_to_relative_ancestor: 0,
+            is_dir: true,
+        };
+        let mut matches = vec![
+            path_match(1, ""),
+            path_match(1, "assets"),
+            path_match(1, "crates"),
+            path_match(2, "crates"),
+            path_match(1, "assets/icons"),
+            path_match(1, "crates/editor"),
+            path_match(1, "crates/ui"),
+            path_match(1, "crates/ui/src"),
+            path_match(1, "crates/ui/Cargo.toml"),
+            path_match(1, "crates/ui/src/button.rs"),
+        ];
+        let active_path = ProjectPath {
+            worktree_id: WorktreeId::from_usize(1),
+            path: Path::new("crates/ui/src/lib.rs").into(),
+        };
+
+        sort_near_active_file(&mut matches, &active_path);
+
+        assert_eq!(
+            matches
+                .iter()
+                .map(|path_match| format!(
+                    "{}:{}",
+                    path_match.worktree_id,
+                    path_match.path.display()
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                "1:crates/ui/src/button.rs",
+                "1:crates/ui/src",
+                "1:crates/ui/Cargo.toml",
+                "1:crates/editor",
+                "1:crates/ui",
+                "1:assets",
+                "1:crates",
+                "1:",
+                "2:crates",
+                "1:assets/icons",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_group_matches_by_worktree() {
+        let path_match = |worktree_id: usize, root_name: &str, path: &str| PathMatch {
+            score: 0.,
+            positions: Vec::new(),
+            worktree_id,
+            path: Path::new(path).into(),
+            path_prefix: root_name.into(),
+            distance_to_relative_ancestor: 0,
+            is_dir: true,
+        };
+        let describe = |entries: &[FileMatchEntry]| {
+            entries
+                .iter()
+                .map(|entry| match entry {
+                    FileMatchEntry::PinnedHeader => "# Pinned".to_string(),
+                    FileMatchEntry::RecentHeader => "# Recent".to_string(),
+                    FileMatchEntry::WorktreeHeader { root_name } => format!("# {root_name}"),
+                    FileMatchEntry::Match(path_match) => path_match.path.display().to_string(),
+                })
+                .collect::<Vec<_>>()
+        };
+        let matches = || {
+            vec![
+                path_match(2, "docs", "src"),
+                path_match(1, "zed", "crates"),
+                path_match(2, "docs", "assets"),
+                path_match(1, "zed", "src"),
+            ]
+        };
+
+        let entries = group_matches_by_worktree(matches(), true);
+        assert_eq!(
+            describe(&entries),
+            vec!["# docs", "src", "assets", "# zed", "crates", "src"]
+        );
+
+        let entries = group_matches_by_worktree(matches(), false);
+        assert_eq!(describe(&entries), vec!["src", "assets", "crates", "src"]);
+
+        assert!(group_matches_by_worktree(Vec::new(), true).is_empty());
+    }
+
+    #[test]
+    fn test_build_match_entries_lists_recent_directories_first() {
+        let path_match = |worktree_id: usize, path: &str| PathMatch {
+            score: 0.,
+            positions: Vec::new(),
+            worktree_id,
+            path: Path::new(path).into(),
+            path_prefix: "zed".into(),
+            distance_to_relative_ancestor: 0,
+            is_dir: true,
+        };
+
+        let (entries, section_entry_count) = build_match_entries(
+            Vec::new(),
+            vec![path_match(1, "crates/ui")],
+            vec![
+                path_match(1, "crates"),
+                path_match(1, "crates/ui"),
+                path_match(2, "crates/ui"),
+            ],
+            false,
+        );
+        let described = entries
+            .iter()
+            .map(|entry| match entry {
+                FileMatchEntry::PinnedHeader => "# Pinned".to_string(),
+                FileMatchEntry::RecentHeader => "# Recent".to_string(),
+                FileMatchEntry::WorktreeHeader { root_name } => format!("# {root_name}"),
+                FileMatchEntry::Match(path_match) => {
+                    format!("{}:{}", path_match.worktree_id, path_match.path.display())
+                }
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            described,
+            vec!["# Recent", "1:crates/ui", "1:crates", "2:crates/ui"]
+        );
+        assert_eq!(section_entry_count, 2);
+
+        let (entries, section_entry_count) =
+            build_match_entries(Vec::new(), Vec::new(), vec![path_match(1, "src")], false);
+        assert!(matches!(entries.as_slice(), [FileMatchEntry::Match(_)]));
+        assert_eq!(section_entry_count, 0);
+    }
+
+    #[test]
+    fn test_build_match_entries_lists_pinned_directories_first() {
+        let path_match = |path: &str| PathMatch {
+            score: 0.,
+            positions: Vec::new(),
+            worktree_id: 1,
+            path: Path::new(path).into(),
+            path_prefix: "zed".into(),
+            distance_to_relative_ancestor: 0,
+            is_dir: true,
+        };
+
+        // A pinned directory that was also attached recently is only listed as pinned.
+        let (entries, section_entry_count) = build_match_entries(
+            vec![path_match("crates/ui")],
+            vec![path_match("crates/ui"), path_match("crates/gpui")],
+            vec![
+                path_match("crates"),
+                path_match("crates/gpui"),
+                path_match("crates/ui"),
+            ],
+            false,
+        );
+        let described = entries
+            .iter()
+            .map(|entry| match entry {
+                FileMatchEntry::PinnedHeader => "# Pinned".to_string(),
+                FileMatchEntry::RecentHeader => "# Recent".to_string(),
+                FileMatchEntry::WorktreeHeader { root_name } => format!("# {root_name}"),
+                FileMatchEntry::Match(path_match) => path_match.path.display().to_string(),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            described,
+            vec!["# Pinned", "crates/ui", "# Recent", "crates/gpui", "crates"]
+        );
+        assert_eq!(section_entry_count, 4);
+    }
+
+    #[test]
+    fn test_sort_pinned_first_breaks_ties() {
+        let path_match = |path: &str, score: f64| PathMatch {
+            score,
+            positions: Vec::new(),
+            worktree_id: 1,
+            path: Path::new(path).into(),
+            path_prefix: "zed".into(),
+            distance_to_relative_ancestor: 0,
+            is_dir: true,
+        };
+        let mut matches = vec![
+            path_match("best", 0.9),
+            path_match("a", 0.5),
+            path_match("b", 0.5),
+            path_match("pinned", 0.5),
+            path_match("worst", 0.1),
+        ];
+        let pinned_keys = [
+            (1, Arc::from(Path::new("pinned"))),
+            (1, Path::new("worst").into()),
+        ]
+        .into_iter()
+        .collect::<HashSet<_>>();
+
+        sort_pinned_first(&mut matches, &pinned_keys);
+        let paths = matches
+            .iter()
+            .map(|path_match| path_match.path.display().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(paths, vec!["best", "pinned", "a", "b", "worst"]);
+    }
+
+    #[test]
+    fn test_toggle_pinned_directory() {
+        let directory = |path: &str| PersistedDirectory {
+            worktree_abs_path: PathBuf::from("/zed"),
+            path: PathBuf::from(path),
+        };
+        let mut pinned = vec![directory("a")];
+
+        toggle_pinned_directory(&mut pinned, directory("b"));
+        assert_eq!(pinned, vec![directory("a"), directory("b")]);
+
+        toggle_pinned_directory(&mut pinned, directory("a"));
+        assert_eq!(pinned, vec![directory("b")]);
+    }
+
+    #[test]
+    fn test_is_in_symlinked_directory() {
+        let symlinked_directories = [Path::new("src/shared").into()]
+            .into_iter()
+            .collect::<HashSet<Arc<Path>>>();
+        assert!(is_in_symlinked_directory(
+            &symlinked_directories,
+            Path::new("src/shared")
+        ));
+        assert!(is_in_symlinked_directory(
+            &symlinked_directories,
+            Path::new("src/shared/util/mod.rs")
+        ));
+        assert!(!is_in_symlinked_directory(
+            &symlinked_directories,
+            Path::new("src/shared_util.rs")
+        ));
+        assert!(!is_in_symlinked_directory(
+            &HashSet::default(),
+            Path::new("src/shared")
+        ));
+    }
+
+    #[test]
+    fn test_push_recent_directories() {
+        let directory = |path: &str| PersistedDirectory {
+            worktree_abs_path: PathBuf::from("/zed"),
+            path: PathBuf::from(path),
+        };
+        let mut recent = vec![directory("a"), directory("b")];
+
+        push_recent_directories(&mut recent, vec![directory("b")]);
+        assert_eq!(recent, vec![directory("b"), directory("a")]);
+
+        push_recent_directories(
+            &mut recent,
+            ["c", "d", "e", "f"].into_iter().map(directory).collect(),
+        );
+        assert_eq!(
+            recent,
+            ["f", "e", "d", "c", "b"]
+                .into_iter()
+                .map(directory)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[gpui::test]
+    async fn test_browse_matches_respects_include_ignored(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            let settings_store = SettingsStore::test(cx);
+            cx.set_global(settings_store);
+            language::init(cx);
+            Project::init_settings(cx);
+        });
+
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            path!("/root"),
+            json!({
+                ".gitignore": "target/\n",
+                "src": { "main.rs": "fn main() {}" },
+                "target": { "debug": { "app": "" } },
+            }),
+        )
+        .await;
+        let project = Project::test(fs, [path!("/root").as_ref()], cx).await;
+        cx.run_until_parked();
+
+        let browsed_paths = |include_ignored: bool, cx: &mut TestAppContext| {
+            project.read_with(cx, |project, cx| {
+                let worktree = project.worktrees(cx).next().unwrap();
+                let (matches, truncated) =
+                    browse_matches(worktree.read(cx), include_ignored, MAX_BROWSE_MATCHES);
+                assert!(!truncated);
+                matches
+                    .into_iter()
+                    .map(|path_match| path_match.path.to_string_lossy().into_owned())
+                    .collect::<Vec<_>>()
+            })
+        };
+
+        let paths = browsed_paths(false, cx);
+        assert!(paths.iter().any(|path| path == "src"));
+        assert!(!paths.iter().any(|path| path.starts_with("target")));
+
+        let paths = browsed_paths(true, cx);
+        assert!(paths.iter().any(|path| path == "src"));
+        assert!(paths.iter().any(|path| path == "target"));
+    }
+
+    #[gpui::test]
+    async fn test_browse_matches_in_ignored_worktree(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            let settings_store = SettingsStore::test(cx);
+            cx.set_global(settings_store);
+            language::init(cx);
+            Project::init_settings(cx);
+        });
+
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            path!("/root"),
+            json!({
+                ".git": {},
+                ".gitignore": "target/\n",
+                "target": { "debug": { "app": "" } },
+            }),
+        )
+        .await;
+        // The worktree's root is itself ignored by the repository containing it.
+        let project = Project::test(fs, [path!("/root/target").as_ref()], cx).await;
+        cx.run_until_parked();
+
+        project.read_with(cx, |project, cx| {
+            let worktree = project.worktrees(cx).next().unwrap();
+            let worktree = worktree.read(cx);
+            assert!(worktree.root_entry().unwrap().is_ignored);
+
+            let (matches, _) = browse_matches(worktree, false, MAX_BROWSE_MATCHES);
+            let paths = matches
+                .into_iter()
+                .map(|path_match| path_match.path.to_string_lossy().into_owned())
+                .collect::<Vec<_>>();
+            assert!(paths.iter().any(|path| path == "debug"));
+            assert!(paths
+                .iter()
+                .any(|path| Path::new(path) == Path::new("debug/app")));
+        });
+    }
+
+    #[gpui::test]
+    async fn test_browse_matches_stops_at_limit(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            let settings_store = SettingsStore::test(cx);
+            cx.set_global(settings_store);
+            language::init(cx);
+            Project::init_settings(cx);
+        });
+
+        // A synthetic worktree with more directories than can be browsed.
+        let directories = (0..MAX_BROWSE_MATCHES)
+            .map(|ix| {
+                (
+                    format!("dir-{ix:04}"),
+                    json!({ "nested": { "file.rs": "" } }),
+                )
+            })
+            .collect::<serde_json::Map<_, _>>();
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(path!("/root"), serde_json::Value::Object(directories))
+            .await;
+        let project = Project::test(fs, [path!("/root").as_ref()], cx).await;
+        cx.run_until_parked();
+
+        project.read_with(cx, |project, cx| {
+            let worktree = project.worktrees(cx).next().unwrap();
+            let worktree = worktree.read(cx);
+
+            let (matches, truncated) = browse_matches(worktree, false, MAX_BROWSE_MATCHES);
+            assert!(truncated);
+            assert_eq!(matches.len(), MAX_BROWSE_MATCHES);
+            // Shallower entries are listed first, so only top-level directories made it.
+            assert_eq!(matches[0].path.as_ref(), Path::new(""));
+            assert!(matches[1..]
+                .iter()
+                .all(|path_match| path_match.path.components().count() == 1));
+
+            let (matches, truncated) = browse_matches(worktree, false, usize::MAX);
+            assert!(!truncated);
+            assert_eq!(matches.len(), 1 + 3 * MAX_BROWSE_MATCHES);
+        });
+    }
+
+    /// Counts the bytes allocated on a thread while it measures them, for checking what browsing
+    /// allocates.
+    struct CountingAllocator;
+
+    thread_local! {
+        static ALLOCATED_BYTES: Cell<Option<usize>> = const { Cell::new(None) };
+    }
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            ALLOCATED_BYTES
+                .try_with(|allocated| {
+                    if let Some(bytes) = allocated.get() {
+                        allocated.set(Some(bytes + layout.size()));
+                    }
+                })
+                .ok();
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    /// Returns the result of `f` along with the bytes it allocated, including ones since freed.
+    fn measure_allocated_bytes<R>(f: impl FnOnce() -> R) -> (R, usize) {
+        ALLOCATED_BYTES.with(|allocated| allocated.set(Some(0)));
+        let result = f();
+        let bytes = ALLOCATED_BYTES.with(|allocated| allocated.take().unwrap_or_default());
+        (result, bytes)
+    }
+
+    #[gpui::test]
+    async fn test_browse_matches_allocations_are_capped(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            let settings_store = SettingsStore::test(cx);
+            cx.set_global(settings_store);
+            language::init(cx);
+            Project::init_settings(cx);
+        });
+
+        let directories = (0..50_000)
+            .map(|ix| (format!("dir-{ix:05}"), json!({})))
+            .collect::<serde_json::Map<_, _>>();
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(path!("/root"), serde_json::Value::Object(directories))
+            .await;
+        let project = Project::test(fs, [path!("/root").as_ref()], cx).await;
+        cx.run_until_parked();
+
+        project.read_with(cx, |project, cx| {
+            let worktree = project.worktrees(cx).next().unwrap();
+            let worktree = worktree.read(cx);
+
+            let ((matches, truncated), capped_bytes) =
+                measure_allocated_bytes(|| browse_matches(worktree, false, MAX_BROWSE_MATCHES));
+            assert!(truncated);
+            assert_eq!(matches.len(), MAX_BROWSE_MATCHES);
+            assert!(
+                capped_bytes < 256 * 1024,
+                "capped browsing allocated {capped_bytes} bytes"
+            );
+
+            let ((matches, truncated), uncapped_bytes) =
+                measure_allocated_bytes(|| browse_matches(worktree, false, usize::MAX));
+            assert!(!truncated);
+            assert_eq!(matches.len(), 50_001);
+            assert!(
+                uncapped_bytes > 10 * 1024 * 1024,
+                "uncapped browsing allocated {uncapped_bytes} bytes"
+            );
+        });
+    }
+
+    #[gpui::test]
+    async fn test_confirm_attaches_selected_directories(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            AppState::test(cx);
+            language::init(cx);
+            editor::init(cx);
+            workspace::init_settings(cx);
+            Project::init_settings(cx);
+        });
+
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            path!("/root"),
+            json!({
+                "src": { "main.rs": "fn main() {}" },
+                "docs": { "guide.md": "# Guide" },
+                "README.md": "# Readme",
+            }),
+        )
+        .await;
+        let project = Project::test(fs, [path!("/root").as_ref()], cx).await;
+        let (workspace, cx) =
+            cx.add_window_view(|window, cx| Workspace::test_new(project.clone(), window, cx));
+        let worktree_id = project.read_with(cx, |project, cx| {
+            project.worktrees(cx).next().unwrap().read(cx).id()
+        });
+        let context_store = cx.new(|_| ContextStore::new(workspace.downgrade()));
+        let (editor, picker) = cx.update(|window, cx| {
+            let editor = cx.new(|cx| Editor::multi_line(window, cx));
+            let delegate = FileContextPickerDelegate::new(
+                WeakEntity::new_invalid(),
+                workspace.downgrade(),
+                editor.downgrade(),
+                context_store.downgrade(),
+                ConfirmBehavior::KeepOpen,
+                cx.new(|cx| Editor::single_line(window, cx)),
+            );
+            let picker = cx.new(|cx| Picker::uniform_list(delegate, window, cx));
+            (editor, picker)
+        });
+        // Let the picker browse the worktree.
+        cx.run_until_parked();
+
+        picker.update_in(cx, |picker, window, cx| {
+            // Select `src` before `docs`, which are attached in sorted order regardless.
+            for path in ["src", "docs"] {
+                picker.delegate.selected_index = picker
+                    .delegate
+                    .matches
+                    .iter()
+                    .position(|entry| {
+                        matches!(entry, FileMatchEntry::Match(mat) if mat.path.as_ref() == Path::new(path))
+                    })
+                    .unwrap();
+                picker.delegate.toggle_selected_directory();
+            }
+            picker.delegate.confirm(false, window, cx);
+            assert!(picker.delegate.selected_directories.is_empty());
+        });
+        cx.run_until_parked();
+
+        context_store.read_with(cx, |context_store, _cx| {
+            for path in ["src", "docs"] {
+                let project_path = ProjectPath {
+                    worktree_id,
+                    path: Path::new(path).into(),
+                };
+                assert!(
+                    context_store.includes_directory(&project_path).is_some(),
+                    "{path} should be attached"
+                );
+            }
+            assert_eq!(context_store.context().len(), 2);
+        });
+        picker.read_with(cx, |picker, _cx| {
+            let recent = picker
+                .delegate
+                .recent_directories
+                .iter()
+                .take(2)
+                .map(|directory| directory.path.as_path())
+                .collect::<Vec<_>>();
+            assert_eq!(
+                recent,
+                [Path::new("src"), Path::new("docs")],
+                "attached directories should be remembered once attached"
+            );
+        });
+        let text = editor.read_with(cx, |editor, cx| editor.text(cx));
+        let docs_ix = text.find("docs").expect("docs should have a crease");
+        let src_ix = text.find("src").expect("src should have a crease");
+        assert!(
+            docs_ix < src_ix,
+            "directories should be inserted in sorted order: {text:?}"
+        );
+    }
+
+    #[gpui::test]
+    async fn test_preview_keystroke_opens_directory_preview(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            AppState::test(cx);
+            language::init(cx);
+            editor::init(cx);
+            workspace::init_settings(cx);
+            Project::init_settings(cx);
+            let default_key_bindings = settings::KeymapFile::load_asset_allow_partial_failure(
+                "keymaps/default-linux.json",
+                cx,
+            )
+            .unwrap();
+            cx.bind_keys(default_key_bindings);
+        });
+
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            path!("/root"),
+            json!({
+                "src": { "main.rs": "fn main() {}" },
+                "README.md": "# Readme",
+            }),
+        )
+        .await;
+        let project = Project::test(fs, [path!("/root").as_ref()], cx).await;
+        let window = cx.add_window(|window, cx| Workspace::test_new(project.clone(), window, cx));
+        let workspace = window.root(cx).unwrap();
+        let context_store = cx.new(|_| ContextStore::new(workspace.downgrade()));
+        let (file_picker, cx) = cx.add_window_view(|window, cx| {
+            FileContextPicker::new(
+                WeakEntity::new_invalid(),
+                workspace.downgrade(),
+                WeakEntity::new_invalid(),
+                context_store.downgrade(),
+                ConfirmBehavior::KeepOpen,
+                window,
+                cx,
+            )
+        });
+        // Let the picker browse the worktree.
+        cx.run_until_parked();
+
+        file_picker.update_in(cx, |file_picker, window, cx| {
+            file_picker.focus_handle(cx).focus(window);
+            file_picker.picker.update(cx, |picker, _cx| {
+                picker.delegate.selected_index = picker
+                    .delegate
+                    .matches
+                    .iter()
+                    .position(|entry| {
+                        matches!(entry, FileMatchEntry::Match(mat) if mat.path.as_ref() == Path::new("src"))
+                    })
+                    .unwrap();
+            });
+        });
+        cx.run_until_parked();
+
+        // The query editor is focused, so this must win over the editor's own binding.
+        cx.simulate_keystrokes("ctrl-i");
+        file_picker.read_with(cx, |file_picker, _cx| {
+            let preview = file_picker
+                .preview
+                .as_ref()
+                .expect("the preview should be open");
+            assert_eq!(preview.path.as_ref(), Path::new("src"));
+            assert_eq!(
+                preview
+                    .entries
+                    .iter()
+                    .map(|entry| entry.name.as_ref())
+                    .collect::<Vec<_>>(),
+                ["main.rs"]
+            );
+        });
+    }
+}
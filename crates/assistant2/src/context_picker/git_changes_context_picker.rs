@@ -0,0 +1,682 @@
+use std::cmp::Ordering;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use collections::HashMap;
+use editor::Editor;
+use futures::future;
+use fuzzy::StringMatchCandidate;
+use git::repository::{DiffType, RepoPath};
+use git::status::FileStatus;
+use gpui::{
+    Action, AnyElement, App, DismissEvent, Entity, EntityId, FocusHandle, Focusable, Task,
+    WeakEntity,
+};
+use picker::{Picker, PickerDelegate};
+use project::git::Repository;
+use project::{Project, ProjectPath};
+use ui::{prelude::*, KeyBinding, ListItem, Tooltip};
+use workspace::notifications::NotifyResultExt;
+use workspace::Workspace;
+
+use crate::context_picker::file_context_picker::{
+    insert_crease_for_path, render_file_context_entry,
+};
+use crate::context_picker::{ConfirmBehavior, ContextPicker};
+use crate::context_store::ContextStore;
+
+pub struct GitChangesContextPicker {
+    picker: Entity<Picker<GitChangesContextPickerDelegate>>,
+}
+
+impl GitChangesContextPicker {
+    pub fn new(
+        context_picker: WeakEntity<ContextPicker>,
+        workspace: WeakEntity<Workspace>,
+        editor: WeakEntity<Editor>,
+        context_store: WeakEntity<ContextStore>,
+        confirm_behavior: ConfirmBehavior,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let delegate = GitChangesContextPickerDelegate::new(
+            context_picker,
+            workspace,
+            editor,
+            context_store,
+            confirm_behavior,
+        );
+        let picker = cx.new(|cx| Picker::uniform_list(delegate, window, cx));
+
+        Self { picker }
+    }
+}
+
+impl Focusable for GitChangesContextPicker {
+    fn focus_handle(&self, cx: &App) -> FocusHandle {
+        self.picker.focus_handle(cx)
+    }
+}
+
+impl Render for GitChangesContextPicker {
+    fn render(&mut self, _window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
+        self.picker.clone()
+    }
+}
+
+/// A file with uncommitted changes in one of the project's repositories.
+#[derive(Clone)]
+struct GitChangeEntry {
+    repository: Entity<Repository>,
+    repo_path: RepoPath,
+    project_path: ProjectPath,
+    /// The name of the file's worktree, followed by a separator.
+    path_prefix: Arc<str>,
+    status: FileStatus,
+}
+
+impl GitChangeEntry {
+    fn full_path(&self) -> Arc<Path> {
+        Path::new(self.path_prefix.as_ref())
+            .join(&self.project_path.path)
+            .into()
+    }
+}
+
+struct GitChangeMatch {
+    entry: GitChangeEntry,
+    /// Byte offsets of the matched characters in `path_prefix` followed by the path.
+    positions: Vec<usize>,
+}
+
+pub struct GitChangesContextPickerDelegate {
+    context_picker: WeakEntity<ContextPicker>,
+    workspace: WeakEntity<Workspace>,
+    editor: WeakEntity<Editor>,
+    context_store: WeakEntity<ContextStore>,
+    confirm_behavior: ConfirmBehavior,
+    /// Whether any file has uncommitted changes, regardless of the query.
+    has_changes: bool,
+    matches: Vec<GitChangeMatch>,
+    selected_index: usize,
+}
+
+impl GitChangesContextPickerDelegate {
+    pub fn new(
+        context_picker: WeakEntity<ContextPicker>,
+        workspace: WeakEntity<Workspace>,
+        editor: WeakEntity<Editor>,
+        context_store: WeakEntity<ContextStore>,
+        confirm_behavior: ConfirmBehavior,
+    ) -> Self {
+        Self {
+            context_picker,
+            workspace,
+            editor,
+            context_store,
+            confirm_behavior,
+            has_changes: false,
+            matches: Vec::new(),
+            selected_index: 0,
+        }
+    }
+
+    /// Attaches the changes to `entries`, as diffs against `HEAD` or, when `full_files` is set,
+    /// as the files' current contents. Deleted files are always attached as diffs, and new files
+    /// that have no diff yet are attached in full.
+    fn attach(
+        &mut self,
+        entries: Vec<GitChangeEntry>,
+        full_files: bool,
+        window: &mut Window,
+        cx: &mut Context<Picker<Self>>,
+    ) {
+        let Some(context_store) = self.context_store.upgrade() else {
+            return;
+        };
+
+        let (file_entries, diff_entries): (Vec<_>, Vec<_>) = entries
+            .into_iter()
+            .partition(|entry| full_files && !entry.status.is_deleted());
+
+        let mut file_tasks = Vec::new();
+        for entry in file_entries {
+            file_tasks.extend(self.attach_file(&context_store, &entry, window, cx));
+        }
+
+        let mut diffs_by_repository = HashMap::<EntityId, (Entity<Repository>, Vec<_>)>::default();
+        for entry in diff_entries {
+            if context_store
+                .read(cx)
+                .includes_git_diff(&entry.project_path)
+                .is_some()
+            {
+                continue;
+            }
+            diffs_by_repository
+                .entry(entry.repository.entity_id())
+                .or_insert_with(|| (entry.repository.clone(), Vec::new()))
+                .1
+                .push(entry);
+        }
+        let diff_tasks = diffs_by_repository
+            .into_values()
+            .map(|(repository, entries)| {
+                let diff = repository.read(cx).diff(DiffType::HeadToWorktree, cx);
+                async move { (diff.await, entries) }
+            })
+            .collect::<Vec<_>>();
+        cx.notify();
+
+        let confirm_behavior = self.confirm_behavior;
+        cx.spawn_in(window, |this, mut cx| async move {
+            let mut all_succeeded = true;
+            for (diff, entries) in future::join_all(diff_tasks).await {
+                let diff = diff.map_err(anyhow::Error::from).and_then(|diff| diff);
+                let Some(diff) = diff.notify_async_err(&mut cx) else {
+                    all_succeeded = false;
+                    continue;
+                };
+                let file_diffs = split_diff_by_file(&diff);
+
+                this.update_in(&mut cx, |this, window, cx| {
+                    for entry in entries {
+                        let file_diff = file_diffs
+                            .iter()
+                            .find(|(path, _)| path.as_path() == entry.repo_path.as_ref())
+                            .map(|(_, file_diff)| *file_diff);
+                        match file_diff {
+                            Some(file_diff) => context_store.update(cx, |context_store, cx| {
+                                context_store.add_git_diff(
+                                    entry.project_path.clone(),
+                                    entry.full_path(),
+                                    file_diff,
+                                    cx,
+                                )
+                            }),
+                            // Untracked files aren't part of the diff against `HEAD`.
+                            None => file_tasks.extend(this.delegate.attach_file(
+                                &context_store,
+                                &entry,
+                                window,
+                                cx,
+                            )),
+                        }
+                    }
+                })?;
+            }
+
+            for result in future::join_all(file_tasks).await {
+                all_succeeded &= result.notify_async_err(&mut cx).is_some();
+            }
+            if !all_succeeded {
+                return anyhow::Ok(());
+            }
+            this.update_in(&mut cx, |this, window, cx| match confirm_behavior {
+                ConfirmBehavior::KeepOpen => cx.notify(),
+                ConfirmBehavior::Close => this.delegate.dismissed(window, cx),
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
+    /// Attaches the current contents of the entry's file, unless they're already attached.
+    fn attach_file(
+        &self,
+        context_store: &Entity<ContextStore>,
+        entry: &GitChangeEntry,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Option<Task<anyhow::Result<()>>> {
+        if context_store
+            .read(cx)
+            .will_include_file_path(&entry.project_path, cx)
+            .is_some()
+        {
+            return None;
+        }
+
+        if let Some(editor_entity) = self.editor.upgrade() {
+            let full_path = entry.project_path.path.display().to_string();
+            let file_name = entry
+                .project_path
+                .path
+                .file_name()
+                .map(|file_name| file_name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| full_path.clone());
+            insert_crease_for_path(
+                &editor_entity,
+                &full_path,
+                file_name,
+                false,
+                false,
+                window,
+                cx,
+            );
+        }
+
+        Some(context_store.update(cx, |context_store, cx| {
+            context_store.add_file_from_path(entry.project_path.clone(), None, cx)
+        }))
+    }
+
+    fn attach_all(
+        &mut self,
+        full_files: bool,
+        window: &mut Window,
+        cx: &mut Context<Picker<Self>>,
+    ) {
+        let entries = self.matches.iter().map(|mat| mat.entry.clone()).collect();
+        self.attach(entries, full_files, window, cx);
+    }
+
+    fn show_root_name(&self, cx: &App) -> bool {
+        self.workspace.upgrade().map_or(false, |workspace| {
+            workspace.read(cx).visible_worktrees(cx).count() > 1
+        })
+    }
+}
+
+impl PickerDelegate for GitChangesContextPickerDelegate {
+    type ListItem = ListItem;
+
+    fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected_index(
+        &mut self,
+        ix: usize,
+        _window: &mut Window,
+        _cx: &mut Context<Picker<Self>>,
+    ) {
+        self.selected_index = ix;
+    }
+
+    fn placeholder_text(&self, _window: &mut Window, _cx: &mut App) -> Arc<str> {
+        "Search changed files…".into()
+    }
+
+    fn no_matches_text(&self, _window: &mut Window, _cx: &mut App) -> Option<SharedString> {
+        if self.has_changes {
+            Some("No matching changed files".into())
+        } else {
+            Some("No uncommitted changes".into())
+        }
+    }
+
+    fn update_matches(
+        &mut self,
+        query: String,
+        window: &mut Window,
+        cx: &mut Context<Picker<Self>>,
+    ) -> Task<()> {
+        let Some(workspace) = self.workspace.upgrade() else {
+            return Task::ready(());
+        };
+
+        let entries = git_change_entries(workspace.read(cx).project().read(cx), cx);
+        let executor = cx.background_executor().clone();
+        let candidates = entries
+            .iter()
+            .enumerate()
+            .map(|(id, entry)| {
+                StringMatchCandidate::new(id, &entry.project_path.path.to_string_lossy())
+            })
+            .collect::<Vec<_>>();
+        let search_task = cx.background_spawn(async move {
+            if query.is_empty() {
+                return None;
+            }
+
+            let mut matches = fuzzy::match_strings(
+                &candidates,
+                &query,
+                false,
+                100,
+                &Default::default(),
+                executor,
+            )
+            .await;
+            matches.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+            Some(matches)
+        });
+
+        let has_changes = !entries.is_empty();
+        cx.spawn_in(window, |this, mut cx| async move {
+            let matches = match search_task.await {
+                Some(matches) => matches
+                    .into_iter()
+                    .map(|mat| {
+                        let entry = entries[mat.candidate_id].clone();
+                        // Positions are relative to the path, but rendering expects them to
+                        // follow the worktree's name.
+                        let positions = mat
+                            .positions
+                            .into_iter()
+                            .map(|position| position + entry.path_prefix.len())
+                            .collect();
+                        GitChangeMatch { entry, positions }
+                    })
+                    .collect(),
+                None => entries
+                    .into_iter()
+                    .map(|entry| GitChangeMatch {
+                        entry,
+                        positions: Vec::new(),
+                    })
+                    .collect(),
+            };
+
+            this.update(&mut cx, |this, cx| {
+                this.delegate.has_changes = has_changes;
+                this.delegate.matches = matches;
+                this.delegate.selected_index = 0;
+                cx.notify();
+            })
+            .ok();
+        })
+    }
+
+    /// Attaches the highlighted file's diff, or its full contents on a secondary confirm.
+    fn confirm(&mut self, secondary: bool, window: &mut Window, cx: &mut Context<Picker<Self>>) {
+        let Some(mat) = self.matches.get(self.selected_index) else {
+            return;
+        };
+
+        let entry = mat.entry.clone();
+        self.attach(vec![entry], secondary, window, cx);
+    }
+
+    fn dismissed(&mut self, _window: &mut Window, cx: &mut Context<Picker<Self>>) {
+        self.context_picker
+            .update(cx, |_, cx| {
+                cx.emit(DismissEvent);
+            })
+            .ok();
+    }
+
+    fn render_footer(
+        &self,
+        window: &mut Window,
+        cx: &mut Context<Picker<Self>>,
+    ) -> Option<AnyElement> {
+        Some(
+            h_flex()
+                .w_full()
+                .p_2()
+                .gap_1()
+                .justify_end()
+                .border_t_1()
+                .border_color(cx.theme().colors().border_variant)
+                .child(
+                    Button::new("attach-all-diffs", "Attach All")
+                        .label_size(LabelSize::Small)
+                        .disabled(self.matches.is_empty())
+                        .tooltip(Tooltip::text("Attach the diffs of every listed file"))
+                        .on_click(cx.listener(|picker, _, window, cx| {
+                            picker.delegate.attach_all(false, window, cx);
+                        })),
+                )
+                .children(
+                    KeyBinding::for_action(&menu::SecondaryConfirm, window, cx).map(|keybind| {
+                        Button::new("attach-full-file", "Attach Full File")
+                            .label_size(LabelSize::Small)
+                            .key_binding(keybind)
+                            .on_click(|_, window, cx| {
+                                window.dispatch_action(menu::SecondaryConfirm.boxed_clone(), cx)
+                            })
+                    }),
+                )
+                .children(
+                    KeyBinding::for_action(&menu::Confirm, window, cx).map(|keybind| {
+                        Button::new("attach-diff", "Attach Diff")
+                            .label_size(LabelSize::Small)
+                            .key_binding(keybind)
+                            .on_click(|_, window, cx| {
+                                window.dispatch_action(menu::Confirm.boxed_clone(), cx)
+                            })
+                    }),
+                )
+                .into_any(),
+        )
+    }
+
+    fn render_match(
+        &self,
+        ix: usize,
+        selected: bool,
+        _window: &mut Window,
+        cx: &mut Context<Picker<Self>>,
+    ) -> Option<Self::ListItem> {
+        let mat = &self.matches[ix];
+        let diff_added = self.context_store.upgrade().map_or(false, |context_store| {
+            context_store
+                .read(cx)
+                .includes_git_diff(&mat.entry.project_path)
+                .is_some()
+        });
+
+        Some(
+            ListItem::new(ix)
+                .inset(true)
+                .toggle_state(selected)
+                .child(render_file_context_entry(
+                    ElementId::NamedInteger("git-changes-ctx-picker".into(), ix),
+                    mat.entry.project_path.worktree_id,
+                    &mat.entry.project_path.path,
+                    &mat.entry.path_prefix,
+                    self.show_root_name(cx),
+                    &mat.positions,
+                    false,
+                    self.context_store.clone(),
+                    cx,
+                ))
+                .end_slot(
+                    h_flex()
+                        .gap_1()
+                        .when(diff_added, |el| {
+                            el.child(
+                                Icon::new(IconName::Check)
+                                    .size(IconSize::Small)
+                                    .color(Color::Success),
+                            )
+                            .child(Label::new("Diff added").size(LabelSize::Small))
+                        })
+                        .child(render_status_label(mat.entry.status)),
+                ),
+        )
+    }
+}
+
+fn render_status_label(status: FileStatus) -> Label {
+    let (label, color) = if status.is_conflicted() {
+        ("Conflict", Color::Conflict)
+    } else if status.is_untracked() {
+        ("Untracked", Color::Created)
+    } else if status.is_created() {
+        ("Added", Color::Created)
+    } else if status.is_deleted() {
+        ("Deleted", Color::Deleted)
+    } else {
+        ("Modified", Color::Modified)
+    };
+
+    Label::new(label).size(LabelSize::Small).color(color)
+}
+
+/// Returns the files with uncommitted changes in each of the project's repositories, ordered by
+/// repository and then by path.
+fn git_change_entries(project: &Project, cx: &App) -> Vec<GitChangeEntry> {
+    project
+        .git_store()
+        .read(cx)
+        .all_repositories()
+        .into_iter()
+        .flat_map(|repository| {
+            let repo = repository.read(cx);
+            repo.status()
+                .filter(|status_entry| status_entry.status.has_changes())
+                .filter_map(|status_entry| {
+                    let project_path = repo.repo_path_to_project_path(&status_entry.repo_path)?;
+                    let worktree = project.worktree_for_id(project_path.worktree_id, cx)?;
+                    let path_prefix = format!(
+                        "{}{}",
+                        worktree.read(cx).root_name(),
+                        std::path::MAIN_SEPARATOR
+                    )
+                    .into();
+
+                    Some(GitChangeEntry {
+                        repository: repository.clone(),
+                        repo_path: status_entry.repo_path,
+                        project_path,
+                        path_prefix,
+                        status: status_entry.status,
+                    })
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Splits the output of `git diff` into the diff of each file, paired with the file's path
+/// relative to the repository.
+fn split_diff_by_file(diff: &str) -> Vec<(PathBuf, &str)> {
+    let mut starts = diff
+        .match_indices("diff --git ")
+        .map(|(ix, _)| ix)
+        .filter(|ix| *ix == 0 || diff.as_bytes()[ix - 1] == b'\n')
+        .collect::<Vec<_>>();
+    starts.push(diff.len());
+
+    starts
+        .windows(2)
+        .filter_map(|range| {
+            let file_diff = &diff[range[0]..range[1]];
+            // Only the header can name the file; the hunks could contain anything.
+            let header = file_diff
+                .lines()
+                .take_while(|line| !line.starts_with("@@"))
+                .collect::<Vec<_>>();
+            let path = header
+                .iter()
+                .find_map(|line| line.strip_prefix("+++ b/"))
+                .or_else(|| header.iter().find_map(|line| line.strip_prefix("--- a/")))
+                .or_else(|| {
+                    header
+                        .first()
+                        .and_then(|line| line.rsplit_once(" b/"))
+                        .map(|(_, path)| path)
+                })?;
+            Some((PathBuf::from(path), file_diff))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git::status::{StatusCode, TrackedStatus};
+    use gpui::TestAppContext;
+    use indoc::indoc;
+    use project::FakeFs;
+    use serde_json::json;
+    use util::path;
+    use workspace::AppState;
+
+    #[test]
+    fn test_split_diff_by_file() {
+        let diff = indoc! {"
+        diff --git a/src/main.rs b/src/main.rs
+        index 1111111..2222222 100644
+        --- a/src/main.rs
+        +++ b/src/main.rs
+        @@ -1 +1 @@
+        -fn main() {}
+        +++ b/not-a-header
+        diff --git a/old.txt b/old.txt
+        deleted file mode 100644
+        index 3333333..0000000
+        --- a/old.txt
+        +++ /dev/null
+        @@ -1 +0,0 @@
+        -old
+        diff --git a/image.png b/image.png
+        Binary files differ
+        "};
+
+        let files = split_diff_by_file(diff);
+        let paths = files
+            .iter()
+            .map(|(path, _)| path.to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+        assert_eq!(paths, ["src/main.rs", "old.txt", "image.png"]);
+        assert!(files[0].1.ends_with("+++ b/not-a-header\n"));
+        assert!(files[1].1.starts_with("diff --git a/old.txt b/old.txt\n"));
+        assert!(files[1].1.ends_with("-old\n"));
+
+        assert!(split_diff_by_file("").is_empty());
+    }
+
+    #[gpui::test]
+    async fn test_git_change_entries(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            AppState::test(cx);
+            language::init(cx);
+            Project::init_settings(cx);
+        });
+
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            path!("/root"),
+            json!({
+                ".git": {},
+                "a.rs": "fn a() {}",
+                "b.rs": "fn b() {}",
+                "new.rs": "fn new() {}",
+            }),
+        )
+        .await;
+        fs.set_status_for_repo_via_git_operation(
+            Path::new(path!("/root/.git")),
+            &[
+                (
+                    Path::new("a.rs"),
+                    FileStatus::Tracked(TrackedStatus {
+                        index_status: StatusCode::Unmodified,
+                        worktree_status: StatusCode::Modified,
+                    }),
+                ),
+                (Path::new("new.rs"), FileStatus::Untracked),
+            ],
+        );
+        let project = Project::test(fs, [path!("/root").as_ref()], cx).await;
+        cx.run_until_parked();
+
+        project.read_with(cx, |project, cx| {
+            let entries = git_change_entries(project, cx)
+                .into_iter()
+                .map(|entry| {
+                    (
+                        entry.project_path.path.to_string_lossy().into_owned(),
+                        entry.full_path().to_string_lossy().into_owned(),
+                        entry.status.is_untracked(),
+                    )
+                })
+                .collect::<Vec<_>>();
+            let prefix = format!("root{}", std::path::MAIN_SEPARATOR);
+            assert_eq!(
+                entries,
+                vec![
+                    ("a.rs".to_string(), format!("{prefix}a.rs"), false),
+                    ("new.rs".to_string(), format!("{prefix}new.rs"), true),
+                ]
+            );
+        });
+    }
+}
@@ -0,0 +1,548 @@
+use std::cmp::Ordering;
+use std::sync::Arc;
+
+use collections::HashSet;
+use editor::Editor;
+use futures::future;
+use fuzzy::StringMatchCandidate;
+use gpui::{
+    Action, AnyElement, App, DismissEvent, Entity, EntityId, FocusHandle, Focusable, Task,
+    WeakEntity,
+};
+use language::Buffer;
+use picker::{Picker, PickerDelegate};
+use project::ProjectPath;
+use ui::{prelude::*, KeyBinding, ListItem, Tooltip};
+use workspace::notifications::NotifyResultExt;
+use workspace::Workspace;
+
+use crate::context_picker::file_context_picker::{
+    insert_crease_for_path, render_file_context_entry,
+};
+use crate::context_picker::{ConfirmBehavior, ContextPicker};
+use crate::context_store::ContextStore;
+use crate::ToggleContextSelection;
+
+pub struct OpenBufferContextPicker {
+    picker: Entity<Picker<OpenBufferContextPickerDelegate>>,
+}
+
+impl OpenBufferContextPicker {
+    pub fn new(
+        context_picker: WeakEntity<ContextPicker>,
+        workspace: WeakEntity<Workspace>,
+        editor: WeakEntity<Editor>,
+        context_store: WeakEntity<ContextStore>,
+        confirm_behavior: ConfirmBehavior,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let delegate = OpenBufferContextPickerDelegate::new(
+            context_picker,
+            workspace,
+            editor,
+            context_store,
+            confirm_behavior,
+        );
+        let picker = cx.new(|cx| Picker::uniform_list(delegate, window, cx));
+
+        Self { picker }
+    }
+
+    fn toggle_context_selection(
+        &mut self,
+        _: &ToggleContextSelection,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.picker.update(cx, |picker, cx| {
+            picker.delegate.toggle_selected_buffer();
+            cx.notify();
+        });
+    }
+}
+
+impl Focusable for OpenBufferContextPicker {
+    fn focus_handle(&self, cx: &App) -> FocusHandle {
+        self.picker.focus_handle(cx)
+    }
+}
+
+impl Render for OpenBufferContextPicker {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        v_flex()
+            .key_context("OpenBufferContextPicker")
+            .on_action(cx.listener(Self::toggle_context_selection))
+            .child(self.picker.clone())
+    }
+}
+
+/// A buffer open in one of the workspace's editors.
+#[derive(Clone)]
+struct OpenBufferEntry {
+    buffer: Entity<Buffer>,
+    project_path: ProjectPath,
+    /// The name of the buffer's worktree, followed by a separator.
+    path_prefix: Arc<str>,
+    /// Whether the buffer has changes that aren't saved to disk yet.
+    is_dirty: bool,
+}
+
+struct OpenBufferMatch {
+    entry: OpenBufferEntry,
+    /// Byte offsets of the matched characters in `path_prefix` followed by the path.
+    positions: Vec<usize>,
+}
+
+pub struct OpenBufferContextPickerDelegate {
+    context_picker: WeakEntity<ContextPicker>,
+    workspace: WeakEntity<Workspace>,
+    editor: WeakEntity<Editor>,
+    context_store: WeakEntity<ContextStore>,
+    confirm_behavior: ConfirmBehavior,
+    /// Buffers toggled for attaching together on the next confirm.
+    selected_buffers: HashSet<EntityId>,
+    matches: Vec<OpenBufferMatch>,
+    selected_index: usize,
+}
+
+impl OpenBufferContextPickerDelegate {
+    pub fn new(
+        context_picker: WeakEntity<ContextPicker>,
+        workspace: WeakEntity<Workspace>,
+        editor: WeakEntity<Editor>,
+        context_store: WeakEntity<ContextStore>,
+        confirm_behavior: ConfirmBehavior,
+    ) -> Self {
+        Self {
+            context_picker,
+            workspace,
+            editor,
+            context_store,
+            confirm_behavior,
+            selected_buffers: HashSet::default(),
+            matches: Vec::new(),
+            selected_index: 0,
+        }
+    }
+
+    /// Adds the highlighted buffer to the multi-selection, or removes it if already selected.
+    fn toggle_selected_buffer(&mut self) {
+        let Some(mat) = self.matches.get(self.selected_index) else {
+            return;
+        };
+
+        let buffer_id = mat.entry.buffer.entity_id();
+        if !self.selected_buffers.remove(&buffer_id) {
+            self.selected_buffers.insert(buffer_id);
+        }
+    }
+
+    /// The buffers a confirm attaches: the selected ones, or else the highlighted one.
+    fn buffers_to_attach(&mut self, cx: &App) -> Vec<OpenBufferEntry> {
+        if self.selected_buffers.is_empty() {
+            return self
+                .matches
+                .get(self.selected_index)
+                .map(|mat| mat.entry.clone())
+                .into_iter()
+                .collect();
+        }
+
+        let selected_buffers = std::mem::take(&mut self.selected_buffers);
+        // Selected buffers may have been filtered out by the query since they were selected.
+        self.workspace
+            .upgrade()
+            .map(|workspace| open_buffer_entries(workspace.read(cx), cx))
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|entry| selected_buffers.contains(&entry.buffer.entity_id()))
+            .collect()
+    }
+
+    fn show_root_name(&self, cx: &App) -> bool {
+        self.workspace.upgrade().map_or(false, |workspace| {
+            workspace.read(cx).visible_worktrees(cx).count() > 1
+        })
+    }
+}
+
+impl PickerDelegate for OpenBufferContextPickerDelegate {
+    type ListItem = ListItem;
+
+    fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected_index(
+        &mut self,
+        ix: usize,
+        _window: &mut Window,
+        _cx: &mut Context<Picker<Self>>,
+    ) {
+        self.selected_index = ix;
+    }
+
+    fn placeholder_text(&self, _window: &mut Window, _cx: &mut App) -> Arc<str> {
+        "Search open buffers…".into()
+    }
+
+    fn no_matches_text(&self, _window: &mut Window, _cx: &mut App) -> Option<SharedString> {
+        Some("No open buffers".into())
+    }
+
+    fn update_matches(
+        &mut self,
+        query: String,
+        window: &mut Window,
+        cx: &mut Context<Picker<Self>>,
+    ) -> Task<()> {
+        let Some(workspace) = self.workspace.upgrade() else {
+            return Task::ready(());
+        };
+
+        let entries = open_buffer_entries(workspace.read(cx), cx);
+        let executor = cx.background_executor().clone();
+        let candidates = entries
+            .iter()
+            .enumerate()
+            .map(|(id, entry)| {
+                StringMatchCandidate::new(id, &entry.project_path.path.to_string_lossy())
+            })
+            .collect::<Vec<_>>();
+        let search_task = cx.background_spawn(async move {
+            if query.is_empty() {
+                return None;
+            }
+
+            let mut matches = fuzzy::match_strings(
+                &candidates,
+                &query,
+                false,
+                100,
+                &Default::default(),
+                executor,
+            )
+            .await;
+            matches.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+            Some(matches)
+        });
+
+        cx.spawn_in(window, |this, mut cx| async move {
+            let matches = match search_task.await {
+                Some(matches) => matches
+                    .into_iter()
+                    .map(|mat| {
+                        let entry = entries[mat.candidate_id].clone();
+                        // Positions are relative to the path, but rendering expects them to
+                        // follow the worktree's name.
+                        let positions = mat
+                            .positions
+                            .into_iter()
+                            .map(|position| position + entry.path_prefix.len())
+                            .collect();
+                        OpenBufferMatch { entry, positions }
+                    })
+                    .collect(),
+                None => entries
+                    .into_iter()
+                    .map(|entry| OpenBufferMatch {
+                        entry,
+                        positions: Vec::new(),
+                    })
+                    .collect(),
+            };
+
+            this.update(&mut cx, |this, cx| {
+                this.delegate.matches = matches;
+                this.delegate.selected_index = 0;
+                cx.notify();
+            })
+            .ok();
+        })
+    }
+
+    /// Attaches the selected buffers, or the highlighted one. Buffers with unsaved changes are
+    /// attached as they are in memory rather than as they are on disk.
+    fn confirm(&mut self, _secondary: bool, window: &mut Window, cx: &mut Context<Picker<Self>>) {
+        let Some(editor_entity) = self.editor.upgrade() else {
+            return;
+        };
+        let Some(context_store) = self.context_store.upgrade() else {
+            return;
+        };
+
+        let mut tasks = Vec::new();
+        for entry in self.buffers_to_attach(cx) {
+            let buffer_id = entry.buffer.read(cx).remote_id();
+            if context_store
+                .read(cx)
+                .will_include_buffer(buffer_id, &entry.project_path)
+                .is_some()
+            {
+                continue;
+            }
+
+            let full_path = entry.project_path.path.display().to_string();
+            let file_name = entry
+                .project_path
+                .path
+                .file_name()
+                .map(|file_name| file_name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| full_path.clone());
+            insert_crease_for_path(
+                &editor_entity,
+                &full_path,
+                file_name,
+                false,
+                false,
+                window,
+                cx,
+            );
+
+            tasks.push(context_store.update(cx, |context_store, cx| {
+                context_store.add_file_from_buffer(entry.buffer, cx)
+            }));
+        }
+        cx.notify();
+
+        let confirm_behavior = self.confirm_behavior;
+        cx.spawn_in(window, |this, mut cx| async move {
+            let mut all_succeeded = true;
+            for result in future::join_all(tasks).await {
+                all_succeeded &= result.notify_async_err(&mut cx).is_some();
+            }
+            if !all_succeeded {
+                return anyhow::Ok(());
+            }
+            this.update_in(&mut cx, |this, window, cx| match confirm_behavior {
+                ConfirmBehavior::KeepOpen => {}
+                ConfirmBehavior::Close => this.delegate.dismissed(window, cx),
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
+    fn dismissed(&mut self, _window: &mut Window, cx: &mut Context<Picker<Self>>) {
+        self.context_picker
+            .update(cx, |_, cx| {
+                cx.emit(DismissEvent);
+            })
+            .ok();
+    }
+
+    fn render_footer(
+        &self,
+        window: &mut Window,
+        cx: &mut Context<Picker<Self>>,
+    ) -> Option<AnyElement> {
+        Some(
+            h_flex()
+                .w_full()
+                .p_2()
+                .gap_1()
+                .justify_between()
+                .border_t_1()
+                .border_color(cx.theme().colors().border_variant)
+                .child(
+                    Label::new(match self.selected_buffers.len() {
+                        0 => "None selected".to_string(),
+                        count => format!("{count} selected"),
+                    })
+                    .size(LabelSize::Small)
+                    .color(Color::Muted),
+                )
+                .child(
+                    h_flex()
+                        .gap_1()
+                        .children(
+                            KeyBinding::for_action(&ToggleContextSelection, window, cx).map(
+                                |keybind| {
+                                    Button::new("select-buffer", "Select")
+                                        .label_size(LabelSize::Small)
+                                        .key_binding(keybind)
+                                        .on_click(|_, window, cx| {
+                                            window.dispatch_action(
+                                                ToggleContextSelection.boxed_clone(),
+                                                cx,
+                                            )
+                                        })
+                                },
+                            ),
+                        )
+                        .children(KeyBinding::for_action(&menu::Confirm, window, cx).map(
+                            |keybind| {
+                                Button::new("attach", "Attach")
+                                    .label_size(LabelSize::Small)
+                                    .key_binding(keybind)
+                                    .on_click(|_, window, cx| {
+                                        window.dispatch_action(menu::Confirm.boxed_clone(), cx)
+                                    })
+                            },
+                        )),
+                )
+                .into_any(),
+        )
+    }
+
+    fn render_match(
+        &self,
+        ix: usize,
+        selected: bool,
+        _window: &mut Window,
+        cx: &mut Context<Picker<Self>>,
+    ) -> Option<Self::ListItem> {
+        let mat = &self.matches[ix];
+        let is_checked = self
+            .selected_buffers
+            .contains(&mat.entry.buffer.entity_id());
+
+        Some(
+            ListItem::new(ix)
+                .inset(true)
+                .toggle_state(selected)
+                .start_slot::<Icon>(is_checked.then(|| {
+                    Icon::new(IconName::Check)
+                        .size(IconSize::Small)
+                        .color(Color::Accent)
+                }))
+                .child(render_file_context_entry(
+                    ElementId::NamedInteger("open-buffer-ctx-picker".into(), ix),
+                    mat.entry.project_path.worktree_id,
+                    &mat.entry.project_path.path,
+                    &mat.entry.path_prefix,
+                    self.show_root_name(cx),
+                    &mat.positions,
+                    false,
+                    self.context_store.clone(),
+                    cx,
+                ))
+                .end_slot::<AnyElement>(mat.entry.is_dirty.then(|| {
+                    div()
+                        .id(("open-buffer-dirty", ix))
+                        .child(
+                            Label::new("Unsaved")
+                                .size(LabelSize::Small)
+                                .color(Color::Modified),
+                        )
+                        .tooltip(Tooltip::text("The unsaved changes will be attached"))
+                        .into_any_element()
+                })),
+        )
+    }
+}
+
+/// Returns the file-backed buffers open in the workspace's editors, in pane order, each listed
+/// once even when several editors show it.
+fn open_buffer_entries(workspace: &Workspace, cx: &App) -> Vec<OpenBufferEntry> {
+    let project = workspace.project().read(cx);
+    let mut seen_buffers = HashSet::default();
+    workspace
+        .items_of_type::<Editor>(cx)
+        .filter_map(|editor| {
+            let buffer = editor.read(cx).buffer().read(cx).as_singleton()?;
+            if !seen_buffers.insert(buffer.entity_id()) {
+                return None;
+            }
+
+            let file = buffer.read(cx).file()?;
+            let project_path = ProjectPath {
+                worktree_id: file.worktree_id(cx),
+                path: file.path().clone(),
+            };
+            let worktree = project.worktree_for_id(project_path.worktree_id, cx)?;
+            let path_prefix = format!(
+                "{}{}",
+                worktree.read(cx).root_name(),
+                std::path::MAIN_SEPARATOR
+            )
+            .into();
+            let is_dirty = buffer.read(cx).is_dirty();
+
+            Some(OpenBufferEntry {
+                buffer,
+                project_path,
+                path_prefix,
+                is_dirty,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::TestAppContext;
+    use project::{FakeFs, Project};
+    use serde_json::json;
+    use util::path;
+    use workspace::AppState;
+
+    #[gpui::test]
+    async fn test_open_buffer_entries(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            AppState::test(cx);
+            language::init(cx);
+            editor::init(cx);
+            workspace::init_settings(cx);
+            Project::init_settings(cx);
+        });
+
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            path!("/root"),
+            json!({
+                "a.rs": "fn a() {}",
+                "b.rs": "fn b() {}",
+            }),
+        )
+        .await;
+        let project = Project::test(fs, [path!("/root").as_ref()], cx).await;
+        let (workspace, cx) =
+            cx.add_window_view(|window, cx| Workspace::test_new(project.clone(), window, cx));
+
+        let open_buffer = |path: &'static str, cx: &mut gpui::VisualTestContext| {
+            let buffer = project.update(cx, |project, cx| project.open_local_buffer(path, cx));
+            async move { buffer.await.unwrap() }
+        };
+        let buffer_a = open_buffer(path!("/root/a.rs"), cx).await;
+        let buffer_b = open_buffer(path!("/root/b.rs"), cx).await;
+        workspace.update_in(cx, |workspace, window, cx| {
+            // Both editors for `a.rs` share its buffer, which is listed once.
+            for buffer in [&buffer_a, &buffer_a, &buffer_b] {
+                let editor = cx.new(|cx| {
+                    Editor::for_buffer(buffer.clone(), Some(project.clone()), window, cx)
+                });
+                workspace.add_item_to_active_pane(Box::new(editor), None, true, window, cx);
+            }
+        });
+        buffer_b.update(cx, |buffer, cx| {
+            buffer.edit([(0..0, "// unsaved\n")], None, cx)
+        });
+
+        workspace.update(cx, |workspace, cx| {
+            let entries = open_buffer_entries(workspace, cx)
+                .into_iter()
+                .map(|entry| {
+                    (
+                        entry.project_path.path.to_string_lossy().into_owned(),
+                        entry.path_prefix.to_string(),
+                        entry.is_dirty,
+                    )
+                })
+                .collect::<Vec<_>>();
+            let prefix = format!("root{}", std::path::MAIN_SEPARATOR);
+            assert_eq!(
+                entries,
+                vec![
+                    ("a.rs".to_string(), prefix.clone(), false),
+                    ("b.rs".to_string(), prefix, true),
+                ]
+            );
+        });
+    }
+}
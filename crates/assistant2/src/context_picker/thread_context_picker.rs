@@ -1,17 +1,40 @@
+use std::cmp::Ordering;
+use std::mem;
 use std::sync::Arc;
+use std::time::Duration;
 
+use anyhow::Context as _;
+use assistant_context_editor::humanize_token_count;
+use chrono::{DateTime, Datelike as _, Local, NaiveDate, TimeZone, Utc};
+use collections::{HashMap, HashSet};
+use futures::future::{self, BoxFuture};
+use futures::FutureExt as _;
 use fuzzy::StringMatchCandidate;
-use gpui::{App, DismissEvent, Entity, FocusHandle, Focusable, Task, WeakEntity};
+use gpui::{
+    Action, AnyElement, App, BackgroundExecutor, ClipboardItem, DismissEvent, Entity, FocusHandle,
+    Focusable, Subscription, Task, WeakEntity,
+};
+use language_model::{
+    LanguageModelRegistry, LanguageModelRequest, LanguageModelRequestMessage, Role,
+};
 use picker::{Picker, PickerDelegate};
-use ui::{prelude::*, ListItem};
+use time::{OffsetDateTime, UtcOffset};
+use ui::{prelude::*, KeyBinding, ListItem, Tooltip};
+use workspace::notifications::{NotificationId, NotifyResultExt};
+use workspace::{Toast, Workspace};
 
-use crate::context_picker::{ConfirmBehavior, ContextPicker};
+use crate::context::estimate_token_count;
+use crate::context_picker::{selectable_index, ConfirmBehavior, ContextPicker};
 use crate::context_store::{self, ContextStore};
-use crate::thread::ThreadId;
-use crate::thread_store::ThreadStore;
+use crate::thread::{ThreadId, ThreadTextOptions};
+use crate::thread_store::{ThreadStore, ThreadStoreEvent};
+use crate::AssistantPanel;
+
+const REFRESH_TIMES_INTERVAL: Duration = Duration::from_secs(30);
 
 pub struct ThreadContextPicker {
     picker: Entity<Picker<ThreadContextPickerDelegate>>,
+    _subscriptions: Vec<Subscription>,
 }
 
 impl ThreadContextPicker {
@@ -20,18 +43,45 @@ impl ThreadContextPicker {
         context_picker: WeakEntity<ContextPicker>,
         context_store: WeakEntity<context_store::ContextStore>,
         confirm_behavior: ConfirmBehavior,
+        text_options: ThreadTextOptions,
+        active_thread_id: Option<ThreadId>,
         window: &mut Window,
         cx: &mut Context<Self>,
     ) -> Self {
         let delegate = ThreadContextPickerDelegate::new(
-            thread_store,
+            thread_store.clone(),
             context_picker,
             context_store,
             confirm_behavior,
+            text_options,
+            active_thread_id,
         );
-        let picker = cx.new(|cx| Picker::uniform_list(delegate, window, cx));
+        // Date group headers are shorter than threads, so the rows aren't uniform.
+        let picker = cx.new(|cx| Picker::list(delegate, window, cx));
 
-        ThreadContextPicker { picker }
+        let _subscriptions = thread_store
+            .upgrade()
+            .map(|thread_store| {
+                cx.subscribe_in(
+                    &thread_store,
+                    window,
+                    |this, _, event: &ThreadStoreEvent, window, cx| match event {
+                        ThreadStoreEvent::ThreadsChanged => {
+                            this.picker.update(cx, |picker, cx| {
+                                picker.delegate.threads_stale = true;
+                                picker.refresh(window, cx);
+                            });
+                        }
+                    },
+                )
+            })
+            .into_iter()
+            .collect();
+
+        ThreadContextPicker {
+            picker,
+            _subscriptions,
+        }
     }
 }
 
@@ -51,6 +101,58 @@ impl Render for ThreadContextPicker {
 pub struct ThreadContextEntry {
     pub id: ThreadId,
     pub summary: SharedString,
+    pub message_count: usize,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A row of the thread picker.
+#[derive(Debug, Clone)]
+enum ThreadPickerEntry {
+    /// The header of the threads below it, which can't be selected.
+    Group(DateGroup),
+    Thread(ThreadContextEntry),
+}
+
+impl ThreadPickerEntry {
+    fn is_thread(&self) -> bool {
+        matches!(self, ThreadPickerEntry::Thread(_))
+    }
+}
+
+/// When a thread was last updated, relative to the local day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DateGroup {
+    Today,
+    Yesterday,
+    ThisWeek,
+    Earlier,
+}
+
+impl DateGroup {
+    /// The group of a thread updated at `updated_at`, with days starting at midnight in `now`'s
+    /// timezone. Timestamps in the future, e.g. from a skewed clock, count as today.
+    fn for_updated_at<Tz: TimeZone>(updated_at: DateTime<Utc>, now: &DateTime<Tz>) -> Self {
+        let today = now.date_naive();
+        let date = updated_at.with_timezone(&now.timezone()).date_naive();
+        if date >= today {
+            DateGroup::Today
+        } else if Some(date) == today.pred_opt() {
+            DateGroup::Yesterday
+        } else if date.iso_week() == today.iso_week() {
+            DateGroup::ThisWeek
+        } else {
+            DateGroup::Earlier
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            DateGroup::Today => "Today",
+            DateGroup::Yesterday => "Yesterday",
+            DateGroup::ThisWeek => "This Week",
+            DateGroup::Earlier => "Earlier",
+        }
+    }
 }
 
 pub struct ThreadContextPickerDelegate {
@@ -58,8 +160,30 @@ pub struct ThreadContextPickerDelegate {
     context_picker: WeakEntity<ContextPicker>,
     context_store: WeakEntity<context_store::ContextStore>,
     confirm_behavior: ConfirmBehavior,
+    /// How attached threads are turned into text.
+    text_options: ThreadTextOptions,
+    /// The thread being composed in, which is never offered as context for itself.
+    active_thread_id: Option<ThreadId>,
+    /// The threads in the thread store, read once and filtered for each query rather than read
+    /// again on every keystroke.
+    threads: Vec<ThreadContextEntry>,
+    /// Whether the thread store changed since `threads` was read.
+    threads_stale: bool,
+    /// Estimated token cost of each thread's text, or `None` while the estimate is being computed.
+    token_estimates: HashMap<ThreadId, Option<usize>>,
     matches: Vec<ThreadContextEntry>,
+    /// The rows showing the matches, which are grouped by date unless they're ranked by a query.
+    entries: Vec<ThreadPickerEntry>,
+    /// The local day the entries were grouped on, or `None` if they aren't grouped.
+    grouped_on: Option<NaiveDate>,
+    /// The query the current matches were found for.
+    query: String,
+    /// The number of threads that could be attached, regardless of the query.
+    thread_count: usize,
     selected_index: usize,
+    /// Re-renders the list now and then, so the relative update times and date groups don't go
+    /// stale.
+    refresh_times_task: Option<Task<()>>,
 }
 
 impl ThreadContextPickerDelegate {
@@ -68,15 +192,236 @@ impl ThreadContextPickerDelegate {
         context_picker: WeakEntity<ContextPicker>,
         context_store: WeakEntity<context_store::ContextStore>,
         confirm_behavior: ConfirmBehavior,
+        text_options: ThreadTextOptions,
+        active_thread_id: Option<ThreadId>,
     ) -> Self {
         ThreadContextPickerDelegate {
             thread_store,
             context_picker,
             context_store,
             confirm_behavior,
+            text_options,
+            active_thread_id,
+            threads: Vec::new(),
+            threads_stale: true,
+            token_estimates: HashMap::default(),
             matches: Vec::new(),
+            entries: Vec::new(),
+            grouped_on: None,
+            query: String::new(),
+            thread_count: 0,
             selected_index: 0,
+            refresh_times_task: None,
+        }
+    }
+
+    /// Applies the [`ConfirmBehavior`] once a confirmation has been handled, whether or not the
+    /// thread could be attached, so the picker never appears stuck.
+    fn finish_confirm(&mut self, window: &mut Window, cx: &mut Context<Picker<Self>>) {
+        match self.confirm_behavior {
+            ConfirmBehavior::KeepOpen => {}
+            ConfirmBehavior::Close => self.dismissed(window, cx),
+        }
+    }
+
+    fn selected_thread(&self) -> Option<&ThreadContextEntry> {
+        match self.entries.get(self.selected_index)? {
+            ThreadPickerEntry::Thread(thread) => Some(thread),
+            ThreadPickerEntry::Group(_) => None,
+        }
+    }
+
+    fn first_thread_ix(&self) -> Option<usize> {
+        self.entries.iter().position(ThreadPickerEntry::is_thread)
+    }
+
+    /// Lays out the matches as rows. Grouping would fight the ranking of a fuzzy query, so the
+    /// matches are only grouped by date when there is no query.
+    fn layout_entries(&mut self, now: &DateTime<Local>) {
+        if self.query.is_empty() {
+            self.entries = group_by_date(self.matches.clone(), now);
+            self.grouped_on = Some(now.date_naive());
+        } else {
+            self.entries = self
+                .matches
+                .iter()
+                .cloned()
+                .map(ThreadPickerEntry::Thread)
+                .collect();
+            self.grouped_on = None;
+        }
+    }
+
+    /// Groups the matches again once the local day changed since they were grouped, keeping the
+    /// selected thread selected.
+    fn regroup_if_day_changed(&mut self, now: &DateTime<Local>) {
+        if self
+            .grouped_on
+            .map_or(true, |grouped_on| grouped_on == now.date_naive())
+        {
+            return;
+        }
+
+        let selected_thread_id = self.selected_thread().map(|thread| thread.id.clone());
+        self.layout_entries(now);
+        self.selected_index = selected_thread_id
+            .and_then(|thread_id| {
+                self.entries.iter().position(|entry| {
+                    matches!(entry, ThreadPickerEntry::Thread(thread) if thread.id == thread_id)
+                })
+            })
+            .or_else(|| self.first_thread_ix())
+            .unwrap_or(0);
+    }
+
+    /// Continues the selected thread in a fork of it that becomes the active thread, rather than
+    /// attaching it as context.
+    fn fork_selected_thread(&mut self, window: &mut Window, cx: &mut Context<Picker<Self>>) {
+        let Some(entry) = self.selected_thread() else {
+            return;
+        };
+
+        let panel = self
+            .workspace(cx)
+            .and_then(|workspace| workspace.read(cx).panel::<AssistantPanel>(cx));
+        let Some(panel) = panel else {
+            log::error!("no assistant panel to fork thread {:?} into", entry.id);
+            self.dismissed(window, cx);
+            return;
+        };
+
+        let fork_thread_task =
+            panel.update(cx, |panel, cx| panel.fork_thread(&entry.id, window, cx));
+        let summary = entry.summary.clone();
+        cx.spawn_in(window, |_, mut cx| async move {
+            fork_thread_task
+                .await
+                .with_context(|| format!("failed to fork thread \"{summary}\""))
+                .notify_async_err(&mut cx);
+        })
+        .detach();
+
+        // The forked thread replaces the one this picker attaches context to.
+        self.dismissed(window, cx);
+    }
+
+    /// Copies the selected thread's transcript to the clipboard, formatted the same way it would
+    /// be attached, without attaching it or closing the picker.
+    fn copy_selected_thread(&mut self, window: &mut Window, cx: &mut Context<Picker<Self>>) {
+        let Some(entry) = self.selected_thread() else {
+            return;
+        };
+
+        let thread_text_task = self.thread_text(&entry.id, self.text_options, cx);
+        let workspace = self.workspace(cx);
+        let summary = entry.summary.clone();
+        cx.spawn_in(window, |_, mut cx| async move {
+            let Some(text) = thread_text_task
+                .await
+                .with_context(|| format!("failed to copy thread \"{summary}\""))
+                .notify_async_err(&mut cx)
+            else {
+                return;
+            };
+
+            cx.update(|_, cx| {
+                cx.write_to_clipboard(ClipboardItem::new_string(text));
+                if let Some(workspace) = workspace {
+                    workspace.update(cx, |workspace, cx| {
+                        struct ThreadCopied;
+                        let id = NotificationId::unique::<ThreadCopied>();
+                        let message = format!("Copied \"{summary}\" to the clipboard");
+                        workspace.show_toast(Toast::new(id, message).autohide(), cx);
+                    });
+                }
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    /// Opens the thread and renders it as text, the same way it's rendered when attached.
+    fn thread_text(
+        &self,
+        thread_id: &ThreadId,
+        text_options: ThreadTextOptions,
+        cx: &mut App,
+    ) -> Task<anyhow::Result<String>> {
+        let Some(thread_store) = self.thread_store.upgrade() else {
+            return Task::ready(Err(anyhow::anyhow!("thread store was dropped")));
+        };
+
+        let open_thread_task = thread_store.update(cx, |this, cx| this.open_thread(thread_id, cx));
+        cx.spawn(|cx| async move {
+            let thread = open_thread_task.await?;
+            thread.read_with(&cx, |thread, _cx| thread.to_context_text(text_options))
+        })
+    }
+
+    fn workspace(&self, cx: &App) -> Option<Entity<Workspace>> {
+        self.context_picker
+            .upgrade()
+            .and_then(|context_picker| context_picker.read(cx).workspace.upgrade())
+    }
+
+    /// Reads the threads from the thread store again if it changed since they were last read.
+    fn refresh_threads(&mut self, cx: &App) {
+        if !self.threads_stale {
+            return;
         }
+        let Some(thread_store) = self.thread_store.upgrade() else {
+            return;
+        };
+
+        let threads = thread_context_entries(thread_store.read(cx));
+        let previous_threads = mem::replace(&mut self.threads, threads);
+        retain_unchanged_token_estimates(
+            &mut self.token_estimates,
+            &previous_threads,
+            &self.threads,
+        );
+        self.threads_stale = false;
+    }
+
+    fn refresh_times_periodically(&mut self, cx: &mut Context<Picker<Self>>) {
+        if self.refresh_times_task.is_some() {
+            return;
+        }
+
+        self.refresh_times_task = Some(cx.spawn(|picker, mut cx| async move {
+            loop {
+                cx.background_executor().timer(REFRESH_TIMES_INTERVAL).await;
+                let updated = picker.update(&mut cx, |picker, cx| {
+                    picker.delegate.regroup_if_day_changed(&Local::now());
+                    cx.notify();
+                });
+                if updated.is_err() {
+                    break;
+                }
+            }
+        }));
+    }
+
+    /// Starts estimating the token cost of the thread, unless it was already estimated. Threads
+    /// are only estimated once they're shown, since each one is opened and rendered as text.
+    fn estimate_thread_tokens(&mut self, thread_id: ThreadId, cx: &mut Context<Picker<Self>>) {
+        if self.token_estimates.contains_key(&thread_id) || self.thread_store.upgrade().is_none() {
+            return;
+        }
+        self.token_estimates.insert(thread_id.clone(), None);
+
+        let thread_text_task = self.thread_text(&thread_id, self.text_options, cx);
+        cx.spawn(|this, mut cx| async move {
+            let text = thread_text_task.await?;
+            let token_count = cx.update(|cx| count_tokens(text, cx))?.await;
+            this.update(&mut cx, |this, cx| {
+                this.delegate
+                    .token_estimates
+                    .insert(thread_id, Some(token_count));
+                cx.notify();
+            })
+        })
+        .detach_and_log_err(cx);
     }
 }
 
@@ -84,7 +429,7 @@ impl PickerDelegate for ThreadContextPickerDelegate {
     type ListItem = ListItem;
 
     fn match_count(&self) -> usize {
-        self.matches.len()
+        self.entries.len()
     }
 
     fn selected_index(&self) -> usize {
@@ -97,97 +442,139 @@ impl PickerDelegate for ThreadContextPickerDelegate {
         _window: &mut Window,
         _cx: &mut Context<Picker<Self>>,
     ) {
-        self.selected_index = ix;
+        // Moving up onto a header continues upwards; anything else skips down past it.
+        let backward = ix + 1 == self.selected_index;
+        self.selected_index =
+            selectable_index(&self.entries, ix, backward, ThreadPickerEntry::is_thread)
+                .unwrap_or(ix);
     }
 
     fn placeholder_text(&self, _window: &mut Window, _cx: &mut App) -> Arc<str> {
         "Search threads…".into()
     }
 
+    fn no_matches_text(&self, _window: &mut Window, _cx: &mut App) -> Option<SharedString> {
+        Some(no_threads_text(&self.query, self.thread_count))
+    }
+
     fn update_matches(
         &mut self,
         query: String,
         window: &mut Window,
         cx: &mut Context<Picker<Self>>,
     ) -> Task<()> {
-        let Ok(threads) = self.thread_store.update(cx, |this, _cx| {
-            this.threads()
-                .into_iter()
-                .map(|thread| ThreadContextEntry {
-                    id: thread.id,
-                    summary: thread.summary,
-                })
-                .collect::<Vec<_>>()
-        }) else {
-            return Task::ready(());
-        };
+        self.refresh_threads(cx);
+        let threads = self.threads.clone();
 
+        let active_thread_id = self.active_thread_id.clone();
+        let thread_count = threads
+            .iter()
+            .filter(|thread| Some(&thread.id) != active_thread_id.as_ref())
+            .count();
         let executor = cx.background_executor().clone();
-        let search_task = cx.background_spawn(async move {
-            if query.is_empty() {
-                threads
-            } else {
-                let candidates = threads
-                    .iter()
-                    .enumerate()
-                    .map(|(id, thread)| StringMatchCandidate::new(id, &thread.summary))
-                    .collect::<Vec<_>>();
-                let matches = fuzzy::match_strings(
-                    &candidates,
-                    &query,
-                    false,
-                    100,
-                    &Default::default(),
-                    executor,
-                )
-                .await;
-
-                matches
-                    .into_iter()
-                    .map(|mat| threads[mat.candidate_id].clone())
-                    .collect()
-            }
+        let search_task = cx.background_spawn({
+            let query = query.clone();
+            async move { search_threads(threads, query, active_thread_id.as_ref(), executor).await }
         });
 
         cx.spawn_in(window, |this, mut cx| async move {
             let matches = search_task.await;
             this.update(&mut cx, |this, cx| {
                 this.delegate.matches = matches;
-                this.delegate.selected_index = 0;
+                this.delegate.query = query;
+                this.delegate.thread_count = thread_count;
+                this.delegate.layout_entries(&Local::now());
+                this.delegate.selected_index = this.delegate.first_thread_ix().unwrap_or(0);
+                this.delegate.refresh_times_periodically(cx);
                 cx.notify();
             })
             .ok();
         })
     }
 
-    fn confirm(&mut self, _secondary: bool, window: &mut Window, cx: &mut Context<Picker<Self>>) {
-        let Some(entry) = self.matches.get(self.selected_index) else {
+    /// Attaches the selected thread's transcript, or only its summary on secondary confirm.
+    fn confirm(&mut self, secondary: bool, window: &mut Window, cx: &mut Context<Picker<Self>>) {
+        let Some(entry) = self.selected_thread() else {
             return;
         };
 
-        let Some(thread_store) = self.thread_store.upgrade() else {
-            return;
+        let text_options = ThreadTextOptions {
+            summary_only: secondary,
+            ..self.text_options
         };
-
-        let open_thread_task = thread_store.update(cx, |this, cx| this.open_thread(&entry.id, cx));
+        let attach_task = attach_thread_entry(
+            entry,
+            text_options,
+            &self.thread_store,
+            &self.context_store,
+            window,
+            cx,
+        );
 
         cx.spawn_in(window, |this, mut cx| async move {
-            let thread = open_thread_task.await?;
+            attach_task.await;
             this.update_in(&mut cx, |this, window, cx| {
-                this.delegate
-                    .context_store
-                    .update(cx, |context_store, cx| context_store.add_thread(thread, cx))
-                    .ok();
-
-                match this.delegate.confirm_behavior {
-                    ConfirmBehavior::KeepOpen => {}
-                    ConfirmBehavior::Close => this.delegate.dismissed(window, cx),
-                }
+                this.delegate.finish_confirm(window, cx);
             })
         })
         .detach_and_log_err(cx);
     }
 
+    fn render_footer(
+        &self,
+        window: &mut Window,
+        cx: &mut Context<Picker<Self>>,
+    ) -> Option<AnyElement> {
+        Some(
+            h_flex()
+                .w_full()
+                .p_2()
+                .gap_1()
+                .justify_end()
+                .border_t_1()
+                .border_color(cx.theme().colors().border_variant)
+                .child(
+                    Button::new("copy-transcript", "Copy Transcript")
+                        .label_size(LabelSize::Small)
+                        .disabled(self.matches.is_empty())
+                        .tooltip(Tooltip::text("Copy the thread's text without attaching it"))
+                        .on_click(cx.listener(|picker, _, window, cx| {
+                            picker.delegate.copy_selected_thread(window, cx);
+                        })),
+                )
+                .child(
+                    Button::new("fork-thread", "Fork Thread")
+                        .label_size(LabelSize::Small)
+                        .disabled(self.matches.is_empty())
+                        .tooltip(Tooltip::text("Continue the conversation in a copy of it"))
+                        .on_click(cx.listener(|picker, _, window, cx| {
+                            picker.delegate.fork_selected_thread(window, cx);
+                        })),
+                )
+                .children(
+                    KeyBinding::for_action(&menu::SecondaryConfirm, window, cx).map(|keybind| {
+                        Button::new("attach-summary", "Attach Summary")
+                            .label_size(LabelSize::Small)
+                            .key_binding(keybind)
+                            .on_click(|_, window, cx| {
+                                window.dispatch_action(menu::SecondaryConfirm.boxed_clone(), cx)
+                            })
+                    }),
+                )
+                .children(
+                    KeyBinding::for_action(&menu::Confirm, window, cx).map(|keybind| {
+                        Button::new("attach-transcript", "Attach Transcript")
+                            .label_size(LabelSize::Small)
+                            .key_binding(keybind)
+                            .on_click(|_, window, cx| {
+                                window.dispatch_action(menu::Confirm.boxed_clone(), cx)
+                            })
+                    }),
+                )
+                .into_any(),
+        )
+    }
+
     fn dismissed(&mut self, _window: &mut Window, cx: &mut Context<Picker<Self>>) {
         self.context_picker
             .update(cx, |_, cx| {
@@ -200,17 +587,227 @@ impl PickerDelegate for ThreadContextPickerDelegate {
         &self,
         ix: usize,
         selected: bool,
-        _window: &mut Window,
+        window: &mut Window,
         cx: &mut Context<Picker<Self>>,
     ) -> Option<Self::ListItem> {
-        let thread = &self.matches[ix];
+        let thread = match &self.entries[ix] {
+            ThreadPickerEntry::Thread(thread) => thread,
+            ThreadPickerEntry::Group(group) => {
+                return Some(
+                    ListItem::new(ix)
+                        .inset(true)
+                        .selectable(false)
+                        // Keeps the picker from confirming a thread when the header is clicked.
+                        .on_click(|_, _, cx| cx.stop_propagation())
+                        .child(
+                            Label::new(group.label())
+                                .size(LabelSize::XSmall)
+                                .color(Color::Muted),
+                        ),
+                );
+            }
+        };
+        if !self.token_estimates.contains_key(&thread.id) {
+            let thread_id = thread.id.clone();
+            cx.defer_in(window, |picker, _window, cx| {
+                picker.delegate.estimate_thread_tokens(thread_id, cx)
+            });
+        }
+        let token_estimate =
+            self.token_estimates
+                .get(&thread.id)
+                .copied()
+                .flatten()
+                .map(|token_count| {
+                    Label::new(format!("~{} tokens", humanize_token_count(token_count)))
+                        .size(LabelSize::Small)
+                        .color(Color::Muted)
+                });
 
-        Some(ListItem::new(ix).inset(true).toggle_state(selected).child(
-            render_thread_context_entry(thread, self.context_store.clone(), cx),
-        ))
+        Some(
+            ListItem::new(ix)
+                .inset(true)
+                .toggle_state(selected)
+                .end_slot(token_estimate)
+                .child(render_thread_context_entry(
+                    thread,
+                    self.context_store.clone(),
+                    cx,
+                )),
+        )
     }
 }
 
+/// Counts the tokens in `text` using the active model's tokenizer, falling back to a rough
+/// estimate when the model can't count tokens.
+fn count_tokens(text: String, cx: &App) -> BoxFuture<'static, usize> {
+    let fallback = estimate_token_count(text.len());
+    let Some(model) = LanguageModelRegistry::read_global(cx).active_model() else {
+        return future::ready(fallback).boxed();
+    };
+
+    let request = LanguageModelRequest {
+        messages: vec![LanguageModelRequestMessage {
+            role: Role::User,
+            content: vec![text.into()],
+            cache: false,
+        }],
+        tools: Vec::new(),
+        stop: Vec::new(),
+        temperature: None,
+    };
+    let token_count = model.count_tokens(request, cx);
+    async move { token_count.await.unwrap_or(fallback) }.boxed()
+}
+
+pub(super) fn thread_context_entries(thread_store: &ThreadStore) -> Vec<ThreadContextEntry> {
+    thread_store
+        .threads()
+        .into_iter()
+        .map(|thread| ThreadContextEntry {
+            id: thread.id,
+            summary: thread.summary,
+            message_count: thread.message_count,
+            updated_at: thread.updated_at,
+        })
+        .collect()
+}
+
+/// Drops the token estimates of threads that were removed or updated, so they're estimated again.
+fn retain_unchanged_token_estimates(
+    token_estimates: &mut HashMap<ThreadId, Option<usize>>,
+    previous_threads: &[ThreadContextEntry],
+    threads: &[ThreadContextEntry],
+) {
+    let previous_updated_at = previous_threads
+        .iter()
+        .map(|thread| (&thread.id, thread.updated_at))
+        .collect::<HashMap<_, _>>();
+    let unchanged = threads
+        .iter()
+        .filter(|thread| previous_updated_at.get(&thread.id) == Some(&thread.updated_at))
+        .map(|thread| &thread.id)
+        .collect::<HashSet<_>>();
+    token_estimates.retain(|thread_id, _| unchanged.contains(thread_id));
+}
+
+/// Puts each date group's header above its threads, which must be ordered by recency.
+fn group_by_date<Tz: TimeZone>(
+    threads: Vec<ThreadContextEntry>,
+    now: &DateTime<Tz>,
+) -> Vec<ThreadPickerEntry> {
+    let mut entries = Vec::with_capacity(threads.len());
+    let mut current_group = None;
+    for thread in threads {
+        let group = DateGroup::for_updated_at(thread.updated_at, now);
+        if current_group != Some(group) {
+            entries.push(ThreadPickerEntry::Group(group));
+            current_group = Some(group);
+        }
+        entries.push(ThreadPickerEntry::Thread(thread));
+    }
+    entries
+}
+
+/// Explains an empty list: either there is nothing to attach yet, or the query filtered it all out.
+fn no_threads_text(query: &str, thread_count: usize) -> SharedString {
+    if thread_count == 0 {
+        "No threads yet — start a conversation".into()
+    } else {
+        format!("No threads match '{query}'").into()
+    }
+}
+
+/// Returns the threads matching `query`, most recently updated first. Fuzzy matches are ordered by
+/// score, with ties going to the more recently updated thread. The `excluded` thread is never
+/// returned.
+async fn search_threads(
+    threads: Vec<ThreadContextEntry>,
+    query: String,
+    excluded: Option<&ThreadId>,
+    executor: BackgroundExecutor,
+) -> Vec<ThreadContextEntry> {
+    search_threads_with_scores(threads, query, excluded, executor)
+        .await
+        .into_iter()
+        .map(|(thread, _score)| thread)
+        .collect()
+}
+
+/// Opens a saved thread and attaches its text the way the thread picker's confirm does. The
+/// returned task resolves once the thread is attached, or once the failure to open it was shown.
+pub(super) fn attach_thread_entry(
+    entry: &ThreadContextEntry,
+    text_options: ThreadTextOptions,
+    thread_store: &WeakEntity<ThreadStore>,
+    context_store: &WeakEntity<ContextStore>,
+    window: &mut Window,
+    cx: &mut App,
+) -> Task<()> {
+    let Some(thread_store) = thread_store.upgrade() else {
+        log::error!(
+            "thread store was dropped before thread {:?} could be opened",
+            entry.id
+        );
+        return Task::ready(());
+    };
+    let Ok(attach_task) = context_store.update(cx, |context_store, cx| {
+        context_store.attach_thread(&thread_store, &entry.id, text_options, cx)
+    }) else {
+        return Task::ready(());
+    };
+
+    let summary = entry.summary.clone();
+    window.spawn(cx, |mut cx| async move {
+        attach_task
+            .await
+            .with_context(|| format!("failed to open thread \"{summary}\""))
+            .notify_async_err(&mut cx);
+    })
+}
+
+/// Like [`search_threads`], but also returns each thread's fuzzy match score, which is zero for
+/// an empty query.
+pub(super) async fn search_threads_with_scores(
+    mut threads: Vec<ThreadContextEntry>,
+    query: String,
+    excluded: Option<&ThreadId>,
+    executor: BackgroundExecutor,
+) -> Vec<(ThreadContextEntry, f64)> {
+    threads.retain(|thread| Some(&thread.id) != excluded);
+    threads.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+    if query.is_empty() {
+        return threads.into_iter().map(|thread| (thread, 0.)).collect();
+    }
+
+    let candidates = threads
+        .iter()
+        .enumerate()
+        .map(|(id, thread)| StringMatchCandidate::new(id, &thread.summary))
+        .collect::<Vec<_>>();
+    let mut matches = fuzzy::match_strings(
+        &candidates,
+        &query,
+        false,
+        100,
+        &Default::default(),
+        executor,
+    )
+    .await;
+    // Candidates are ordered by recency, so a lower ID means a more recent thread.
+    matches.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| a.candidate_id.cmp(&b.candidate_id))
+    });
+
+    matches
+        .into_iter()
+        .map(|mat| (threads[mat.candidate_id].clone(), mat.score))
+        .collect()
+}
+
 pub fn render_thread_context_entry(
     thread: &ThreadContextEntry,
     context_store: WeakEntity<ContextStore>,
@@ -233,7 +830,15 @@ pub fn render_thread_context_entry(
                         .size(IconSize::XSmall)
                         .color(Color::Muted),
                 )
-                .child(Label::new(thread.summary.clone()).truncate()),
+                .child(
+                    v_flex()
+                        .child(Label::new(thread.summary.clone()).truncate())
+                        .child(
+                            Label::new(thread_subtitle(thread))
+                                .size(LabelSize::XSmall)
+                                .color(Color::Muted),
+                        ),
+                ),
         )
         .when(added, |el| {
             el.child(
@@ -248,3 +853,256 @@ pub fn render_thread_context_entry(
             )
         })
 }
+
+/// Describes the thread's length and how long ago it was last updated, e.g.
+/// "3 messages · 2 hours ago".
+fn thread_subtitle(thread: &ThreadContextEntry) -> String {
+    let message_count = match thread.message_count {
+        0 => "empty".to_string(),
+        1 => "1 message".to_string(),
+        count => format!("{count} messages"),
+    };
+
+    format!(
+        "{message_count} · {}",
+        format_updated_at(thread.updated_at, Utc::now())
+    )
+}
+
+/// Formats `updated_at` relative to `now`. Timestamps in the future, e.g. from another machine
+/// with a skewed clock, read as "Just now".
+fn format_updated_at(updated_at: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let local_timezone =
+        UtcOffset::from_whole_seconds(chrono::Local::now().offset().local_minus_utc())
+            .unwrap_or(UtcOffset::UTC);
+    let (Ok(updated_at), Ok(now)) = (
+        OffsetDateTime::from_unix_timestamp(updated_at.min(now).timestamp()),
+        OffsetDateTime::from_unix_timestamp(now.timestamp()),
+    ) else {
+        return String::new();
+    };
+
+    time_format::format_localized_timestamp(
+        updated_at,
+        now,
+        local_timezone,
+        time_format::TimestampFormat::Relative,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone as _;
+    use gpui::TestAppContext;
+
+    #[gpui::test]
+    async fn test_search_threads_orders_by_recency(cx: &mut TestAppContext) {
+        let thread = |summary: &str, updated_at: i64| ThreadContextEntry {
+            id: ThreadId::new(),
+            summary: summary.to_string().into(),
+            message_count: 1,
+            updated_at: Utc.timestamp_opt(updated_at, 0).unwrap(),
+        };
+        let threads = vec![
+            thread("Fix parser", 100),
+            thread("Fix picker", 300),
+            thread("Refactor parser", 200),
+        ];
+        let summaries = |threads: Vec<ThreadContextEntry>| {
+            threads
+                .into_iter()
+                .map(|thread| thread.summary.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let matches = search_threads(threads.clone(), String::new(), None, cx.executor()).await;
+        assert_eq!(
+            summaries(matches),
+            vec!["Fix picker", "Refactor parser", "Fix parser"]
+        );
+
+        // "Fix parser" and "Fix picker" score the same, so the newer one comes first.
+        let matches = search_threads(threads, "fix p".to_string(), None, cx.executor()).await;
+        assert_eq!(summaries(matches), vec!["Fix picker", "Fix parser"]);
+    }
+
+    #[test]
+    fn test_no_threads_text() {
+        assert_eq!(
+            no_threads_text("", 0).as_ref(),
+            "No threads yet — start a conversation"
+        );
+        assert_eq!(
+            no_threads_text("parser", 0).as_ref(),
+            "No threads yet — start a conversation"
+        );
+        assert_eq!(
+            no_threads_text("parser", 3).as_ref(),
+            "No threads match 'parser'"
+        );
+    }
+
+    #[test]
+    fn test_format_updated_at() {
+        let now = Utc.timestamp_opt(1_700_000_000, 0).unwrap();
+        let ago = |seconds: i64| now - chrono::Duration::seconds(seconds);
+
+        assert_eq!(format_updated_at(now, now), "Just now");
+        assert_eq!(format_updated_at(ago(90), now), "1 minute ago");
+        assert_eq!(format_updated_at(ago(2 * 60 * 60), now), "2 hours ago");
+        // Clock skew can put the timestamp slightly in the future.
+        assert_eq!(format_updated_at(ago(-3), now), "Just now");
+        assert_eq!(format_updated_at(ago(-3 * 60 * 60), now), "Just now");
+    }
+
+    #[test]
+    fn test_date_group_for_updated_at() {
+        // Wednesday, 2023-11-15 at 01:00 in UTC+02:00, which is still the 14th in UTC.
+        let timezone = chrono::FixedOffset::east_opt(2 * 60 * 60).unwrap();
+        let now = timezone.with_ymd_and_hms(2023, 11, 15, 1, 0, 0).unwrap();
+        let at = |day: u32, hour: u32| {
+            timezone
+                .with_ymd_and_hms(2023, 11, day, hour, 0, 0)
+                .unwrap()
+                .with_timezone(&Utc)
+        };
+
+        assert_eq!(DateGroup::for_updated_at(at(15, 0), &now), DateGroup::Today);
+        assert_eq!(
+            DateGroup::for_updated_at(at(15, 23), &now),
+            DateGroup::Today
+        );
+        assert_eq!(
+            DateGroup::for_updated_at(at(14, 23), &now),
+            DateGroup::Yesterday
+        );
+        assert_eq!(
+            DateGroup::for_updated_at(at(13, 0), &now),
+            DateGroup::ThisWeek
+        );
+        assert_eq!(
+            DateGroup::for_updated_at(at(12, 23), &now),
+            DateGroup::Earlier
+        );
+        assert_eq!(
+            DateGroup::for_updated_at(at(1, 12), &now),
+            DateGroup::Earlier
+        );
+
+        // On Monday, yesterday is last week, and there is nothing else this week.
+        let monday = timezone.with_ymd_and_hms(2023, 11, 13, 12, 0, 0).unwrap();
+        assert_eq!(
+            DateGroup::for_updated_at(at(12, 12), &monday),
+            DateGroup::Yesterday
+        );
+        assert_eq!(
+            DateGroup::for_updated_at(at(11, 12), &monday),
+            DateGroup::Earlier
+        );
+    }
+
+    #[test]
+    fn test_group_by_date() {
+        let now = Utc.with_ymd_and_hms(2023, 11, 15, 12, 0, 0).unwrap();
+        let thread = |summary: &str, hours_ago: i64| ThreadContextEntry {
+            id: ThreadId::new(),
+            summary: summary.to_string().into(),
+            message_count: 1,
+            updated_at: now - chrono::Duration::hours(hours_ago),
+        };
+        let threads = vec![
+            thread("Fix picker", 1),
+            thread("Fix parser", 2),
+            thread("Refactor parser", 30),
+            thread("Add tests", 24 * 20),
+        ];
+
+        let rows = group_by_date(threads, &now)
+            .into_iter()
+            .map(|entry| match entry {
+                ThreadPickerEntry::Group(group) => format!("# {}", group.label()),
+                ThreadPickerEntry::Thread(thread) => thread.summary.to_string(),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            rows,
+            vec![
+                "# Today",
+                "Fix picker",
+                "Fix parser",
+                "# Yesterday",
+                "Refactor parser",
+                "# Earlier",
+                "Add tests"
+            ]
+        );
+        assert!(group_by_date(Vec::new(), &now).is_empty());
+    }
+
+    #[test]
+    fn test_retain_unchanged_token_estimates() {
+        let thread = |updated_at: i64| ThreadContextEntry {
+            id: ThreadId::new(),
+            summary: "Thread".into(),
+            message_count: 1,
+            updated_at: Utc.timestamp_opt(updated_at, 0).unwrap(),
+        };
+        let unchanged = thread(100);
+        let updated = thread(100);
+        let deleted = thread(100);
+        let previous_threads = vec![unchanged.clone(), updated.clone(), deleted.clone()];
+        let threads = vec![
+            unchanged.clone(),
+            ThreadContextEntry {
+                updated_at: Utc.timestamp_opt(200, 0).unwrap(),
+                ..updated.clone()
+            },
+        ];
+
+        let mut token_estimates = HashMap::default();
+        for thread in &previous_threads {
+            token_estimates.insert(thread.id.clone(), Some(10));
+        }
+        retain_unchanged_token_estimates(&mut token_estimates, &previous_threads, &threads);
+
+        assert_eq!(token_estimates.len(), 1);
+        assert_eq!(token_estimates.get(&unchanged.id), Some(&Some(10)));
+    }
+
+    #[gpui::test]
+    async fn test_search_threads_excludes_active_thread(cx: &mut TestAppContext) {
+        let thread = |summary: &str| ThreadContextEntry {
+            id: ThreadId::new(),
+            summary: summary.to_string().into(),
+            message_count: 1,
+            updated_at: Utc.timestamp_opt(0, 0).unwrap(),
+        };
+        let active = thread("Fix parser");
+        let threads = vec![active.clone(), thread("Fix picker")];
+
+        for query in ["", "fix", "parser"] {
+            let matches = search_threads(
+                threads.clone(),
+                query.to_string(),
+                Some(&active.id),
+                cx.executor(),
+            )
+            .await;
+            assert!(
+                matches.iter().all(|thread| thread.id != active.id),
+                "active thread matched {query:?}"
+            );
+        }
+
+        // Excluding the only thread leaves nothing to show.
+        let matches = search_threads(
+            vec![active.clone()],
+            String::new(),
+            Some(&active.id),
+            cx.executor(),
+        )
+        .await;
+        assert!(matches.is_empty());
+    }
+}
@@ -0,0 +1,439 @@
+use std::cmp::Ordering;
+use std::sync::atomic::{self, AtomicBool};
+use std::sync::Arc;
+
+use editor::Editor;
+use fuzzy::PathMatch;
+use gpui::{App, DismissEvent, Entity, FocusHandle, Focusable, Task, WeakEntity};
+use picker::{Picker, PickerDelegate};
+use project::{ProjectPath, WorktreeId};
+use ui::{prelude::*, ListItem};
+use util::ResultExt as _;
+use workspace::Workspace;
+
+use crate::context::DirectoryContextOptions;
+use crate::context_picker::file_context_picker::{
+    attach_paths, recent_file_matches, render_file_context_entry, search_paths,
+};
+use crate::context_picker::thread_context_picker::{
+    attach_thread_entry, render_thread_context_entry, search_threads_with_scores,
+    thread_context_entries, ThreadContextEntry,
+};
+use crate::context_picker::{ConfirmBehavior, ContextPicker};
+use crate::context_store::ContextStore;
+use crate::thread::{ThreadId, ThreadTextOptions};
+use crate::thread_store::ThreadStore;
+
+/// Searches project files and threads at once, for when it isn't clear which one holds the
+/// context being looked for.
+pub struct UnifiedContextPicker {
+    picker: Entity<Picker<UnifiedContextPickerDelegate>>,
+}
+
+impl UnifiedContextPicker {
+    pub fn new(
+        context_picker: WeakEntity<ContextPicker>,
+        workspace: WeakEntity<Workspace>,
+        editor: WeakEntity<Editor>,
+        context_store: WeakEntity<ContextStore>,
+        thread_store: WeakEntity<ThreadStore>,
+        confirm_behavior: ConfirmBehavior,
+        active_thread_id: Option<ThreadId>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let delegate = UnifiedContextPickerDelegate::new(
+            context_picker,
+            workspace,
+            editor,
+            context_store,
+            thread_store,
+            confirm_behavior,
+            active_thread_id,
+        );
+        let picker = cx.new(|cx| Picker::uniform_list(delegate, window, cx));
+
+        Self { picker }
+    }
+}
+
+impl Focusable for UnifiedContextPicker {
+    fn focus_handle(&self, cx: &App) -> FocusHandle {
+        self.picker.focus_handle(cx)
+    }
+}
+
+impl Render for UnifiedContextPicker {
+    fn render(&mut self, _window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
+        self.picker.clone()
+    }
+}
+
+#[derive(Debug, Clone)]
+enum UnifiedMatch {
+    Path(PathMatch),
+    Thread(ThreadContextEntry),
+}
+
+pub struct UnifiedContextPickerDelegate {
+    context_picker: WeakEntity<ContextPicker>,
+    workspace: WeakEntity<Workspace>,
+    editor: WeakEntity<Editor>,
+    context_store: WeakEntity<ContextStore>,
+    thread_store: WeakEntity<ThreadStore>,
+    confirm_behavior: ConfirmBehavior,
+    /// The thread being composed in, which is never offered as context for itself.
+    active_thread_id: Option<ThreadId>,
+    /// Set when a newer query supersedes the search that is currently in flight.
+    cancellation_flag: Arc<AtomicBool>,
+    /// Set when the picker is dismissed while a confirmed directory is still being read.
+    confirm_cancellation_flag: Arc<AtomicBool>,
+    matches: Vec<UnifiedMatch>,
+    selected_index: usize,
+}
+
+impl UnifiedContextPickerDelegate {
+    pub fn new(
+        context_picker: WeakEntity<ContextPicker>,
+        workspace: WeakEntity<Workspace>,
+        editor: WeakEntity<Editor>,
+        context_store: WeakEntity<ContextStore>,
+        thread_store: WeakEntity<ThreadStore>,
+        confirm_behavior: ConfirmBehavior,
+        active_thread_id: Option<ThreadId>,
+    ) -> Self {
+        Self {
+            context_picker,
+            workspace,
+            editor,
+            context_store,
+            thread_store,
+            confirm_behavior,
+            active_thread_id,
+            cancellation_flag: Arc::default(),
+            confirm_cancellation_flag: Arc::default(),
+            matches: Vec::new(),
+            selected_index: 0,
+        }
+    }
+
+    fn finish_confirm(&mut self, window: &mut Window, cx: &mut Context<Picker<Self>>) {
+        match self.confirm_behavior {
+            ConfirmBehavior::KeepOpen => {}
+            ConfirmBehavior::Close => self.dismissed(window, cx),
+        }
+    }
+
+    /// Attaches a file, or a directory with its subdirectories, the same way the file picker's
+    /// primary confirm does.
+    fn confirm_path(
+        &mut self,
+        path_match: PathMatch,
+        window: &mut Window,
+        cx: &mut Context<Picker<Self>>,
+    ) {
+        let Some(editor_entity) = self.editor.upgrade() else {
+            return;
+        };
+        let file_name = path_match
+            .path
+            .file_name()
+            .map(|os_str| os_str.to_string_lossy().into_owned())
+            .unwrap_or(path_match.path_prefix.to_string());
+        let project_path = ProjectPath {
+            worktree_id: WorktreeId::from_usize(path_match.worktree_id),
+            path: path_match.path,
+        };
+        let Some(attach_task) = attach_paths(
+            vec![(project_path, file_name, path_match.is_dir)],
+            None,
+            DirectoryContextOptions::default(),
+            self.confirm_cancellation_flag.clone(),
+            &self.workspace,
+            &editor_entity,
+            &self.context_store,
+            window,
+            cx,
+        ) else {
+            return;
+        };
+
+        cx.spawn_in(window, |this, mut cx| async move {
+            if !attach_task.await.all_succeeded {
+                return anyhow::Ok(());
+            }
+            this.update_in(&mut cx, |this, window, cx| {
+                this.delegate.finish_confirm(window, cx)
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
+    /// Attaches a thread's full transcript, the same way the thread picker's primary confirm does.
+    fn confirm_thread(
+        &mut self,
+        entry: ThreadContextEntry,
+        window: &mut Window,
+        cx: &mut Context<Picker<Self>>,
+    ) {
+        let attach_task = attach_thread_entry(
+            &entry,
+            ThreadTextOptions::default(),
+            &self.thread_store,
+            &self.context_store,
+            window,
+            cx,
+        );
+        cx.spawn_in(window, |this, mut cx| async move {
+            attach_task.await;
+            this.update_in(&mut cx, |this, window, cx| {
+                this.delegate.finish_confirm(window, cx);
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+}
+
+impl PickerDelegate for UnifiedContextPickerDelegate {
+    type ListItem = ListItem;
+
+    fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected_index(
+        &mut self,
+        ix: usize,
+        _window: &mut Window,
+        _cx: &mut Context<Picker<Self>>,
+    ) {
+        self.selected_index = ix;
+    }
+
+    fn placeholder_text(&self, _window: &mut Window, _cx: &mut App) -> Arc<str> {
+        "Search files and threads…".into()
+    }
+
+    fn no_matches_text(&self, _window: &mut Window, _cx: &mut App) -> Option<SharedString> {
+        Some("No files or threads match".into())
+    }
+
+    fn update_matches(
+        &mut self,
+        query: String,
+        window: &mut Window,
+        cx: &mut Context<Picker<Self>>,
+    ) -> Task<()> {
+        let Some(workspace) = self.workspace.upgrade() else {
+            return Task::ready(());
+        };
+        let threads = self
+            .thread_store
+            .read_with(cx, |thread_store, _cx| thread_context_entries(thread_store))
+            .unwrap_or_default();
+
+        self.cancellation_flag
+            .store(true, atomic::Ordering::Relaxed);
+        self.cancellation_flag = Arc::default();
+        let cancellation_flag = self.cancellation_flag.clone();
+
+        // Browsing every file of the project would bury the threads, so an empty query only
+        // lists the recently opened files.
+        let path_search = if query.is_empty() {
+            Task::ready(recent_file_matches(workspace.read(cx), false, cx))
+        } else {
+            search_paths(
+                query.clone(),
+                false,
+                cancellation_flag.clone(),
+                &workspace,
+                cx,
+            )
+        };
+        let active_thread_id = self.active_thread_id.clone();
+        let executor = cx.background_executor().clone();
+        let thread_search = cx.background_spawn(async move {
+            search_threads_with_scores(threads, query, active_thread_id.as_ref(), executor).await
+        });
+
+        cx.spawn_in(window, |this, mut cx| async move {
+            let (paths, threads) = futures::join!(path_search, thread_search);
+            if cancellation_flag.load(atomic::Ordering::Relaxed) {
+                return;
+            }
+
+            this.update(&mut cx, |this, cx| {
+                this.delegate.matches = merge_matches(paths, threads);
+                this.delegate.selected_index = 0;
+                cx.notify();
+            })
+            .log_err();
+        })
+    }
+
+    fn confirm(&mut self, _secondary: bool, window: &mut Window, cx: &mut Context<Picker<Self>>) {
+        let Some(mat) = self.matches.get(self.selected_index).cloned() else {
+            return;
+        };
+
+        match mat {
+            UnifiedMatch::Path(path_match) => self.confirm_path(path_match, window, cx),
+            UnifiedMatch::Thread(entry) => self.confirm_thread(entry, window, cx),
+        }
+    }
+
+    fn dismissed(&mut self, _window: &mut Window, cx: &mut Context<Picker<Self>>) {
+        self.confirm_cancellation_flag
+            .store(true, atomic::Ordering::Relaxed);
+        self.confirm_cancellation_flag = Arc::default();
+        self.context_picker
+            .update(cx, |_, cx| {
+                cx.emit(DismissEvent);
+            })
+            .ok();
+    }
+
+    fn render_match(
+        &self,
+        ix: usize,
+        selected: bool,
+        _window: &mut Window,
+        cx: &mut Context<Picker<Self>>,
+    ) -> Option<Self::ListItem> {
+        let (entry, badge) = match &self.matches[ix] {
+            UnifiedMatch::Path(path_match) => (
+                render_file_context_entry(
+                    ElementId::NamedInteger("unified-ctx-picker".into(), ix),
+                    WorktreeId::from_usize(path_match.worktree_id),
+                    &path_match.path,
+                    &path_match.path_prefix,
+                    false,
+                    &path_match.positions,
+                    path_match.is_dir,
+                    self.context_store.clone(),
+                    cx,
+                )
+                .into_any_element(),
+                if path_match.is_dir { "Folder" } else { "File" },
+            ),
+            UnifiedMatch::Thread(thread) => (
+                render_thread_context_entry(thread, self.context_store.clone(), cx)
+                    .into_any_element(),
+                "Thread",
+            ),
+        };
+
+        Some(
+            ListItem::new(ix)
+                .inset(true)
+                .toggle_state(selected)
+                .child(entry)
+                .end_slot(
+                    Label::new(badge)
+                        .size(LabelSize::XSmall)
+                        .color(Color::Muted),
+                ),
+        )
+    }
+}
+
+impl Drop for UnifiedContextPickerDelegate {
+    fn drop(&mut self) {
+        self.confirm_cancellation_flag
+            .store(true, atomic::Ordering::Relaxed);
+    }
+}
+
+/// Merges the path and thread matches into one list ordered by score. Paths come first on equal
+/// scores, so that with an empty query the recent files are followed by the recent threads.
+fn merge_matches(
+    paths: Vec<PathMatch>,
+    threads: Vec<(ThreadContextEntry, f64)>,
+) -> Vec<UnifiedMatch> {
+    let mut matches = paths
+        .into_iter()
+        .map(|path_match| (path_match.score, UnifiedMatch::Path(path_match)))
+        .chain(
+            threads
+                .into_iter()
+                .map(|(thread, score)| (score, UnifiedMatch::Thread(thread))),
+        )
+        .collect::<Vec<_>>();
+    // A stable sort keeps each source's own ordering among equal scores.
+    matches.sort_by(|(a, _), (b, _)| b.partial_cmp(a).unwrap_or(Ordering::Equal));
+    matches.into_iter().map(|(_, mat)| mat).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use std::path::Path;
+
+    fn path_match(path: &str, score: f64) -> PathMatch {
+        PathMatch {
+            score,
+            positions: Vec::new(),
+            worktree_id: 0,
+            path: Path::new(path).into(),
+            path_prefix: "root".into(),
+            distance_to_relative_ancestor: 0,
+            is_dir: false,
+        }
+    }
+
+    fn thread(summary: &str) -> ThreadContextEntry {
+        ThreadContextEntry {
+            id: ThreadId::new(),
+            summary: summary.to_string().into(),
+            message_count: 1,
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn describe(matches: &[UnifiedMatch]) -> Vec<String> {
+        matches
+            .iter()
+            .map(|mat| match mat {
+                UnifiedMatch::Path(path_match) => path_match.path.display().to_string(),
+                UnifiedMatch::Thread(thread) => format!("thread: {}", thread.summary),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_merge_matches_orders_by_score() {
+        let matches = merge_matches(
+            vec![
+                path_match("src/parser.rs", 0.9),
+                path_match("src/lib.rs", 0.2),
+            ],
+            vec![(thread("Fix parser"), 0.5), (thread("Parse docs"), 0.1)],
+        );
+        assert_eq!(
+            describe(&matches),
+            [
+                "src/parser.rs",
+                "thread: Fix parser",
+                "src/lib.rs",
+                "thread: Parse docs"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merge_matches_keeps_paths_first_on_ties() {
+        let matches = merge_matches(
+            vec![path_match("b.rs", 0.), path_match("a.rs", 0.)],
+            vec![(thread("Newest"), 0.), (thread("Oldest"), 0.)],
+        );
+        assert_eq!(
+            describe(&matches),
+            ["b.rs", "a.rs", "thread: Newest", "thread: Oldest"]
+        );
+    }
+}
@@ -1,23 +1,36 @@
+use std::io::Read as _;
+use std::ops::Range;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
 use anyhow::{anyhow, bail, Result};
 use collections::{BTreeMap, HashMap, HashSet};
-use futures::{self, future, Future, FutureExt};
-use gpui::{App, AppContext as _, AsyncApp, Context, Entity, SharedString, Task, WeakEntity};
-use language::Buffer;
-use project::{ProjectPath, Worktree};
-use rope::Rope;
-use text::BufferId;
+use fs::Fs;
+use futures::future::BoxFuture;
+use futures::stream::FuturesUnordered;
+use futures::{self, future, Future, FutureExt, StreamExt};
+use gpui::{
+    App, AppContext as _, AsyncApp, Context, Entity, EventEmitter, SharedString, Task, WeakEntity,
+};
+use language::{Buffer, PLAIN_TEXT};
+use project::{Project, ProjectPath, Worktree, WorktreeId};
+use rope::{Point, Rope};
+use text::{Bias, BufferId};
 use util::maybe;
+use util::paths::PathMatcher;
+use util::ResultExt as _;
 use workspace::Workspace;
 
 use crate::context::{
-    AssistantContext, ContextBuffer, ContextId, ContextSnapshot, DirectoryContext,
-    FetchedUrlContext, FileContext, ThreadContext,
+    estimate_token_count, AssistantContext, ContextBuffer, ContextId, ContextKind,
+    ContextProvenance, ContextSnapshot, DirectoryContext, DirectoryContextMode,
+    DirectoryContextOptions, FetchedUrlContext, FileContext, GitDiffContext, ThreadContext,
+    DEFAULT_DIRECTORY_CONTEXT_MAX_BYTES,
 };
 use crate::context_strip::SuggestedContext;
-use crate::thread::{Thread, ThreadId};
+use crate::thread::{Thread, ThreadId, ThreadTextOptions};
+use crate::thread_store::ThreadStore;
 
 pub struct ContextStore {
     workspace: WeakEntity<Workspace>,
@@ -25,9 +38,71 @@ pub struct ContextStore {
     // TODO: If an EntityId is used for all context types (like BufferId), can remove ContextId.
     next_context_id: ContextId,
     files: BTreeMap<BufferId, ContextId>,
-    directories: HashMap<PathBuf, ContextId>,
+    directories: HashMap<ProjectPath, ContextId>,
     threads: HashMap<ThreadId, ContextId>,
     fetched_urls: HashMap<String, ContextId>,
+    git_diffs: HashMap<ProjectPath, ContextId>,
+    pending_directories: Vec<PendingDirectory>,
+    /// Insertions and removals of context, most recent last, for undoing them in order. A refresh
+    /// clears it, so there's nothing to undo until the context changes again.
+    history: Vec<ContextChange>,
+    /// Context whose worktree or thread had disappeared when it was last refreshed. Its text is
+    /// kept from before the refresh.
+    stale: HashSet<ContextId>,
+}
+
+pub enum ContextStoreEvent {
+    /// New context was added, as opposed to existing context being refreshed in place.
+    ContextInserted { id: ContextId, kind: ContextKind },
+    /// Context was removed by undoing its insertion.
+    ContextRemoved { id: ContextId },
+    /// Several pieces of context were removed at once.
+    ContextsRemoved { ids: Vec<ContextId> },
+    /// Context removed at once was attached again by undoing its removal.
+    ContextsRestored { ids: Vec<ContextId> },
+}
+
+impl EventEmitter<ContextStoreEvent> for ContextStore {}
+
+/// A change to the attached context that [`ContextStore::undo_last_change`] takes back.
+enum ContextChange {
+    Inserted(ContextId),
+    /// Context removed together by [`ContextStore::remove_contexts`].
+    Removed(Vec<RemovedContext>),
+}
+
+/// What [`ContextStore::undo_last_change`] took back.
+#[derive(Debug, PartialEq, Eq)]
+pub enum UndoneContextChange {
+    /// The inserted context with this ID was removed.
+    Insertion(ContextId),
+    /// The context with these IDs was attached again where it was.
+    Removal(Vec<ContextId>),
+}
+
+/// Removed context along with what's needed to attach it again where it was.
+struct RemovedContext {
+    ix: usize,
+    context: AssistantContext,
+    key: Option<ContextKey>,
+    stale: bool,
+}
+
+/// The key context is looked up by, which depends on its kind.
+enum ContextKey {
+    File(BufferId),
+    Directory(ProjectPath),
+    Thread(ThreadId),
+    FetchedUrl(String),
+    GitDiff(ProjectPath),
+}
+
+/// Progress of a directory whose files are being read before it is added to the context.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingDirectory {
+    pub project_path: ProjectPath,
+    pub files_read: usize,
+    pub total_files: usize,
 }
 
 impl ContextStore {
@@ -40,6 +115,10 @@ impl ContextStore {
             directories: HashMap::default(),
             threads: HashMap::default(),
             fetched_urls: HashMap::default(),
+            git_diffs: HashMap::default(),
+            pending_directories: Vec::new(),
+            history: Vec::new(),
+            stale: HashSet::default(),
         }
     }
 
@@ -53,17 +132,129 @@ impl ContextStore {
         &self.context
     }
 
+    /// The attached context of `kind`, in the order it was attached.
+    pub fn entries_of_kind(
+        &self,
+        kind: ContextKind,
+    ) -> impl Iterator<Item = &AssistantContext> + '_ {
+        self.context
+            .iter()
+            .filter(move |context| context.kind() == kind)
+    }
+
+    /// The number of bytes of text all the attached context contributes to a message.
+    pub fn total_text_len(&self) -> usize {
+        self.context.iter().map(|context| context.text_len()).sum()
+    }
+
+    /// Roughly estimates the number of tokens in the attached context.
+    pub fn estimated_token_count(&self) -> usize {
+        estimate_token_count(self.total_text_len())
+    }
+
     pub fn clear(&mut self) {
         self.context.clear();
         self.files.clear();
         self.directories.clear();
         self.threads.clear();
         self.fetched_urls.clear();
+        self.git_diffs.clear();
+        self.history.clear();
+        self.stale.clear();
+    }
+
+    /// Re-reads all the attached directories and threads. Directories are walked again, so files
+    /// created since they were attached are picked up, and threads are serialized again to include
+    /// the messages sent since. Context whose worktree or thread has since disappeared is marked
+    /// stale and keeps its previous text, rather than failing the whole refresh.
+    pub fn refresh_all(
+        &mut self,
+        thread_store: Option<Entity<ThreadStore>>,
+        cx: &mut Context<Self>,
+    ) -> Task<()> {
+        // Undoing a change from before the refresh would be surprising, as the refresh is what the
+        // user did last, and removed context would be attached again with its text from before.
+        self.history.clear();
+
+        let project = self
+            .workspace
+            .upgrade()
+            .map(|workspace| workspace.read(cx).project().clone());
+        let saved_threads = thread_store.map(|thread_store| {
+            thread_store
+                .read(cx)
+                .threads()
+                .into_iter()
+                .map(|thread| thread.id)
+                .collect::<HashSet<_>>()
+        });
+
+        let mut tasks = Vec::new();
+        let mut threads = Vec::new();
+        for context in &self.context {
+            match context {
+                AssistantContext::Directory(directory_context) => {
+                    let id = directory_context.snapshot.id;
+                    let project_path = self
+                        .directories
+                        .iter()
+                        .find(|(_, context_id)| **context_id == id)
+                        .map(|(project_path, _)| project_path.clone());
+                    let (Some(project), Some(project_path)) = (project.clone(), project_path)
+                    else {
+                        self.stale.insert(id);
+                        continue;
+                    };
+
+                    tasks.push(self.reread_directory(directory_context, project, project_path, cx));
+                }
+                AssistantContext::Thread(thread_context) => {
+                    threads.push((
+                        thread_context.id,
+                        thread_context.thread.clone(),
+                        thread_context.text_options,
+                    ));
+                }
+                AssistantContext::File(_)
+                | AssistantContext::FetchedUrl(_)
+                | AssistantContext::GitDiff(_) => {}
+            }
+        }
+
+        for (id, thread, text_options) in threads {
+            let thread_id = thread.read(cx).id().clone();
+            // The thread stays in memory after it's deleted from the history, so it can still be
+            // serialized, but it's marked stale as it no longer exists for the user. Empty threads
+            // haven't been saved yet.
+            let deleted = !thread.read(cx).is_empty()
+                && saved_threads
+                    .as_ref()
+                    .map_or(false, |saved_threads| !saved_threads.contains(&thread_id));
+            if deleted {
+                self.stale.insert(id);
+            } else {
+                self.stale.remove(&id);
+            }
+            self.refresh_thread(id, thread, text_options, cx);
+        }
+        cx.notify();
+
+        cx.spawn(|_, _| async move {
+            future::join_all(tasks).await;
+        })
+    }
+
+    /// Whether this context's worktree or thread had disappeared when it was last refreshed.
+    pub fn is_stale(&self, id: ContextId) -> bool {
+        self.stale.contains(&id)
     }
 
+    /// Attaches the file at `project_path`, or only the zero-based rows in `line_range` of it.
+    /// A range starting past the end of the file is ignored, and the whole file is attached.
     pub fn add_file_from_path(
         &mut self,
         project_path: ProjectPath,
+        line_range: Option<Range<u32>>,
         cx: &mut Context<Self>,
     ) -> Task<Result<()>> {
         let workspace = self.workspace.clone();
@@ -83,13 +274,31 @@ impl ContextStore {
             let buffer_entity = open_buffer_task.await?;
             let buffer_id = this.update(&mut cx, |_, cx| buffer_entity.read(cx).remote_id())?;
 
-            let already_included = this.update(&mut cx, |this, _cx| {
-                match this.will_include_buffer(buffer_id, &project_path.path) {
+            let line_range = this.update(&mut cx, |_, cx| {
+                let buffer = buffer_entity.read(cx);
+                line_range
+                    .and_then(|line_range| clamp_line_range(line_range, last_row(buffer.as_rope())))
+            })?;
+
+            // A file attached with a different line range is replaced rather than toggled off.
+            let mut replaced_id = None;
+            let already_included = this.update(&mut cx, |this, cx| {
+                match this.will_include_buffer(buffer_id, &project_path) {
                     Some(FileInclusion::Direct(context_id)) => {
-                        this.remove_context(context_id);
+                        if this.file_line_range(context_id) == Some(&line_range) {
+                            this.remove_context(context_id, cx);
+                            true
+                        } else {
+                            replaced_id = Some(context_id);
+                            false
+                        }
+                    }
+                    // Rather than attaching the file a second time, bring the directory's copy of
+                    // it up to date.
+                    Some(FileInclusion::InDirectory(directory)) => {
+                        this.refresh_directory(project_path.worktree_id, &directory, cx);
                         true
                     }
-                    Some(FileInclusion::InDirectory(_)) => true,
                     None => false,
                 }
             })?;
@@ -104,14 +313,27 @@ impl ContextStore {
                     project_path.path.clone(),
                     buffer_entity,
                     buffer,
-                    cx.to_async(),
+                    None,
+                    line_range.clone(),
+                    cx,
                 )
             })?;
 
             let text = text_task.await;
 
-            this.update(&mut cx, |this, _cx| {
-                this.insert_file(make_context_buffer(buffer_info, text));
+            this.update(&mut cx, |this, cx| {
+                let context_buffer = make_context_buffer(buffer_info, text);
+                match replaced_id {
+                    Some(id) => {
+                        this.replace_context(AssistantContext::File(FileContext {
+                            id,
+                            context_buffer,
+                            line_range,
+                        }));
+                        cx.notify();
+                    }
+                    None => this.insert_file(context_buffer, line_range, cx),
+                }
             })?;
 
             anyhow::Ok(())
@@ -133,162 +355,283 @@ impl ContextStore {
                     file.path().clone(),
                     buffer_entity,
                     buffer,
-                    cx.to_async(),
+                    None,
+                    None,
+                    cx,
                 ))
             })??;
 
             let text = text_task.await;
 
-            this.update(&mut cx, |this, _cx| {
-                this.insert_file(make_context_buffer(buffer_info, text))
+            this.update(&mut cx, |this, cx| {
+                this.insert_file(make_context_buffer(buffer_info, text), None, cx)
             })?;
 
             anyhow::Ok(())
         })
     }
 
-    fn insert_file(&mut self, context_buffer: ContextBuffer) {
+    fn insert_file(
+        &mut self,
+        context_buffer: ContextBuffer,
+        line_range: Option<Range<u32>>,
+        cx: &mut Context<Self>,
+    ) {
         let id = self.next_context_id.post_inc();
         self.files.insert(context_buffer.id, id);
-        self.context
-            .push(AssistantContext::File(FileContext { id, context_buffer }));
+        self.push_context(
+            AssistantContext::File(FileContext {
+                id,
+                context_buffer,
+                line_range,
+            }),
+            cx,
+        );
     }
 
-    pub fn add_directory(
+    /// Attaches a directory the way `options` describe: its files' contents in path order until
+    /// the byte budget is reached, or only their paths and sizes. Attaching a directory that is
+    /// already attached removes it instead. Nothing is added if `cancellation_flag` is set before
+    /// the files have been read.
+    ///
+    /// The context pickers attach directories through this, and so can callers outside the
+    /// assistant, such as commands, usually with [`DirectoryContextOptions::default`].
+    pub fn attach_directory(
         &mut self,
         project_path: ProjectPath,
+        options: DirectoryContextOptions,
+        cancellation_flag: Arc<AtomicBool>,
         cx: &mut Context<Self>,
     ) -> Task<Result<()>> {
-        let workspace = self.workspace.clone();
-        let Some(project) = workspace
+        let Some(project) = self
+            .workspace
             .upgrade()
             .map(|workspace| workspace.read(cx).project().clone())
         else {
             return Task::ready(Err(anyhow!("failed to read project")));
         };
 
-        let already_included = if let Some(context_id) = self.includes_directory(&project_path.path)
-        {
-            self.remove_context(context_id);
-            true
-        } else {
-            false
-        };
-        if already_included {
+        if let Some(context_id) = self.includes_directory(&project_path) {
+            self.remove_context(context_id, cx);
             return Task::ready(Ok(()));
         }
 
-        let worktree_id = project_path.worktree_id;
+        let id = self.next_context_id.post_inc();
         cx.spawn(|this, mut cx| async move {
-            let worktree = project.update(&mut cx, |project, cx| {
-                project
-                    .worktree_for_id(worktree_id, cx)
-                    .ok_or_else(|| anyhow!("no worktree found for {worktree_id:?}"))
-            })??;
-
-            let files = worktree.update(&mut cx, |worktree, _cx| {
-                collect_files_in_path(worktree, &project_path.path)
-            })?;
-
-            let open_buffers_task = project.update(&mut cx, |project, cx| {
-                let tasks = files.iter().map(|file_path| {
-                    project.open_buffer(
-                        ProjectPath {
-                            worktree_id,
-                            path: file_path.clone(),
-                        },
-                        cx,
-                    )
-                });
-                future::join_all(tasks)
-            })?;
-
-            let buffers = open_buffers_task.await;
-
-            let mut buffer_infos = Vec::new();
-            let mut text_tasks = Vec::new();
-            this.update(&mut cx, |_, cx| {
-                for (path, buffer_entity) in files.into_iter().zip(buffers) {
-                    // Skip all binary files and other non-UTF8 files
-                    if let Ok(buffer_entity) = buffer_entity {
-                        let buffer = buffer_entity.read(cx);
-                        let (buffer_info, text_task) = collect_buffer_info_and_text(
-                            path,
-                            buffer_entity,
-                            buffer,
-                            cx.to_async(),
-                        );
-                        buffer_infos.push(buffer_info);
-                        text_tasks.push(text_task);
-                    }
-                }
-                anyhow::Ok(())
-            })??;
-
-            let buffer_texts = future::join_all(text_tasks).await;
-            let context_buffers = buffer_infos
-                .into_iter()
-                .zip(buffer_texts)
-                .map(|(info, text)| make_context_buffer(info, text))
-                .collect::<Vec<_>>();
-
-            if context_buffers.is_empty() {
-                bail!("No text files found in {}", &project_path.path.display());
-            }
-
-            this.update(&mut cx, |this, _| {
-                this.insert_directory(&project_path.path, context_buffers);
-            })?;
+            let Some(directory_context) = read_directory_context(
+                Some(&this),
+                &project,
+                &project_path,
+                id,
+                options,
+                &cancellation_flag,
+                &mut cx,
+            )
+            .await?
+            else {
+                return Ok(());
+            };
+
+            // The store may also have been released while the files were read, e.g. when the
+            // panel was closed.
+            let Some(this) = this
+                .upgrade()
+                .filter(|_| !cancellation_flag.load(Ordering::Relaxed))
+            else {
+                return Ok(());
+            };
+
+            this.update(&mut cx, |this, cx| {
+                this.directories.insert(project_path, id);
+                this.push_context(AssistantContext::Directory(directory_context), cx);
+            })
+        })
+    }
 
-            anyhow::Ok(())
+    /// Opens a saved thread and attaches its text the way `text_options` describe. The context
+    /// pickers attach threads through this, and so can callers outside the assistant, such as
+    /// commands, usually with [`ThreadTextOptions::default`].
+    pub fn attach_thread(
+        &mut self,
+        thread_store: &Entity<ThreadStore>,
+        thread_id: &ThreadId,
+        text_options: ThreadTextOptions,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<()>> {
+        let open_thread_task = thread_store.update(cx, |thread_store, cx| {
+            thread_store.open_thread(thread_id, cx)
+        });
+        cx.spawn(|this, mut cx| async move {
+            let thread = open_thread_task.await?;
+            this.update(&mut cx, |this, cx| {
+                this.add_thread(thread, text_options, cx)
+            })
         })
     }
 
-    fn insert_directory(&mut self, path: &Path, context_buffers: Vec<ContextBuffer>) {
-        let id = self.next_context_id.post_inc();
-        self.directories.insert(path.to_path_buf(), id);
+    /// Directories whose files are still being read, in the order they were attached.
+    pub fn pending_directories(&self) -> &[PendingDirectory] {
+        &self.pending_directories
+    }
 
-        self.context
-            .push(AssistantContext::Directory(DirectoryContext::new(
-                id,
-                path,
-                context_buffers,
-            )));
+    fn pending_directory_mut(
+        &mut self,
+        project_path: &ProjectPath,
+    ) -> Option<&mut PendingDirectory> {
+        self.pending_directories
+            .iter_mut()
+            .find(|pending| pending.project_path == *project_path)
     }
 
-    pub fn add_thread(&mut self, thread: Entity<Thread>, cx: &mut Context<Self>) {
+    /// Adds the thread to the context. If the thread is already included, its text is refreshed
+    /// instead, since threads grow over time.
+    pub fn add_thread(
+        &mut self,
+        thread: Entity<Thread>,
+        text_options: ThreadTextOptions,
+        cx: &mut Context<Self>,
+    ) {
         if let Some(context_id) = self.includes_thread(&thread.read(cx).id()) {
-            self.remove_context(context_id);
+            self.refresh_thread(context_id, thread, text_options, cx);
         } else {
-            self.insert_thread(thread, cx);
+            self.insert_thread(thread, text_options, cx);
         }
     }
 
-    fn insert_thread(&mut self, thread: Entity<Thread>, cx: &App) {
+    fn refresh_thread(
+        &mut self,
+        id: ContextId,
+        thread: Entity<Thread>,
+        text_options: ThreadTextOptions,
+        cx: &App,
+    ) {
+        let text = thread.read(cx).to_context_text(text_options).into();
+        self.replace_context(AssistantContext::Thread(ThreadContext {
+            id,
+            thread,
+            text_options,
+            text,
+        }));
+    }
+
+    fn insert_thread(
+        &mut self,
+        thread: Entity<Thread>,
+        text_options: ThreadTextOptions,
+        cx: &mut Context<Self>,
+    ) {
         let id = self.next_context_id.post_inc();
-        let text = thread.read(cx).text().into();
+        let text = thread.read(cx).to_context_text(text_options).into();
 
-        self.threads.insert(thread.read(cx).id().clone(), id);
-        self.context
-            .push(AssistantContext::Thread(ThreadContext { id, thread, text }));
+        let thread_id = thread.read(cx).id().clone();
+        self.threads.insert(thread_id, id);
+        self.push_context(
+            AssistantContext::Thread(ThreadContext {
+                id,
+                thread,
+                text_options,
+                text,
+            }),
+            cx,
+        );
     }
 
-    pub fn add_fetched_url(&mut self, url: String, text: impl Into<SharedString>) {
+    pub fn add_fetched_url(
+        &mut self,
+        url: String,
+        title: Option<SharedString>,
+        text: &str,
+        cx: &mut Context<Self>,
+    ) {
         if self.includes_url(&url).is_none() {
-            self.insert_fetched_url(url, text);
+            self.insert_fetched_url(url, title, text, cx);
         }
     }
 
-    fn insert_fetched_url(&mut self, url: String, text: impl Into<SharedString>) {
+    fn insert_fetched_url(
+        &mut self,
+        url: String,
+        title: Option<SharedString>,
+        text: &str,
+        cx: &mut Context<Self>,
+    ) {
         let id = self.next_context_id.post_inc();
 
         self.fetched_urls.insert(url.clone(), id);
-        self.context
-            .push(AssistantContext::FetchedUrl(FetchedUrlContext {
+        self.push_context(
+            AssistantContext::FetchedUrl(FetchedUrlContext::new(
+                id,
+                url.into(),
+                title,
+                text,
+                DEFAULT_DIRECTORY_CONTEXT_MAX_BYTES,
+            )),
+            cx,
+        );
+    }
+
+    /// Attaches the uncommitted changes to the file at `project_path`, given as a unified diff.
+    pub fn add_git_diff(
+        &mut self,
+        project_path: ProjectPath,
+        full_path: Arc<Path>,
+        diff: &str,
+        cx: &mut Context<Self>,
+    ) {
+        if self.includes_git_diff(&project_path).is_some() {
+            return;
+        }
+
+        let id = self.next_context_id.post_inc();
+        let text = to_fenced_codeblock_with_header(
+            Some("diff"),
+            &full_path.to_string_lossy(),
+            Rope::from(diff),
+        );
+        self.git_diffs.insert(project_path.clone(), id);
+        self.push_context(
+            AssistantContext::GitDiff(GitDiffContext {
                 id,
-                url: url.into(),
-                text: text.into(),
-            }));
+                project_path,
+                full_path,
+                text,
+            }),
+            cx,
+        );
+    }
+
+    fn push_context(&mut self, context: AssistantContext, cx: &mut Context<Self>) {
+        let id = context.id();
+        let kind = context.kind();
+        self.context.push(context);
+        self.history.push(ContextChange::Inserted(id));
+        cx.emit(ContextStoreEvent::ContextInserted { id, kind });
+    }
+
+    /// Where the context with the given ID came from. Unlike its snapshot's name, this identifies
+    /// the directory's worktree, or the thread, so the context can be found again.
+    pub fn provenance(&self, id: ContextId, cx: &App) -> Option<ContextProvenance> {
+        let context = self.context.iter().find(|context| context.id() == id)?;
+        match context {
+            AssistantContext::File(file_context) => {
+                let file = file_context.context_buffer.buffer.read(cx).file()?;
+                Some(ContextProvenance::File(ProjectPath {
+                    worktree_id: file.worktree_id(cx),
+                    path: file.path().clone(),
+                }))
+            }
+            AssistantContext::Directory(_) => key_for(&self.directories, id)
+                .cloned()
+                .map(ContextProvenance::Directory),
+            AssistantContext::FetchedUrl(_) => key_for(&self.fetched_urls, id)
+                .map(|url| ContextProvenance::FetchedUrl(url.clone().into())),
+            AssistantContext::Thread(_) => key_for(&self.threads, id)
+                .cloned()
+                .map(ContextProvenance::Thread),
+            AssistantContext::GitDiff(_) => key_for(&self.git_diffs, id)
+                .cloned()
+                .map(ContextProvenance::GitDiff),
+        }
     }
 
     pub fn accept_suggested_context(
@@ -308,54 +651,248 @@ impl ContextStore {
             }
             SuggestedContext::Thread { thread, name: _ } => {
                 if let Some(thread) = thread.upgrade() {
-                    self.insert_thread(thread, cx);
+                    self.insert_thread(thread, ThreadTextOptions::default(), cx);
                 };
             }
         }
         Task::ready(Ok(()))
     }
 
-    pub fn remove_context(&mut self, id: ContextId) {
-        let Some(ix) = self.context.iter().position(|context| context.id() == id) else {
+    pub fn remove_context(&mut self, id: ContextId, cx: &mut Context<Self>) {
+        // Context removed on its own can't be attached again by undoing, so there's nothing left
+        // to undo about its insertion.
+        self.history.retain(
+            |change| !matches!(change, ContextChange::Inserted(context_id) if *context_id == id),
+        );
+        if let Some(removed) = self.take_context(id) {
+            if let Some(file_path) = full_file_path(&removed.context, cx) {
+                self.reread_directories_containing(&file_path, cx);
+            }
+        }
+    }
+
+    /// Removes all the given context at once, as a single removal that
+    /// [`Self::undo_last_change`] undoes.
+    pub fn remove_contexts(&mut self, ids: &[ContextId], cx: &mut Context<Self>) {
+        // Removing from the back keeps the recorded indices those from before the removal.
+        let mut ixs = self
+            .context
+            .iter()
+            .enumerate()
+            .filter(|(_, context)| ids.contains(&context.id()))
+            .map(|(ix, _)| ix)
+            .collect::<Vec<_>>();
+        if ixs.is_empty() {
             return;
-        };
+        }
+        ixs.reverse();
+
+        let removed = ixs
+            .into_iter()
+            .filter_map(|ix| {
+                let id = self.context[ix].id();
+                self.take_context(id)
+            })
+            .collect::<Vec<_>>();
+        for file_path in removed
+            .iter()
+            .filter_map(|removed| full_file_path(&removed.context, cx))
+        {
+            self.reread_directories_containing(&file_path, cx);
+        }
+        let ids = removed
+            .iter()
+            .rev()
+            .map(|removed| removed.context.id())
+            .collect();
+        self.history.push(ContextChange::Removed(removed));
+        cx.emit(ContextStoreEvent::ContextsRemoved { ids });
+        cx.notify();
+    }
+
+    /// Whether there is a change that [`Self::undo_last_change`] would undo.
+    pub fn can_undo_last_change(&self) -> bool {
+        self.history
+            .iter()
+            .any(|change| self.can_undo_change(change))
+    }
+
+    /// Takes back the most recent insertion or removal of context that can still be taken back:
+    /// inserted context is removed, and context removed together is attached again where it was.
+    /// Context removed on its own since it was inserted, or attached again since it was removed,
+    /// is skipped.
+    pub fn undo_last_change(&mut self, cx: &mut Context<Self>) -> Option<UndoneContextChange> {
+        while let Some(change) = self.history.pop() {
+            if !self.can_undo_change(&change) {
+                continue;
+            }
+            let undone = match change {
+                ContextChange::Inserted(id) => {
+                    if let Some(removed) = self.take_context(id) {
+                        if let Some(file_path) = full_file_path(&removed.context, cx) {
+                            self.reread_directories_containing(&file_path, cx);
+                        }
+                    }
+                    cx.emit(ContextStoreEvent::ContextRemoved { id });
+                    UndoneContextChange::Insertion(id)
+                }
+                ContextChange::Removed(removed) => {
+                    let ids = self.restore_contexts(removed, cx);
+                    cx.emit(ContextStoreEvent::ContextsRestored { ids: ids.clone() });
+                    UndoneContextChange::Removal(ids)
+                }
+            };
+            cx.notify();
+            return Some(undone);
+        }
+        None
+    }
+
+    fn can_undo_change(&self, change: &ContextChange) -> bool {
+        match change {
+            ContextChange::Inserted(id) => self.context.iter().any(|context| context.id() == *id),
+            ContextChange::Removed(removed) => removed
+                .iter()
+                .any(|removed| !self.is_attached(removed.key.as_ref())),
+        }
+    }
+
+    /// Whether context with the given lookup key is attached.
+    fn is_attached(&self, key: Option<&ContextKey>) -> bool {
+        match key {
+            Some(ContextKey::File(buffer_id)) => self.files.contains_key(buffer_id),
+            Some(ContextKey::Directory(project_path)) => {
+                self.directories.contains_key(project_path)
+            }
+            Some(ContextKey::Thread(thread_id)) => self.threads.contains_key(thread_id),
+            Some(ContextKey::FetchedUrl(url)) => self.fetched_urls.contains_key(url),
+            Some(ContextKey::GitDiff(project_path)) => self.git_diffs.contains_key(project_path),
+            None => false,
+        }
+    }
+
+    /// Attaches removed context again where it was, returning the IDs of the context that wasn't
+    /// attached again since.
+    fn restore_contexts(
+        &mut self,
+        removed: Vec<RemovedContext>,
+        cx: &mut Context<Self>,
+    ) -> Vec<ContextId> {
+        let mut ids = Vec::new();
+        let mut restored_file_paths = Vec::new();
+        for removed in removed.into_iter().rev() {
+            if self.is_attached(removed.key.as_ref()) {
+                continue;
+            }
 
-        match self.context.remove(ix) {
-            AssistantContext::File(_) => {
-                self.files.retain(|_, context_id| *context_id != id);
+            let id = removed.context.id();
+            match removed.key {
+                Some(ContextKey::File(buffer_id)) => {
+                    self.files.insert(buffer_id, id);
+                }
+                Some(ContextKey::Directory(project_path)) => {
+                    self.directories.insert(project_path, id);
+                }
+                Some(ContextKey::Thread(thread_id)) => {
+                    self.threads.insert(thread_id, id);
+                }
+                Some(ContextKey::FetchedUrl(url)) => {
+                    self.fetched_urls.insert(url, id);
+                }
+                Some(ContextKey::GitDiff(project_path)) => {
+                    self.git_diffs.insert(project_path, id);
+                }
+                None => {}
+            }
+            if removed.stale {
+                self.stale.insert(id);
             }
+            if let Some(file_path) = full_file_path(&removed.context, cx) {
+                restored_file_paths.push(file_path);
+            }
+            let ix = removed.ix.min(self.context.len());
+            self.context.insert(ix, removed.context);
+            ids.push(id);
+        }
+        for file_path in restored_file_paths {
+            self.reread_directories_containing(&file_path, cx);
+        }
+        ids
+    }
+
+    /// Removes the context with the given ID, returning it along with its index and lookup key.
+    fn take_context(&mut self, id: ContextId) -> Option<RemovedContext> {
+        let ix = self.context.iter().position(|context| context.id() == id)?;
+        let stale = self.stale.remove(&id);
+
+        let context = self.context.remove(ix);
+        let key = match &context {
+            AssistantContext::File(_) => key_for(&self.files, id).copied().map(|buffer_id| {
+                self.files.remove(&buffer_id);
+                ContextKey::File(buffer_id)
+            }),
             AssistantContext::Directory(_) => {
-                self.directories.retain(|_, context_id| *context_id != id);
+                key_for(&self.directories, id).cloned().map(|project_path| {
+                    self.directories.remove(&project_path);
+                    ContextKey::Directory(project_path)
+                })
             }
             AssistantContext::FetchedUrl(_) => {
-                self.fetched_urls.retain(|_, context_id| *context_id != id);
+                key_for(&self.fetched_urls, id).cloned().map(|url| {
+                    self.fetched_urls.remove(&url);
+                    ContextKey::FetchedUrl(url)
+                })
             }
-            AssistantContext::Thread(_) => {
-                self.threads.retain(|_, context_id| *context_id != id);
+            AssistantContext::Thread(_) => key_for(&self.threads, id).cloned().map(|thread_id| {
+                self.threads.remove(&thread_id);
+                ContextKey::Thread(thread_id)
+            }),
+            AssistantContext::GitDiff(_) => {
+                key_for(&self.git_diffs, id).cloned().map(|project_path| {
+                    self.git_diffs.remove(&project_path);
+                    ContextKey::GitDiff(project_path)
+                })
             }
-        }
+        };
+
+        Some(RemovedContext {
+            ix,
+            context,
+            key,
+            stale,
+        })
     }
 
     /// Returns whether the buffer is already included directly in the context, or if it will be
     /// included in the context via a directory. Directory inclusion is based on paths rather than
     /// buffer IDs as the directory will be re-scanned.
-    pub fn will_include_buffer(&self, buffer_id: BufferId, path: &Path) -> Option<FileInclusion> {
+    pub fn will_include_buffer(
+        &self,
+        buffer_id: BufferId,
+        project_path: &ProjectPath,
+    ) -> Option<FileInclusion> {
         if let Some(context_id) = self.files.get(&buffer_id) {
             return Some(FileInclusion::Direct(*context_id));
         }
 
-        self.will_include_file_path_via_directory(path)
+        self.will_include_file_path_via_directory(project_path)
     }
 
     /// Returns whether this file path is already included directly in the context, or if it will be
     /// included in the context via a directory.
-    pub fn will_include_file_path(&self, path: &Path, cx: &App) -> Option<FileInclusion> {
+    pub fn will_include_file_path(
+        &self,
+        project_path: &ProjectPath,
+        cx: &App,
+    ) -> Option<FileInclusion> {
         if !self.files.is_empty() {
             let found_file_context = self.context.iter().find(|context| match &context {
                 AssistantContext::File(file_context) => {
                     let buffer = file_context.context_buffer.buffer.read(cx);
                     if let Some(file_path) = buffer_path_log_err(buffer) {
-                        *file_path == *path
+                        *file_path == *project_path.path
+                            && buffer.file().map(|file| file.worktree_id(cx))
+                                == Some(project_path.worktree_id)
                     } else {
                         false
                     }
@@ -367,27 +904,56 @@ impl ContextStore {
             }
         }
 
-        self.will_include_file_path_via_directory(path)
+        self.will_include_file_path_via_directory(project_path)
     }
 
-    fn will_include_file_path_via_directory(&self, path: &Path) -> Option<FileInclusion> {
+    fn will_include_file_path_via_directory(
+        &self,
+        project_path: &ProjectPath,
+    ) -> Option<FileInclusion> {
         if self.directories.is_empty() {
             return None;
         }
 
-        let mut buf = path.to_path_buf();
-
-        while buf.pop() {
-            if let Some(_) = self.directories.get(&buf) {
-                return Some(FileInclusion::InDirectory(buf));
+        let mut ancestor = project_path.clone();
+        let mut is_parent = true;
+
+        while let Some(parent) = ancestor.path.parent() {
+            ancestor.path = parent.into();
+            if let Some(directory_context) = self
+                .directories
+                .get(&ancestor)
+                .and_then(|context_id| self.directory_context(*context_id))
+            {
+                // Directories attached without recursion only include their own files, and file
+                // lists don't include any contents.
+                if (is_parent || directory_context.recursive)
+                    && directory_context.mode == DirectoryContextMode::Contents
+                {
+                    return Some(FileInclusion::InDirectory(ancestor.path.to_path_buf()));
+                }
             }
+            is_parent = false;
         }
 
         None
     }
 
-    pub fn includes_directory(&self, path: &Path) -> Option<ContextId> {
-        self.directories.get(path).copied()
+    fn directory_context(&self, context_id: ContextId) -> Option<&DirectoryContext> {
+        self.context.iter().find_map(|context| match context {
+            AssistantContext::Directory(directory_context)
+                if directory_context.snapshot.id == context_id =>
+            {
+                Some(directory_context)
+            }
+            _ => None,
+        })
+    }
+
+    /// Returns the context for the directory at this path in this worktree, if any. Directories
+    /// with the same relative path in different worktrees are distinct.
+    pub fn includes_directory(&self, project_path: &ProjectPath) -> Option<ContextId> {
+        self.directories.get(project_path).copied()
     }
 
     pub fn includes_thread(&self, thread_id: &ThreadId) -> Option<ContextId> {
@@ -398,6 +964,18 @@ impl ContextStore {
         self.fetched_urls.get(url).copied()
     }
 
+    pub fn includes_git_diff(&self, project_path: &ProjectPath) -> Option<ContextId> {
+        self.git_diffs.get(project_path).copied()
+    }
+
+    /// The line range of an attached file, which is `None` when the whole file is attached.
+    fn file_line_range(&self, id: ContextId) -> Option<&Option<Range<u32>>> {
+        self.context.iter().find_map(|context| match context {
+            AssistantContext::File(file) if file.id == id => Some(&file.line_range),
+            _ => None,
+        })
+    }
+
     /// Replaces the context that matches the ID of the new context, if any match.
     fn replace_context(&mut self, new_context: AssistantContext) {
         let id = new_context.id();
@@ -409,6 +987,120 @@ impl ContextStore {
         }
     }
 
+    /// Reads the files of an attached directory again, so that the files attached on their own
+    /// are skipped and the rest included as they are now. The directory is marked stale if it
+    /// can no longer be read.
+    fn reread_directory(
+        &self,
+        directory_context: &DirectoryContext,
+        project: Entity<Project>,
+        project_path: ProjectPath,
+        cx: &mut Context<Self>,
+    ) -> Task<()> {
+        let id = directory_context.snapshot.id;
+        let options = directory_context.options();
+        cx.spawn(|this, mut cx| async move {
+            let result = read_directory_context(
+                Some(&this),
+                &project,
+                &project_path,
+                id,
+                options,
+                &AtomicBool::new(false),
+                &mut cx,
+            )
+            .await;
+
+            this.update(&mut cx, |this, cx| {
+                match result {
+                    Ok(Some(directory_context)) => {
+                        this.stale.remove(&id);
+                        this.replace_context(AssistantContext::Directory(directory_context));
+                    }
+                    Ok(None) => {}
+                    Err(error) => {
+                        log::info!(
+                            "marking {} context stale: {error}",
+                            project_path.path.display()
+                        );
+                        this.stale.insert(id);
+                    }
+                }
+                cx.notify();
+            })
+            .ok();
+        })
+    }
+
+    /// Reads the attached directories containing a file again after the file was attached on its
+    /// own or removed, so that its text is either in its own context or in theirs, never both.
+    fn reread_directories_containing(&self, file_path: &ProjectPath, cx: &mut Context<Self>) {
+        let Some(project) = self
+            .workspace
+            .upgrade()
+            .map(|workspace| workspace.read(cx).project().clone())
+        else {
+            return;
+        };
+
+        for context in &self.context {
+            let AssistantContext::Directory(directory_context) = context else {
+                continue;
+            };
+            if directory_context.mode != DirectoryContextMode::Contents {
+                continue;
+            }
+            let Some(project_path) = key_for(&self.directories, directory_context.snapshot.id)
+            else {
+                continue;
+            };
+            if project_path.worktree_id == file_path.worktree_id
+                && file_path.path.starts_with(&project_path.path)
+            {
+                self.reread_directory(directory_context, project.clone(), project_path.clone(), cx)
+                    .detach();
+            }
+        }
+    }
+
+    /// Returns the paths of the files attached on their own in full, rather than as a line range
+    /// or as part of a directory. Attaching a directory skips these files.
+    pub fn attached_file_paths(&self, cx: &App) -> HashSet<ProjectPath> {
+        self.context
+            .iter()
+            .filter_map(|context| full_file_path(context, cx))
+            .collect()
+    }
+
+    /// Re-reads the files of an attached directory that changed since it was attached.
+    fn refresh_directory(
+        &mut self,
+        worktree_id: WorktreeId,
+        directory: &Path,
+        cx: &mut Context<Self>,
+    ) {
+        let project_path = ProjectPath {
+            worktree_id,
+            path: directory.into(),
+        };
+        let Some(context_id) = self.directories.get(&project_path).copied() else {
+            return;
+        };
+
+        let context_store = cx.entity();
+        let refresh_task = self.context.iter().find_map(|context| match context {
+            AssistantContext::Directory(directory_context)
+                if directory_context.snapshot.id == context_id =>
+            {
+                refresh_directory_text(context_store.clone(), directory_context, cx)
+            }
+            _ => None,
+        });
+        if let Some(refresh_task) = refresh_task {
+            refresh_task.detach();
+        }
+    }
+
     pub fn file_paths(&self, cx: &App) -> HashSet<PathBuf> {
         self.context
             .iter()
@@ -419,7 +1111,8 @@ impl ContextStore {
                 }
                 AssistantContext::Directory(_)
                 | AssistantContext::FetchedUrl(_)
-                | AssistantContext::Thread(_) => None,
+                | AssistantContext::Thread(_)
+                | AssistantContext::GitDiff(_) => None,
             })
             .collect()
     }
@@ -429,6 +1122,30 @@ impl ContextStore {
     }
 }
 
+/// The path of a file attached on its own in full, rather than as a line range.
+fn full_file_path(context: &AssistantContext, cx: &App) -> Option<ProjectPath> {
+    match context {
+        AssistantContext::File(file) if file.line_range.is_none() => {
+            let file = file.context_buffer.buffer.read(cx).file()?;
+            Some(ProjectPath {
+                worktree_id: file.worktree_id(cx),
+                path: file.path().clone(),
+            })
+        }
+        _ => None,
+    }
+}
+
+/// The key of the context with the given ID in one of the maps of context by key.
+fn key_for<'a, K: 'a>(
+    map: impl IntoIterator<Item = (&'a K, &'a ContextId)>,
+    id: ContextId,
+) -> Option<&'a K> {
+    map.into_iter()
+        .find(|(_, context_id)| **context_id == id)
+        .map(|(key, _)| key)
+}
+
 pub enum FileInclusion {
     Direct(ContextId),
     InDirectory(PathBuf),
@@ -454,7 +1171,9 @@ fn collect_buffer_info_and_text(
     path: Arc<Path>,
     buffer_entity: Entity<Buffer>,
     buffer: &Buffer,
-    cx: AsyncApp,
+    max_bytes: Option<usize>,
+    line_range: Option<Range<u32>>,
+    cx: &App,
 ) -> (BufferInfo, Task<SharedString>) {
     let buffer_info = BufferInfo {
         id: buffer.remote_id(),
@@ -463,37 +1182,179 @@ fn collect_buffer_info_and_text(
     };
     // Important to collect version at the same time as content so that staleness logic is correct.
     let content = buffer.as_rope().clone();
-    let text_task = cx.background_spawn(async move { to_fenced_codeblock(&path, content) });
+    let language = code_fence_language(buffer, cx);
+    let text_task = cx.background_executor().spawn(async move {
+        let language = language.await;
+        buffer_text_for_context(&path, language.as_deref(), content, max_bytes, line_range)
+    });
     (buffer_info, text_task)
 }
 
-pub fn buffer_path_log_err(buffer: &Buffer) -> Option<Arc<Path>> {
-    if let Some(file) = buffer.file() {
-        Some(file.path().clone())
-    } else {
-        log::error!("Buffer that had a path unexpectedly no longer has a path.");
+/// The language named after the opening fence of a buffer's code block. It is the buffer's
+/// language, or when the buffer hasn't been assigned one yet, the language the project's registry
+/// picks for its path. `None` for plain text and files no language matches.
+fn code_fence_language(buffer: &Buffer, cx: &App) -> BoxFuture<'static, Option<Arc<str>>> {
+    let language = match buffer.language() {
+        Some(language) => future::ready(Some(language.clone())).boxed(),
+        None => {
+            let loading_language = maybe!({
+                let registry = buffer.language_registry()?;
+                let language =
+                    registry.language_for_file(buffer.file()?, Some(buffer.as_rope()), cx)?;
+                Some(registry.load_language(&language))
+            });
+            match loading_language {
+                Some(loading_language) => loading_language
+                    .map(|language| language.ok()?.log_err())
+                    .boxed(),
+                None => future::ready(None).boxed(),
+            }
+        }
+    };
+    language
+        .map(|language| {
+            let language = language?;
+            (language.name() != PLAIN_TEXT.name()).then(|| language.code_fence_block_name())
+        })
+        .boxed()
+}
+
+/// Number of leading bytes inspected when deciding whether a buffer holds binary content.
+const BINARY_DETECTION_LEN: usize = 8192;
+
+/// Returns the text used to represent a buffer's content in the context.
+///
+/// Binary content (images, compiled artifacts, ...) would only add noise for the model, so it is
+/// replaced by a short placeholder mentioning its size. Text beyond `max_bytes` is cut off, so
+/// that a single large generated file can't crowd out the rest of a directory. When `line_range`
+/// is given, only those zero-based rows are included, and the code block header names them.
+fn buffer_text_for_context(
+    path: &Path,
+    language: Option<&str>,
+    content: Rope,
+    max_bytes: Option<usize>,
+    line_range: Option<Range<u32>>,
+) -> SharedString {
+    if is_binary(&content) {
+        return format!(
+            "=== {} (binary, {} bytes, skipped) ===\n",
+            path.display(),
+            content.len()
+        )
+        .into();
+    }
+
+    // Without a known language, the extension is the best guess at one.
+    let language = language.or_else(|| path.extension().and_then(|extension| extension.to_str()));
+    if let Some(line_range) =
+        line_range.and_then(|line_range| clamp_line_range(line_range, last_row(&content)))
+    {
+        let start = content.point_to_offset(Point::new(line_range.start, 0));
+        let end = if line_range.end > content.max_point().row {
+            content.len()
+        } else {
+            content.point_to_offset(Point::new(line_range.end, 0))
+        };
+        let header = format!(
+            "{}:{}-{}",
+            path.to_string_lossy(),
+            line_range.start + 1,
+            line_range.end
+        );
+        return to_fenced_codeblock_with_header(language, &header, content.slice(start..end));
+    }
+
+    match max_bytes.filter(|max_bytes| content.len() > *max_bytes) {
+        Some(max_bytes) => {
+            let mut truncated = content.slice(0..content.clip_offset(max_bytes, Bias::Left));
+            if truncated.reversed_chars_at(truncated.len()).next() != Some('\n') {
+                truncated.push("\n");
+            }
+            truncated.push(&format!("… (file truncated at {max_bytes} bytes)\n"));
+            to_fenced_codeblock(path, language, truncated)
+        }
+        None => to_fenced_codeblock(path, language, content),
+    }
+}
+
+/// Clamps a range of zero-based rows to the rows of a buffer whose last line is at `last_row`.
+/// Returns `None` for an empty range or one that starts past the end of the buffer.
+fn clamp_line_range(line_range: Range<u32>, last_row: u32) -> Option<Range<u32>> {
+    if line_range.is_empty() || line_range.start > last_row {
+        return None;
+    }
+    Some(line_range.start..line_range.end.min(last_row + 1))
+}
+
+/// The row of the last line, not counting the empty row after a trailing newline.
+fn last_row(content: &Rope) -> u32 {
+    let max_row = content.max_point().row;
+    if max_row > 0 && content.line_len(max_row) == 0 {
+        max_row - 1
+    } else {
+        max_row
+    }
+}
+
+/// Detects binary content by looking for NUL bytes at the start of the content.
+fn is_binary(content: &Rope) -> bool {
+    let mut remaining = BINARY_DETECTION_LEN;
+    for chunk in content.chunks() {
+        let bytes = &chunk.as_bytes()[..chunk.len().min(remaining)];
+        if bytes.contains(&0) {
+            return true;
+        }
+
+        remaining -= bytes.len();
+        if remaining == 0 {
+            break;
+        }
+    }
+
+    false
+}
+
+pub fn buffer_path_log_err(buffer: &Buffer) -> Option<Arc<Path>> {
+    if let Some(file) = buffer.file() {
+        Some(file.path().clone())
+    } else {
+        log::error!("Buffer that had a path unexpectedly no longer has a path.");
         None
     }
 }
 
-fn to_fenced_codeblock(path: &Path, content: Rope) -> SharedString {
-    let path_extension = path.extension().and_then(|ext| ext.to_str());
-    let path_string = path.to_string_lossy();
+fn to_fenced_codeblock(path: &Path, language: Option<&str>, content: Rope) -> SharedString {
+    to_fenced_codeblock_with_header(language, &path.to_string_lossy(), content)
+}
+
+/// Like [`to_fenced_codeblock`], but with `header` in place of the path after the language.
+/// Without a language, the header goes on its own line above a bare fence, so that it isn't
+/// mistaken for the language.
+fn to_fenced_codeblock_with_header(
+    language: Option<&str>,
+    header: &str,
+    content: Rope,
+) -> SharedString {
     let capacity = 3
-        + path_extension.map_or(0, |extension| extension.len() + 1)
-        + path_string.len()
+        + language.map_or(1, |language| language.len() + 1)
+        + header.len()
         + 1
         + content.len()
         + 5;
     let mut buffer = String::with_capacity(capacity);
 
-    buffer.push_str("```");
-
-    if let Some(extension) = path_extension {
-        buffer.push_str(extension);
-        buffer.push(' ');
+    match language {
+        Some(language) => {
+            buffer.push_str("```");
+            buffer.push_str(language);
+            buffer.push(' ');
+            buffer.push_str(header);
+        }
+        None => {
+            buffer.push_str(header);
+            buffer.push_str("\n```");
+        }
     }
-    buffer.push_str(&path_string);
 
     buffer.push('\n');
     for chunk in content.chunks() {
@@ -516,18 +1377,427 @@ fn to_fenced_codeblock(path: &Path, content: Rope) -> SharedString {
     buffer.into()
 }
 
-fn collect_files_in_path(worktree: &Worktree, path: &Path) -> Vec<Arc<Path>> {
+/// Builds the text a directory contributes to a message, walking and reading its files like
+/// [`ContextStore::attach_directory`] does, but without a context store or a window.
+/// As there is no store, no files are skipped for being attached on their own.
+pub fn build_directory_context(
+    project: Entity<Project>,
+    worktree_id: WorktreeId,
+    path: Arc<Path>,
+    options: DirectoryContextOptions,
+    cx: &mut App,
+) -> Task<Result<String>> {
+    let project_path = ProjectPath { worktree_id, path };
+    cx.spawn(|mut cx| async move {
+        // The context is never inserted into a store, so its ID is never looked up.
+        let directory_context = read_directory_context(
+            None,
+            &project,
+            &project_path,
+            ContextId(0),
+            options,
+            &AtomicBool::new(false),
+            &mut cx,
+        )
+        .await?
+        .ok_or_else(|| anyhow!("reading {} was cancelled", project_path.path.display()))?;
+        Ok(directory_context.snapshot.text.join("\n"))
+    })
+}
+
+/// Walks a directory the way `options` describe and builds its context, either from the contents
+/// of its files or from their paths and sizes. Returns `None` if `cancellation_flag` was set
+/// before the files had been read. Without a store, no files are skipped as already attached and
+/// no progress is reported.
+async fn read_directory_context(
+    this: Option<&WeakEntity<ContextStore>>,
+    project: &Entity<Project>,
+    project_path: &ProjectPath,
+    id: ContextId,
+    options: DirectoryContextOptions,
+    cancellation_flag: &AtomicBool,
+    cx: &mut AsyncApp,
+) -> Result<Option<DirectoryContext>> {
+    match options.mode {
+        DirectoryContextMode::Contents => {
+            let Some((context_buffers, excluded_files, attached_files)) = read_directory(
+                this,
+                project,
+                project_path,
+                options.max_file_bytes,
+                &options.exclude,
+                options.recursive,
+                options.follow_symlinks,
+                cancellation_flag,
+                cx,
+            )
+            .await?
+            else {
+                return Ok(None);
+            };
+            Ok(Some(DirectoryContext::new(
+                id,
+                &project_path.path,
+                context_buffers,
+                options.max_bytes,
+                options.max_file_bytes,
+                excluded_files,
+                attached_files,
+                options.recursive,
+                options.exclude,
+                options.follow_symlinks,
+            )))
+        }
+        DirectoryContextMode::FileList => {
+            let (listed_files, excluded_files) = list_directory(
+                project,
+                project_path,
+                &options.exclude,
+                options.recursive,
+                options.follow_symlinks,
+                cx,
+            )?;
+            Ok(Some(DirectoryContext::file_list(
+                id,
+                &project_path.path,
+                listed_files,
+                excluded_files,
+                options.recursive,
+                options.exclude,
+                options.follow_symlinks,
+            )))
+        }
+    }
+}
+
+/// Reads the text files of a directory, reporting progress through the store's pending
+/// directories. Returns the files that were read along with the number of excluded files, or
+/// `None` if `cancellation_flag` was set before the files had been read. Without a store, no
+/// files are skipped as already attached and no progress is reported.
+async fn read_directory(
+    this: Option<&WeakEntity<ContextStore>>,
+    project: &Entity<Project>,
+    project_path: &ProjectPath,
+    max_file_bytes: usize,
+    exclude: &PathMatcher,
+    recursive: bool,
+    follow_symlinks: bool,
+    cancellation_flag: &AtomicBool,
+    cx: &mut AsyncApp,
+) -> Result<Option<(Vec<ContextBuffer>, usize, usize)>> {
+    let worktree_id = project_path.worktree_id;
+    let result = async {
+        let worktree = project.update(cx, |project, cx| {
+            project
+                .worktree_for_id(worktree_id, cx)
+                .ok_or_else(|| anyhow!("no worktree found for {worktree_id:?}"))
+        })??;
+
+        let (mut files, worktree_abs_path) = worktree.update(cx, |worktree, _cx| {
+            (
+                collect_files_in_path(worktree, &project_path.path, recursive, follow_symlinks),
+                worktree.abs_path(),
+            )
+        })?;
+        // Sorted so that the files cut off by the budget are the same across runs.
+        files.sort();
+        let file_count = files.len();
+        files.retain(|path| !exclude.is_match(path));
+        let excluded_files = file_count - files.len();
+        // Files attached on their own are already in the context.
+        let attached_file_paths = match this {
+            Some(this) => this.update(cx, |this, cx| this.attached_file_paths(cx))?,
+            None => HashSet::default(),
+        };
+        let file_count = files.len();
+        files.retain(|path| {
+            !attached_file_paths.contains(&ProjectPath {
+                worktree_id,
+                path: path.clone(),
+            })
+        });
+        let attached_files = file_count - files.len();
+
+        if cancellation_flag.load(Ordering::Relaxed) {
+            return Ok(None);
+        }
+
+        let total_files = files.len();
+        if let Some(this) = this {
+            this.update(cx, |this, cx| {
+                this.pending_directories.push(PendingDirectory {
+                    project_path: project_path.clone(),
+                    files_read: 0,
+                    total_files,
+                });
+                cx.notify();
+            })?;
+        }
+
+        // Files over the per-file cap are never opened as buffers, since only their start would be
+        // included. Their sizes come from the worktree's metadata.
+        let oversized = worktree.update(cx, |worktree, _cx| {
+            files
+                .iter()
+                .map(|path| {
+                    worktree
+                        .entry_for_path(path)
+                        .is_some_and(|entry| entry.size > max_file_bytes as u64)
+                })
+                .collect::<Vec<_>>()
+        })?;
+
+        let mut open_buffers = project.update(cx, |project, cx| {
+            let fs = project.is_local().then(|| project.fs().clone());
+            files
+                .iter()
+                .zip(&oversized)
+                .enumerate()
+                .map(|(ix, (file_path, oversized))| {
+                    let contents = if *oversized {
+                        // Without a local file system, oversized files can only be skipped.
+                        let prefix = fs.clone().map(|fs| {
+                            let abs_path = worktree_abs_path.join(file_path);
+                            cx.background_spawn(async move {
+                                read_file_prefix(fs.as_ref(), &abs_path, max_file_bytes).await
+                            })
+                        });
+                        async move {
+                            match prefix {
+                                Some(prefix) => prefix.await.map(FileContents::Prefix),
+                                None => Err(anyhow!("file is over the per-file limit")),
+                            }
+                        }
+                        .boxed_local()
+                    } else {
+                        let open_buffer = project.open_buffer(
+                            ProjectPath {
+                                worktree_id,
+                                path: file_path.clone(),
+                            },
+                            cx,
+                        );
+                        async move { open_buffer.await.map(FileContents::Buffer) }.boxed_local()
+                    };
+                    async move { (ix, contents.await) }
+                })
+                .collect::<FuturesUnordered<_>>()
+        })?;
+
+        let mut buffers = (0..total_files).map(|_| None).collect::<Vec<_>>();
+        let mut files_read = 0;
+        while let Some((ix, buffer)) = open_buffers.next().await {
+            if cancellation_flag.load(Ordering::Relaxed) {
+                return Ok(None);
+            }
+
+            buffers[ix] = Some(buffer);
+            files_read += 1;
+            if let Some(this) = this {
+                this.update(cx, |this, cx| {
+                    if let Some(pending) = this.pending_directory_mut(project_path) {
+                        pending.files_read = files_read;
+                        cx.notify();
+                    }
+                })?;
+            }
+        }
+
+        let mut buffer_infos = Vec::new();
+        let mut text_tasks = Vec::new();
+        cx.update(|cx| {
+            for (path, buffer_entity) in files.into_iter().zip(buffers) {
+                // Skip all binary files and other non-UTF8 files
+                if let Some(Ok(contents)) = buffer_entity {
+                    let buffer_entity = match contents {
+                        FileContents::Buffer(buffer_entity) => buffer_entity,
+                        FileContents::Prefix(text) => cx.new(|cx| Buffer::local(text, cx)),
+                    };
+                    let buffer = buffer_entity.read(cx);
+                    let (buffer_info, text_task) = collect_buffer_info_and_text(
+                        path,
+                        buffer_entity,
+                        buffer,
+                        Some(max_file_bytes),
+                        None,
+                        cx,
+                    );
+                    buffer_infos.push(buffer_info);
+                    text_tasks.push(text_task);
+                }
+            }
+        })?;
+
+        let buffer_texts = future::join_all(text_tasks).await;
+        let context_buffers = buffer_infos
+            .into_iter()
+            .zip(buffer_texts)
+            .map(|(info, text)| make_context_buffer(info, text))
+            .collect::<Vec<_>>();
+
+        if context_buffers.is_empty() {
+            if attached_files > 0 {
+                bail!(
+                    "All files in {} are already attached",
+                    &project_path.path.display()
+                );
+            }
+            if excluded_files > 0 {
+                bail!(
+                    "All files in {} match an exclude pattern",
+                    &project_path.path.display()
+                );
+            }
+            bail!("No text files found in {}", &project_path.path.display());
+        }
+
+        anyhow::Ok(Some((context_buffers, excluded_files, attached_files)))
+    }
+    .await;
+
+    // Progress is cleared however the read ended, including when it was cancelled.
+    if let Some(this) = this {
+        this.update(cx, |this, cx| {
+            this.pending_directories
+                .retain(|pending| pending.project_path != *project_path);
+            cx.notify();
+        })
+        .ok();
+    }
+
+    result
+}
+
+/// What was read of a directory's file.
+enum FileContents {
+    /// The file, opened as a buffer of the project.
+    Buffer(Entity<Buffer>),
+    /// The start of a file over the per-file cap, read without opening it. It is held in a buffer
+    /// outside the project, so later edits to the file don't mark the directory as stale.
+    Prefix(String),
+}
+
+/// Reads slightly more than the first `max_bytes` of a file, so that it's still truncated once an
+/// incomplete trailing character is dropped. Fails for files that aren't UTF-8, like opening them
+/// as a buffer would.
+async fn read_file_prefix(fs: &dyn Fs, abs_path: &Path, max_bytes: usize) -> Result<String> {
+    let mut bytes = Vec::new();
+    fs.open_sync(abs_path)
+        .await?
+        .take(max_bytes as u64 + 4)
+        .read_to_end(&mut bytes)?;
+    match String::from_utf8(bytes) {
+        Ok(text) => Ok(text),
+        Err(error) if error.utf8_error().error_len().is_none() => {
+            let valid_len = error.utf8_error().valid_up_to();
+            let mut bytes = error.into_bytes();
+            bytes.truncate(valid_len);
+            Ok(String::from_utf8(bytes)?)
+        }
+        Err(error) => Err(error.into()),
+    }
+}
+
+/// Lists the files of a directory with their sizes, in path order. Returns the listed files along
+/// with the number of excluded files.
+fn list_directory(
+    project: &Entity<Project>,
+    project_path: &ProjectPath,
+    exclude: &PathMatcher,
+    recursive: bool,
+    follow_symlinks: bool,
+    cx: &mut AsyncApp,
+) -> Result<(Vec<(Arc<Path>, u64)>, usize)> {
+    let worktree_id = project_path.worktree_id;
+    let worktree = project.update(cx, |project, cx| {
+        project
+            .worktree_for_id(worktree_id, cx)
+            .ok_or_else(|| anyhow!("no worktree found for {worktree_id:?}"))
+    })??;
+
+    worktree.update(cx, |worktree, _cx| {
+        let mut files =
+            collect_files_in_path(worktree, &project_path.path, recursive, follow_symlinks);
+        files.sort();
+        let file_count = files.len();
+        files.retain(|path| !exclude.is_match(path));
+        let excluded_files = file_count - files.len();
+
+        if files.is_empty() {
+            if excluded_files > 0 {
+                bail!(
+                    "All files in {} match an exclude pattern",
+                    &project_path.path.display()
+                );
+            }
+            bail!("No files found in {}", &project_path.path.display());
+        }
+
+        let listed_files = files
+            .into_iter()
+            .map(|path| {
+                let size = worktree.entry_for_path(&path).map_or(0, |entry| entry.size);
+                (path, size)
+            })
+            .collect();
+        Ok((listed_files, excluded_files))
+    })?
+}
+
+fn collect_files_in_path(
+    worktree: &Worktree,
+    path: &Path,
+    recursive: bool,
+    follow_symlinks: bool,
+) -> Vec<Arc<Path>> {
+    let mut visited = HashSet::default();
+    visited.insert(worktree.abs_path().join(path));
     let mut files = Vec::new();
+    collect_files_in_directory(
+        worktree,
+        path,
+        recursive,
+        follow_symlinks,
+        &mut visited,
+        &mut files,
+    );
+    files
+}
 
+/// Symlinked directories are only descended into when `follow_symlinks` is set, and each
+/// directory at most once, by canonical path, so a link to an ancestor can't loop forever.
+fn collect_files_in_directory(
+    worktree: &Worktree,
+    path: &Path,
+    recursive: bool,
+    follow_symlinks: bool,
+    visited: &mut HashSet<PathBuf>,
+    files: &mut Vec<Arc<Path>>,
+) {
     for entry in worktree.child_entries(path) {
         if entry.is_dir() {
-            files.extend(collect_files_in_path(worktree, &entry.path));
+            if !recursive {
+                continue;
+            }
+            let canonical_path = match &entry.canonical_path {
+                Some(_) if !follow_symlinks => continue,
+                Some(canonical_path) => canonical_path.to_path_buf(),
+                None => worktree.abs_path().join(&entry.path),
+            };
+            if visited.insert(canonical_path) {
+                collect_files_in_directory(
+                    worktree,
+                    &entry.path,
+                    recursive,
+                    follow_symlinks,
+                    visited,
+                    files,
+                );
+            }
         } else if entry.is_file() {
             files.push(entry.path.clone());
         }
     }
-
-    files
 }
 
 pub fn refresh_context_store_text(
@@ -574,6 +1844,9 @@ pub fn refresh_context_store_text(
                 // and doing the caching properly could be tricky (unless it's already handled by
                 // the HttpClient?).
                 AssistantContext::FetchedUrl(_) => {}
+                // A diff describes the changes as they were when it was attached; re-diffing
+                // would need the repository rather than the changed buffers.
+                AssistantContext::GitDiff(_) => {}
             }
 
             None
@@ -593,13 +1866,18 @@ fn refresh_file_text(
     cx: &App,
 ) -> Option<Task<()>> {
     let id = file_context.id;
-    let task = refresh_context_buffer(&file_context.context_buffer, cx);
+    let line_range = file_context.line_range.clone();
+    let task = refresh_context_buffer(&file_context.context_buffer, None, line_range.clone(), cx);
     if let Some(task) = task {
         Some(cx.spawn(|mut cx| async move {
             let context_buffer = task.await;
             context_store
                 .update(&mut cx, |context_store, _| {
-                    let new_file_context = FileContext { id, context_buffer };
+                    let new_file_context = FileContext {
+                        id,
+                        context_buffer,
+                        line_range,
+                    };
                     context_store.replace_context(AssistantContext::File(new_file_context));
                 })
                 .ok();
@@ -619,7 +1897,12 @@ fn refresh_directory_text(
         .context_buffers
         .iter()
         .map(|context_buffer| {
-            if let Some(refresh_task) = refresh_context_buffer(context_buffer, cx) {
+            if let Some(refresh_task) = refresh_context_buffer(
+                context_buffer,
+                Some(directory_context.max_file_bytes),
+                None,
+                cx,
+            ) {
                 stale = true;
                 future::Either::Left(refresh_task)
             } else {
@@ -636,11 +1919,29 @@ fn refresh_directory_text(
 
     let id = directory_context.snapshot.id;
     let path = directory_context.path.clone();
+    let max_bytes = directory_context.max_bytes;
+    let max_file_bytes = directory_context.max_file_bytes;
+    let excluded_files = directory_context.excluded_files;
+    let attached_files = directory_context.attached_files;
+    let recursive = directory_context.recursive;
+    let exclude = directory_context.exclude.clone();
+    let follow_symlinks = directory_context.follow_symlinks;
     Some(cx.spawn(|mut cx| async move {
         let context_buffers = context_buffers.await;
         context_store
             .update(&mut cx, |context_store, _| {
-                let new_directory_context = DirectoryContext::new(id, &path, context_buffers);
+                let new_directory_context = DirectoryContext::new(
+                    id,
+                    &path,
+                    context_buffers,
+                    max_bytes,
+                    max_file_bytes,
+                    excluded_files,
+                    attached_files,
+                    recursive,
+                    exclude,
+                    follow_symlinks,
+                );
                 context_store.replace_context(AssistantContext::Directory(new_directory_context));
             })
             .ok();
@@ -654,15 +1955,11 @@ fn refresh_thread_text(
 ) -> Task<()> {
     let id = thread_context.id;
     let thread = thread_context.thread.clone();
+    let text_options = thread_context.text_options;
     cx.spawn(move |mut cx| async move {
         context_store
             .update(&mut cx, |context_store, cx| {
-                let text = thread.read(cx).text().into();
-                context_store.replace_context(AssistantContext::Thread(ThreadContext {
-                    id,
-                    thread,
-                    text,
-                }));
+                context_store.refresh_thread(id, thread, text_options, cx);
             })
             .ok();
     })
@@ -670,6 +1967,8 @@ fn refresh_thread_text(
 
 fn refresh_context_buffer(
     context_buffer: &ContextBuffer,
+    max_bytes: Option<usize>,
+    line_range: Option<Range<u32>>,
     cx: &App,
 ) -> Option<impl Future<Output = ContextBuffer>> {
     let buffer = context_buffer.buffer.read(cx);
@@ -679,10 +1978,1509 @@ fn refresh_context_buffer(
             path,
             context_buffer.buffer.clone(),
             buffer,
-            cx.to_async(),
+            max_bytes,
+            line_range,
+            cx,
         );
         Some(text_task.map(move |text| make_context_buffer(buffer_info, text)))
     } else {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::DEFAULT_DIRECTORY_CONTEXT_EXCLUDE_GLOBS;
+    use fs::FakeFs;
+    use gpui::{TestAppContext, VisualTestContext};
+    use language_model::Role;
+    use project::Project;
+    use prompt_store::PromptBuilder;
+    use serde_json::json;
+    use std::cell::RefCell;
+    use std::collections::BTreeSet;
+    use std::rc::Rc;
+    use util::path;
+    use workspace::AppState;
+
+    struct TestContext<'a> {
+        fs: Arc<FakeFs>,
+        project: Entity<Project>,
+        context_store: Entity<ContextStore>,
+        /// The project's first worktree.
+        worktree_id: WorktreeId,
+        cx: &'a mut VisualTestContext,
+    }
+
+    fn init_settings(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            AppState::test(cx);
+            language::init(cx);
+            workspace::init_settings(cx);
+            Project::init_settings(cx);
+        });
+    }
+
+    /// Opens a workspace on a `/root` worktree containing `tree`, with a context store for it.
+    async fn init_test(tree: serde_json::Value, cx: &mut TestAppContext) -> TestContext<'_> {
+        init_test_with_worktrees(&[(path!("/root"), tree)], cx).await
+    }
+
+    /// Opens a workspace with a worktree for each root and its contents, with a context store
+    /// for it.
+    async fn init_test_with_worktrees<'a>(
+        worktrees: &[(&str, serde_json::Value)],
+        cx: &'a mut TestAppContext,
+    ) -> TestContext<'a> {
+        init_settings(cx);
+
+        let fs = FakeFs::new(cx.executor());
+        for (root, tree) in worktrees {
+            fs.insert_tree(root, tree.clone()).await;
+        }
+        let project = Project::test(
+            fs.clone(),
+            worktrees.iter().map(|(root, _)| Path::new(root)),
+            cx,
+        )
+        .await;
+        let (workspace, cx) =
+            cx.add_window_view(|window, cx| Workspace::test_new(project.clone(), window, cx));
+        let context_store = cx.new(|_| ContextStore::new(workspace.downgrade()));
+        let worktree_id = project.read_with(cx, |project, cx| {
+            project.worktrees(cx).next().unwrap().read(cx).id()
+        });
+
+        TestContext {
+            fs,
+            project,
+            context_store,
+            worktree_id,
+            cx,
+        }
+    }
+
+    fn project_path(worktree_id: WorktreeId, path: &str) -> ProjectPath {
+        ProjectPath {
+            worktree_id,
+            path: Path::new(path).into(),
+        }
+    }
+
+    #[test]
+    fn test_binary_files_are_skipped() {
+        let png = Rope::from("\u{89}PNG\r\n\u{1a}\n\0\0\0\rIHDR\0\0\0\u{1}");
+        let text =
+            buffer_text_for_context(Path::new("assets/logo.png"), None, png.clone(), None, None);
+        assert_eq!(
+            text.as_ref(),
+            format!(
+                "=== assets/logo.png (binary, {} bytes, skipped) ===\n",
+                png.len()
+            )
+        );
+
+        let source = Rope::from("fn main() {}\n");
+        let text = buffer_text_for_context(Path::new("src/main.rs"), None, source, None, None);
+        assert_eq!(text.as_ref(), "```rs src/main.rs\nfn main() {}\n```\n");
+
+        // Without a language to name, the path isn't put where the language goes.
+        let text =
+            buffer_text_for_context(Path::new("Makefile"), None, "all:\n".into(), None, None);
+        assert_eq!(text.as_ref(), "Makefile\n```\nall:\n```\n");
+    }
+
+    #[test]
+    fn test_file_text_is_limited_to_line_range() {
+        let content = Rope::from("one\ntwo\nthree\nfour\n");
+        let path = Path::new("a.txt");
+
+        let text = buffer_text_for_context(path, None, content.clone(), None, Some(1..3));
+        assert_eq!(text.as_ref(), "```txt a.txt:2-3\ntwo\nthree\n```\n");
+
+        // The first and last lines are included.
+        let text = buffer_text_for_context(path, None, content.clone(), None, Some(0..1));
+        assert_eq!(text.as_ref(), "```txt a.txt:1-1\none\n```\n");
+        let text = buffer_text_for_context(path, None, content.clone(), None, Some(3..4));
+        assert_eq!(text.as_ref(), "```txt a.txt:4-4\nfour\n```\n");
+
+        // A range past the end is clamped to the end of the file.
+        let text = buffer_text_for_context(path, None, content.clone(), None, Some(2..80));
+        assert_eq!(text.as_ref(), "```txt a.txt:3-4\nthree\nfour\n```\n");
+
+        // A range starting past the end attaches the whole file.
+        let text = buffer_text_for_context(path, None, content.clone(), None, Some(40..80));
+        assert_eq!(text.as_ref(), "```txt a.txt\none\ntwo\nthree\nfour\n```\n");
+
+        assert_eq!(clamp_line_range(0..2, 1), Some(0..2));
+        assert_eq!(clamp_line_range(1..5, 1), Some(1..2));
+        assert_eq!(clamp_line_range(2..5, 1), None);
+        assert_eq!(clamp_line_range(3..3, 10), None);
+    }
+
+    #[test]
+    fn test_file_text_is_truncated_past_max_bytes() {
+        let content = Rope::from("0123456789");
+
+        let text =
+            buffer_text_for_context(Path::new("a.txt"), None, content.clone(), Some(10), None);
+        assert_eq!(text.as_ref(), "```txt a.txt\n0123456789\n```\n");
+
+        let text = buffer_text_for_context(Path::new("a.txt"), None, content, Some(9), None);
+        assert_eq!(
+            text.as_ref(),
+            "```txt a.txt\n012345678\n… (file truncated at 9 bytes)\n```\n"
+        );
+
+        // The cut never splits a character.
+        let text =
+            buffer_text_for_context(Path::new("a.txt"), None, Rope::from("aé"), Some(2), None);
+        assert_eq!(
+            text.as_ref(),
+            "```txt a.txt\na\n… (file truncated at 2 bytes)\n```\n"
+        );
+    }
+
+    #[test]
+    fn test_binary_detection_only_inspects_prefix() {
+        let mut content = "a".repeat(BINARY_DETECTION_LEN);
+        content.push('\0');
+        assert!(!is_binary(&Rope::from(content.as_str())));
+        assert!(is_binary(&Rope::from("abc\0def")));
+    }
+
+    #[gpui::test]
+    async fn test_non_recursive_directory_skips_subdirectories(cx: &mut TestAppContext) {
+        let TestContext {
+            context_store,
+            worktree_id,
+            cx,
+            ..
+        } = init_test(
+            json!({ "src": { "lib.rs": "// lib", "nested": { "mod.rs": "// mod" } } }),
+            cx,
+        )
+        .await;
+
+        context_store
+            .update(cx, |context_store, cx| {
+                context_store.attach_directory(
+                    project_path(worktree_id, "src"),
+                    DirectoryContextOptions {
+                        exclude: PathMatcher::default(),
+                        recursive: false,
+                        ..DirectoryContextOptions::default()
+                    },
+                    Arc::default(),
+                    cx,
+                )
+            })
+            .await
+            .unwrap();
+
+        context_store.read_with(cx, |context_store, _cx| {
+            let [AssistantContext::Directory(directory_context)] =
+                context_store.context().as_slice()
+            else {
+                panic!("expected a single directory context");
+            };
+            assert_eq!(directory_context.context_buffers.len(), 1);
+            assert!(matches!(
+                context_store
+                    .will_include_file_path_via_directory(&project_path(worktree_id, "src/lib.rs")),
+                Some(FileInclusion::InDirectory(_))
+            ));
+            assert!(context_store
+                .will_include_file_path_via_directory(&project_path(
+                    worktree_id,
+                    "src/nested/mod.rs"
+                ))
+                .is_none());
+        });
+    }
+
+    #[gpui::test]
+    async fn test_oversized_files_are_read_without_opening_them(cx: &mut TestAppContext) {
+        let TestContext {
+            project,
+            context_store,
+            worktree_id,
+            cx,
+            ..
+        } = init_test(
+            json!({ "src": { "big.rs": "0123456789\nabcdef\n", "small.rs": "// small" } }),
+            cx,
+        )
+        .await;
+
+        context_store
+            .update(cx, |context_store, cx| {
+                context_store.attach_directory(
+                    project_path(worktree_id, "src"),
+                    DirectoryContextOptions {
+                        max_file_bytes: 10,
+                        ..DirectoryContextOptions::default()
+                    },
+                    Arc::default(),
+                    cx,
+                )
+            })
+            .await
+            .unwrap();
+
+        project.read_with(cx, |project, cx| {
+            assert!(project
+                .get_open_buffer(&project_path(worktree_id, "src/big.rs"), cx)
+                .is_none());
+            assert!(project
+                .get_open_buffer(&project_path(worktree_id, "src/small.rs"), cx)
+                .is_some());
+        });
+        context_store.read_with(cx, |context_store, _cx| {
+            let [AssistantContext::Directory(directory_context)] =
+                context_store.context().as_slice()
+            else {
+                panic!("expected a single directory context");
+            };
+            let text = directory_context.snapshot.text.join("\n");
+            assert!(text
+                .contains("```rs src/big.rs\n0123456789\n… (file truncated at 10 bytes)\n```\n"));
+            assert!(text.contains("```rs src/small.rs\n// small\n```\n"));
+        });
+    }
+
+    #[gpui::test]
+    async fn test_symlinked_directories_are_followed_once(cx: &mut TestAppContext) {
+        init_settings(cx);
+
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            path!("/root"),
+            json!({
+                "src": { "lib.rs": "// lib" },
+                "shared": { "util.rs": "// util" },
+            }),
+        )
+        .await;
+        fs.insert_symlink(path!("/root/src/shared"), path!("/root/shared").into())
+            .await;
+        // A link back to an ancestor would recurse forever without the cycle guard.
+        fs.insert_symlink(path!("/root/src/again"), path!("/root/src").into())
+            .await;
+        let project = Project::test(fs, [path!("/root").as_ref()], cx).await;
+        cx.run_until_parked();
+
+        let worktree = project.read_with(cx, |project, cx| project.worktrees(cx).next().unwrap());
+        worktree.read_with(cx, |worktree, _cx| {
+            let files = |follow_symlinks| {
+                collect_files_in_path(worktree, Path::new("src"), true, follow_symlinks)
+                    .into_iter()
+                    .map(|path| path.to_string_lossy().replace('\\', "/"))
+                    .collect::<BTreeSet<_>>()
+            };
+            assert_eq!(files(false), BTreeSet::from(["src/lib.rs".to_string()]));
+            assert_eq!(
+                files(true),
+                BTreeSet::from(["src/lib.rs".to_string(), "src/shared/util.rs".to_string()])
+            );
+        });
+    }
+
+    #[gpui::test]
+    async fn test_directories_are_keyed_by_worktree(cx: &mut TestAppContext) {
+        let TestContext {
+            project,
+            context_store,
+            cx,
+            ..
+        } = init_test_with_worktrees(
+            &[
+                (path!("/a"), json!({ "src": { "lib.rs": "// a" } })),
+                (path!("/b"), json!({ "src": { "lib.rs": "// b" } })),
+            ],
+            cx,
+        )
+        .await;
+
+        let src_paths = project.read_with(cx, |project, cx| {
+            project
+                .worktrees(cx)
+                .map(|worktree| ProjectPath {
+                    worktree_id: worktree.read(cx).id(),
+                    path: Path::new("src").into(),
+                })
+                .collect::<Vec<_>>()
+        });
+        assert_eq!(src_paths.len(), 2);
+
+        for src_path in &src_paths {
+            context_store
+                .update(cx, |context_store, cx| {
+                    context_store.attach_directory(
+                        src_path.clone(),
+                        DirectoryContextOptions {
+                            exclude: PathMatcher::default(),
+                            ..DirectoryContextOptions::default()
+                        },
+                        Arc::default(),
+                        cx,
+                    )
+                })
+                .await
+                .unwrap();
+        }
+
+        context_store.read_with(cx, |context_store, cx| {
+            assert_eq!(context_store.context().len(), 2);
+            let ids = src_paths
+                .iter()
+                .map(|src_path| {
+                    let id = context_store.includes_directory(src_path).unwrap();
+                    assert_eq!(
+                        context_store.provenance(id, cx),
+                        Some(ContextProvenance::Directory(src_path.clone()))
+                    );
+                    id
+                })
+                .collect::<HashSet<_>>();
+            assert_eq!(ids.len(), 2);
+
+            let file_path = ProjectPath {
+                worktree_id: src_paths[0].worktree_id,
+                path: Path::new("src/lib.rs").into(),
+            };
+            assert!(matches!(
+                context_store.will_include_file_path_via_directory(&file_path),
+                Some(FileInclusion::InDirectory(_))
+            ));
+        });
+    }
+
+    #[gpui::test]
+    async fn test_files_are_keyed_by_worktree(cx: &mut TestAppContext) {
+        let TestContext {
+            project,
+            context_store,
+            worktree_id,
+            cx,
+            ..
+        } = init_test_with_worktrees(
+            &[
+                (path!("/a"), json!({ "src": { "lib.rs": "// a" } })),
+                (path!("/b"), json!({ "src": { "lib.rs": "// b" } })),
+            ],
+            cx,
+        )
+        .await;
+        let other_worktree_id = project.read_with(cx, |project, cx| {
+            project.worktrees(cx).nth(1).unwrap().read(cx).id()
+        });
+
+        context_store
+            .update(cx, |context_store, cx| {
+                context_store.add_file_from_path(project_path(worktree_id, "src/lib.rs"), None, cx)
+            })
+            .await
+            .unwrap();
+
+        context_store.read_with(cx, |context_store, cx| {
+            assert!(matches!(
+                context_store.will_include_file_path(&project_path(worktree_id, "src/lib.rs"), cx),
+                Some(FileInclusion::Direct(_))
+            ));
+            assert!(context_store
+                .will_include_file_path(&project_path(other_worktree_id, "src/lib.rs"), cx)
+                .is_none());
+        });
+    }
+
+    #[gpui::test]
+    async fn test_attaching_line_range_replaces_attached_file(cx: &mut TestAppContext) {
+        let TestContext {
+            context_store,
+            worktree_id,
+            cx,
+            ..
+        } = init_test(
+            json!({ "src": { "main.rs": "// one\n// two\n// three\n// four" } }),
+            cx,
+        )
+        .await;
+        let add_file = |line_range: Option<Range<u32>>, cx: &mut VisualTestContext| {
+            context_store.update(cx, |context_store, cx| {
+                context_store.add_file_from_path(
+                    project_path(worktree_id, "src/main.rs"),
+                    line_range,
+                    cx,
+                )
+            })
+        };
+        let attached_line_ranges = |cx: &mut VisualTestContext| {
+            context_store.read_with(cx, |context_store, _cx| {
+                context_store
+                    .context()
+                    .iter()
+                    .filter_map(|context| match context {
+                        AssistantContext::File(file) => Some((file.id, file.line_range.clone())),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>()
+            })
+        };
+
+        add_file(None, cx).await.unwrap();
+        let [(id, None)] = attached_line_ranges(cx)[..] else {
+            panic!("the whole file should be attached");
+        };
+
+        // Attaching a range of the file replaces the whole file, keeping its place.
+        add_file(Some(1..2), cx).await.unwrap();
+        assert_eq!(attached_line_ranges(cx), [(id, Some(1..2))]);
+
+        // Another range replaces the range.
+        add_file(Some(2..3), cx).await.unwrap();
+        assert_eq!(attached_line_ranges(cx), [(id, Some(2..3))]);
+
+        // Attaching the same range again still removes it.
+        add_file(Some(2..3), cx).await.unwrap();
+        assert!(attached_line_ranges(cx).is_empty());
+    }
+
+    #[gpui::test]
+    async fn test_single_file_is_attached_on_its_own(cx: &mut TestAppContext) {
+        let TestContext {
+            context_store,
+            worktree_id,
+            cx,
+            ..
+        } = init_test(
+            json!({ "src": { "lib.rs": "// lib", "main.rs": "// main" } }),
+            cx,
+        )
+        .await;
+
+        context_store
+            .update(cx, |context_store, cx| {
+                context_store.add_file_from_path(project_path(worktree_id, "src/lib.rs"), None, cx)
+            })
+            .await
+            .unwrap();
+
+        context_store.read_with(cx, |context_store, cx| {
+            let [AssistantContext::File(file_context)] = context_store.context().as_slice() else {
+                panic!("expected a single file context");
+            };
+            assert_eq!(
+                context_store.provenance(file_context.id, cx),
+                Some(ContextProvenance::File(project_path(
+                    worktree_id,
+                    "src/lib.rs"
+                )))
+            );
+            assert!(matches!(
+                context_store.will_include_file_path(&project_path(worktree_id, "src/lib.rs"), cx),
+                Some(FileInclusion::Direct(_))
+            ));
+            assert!(context_store
+                .will_include_file_path(&project_path(worktree_id, "src/main.rs"), cx)
+                .is_none());
+        });
+    }
+
+    #[gpui::test]
+    async fn test_code_blocks_are_fenced_with_the_file_language(cx: &mut TestAppContext) {
+        let TestContext {
+            project,
+            context_store,
+            worktree_id,
+            cx,
+            ..
+        } = init_test(
+            json!({ "src": { "lib.rs": "// lib", "build.sh": "make", "notes.xyz": "notes" } }),
+            cx,
+        )
+        .await;
+        project.read_with(cx, |project, _| {
+            for (name, code_fence_block_name, suffix) in
+                [("Rust", None, "rs"), ("Shell Script", Some("bash"), "sh")]
+            {
+                project.languages().add(Arc::new(language::Language::new(
+                    language::LanguageConfig {
+                        name: name.into(),
+                        code_fence_block_name: code_fence_block_name.map(Into::into),
+                        matcher: language::LanguageMatcher {
+                            path_suffixes: vec![suffix.to_string()],
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                    None,
+                )))
+            }
+        });
+
+        for path in ["src/lib.rs", "src/build.sh", "src/notes.xyz"] {
+            context_store
+                .update(cx, |context_store, cx| {
+                    context_store.add_file_from_path(project_path(path), None, cx)
+                })
+                .await
+                .unwrap();
+        }
+
+        context_store.read_with(cx, |context_store, cx| {
+            let texts = context_store
+                .snapshot(cx)
+                .map(|snapshot| snapshot.text[0].to_string())
+                .collect::<Vec<_>>();
+            assert_eq!(
+                texts,
+                [
+                    "```rust src/lib.rs\n// lib\n```\n",
+                    "```bash src/build.sh\nmake\n```\n",
+                    "```xyz src/notes.xyz\nnotes\n```\n"
+                ]
+            );
+        });
+
+        // Directories fence each of their files the same way.
+        let text = cx
+            .update(|_, cx| {
+                build_directory_context(
+                    project.clone(),
+                    worktree_id,
+                    Path::new("src").into(),
+                    DirectoryContextOptions::default(),
+                    cx,
+                )
+            })
+            .await
+            .unwrap();
+        assert!(text.contains("```rust src/lib.rs\n"));
+        assert!(text.contains("```bash src/build.sh\n"));
+        assert!(text.contains("```xyz src/notes.xyz\n"));
+    }
+
+    #[gpui::test]
+    async fn test_directory_skips_files_attached_on_their_own(cx: &mut TestAppContext) {
+        let TestContext {
+            context_store,
+            worktree_id,
+            cx,
+            ..
+        } = init_test(
+            json!({ "src": { "lib.rs": "// lib", "main.rs": "// main" } }),
+            cx,
+        )
+        .await;
+
+        let add_directory = |cx: &mut gpui::VisualTestContext| {
+            context_store.update(cx, |context_store, cx| {
+                context_store.attach_directory(
+                    project_path(worktree_id, "src"),
+                    DirectoryContextOptions {
+                        exclude: PathMatcher::default(),
+                        ..DirectoryContextOptions::default()
+                    },
+                    Arc::default(),
+                    cx,
+                )
+            })
+        };
+
+        // The file first, then its directory.
+        context_store
+            .update(cx, |context_store, cx| {
+                context_store.add_file_from_path(project_path(worktree_id, "src/lib.rs"), None, cx)
+            })
+            .await
+            .unwrap();
+        add_directory(cx).await.unwrap();
+
+        context_store.read_with(cx, |context_store, _cx| {
+            let [AssistantContext::File(_), AssistantContext::Directory(directory_context)] =
+                context_store.context().as_slice()
+            else {
+                panic!("expected a file and a directory context");
+            };
+            assert_eq!(directory_context.context_buffers.len(), 1);
+            assert_eq!(directory_context.attached_files, 1);
+            assert!(!directory_context.context_buffers[0].text.contains("// lib"));
+            assert!(directory_context
+                .snapshot
+                .text
+                .iter()
+                .any(|text| text.as_ref() == "[1 file skipped, already attached on its own]\n"));
+        });
+
+        // Removing the file includes it in the directory again.
+        let file_id =
+            context_store.read_with(cx, |context_store, _cx| context_store.context()[0].id());
+        context_store.update(cx, |context_store, cx| {
+            context_store.remove_context(file_id, cx)
+        });
+        cx.run_until_parked();
+        context_store.read_with(cx, |context_store, _cx| {
+            let [AssistantContext::Directory(directory_context)] =
+                context_store.context().as_slice()
+            else {
+                panic!("expected a single directory context");
+            };
+            assert_eq!(directory_context.context_buffers.len(), 2);
+            assert_eq!(directory_context.attached_files, 0);
+            assert!(directory_context
+                .snapshot
+                .text
+                .iter()
+                .any(|text| text.contains("// lib")));
+        });
+
+        // The directory first, then one of its files.
+        context_store.update(cx, |context_store, _cx| context_store.clear());
+        add_directory(cx).await.unwrap();
+        context_store
+            .update(cx, |context_store, cx| {
+                context_store.add_file_from_path(project_path(worktree_id, "src/lib.rs"), None, cx)
+            })
+            .await
+            .unwrap();
+
+        context_store.read_with(cx, |context_store, _cx| {
+            let [AssistantContext::Directory(directory_context)] =
+                context_store.context().as_slice()
+            else {
+                panic!("expected a single directory context");
+            };
+            assert_eq!(directory_context.context_buffers.len(), 2);
+            assert_eq!(directory_context.attached_files, 0);
+        });
+    }
+
+    #[gpui::test]
+    async fn test_inserted_context_is_announced(cx: &mut TestAppContext) {
+        let TestContext {
+            context_store,
+            worktree_id,
+            cx,
+            ..
+        } = init_test(json!({ "src": { "lib.rs": "// lib" } }), cx).await;
+
+        let inserted = Rc::new(RefCell::new(Vec::new()));
+        let _subscription = cx.update({
+            let inserted = inserted.clone();
+            |_, cx| {
+                cx.subscribe(&context_store, move |_, event, _| match event {
+                    ContextStoreEvent::ContextInserted { id, kind } => {
+                        inserted.borrow_mut().push((*id, *kind));
+                    }
+                    ContextStoreEvent::ContextRemoved { .. } => {}
+                })
+            }
+        });
+
+        context_store
+            .update(cx, |context_store, cx| {
+                context_store.add_file_from_path(project_path(worktree_id, "src/lib.rs"), None, cx)
+            })
+            .await
+            .unwrap();
+        context_store.update(cx, |context_store, cx| {
+            context_store.add_fetched_url("https://zed.dev".into(), None, "Zed", cx);
+            // Already included, so nothing new is inserted.
+            context_store.add_fetched_url("https://zed.dev".into(), None, "Zed", cx);
+        });
+
+        assert_eq!(
+            inserted.borrow().as_slice(),
+            [
+                (ContextId(0), ContextKind::File),
+                (ContextId(1), ContextKind::FetchedUrl)
+            ]
+        );
+    }
+
+    #[gpui::test]
+    async fn test_entries_of_kind(cx: &mut TestAppContext) {
+        let TestContext {
+            context_store,
+            worktree_id,
+            cx,
+            ..
+        } = init_test(json!({ "src": { "lib.rs": "// lib" } }), cx).await;
+
+        context_store.update(cx, |context_store, cx| {
+            context_store.add_fetched_url("https://zed.dev".into(), None, "Zed", cx);
+        });
+        context_store
+            .update(cx, |context_store, cx| {
+                context_store.add_file_from_path(project_path(worktree_id, "src/lib.rs"), None, cx)
+            })
+            .await
+            .unwrap();
+        context_store.update(cx, |context_store, cx| {
+            context_store.add_fetched_url("https://zed.dev/docs".into(), None, "Docs", cx);
+        });
+
+        context_store.read_with(cx, |context_store, _| {
+            let ids_of_kind = |kind| {
+                context_store
+                    .entries_of_kind(kind)
+                    .map(|context| context.id())
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(
+                ids_of_kind(ContextKind::FetchedUrl),
+                [ContextId(0), ContextId(2)]
+            );
+            assert_eq!(ids_of_kind(ContextKind::File), [ContextId(1)]);
+            assert!(ids_of_kind(ContextKind::Thread).is_empty());
+        });
+    }
+
+    #[gpui::test]
+    async fn test_total_text_len(cx: &mut TestAppContext) {
+        let TestContext {
+            context_store, cx, ..
+        } = init_test(json!({}), cx).await;
+
+        context_store.update(cx, |context_store, cx| {
+            assert_eq!(context_store.total_text_len(), 0);
+            assert_eq!(context_store.estimated_token_count(), 0);
+
+            context_store.add_fetched_url("https://zed.dev".into(), None, "12345678", cx);
+            context_store.add_fetched_url("https://zed.dev/docs".into(), None, "123", cx);
+            assert_eq!(context_store.total_text_len(), 11);
+            assert_eq!(context_store.estimated_token_count(), 3);
+        });
+    }
+
+    #[gpui::test]
+    async fn test_remove_contexts_is_undone_at_once(cx: &mut TestAppContext) {
+        let TestContext {
+            context_store, cx, ..
+        } = init_test(json!({}), cx).await;
+
+        let removals = Rc::new(RefCell::new(Vec::new()));
+        let _subscription = cx.update(|_, cx| {
+            let removals = removals.clone();
+            cx.subscribe(&context_store, move |_, event, _| {
+                if let ContextStoreEvent::ContextsRemoved { ids } = event {
+                    removals.borrow_mut().push(ids.clone());
+                }
+            })
+        });
+
+        context_store.update(cx, |context_store, cx| {
+            for (url, text) in [
+                ("a", "first"),
+                ("b", "second"),
+                ("c", "third"),
+                ("d", "fourth"),
+            ] {
+                context_store.add_fetched_url(format!("https://zed.dev/{url}"), None, text, cx);
+            }
+        });
+
+        let (total_len, text_lens) = context_store.read_with(cx, |context_store, _| {
+            let text_lens = context_store
+                .context()
+                .iter()
+                .map(|context| context.text_len())
+                .collect::<Vec<_>>();
+            (context_store.total_text_len(), text_lens)
+        });
+
+        context_store.update(cx, |context_store, cx| {
+            context_store.remove_contexts(&[ContextId(2), ContextId(1)], cx);
+
+            assert_eq!(
+                context_store
+                    .context()
+                    .iter()
+                    .map(|context| context.id())
+                    .collect::<Vec<_>>(),
+                vec![ContextId(0), ContextId(3)]
+            );
+            assert!(context_store.includes_url("https://zed.dev/b").is_none());
+            assert_eq!(context_store.total_text_len(), text_lens[0] + text_lens[3]);
+            assert_eq!(
+                context_store.estimated_token_count(),
+                estimate_token_count(text_lens[0] + text_lens[3])
+            );
+        });
+        assert_eq!(
+            *removals.borrow(),
+            vec![vec![ContextId(1), ContextId(2)]],
+            "The removal should be reported once"
+        );
+
+        context_store.update(cx, |context_store, cx| {
+            assert_eq!(
+                context_store.undo_last_change(cx),
+                Some(UndoneContextChange::Removal(vec![
+                    ContextId(1),
+                    ContextId(2)
+                ]))
+            );
+            assert_eq!(
+                context_store
+                    .context()
+                    .iter()
+                    .map(|context| context.id())
+                    .collect::<Vec<_>>(),
+                vec![ContextId(0), ContextId(1), ContextId(2), ContextId(3)]
+            );
+            assert_eq!(
+                context_store.includes_url("https://zed.dev/b"),
+                Some(ContextId(1))
+            );
+            assert_eq!(
+                context_store.provenance(ContextId(1), cx),
+                Some(ContextProvenance::FetchedUrl("https://zed.dev/b".into()))
+            );
+            assert_eq!(context_store.total_text_len(), total_len);
+
+            // Restored context can still have its insertion undone.
+            assert_eq!(
+                context_store.undo_last_change(cx),
+                Some(UndoneContextChange::Insertion(ContextId(3)))
+            );
+        });
+    }
+
+    #[gpui::test]
+    async fn test_fetched_url_is_named_by_its_title(cx: &mut TestAppContext) {
+        let TestContext {
+            context_store, cx, ..
+        } = init_test(json!({}), cx).await;
+
+        context_store.update(cx, |context_store, cx| {
+            context_store.add_fetched_url(
+                "https://zed.dev/docs".into(),
+                Some("Zed Docs".into()),
+                "docs",
+                cx,
+            );
+            context_store.add_fetched_url("https://zed.dev/raw".into(), None, "raw", cx);
+
+            let snapshots = context_store
+                .context()
+                .iter()
+                .flat_map(|context| context.snapshot(cx))
+                .map(|snapshot| (snapshot.name, snapshot.tooltip))
+                .collect::<Vec<_>>();
+            assert_eq!(
+                snapshots,
+                vec![
+                    ("Zed Docs".into(), Some("https://zed.dev/docs".into())),
+                    (
+                        "https://zed.dev/raw".into(),
+                        Some("https://zed.dev/raw".into())
+                    ),
+                ]
+            );
+        });
+    }
+
+    #[gpui::test]
+    async fn test_undo_last_change(cx: &mut TestAppContext) {
+        let TestContext {
+            context_store, cx, ..
+        } = init_test(json!({}), cx).await;
+
+        context_store.update(cx, |context_store, cx| {
+            assert!(!context_store.can_undo_last_change());
+
+            context_store.add_fetched_url("https://zed.dev/a".into(), None, "a", cx);
+            context_store.add_fetched_url("https://zed.dev/b".into(), None, "b", cx);
+            context_store.add_fetched_url("https://zed.dev/c".into(), None, "c", cx);
+            assert_eq!(
+                context_store.provenance(ContextId(2), cx),
+                Some(ContextProvenance::FetchedUrl("https://zed.dev/c".into()))
+            );
+            // Context removed by other means is skipped.
+            context_store.remove_context(ContextId(2), cx);
+            assert_eq!(context_store.provenance(ContextId(2), cx), None);
+
+            assert_eq!(
+                context_store.undo_last_change(cx),
+                Some(UndoneContextChange::Insertion(ContextId(1)))
+            );
+            assert!(context_store.includes_url("https://zed.dev/b").is_none());
+
+            // Insertions and removals are undone in the order they happened.
+            context_store.add_fetched_url("https://zed.dev/d".into(), None, "d", cx);
+            context_store.remove_contexts(&[ContextId(0)], cx);
+            assert_eq!(
+                context_store.undo_last_change(cx),
+                Some(UndoneContextChange::Removal(vec![ContextId(0)]))
+            );
+            assert_eq!(
+                context_store.undo_last_change(cx),
+                Some(UndoneContextChange::Insertion(ContextId(3)))
+            );
+            assert_eq!(
+                context_store.undo_last_change(cx),
+                Some(UndoneContextChange::Insertion(ContextId(0)))
+            );
+            assert_eq!(context_store.undo_last_change(cx), None);
+            assert!(context_store.context().is_empty());
+
+            // A refresh leaves nothing to undo, so removed context can't come back with the text
+            // it had before the refresh.
+            context_store.add_fetched_url("https://zed.dev/e".into(), None, "e", cx);
+            context_store.add_fetched_url("https://zed.dev/f".into(), None, "f", cx);
+            context_store.remove_contexts(&[ContextId(5)], cx);
+            assert!(context_store.can_undo_last_change());
+            drop(context_store.refresh_all(None, cx));
+            assert!(!context_store.can_undo_last_change());
+            assert_eq!(context_store.undo_last_change(cx), None);
+            assert_eq!(context_store.context().len(), 1);
+        });
+    }
+
+    #[gpui::test]
+    async fn test_cancelled_directory_is_not_inserted(cx: &mut TestAppContext) {
+        let TestContext {
+            context_store,
+            worktree_id,
+            cx,
+            ..
+        } = init_test(json!({ "src": { "lib.rs": "// lib" } }), cx).await;
+
+        let src_path = project_path(worktree_id, "src");
+
+        // Dismissing the picker sets the flag while the directory is still being read.
+        let cancellation_flag = Arc::new(AtomicBool::new(false));
+        let task = context_store.update(cx, |context_store, cx| {
+            context_store.attach_directory(
+                src_path.clone(),
+                DirectoryContextOptions {
+                    exclude: PathMatcher::default(),
+                    ..DirectoryContextOptions::default()
+                },
+                cancellation_flag.clone(),
+                cx,
+            )
+        });
+        cancellation_flag.store(true, Ordering::Relaxed);
+        task.await.unwrap();
+
+        context_store.read_with(cx, |context_store, _cx| {
+            assert!(context_store.context().is_empty());
+            assert!(context_store.includes_directory(&src_path).is_none());
+            assert!(context_store.pending_directories().is_empty());
+        });
+    }
+
+    #[gpui::test]
+    async fn test_directory_progress_is_reported(cx: &mut TestAppContext) {
+        let TestContext {
+            context_store,
+            worktree_id,
+            cx,
+            ..
+        } = init_test(
+            json!({ "src": { "a.rs": "// a", "b.rs": "// b", "c.rs": "// c" } }),
+            cx,
+        )
+        .await;
+
+        let src_path = project_path(worktree_id, "src");
+
+        let progress = Rc::new(RefCell::new(Vec::new()));
+        let _subscription = cx.update(|_, cx| {
+            let progress = progress.clone();
+            cx.observe(&context_store, move |context_store, cx| {
+                progress.borrow_mut().extend(
+                    context_store
+                        .read(cx)
+                        .pending_directories()
+                        .iter()
+                        .map(|pending| (pending.files_read, pending.total_files)),
+                );
+            })
+        });
+
+        context_store
+            .update(cx, |context_store, cx| {
+                context_store.attach_directory(
+                    src_path.clone(),
+                    DirectoryContextOptions {
+                        exclude: PathMatcher::default(),
+                        ..DirectoryContextOptions::default()
+                    },
+                    Arc::default(),
+                    cx,
+                )
+            })
+            .await
+            .unwrap();
+        cx.run_until_parked();
+
+        let progress = progress.borrow().clone();
+        assert_eq!(progress.first(), Some(&(0, 3)));
+        assert_eq!(progress.last(), Some(&(3, 3)));
+        context_store.read_with(cx, |context_store, _cx| {
+            assert!(context_store.pending_directories().is_empty());
+            assert!(context_store.includes_directory(&src_path).is_some());
+        });
+    }
+
+    #[gpui::test]
+    async fn test_excluded_files_are_skipped(cx: &mut TestAppContext) {
+        let TestContext {
+            context_store,
+            worktree_id,
+            cx,
+            ..
+        } = init_test(
+            json!({
+                "web": {
+                    "index.js": "// index",
+                    "app.min.js": "// minified",
+                    "package-lock.json": "{}",
+                    "node_modules": { "dep": { "index.js": "// dep" } },
+                }
+            }),
+            cx,
+        )
+        .await;
+
+        let web_path = project_path(worktree_id, "web");
+        let exclude = PathMatcher::new(
+            &DEFAULT_DIRECTORY_CONTEXT_EXCLUDE_GLOBS
+                .iter()
+                .map(|glob| glob.to_string())
+                .collect::<Vec<_>>(),
+        )
+        .unwrap();
+
+        context_store
+            .update(cx, |context_store, cx| {
+                context_store.attach_directory(
+                    web_path,
+                    DirectoryContextOptions {
+                        exclude,
+                        ..DirectoryContextOptions::default()
+                    },
+                    Arc::default(),
+                    cx,
+                )
+            })
+            .await
+            .unwrap();
+
+        context_store.read_with(cx, |context_store, _cx| {
+            let [AssistantContext::Directory(directory_context)] =
+                context_store.context().as_slice()
+            else {
+                panic!("expected a single directory context");
+            };
+            let paths = directory_context
+                .context_buffers
+                .iter()
+                .map(|context_buffer| context_buffer.text.lines().next().unwrap().to_string())
+                .collect::<Vec<_>>();
+            assert_eq!(paths, ["```js web/index.js"]);
+            assert_eq!(directory_context.excluded_files, 3);
+            assert_eq!(
+                directory_context.snapshot.text.last().unwrap().as_ref(),
+                "[3 files skipped by exclude patterns]\n"
+            );
+        });
+    }
+
+    #[gpui::test]
+    async fn test_directory_file_list_omits_contents(cx: &mut TestAppContext) {
+        let TestContext {
+            context_store,
+            worktree_id,
+            cx,
+            ..
+        } = init_test(
+            json!({
+                "web": {
+                    "index.js": "// index",
+                    "app.min.js": "// minified",
+                    "lib": { "util.js": "// util" },
+                }
+            }),
+            cx,
+        )
+        .await;
+
+        let web_path = project_path(worktree_id, "web");
+
+        context_store
+            .update(cx, |context_store, cx| {
+                context_store.attach_directory(
+                    web_path.clone(),
+                    DirectoryContextOptions {
+                        mode: DirectoryContextMode::FileList,
+                        exclude: PathMatcher::new(&["**/*.min.js".to_string()]).unwrap(),
+                        ..DirectoryContextOptions::default()
+                    },
+                    Arc::default(),
+                    cx,
+                )
+            })
+            .await
+            .unwrap();
+
+        context_store.read_with(cx, |context_store, _cx| {
+            let [AssistantContext::Directory(directory_context)] =
+                context_store.context().as_slice()
+            else {
+                panic!("expected a single directory context");
+            };
+            assert_eq!(directory_context.mode, DirectoryContextMode::FileList);
+            assert!(directory_context.context_buffers.is_empty());
+            assert_eq!(
+                directory_context.snapshot.text.join(""),
+                "Files in web (contents not included):\n\
+                 web/index.js (8 B)\n\
+                 web/lib/util.js (7 B)\n\
+                 [1 file skipped by exclude patterns]\n"
+            );
+
+            // Files in a listed directory aren't attached through it.
+            let file_path = project_path(worktree_id, "web/index.js");
+            assert!(context_store
+                .will_include_file_path_via_directory(&file_path)
+                .is_none());
+        });
+    }
+
+    #[gpui::test]
+    async fn test_attach_directory_uses_default_excludes(cx: &mut TestAppContext) {
+        let TestContext {
+            context_store,
+            worktree_id,
+            cx,
+            ..
+        } = init_test(
+            json!({
+                "src": {
+                    "lib.rs": "// lib",
+                    "Cargo.lock": "# lock",
+                    "nested": { "mod.rs": "// mod" },
+                }
+            }),
+            cx,
+        )
+        .await;
+
+        context_store
+            .update(cx, |context_store, cx| {
+                context_store.attach_directory(
+                    project_path(worktree_id, "src"),
+                    DirectoryContextOptions::default(),
+                    Arc::default(),
+                    cx,
+                )
+            })
+            .await
+            .unwrap();
+
+        context_store.read_with(cx, |context_store, _cx| {
+            let [AssistantContext::Directory(directory_context)] =
+                context_store.context().as_slice()
+            else {
+                panic!("expected a single directory context");
+            };
+            let paths = directory_context
+                .context_buffers
+                .iter()
+                .map(|context_buffer| context_buffer.text.lines().next().unwrap().to_string())
+                .collect::<Vec<_>>();
+            assert_eq!(paths, ["```rs src/lib.rs", "```rs src/nested/mod.rs"]);
+            assert_eq!(directory_context.excluded_files, 1);
+        });
+    }
+
+    #[gpui::test]
+    async fn test_build_directory_context(cx: &mut TestAppContext) {
+        init_settings(cx);
+
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            path!("/root"),
+            json!({
+                "src": {
+                    "a.rs": "// a",
+                    "b.rs": "// b",
+                    "Cargo.lock": "# lock",
+                    "nested": { "mod.rs": "// mod" },
+                }
+            }),
+        )
+        .await;
+        let project = Project::test(fs, [path!("/root").as_ref()], cx).await;
+        let worktree_id = project.read_with(cx, |project, cx| {
+            project.worktrees(cx).next().unwrap().read(cx).id()
+        });
+        let build = |options: DirectoryContextOptions, cx: &mut TestAppContext| {
+            cx.update(|cx| {
+                build_directory_context(
+                    project.clone(),
+                    worktree_id,
+                    Path::new("src").into(),
+                    options,
+                    cx,
+                )
+            })
+        };
+
+        let text = build(DirectoryContextOptions::default(), cx).await.unwrap();
+        assert!(text.contains("```rs src/a.rs"));
+        assert!(text.contains("```rs src/b.rs"));
+        assert!(text.contains("```rs src/nested/mod.rs"));
+        assert!(!text.contains("# lock"));
+        assert!(text.contains("[1 file skipped by exclude patterns]"));
+
+        let text = build(
+            DirectoryContextOptions {
+                recursive: false,
+                exclude: PathMatcher::default(),
+                ..Default::default()
+            },
+            cx,
+        )
+        .await
+        .unwrap();
+        assert!(text.contains("# lock"));
+        assert!(!text.contains("src/nested/mod.rs"));
+
+        let first_file_len = build(
+            DirectoryContextOptions {
+                recursive: false,
+                ..Default::default()
+            },
+            cx,
+        )
+        .await
+        .unwrap()
+        .find("```rs src/b.rs")
+        .unwrap();
+        let text = build(
+            DirectoryContextOptions {
+                recursive: false,
+                max_bytes: first_file_len,
+                ..Default::default()
+            },
+            cx,
+        )
+        .await
+        .unwrap();
+        assert!(text.contains("```rs src/a.rs"));
+        assert!(!text.contains("src/b.rs"));
+        assert!(text.contains("1 of 2 files omitted"));
+
+        // A first file over the budget is cut down instead of leaving the directory empty.
+        let text = build(
+            DirectoryContextOptions {
+                recursive: false,
+                max_bytes: 5,
+                ..Default::default()
+            },
+            cx,
+        )
+        .await
+        .unwrap();
+        assert!(text.starts_with("```rs src/a.rs\n… (file truncated at 5 bytes)\n```\n"));
+        assert!(!text.contains("src/b.rs"));
+        assert!(text.contains("1 of 2 files omitted"));
+
+        let text = build(
+            DirectoryContextOptions {
+                mode: DirectoryContextMode::FileList,
+                ..Default::default()
+            },
+            cx,
+        )
+        .await
+        .unwrap();
+        assert!(text.starts_with("Files in src (contents not included):\n"));
+        assert!(text.contains("src/nested/mod.rs ("));
+        assert!(!text.contains("// a"));
+    }
+
+    #[gpui::test]
+    async fn test_attached_directory_matches_built_context(cx: &mut TestAppContext) {
+        let TestContext {
+            project,
+            context_store,
+            worktree_id,
+            cx,
+            ..
+        } = init_test(
+            json!({ "src": { "a.rs": "// a", "nested": { "b.rs": "// b" } } }),
+            cx,
+        )
+        .await;
+
+        for mode in [
+            DirectoryContextMode::Contents,
+            DirectoryContextMode::FileList,
+        ] {
+            let options = DirectoryContextOptions {
+                mode,
+                ..DirectoryContextOptions::default()
+            };
+            let built = cx
+                .update(|_, cx| {
+                    build_directory_context(
+                        project.clone(),
+                        worktree_id,
+                        Path::new("src").into(),
+                        options.clone(),
+                        cx,
+                    )
+                })
+                .await
+                .unwrap();
+            context_store
+                .update(cx, |context_store, cx| {
+                    context_store.attach_directory(
+                        project_path(worktree_id, "src"),
+                        options,
+                        Arc::default(),
+                        cx,
+                    )
+                })
+                .await
+                .unwrap();
+
+            context_store.update(cx, |context_store, cx| {
+                let [AssistantContext::Directory(directory_context)] =
+                    context_store.context().as_slice()
+                else {
+                    panic!("expected a single directory context");
+                };
+                assert_eq!(directory_context.snapshot.text.join("\n"), built);
+                context_store.clear();
+            });
+        }
+    }
+
+    #[gpui::test]
+    async fn test_readding_thread_refreshes_context(cx: &mut TestAppContext) {
+        let TestContext {
+            project,
+            context_store,
+            cx,
+            ..
+        } = init_test(json!({}), cx).await;
+
+        let prompt_builder = Arc::new(PromptBuilder::new(None).unwrap());
+        let new_thread = |text: &str, cx: &mut gpui::VisualTestContext| {
+            cx.new(|cx| {
+                let mut thread =
+                    Thread::new(project.clone(), Arc::default(), prompt_builder.clone(), cx);
+                thread.set_summary("Same summary", cx);
+                thread.insert_message(Role::User, text, cx);
+                thread
+            })
+        };
+        let thread_a = new_thread("first", cx);
+        let thread_b = new_thread("other", cx);
+
+        context_store.update(cx, |context_store, cx| {
+            context_store.add_thread(thread_a.clone(), ThreadTextOptions::default(), cx);
+            context_store.add_thread(thread_b.clone(), ThreadTextOptions::default(), cx);
+        });
+        let (id_a, id_b) = context_store.read_with(cx, |context_store, cx| {
+            assert_eq!(context_store.context().len(), 2);
+            (
+                context_store
+                    .includes_thread(&thread_a.read(cx).id())
+                    .unwrap(),
+                context_store
+                    .includes_thread(&thread_b.read(cx).id())
+                    .unwrap(),
+            )
+        });
+        assert_ne!(id_a, id_b);
+        context_store.read_with(cx, |context_store, cx| {
+            assert_eq!(
+                context_store.provenance(id_a, cx),
+                Some(ContextProvenance::Thread(thread_a.read(cx).id().clone()))
+            );
+        });
+
+        thread_a.update(cx, |thread, cx| {
+            thread.insert_message(Role::User, "second", cx);
+        });
+        context_store.update(cx, |context_store, cx| {
+            context_store.add_thread(thread_a.clone(), ThreadTextOptions::default(), cx);
+        });
+
+        context_store.read_with(cx, |context_store, cx| {
+            assert_eq!(context_store.context().len(), 2);
+            assert_eq!(
+                context_store.includes_thread(&thread_a.read(cx).id()),
+                Some(id_a)
+            );
+            let text = context_store
+                .snapshot(cx)
+                .find(|snapshot| snapshot.id == id_a)
+                .unwrap()
+                .text;
+            assert_eq!(text[0].as_ref(), "User:\nfirst\nUser:\nsecond\n");
+        });
+    }
+
+    #[gpui::test]
+    async fn test_refresh_all_rewalks_directories_and_marks_missing_stale(cx: &mut TestAppContext) {
+        let TestContext {
+            fs,
+            project,
+            context_store,
+            worktree_id,
+            cx,
+        } = init_test(json!({ "src": { "a.rs": "// a" } }), cx).await;
+
+        context_store
+            .update(cx, |context_store, cx| {
+                context_store.attach_directory(
+                    project_path(worktree_id, "src"),
+                    DirectoryContextOptions::default(),
+                    Arc::default(),
+                    cx,
+                )
+            })
+            .await
+            .unwrap();
+        let id = context_store.read_with(cx, |context_store, _cx| {
+            context_store
+                .includes_directory(&project_path(worktree_id, "src"))
+                .unwrap()
+        });
+
+        fs.insert_file(path!("/root/src/b.rs"), b"// b".to_vec())
+            .await;
+        cx.run_until_parked();
+        context_store
+            .update(cx, |context_store, cx| context_store.refresh_all(None, cx))
+            .await;
+
+        context_store.read_with(cx, |context_store, cx| {
+            assert!(!context_store.is_stale(id));
+            let snapshot = context_store.snapshot(cx).next().unwrap();
+            assert_eq!(snapshot.id, id);
+            assert_eq!(snapshot.text.len(), 2);
+        });
+
+        project.update(cx, |project, cx| project.remove_worktree(worktree_id, cx));
+        context_store
+            .update(cx, |context_store, cx| context_store.refresh_all(None, cx))
+            .await;
+
+        context_store.read_with(cx, |context_store, cx| {
+            assert!(context_store.is_stale(id));
+            assert_eq!(context_store.snapshot(cx).next().unwrap().text.len(), 2);
+        });
+    }
+}
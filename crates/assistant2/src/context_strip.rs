@@ -9,18 +9,19 @@ use gpui::{
 };
 use itertools::Itertools;
 use language::Buffer;
-use ui::{prelude::*, KeyBinding, PopoverMenu, PopoverMenuHandle, Tooltip};
+use project::ProjectPath;
+use ui::{prelude::*, ContextMenu, KeyBinding, PopoverMenu, PopoverMenuHandle, Tooltip};
 use workspace::{notifications::NotifyResultExt, Workspace};
 
-use crate::context::ContextKind;
-use crate::context_picker::{ConfirmBehavior, ContextPicker};
+use crate::context::{AssistantContext, ContextId, ContextKind, ContextProvenance};
+use crate::context_picker::ContextPicker;
 use crate::context_store::ContextStore;
 use crate::thread::Thread;
 use crate::thread_store::ThreadStore;
 use crate::ui::ContextPill;
 use crate::{
     AcceptSuggestedContext, AssistantPanel, FocusDown, FocusLeft, FocusRight, FocusUp,
-    RemoveAllContext, RemoveFocusedContext, ToggleContextPicker,
+    RemoveAllContext, RemoveFocusedContext, ToggleContextPicker, UndoContextChange,
 };
 
 pub struct ContextStrip {
@@ -30,9 +31,14 @@ pub struct ContextStrip {
     focus_handle: FocusHandle,
     suggest_context_kind: SuggestContextKind,
     workspace: WeakEntity<Workspace>,
+    thread_store: Option<WeakEntity<ThreadStore>>,
     _subscriptions: Vec<Subscription>,
     focused_index: Option<usize>,
     children_bounds: Option<Vec<Bounds<Pixels>>>,
+    /// The context marked for removal, while selecting context to remove several at once.
+    marked_context: Option<HashSet<ContextId>>,
+    /// The only kind of context shown, or `None` to show all of it.
+    kind_filter: Option<ContextKind>,
 }
 
 impl ContextStrip {
@@ -52,7 +58,7 @@ impl ContextStrip {
                 thread_store.clone(),
                 context_store.downgrade(),
                 editor.clone(),
-                ConfirmBehavior::KeepOpen,
+                None,
                 window,
                 cx,
             )
@@ -61,6 +67,21 @@ impl ContextStrip {
         let focus_handle = cx.focus_handle();
 
         let subscriptions = vec![
+            // Keeps the progress of directories that are still being read up to date.
+            cx.observe(&context_store, |this, context_store, cx| {
+                // Nothing would be shown once the last context of the filtered kind is removed.
+                if let Some(kind) = this.kind_filter {
+                    if context_store
+                        .read(cx)
+                        .entries_of_kind(kind)
+                        .next()
+                        .is_none()
+                    {
+                        this.kind_filter = None;
+                    }
+                }
+                cx.notify();
+            }),
             cx.subscribe_in(&context_picker, window, Self::handle_context_picker_event),
             cx.on_focus(&focus_handle, window, Self::handle_focus),
             cx.on_blur(&focus_handle, window, Self::handle_blur),
@@ -73,17 +94,43 @@ impl ContextStrip {
             focus_handle,
             suggest_context_kind,
             workspace,
+            thread_store,
             _subscriptions: subscriptions,
             focused_index: None,
             children_bounds: None,
+            marked_context: None,
+            kind_filter: None,
         }
     }
 
+    /// Only shows context of `kind`, or all context when `kind` is `None`.
+    pub fn set_kind_filter(&mut self, kind: Option<ContextKind>, cx: &mut Context<Self>) {
+        self.kind_filter = kind;
+        self.focused_index = None;
+        cx.notify();
+    }
+
+    fn refresh_context(&mut self, cx: &mut Context<Self>) {
+        let thread_store = self
+            .thread_store
+            .as_ref()
+            .and_then(|thread_store| thread_store.upgrade());
+        self.context_store
+            .update(cx, |context_store, cx| {
+                context_store.refresh_all(thread_store, cx)
+            })
+            .detach();
+    }
+
     fn suggested_context(&self, cx: &Context<Self>) -> Option<SuggestedContext> {
-        match self.suggest_context_kind {
+        let suggested = match self.suggest_context_kind {
             SuggestContextKind::File => self.suggested_file(cx),
             SuggestContextKind::Thread => self.suggested_thread(cx),
-        }
+        };
+        suggested.filter(|suggested| {
+            self.kind_filter
+                .map_or(true, |kind| suggested.kind() == kind)
+        })
     }
 
     fn suggested_file(&self, cx: &Context<Self>) -> Option<SuggestedContext> {
@@ -94,12 +141,17 @@ impl ContextStrip {
         let active_buffer_entity = editor.buffer().read(cx).as_singleton()?;
         let active_buffer = active_buffer_entity.read(cx);
 
-        let path = active_buffer.file()?.path();
+        let file = active_buffer.file()?;
+        let path = file.path();
+        let project_path = ProjectPath {
+            worktree_id: file.worktree_id(cx),
+            path: path.clone(),
+        };
 
         if self
             .context_store
             .read(cx)
-            .will_include_buffer(active_buffer.remote_id(), path)
+            .will_include_buffer(active_buffer.remote_id(), &project_path)
             .is_some()
         {
             return None;
@@ -159,6 +211,20 @@ impl ContextStrip {
         cx.emit(ContextStripEvent::PickerDismissed);
     }
 
+    /// Moves the keyboard focus to the pill of newly inserted context, if a pill is focused.
+    pub fn focus_context(&mut self, id: ContextId, cx: &mut Context<Self>) {
+        if self.focused_index.is_none() {
+            return;
+        }
+        if let Some(ix) = visible_context(self.context_store.read(cx), self.kind_filter)
+            .iter()
+            .position(|context| context.id() == id)
+        {
+            self.focused_index = Some(ix);
+            cx.notify();
+        }
+    }
+
     fn handle_focus(&mut self, _window: &mut Window, cx: &mut Context<Self>) {
         self.focused_index = self.last_pill_index();
         cx.notify();
@@ -286,11 +352,15 @@ impl ContextStrip {
         cx: &mut Context<Self>,
     ) {
         if let Some(index) = self.focused_index {
+            let kind_filter = self.kind_filter;
             let mut is_empty = false;
 
-            self.context_store.update(cx, |this, _cx| {
-                if let Some(item) = this.context().get(index) {
-                    this.remove_context(item.id());
+            self.context_store.update(cx, |this, cx| {
+                if let Some(id) = visible_context(this, kind_filter)
+                    .get(index)
+                    .map(|context| context.id())
+                {
+                    this.remove_context(id, cx);
                 }
 
                 is_empty = this.context().is_empty();
@@ -305,6 +375,67 @@ impl ContextStrip {
         }
     }
 
+    fn toggle_selecting(&mut self, cx: &mut Context<Self>) {
+        self.marked_context = match self.marked_context {
+            Some(_) => None,
+            None => Some(HashSet::default()),
+        };
+        cx.notify();
+    }
+
+    fn toggle_marked(&mut self, id: ContextId, cx: &mut Context<Self>) {
+        if let Some(marked_context) = &mut self.marked_context {
+            if !marked_context.remove(&id) {
+                marked_context.insert(id);
+            }
+            cx.notify();
+        }
+    }
+
+    /// Removes the marked context at once, so a single undo attaches all of it again.
+    fn remove_marked_context(&mut self, cx: &mut Context<Self>) {
+        let Some(marked_context) = self.marked_context.take() else {
+            return;
+        };
+        let ids = marked_context.into_iter().collect::<Vec<_>>();
+        self.context_store
+            .update(cx, |store, cx| store.remove_contexts(&ids, cx));
+        self.focused_index = None;
+        cx.notify();
+    }
+
+    /// Reveals where attached context came from: files, directories and diffs in the project
+    /// panel, and fetched URLs in the browser. Threads aren't opened, as that would leave the
+    /// thread being written.
+    fn reveal_context_source(&self, id: ContextId, cx: &mut Context<Self>) {
+        let project_path = match self.context_store.read(cx).provenance(id, cx) {
+            Some(
+                ContextProvenance::File(project_path)
+                | ContextProvenance::Directory(project_path)
+                | ContextProvenance::GitDiff(project_path),
+            ) => project_path,
+            Some(ContextProvenance::FetchedUrl(url)) => {
+                cx.open_url(&url);
+                return;
+            }
+            Some(ContextProvenance::Thread(_)) | None => return,
+        };
+        let Some(workspace) = self.workspace.upgrade() else {
+            return;
+        };
+        let project = workspace.read(cx).project().clone();
+        let Some(entry_id) = project
+            .read(cx)
+            .entry_for_path(&project_path, cx)
+            .map(|entry| entry.id)
+        else {
+            return;
+        };
+        project.update(cx, |_, cx| {
+            cx.emit(project::Event::RevealInProjectPanel(entry_id));
+        });
+    }
+
     fn is_suggested_focused<T>(&self, context: &Vec<T>) -> bool {
         // We only suggest one item after the actual context
         self.focused_index == Some(context.len())
@@ -319,7 +450,7 @@ impl ContextStrip {
         if let Some(suggested) = self.suggested_context(cx) {
             let context_store = self.context_store.read(cx);
 
-            if self.is_suggested_focused(context_store.context()) {
+            if self.is_suggested_focused(&visible_context(context_store, self.kind_filter)) {
                 self.add_suggested_context(&suggested, window, cx);
             }
         }
@@ -350,6 +481,80 @@ impl ContextStrip {
 
         cx.notify();
     }
+
+    /// Offers to show only one kind of context, listing how much of each kind is attached.
+    fn render_kind_filter(&self, cx: &mut Context<Self>) -> Option<impl IntoElement> {
+        let context_store = self.context_store.read(cx);
+        let kind_counts = ContextKind::ALL
+            .into_iter()
+            .map(|kind| (kind, context_store.entries_of_kind(kind).count()))
+            .filter(|(_, count)| *count > 0)
+            .collect::<Vec<_>>();
+        // Filtering by the only kind there is would show the same context.
+        if kind_counts.len() < 2 && self.kind_filter.is_none() {
+            return None;
+        }
+        let total_count = context_store.context().len();
+        let kind_filter = self.kind_filter;
+        let tooltip = match kind_filter {
+            Some(kind) => format!("Showing Only {}", kind.plural_label()),
+            None => "Filter Context by Kind".to_string(),
+        };
+
+        let context_strip = cx.entity().downgrade();
+        Some(
+            PopoverMenu::new("context-kind-filter")
+                .trigger_with_tooltip(
+                    IconButton::new("context-kind-filter-button", IconName::Filter)
+                        .icon_size(IconSize::Small)
+                        .toggle_state(kind_filter.is_some()),
+                    Tooltip::text(tooltip),
+                )
+                .menu(move |window, cx| {
+                    let context_strip = context_strip.clone();
+                    let kind_counts = kind_counts.clone();
+                    Some(ContextMenu::build(window, cx, move |menu, _window, _cx| {
+                        let set_kind_filter = |kind: Option<ContextKind>| {
+                            let context_strip = context_strip.clone();
+                            move |_: &mut Window, cx: &mut App| {
+                                context_strip
+                                    .update(cx, |this, cx| this.set_kind_filter(kind, cx))
+                                    .ok();
+                            }
+                        };
+
+                        let menu = menu.toggleable_entry(
+                            format!("All ({total_count})"),
+                            kind_filter.is_none(),
+                            IconPosition::End,
+                            None,
+                            set_kind_filter(None),
+                        );
+                        kind_counts.iter().fold(menu, |menu, (kind, count)| {
+                            menu.toggleable_entry(
+                                format!("{} ({count})", kind.plural_label()),
+                                kind_filter == Some(*kind),
+                                IconPosition::End,
+                                None,
+                                set_kind_filter(Some(*kind)),
+                            )
+                        })
+                    }))
+                })
+                .anchor(gpui::Corner::BottomLeft),
+        )
+    }
+}
+
+/// The attached context shown as pills, which is only the context of `kind_filter` if set.
+fn visible_context(
+    context_store: &ContextStore,
+    kind_filter: Option<ContextKind>,
+) -> Vec<&AssistantContext> {
+    match kind_filter {
+        Some(kind) => context_store.entries_of_kind(kind).collect(),
+        None => context_store.context().iter().collect(),
+    }
 }
 
 impl Focusable for ContextStrip {
@@ -360,16 +565,31 @@ impl Focusable for ContextStrip {
 
 impl Render for ContextStrip {
     fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let kind_filter_menu = self.render_kind_filter(cx);
         let context_store = self.context_store.read(cx);
-        let context = context_store
-            .context()
-            .iter()
+        let context = visible_context(context_store, self.kind_filter)
+            .into_iter()
             .flat_map(|context| context.snapshot(cx))
             .collect::<Vec<_>>();
         let context_picker = self.context_picker.clone();
         let focus_handle = self.focus_handle.clone();
 
+        let pending_directories = if self
+            .kind_filter
+            .map_or(true, |kind| kind == ContextKind::Directory)
+        {
+            context_store.pending_directories().to_vec()
+        } else {
+            Vec::new()
+        };
+        let stale_context = context
+            .iter()
+            .map(|context| context.id)
+            .filter(|id| context_store.is_stale(*id))
+            .collect::<HashSet<ContextId>>();
         let suggested_context = self.suggested_context(cx);
+        let can_undo_change = context_store.can_undo_last_change();
+        let marked_context = self.marked_context.clone();
 
         let dupe_names = context
             .iter()
@@ -435,51 +655,91 @@ impl Render for ContextStrip {
                     })
                     .with_handle(self.context_picker_menu_handle.clone()),
             )
-            .when(context.is_empty() && suggested_context.is_none(), {
-                |parent| {
-                    parent.child(
-                        h_flex()
-                            .ml_1p5()
-                            .gap_2()
-                            .child(
-                                Label::new("Add Context")
-                                    .size(LabelSize::Small)
-                                    .color(Color::Muted),
-                            )
-                            .opacity(0.5)
-                            .children(
-                                KeyBinding::for_action_in(
-                                    &ToggleContextPicker,
-                                    &focus_handle,
-                                    window,
-                                    cx,
+            .when(
+                context.is_empty() && pending_directories.is_empty() && suggested_context.is_none(),
+                {
+                    |parent| {
+                        parent.child(
+                            h_flex()
+                                .ml_1p5()
+                                .gap_2()
+                                .child(
+                                    Label::new("Add Context")
+                                        .size(LabelSize::Small)
+                                        .color(Color::Muted),
                                 )
-                                .map(|binding| binding.into_any_element()),
-                            ),
-                    )
-                }
-            })
+                                .opacity(0.5)
+                                .children(
+                                    KeyBinding::for_action_in(
+                                        &ToggleContextPicker,
+                                        &focus_handle,
+                                        window,
+                                        cx,
+                                    )
+                                    .map(|binding| binding.into_any_element()),
+                                ),
+                        )
+                    }
+                },
+            )
             .children(context.iter().enumerate().map(|(i, context)| {
+                let stale = stale_context.contains(&context.id);
+                let marked = marked_context
+                    .as_ref()
+                    .map(|marked_context| marked_context.contains(&context.id));
                 ContextPill::added(
                     context.clone(),
                     dupe_names.contains(&context.name),
                     self.focused_index == Some(i),
-                    Some({
+                    // While selecting, context is removed through the selection instead.
+                    if marked.is_none() {
                         let id = context.id;
                         let context_store = self.context_store.clone();
-                        Rc::new(cx.listener(move |_this, _event, _window, cx| {
-                            context_store.update(cx, |this, _cx| {
-                                this.remove_context(id);
+                        Some(Rc::new(cx.listener(move |_this, _event, _window, cx| {
+                            context_store.update(cx, |this, cx| {
+                                this.remove_context(id, cx);
                             });
                             cx.notify();
-                        }))
-                    }),
+                        })))
+                    } else {
+                        None
+                    },
                 )
-                .on_click(Rc::new(cx.listener(move |this, _, _window, cx| {
-                    this.focused_index = Some(i);
-                    cx.notify();
+                .stale(stale)
+                .marked(marked)
+                .on_click(Rc::new(cx.listener({
+                    let id = context.id;
+                    move |this, _, _window, cx| {
+                        if this.marked_context.is_some() {
+                            this.toggle_marked(id, cx);
+                            return;
+                        }
+                        this.focused_index = Some(i);
+                        this.reveal_context_source(id, cx);
+                        cx.notify();
+                    }
                 })))
             }))
+            .children(
+                pending_directories
+                    .into_iter()
+                    .enumerate()
+                    .map(|(ix, pending)| {
+                        let name = pending
+                            .project_path
+                            .path
+                            .file_name()
+                            .unwrap_or(pending.project_path.path.as_os_str())
+                            .to_string_lossy()
+                            .into_owned();
+                        ContextPill::pending(
+                            ix,
+                            name.into(),
+                            pending.files_read,
+                            pending.total_files,
+                        )
+                    }),
+            )
             .when_some(suggested_context, |el, suggested| {
                 el.child(
                     ContextPill::suggested(
@@ -495,30 +755,95 @@ impl Render for ContextStrip {
                     ))),
                 )
             })
+            .when(can_undo_change, |parent| {
+                parent.child(
+                    IconButton::new("undo-context-change", IconName::Undo)
+                        .icon_size(IconSize::Small)
+                        .tooltip({
+                            let focus_handle = focus_handle.clone();
+                            move |window, cx| {
+                                Tooltip::for_action_in(
+                                    "Undo Last Context Change",
+                                    &UndoContextChange,
+                                    &focus_handle,
+                                    window,
+                                    cx,
+                                )
+                            }
+                        })
+                        .on_click(cx.listener({
+                            let focus_handle = focus_handle.clone();
+                            move |_this, _event, window, cx| {
+                                focus_handle.dispatch_action(&UndoContextChange, window, cx);
+                            }
+                        })),
+                )
+            })
             .when(!context.is_empty(), {
                 move |parent| {
-                    parent.child(
-                        IconButton::new("remove-all-context", IconName::Eraser)
-                            .icon_size(IconSize::Small)
-                            .tooltip({
-                                let focus_handle = focus_handle.clone();
-                                move |window, cx| {
-                                    Tooltip::for_action_in(
-                                        "Remove All Context",
-                                        &RemoveAllContext,
-                                        &focus_handle,
-                                        window,
-                                        cx,
-                                    )
-                                }
-                            })
-                            .on_click(cx.listener({
-                                let focus_handle = focus_handle.clone();
-                                move |_this, _event, window, cx| {
-                                    focus_handle.dispatch_action(&RemoveAllContext, window, cx);
-                                }
-                            })),
-                    )
+                    parent
+                        .children(kind_filter_menu)
+                        .map(|parent| match &marked_context {
+                            Some(marked_context) => parent
+                                .child(
+                                    IconButton::new("remove-marked-context", IconName::Trash)
+                                        .icon_size(IconSize::Small)
+                                        .disabled(marked_context.is_empty())
+                                        .tooltip(Tooltip::text(format!(
+                                            "Remove {} Selected",
+                                            marked_context.len()
+                                        )))
+                                        .on_click(cx.listener(|this, _event, _window, cx| {
+                                            this.remove_marked_context(cx);
+                                        })),
+                                )
+                                .child(
+                                    IconButton::new("cancel-context-selection", IconName::Close)
+                                        .icon_size(IconSize::Small)
+                                        .tooltip(Tooltip::text("Cancel Selection"))
+                                        .on_click(cx.listener(|this, _event, _window, cx| {
+                                            this.toggle_selecting(cx);
+                                        })),
+                                ),
+                            None => parent.child(
+                                IconButton::new("select-context", IconName::ListX)
+                                    .icon_size(IconSize::Small)
+                                    .tooltip(Tooltip::text("Select Context to Remove"))
+                                    .on_click(cx.listener(|this, _event, _window, cx| {
+                                        this.toggle_selecting(cx);
+                                    })),
+                            ),
+                        })
+                        .child(
+                            IconButton::new("refresh-context", IconName::RotateCw)
+                                .icon_size(IconSize::Small)
+                                .tooltip(Tooltip::text("Refresh Directories and Threads"))
+                                .on_click(cx.listener(|this, _event, _window, cx| {
+                                    this.refresh_context(cx);
+                                })),
+                        )
+                        .child(
+                            IconButton::new("remove-all-context", IconName::Eraser)
+                                .icon_size(IconSize::Small)
+                                .tooltip({
+                                    let focus_handle = focus_handle.clone();
+                                    move |window, cx| {
+                                        Tooltip::for_action_in(
+                                            "Remove All Context",
+                                            &RemoveAllContext,
+                                            &focus_handle,
+                                            window,
+                                            cx,
+                                        )
+                                    }
+                                })
+                                .on_click(cx.listener({
+                                    let focus_handle = focus_handle.clone();
+                                    move |_this, _event, window, cx| {
+                                        focus_handle.dispatch_action(&RemoveAllContext, window, cx);
+                                    }
+                                })),
+                        )
                 }
             })
     }
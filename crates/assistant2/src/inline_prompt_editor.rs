@@ -6,7 +6,7 @@ use crate::context_strip::{ContextStrip, ContextStripEvent, SuggestContextKind};
 use crate::terminal_codegen::TerminalCodegen;
 use crate::thread_store::ThreadStore;
 use crate::{CycleNextInlineAssist, CyclePreviousInlineAssist};
-use crate::{RemoveAllContext, ToggleContextPicker};
+use crate::{RemoveAllContext, ToggleContextPicker, UndoContextChange};
 use client::ErrorExt;
 use collections::VecDeque;
 use editor::{
@@ -112,6 +112,7 @@ impl<T: 'static> Render for PromptEditor<T> {
                     .on_action(cx.listener(Self::move_up))
                     .on_action(cx.listener(Self::move_down))
                     .on_action(cx.listener(Self::remove_all_context))
+                    .on_action(cx.listener(Self::undo_context_change))
                     .capture_action(cx.listener(Self::cycle_prev))
                     .capture_action(cx.listener(Self::cycle_next))
                     .child(
@@ -359,6 +360,16 @@ impl<T: 'static> PromptEditor<T> {
         cx.notify();
     }
 
+    fn undo_context_change(
+        &mut self,
+        _: &UndoContextChange,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.context_store
+            .update(cx, |store, cx| store.undo_last_change(cx));
+    }
+
     fn cancel(
         &mut self,
         _: &editor::actions::Cancel,
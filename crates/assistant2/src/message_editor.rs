@@ -26,12 +26,12 @@ use workspace::{Toast, Workspace};
 
 use crate::assistant_model_selector::AssistantModelSelector;
 use crate::context_picker::{ConfirmBehavior, ContextPicker};
-use crate::context_store::{refresh_context_store_text, ContextStore};
+use crate::context_store::{refresh_context_store_text, ContextStore, ContextStoreEvent};
 use crate::context_strip::{ContextStrip, ContextStripEvent, SuggestContextKind};
 use crate::thread::{RequestKind, Thread};
 use crate::thread_store::ThreadStore;
 use crate::tool_selector::ToolSelector;
-use crate::{Chat, ChatMode, RemoveAllContext, ToggleContextPicker};
+use crate::{Chat, ChatMode, RemoveAllContext, ToggleContextPicker, UndoContextChange};
 
 pub struct MessageEditor {
     thread: Entity<Thread>,
@@ -49,6 +49,10 @@ pub struct MessageEditor {
 }
 
 impl MessageEditor {
+    pub(crate) fn context_store(&self) -> &Entity<ContextStore> {
+        &self.context_store
+    }
+
     pub fn new(
         fs: Arc<dyn Fs>,
         workspace: WeakEntity<Workspace>,
@@ -77,7 +81,7 @@ impl MessageEditor {
                 Some(thread_store.clone()),
                 context_store.downgrade(),
                 editor.downgrade(),
-                ConfirmBehavior::Close,
+                Some(ConfirmBehavior::Close),
                 window,
                 cx,
             )
@@ -104,6 +108,7 @@ impl MessageEditor {
                 Self::handle_inline_context_picker_event,
             ),
             cx.subscribe_in(&context_strip, window, Self::handle_context_strip_event),
+            cx.subscribe(&context_store, Self::handle_context_store_event),
         ];
 
         Self {
@@ -153,6 +158,16 @@ impl MessageEditor {
         cx.notify();
     }
 
+    fn undo_context_change(
+        &mut self,
+        _: &UndoContextChange,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.context_store
+            .update(cx, |store, cx| store.undo_last_change(cx));
+    }
+
     fn chat(&mut self, _: &Chat, window: &mut Window, cx: &mut Context<Self>) {
         if self.is_editor_empty(cx) {
             return;
@@ -276,6 +291,24 @@ impl MessageEditor {
         }
     }
 
+    fn handle_context_store_event(
+        &mut self,
+        _context_store: Entity<ContextStore>,
+        event: &ContextStoreEvent,
+        cx: &mut Context<Self>,
+    ) {
+        match event {
+            ContextStoreEvent::ContextInserted { id, .. } => {
+                self.context_strip
+                    .update(cx, |context_strip, cx| context_strip.focus_context(*id, cx));
+                cx.notify();
+            }
+            ContextStoreEvent::ContextRemoved { .. }
+            | ContextStoreEvent::ContextsRemoved { .. }
+            | ContextStoreEvent::ContextsRestored { .. } => cx.notify(),
+        }
+    }
+
     fn move_up(&mut self, _: &MoveUp, window: &mut Window, cx: &mut Context<Self>) {
         if self.context_picker_menu_handle.is_deployed()
             || self.inline_context_picker_menu_handle.is_deployed()
@@ -523,6 +556,7 @@ impl Render for MessageEditor {
                     }))
                     .on_action(cx.listener(Self::toggle_context_picker))
                     .on_action(cx.listener(Self::remove_all_context))
+                    .on_action(cx.listener(Self::undo_context_change))
                     .on_action(cx.listener(Self::move_up))
                     .on_action(cx.listener(Self::toggle_chat_mode))
                     .gap_2()
@@ -598,8 +632,10 @@ impl Render for MessageEditor {
                             .child(
                                 PopoverMenu::new("inline-context-picker")
                                     .menu(move |window, cx| {
+                                        // Mentions always start from the menu, where recent
+                                        // entries are a single keystroke away.
                                         inline_context_picker.update(cx, |this, cx| {
-                                            this.init(window, cx);
+                                            this.init_menu(window, cx);
                                         });
 
                                         Some(inline_context_picker.clone())
@@ -374,21 +374,15 @@ impl Thread {
     ///
     /// This is the representation we use when attaching a thread as context to another thread.
     pub fn text(&self) -> String {
-        let mut text = String::new();
-
-        for message in &self.messages {
-            text.push_str(match message.role {
-                language_model::Role::User => "User:",
-                language_model::Role::Assistant => "Assistant:",
-                language_model::Role::System => "System:",
-            });
-            text.push('\n');
-
-            text.push_str(&message.text);
-            text.push('\n');
-        }
+        self.to_context_text(ThreadTextOptions::default())
+    }
 
-        text
+    /// Returns the text of the thread, shaped by `options`.
+    ///
+    /// This is how a thread is rendered wherever its text leaves it, e.g. when it's attached as
+    /// context or copied to the clipboard.
+    pub fn to_context_text(&self, options: ThreadTextOptions) -> String {
+        context_text(&self.messages, self.summary.as_deref(), options)
     }
 
     /// Serializes this thread into a format for storage or telemetry.
@@ -1131,6 +1125,79 @@ impl Thread {
     }
 }
 
+/// How a thread is turned into text when it's attached as context.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ThreadTextFormat {
+    /// Each message is preceded by a `User:`, `Assistant:`, or `System:` line.
+    #[default]
+    Plain,
+    /// Each message is preceded by a `### User`, `### Assistant`, or `### System` heading, with
+    /// a horizontal rule between messages.
+    Markdown,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ThreadTextOptions {
+    /// When set, only this many of the most recent messages are included, along with any system
+    /// messages at the start of the thread, and a marker notes how many messages were left out.
+    pub max_messages: Option<usize>,
+    pub format: ThreadTextFormat,
+    /// When set, only the thread's summary is included instead of its messages.
+    pub summary_only: bool,
+}
+
+/// Renders `messages` as text, or only `summary` when `options` ask for it and there is one.
+fn context_text(messages: &[Message], summary: Option<&str>, options: ThreadTextOptions) -> String {
+    if options.summary_only {
+        // Threads that haven't been summarized yet fall back to their messages.
+        if let Some(summary) = summary {
+            return format!("Summary: {summary}\n");
+        }
+    }
+
+    let leading_system_messages = messages
+        .iter()
+        .take_while(|message| message.role == Role::System)
+        .count();
+    let (system_messages, messages) = messages.split_at(leading_system_messages);
+    let omitted = options.max_messages.map_or(0, |max_messages| {
+        messages.len().saturating_sub(max_messages)
+    });
+
+    let mut blocks = Vec::new();
+    blocks.extend(
+        system_messages
+            .iter()
+            .map(|message| message_text(message, options.format)),
+    );
+    if omitted > 0 {
+        blocks.push(format!("[earlier {omitted} messages omitted]\n"));
+    }
+    blocks.extend(
+        messages[omitted..]
+            .iter()
+            .map(|message| message_text(message, options.format)),
+    );
+
+    match options.format {
+        ThreadTextFormat::Plain => blocks.concat(),
+        ThreadTextFormat::Markdown => blocks.join("\n---\n\n"),
+    }
+}
+
+fn message_text(message: &Message, format: ThreadTextFormat) -> String {
+    let role = match message.role {
+        Role::User => "User",
+        Role::Assistant => "Assistant",
+        Role::System => "System",
+    };
+
+    match format {
+        ThreadTextFormat::Plain => format!("{role}:\n{}\n", message.text),
+        ThreadTextFormat::Markdown => format!("### {role}\n\n{}\n", message.text),
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum ThreadError {
     PaymentRequired,
@@ -1165,3 +1232,106 @@ struct PendingCompletion {
     id: usize,
     _task: Task<()>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(id: usize, role: Role, text: &str) -> Message {
+        Message {
+            id: MessageId(id),
+            role,
+            text: text.to_string(),
+        }
+    }
+
+    fn plain() -> ThreadTextOptions {
+        ThreadTextOptions::default()
+    }
+
+    fn markdown() -> ThreadTextOptions {
+        ThreadTextOptions {
+            format: ThreadTextFormat::Markdown,
+            ..ThreadTextOptions::default()
+        }
+    }
+
+    #[test]
+    fn test_context_text_of_empty_thread() {
+        assert_eq!(context_text(&[], None, plain()), "");
+        assert_eq!(context_text(&[], None, markdown()), "");
+        // Without messages, only a summary can be rendered.
+        let summary_only = ThreadTextOptions {
+            summary_only: true,
+            ..plain()
+        };
+        assert_eq!(context_text(&[], None, summary_only), "");
+        assert_eq!(
+            context_text(&[], Some("Nothing yet"), summary_only),
+            "Summary: Nothing yet\n"
+        );
+    }
+
+    #[test]
+    fn test_context_text_prefixes_each_role() {
+        let messages = [
+            message(0, Role::System, "Be brief."),
+            message(1, Role::User, "Hi"),
+            message(2, Role::Assistant, "Hello!"),
+        ];
+
+        assert_eq!(
+            context_text(&messages, None, plain()),
+            "System:\nBe brief.\nUser:\nHi\nAssistant:\nHello!\n"
+        );
+        assert_eq!(
+            context_text(&messages, None, markdown()),
+            "### System\n\nBe brief.\n\n---\n\n### User\n\nHi\n\n---\n\n### Assistant\n\nHello!\n"
+        );
+    }
+
+    #[test]
+    fn test_context_text_limits_messages() {
+        let messages = [
+            message(0, Role::System, "Be brief."),
+            message(1, Role::User, "One"),
+            message(2, Role::Assistant, "Two"),
+            message(3, Role::User, "Three"),
+        ];
+        let options = ThreadTextOptions {
+            max_messages: Some(1),
+            ..plain()
+        };
+
+        // Leading system messages are always kept.
+        assert_eq!(
+            context_text(&messages, None, options),
+            "System:\nBe brief.\n[earlier 2 messages omitted]\nUser:\nThree\n"
+        );
+        // Nothing is omitted when the limit isn't reached.
+        let options = ThreadTextOptions {
+            max_messages: Some(3),
+            ..plain()
+        };
+        assert_eq!(
+            context_text(&messages, None, options),
+            context_text(&messages, None, plain())
+        );
+    }
+
+    #[test]
+    fn test_context_text_summary_only() {
+        let messages = [message(0, Role::User, "Hi")];
+        let options = ThreadTextOptions {
+            summary_only: true,
+            ..plain()
+        };
+
+        assert_eq!(
+            context_text(&messages, Some("Greeting"), options),
+            "Summary: Greeting\n"
+        );
+        // Threads that haven't been summarized yet fall back to their messages.
+        assert_eq!(context_text(&messages, None, options), "User:\nHi\n");
+    }
+}
@@ -10,7 +10,8 @@ use context_server::{ContextServerFactoryRegistry, ContextServerTool};
 use futures::future::{self, BoxFuture, Shared};
 use futures::FutureExt as _;
 use gpui::{
-    prelude::*, App, BackgroundExecutor, Context, Entity, Global, ReadGlobal, SharedString, Task,
+    prelude::*, App, BackgroundExecutor, Context, Entity, EventEmitter, Global, ReadGlobal,
+    SharedString, Task,
 };
 use heed::types::{SerdeBincode, SerdeJson};
 use heed::Database;
@@ -35,6 +36,13 @@ pub struct ThreadStore {
     threads: Vec<SerializedThreadMetadata>,
 }
 
+pub enum ThreadStoreEvent {
+    /// The list of threads was reloaded or a thread was deleted.
+    ThreadsChanged,
+}
+
+impl EventEmitter<ThreadStoreEvent> for ThreadStore {}
+
 impl ThreadStore {
     pub fn new(
         project: Entity<Project>,
@@ -120,6 +128,42 @@ impl ThreadStore {
         })
     }
 
+    /// Copies a saved thread's messages into a new thread with a fresh ID and saves it, so the
+    /// conversation can be continued without changing the original.
+    pub fn fork_thread(
+        &self,
+        id: &ThreadId,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<Entity<Thread>>> {
+        let id = id.clone();
+        let database_future = ThreadsDatabase::global_future(cx);
+        cx.spawn(|this, mut cx| async move {
+            let database = database_future.await.map_err(|err| anyhow!(err))?;
+            let thread = database
+                .try_find_thread(id.clone())
+                .await?
+                .ok_or_else(|| anyhow!("no thread found with ID: {id:?}"))?;
+
+            let (fork, save_task) = this.update(&mut cx, |this, cx| {
+                let fork = cx.new(|cx| {
+                    Thread::deserialize(
+                        ThreadId::new(),
+                        thread,
+                        this.project.clone(),
+                        this.tools.clone(),
+                        this.prompt_builder.clone(),
+                        cx,
+                    )
+                });
+                let save_task = this.save_thread(&fork, cx);
+                (fork, save_task)
+            })?;
+            save_task.await?;
+
+            Ok(fork)
+        })
+    }
+
     pub fn save_thread(&self, thread: &Entity<Thread>, cx: &mut Context<Self>) -> Task<Result<()>> {
         let (metadata, serialized_thread) =
             thread.update(cx, |thread, cx| (thread.id().clone(), thread.serialize(cx)));
@@ -141,8 +185,9 @@ impl ThreadStore {
             let database = database_future.await.map_err(|err| anyhow!(err))?;
             database.delete_thread(id.clone()).await?;
 
-            this.update(&mut cx, |this, _cx| {
-                this.threads.retain(|thread| thread.id != id)
+            this.update(&mut cx, |this, cx| {
+                this.threads.retain(|thread| thread.id != id);
+                cx.emit(ThreadStoreEvent::ThreadsChanged);
             })
         })
     }
@@ -158,6 +203,7 @@ impl ThreadStore {
 
             this.update(&mut cx, |this, cx| {
                 this.threads = threads;
+                cx.emit(ThreadStoreEvent::ThreadsChanged);
                 cx.notify();
             })
         })
@@ -235,6 +281,8 @@ pub struct SerializedThreadMetadata {
     pub id: ThreadId,
     pub summary: SharedString,
     pub updated_at: DateTime<Utc>,
+    #[serde(default)]
+    pub message_count: usize,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -340,6 +388,7 @@ impl ThreadsDatabase {
                     id: key,
                     summary: value.summary,
                     updated_at: value.updated_at,
+                    message_count: value.messages.len(),
                 });
             }
 
@@ -382,3 +431,83 @@ impl ThreadsDatabase {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use context_server::ContextServerSettings;
+    use fs::FakeFs;
+    use gpui::TestAppContext;
+    use settings::{Settings as _, SettingsStore};
+    use util::path;
+
+    #[gpui::test]
+    async fn test_fork_thread(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            let settings_store = SettingsStore::test(cx);
+            cx.set_global(settings_store);
+            language::init(cx);
+            Project::init_settings(cx);
+            ContextServerSettings::register(cx);
+        });
+        let database_dir = tempfile::tempdir().unwrap();
+        let database =
+            ThreadsDatabase::new(database_dir.path().to_path_buf(), cx.executor()).unwrap();
+        cx.update(|cx| {
+            cx.set_global(GlobalThreadsDatabase(
+                future::ready(Ok(Arc::new(database))).boxed().shared(),
+            ));
+        });
+
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(path!("/root"), serde_json::json!({})).await;
+        let project = Project::test(fs, [path!("/root").as_ref()], cx).await;
+        let prompt_builder = Arc::new(PromptBuilder::new(None).unwrap());
+        let thread_store = cx
+            .update(|cx| ThreadStore::new(project, Arc::default(), prompt_builder, cx))
+            .unwrap();
+
+        let thread = thread_store.update(cx, |thread_store, cx| thread_store.create_thread(cx));
+        thread.update(cx, |thread, cx| {
+            thread.insert_message(Role::User, "Hello", cx);
+            thread.insert_message(Role::Assistant, "Hi there", cx);
+        });
+        thread_store
+            .update(cx, |thread_store, cx| thread_store.save_thread(&thread, cx))
+            .await
+            .unwrap();
+        let thread_id = thread.read_with(cx, |thread, _| thread.id().clone());
+
+        let fork = thread_store
+            .update(cx, |thread_store, cx| {
+                thread_store.fork_thread(&thread_id, cx)
+            })
+            .await
+            .unwrap();
+        // Messages added to the original after the fork point aren't copied.
+        thread.update(cx, |thread, cx| {
+            thread.insert_message(Role::User, "Only in the original", cx);
+        });
+
+        fork.read_with(cx, |fork, _| {
+            assert_ne!(fork.id(), &thread_id);
+            assert_eq!(
+                fork.messages()
+                    .map(|message| (message.role, message.text.as_str()))
+                    .collect::<Vec<_>>(),
+                [(Role::User, "Hello"), (Role::Assistant, "Hi there")]
+            );
+        });
+        thread_store.read_with(cx, |thread_store, cx| {
+            let fork_id = fork.read(cx).id();
+            let saved_ids = thread_store
+                .threads()
+                .into_iter()
+                .map(|thread| thread.id)
+                .collect::<Vec<_>>();
+            assert_eq!(saved_ids.len(), 2);
+            assert!(saved_ids.contains(&thread_id));
+            assert!(saved_ids.contains(fork_id));
+        });
+    }
+}
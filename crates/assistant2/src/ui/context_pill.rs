@@ -11,6 +11,10 @@ pub enum ContextPill {
         context: ContextSnapshot,
         dupe_name: bool,
         focused: bool,
+        /// Whether the context's worktree or thread disappeared when it was last refreshed.
+        stale: bool,
+        /// Whether the context is marked for removal, while context is being selected.
+        marked: Option<bool>,
         on_click: Option<Rc<dyn Fn(&ClickEvent, &mut Window, &mut App)>>,
         on_remove: Option<Rc<dyn Fn(&ClickEvent, &mut Window, &mut App)>>,
     },
@@ -21,6 +25,13 @@ pub enum ContextPill {
         focused: bool,
         on_click: Option<Rc<dyn Fn(&ClickEvent, &mut Window, &mut App)>>,
     },
+    /// A directory whose files are still being read.
+    Pending {
+        ix: usize,
+        name: SharedString,
+        files_read: usize,
+        total_files: usize,
+    },
 }
 
 impl ContextPill {
@@ -35,6 +46,8 @@ impl ContextPill {
             dupe_name,
             on_remove,
             focused,
+            stale: false,
+            marked: None,
             on_click: None,
         }
     }
@@ -54,6 +67,15 @@ impl ContextPill {
         }
     }
 
+    pub fn pending(ix: usize, name: SharedString, files_read: usize, total_files: usize) -> Self {
+        Self::Pending {
+            ix,
+            name,
+            files_read,
+            total_files,
+        }
+    }
+
     pub fn on_click(mut self, listener: Rc<dyn Fn(&ClickEvent, &mut Window, &mut App)>) -> Self {
         match &mut self {
             ContextPill::Added { on_click, .. } => {
@@ -62,6 +84,21 @@ impl ContextPill {
             ContextPill::Suggested { on_click, .. } => {
                 *on_click = Some(listener);
             }
+            ContextPill::Pending { .. } => {}
+        }
+        self
+    }
+
+    pub fn stale(mut self, is_stale: bool) -> Self {
+        if let ContextPill::Added { stale, .. } = &mut self {
+            *stale = is_stale;
+        }
+        self
+    }
+
+    pub fn marked(mut self, is_marked: Option<bool>) -> Self {
+        if let ContextPill::Added { marked, .. } = &mut self {
+            *marked = is_marked;
         }
         self
     }
@@ -72,6 +109,7 @@ impl ContextPill {
                 ElementId::NamedInteger("context-pill".into(), context.id.0)
             }
             Self::Suggested { .. } => "suggested-context-pill".into(),
+            Self::Pending { ix, .. } => ElementId::NamedInteger("pending-context-pill".into(), *ix),
         }
     }
 
@@ -90,6 +128,7 @@ impl ContextPill {
                 icon_path: None,
                 ..
             } => Icon::new(kind.icon()),
+            Self::Pending { .. } => Icon::new(ContextKind::Directory.icon()),
         }
     }
 }
@@ -113,14 +152,35 @@ impl RenderOnce for ContextPill {
                 dupe_name,
                 on_remove,
                 focused,
+                stale,
+                marked,
                 on_click,
             } => base_pill
-                .bg(color.element_background)
+                .bg(if *marked == Some(true) {
+                    color.element_selected
+                } else {
+                    color.element_background
+                })
                 .border_color(if *focused {
                     color.border_focused
                 } else {
                     color.border.opacity(0.5)
                 })
+                .when_some(*marked, |element, marked| {
+                    element.child(
+                        Icon::new(if marked {
+                            IconName::Check
+                        } else {
+                            IconName::Circle
+                        })
+                        .size(IconSize::XSmall)
+                        .color(if marked {
+                            Color::Accent
+                        } else {
+                            Color::Muted
+                        }),
+                    )
+                })
                 .pr(if on_remove.is_some() { px(2.) } else { px(4.) })
                 .child(
                     h_flex()
@@ -133,6 +193,13 @@ impl RenderOnce for ContextPill {
                                     .truncate(),
                             ),
                         )
+                        .when_some(context.detail.clone(), |element, detail| {
+                            element.child(
+                                Label::new(detail)
+                                    .size(LabelSize::XSmall)
+                                    .color(Color::Muted),
+                            )
+                        })
                         .when_some(context.parent.as_ref(), |element, parent_name| {
                             if *dupe_name {
                                 element.child(
@@ -148,6 +215,20 @@ impl RenderOnce for ContextPill {
                             element.tooltip(Tooltip::text(tooltip.clone()))
                         }),
                 )
+                .when(*stale, |element| {
+                    element.child(
+                        div()
+                            .id(("stale", context.id.0))
+                            .child(
+                                Icon::new(IconName::Warning)
+                                    .size(IconSize::XSmall)
+                                    .color(Color::Warning),
+                            )
+                            .tooltip(Tooltip::text(
+                                "No longer available, showing the text from before the refresh",
+                            )),
+                    )
+                })
                 .when_some(on_remove.as_ref(), |element, on_remove| {
                     element.child(
                         IconButton::new(("remove", context.id.0), IconName::Close)
@@ -190,9 +271,10 @@ impl RenderOnce for ContextPill {
                 .child(
                     Label::new(match kind {
                         ContextKind::File => "Active Tab",
-                        ContextKind::Thread | ContextKind::Directory | ContextKind::FetchedUrl => {
-                            "Active"
-                        }
+                        ContextKind::Thread
+                        | ContextKind::Directory
+                        | ContextKind::FetchedUrl
+                        | ContextKind::GitDiff => "Active",
                     })
                     .size(LabelSize::XSmall)
                     .color(Color::Muted),
@@ -209,6 +291,28 @@ impl RenderOnce for ContextPill {
                     let on_click = on_click.clone();
                     element.on_click(move |event, window, cx| on_click(event, window, cx))
                 }),
+            ContextPill::Pending {
+                name,
+                files_read,
+                total_files,
+                ..
+            } => base_pill
+                .pr_1()
+                .border_color(color.border_variant.opacity(0.5))
+                .child(
+                    div().max_w_64().child(
+                        Label::new(name.clone())
+                            .size(LabelSize::Small)
+                            .color(Color::Muted)
+                            .truncate(),
+                    ),
+                )
+                .child(
+                    Label::new(format!("{files_read}/{total_files} files"))
+                        .size(LabelSize::XSmall)
+                        .color(Color::Muted),
+                )
+                .tooltip(Tooltip::text("Reading files…")),
         }
     }
 }
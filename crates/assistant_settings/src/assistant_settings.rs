@@ -21,6 +21,17 @@ pub enum AssistantDockPosition {
     Bottom,
 }
 
+/// What a context picker does after context is attached from it.
+#[derive(Copy, Clone, Default, Debug, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ContextPickerConfirmBehavior {
+    /// Stay open, so that several entries can be attached in a row.
+    #[default]
+    KeepOpen,
+    /// Close once an entry is attached.
+    Close,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq)]
 #[serde(tag = "name", rename_all = "snake_case")]
 pub enum AssistantProviderContentV1 {
@@ -66,6 +77,7 @@ pub struct AssistantSettings {
     pub inline_alternatives: Vec<LanguageModelSelection>,
     pub using_outdated_settings_version: bool,
     pub enable_experimental_live_diffs: bool,
+    pub context_picker_confirm_behavior: ContextPickerConfirmBehavior,
 }
 
 impl AssistantSettings {
@@ -166,6 +178,7 @@ impl AssistantSettingsContent {
                     editor_model: None,
                     inline_alternatives: None,
                     enable_experimental_live_diffs: None,
+                    context_picker_confirm_behavior: None,
                 },
                 VersionedAssistantSettingsContent::V2(settings) => settings.clone(),
             },
@@ -187,6 +200,7 @@ impl AssistantSettingsContent {
                 editor_model: None,
                 inline_alternatives: None,
                 enable_experimental_live_diffs: None,
+                context_picker_confirm_behavior: None,
             },
         }
     }
@@ -316,6 +330,7 @@ impl Default for VersionedAssistantSettingsContent {
             editor_model: None,
             inline_alternatives: None,
             enable_experimental_live_diffs: None,
+            context_picker_confirm_behavior: None,
         })
     }
 }
@@ -352,6 +367,11 @@ pub struct AssistantSettingsContentV2 {
     ///
     /// Default: false
     enable_experimental_live_diffs: Option<bool>,
+    /// Whether context pickers stay open after attaching context. Pickers that always close,
+    /// like the one for `@` mentions, aren't affected.
+    ///
+    /// Default: keep_open
+    context_picker_confirm_behavior: Option<ContextPickerConfirmBehavior>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq)]
@@ -482,6 +502,10 @@ impl Settings for AssistantSettings {
                 &mut settings.enable_experimental_live_diffs,
                 value.enable_experimental_live_diffs,
             );
+            merge(
+                &mut settings.context_picker_confirm_behavior,
+                value.context_picker_confirm_behavior,
+            );
         }
 
         Ok(settings)
@@ -546,6 +570,7 @@ mod tests {
                             default_width: None,
                             default_height: None,
                             enable_experimental_live_diffs: None,
+                            context_picker_confirm_behavior: None,
                         }),
                     )
                 },
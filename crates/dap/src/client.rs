@@ -233,6 +233,11 @@ impl DebugAdapterClient {
         self.transport_delegate.has_adapter_logs()
     }
 
+    /// What the adapter wrote to stderr since it started, up to a limit.
+    pub fn stderr(&self) -> String {
+        self.transport_delegate.stderr()
+    }
+
     pub fn add_log_handler<F>(&self, f: F, kind: LogKind)
     where
         F: 'static + Send + FnMut(IoKind, &str),
@@ -282,7 +287,11 @@ impl DebugAdapterClient {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{client::DebugAdapterClient, debugger_settings::DebuggerSettings};
+    use crate::{
+        client::DebugAdapterClient,
+        debugger_settings::DebuggerSettings,
+        transport::{AdapterStartError, StdioTransport},
+    };
     use dap_types::{
         messages::Events,
         requests::{Initialize, Request, RunInTerminal},
@@ -487,4 +496,27 @@ mod tests {
 
         client.shutdown().await.unwrap();
     }
+    #[gpui::test]
+    pub async fn test_adapter_that_fails_to_spawn(cx: &mut TestAppContext) {
+        init_test(cx);
+
+        let binary = DebugAdapterBinary {
+            command: "/non-existing/debug-adapter".into(),
+            arguments: None,
+            envs: None,
+            cwd: None,
+            connection: None,
+        };
+        let Err(error) = StdioTransport::start(&binary, cx.to_async()).await else {
+            panic!("a missing adapter binary shouldn't start");
+        };
+
+        let start_error = error
+            .downcast_ref::<AdapterStartError>()
+            .expect("spawn failures are adapter start failures");
+        assert_eq!("", start_error.stderr());
+        assert!(error.chain().any(|cause| cause
+            .downcast_ref::<std::io::Error>()
+            .is_some_and(|error| error.kind() == std::io::ErrorKind::NotFound)));
+    }
 }
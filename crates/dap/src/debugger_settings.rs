@@ -31,6 +31,28 @@ pub struct DebuggerSettings {
     ///
     /// Default: true
     pub format_dap_log_messages: bool,
+    /// Whether to show output the debug adapter marks as telemetry in the console
+    ///
+    /// Default: false
+    pub show_telemetry_output: bool,
+    /// Maximum number of lines kept in the debug console. The oldest lines are dropped first.
+    ///
+    /// Default: 10000
+    pub console_max_lines: usize,
+    /// Whether to open the debug panel and switch to a thread when it stops, e.g. on hitting a
+    /// breakpoint. When disabled, a stopped thread is only selected if no thread is selected yet.
+    ///
+    /// Default: true
+    pub select_stopped_thread: bool,
+    /// Whether to switch to the variables tab when the program stops on an exception, so its
+    /// locals are shown straight away.
+    ///
+    /// Default: true
+    pub show_variables_on_exception: bool,
+    /// Whether to ask for confirmation before closing a debug session that is still running.
+    ///
+    /// Default: true
+    pub confirm_close_running_session: bool,
 }
 
 impl Default for DebuggerSettings {
@@ -42,6 +64,11 @@ impl Default for DebuggerSettings {
             timeout: 2000,
             log_dap_communications: true,
             format_dap_log_messages: true,
+            show_telemetry_output: false,
+            console_max_lines: 10_000,
+            select_stopped_thread: true,
+            show_variables_on_exception: true,
+            confirm_close_running_session: true,
         }
     }
 }
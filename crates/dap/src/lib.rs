@@ -34,5 +34,6 @@ pub fn test_config(
         supports_attach: false,
         cwd: None,
         initialize_args: None,
+        stop_on_entry: false,
     }
 }
@@ -66,6 +66,50 @@ impl TransportPipe {
 type Requests = Arc<Mutex<HashMap<u64, oneshot::Sender<Result<Response>>>>>;
 type LogHandlers = Arc<parking_lot::Mutex<SmallVec<[(LogKind, IoHandler); 2]>>>;
 
+/// How much of an adapter's stderr is kept, which is plenty to explain a failure to start.
+const MAX_ADAPTER_STDERR_BYTES: usize = 16 * 1024;
+
+/// A debug adapter that failed to start, with what it wrote to stderr before failing.
+///
+/// The error that made it fail is its source, so it stays reachable through [`anyhow::Error::chain`].
+#[derive(Debug)]
+pub struct AdapterStartError {
+    error: anyhow::Error,
+    stderr: String,
+}
+
+impl AdapterStartError {
+    /// Marks `error` as a failure to start an adapter that wrote `stderr`, unless it already is one.
+    pub fn attach(error: anyhow::Error, stderr: String) -> anyhow::Error {
+        if error.downcast_ref::<Self>().is_some() {
+            return error;
+        }
+        Self { error, stderr }.into()
+    }
+
+    pub fn stderr(&self) -> &str {
+        &self.stderr
+    }
+}
+
+impl std::fmt::Display for AdapterStartError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "debug adapter failed to start")
+    }
+}
+
+impl std::error::Error for AdapterStartError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(self.error.as_ref())
+    }
+}
+
+fn push_stderr(stderr: &mut String, output: &str) {
+    if stderr.len() + output.len() <= MAX_ADAPTER_STDERR_BYTES {
+        stderr.push_str(output);
+    }
+}
+
 pub enum Transport {
     Stdio(StdioTransport),
     Tcp(TcpTransport),
@@ -128,6 +172,7 @@ pub(crate) struct TransportDelegate {
     pending_requests: Requests,
     transport: Transport,
     server_tx: Arc<Mutex<Option<Sender<Message>>>>,
+    stderr: Arc<parking_lot::Mutex<String>>,
 }
 
 impl TransportDelegate {
@@ -142,6 +187,7 @@ impl TransportDelegate {
             log_handlers: Default::default(),
             current_requests: Default::default(),
             pending_requests: Default::default(),
+            stderr: Default::default(),
         };
         let messages = this.start_handlers(transport_pipes, cx).await?;
         Ok((messages, this))
@@ -184,7 +230,11 @@ impl TransportDelegate {
 
             if let Some(stderr) = params.stderr.take() {
                 cx.background_executor()
-                    .spawn(Self::handle_error(stderr, self.log_handlers.clone()))
+                    .spawn(Self::handle_error(
+                        stderr,
+                        self.stderr.clone(),
+                        self.log_handlers.clone(),
+                    ))
                     .detach_and_log_err(cx);
             }
 
@@ -221,6 +271,11 @@ impl TransportDelegate {
         pending_requests.remove(sequence_id);
     }
 
+    /// What the adapter wrote to stderr since it started, up to a limit.
+    pub(crate) fn stderr(&self) -> String {
+        self.stderr.lock().clone()
+    }
+
     pub(crate) async fn send_message(&self, message: Message) -> Result<()> {
         if let Some(server_tx) = self.server_tx.lock().await.as_ref() {
             server_tx
@@ -371,7 +426,11 @@ impl TransportDelegate {
         result
     }
 
-    async fn handle_error<Stderr>(stderr: Stderr, log_handlers: LogHandlers) -> Result<()>
+    async fn handle_error<Stderr>(
+        stderr: Stderr,
+        captured_stderr: Arc<parking_lot::Mutex<String>>,
+        log_handlers: LogHandlers,
+    ) -> Result<()>
     where
         Stderr: AsyncRead + Unpin + Send + 'static,
     {
@@ -383,6 +442,8 @@ impl TransportDelegate {
             match reader.read_line(&mut buffer).await {
                 Ok(0) => break Err(anyhow!("debugger error stream closed")),
                 Ok(_) => {
+                    push_stderr(&mut captured_stderr.lock(), &buffer);
+
                     for (kind, log_handler) in log_handlers.lock().iter_mut() {
                         if matches!(kind, LogKind::Adapter) {
                             log_handler(IoKind::StdErr, buffer.as_str());
@@ -570,7 +631,8 @@ impl TcpTransport {
 
         let mut process = command
             .spawn()
-            .with_context(|| "failed to start debug adapter.")?;
+            .with_context(|| "failed to start debug adapter.")
+            .map_err(|error| AdapterStartError::attach(error, String::new()))?;
 
         let address = SocketAddrV4::new(host, port);
 
@@ -581,7 +643,22 @@ impl TcpTransport {
 
         let (rx, tx) = select! {
             _ = cx.background_executor().timer(Duration::from_millis(timeout)).fuse() => {
-                return Err(anyhow!(format!("Connection to TCP DAP timeout {}:{}", host, port)))
+                // A timeout is reported as such, so that callers can tell it may be transient.
+                let error = std::io::Error::new(
+                    std::io::ErrorKind::TimedOut,
+                    format!("Connection to TCP DAP timeout {}:{}", host, port),
+                );
+                process.kill().log_err();
+                let mut stderr = Vec::new();
+                if let Some(output) = process.stderr.take() {
+                    output
+                        .take(MAX_ADAPTER_STDERR_BYTES as u64)
+                        .read_to_end(&mut stderr)
+                        .await
+                        .log_err();
+                }
+                let stderr = String::from_utf8_lossy(&stderr).into_owned();
+                return Err(AdapterStartError::attach(error.into(), stderr));
             },
             result = cx.spawn(|cx| async move {
                 loop {
@@ -636,7 +713,10 @@ pub struct StdioTransport {
 
 impl StdioTransport {
     #[allow(dead_code, reason = "This is used in non test builds of Zed")]
-    async fn start(binary: &DebugAdapterBinary, _: AsyncApp) -> Result<(TransportPipe, Self)> {
+    pub(crate) async fn start(
+        binary: &DebugAdapterBinary,
+        _: AsyncApp,
+    ) -> Result<(TransportPipe, Self)> {
         let mut command = util::command::new_smol_command(&binary.command);
 
         if let Some(cwd) = &binary.cwd {
@@ -659,7 +739,8 @@ impl StdioTransport {
 
         let mut process = command
             .spawn()
-            .with_context(|| "failed to spawn command.")?;
+            .with_context(|| "failed to spawn command.")
+            .map_err(|error| AdapterStartError::attach(error, String::new()))?;
 
         let stdin = process
             .stdin
@@ -738,6 +819,9 @@ impl FakeTransport {
             Box::new(
                 move |seq, args, writer: Arc<Mutex<async_pipe::PipeWriter>>| {
                     let response = handler(seq, serde_json::from_value(args).unwrap());
+                    let error_message = response.as_ref().err().and_then(|error| {
+                        error.error.as_ref().map(|message| message.format.clone())
+                    });
 
                     let message = serde_json::to_string(&Message::Response(Response {
                         seq: seq + 1,
@@ -745,7 +829,7 @@ impl FakeTransport {
                         success: response.as_ref().is_ok(),
                         command: R::COMMAND.into(),
                         body: util::maybe!({ serde_json::to_value(response.ok()?).ok() }),
-                        message: None,
+                        message: error_message,
                     }))
                     .unwrap();
 
@@ -1,30 +1,39 @@
-use crate::session::DebugSession;
-use anyhow::{anyhow, Result};
+use crate::{
+    persistence::{self, SerializedDebugPanel},
+    session::{DebugPanelItemEvent, DebugSession},
+};
+use anyhow::{anyhow, Context as _, Result};
 use collections::HashMap;
 use command_palette_hooks::CommandPaletteFilter;
 use dap::{
     client::SessionId, debugger_settings::DebuggerSettings, ContinuedEvent, LoadedSourceEvent,
     ModuleEvent, OutputEvent, StoppedEvent, ThreadEvent,
 };
+use db::kvp::KEY_VALUE_STORE;
 use futures::{channel::mpsc, SinkExt as _};
 use gpui::{
-    actions, Action, App, AsyncWindowContext, Context, Entity, EventEmitter, FocusHandle,
+    actions, Action, App, AsyncWindowContext, Context, Entity, EntityId, EventEmitter, FocusHandle,
     Focusable, Subscription, Task, WeakEntity,
 };
 use project::{
-    debugger::dap_store::{self, DapStore},
+    debugger::{
+        dap_store::{self, DapStore},
+        session::ThreadStatus,
+    },
     terminals::TerminalKind,
     Project,
 };
 use rpc::proto::{self};
 use settings::Settings;
-use std::{any::TypeId, path::PathBuf};
+use std::{any::TypeId, path::PathBuf, sync::Arc};
 use terminal_view::terminal_panel::TerminalPanel;
 use ui::prelude::*;
+use util::{ResultExt as _, TryFutureExt as _};
 use workspace::{
     dock::{DockPosition, Panel, PanelEvent},
-    pane, Continue, Disconnect, Pane, Pause, Restart, StepBack, StepInto, StepOut, StepOver, Stop,
-    ToggleIgnoreBreakpoints, Workspace,
+    item::ItemHandle,
+    pane, Continue, Disconnect, EvaluateSelection, Pane, Pause, Restart, StepBack, StepInto,
+    StepOut, StepOver, Stop, ToggleIgnoreBreakpoints, Workspace,
 };
 
 pub enum DebugPanelEvent {
@@ -50,6 +59,9 @@ pub struct DebugPanel {
     pane: Entity<Pane>,
     project: WeakEntity<Project>,
     workspace: WeakEntity<Workspace>,
+    /// Subscriptions to the events of each session in the pane, keyed by the session's entity.
+    session_subscriptions: HashMap<EntityId, Subscription>,
+    pending_serialization: Task<Option<()>>,
     _subscriptions: Vec<Subscription>,
 }
 
@@ -78,6 +90,13 @@ impl DebugPanel {
                 pane.display_nav_history_buttons(None);
                 pane.set_should_display_tab_bar(|_window, _cx| true);
                 pane.set_close_pane_if_empty(true, cx);
+                pane.set_close_confirmation(Some(Arc::new(|item: &dyn ItemHandle, cx: &App| {
+                    let debug_session = item.downcast::<DebugSession>()?;
+                    debug_session
+                        .read(cx)
+                        .should_confirm_close(cx)
+                        .then(|| "Stop debugging and close?".into())
+                })));
                 pane.set_render_tab_bar_buttons(cx, {
                     let project = project.clone();
                     let weak_workspace = weak_workspace.clone();
@@ -137,6 +156,8 @@ impl DebugPanel {
             let debug_panel = Self {
                 pane,
                 size: px(300.),
+                session_subscriptions: HashMap::default(),
+                pending_serialization: Task::ready(None),
                 _subscriptions,
                 project: project.downgrade(),
                 workspace: workspace.weak_handle(),
@@ -151,29 +172,56 @@ impl DebugPanel {
         cx: AsyncWindowContext,
     ) -> Task<Result<Entity<Self>>> {
         cx.spawn(|mut cx| async move {
+            let serialization_key = workspace
+                .read_with(&cx, |workspace, _| {
+                    persistence::serialization_key(workspace)
+                })
+                .ok()
+                .flatten();
+            let serialized_panel = match serialization_key {
+                Some(serialization_key) => cx
+                    .background_spawn(async move { KEY_VALUE_STORE.read_kvp(&serialization_key) })
+                    .await
+                    .context("loading debug panel")
+                    .log_err()
+                    .flatten()
+                    .and_then(|panel| {
+                        serde_json::from_str::<SerializedDebugPanel>(&panel).log_err()
+                    }),
+                None => None,
+            };
+
             workspace.update_in(&mut cx, |workspace, window, cx| {
                 let debug_panel = DebugPanel::new(workspace, window, cx);
+                if let Some(serialized_panel) = serialized_panel {
+                    debug_panel.update(cx, |debug_panel, cx| {
+                        debug_panel.restore(serialized_panel, window, cx);
+                    });
+                }
 
                 cx.observe(&debug_panel, |_, debug_panel, cx| {
-                    let (has_active_session, supports_restart, support_step_back) = debug_panel
-                        .update(cx, |this, cx| {
+                    let (has_active_session, is_stopped, supports_restart, support_step_back) =
+                        debug_panel.update(cx, |this, cx| {
                             this.active_session(cx)
                                 .map(|item| {
                                     let running = item.read(cx).mode().as_running().cloned();
 
                                     match running {
                                         Some(running) => {
-                                            let caps = running.read(cx).capabilities(cx);
+                                            let running = running.read(cx);
+                                            let caps = running.capabilities(cx);
                                             (
                                                 true,
+                                                running.thread_status(cx)
+                                                    == Some(ThreadStatus::Stopped),
                                                 caps.supports_restart_request.unwrap_or_default(),
                                                 caps.supports_step_back.unwrap_or_default(),
                                             )
                                         }
-                                        None => (false, false, false),
+                                        None => (false, false, false, false),
                                     }
                                 })
-                                .unwrap_or((false, false, false))
+                                .unwrap_or((false, false, false, false))
                         });
 
                     let filter = CommandPaletteFilter::global_mut(cx);
@@ -190,6 +238,14 @@ impl DebugPanel {
 
                     let step_back_action_type = [TypeId::of::<StepBack>()];
                     let restart_action_type = [TypeId::of::<Restart>()];
+                    // Expressions can only be evaluated in the frames of a stopped thread.
+                    let evaluate_selection_action_type = [TypeId::of::<EvaluateSelection>()];
+
+                    if is_stopped {
+                        filter.show_action_types(evaluate_selection_action_type.iter());
+                    } else {
+                        filter.hide_action_types(&evaluate_selection_action_type);
+                    }
 
                     if has_active_session {
                         filter.show_action_types(debugger_action_types.iter());
@@ -219,6 +275,87 @@ impl DebugPanel {
         })
     }
 
+    /// Replaces the initial session with the sessions persisted from a previous run, which
+    /// come back inert so they can be relaunched.
+    pub(crate) fn restore(
+        &mut self,
+        serialized: SerializedDebugPanel,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if serialized.sessions.is_empty() {
+            return;
+        }
+        let Some(project) = self.project.upgrade() else {
+            return;
+        };
+
+        self.pane.update(cx, |pane, cx| {
+            let initial_items = pane
+                .items_of_type::<DebugSession>()
+                .filter(|item| item.read(cx).serialize(cx).is_none())
+                .map(|item| item.entity_id())
+                .collect::<Vec<_>>();
+
+            for session in serialized.sessions {
+                let item = DebugSession::restored(
+                    project.clone(),
+                    self.workspace.clone(),
+                    session,
+                    window,
+                    cx,
+                );
+                pane.add_item(Box::new(item), false, false, None, window, cx);
+            }
+            for item_id in initial_items {
+                pane.remove_item(item_id, false, false, window, cx);
+            }
+
+            if let Some(active_session) = serialized.active_session {
+                if active_session < pane.items_len() {
+                    pane.activate_item(active_session, false, false, window, cx);
+                }
+            }
+        });
+    }
+
+    pub(crate) fn serialized_sessions(&self, cx: &App) -> SerializedDebugPanel {
+        let pane = self.pane.read(cx);
+        let active_item_id = pane.active_item().map(|item| item.item_id());
+
+        let mut serialized = SerializedDebugPanel::default();
+        for item in pane.items_of_type::<DebugSession>() {
+            if let Some(session) = item.read(cx).serialize(cx) {
+                if Some(item.entity_id()) == active_item_id {
+                    serialized.active_session = Some(serialized.sessions.len());
+                }
+                serialized.sessions.push(session);
+            }
+        }
+        serialized
+    }
+
+    fn serialize(&mut self, cx: &mut Context<Self>) {
+        let Some(serialization_key) = self
+            .workspace
+            .read_with(cx, |workspace, _| persistence::serialization_key(workspace))
+            .ok()
+            .flatten()
+        else {
+            return;
+        };
+        let serialized = self.serialized_sessions(cx);
+        self.pending_serialization = cx.background_spawn(
+            async move {
+                KEY_VALUE_STORE
+                    .write_kvp(serialization_key, serde_json::to_string(&serialized)?)
+                    .await?;
+                anyhow::Ok(())
+            }
+            .log_err(),
+        );
+    }
+
     pub fn active_session(&self, cx: &App) -> Option<Entity<DebugSession>> {
         self.pane
             .read(cx)
@@ -226,6 +363,13 @@ impl DebugPanel {
             .and_then(|panel| panel.downcast::<DebugSession>())
     }
 
+    /// Whether the user is inspecting the active session, which other sessions shouldn't take
+    /// the panel away from.
+    fn is_inspecting_active_session(&self, cx: &App) -> bool {
+        self.active_session(cx)
+            .is_some_and(|session| session.read(cx).is_stopped(cx))
+    }
+
     pub fn debug_panel_items_by_client(
         &self,
         client_id: &SessionId,
@@ -283,9 +427,14 @@ impl DebugPanel {
                 let session_item =
                     DebugSession::running(project, self.workspace.clone(), session, window, cx);
 
+                // A session starting in the background, e.g. a child process, is added without
+                // switching away from the session the user is looking at.
+                let activate = !self.is_inspecting_active_session(cx);
                 self.pane.update(cx, |pane, cx| {
-                    pane.add_item(Box::new(session_item), true, true, None, window, cx);
-                    window.focus(&pane.focus_handle(cx));
+                    pane.add_item(Box::new(session_item), activate, activate, None, window, cx);
+                    if activate {
+                        window.focus(&pane.focus_handle(cx));
+                    }
                     cx.notify();
                 });
             }
@@ -398,18 +547,29 @@ impl DebugPanel {
                         item.added_to_pane(workspace, self.pane.clone(), window, cx)
                     })
                     .ok();
+                if let Some(debug_session) = item.downcast::<DebugSession>() {
+                    self.session_subscriptions.insert(
+                        debug_session.entity_id(),
+                        cx.subscribe_in(&debug_session, window, Self::handle_session_event),
+                    );
+                }
+                self.serialize(cx);
             }
             pane::Event::RemovedItem { item } => {
                 if let Some(debug_session) = item.downcast::<DebugSession>() {
+                    self.session_subscriptions
+                        .remove(&debug_session.entity_id());
                     debug_session.update(cx, |session, cx| {
                         session.shutdown(cx);
                     })
                 }
+                self.serialize(cx);
             }
             pane::Event::ActivateItem {
                 local: _,
                 focus_changed,
             } => {
+                self.serialize(cx);
                 if *focus_changed {
                     if let Some(debug_session) = self
                         .pane
@@ -431,6 +591,36 @@ impl DebugPanel {
             _ => {}
         }
     }
+
+    /// Persists the sessions when their state changes, and brings a session that stopped in the
+    /// background to the front, unless the user is inspecting another stopped session.
+    fn handle_session_event(
+        &mut self,
+        debug_session: &Entity<DebugSession>,
+        event: &DebugPanelItemEvent,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        match event {
+            // Notifies so the debugger actions available in the command palette are updated.
+            DebugPanelItemEvent::Stopped { .. } => cx.notify(),
+            DebugPanelItemEvent::Continued => return cx.notify(),
+            DebugPanelItemEvent::ThreadItemChanged(_)
+            | DebugPanelItemEvent::PersistentStateChanged => return self.serialize(cx),
+            DebugPanelItemEvent::Close => return,
+        }
+        if self.active_session(cx).as_ref() == Some(debug_session)
+            || self.is_inspecting_active_session(cx)
+        {
+            return;
+        }
+
+        self.pane.update(cx, |pane, cx| {
+            if let Some(ix) = pane.index_for_item(debug_session) {
+                pane.activate_item(ix, false, false, window, cx);
+            }
+        });
+    }
 }
 
 impl EventEmitter<PanelEvent> for DebugPanel {}
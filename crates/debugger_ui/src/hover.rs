@@ -0,0 +1,109 @@
+use std::ops::Range;
+
+use editor::{Addon, Editor};
+use gpui::{App, Entity, Task};
+use language::{Anchor, Buffer, Point, ToPoint as _};
+use project::{Hover, HoverBlock, HoverBlockKind};
+
+use crate::debugger_panel::DebugPanel;
+
+/// Shows the values of variables hovered in the source while the active debug session is stopped,
+/// evaluated in the selected stack frame.
+pub(crate) struct DebuggerHoverAddon;
+
+impl Addon for DebuggerHoverAddon {
+    fn hover(
+        &self,
+        editor: &Editor,
+        buffer: &Entity<Buffer>,
+        position: Anchor,
+        cx: &mut App,
+    ) -> Option<Task<Vec<Hover>>> {
+        let running_state = editor
+            .workspace()?
+            .read(cx)
+            .panel::<DebugPanel>(cx)?
+            .read(cx)
+            .active_session(cx)?
+            .read(cx)
+            .mode()
+            .as_running()
+            .cloned()?;
+
+        let snapshot = buffer.read(cx).snapshot();
+        let point = position.to_point(&snapshot);
+        let line_start = Point::new(point.row, 0);
+        let line = snapshot
+            .text_for_range(line_start..Point::new(point.row, snapshot.line_len(point.row)))
+            .collect::<String>();
+        let range = expression_at(&line, point.column as usize)?;
+        let expression = line[range.clone()].to_string();
+
+        let evaluation = running_state.update(cx, |state, cx| {
+            state.evaluate_for_hover(expression.clone(), cx)
+        })?;
+        let line_offset = snapshot.point_to_offset(line_start);
+        let range = snapshot.anchor_before(line_offset + range.start)
+            ..snapshot.anchor_after(line_offset + range.end);
+
+        Some(cx.background_spawn(async move {
+            // Expressions the adapter can't evaluate, such as keywords, show nothing.
+            let Ok(response) = evaluation.await else {
+                return Vec::new();
+            };
+            let text = match response.type_ {
+                Some(type_) if !type_.is_empty() => {
+                    format!("{expression}: {type_} = {}", response.result)
+                }
+                _ => format!("{expression} = {}", response.result),
+            };
+            vec![Hover {
+                contents: vec![HoverBlock {
+                    text,
+                    kind: HoverBlockKind::PlainText,
+                }],
+                range: Some(range),
+                language: None,
+            }]
+        }))
+    }
+
+    fn to_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+/// The range of the expression hovered at `column` of `line`: the identifier under the pointer,
+/// along with the identifiers it's a member of, such as `self.count` when hovering `count`.
+pub(crate) fn expression_at(line: &str, column: usize) -> Option<Range<usize>> {
+    let is_identifier_char = |c: char| c.is_alphanumeric() || c == '_' || c == '$';
+    let identifier_start = |end: usize| {
+        line[..end]
+            .char_indices()
+            .rev()
+            .take_while(|(_, c)| is_identifier_char(*c))
+            .last()
+            .map_or(end, |(ix, _)| ix)
+    };
+
+    if !line.get(column..)?.starts_with(is_identifier_char) {
+        return None;
+    }
+    let end = line[column..]
+        .find(|c| !is_identifier_char(c))
+        .map_or(line.len(), |ix| column + ix);
+    let mut start = identifier_start(column);
+    while line[..start].ends_with('.') {
+        let member_of = identifier_start(start - 1);
+        if member_of == start - 1 {
+            break;
+        }
+        start = member_of;
+    }
+
+    // Numbers, including the parts of a float literal, aren't worth evaluating.
+    if line[start..].starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    Some(start..end)
+}
@@ -1,16 +1,19 @@
 use dap::debugger_settings::DebuggerSettings;
 use debugger_panel::{DebugPanel, ToggleFocus};
+use editor::{Editor, EditorMode};
 use feature_flags::{Debugger, FeatureFlagViewExt};
-use gpui::App;
+use gpui::{App, Entity};
 use session::DebugSession;
 use settings::Settings;
 use workspace::{
-    Pause, Restart, ShutdownDebugAdapters, StepBack, StepInto, StepOver, Stop,
+    EvaluateSelection, Pause, Restart, ShutdownDebugAdapters, StepBack, StepInto, StepOver, Stop,
     ToggleIgnoreBreakpoints, Workspace,
 };
 
 pub mod attach_modal;
 pub mod debugger_panel;
+mod hover;
+mod persistence;
 pub mod session;
 
 #[cfg(test)]
@@ -20,6 +23,13 @@ pub fn init(cx: &mut App) {
     DebuggerSettings::register(cx);
     workspace::FollowableViewRegistry::register::<DebugSession>(cx);
 
+    cx.observe_new(|editor: &mut Editor, _, _| {
+        if editor.mode() == EditorMode::Full {
+            editor.register_addon(hover::DebuggerHoverAddon);
+        }
+    })
+    .detach();
+
     cx.observe_new(|_: &mut Workspace, window, cx| {
         let Some(window) = window else {
             return;
@@ -107,6 +117,27 @@ pub fn init(cx: &mut App) {
                         active_item.update(cx, |item, cx| item.toggle_ignore_breakpoints(cx))
                     }
                 })
+                .register_action(|workspace, _: &EvaluateSelection, window, cx| {
+                    let Some(expression) = workspace
+                        .active_item_as::<Editor>(cx)
+                        .and_then(|editor| selected_text(&editor, cx))
+                    else {
+                        return;
+                    };
+                    let debug_panel = workspace.panel::<DebugPanel>(cx).unwrap();
+
+                    if let Some(active_item) = debug_panel.read_with(cx, |panel, cx| {
+                        panel
+                            .active_session(cx)
+                            .and_then(|session| session.read(cx).mode().as_running().cloned())
+                    }) {
+                        let evaluated = active_item
+                            .update(cx, |item, cx| item.evaluate_selection(expression, cx));
+                        if evaluated {
+                            workspace.open_panel::<DebugPanel>(window, cx);
+                        }
+                    }
+                })
                 .register_action(
                     |workspace: &mut Workspace, _: &ShutdownDebugAdapters, _window, cx| {
                         workspace.project().update(cx, |project, cx| {
@@ -120,3 +151,18 @@ pub fn init(cx: &mut App) {
     })
     .detach();
 }
+
+/// The text of the editor's newest selection, if it selects more than whitespace.
+fn selected_text(editor: &Entity<Editor>, cx: &mut App) -> Option<String> {
+    editor.update(cx, |editor, cx| {
+        let selection = editor.selections.newest::<usize>(cx);
+        let text = editor
+            .buffer()
+            .read(cx)
+            .snapshot(cx)
+            .text_for_range(selection.range())
+            .collect::<String>();
+        let text = text.trim();
+        (!text.is_empty()).then(|| text.to_string())
+    })
+}
@@ -0,0 +1,34 @@
+use dap::DebugAdapterConfig;
+use serde::{Deserialize, Serialize};
+use workspace::Workspace;
+
+const DEBUG_PANEL_KEY: &str = "DebugPanel";
+
+/// The debug panel's sessions, as persisted across restarts. Running sessions can't be resumed,
+/// so each one comes back as an inert session that relaunches its configuration.
+///
+/// Breakpoints are persisted with the workspace, and exception filters with their adapter and
+/// configuration, so neither is stored here.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub(crate) struct SerializedDebugPanel {
+    pub sessions: Vec<SerializedDebugSession>,
+    /// The index of the session that was active.
+    pub active_session: Option<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct SerializedDebugSession {
+    pub config: DebugAdapterConfig,
+    /// The open tab, as a `proto::DebuggerThreadItem` so that tabs added by newer versions are
+    /// skipped rather than failing to load.
+    pub thread_item: Option<i32>,
+    pub watches: Vec<String>,
+}
+
+pub(crate) fn serialization_key(workspace: &Workspace) -> Option<String> {
+    workspace
+        .database_id()
+        .map(|id| i64::from(id).to_string())
+        .or(workspace.session_id())
+        .map(|id| format!("{DEBUG_PANEL_KEY}-{id}"))
+}
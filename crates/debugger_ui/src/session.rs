@@ -3,20 +3,28 @@ mod inert;
 pub mod running;
 mod starting;
 
-use std::time::Duration;
+use std::{rc::Rc, time::Duration};
 
-use dap::client::SessionId;
-use failed::FailedState;
+use anyhow::anyhow;
+use dap::{client::SessionId, debugger_settings::DebuggerSettings};
+use failed::{FailedEvent, FailedState};
 use gpui::{
     percentage, Animation, AnimationExt, AnyElement, App, Entity, EventEmitter, FocusHandle,
     Focusable, Subscription, Task, Transformation, WeakEntity,
 };
 use inert::{InertEvent, InertState};
-use project::debugger::{dap_store::DapStore, session::Session};
+use project::debugger::{
+    dap_store::DapStore,
+    session::{Session, ThreadStatus},
+};
 use project::worktree_store::WorktreeStore;
 use project::Project;
 use rpc::proto::{self, PeerId};
-use running::RunningState;
+use running::{RunningState, SessionExit};
+use settings::Settings;
+pub(crate) use starting::Relaunch;
+
+use crate::persistence::SerializedDebugSession;
 use starting::{StartingEvent, StartingState};
 use ui::prelude::*;
 use workspace::{
@@ -46,21 +54,66 @@ pub struct DebugSession {
     dap_store: WeakEntity<DapStore>,
     worktree_store: WeakEntity<WorktreeStore>,
     workspace: WeakEntity<Workspace>,
+    /// Launches the adapter again under the same session ID, when the session was started here.
+    relaunch: Option<(SessionId, Relaunch)>,
+    /// The configuration this session was launched with from the inert state, with the watches
+    /// and tab to restore once it runs, when restored from a previous run.
+    persisted: Option<SerializedDebugSession>,
     _subscriptions: [Subscription; 1],
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum DebugPanelItemEvent {
     Close,
-    Stopped { go_to_stack_frame: bool },
+    Stopped {
+        go_to_stack_frame: bool,
+    },
+    /// The session's threads resumed running.
+    Continued,
+    /// The local user switched to a different tab of a running session.
+    ThreadItemChanged(ThreadItem),
+    /// The session's configuration or watches changed, so the persisted sessions are outdated.
+    PersistentStateChanged,
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum ThreadItem {
+    Breakpoints,
     Console,
+    Disassembly,
+    ExceptionBreakpoints,
     LoadedSource,
     Modules,
     Variables,
+    Watches,
+}
+
+impl ThreadItem {
+    pub(crate) fn to_proto(&self) -> proto::DebuggerThreadItem {
+        match self {
+            ThreadItem::Breakpoints => proto::DebuggerThreadItem::Breakpoints,
+            ThreadItem::Console => proto::DebuggerThreadItem::Console,
+            ThreadItem::Disassembly => proto::DebuggerThreadItem::Disassembly,
+            ThreadItem::ExceptionBreakpoints => proto::DebuggerThreadItem::ExceptionBreakpoints,
+            ThreadItem::LoadedSource => proto::DebuggerThreadItem::LoadedSource,
+            ThreadItem::Modules => proto::DebuggerThreadItem::Modules,
+            ThreadItem::Variables => proto::DebuggerThreadItem::Variables,
+            ThreadItem::Watches => proto::DebuggerThreadItem::Watches,
+        }
+    }
+
+    pub(crate) fn from_proto(proto: proto::DebuggerThreadItem) -> Self {
+        match proto {
+            proto::DebuggerThreadItem::Breakpoints => ThreadItem::Breakpoints,
+            proto::DebuggerThreadItem::Console => ThreadItem::Console,
+            proto::DebuggerThreadItem::Disassembly => ThreadItem::Disassembly,
+            proto::DebuggerThreadItem::ExceptionBreakpoints => ThreadItem::ExceptionBreakpoints,
+            proto::DebuggerThreadItem::LoadedSource => ThreadItem::LoadedSource,
+            proto::DebuggerThreadItem::Modules => ThreadItem::Modules,
+            proto::DebuggerThreadItem::Variables => ThreadItem::Variables,
+            proto::DebuggerThreadItem::Watches => ThreadItem::Watches,
+        }
+    }
 }
 
 impl DebugSession {
@@ -90,6 +143,8 @@ impl DebugSession {
                 dap_store,
                 worktree_store,
                 workspace,
+                relaunch: None,
+                persisted: None,
                 _subscriptions,
             }
         })
@@ -105,14 +160,79 @@ impl DebugSession {
         let mode = cx.new(|cx| RunningState::new(session.clone(), workspace.clone(), window, cx));
 
         cx.new(|cx| Self {
-            _subscriptions: [cx.subscribe(&mode, |_, _, _, cx| {
-                cx.notify();
-            })],
+            _subscriptions: [cx.subscribe(&mode, Self::on_running_event)],
             remote_id: None,
             mode: DebugSessionState::Running(mode),
             dap_store: project.read(cx).dap_store().downgrade(),
             worktree_store: project.read(cx).worktree_store().downgrade(),
             workspace,
+            relaunch: None,
+            persisted: None,
+        })
+    }
+
+    #[cfg(any(test, feature = "test-support"))]
+    pub(crate) fn starting(
+        project: Entity<Project>,
+        workspace: WeakEntity<Workspace>,
+        session_id: SessionId,
+        task: Task<anyhow::Result<Entity<Session>>>,
+        relaunch: Option<Relaunch>,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Entity<Self> {
+        let starting = cx.new(|cx| StartingState::new(session_id, task, relaunch.clone(), cx));
+
+        cx.new(|cx| Self {
+            _subscriptions: [cx.subscribe_in(&starting, window, Self::on_starting_event)],
+            remote_id: None,
+            mode: DebugSessionState::Starting(starting),
+            dap_store: project.read(cx).dap_store().downgrade(),
+            worktree_store: project.read(cx).worktree_store().downgrade(),
+            workspace,
+            relaunch: relaunch.map(|relaunch| (session_id, relaunch)),
+            persisted: None,
+        })
+    }
+
+    /// An inert session set up with a session persisted from a previous run, which launches it
+    /// again with one click.
+    pub(crate) fn restored(
+        project: Entity<Project>,
+        workspace: WeakEntity<Workspace>,
+        serialized: SerializedDebugSession,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Entity<Self> {
+        let debug_session = Self::inert(project, workspace, window, cx);
+        debug_session.update(cx, |debug_session, cx| {
+            if let DebugSessionState::Inert(inert) = &debug_session.mode {
+                inert.update(cx, |inert, cx| {
+                    inert.restore(&serialized.config, window, cx)
+                });
+            }
+            debug_session.persisted = Some(serialized);
+        });
+        debug_session
+    }
+
+    /// The session's state to restore after a restart. Sessions that were never launched have
+    /// nothing worth restoring.
+    pub(crate) fn serialize(&self, cx: &App) -> Option<SerializedDebugSession> {
+        let Some(running_state) = self.mode.as_running() else {
+            return self.persisted.clone();
+        };
+        let running_state = running_state.read(cx);
+        let config = running_state
+            .session()
+            .read(cx)
+            .configuration()
+            .or_else(|| Some(self.persisted.as_ref()?.config.clone()))?;
+
+        Some(SerializedDebugSession {
+            config,
+            thread_item: Some(running_state.active_thread_item().to_proto().into()),
+            watches: running_state.watch_list().read(cx).expressions(),
         })
     }
 
@@ -134,6 +254,31 @@ impl DebugSession {
         }
     }
 
+    /// Whether closing this session should be confirmed first, because it would stop a program
+    /// that's still running.
+    pub(crate) fn should_confirm_close(&self, cx: &App) -> bool {
+        if !DebuggerSettings::get_global(cx).confirm_close_running_session {
+            return false;
+        }
+
+        let DebugSessionState::Running(state) = &self.mode else {
+            return false;
+        };
+        let state = state.read(cx);
+        !state.session().read(cx).is_terminated()
+            && !matches!(
+                state.thread_status(cx),
+                Some(ThreadStatus::Exited | ThreadStatus::Ended)
+            )
+    }
+
+    /// Whether the session is paused, so the user may be inspecting its state.
+    pub(crate) fn is_stopped(&self, cx: &App) -> bool {
+        self.mode
+            .as_running()
+            .is_some_and(|state| state.read(cx).thread_status(cx) == Some(ThreadStatus::Stopped))
+    }
+
     pub(crate) fn mode(&self) -> &DebugSessionState {
         &self.mode
     }
@@ -148,18 +293,39 @@ impl DebugSession {
         let dap_store = self.dap_store.clone();
         let InertEvent::Spawned { config } = event;
         let config = config.clone();
+        self.persisted = Some(match self.persisted.take() {
+            Some(persisted) => SerializedDebugSession {
+                config: config.clone(),
+                ..persisted
+            },
+            None => SerializedDebugSession {
+                config: config.clone(),
+                thread_item: None,
+                watches: Vec::new(),
+            },
+        });
+        cx.emit(DebugPanelItemEvent::PersistentStateChanged);
+
         let worktree = self
             .worktree_store
             .update(cx, |this, _| this.worktrees().next())
             .ok()
             .flatten()
             .expect("worktree-less project");
-        let Ok((new_session_id, task)) = dap_store.update(cx, |store, cx| {
-            store.new_session(config, &worktree, None, cx)
-        }) else {
+        let Ok(Ok(new_session_id)) = dap_store.read_with(cx, |store, _| store.next_session_id())
+        else {
             return;
         };
-        let starting = cx.new(|cx| StartingState::new(new_session_id, task, cx));
+        let relaunch: Relaunch = Rc::new(move |cx| {
+            dap_store
+                .update(cx, |store, cx| {
+                    store.start_session(new_session_id, config.clone(), &worktree, None, cx)
+                })
+                .unwrap_or_else(|error| Task::ready(Err(error)))
+        });
+        self.relaunch = Some((new_session_id, relaunch.clone()));
+        let task = relaunch(cx);
+        let starting = cx.new(|cx| StartingState::new(new_session_id, task, Some(relaunch), cx));
 
         self._subscriptions = [cx.subscribe_in(&starting, window, Self::on_starting_event)];
         self.mode = DebugSessionState::Starting(starting);
@@ -175,12 +341,84 @@ impl DebugSession {
         if let StartingEvent::Finished(session) = event {
             let mode =
                 cx.new(|cx| RunningState::new(session.clone(), self.workspace.clone(), window, cx));
+            if let Some(persisted) = &self.persisted {
+                let thread_item = persisted
+                    .thread_item
+                    .and_then(proto::DebuggerThreadItem::from_i32)
+                    .map(ThreadItem::from_proto);
+                let watches = persisted.watches.clone();
+                mode.update(cx, |running_state, cx| {
+                    if !watches.is_empty() {
+                        running_state
+                            .watch_list()
+                            .update(cx, |watch_list, cx| watch_list.set_expressions(watches, cx));
+                    }
+                    if let Some(thread_item) = thread_item
+                        .filter(|thread_item| running_state.supports_thread_item(*thread_item, cx))
+                    {
+                        running_state.set_thread_item(thread_item, cx);
+                    }
+                });
+            }
+            self._subscriptions = [cx.subscribe(&mode, Self::on_running_event)];
             self.mode = DebugSessionState::Running(mode);
-        } else if let StartingEvent::Failed = event {
-            self.mode = DebugSessionState::Failed(cx.new(FailedState::new));
+        } else if let StartingEvent::Failed(error) = event {
+            let can_retry = self.relaunch.is_some();
+            // Sessions that can be retried are started without notifying about failures, so
+            // they're only reported once the automatic retries ran out.
+            if can_retry {
+                self.dap_store
+                    .update(cx, |store, cx| store.notify_start_failure(error, cx))
+                    .ok();
+            }
+            let failed = cx.new(|cx| FailedState::new(error, can_retry, cx));
+            self._subscriptions = [cx.subscribe_in(&failed, window, Self::on_failed_event)];
+            self.mode = DebugSessionState::Failed(failed);
         };
         cx.notify();
     }
+
+    fn on_failed_event(
+        &mut self,
+        _: &Entity<FailedState>,
+        event: &FailedEvent,
+        window: &mut Window,
+        cx: &mut Context<'_, Self>,
+    ) {
+        let FailedEvent::Retry = event;
+        let Some((session_id, relaunch)) = self.relaunch.clone() else {
+            return;
+        };
+        let task = relaunch(cx);
+        let starting = cx.new(|cx| StartingState::new(session_id, task, Some(relaunch), cx));
+
+        self._subscriptions = [cx.subscribe_in(&starting, window, Self::on_starting_event)];
+        self.mode = DebugSessionState::Starting(starting);
+        cx.notify();
+    }
+
+    /// Switches the running session to the tab a leader has open. Tabs this version doesn't know
+    /// about are ignored, leaving the current tab unchanged.
+    fn set_thread_item_from_proto(&mut self, thread_item: i32, cx: &mut Context<Self>) {
+        let Some(thread_item) = proto::DebuggerThreadItem::from_i32(thread_item) else {
+            return;
+        };
+        if let Some(running_state) = self.mode.as_running() {
+            running_state.update(cx, |running_state, cx| {
+                running_state.set_thread_item(ThreadItem::from_proto(thread_item), cx)
+            });
+        }
+    }
+
+    fn on_running_event(
+        &mut self,
+        _: Entity<RunningState>,
+        event: &DebugPanelItemEvent,
+        cx: &mut Context<'_, Self>,
+    ) {
+        cx.emit(event.clone());
+        cx.notify();
+    }
 }
 impl EventEmitter<DebugPanelItemEvent> for DebugSession {}
 
@@ -199,6 +437,9 @@ impl Item for DebugSession {
     type Event = DebugPanelItemEvent;
     fn tab_content(&self, _: item::TabContentParams, _: &Window, cx: &App) -> AnyElement {
         let (label, color) = match &self.mode {
+            DebugSessionState::Inert(_) if self.persisted.is_some() => {
+                ("Not Running", Color::Muted)
+            }
             DebugSessionState::Inert(_) => ("New Session", Color::Default),
             DebugSessionState::Starting(_) => ("Starting", Color::Default),
             DebugSessionState::Failed(_) => ("Failed", Color::Error),
@@ -210,6 +451,20 @@ impl Item for DebugSession {
                 Color::Default,
             ),
         };
+        // Several sessions can run at once, so each tab names the configuration it was started
+        // from.
+        let session_label = self
+            .mode
+            .as_running()
+            .and_then(|state| {
+                state
+                    .read(cx)
+                    .session()
+                    .read(cx)
+                    .configuration()
+                    .map(|config| config.label)
+            })
+            .or_else(|| Some(self.persisted.as_ref()?.config.label.clone()));
 
         let is_starting = matches!(self.mode, DebugSessionState::Starting(_));
 
@@ -222,6 +477,9 @@ impl Item for DebugSession {
                     |this, delta| this.transform(Transformation::rotate(percentage(delta))),
                 )
             }))
+            .when_some(session_label, |this, session_label| {
+                this.child(Label::new(session_label))
+            })
             .child(Label::new(label).color(color))
             .into_any_element()
     }
@@ -232,39 +490,90 @@ impl FollowableItem for DebugSession {
         self.remote_id
     }
 
-    fn to_state_proto(&self, _window: &Window, _cx: &App) -> Option<proto::view::Variant> {
-        None
+    fn to_state_proto(&self, _window: &Window, cx: &App) -> Option<proto::view::Variant> {
+        // Only running sessions exist on the followers' side, so there's nothing to share before
+        // the debug adapter has started.
+        let running_state = self.mode.as_running()?.read(cx);
+
+        Some(proto::view::Variant::DebugSession(
+            proto::view::DebugSession {
+                session_id: running_state.session_id().to_proto(),
+                active_thread_item: running_state.active_thread_item().to_proto().into(),
+            },
+        ))
     }
 
     fn from_state_proto(
-        _workspace: Entity<Workspace>,
-        _remote_id: ViewId,
-        _state: &mut Option<proto::view::Variant>,
-        _window: &mut Window,
-        _cx: &mut App,
+        workspace: Entity<Workspace>,
+        remote_id: ViewId,
+        state: &mut Option<proto::view::Variant>,
+        window: &mut Window,
+        cx: &mut App,
     ) -> Option<gpui::Task<gpui::Result<Entity<Self>>>> {
-        None
+        let Some(proto::view::Variant::DebugSession(_)) = state else {
+            return None;
+        };
+        let Some(proto::view::Variant::DebugSession(state)) = state.take() else {
+            unreachable!()
+        };
+
+        let project = workspace.read(cx).project().clone();
+        let session_id = SessionId::from_proto(state.session_id);
+        let Some(session) = project
+            .read(cx)
+            .dap_store()
+            .read(cx)
+            .session_by_id(session_id)
+        else {
+            return Some(Task::ready(Err(anyhow!(
+                "no debug session found with ID: {session_id:?}"
+            ))));
+        };
+
+        let debug_session =
+            DebugSession::running(project, workspace.downgrade(), session, window, cx);
+        debug_session.update(cx, |debug_session, cx| {
+            debug_session.remote_id = Some(remote_id);
+            debug_session.set_thread_item_from_proto(state.active_thread_item, cx);
+        });
+
+        Some(Task::ready(Ok(debug_session)))
     }
 
     fn add_event_to_update_proto(
         &self,
-        _event: &Self::Event,
-        _update: &mut Option<proto::update_view::Variant>,
+        event: &Self::Event,
+        update: &mut Option<proto::update_view::Variant>,
         _window: &Window,
         _cx: &App,
     ) -> bool {
-        // update.get_or_insert_with(|| proto::update_view::Variant::DebugPanel(Default::default()));
-
-        true
+        match event {
+            DebugPanelItemEvent::ThreadItemChanged(thread_item) => {
+                *update = Some(proto::update_view::Variant::DebugSession(
+                    proto::update_view::DebugSession {
+                        active_thread_item: thread_item.to_proto().into(),
+                    },
+                ));
+                true
+            }
+            DebugPanelItemEvent::Close
+            | DebugPanelItemEvent::Stopped { .. }
+            | DebugPanelItemEvent::Continued
+            | DebugPanelItemEvent::PersistentStateChanged => false,
+        }
     }
 
     fn apply_update_proto(
         &mut self,
         _project: &Entity<project::Project>,
-        _message: proto::update_view::Variant,
+        message: proto::update_view::Variant,
         _window: &mut Window,
-        _cx: &mut Context<Self>,
+        cx: &mut Context<Self>,
     ) -> gpui::Task<gpui::Result<()>> {
+        if let proto::update_view::Variant::DebugSession(message) = message {
+            self.set_thread_item_from_proto(message.active_thread_item, cx);
+        }
+
         Task::ready(Ok(()))
     }
 
@@ -281,7 +590,9 @@ impl FollowableItem for DebugSession {
     }
 
     fn dedup(&self, existing: &Self, _window: &Window, cx: &App) -> Option<workspace::item::Dedup> {
-        if existing.session_id(cx) == self.session_id(cx) {
+        // Sessions that haven't started have no ID, and are never duplicates of each other.
+        let session_id = self.session_id(cx)?;
+        if existing.session_id(cx) == Some(session_id) {
             Some(item::Dedup::KeepExisting)
         } else {
             None
@@ -306,8 +617,38 @@ impl Render for DebugSession {
                 failed_state.update(cx, |this, cx| this.render(window, cx).into_any_element())
             }
             DebugSessionState::Running(running_state) => {
-                running_state.update(cx, |this, cx| this.render(window, cx).into_any_element())
+                let exit = running_state.read(cx).exit();
+                v_flex()
+                    .size_full()
+                    .when_some(exit, |this, exit| this.child(render_exit_banner(exit, cx)))
+                    .child(
+                        running_state
+                            .update(cx, |this, cx| this.render(window, cx).into_any_element()),
+                    )
+                    .into_any_element()
             }
         }
     }
 }
+
+/// Tells how the session ended, above the panes that are kept for inspection.
+fn render_exit_banner(exit: SessionExit, cx: &App) -> impl IntoElement {
+    let (icon, color) = if exit.is_error() {
+        (IconName::XCircle, Color::Error)
+    } else {
+        (IconName::Check, Color::Muted)
+    };
+
+    h_flex()
+        .w_full()
+        .px_2()
+        .py_1()
+        .gap_2()
+        .border_b_1()
+        .border_color(cx.theme().colors().border_variant)
+        .when(exit.is_error(), |this| {
+            this.bg(cx.theme().status().error_background)
+        })
+        .child(Icon::new(icon).size(IconSize::Small).color(color))
+        .child(Label::new(exit.message()).size(LabelSize::Small))
+}
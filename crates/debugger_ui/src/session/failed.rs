@@ -1,17 +1,53 @@
-use gpui::{FocusHandle, Focusable};
-use ui::{
-    h_flex, Color, Context, IntoElement, Label, LabelCommon, ParentElement, Render, Styled, Window,
-};
+use dap::transport::AdapterStartError;
+use gpui::{EventEmitter, FocusHandle, Focusable};
+use ui::prelude::*;
 
 pub(crate) struct FailedState {
     focus_handle: FocusHandle,
+    /// Why the adapter failed to start, with the causes of the failure.
+    message: SharedString,
+    /// What the adapter wrote to stderr before failing, if anything.
+    stderr: Option<SharedString>,
+    can_retry: bool,
 }
+
+pub(crate) enum FailedEvent {
+    Retry,
+}
+
+impl EventEmitter<FailedEvent> for FailedState {}
+
 impl FailedState {
-    pub(super) fn new(cx: &mut Context<Self>) -> Self {
+    pub(super) fn new(error: &anyhow::Error, can_retry: bool, cx: &mut Context<Self>) -> Self {
+        let message = error
+            .chain()
+            .filter(|cause| !cause.is::<AdapterStartError>())
+            .map(|cause| cause.to_string())
+            .collect::<Vec<_>>()
+            .join(": ");
+        let stderr = error
+            .downcast_ref::<AdapterStartError>()
+            .map(|error| error.stderr().trim_end())
+            .filter(|stderr| !stderr.is_empty())
+            .map(|stderr| stderr.to_string().into());
+
         Self {
             focus_handle: cx.focus_handle(),
+            message: message.into(),
+            stderr,
+            can_retry,
         }
     }
+
+    #[cfg(any(test, feature = "test-support"))]
+    pub(crate) fn message(&self) -> &SharedString {
+        &self.message
+    }
+
+    #[cfg(any(test, feature = "test-support"))]
+    pub(crate) fn stderr(&self) -> Option<&SharedString> {
+        self.stderr.as_ref()
+    }
 }
 
 impl Focusable for FailedState {
@@ -20,11 +56,42 @@ impl Focusable for FailedState {
     }
 }
 impl Render for FailedState {
-    fn render(&mut self, _: &mut Window, _: &mut Context<'_, Self>) -> impl IntoElement {
-        h_flex()
+    fn render(&mut self, _: &mut Window, cx: &mut Context<'_, Self>) -> impl IntoElement {
+        v_flex()
             .size_full()
             .items_center()
             .justify_center()
+            .gap_2()
+            .p_4()
             .child(Label::new("Failed to spawn debugging session").color(Color::Error))
+            .child(
+                Label::new(self.message.clone())
+                    .size(LabelSize::Small)
+                    .color(Color::Muted),
+            )
+            .when_some(self.stderr.clone(), |this, stderr| {
+                this.child(
+                    div()
+                        .id("debug-adapter-stderr")
+                        .max_w_full()
+                        .max_h_48()
+                        .overflow_y_scroll()
+                        .p_2()
+                        .rounded_md()
+                        .bg(cx.theme().colors().editor_background)
+                        .font_buffer(cx)
+                        .text_ui_sm(cx)
+                        .child(stderr),
+                )
+            })
+            .when(self.can_retry, |this| {
+                this.child(
+                    h_flex().child(Button::new("retry-debug-session", "Retry").on_click(
+                        cx.listener(|_, _, _, cx| {
+                            cx.emit(FailedEvent::Retry);
+                        }),
+                    )),
+                )
+            })
     }
 }
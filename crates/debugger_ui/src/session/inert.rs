@@ -1,16 +1,21 @@
 use std::path::PathBuf;
 
 use dap::{DebugAdapterConfig, DebugAdapterKind, DebugRequestType};
+use db::kvp::KEY_VALUE_STORE;
 use editor::{Editor, EditorElement, EditorStyle};
-use gpui::{App, AppContext, Entity, EventEmitter, FocusHandle, Focusable, TextStyle, WeakEntity};
+use gpui::{
+    App, AppContext, Entity, EventEmitter, FocusHandle, Focusable, Task, TextStyle, WeakEntity,
+};
 use settings::Settings as _;
 use task::TCPHost;
 use theme::ThemeSettings;
 use ui::{
-    h_flex, relative, v_flex, ActiveTheme as _, Button, ButtonCommon, ButtonStyle, Clickable,
-    Context, ContextMenu, Disableable, DropdownMenu, InteractiveElement, IntoElement,
-    ParentElement, Render, SharedString, Styled, Window,
+    h_flex, relative, v_flex, ActiveTheme as _, Button, ButtonCommon, ButtonStyle,
+    CheckboxWithLabel, Clickable, Context, ContextMenu, Disableable, DropdownMenu,
+    InteractiveElement, IntoElement, Label, ParentElement, Render, SharedString, Styled,
+    ToggleState, Window,
 };
+use util::{ResultExt as _, TryFutureExt as _};
 use workspace::Workspace;
 
 use crate::attach_modal::AttachModal;
@@ -20,7 +25,13 @@ pub(crate) struct InertState {
     selected_debugger: Option<SharedString>,
     program_editor: Entity<Editor>,
     cwd_editor: Entity<Editor>,
+    /// Whether the session should stop at the program's entry point. Remembered per debugger.
+    stop_on_entry: bool,
     workspace: WeakEntity<Workspace>,
+    /// The configuration restored from a previous run, launched again with the edited program,
+    /// working directory and debugger.
+    restored_config: Option<DebugAdapterConfig>,
+    pending_serialization: Task<Option<()>>,
 }
 
 impl InertState {
@@ -46,10 +57,76 @@ impl InertState {
             cwd_editor,
             program_editor,
             selected_debugger: None,
+            stop_on_entry: false,
+            restored_config: None,
             focus_handle: cx.focus_handle(),
+            pending_serialization: Task::ready(None),
+        }
+    }
+
+    /// Fills in the fields from a configuration persisted from a previous run.
+    pub(super) fn restore(
+        &mut self,
+        config: &DebugAdapterConfig,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if let Some(program) = &config.program {
+            self.program_editor.update(cx, |editor, cx| {
+                editor.set_text(program.as_str(), window, cx)
+            });
+        }
+        if let Some(cwd) = &config.cwd {
+            self.cwd_editor.update(cx, |editor, cx| {
+                editor.set_text(cwd.to_string_lossy().as_ref(), window, cx)
+            });
         }
+        self.selected_debugger = Some(
+            label_for_kind(&config.kind)
+                .unwrap_or_else(|| config.kind.display_name())
+                .to_string()
+                .into(),
+        );
+        self.stop_on_entry = config.stop_on_entry;
+        self.restored_config = Some(config.clone());
+        cx.notify();
+    }
+
+    fn select_debugger(&mut self, name: &'static str, cx: &mut Context<Self>) {
+        self.selected_debugger = Some(name.into());
+        if let Some(config) = &mut self.restored_config {
+            config.kind = kind_for_label(name);
+        }
+        self.stop_on_entry = KEY_VALUE_STORE
+            .read_kvp(&stop_on_entry_key(name))
+            .log_err()
+            .flatten()
+            .is_some_and(|value| value == "true");
+        cx.notify();
+    }
+
+    fn set_stop_on_entry(&mut self, stop_on_entry: bool, cx: &mut Context<Self>) {
+        self.stop_on_entry = stop_on_entry;
+        cx.notify();
+
+        let Some(debugger) = self.selected_debugger.clone() else {
+            return;
+        };
+        self.pending_serialization = cx.background_spawn(
+            async move {
+                KEY_VALUE_STORE
+                    .write_kvp(stop_on_entry_key(&debugger), stop_on_entry.to_string())
+                    .await
+            }
+            .log_err(),
+        );
     }
 }
+
+fn stop_on_entry_key(debugger: &str) -> String {
+    format!("debugger_stop_on_entry_{debugger}")
+}
+
 impl Focusable for InertState {
     fn focus_handle(&self, _cx: &App) -> FocusHandle {
         self.focus_handle.clone()
@@ -77,14 +154,13 @@ impl Render for InertState {
             .size_full()
             .gap_1()
             .p_2()
-
             .child(
-                v_flex().gap_1()
+                v_flex()
+                    .gap_1()
                     .child(
                         h_flex()
                             .w_full()
                             .gap_2()
-
                             .child(Self::render_editor(&self.program_editor, cx))
                             .child(
                                 h_flex().child(DropdownMenu::new(
@@ -99,8 +175,8 @@ impl Render for InertState {
                                             move |_: &mut Window, cx: &mut App| {
                                                 let name = name;
                                                 (&weak)
-                                                    .update(cx, move |this, _| {
-                                                        this.selected_debugger = Some(name.into());
+                                                    .update(cx, move |this, cx| {
+                                                        this.select_debugger(name, cx);
                                                     })
                                                     .ok();
                                             }
@@ -109,49 +185,72 @@ impl Render for InertState {
                                             .entry("Delve", None, setter_for_name("Delve"))
                                             .entry("LLDB", None, setter_for_name("LLDB"))
                                             .entry("PHP", None, setter_for_name("PHP"))
-                                            .entry("JavaScript", None, setter_for_name("JavaScript"))
+                                            .entry(
+                                                "JavaScript",
+                                                None,
+                                                setter_for_name("JavaScript"),
+                                            )
                                             .entry("Debugpy", None, setter_for_name("Debugpy"))
                                     }),
                                 )),
-                            )
+                            ),
                     )
                     .child(
-                        h_flex().gap_2().child(
-                            Self::render_editor(&self.cwd_editor, cx),
-                        ).child(h_flex()
-                            .gap_4()
-                            .pl_2()
+                        h_flex()
+                            .gap_2()
+                            .child(Self::render_editor(&self.cwd_editor, cx))
                             .child(
-                                Button::new("launch-dap", "Launch")
-                                    .style(ButtonStyle::Filled)
-                                    .disabled(disable_buttons)
-                                    .on_click(cx.listener(|this, _, _, cx| {
-                                        let program = this.program_editor.read(cx).text(cx);
-                                        let cwd = PathBuf::from(this.cwd_editor.read(cx).text(cx));
-                                        let kind = kind_for_label(this.selected_debugger.as_deref().unwrap_or_else(|| unimplemented!("Automatic selection of a debugger based on users project")));
-                                        cx.emit(InertEvent::Spawned {
-                                            config: DebugAdapterConfig {
-                                                label: "hard coded".into(),
-                                                kind,
-                                                request: DebugRequestType::Launch,
-                                                program: Some(program),
-                                                cwd: Some(cwd),
-                                                initialize_args: None,
-                                                supports_attach: false,
-                                            },
-                                        });
-                                    })),
-                            )
-                            .child(Button::new("attach-dap", "Attach")
-                                .style(ButtonStyle::Filled)
-                                .disabled(disable_buttons)
-                                .on_click(cx.listener(|this, _, window, cx| this.attach(window, cx)))
-                            ))
-                    )
+                                h_flex()
+                                    .gap_4()
+                                    .pl_2()
+                                    .child(CheckboxWithLabel::new(
+                                        "stop-on-entry",
+                                        Label::new("Stop on entry"),
+                                        ToggleState::from(self.stop_on_entry),
+                                        {
+                                            let weak = cx.weak_entity();
+                                            move |state, _, cx| {
+                                                weak.update(cx, |this, cx| {
+                                                    this.set_stop_on_entry(state.selected(), cx);
+                                                })
+                                                .ok();
+                                            }
+                                        },
+                                    ))
+                                    .child(
+                                        Button::new("launch-dap", "Launch")
+                                            .style(ButtonStyle::Filled)
+                                            .disabled(disable_buttons)
+                                            .on_click(
+                                                cx.listener(|this, _, _, cx| this.launch(cx)),
+                                            ),
+                                    )
+                                    .child(
+                                        Button::new("attach-dap", "Attach")
+                                            .style(ButtonStyle::Filled)
+                                            .disabled(disable_buttons)
+                                            .on_click(cx.listener(|this, _, window, cx| {
+                                                this.attach(window, cx)
+                                            })),
+                                    ),
+                            ),
+                    ),
             )
     }
 }
 
+/// The debugger picker's label for `kind`, for the kinds that can be picked.
+fn label_for_kind(kind: &DebugAdapterKind) -> Option<&'static str> {
+    match kind {
+        DebugAdapterKind::Lldb => Some("LLDB"),
+        DebugAdapterKind::Python(_) => Some("Debugpy"),
+        DebugAdapterKind::Javascript(_) => Some("JavaScript"),
+        DebugAdapterKind::Php(_) => Some("PHP"),
+        DebugAdapterKind::Go(_) => Some("Delve"),
+        _ => None,
+    }
+}
+
 fn kind_for_label(label: &str) -> DebugAdapterKind {
     match label {
         "LLDB" => DebugAdapterKind::Lldb,
@@ -188,12 +287,51 @@ impl InertState {
         )
     }
 
+    pub(crate) fn launch(&self, cx: &mut Context<Self>) {
+        let program = self.program_editor.read(cx).text(cx);
+        let cwd = PathBuf::from(self.cwd_editor.read(cx).text(cx));
+        let config = match &self.restored_config {
+            Some(config) => DebugAdapterConfig {
+                request: DebugRequestType::Launch,
+                program: Some(program),
+                cwd: Some(cwd),
+                stop_on_entry: self.stop_on_entry,
+                ..config.clone()
+            },
+            // The buttons are disabled until a debugger is selected.
+            None => {
+                let Some(debugger) = self.selected_debugger.as_deref() else {
+                    log::error!("no debugger selected to launch");
+                    return;
+                };
+                DebugAdapterConfig {
+                    label: "hard coded".into(),
+                    kind: kind_for_label(debugger),
+                    request: DebugRequestType::Launch,
+                    program: Some(program),
+                    cwd: Some(cwd),
+                    initialize_args: None,
+                    supports_attach: false,
+                    stop_on_entry: self.stop_on_entry,
+                }
+            }
+        };
+        cx.emit(InertEvent::Spawned { config });
+    }
+
     fn attach(&self, window: &mut Window, cx: &mut Context<Self>) {
         let process_id = self.program_editor.read(cx).text(cx).parse::<u32>().ok();
         let cwd = PathBuf::from(self.cwd_editor.read(cx).text(cx));
-        let kind = kind_for_label(self.selected_debugger.as_deref().unwrap_or_else(|| {
-            unimplemented!("Automatic selection of a debugger based on users project")
-        }));
+        let kind = match &self.restored_config {
+            Some(config) => config.kind.clone(),
+            None => {
+                let Some(debugger) = self.selected_debugger.as_deref() else {
+                    log::error!("no debugger selected to attach with");
+                    return;
+                };
+                kind_for_label(debugger)
+            }
+        };
 
         let config = DebugAdapterConfig {
             label: "hard coded attach".into(),
@@ -203,6 +341,7 @@ impl InertState {
             cwd: Some(cwd),
             initialize_args: None,
             supports_attach: true,
+            stop_on_entry: self.stop_on_entry,
         };
 
         if process_id.is_some() {
@@ -1,33 +1,151 @@
-mod console;
+pub mod breakpoint_list;
+pub mod console;
+mod disassembly_view;
+pub mod exception_breakpoint_list;
 mod loaded_source_list;
 mod module_list;
 pub mod stack_frame_list;
 pub mod variable_list;
+pub mod watch_list;
 
 use super::{DebugPanelItemEvent, ThreadItem};
+use breakpoint_list::BreakpointList;
 use console::Console;
-use dap::{client::SessionId, debugger_settings::DebuggerSettings, Capabilities, Thread};
-use gpui::{AppContext, Entity, EventEmitter, FocusHandle, Focusable, Subscription, WeakEntity};
-use loaded_source_list::LoadedSourceList;
+use dap::{
+    client::SessionId, debugger_settings::DebuggerSettings, Capabilities, SteppingGranularity,
+    StoppedEventReason, Thread,
+};
+use disassembly_view::{parse_address, DisassemblyView};
+use editor::{Editor, EditorEvent};
+use exception_breakpoint_list::ExceptionBreakpointList;
+use gpui::{
+    actions, AppContext, ClipboardItem, Entity, EventEmitter, FocusHandle, Focusable, Subscription,
+    Task, WeakEntity,
+};
+use loaded_source_list::{open_source, LoadedSourceList};
+use menu::Confirm;
 use module_list::ModuleList;
 use project::debugger::session::{Session, SessionEvent, ThreadId, ThreadStatus};
 use rpc::proto::ViewId;
 use settings::Settings;
-use stack_frame_list::StackFrameList;
+use stack_frame_list::{
+    format_stack_trace, StackFrameHistory, StackFrameList, StackFrameListEvent,
+};
+use std::time::{Duration, Instant};
 use ui::{
-    div, h_flex, v_flex, ActiveTheme, AnyElement, App, Button, ButtonCommon, Clickable, Context,
-    ContextMenu, Disableable, DropdownMenu, FluentBuilder, IconButton, IconName, IconSize,
-    Indicator, InteractiveElement, IntoElement, ParentElement, Render, SharedString,
-    StatefulInteractiveElement, Styled, Tooltip, Window,
+    div, h_flex, v_flex, ActiveTheme, AnyElement, App, Button, ButtonCommon, Clickable, Color,
+    Context, ContextMenu, Disableable, DropdownMenu, FluentBuilder, IconButton, IconName,
+    IconPosition, IconSize, Indicator, InteractiveElement, IntoElement, Label, LabelCommon,
+    LabelSize, ParentElement, PopoverMenu, Render, SharedString, StatefulInteractiveElement,
+    Styled, Tooltip, Window,
 };
 use util::ResultExt;
 use variable_list::VariableList;
-use workspace::Workspace;
+use watch_list::{WatchList, WatchListEvent};
+use workspace::{notifications::NotificationId, Toast, Workspace};
+
+actions!(
+    debugger,
+    [
+        GoBackStackFrame,
+        GoForwardStackFrame,
+        CopyStackTrace,
+        CopyAllStackTraces
+    ]
+);
+
+/// How a debug session ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SessionExit {
+    /// The debuggee exited by itself with this exit code.
+    Exited { exit_code: u64 },
+    /// The user stopped or disconnected the session.
+    TerminatedByUser,
+    /// The adapter ended the session without reporting an exit code, e.g. after a crash.
+    Terminated,
+}
+
+impl SessionExit {
+    pub fn message(&self) -> SharedString {
+        match self {
+            SessionExit::Exited { exit_code } => {
+                format!("Process exited with code {exit_code}").into()
+            }
+            SessionExit::TerminatedByUser => "Process terminated by user".into(),
+            SessionExit::Terminated => "Process terminated".into(),
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        match self {
+            SessionExit::Exited { exit_code } => *exit_code != 0,
+            SessionExit::TerminatedByUser => false,
+            SessionExit::Terminated => true,
+        }
+    }
+}
+
+/// Wall-clock time since the session launched and since the debuggee last stopped.
+#[derive(Clone, Copy, Debug)]
+struct SessionTimers {
+    launched_at: Instant,
+    /// When the debuggee last stopped, cleared when it continues.
+    stopped_at: Option<Instant>,
+    /// When the session ended, which freezes both timers.
+    ended_at: Option<Instant>,
+}
+
+impl SessionTimers {
+    fn new() -> Self {
+        Self {
+            launched_at: Instant::now(),
+            stopped_at: None,
+            ended_at: None,
+        }
+    }
+
+    fn now(&self) -> Instant {
+        self.ended_at.unwrap_or_else(Instant::now)
+    }
+
+    fn since_launch(&self) -> Duration {
+        self.now().saturating_duration_since(self.launched_at)
+    }
+
+    fn since_stop(&self) -> Option<Duration> {
+        Some(self.now().saturating_duration_since(self.stopped_at?))
+    }
+}
+
+/// Formats a duration as `m:ss`, or `h:mm:ss` once it reaches an hour.
+fn format_elapsed(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{seconds:02}")
+    } else {
+        format!("{minutes}:{seconds:02}")
+    }
+}
 
 pub struct RunningState {
     session: Entity<Session>,
     thread_id: Option<ThreadId>,
+    /// The stack frames selected since the program last stopped, reset on every stop.
+    stack_frame_history: StackFrameHistory,
+    /// The granularity steps are taken at, when the adapter supports choosing it.
+    stepping_granularity: SteppingGranularity,
+    /// How the session ended, once it has. The panes are kept around for inspection.
+    exit: Option<SessionExit>,
+    /// Whether the user asked to stop the session, so its exit isn't reported as a crash.
+    terminated_by_user: bool,
+    timers: SessionTimers,
+    /// Re-renders every second so the timers tick, until the session ends.
+    _tick_timers: Task<()>,
     console: Entity<console::Console>,
+    /// Where the console was scrolled to when another tab was selected, restored when it's
+    /// selected again.
+    console_scroll: Option<console::ConsoleScroll>,
     focus_handle: FocusHandle,
     _remote_id: Option<ViewId>,
     show_console_indicator: bool,
@@ -39,6 +157,41 @@ pub struct RunningState {
     _subscriptions: Vec<Subscription>,
     stack_frame_list: Entity<stack_frame_list::StackFrameList>,
     loaded_source_list: Entity<loaded_source_list::LoadedSourceList>,
+    watch_list: Entity<watch_list::WatchList>,
+    breakpoint_list: Entity<BreakpointList>,
+    exception_breakpoint_list: Entity<ExceptionBreakpointList>,
+    disassembly_view: Entity<DisassemblyView>,
+    /// Takes a line of the selected stack frame's source, or an instruction address, to go to.
+    go_to_editor: Entity<Editor>,
+    /// Why the last go to failed, shown below the input until it's edited.
+    go_to_error: Option<SharedString>,
+    go_to_task: Task<()>,
+}
+
+/// Where the go to input navigates.
+#[derive(Debug, PartialEq, Eq)]
+enum GoToTarget {
+    /// A one-based line in the selected stack frame's source.
+    Line(u32),
+    /// An instruction address in the disassembly.
+    Address(u64),
+}
+
+fn parse_go_to_target(input: &str) -> Result<GoToTarget, SharedString> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err("Enter a line number or an address such as 0x1000".into());
+    }
+    if input.starts_with("0x") || input.starts_with("0X") {
+        return parse_address(input)
+            .map(GoToTarget::Address)
+            .ok_or_else(|| format!("\"{input}\" isn't a valid address").into());
+    }
+    match input.parse::<u32>() {
+        Ok(0) => Err("Lines start at 1".into()),
+        Ok(line) => Ok(GoToTarget::Line(line)),
+        Err(_) => Err(format!("\"{input}\" isn't a line number or an address").into()),
+    }
 }
 
 impl Render for RunningState {
@@ -61,16 +214,30 @@ impl Render for RunningState {
             this.disabled(thread_status != ThreadStatus::Stopped, cx);
         });
 
-        let is_terminated = self.session.read(cx).is_terminated();
         let active_thread_item = &self.active_thread_item;
 
         let has_no_threads = threads.is_empty();
+        let thread_count = threads.len();
         let capabilities = self.capabilities(cx);
+        let supports_step_back = capabilities.supports_step_back.unwrap_or_default();
+        let can_restart = self.can_restart(cx);
+        let restart_tooltip = if capabilities.supports_restart_request.unwrap_or_default() {
+            "Restart"
+        } else if can_restart {
+            "Restart (relaunches the debug adapter)"
+        } else {
+            "This debug adapter doesn't support restarting"
+        };
+        let can_go_back = self.stack_frame_history.can_go_back();
+        let can_go_forward = self.stack_frame_history.can_go_forward();
         let state = cx.entity();
         h_flex()
-            .when(is_terminated, |this| this.bg(gpui::red()))
             .key_context("DebugPanelItem")
             .track_focus(&self.focus_handle(cx))
+            .on_action(cx.listener(Self::go_back_stack_frame))
+            .on_action(cx.listener(Self::go_forward_stack_frame))
+            .on_action(cx.listener(Self::copy_stack_trace))
+            .on_action(cx.listener(Self::copy_all_stack_traces))
             .size_full()
             .items_start()
             .child(
@@ -120,24 +287,23 @@ impl Render for RunningState {
                                             )
                                         }
                                     })
-                                    .when(
-                                        capabilities.supports_step_back.unwrap_or(false),
-                                        |this| {
-                                            this.child(
-                                                IconButton::new(
-                                                    "debug-step-back",
-                                                    IconName::DebugStepBack,
-                                                )
-                                                .icon_size(IconSize::Small)
-                                                .on_click(cx.listener(|this, _, _window, cx| {
-                                                    this.step_back(cx);
-                                                }))
-                                                .disabled(thread_status != ThreadStatus::Stopped)
-                                                .tooltip(move |window, cx| {
-                                                    Tooltip::text("Step back")(window, cx)
-                                                }),
+                                    .child(
+                                        IconButton::new("debug-step-back", IconName::DebugStepBack)
+                                            .icon_size(IconSize::Small)
+                                            .on_click(cx.listener(|this, _, _window, cx| {
+                                                this.step_back(cx);
+                                            }))
+                                            .disabled(
+                                                !supports_step_back
+                                                    || thread_status != ThreadStatus::Stopped,
                                             )
-                                        },
+                                            .tooltip(move |window, cx| {
+                                                Tooltip::text(if supports_step_back {
+                                                    "Step back"
+                                                } else {
+                                                    "This debug adapter doesn't support stepping back"
+                                                })(window, cx)
+                                            }),
                                     )
                                     .child(
                                         IconButton::new("debug-step-over", IconName::DebugStepOver)
@@ -178,13 +344,9 @@ impl Render for RunningState {
                                             .on_click(cx.listener(|this, _, _window, cx| {
                                                 this.restart_session(cx);
                                             }))
-                                            .disabled(
-                                                !capabilities
-                                                    .supports_restart_request
-                                                    .unwrap_or_default(),
-                                            )
+                                            .disabled(!can_restart)
                                             .tooltip(move |window, cx| {
-                                                Tooltip::text("Restart")(window, cx)
+                                                Tooltip::text(restart_tooltip)(window, cx)
                                             }),
                                     )
                                     .child(
@@ -203,8 +365,13 @@ impl Render for RunningState {
                                                     .unwrap_or_default()
                                                 {
                                                     "Terminate Thread"
-                                                } else {
+                                                } else if capabilities
+                                                    .supports_terminate_request
+                                                    .unwrap_or_default()
+                                                {
                                                     "Terminate all Threads"
+                                                } else {
+                                                    "This debug adapter doesn't support terminating, so it will be disconnected"
                                                 };
                                                 move |window, cx| Tooltip::text(label)(window, cx)
                                             }),
@@ -228,6 +395,46 @@ impl Render for RunningState {
                                             },
                                         ),
                                     )
+                                    .child(
+                                        IconButton::new(
+                                            "debug-go-back-stack-frame",
+                                            IconName::ArrowLeft,
+                                        )
+                                        .icon_size(IconSize::Small)
+                                        .on_click(cx.listener(|this, _, window, cx| {
+                                            this.go_back_stack_frame(&GoBackStackFrame, window, cx);
+                                        }))
+                                        .disabled(!can_go_back)
+                                        .tooltip(
+                                            move |window, cx| {
+                                                Tooltip::text("Go back to previous frame")(
+                                                    window, cx,
+                                                )
+                                            },
+                                        ),
+                                    )
+                                    .child(
+                                        IconButton::new(
+                                            "debug-go-forward-stack-frame",
+                                            IconName::ArrowRight,
+                                        )
+                                        .icon_size(IconSize::Small)
+                                        .on_click(cx.listener(|this, _, window, cx| {
+                                            this.go_forward_stack_frame(
+                                                &GoForwardStackFrame,
+                                                window,
+                                                cx,
+                                            );
+                                        }))
+                                        .disabled(!can_go_forward)
+                                        .tooltip(
+                                            move |window, cx| {
+                                                Tooltip::text("Go forward to next frame")(
+                                                    window, cx,
+                                                )
+                                            },
+                                        ),
+                                    )
                                     .child(
                                         IconButton::new(
                                             "debug-ignore-breakpoints",
@@ -250,37 +457,65 @@ impl Render for RunningState {
                                                 Tooltip::text("Ignore breakpoints")(window, cx)
                                             },
                                         ),
-                                    ),
+                                    )
+                                    .child(self.render_copy_stack_trace(
+                                        thread_status == ThreadStatus::Stopped,
+                                        thread_count,
+                                        cx,
+                                    )),
                             )
                             //.child(h_flex())
                             .child(
-                                h_flex().p_1().mx_2().w_3_4().justify_end().child(
-                                    DropdownMenu::new(
-                                        ("thread-list", self.session_id.0),
-                                        selected_thread_name,
-                                        ContextMenu::build(window, cx, move |mut this, _, _| {
-                                            for (thread, _) in threads {
-                                                let state = state.clone();
-                                                let thread_id = thread.id;
-                                                this =
-                                                    this.entry(thread.name, None, move |_, cx| {
-                                                        state.update(cx, |state, cx| {
-                                                            state.select_thread(
-                                                                ThreadId(thread_id),
-                                                                cx,
-                                                            );
-                                                        });
-                                                    });
-                                            }
-                                            this
-                                        }),
-                                    )
-                                    .disabled(
-                                        has_no_threads || thread_status != ThreadStatus::Stopped,
+                                h_flex()
+                                    .p_1()
+                                    .mx_2()
+                                    .w_3_4()
+                                    .gap_2()
+                                    .justify_end()
+                                    .child(self.render_timers(cx))
+                                    .child(self.render_stepping_granularity(window, cx))
+                                    .child(
+                                        DropdownMenu::new(
+                                            ("thread-list", self.session_id.0),
+                                            selected_thread_name,
+                                            ContextMenu::build(window, cx, move |mut this, _, _| {
+                                                for (thread, status) in threads {
+                                                    let state = state.clone();
+                                                    let thread_id = thread.id;
+                                                    let name = SharedString::from(thread.name);
+                                                    this =
+                                                        this.custom_entry(
+                                                            move |_, _| {
+                                                                h_flex()
+                                                                    .gap_2()
+                                                                    .child(Indicator::dot().color(
+                                                                        thread_status_color(status),
+                                                                    ))
+                                                                    .child(Label::new(name.clone()))
+                                                                    .into_any_element()
+                                                            },
+                                                            move |_, cx| {
+                                                                state.update(cx, |state, cx| {
+                                                                    state.pick_thread(
+                                                                        ThreadId(thread_id),
+                                                                        cx,
+                                                                    );
+                                                                });
+                                                            },
+                                                        );
+                                                }
+                                                this
+                                            }),
+                                        )
+                                        .disabled(
+                                            has_no_threads
+                                                || thread_status != ThreadStatus::Stopped,
+                                        ),
                                     ),
-                                ),
                             ),
                     )
+                    .children(self.render_stop_reason(cx))
+                    .child(self.render_go_to(cx))
                     .child(
                         h_flex()
                             .size_full()
@@ -306,28 +541,41 @@ impl Render for RunningState {
                                 ThreadItem::Variables,
                                 cx,
                             ))
+                            .child(self.render_entry_button(
+                                &SharedString::from("Watches"),
+                                ThreadItem::Watches,
+                                cx,
+                            ))
+                            .child(self.render_entry_button(
+                                &SharedString::from("Breakpoints"),
+                                ThreadItem::Breakpoints,
+                                cx,
+                            ))
                             .when(
-                                capabilities.supports_modules_request.unwrap_or_default(),
-                                |this| {
-                                    this.child(self.render_entry_button(
-                                        &SharedString::from("Modules"),
-                                        ThreadItem::Modules,
-                                        cx,
-                                    ))
-                                },
-                            )
-                            .when(
-                                capabilities
-                                    .supports_loaded_sources_request
-                                    .unwrap_or_default(),
+                                self.supports_thread_item(ThreadItem::ExceptionBreakpoints, cx),
                                 |this| {
                                     this.child(self.render_entry_button(
-                                        &SharedString::from("Loaded Sources"),
-                                        ThreadItem::LoadedSource,
+                                        &SharedString::from("Exceptions"),
+                                        ThreadItem::ExceptionBreakpoints,
                                         cx,
                                     ))
                                 },
                             )
+                            .child(self.render_entry_button(
+                                &SharedString::from("Modules"),
+                                ThreadItem::Modules,
+                                cx,
+                            ))
+                            .child(self.render_entry_button(
+                                &SharedString::from("Loaded Sources"),
+                                ThreadItem::LoadedSource,
+                                cx,
+                            ))
+                            .child(self.render_entry_button(
+                                &SharedString::from("Disassembly"),
+                                ThreadItem::Disassembly,
+                                cx,
+                            ))
                             .child(self.render_entry_button(
                                 &SharedString::from("Console"),
                                 ThreadItem::Console,
@@ -337,12 +585,25 @@ impl Render for RunningState {
                     .when(*active_thread_item == ThreadItem::Variables, |this| {
                         this.child(self.variable_list.clone())
                     })
+                    .when(*active_thread_item == ThreadItem::Watches, |this| {
+                        this.size_full().child(self.watch_list.clone())
+                    })
+                    .when(*active_thread_item == ThreadItem::Breakpoints, |this| {
+                        this.size_full().child(self.breakpoint_list.clone())
+                    })
+                    .when(
+                        *active_thread_item == ThreadItem::ExceptionBreakpoints,
+                        |this| this.size_full().child(self.exception_breakpoint_list.clone()),
+                    )
                     .when(*active_thread_item == ThreadItem::Modules, |this| {
                         this.size_full().child(self.module_list.clone())
                     })
                     .when(*active_thread_item == ThreadItem::LoadedSource, |this| {
                         this.size_full().child(self.loaded_source_list.clone())
                     })
+                    .when(*active_thread_item == ThreadItem::Disassembly, |this| {
+                        this.size_full().child(self.disassembly_view.clone())
+                    })
                     .when(*active_thread_item == ThreadItem::Console, |this| {
                         this.child(self.console.clone())
                     }),
@@ -361,19 +622,41 @@ impl RunningState {
         let session_id = session.read(cx).session_id();
         let weak_state = cx.weak_entity();
         let stack_frame_list = cx.new(|cx| {
-            StackFrameList::new(workspace.clone(), session.clone(), weak_state, window, cx)
+            StackFrameList::new(
+                workspace.clone(),
+                session.clone(),
+                weak_state.clone(),
+                window,
+                cx,
+            )
+        });
+
+        let disassembly_view = cx.new(|cx| {
+            DisassemblyView::new(session.clone(), stack_frame_list.clone(), weak_state, cx)
         });
 
         let variable_list =
             cx.new(|cx| VariableList::new(session.clone(), stack_frame_list.clone(), window, cx));
 
-        let module_list = cx.new(|cx| ModuleList::new(session.clone(), workspace.clone(), cx));
+        let watch_list =
+            cx.new(|cx| WatchList::new(session.clone(), stack_frame_list.clone(), window, cx));
+
+        let breakpoint_list =
+            cx.new(|cx| BreakpointList::new(session.clone(), workspace.clone(), cx));
+
+        let exception_breakpoint_list =
+            cx.new(|cx| ExceptionBreakpointList::new(session.clone(), window, cx));
+
+        let module_list =
+            cx.new(|cx| ModuleList::new(session.clone(), workspace.clone(), window, cx));
 
-        let loaded_source_list = cx.new(|cx| LoadedSourceList::new(session.clone(), cx));
+        let loaded_source_list =
+            cx.new(|cx| LoadedSourceList::new(session.clone(), workspace.clone(), cx));
 
         let console = cx.new(|cx| {
             Console::new(
                 session.clone(),
+                workspace.clone(),
                 stack_frame_list.clone(),
                 variable_list.clone(),
                 window,
@@ -381,43 +664,152 @@ impl RunningState {
             )
         });
 
+        let go_to_editor = cx.new(|cx| {
+            let mut editor = Editor::single_line(window, cx);
+            editor.set_placeholder_text("Go to line or address", cx);
+            editor
+        });
+
         let _subscriptions = vec![
             cx.observe(&module_list, |_, _, cx| cx.notify()),
+            cx.subscribe(&watch_list, |_, _, event, cx| match event {
+                WatchListEvent::WatchesChanged => {
+                    cx.emit(DebugPanelItemEvent::PersistentStateChanged)
+                }
+            }),
+            cx.subscribe(&go_to_editor, |this, _, event: &EditorEvent, cx| {
+                if matches!(event, EditorEvent::BufferEdited) && this.go_to_error.take().is_some() {
+                    cx.notify();
+                }
+            }),
+            cx.subscribe(&stack_frame_list, |this, _, event, cx| match event {
+                StackFrameListEvent::SelectedStackFrameChanged(stack_frame_id) => {
+                    this.stack_frame_history.push(*stack_frame_id);
+                    cx.notify();
+                }
+            }),
+            // Keeps the thread indicators live as threads start, continue, and exit.
+            cx.observe(&session, |_, _, cx| cx.notify()),
             cx.subscribe_in(&session, window, |this, _, event, window, cx| {
                 match event {
                     SessionEvent::Stopped(thread_id) => {
-                        this.workspace
-                            .update(cx, |workspace, cx| {
-                                workspace.open_panel::<crate::DebugPanel>(window, cx);
-                            })
-                            .log_err();
+                        // Frames from an earlier stop are gone, so navigation starts over.
+                        this.stack_frame_history.clear();
+                        this.timers.stopped_at = Some(Instant::now());
+
+                        let select_stopped_thread =
+                            DebuggerSettings::get_global(cx).select_stopped_thread;
+
+                        if select_stopped_thread {
+                            this.workspace
+                                .update(cx, |workspace, cx| {
+                                    workspace.open_panel::<crate::DebugPanel>(window, cx);
+                                })
+                                .log_err();
+                        }
 
+                        // Otherwise the stopped frame is still selected for the variables and
+                        // console, without opening it.
+                        this.stack_frame_list.update(cx, |list, _| {
+                            list.set_go_to_first_stack_frame(select_stopped_thread)
+                        });
                         if let Some(thread_id) = thread_id {
-                            this.select_thread(*thread_id, cx);
+                            if select_stopped_thread || this.thread_id.is_none() {
+                                this.select_thread(*thread_id, cx);
+                            }
                         }
+
+                        let stopped_on_exception =
+                            this.session.read(cx).last_stop().is_some_and(|stop| {
+                                matches!(stop.reason, StoppedEventReason::Exception)
+                            });
+                        if stopped_on_exception
+                            && DebuggerSettings::get_global(cx).show_variables_on_exception
+                        {
+                            this.set_thread_item(ThreadItem::Variables, cx);
+                            cx.emit(DebugPanelItemEvent::ThreadItemChanged(
+                                ThreadItem::Variables,
+                            ));
+                        }
+
+                        cx.emit(DebugPanelItemEvent::Stopped {
+                            go_to_stack_frame: select_stopped_thread,
+                        });
+                    }
+                    SessionEvent::Continued => {
+                        this.timers.stopped_at = None;
+                        cx.emit(DebugPanelItemEvent::Continued);
                     }
                     SessionEvent::Threads => {
                         let threads = this.session.update(cx, |this, cx| this.threads(cx));
                         this.select_current_thread(&threads, cx);
                     }
+                    SessionEvent::Exited { exit_code } => {
+                        this.timers.ended_at.get_or_insert_with(Instant::now);
+                        this.exit = Some(if this.terminated_by_user {
+                            SessionExit::TerminatedByUser
+                        } else {
+                            SessionExit::Exited {
+                                exit_code: *exit_code,
+                            }
+                        });
+                    }
+                    // Adapters usually report the exit code before terminating, which is kept.
+                    SessionEvent::Terminated => {
+                        this.timers.ended_at.get_or_insert_with(Instant::now);
+                        if this.exit.is_none() {
+                            this.exit = Some(if this.terminated_by_user {
+                                SessionExit::TerminatedByUser
+                            } else {
+                                SessionExit::Terminated
+                            });
+                        }
+                    }
                     _ => {}
                 }
                 cx.notify()
             }),
         ];
 
+        let _tick_timers = cx.spawn(|this, mut cx| async move {
+            loop {
+                cx.background_executor().timer(Duration::from_secs(1)).await;
+                let ended = this.update(&mut cx, |this, cx| {
+                    cx.notify();
+                    this.timers.ended_at.is_some()
+                });
+                if ended.unwrap_or(true) {
+                    break;
+                }
+            }
+        });
+
         Self {
             session,
             console,
+            console_scroll: None,
             workspace,
             module_list,
             focus_handle,
             variable_list,
             _subscriptions,
             thread_id: None,
+            stack_frame_history: StackFrameHistory::default(),
+            stepping_granularity: DebuggerSettings::get_global(cx).stepping_granularity,
+            exit: None,
+            terminated_by_user: false,
+            timers: SessionTimers::new(),
+            _tick_timers,
             _remote_id: None,
             stack_frame_list,
             loaded_source_list,
+            watch_list,
+            breakpoint_list,
+            exception_breakpoint_list,
+            disassembly_view,
+            go_to_editor,
+            go_to_error: None,
+            go_to_task: Task::ready(()),
             session_id,
             show_console_indicator: false,
             active_thread_item: ThreadItem::Variables,
@@ -431,6 +823,182 @@ impl RunningState {
         }
     }
 
+    fn confirm_go_to(&mut self, _: &Confirm, window: &mut Window, cx: &mut Context<Self>) {
+        let input = self.go_to_editor.read(cx).text(cx);
+        self.go_to(&input, window, cx);
+    }
+
+    /// Goes to a line of the selected stack frame's source, or to the instruction at an address
+    /// such as `0x1000` in the disassembly. Invalid input is reported below the go to input.
+    pub fn go_to(&mut self, input: &str, window: &mut Window, cx: &mut Context<Self>) {
+        let result = parse_go_to_target(input).and_then(|target| match target {
+            GoToTarget::Line(line) => self.go_to_line(line, window, cx),
+            GoToTarget::Address(address) => self.go_to_address(address, cx),
+        });
+        self.go_to_error = result.err();
+        cx.notify();
+    }
+
+    fn go_to_line(
+        &mut self,
+        line: u32,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Result<(), SharedString> {
+        let source = self
+            .stack_frame_list
+            .update(cx, |list, cx| list.current_stack_frame(cx))
+            .and_then(|stack_frame| stack_frame.source);
+        let Some(source) = source else {
+            return Err("The selected stack frame has no source to go to".into());
+        };
+
+        let open_source = open_source(
+            self.session.clone(),
+            self.workspace.clone(),
+            source,
+            Some(line - 1),
+            window,
+            cx,
+        );
+        // Lines past the end of the source are only known once it's open.
+        self.go_to_task = cx.spawn(|this, mut cx| async move {
+            if let Err(error) = open_source.await {
+                this.update(&mut cx, |this, cx| {
+                    this.go_to_error = Some(error.to_string().into());
+                    cx.notify();
+                })
+                .ok();
+            }
+        });
+        Ok(())
+    }
+
+    fn go_to_address(&mut self, address: u64, cx: &mut Context<Self>) -> Result<(), SharedString> {
+        if !self.supports_thread_item(ThreadItem::Disassembly, cx) {
+            return Err("This debug adapter can't disassemble".into());
+        }
+
+        self.disassembly_view
+            .update(cx, |view, cx| view.go_to_address(address, cx))?;
+        self.set_thread_item(ThreadItem::Disassembly, cx);
+        Ok(())
+    }
+
+    /// Why the last go to failed, if it did.
+    pub fn go_to_error(&self) -> Option<&SharedString> {
+        self.go_to_error.as_ref()
+    }
+
+    fn render_go_to(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        v_flex()
+            .key_context("DebugGoTo")
+            .on_action(cx.listener(Self::confirm_go_to))
+            .w_full()
+            .px_2()
+            .py_1()
+            .gap_0p5()
+            .border_b_1()
+            .border_color(cx.theme().colors().border_variant)
+            .child(self.go_to_editor.clone())
+            .when_some(self.go_to_error.clone(), |this, error| {
+                this.child(Label::new(error).size(LabelSize::Small).color(Color::Error))
+            })
+    }
+
+    /// Why the debuggee is stopped, and for exceptions the adapter's description of the exception.
+    pub fn stop_reason(&self, cx: &App) -> Option<(SharedString, Option<SharedString>)> {
+        if self.exit.is_some() {
+            return None;
+        }
+        let stop = self.session.read(cx).last_stop()?;
+        let details = match stop.reason {
+            StoppedEventReason::Exception => {
+                match (stop.description.as_deref(), stop.text.as_deref()) {
+                    (Some(description), Some(text)) if description != text => {
+                        Some(format!("{description}: {text}").into())
+                    }
+                    (Some(details), _) | (None, Some(details)) => Some(details.to_owned().into()),
+                    (None, None) => None,
+                }
+            }
+            _ => None,
+        };
+        Some((stop_reason_label(&stop.reason).into(), details))
+    }
+
+    fn render_stop_reason(&self, cx: &mut Context<Self>) -> Option<impl IntoElement> {
+        let (label, details) = self.stop_reason(cx)?;
+        let is_exception = self
+            .session
+            .read(cx)
+            .last_stop()
+            .is_some_and(|stop| matches!(stop.reason, StoppedEventReason::Exception));
+
+        Some(
+            v_flex()
+                .w_full()
+                .px_2()
+                .py_1()
+                .gap_0p5()
+                .border_b_1()
+                .border_color(cx.theme().colors().border_variant)
+                .child(Label::new(label).color(if is_exception {
+                    Color::Error
+                } else {
+                    Color::Warning
+                }))
+                .when_some(details, |this, details| {
+                    this.child(
+                        Label::new(details)
+                            .size(LabelSize::Small)
+                            .color(Color::Error),
+                    )
+                }),
+        )
+    }
+
+    /// How the session ended, if it has.
+    pub fn exit(&self) -> Option<SessionExit> {
+        self.exit
+    }
+
+    /// How long the session has been running, up to when it ended.
+    pub fn elapsed_since_launch(&self) -> Duration {
+        self.timers.since_launch()
+    }
+
+    /// How long the debuggee has been stopped, or `None` while it's running.
+    pub fn elapsed_since_stop(&self) -> Option<Duration> {
+        self.timers.since_stop()
+    }
+
+    fn render_timers(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let since_launch = format_elapsed(self.timers.since_launch());
+        let since_stop = self.timers.since_stop().map(format_elapsed);
+        let tooltip = match since_stop {
+            Some(_) => "Time since launch, and since the program last stopped",
+            None => "Time since launch",
+        };
+
+        h_flex()
+            .id("debug-session-timers")
+            .gap_1()
+            .child(
+                Label::new(since_launch)
+                    .size(LabelSize::Small)
+                    .color(Color::Muted),
+            )
+            .when_some(since_stop, |this, since_stop| {
+                this.child(
+                    Label::new(format!("(stopped {since_stop})"))
+                        .size(LabelSize::Small)
+                        .color(Color::Muted),
+                )
+            })
+            .tooltip(Tooltip::text(tooltip))
+    }
+
     pub fn session(&self) -> &Entity<Session> {
         &self.session
     }
@@ -439,9 +1007,27 @@ impl RunningState {
         self.session_id
     }
 
-    #[cfg(any(test, feature = "test-support"))]
+    pub fn active_thread_item(&self) -> ThreadItem {
+        self.active_thread_item
+    }
+
+    /// Switches to the given tab. The selection lives here rather than in the view so it survives
+    /// switching between sessions, and is what gets sent to followers.
     pub fn set_thread_item(&mut self, thread_item: ThreadItem, cx: &mut Context<Self>) {
+        let was_console = self.active_thread_item == ThreadItem::Console;
         self.active_thread_item = thread_item;
+        if thread_item == ThreadItem::Console {
+            self.show_console_indicator = false;
+            if let Some(scroll) = self.console_scroll.take() {
+                self.console
+                    .update(cx, |console, _| console.restore_scroll(scroll));
+            }
+        } else if was_console {
+            self.console_scroll = Some(
+                self.console
+                    .update(cx, |console, cx| console.scroll_state(cx)),
+            );
+        }
         cx.notify()
     }
 
@@ -460,6 +1046,30 @@ impl RunningState {
         &self.module_list
     }
 
+    #[cfg(any(test, feature = "test-support"))]
+    pub fn loaded_source_list(&self) -> &Entity<LoadedSourceList> {
+        &self.loaded_source_list
+    }
+
+    pub fn watch_list(&self) -> &Entity<WatchList> {
+        &self.watch_list
+    }
+
+    #[cfg(any(test, feature = "test-support"))]
+    pub fn breakpoint_list(&self) -> &Entity<BreakpointList> {
+        &self.breakpoint_list
+    }
+
+    #[cfg(any(test, feature = "test-support"))]
+    pub fn exception_breakpoint_list(&self) -> &Entity<ExceptionBreakpointList> {
+        &self.exception_breakpoint_list
+    }
+
+    #[cfg(any(test, feature = "test-support"))]
+    pub fn disassembly_view(&self) -> &Entity<DisassemblyView> {
+        &self.disassembly_view
+    }
+
     #[cfg(any(test, feature = "test-support"))]
     pub fn variable_list(&self) -> &Entity<VariableList> {
         &self.variable_list
@@ -503,18 +1113,230 @@ impl RunningState {
             .map(|id| self.session().read(cx).thread_status(id))
     }
 
+    /// Selects the thread the user picked, opening the frame it's stopped in.
+    fn pick_thread(&mut self, thread_id: ThreadId, cx: &mut Context<Self>) {
+        self.stack_frame_list
+            .update(cx, |list, _| list.set_go_to_first_stack_frame(true));
+        self.select_thread(thread_id, cx);
+    }
+
     fn select_thread(&mut self, thread_id: ThreadId, cx: &mut Context<Self>) {
         if self.thread_id.is_some_and(|id| id == thread_id) {
             return;
         }
 
         self.thread_id = Some(thread_id);
+        // The history only holds frames of the selected thread.
+        self.stack_frame_history.clear();
 
         self.stack_frame_list
             .update(cx, |list, cx| list.refresh(cx));
         cx.notify();
     }
 
+    pub(crate) fn go_back_stack_frame(
+        &mut self,
+        _: &GoBackStackFrame,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if let Some(stack_frame_id) = self.stack_frame_history.go_back() {
+            self.stack_frame_list.update(cx, |list, cx| {
+                list.select_stack_frame_by_id(stack_frame_id, window, cx)
+            });
+            cx.notify();
+        }
+    }
+
+    pub(crate) fn go_forward_stack_frame(
+        &mut self,
+        _: &GoForwardStackFrame,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if let Some(stack_frame_id) = self.stack_frame_history.go_forward() {
+            self.stack_frame_list.update(cx, |list, cx| {
+                list.select_stack_frame_by_id(stack_frame_id, window, cx)
+            });
+            cx.notify();
+        }
+    }
+
+    /// Evaluates an expression selected in an editor in the selected stack frame, showing the
+    /// result, or the adapter's error, in the console. Returns whether the expression was sent,
+    /// which requires the selected thread to be stopped.
+    pub fn evaluate_selection(&mut self, expression: String, cx: &mut Context<Self>) -> bool {
+        if self.thread_status(cx) != Some(ThreadStatus::Stopped) {
+            return false;
+        }
+
+        let frame_id = self.stack_frame_list.read(cx).current_stack_frame_id();
+        self.session.update(cx, |session, cx| {
+            session.evaluate(
+                expression,
+                Some(dap::EvaluateArgumentsContext::Repl),
+                frame_id,
+                None,
+                cx,
+            );
+        });
+        if self.active_thread_item != ThreadItem::Console {
+            self.set_thread_item(ThreadItem::Console, cx);
+            cx.emit(DebugPanelItemEvent::ThreadItemChanged(ThreadItem::Console));
+        }
+        true
+    }
+
+    /// Evaluates an expression hovered in an editor in the selected stack frame. Returns `None`
+    /// unless the selected thread is stopped at a frame and the adapter evaluates hovers.
+    pub fn evaluate_for_hover(
+        &mut self,
+        expression: String,
+        cx: &mut Context<Self>,
+    ) -> Option<Task<anyhow::Result<dap::EvaluateResponse>>> {
+        if self.thread_status(cx) != Some(ThreadStatus::Stopped)
+            || !self
+                .capabilities(cx)
+                .supports_evaluate_for_hovers
+                .unwrap_or_default()
+        {
+            return None;
+        }
+
+        let frame_id = self.stack_frame_list.read(cx).current_stack_frame_id()?;
+        Some(self.session.update(cx, |session, cx| {
+            session.evaluate_hover(expression, frame_id, cx)
+        }))
+    }
+
+    /// Copies the selected thread's stack trace to the clipboard, from the frames already
+    /// listed for it.
+    pub(crate) fn copy_stack_trace(
+        &mut self,
+        _: &CopyStackTrace,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(thread_id) = self.thread_id else {
+            return;
+        };
+        let (thread, stack_frames) = self.session.update(cx, |session, cx| {
+            let thread = session
+                .threads(cx)
+                .into_iter()
+                .find(|(thread, _)| thread.id == thread_id.0)
+                .map(|(thread, _)| thread);
+            (thread, session.stack_frames(thread_id, cx))
+        });
+        let Some(thread) = thread.filter(|_| !stack_frames.is_empty()) else {
+            return;
+        };
+
+        cx.write_to_clipboard(ClipboardItem::new_string(format_stack_trace(
+            &thread,
+            &stack_frames,
+        )));
+        self.show_copied_toast(
+            format!("Copied the stack trace of thread \"{}\"", thread.name),
+            cx,
+        );
+    }
+
+    /// Copies the stack traces of all threads to the clipboard, once the frames of the threads
+    /// that weren't inspected have loaded.
+    pub(crate) fn copy_all_stack_traces(
+        &mut self,
+        _: &CopyAllStackTraces,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let task = self
+            .session
+            .update(cx, |session, cx| session.all_stack_frames(cx));
+        cx.spawn(|this, mut cx| async move {
+            let threads = task.await;
+            if threads.is_empty() {
+                return;
+            }
+            let text = threads
+                .iter()
+                .map(|(thread, stack_frames)| format_stack_trace(thread, stack_frames))
+                .collect::<Vec<_>>()
+                .join("\n");
+
+            this.update(&mut cx, |this, cx| {
+                cx.write_to_clipboard(ClipboardItem::new_string(text));
+                this.show_copied_toast(
+                    format!("Copied the stack traces of {} threads", threads.len()),
+                    cx,
+                );
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    fn show_copied_toast(&self, message: String, cx: &mut Context<Self>) {
+        struct CopiedStackTrace;
+
+        self.workspace
+            .update(cx, |workspace, cx| {
+                workspace.show_toast(
+                    Toast::new(NotificationId::unique::<CopiedStackTrace>(), message).autohide(),
+                    cx,
+                )
+            })
+            .ok();
+    }
+
+    /// Copies the selected thread's stack trace, or offers to copy all threads' when there are
+    /// several.
+    fn render_copy_stack_trace(
+        &self,
+        is_stopped: bool,
+        thread_count: usize,
+        cx: &mut Context<Self>,
+    ) -> AnyElement {
+        let button = IconButton::new("debug-copy-stack-trace", IconName::Copy)
+            .icon_size(IconSize::Small)
+            .disabled(!is_stopped);
+
+        if thread_count <= 1 {
+            return button
+                .on_click(cx.listener(|this, _, window, cx| {
+                    this.copy_stack_trace(&CopyStackTrace, window, cx);
+                }))
+                .tooltip(Tooltip::text("Copy Stack Trace"))
+                .into_any_element();
+        }
+
+        let state = cx.entity();
+        PopoverMenu::new("debug-copy-stack-trace-menu")
+            .trigger_with_tooltip(button, Tooltip::text("Copy Stack Trace"))
+            .menu(move |window, cx| {
+                let state = state.clone();
+                Some(ContextMenu::build(window, cx, move |menu, _, _| {
+                    menu.entry("Copy This Thread", None, {
+                        let state = state.clone();
+                        move |window, cx| {
+                            state.update(cx, |state, cx| {
+                                state.copy_stack_trace(&CopyStackTrace, window, cx);
+                            });
+                        }
+                    })
+                    .entry("Copy All Threads", None, {
+                        let state = state.clone();
+                        move |window, cx| {
+                            state.update(cx, |state, cx| {
+                                state.copy_all_stack_traces(&CopyAllStackTraces, window, cx);
+                            });
+                        }
+                    })
+                }))
+            })
+            .into_any_element()
+    }
+
     fn render_entry_button(
         &self,
         label: &SharedString,
@@ -524,6 +1346,21 @@ impl RunningState {
         let has_indicator =
             matches!(thread_item, ThreadItem::Console) && self.show_console_indicator;
 
+        if !self.supports_thread_item(thread_item, cx) {
+            let tooltip = format!(
+                "This debug adapter doesn't support {}",
+                label.to_lowercase()
+            );
+            return div()
+                .id(label.clone())
+                .px_2()
+                .py_1()
+                .border_b_2()
+                .child(Button::new(label.clone(), label.clone()).disabled(true))
+                .tooltip(Tooltip::text(tooltip))
+                .into_any_element();
+        }
+
         div()
             .id(label.clone())
             .px_2()
@@ -539,22 +1376,108 @@ impl RunningState {
                     .when(has_indicator, |this| this.child(Indicator::dot())),
             )
             .on_click(cx.listener(move |this, _, _window, cx| {
-                this.active_thread_item = thread_item;
+                this.set_thread_item(thread_item, cx);
+                cx.emit(DebugPanelItemEvent::ThreadItemChanged(thread_item));
+            }))
+            .into_any_element()
+    }
 
-                if matches!(this.active_thread_item, ThreadItem::Console) {
-                    this.show_console_indicator = false;
-                }
+    /// Whether the adapter can provide the contents of the given tab.
+    pub fn supports_thread_item(&self, thread_item: ThreadItem, cx: &App) -> bool {
+        let capabilities = self.capabilities(cx);
+        match thread_item {
+            ThreadItem::Modules => capabilities.supports_modules_request.unwrap_or_default(),
+            ThreadItem::LoadedSource => capabilities
+                .supports_loaded_sources_request
+                .unwrap_or_default(),
+            ThreadItem::Disassembly => capabilities
+                .supports_disassemble_request
+                .unwrap_or_default(),
+            ThreadItem::ExceptionBreakpoints => !self
+                .session
+                .read(cx)
+                .exception_breakpoint_filters()
+                .is_empty(),
+            ThreadItem::Breakpoints
+            | ThreadItem::Console
+            | ThreadItem::Variables
+            | ThreadItem::Watches => true,
+        }
+    }
 
-                cx.notify();
-            }))
+    fn render_stepping_granularity(
+        &self,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> AnyElement {
+        let supports_stepping_granularity = self
+            .capabilities(cx)
+            .supports_stepping_granularity
+            .unwrap_or_default();
+        let selected = self.stepping_granularity;
+        let state = cx.weak_entity();
+
+        div()
+            .id(("stepping-granularity", self.session_id.0))
+            .child(
+                DropdownMenu::new(
+                    ("stepping-granularity-menu", self.session_id.0),
+                    stepping_granularity_label(selected),
+                    ContextMenu::build(window, cx, move |mut this, _, _| {
+                        for granularity in [
+                            SteppingGranularity::Line,
+                            SteppingGranularity::Statement,
+                            SteppingGranularity::Instruction,
+                        ] {
+                            let state = state.clone();
+                            this = this.toggleable_entry(
+                                stepping_granularity_label(granularity),
+                                granularity == selected,
+                                IconPosition::Start,
+                                None,
+                                move |_, cx| {
+                                    state
+                                        .update(cx, |state, cx| {
+                                            state.set_stepping_granularity(granularity, cx);
+                                        })
+                                        .ok();
+                                },
+                            );
+                        }
+                        this
+                    }),
+                )
+                .disabled(!supports_stepping_granularity),
+            )
+            .when(!supports_stepping_granularity, |this| {
+                this.tooltip(Tooltip::text(
+                    "This debug adapter doesn't support choosing the stepping granularity",
+                ))
+            })
             .into_any_element()
     }
 
+    pub fn stepping_granularity(&self) -> SteppingGranularity {
+        self.stepping_granularity
+    }
+
+    pub fn set_stepping_granularity(
+        &mut self,
+        granularity: SteppingGranularity,
+        cx: &mut Context<Self>,
+    ) {
+        self.stepping_granularity = granularity;
+        cx.notify();
+    }
+
     pub fn continue_thread(&mut self, cx: &mut Context<Self>) {
         let Some(thread_id) = self.thread_id else {
             return;
         };
 
+        // Adapters don't send `continued` for requests the client made.
+        self.timers.stopped_at = None;
+
         self.session().update(cx, |state, cx| {
             state.continue_thread(thread_id, cx);
         });
@@ -565,7 +1488,9 @@ impl RunningState {
             return;
         };
 
-        let granularity = DebuggerSettings::get_global(cx).stepping_granularity;
+        self.timers.stopped_at = None;
+
+        let granularity = self.stepping_granularity;
 
         self.session().update(cx, |state, cx| {
             state.step_over(thread_id, granularity, cx);
@@ -577,19 +1502,41 @@ impl RunningState {
             return;
         };
 
-        let granularity = DebuggerSettings::get_global(cx).stepping_granularity;
+        self.timers.stopped_at = None;
+
+        let granularity = self.stepping_granularity;
 
         self.session().update(cx, |state, cx| {
             state.step_in(thread_id, granularity, cx);
         });
     }
 
+    /// Steps a single instruction, into calls when `step_in` is set, regardless of the
+    /// selected stepping granularity.
+    pub fn step_instruction(&mut self, step_in: bool, cx: &mut Context<Self>) {
+        let Some(thread_id) = self.thread_id else {
+            return;
+        };
+
+        self.timers.stopped_at = None;
+
+        self.session().update(cx, |state, cx| {
+            if step_in {
+                state.step_in(thread_id, SteppingGranularity::Instruction, cx);
+            } else {
+                state.step_over(thread_id, SteppingGranularity::Instruction, cx);
+            }
+        });
+    }
+
     pub fn step_out(&mut self, cx: &mut Context<Self>) {
         let Some(thread_id) = self.thread_id else {
             return;
         };
 
-        let granularity = DebuggerSettings::get_global(cx).stepping_granularity;
+        self.timers.stopped_at = None;
+
+        let granularity = self.stepping_granularity;
 
         self.session().update(cx, |state, cx| {
             state.step_out(thread_id, granularity, cx);
@@ -601,17 +1548,56 @@ impl RunningState {
             return;
         };
 
-        let granularity = DebuggerSettings::get_global(cx).stepping_granularity;
+        self.timers.stopped_at = None;
+
+        let granularity = self.stepping_granularity;
 
         self.session().update(cx, |state, cx| {
             state.step_back(thread_id, granularity, cx);
         });
     }
 
+    /// Restarts the session in place when the adapter supports it, and otherwise terminates it
+    /// and launches a new session with the same configuration.
     pub fn restart_session(&self, cx: &mut Context<Self>) {
-        self.session().update(cx, |state, cx| {
-            state.restart(None, cx);
-        });
+        if self
+            .capabilities(cx)
+            .supports_restart_request
+            .unwrap_or_default()
+        {
+            self.session().update(cx, |state, cx| {
+                state.restart(None, cx);
+            });
+            return;
+        }
+
+        let Some(config) = self.session.read(cx).configuration() else {
+            return;
+        };
+
+        let shutdown = self.session.update(cx, |session, cx| session.shutdown(cx));
+        let workspace = self.workspace.clone();
+        cx.spawn(|_, mut cx| async move {
+            shutdown.await;
+            workspace
+                .update(&mut cx, |workspace, cx| {
+                    workspace
+                        .project()
+                        .update(cx, |project, cx| project.start_debug_session(config, cx))
+                })?
+                .await?;
+            anyhow::Ok(())
+        })
+        .detach_and_log_err(cx);
+    }
+
+    /// Whether [`Self::restart_session`] can restart this session, either through the adapter or
+    /// by relaunching it. Only local sessions can be relaunched.
+    pub fn can_restart(&self, cx: &App) -> bool {
+        self.capabilities(cx)
+            .supports_restart_request
+            .unwrap_or_default()
+            || self.session.read(cx).configuration().is_some()
     }
 
     pub fn pause_thread(&self, cx: &mut Context<Self>) {
@@ -642,11 +1628,20 @@ impl RunningState {
         })
     }
 
-    pub fn stop_thread(&self, cx: &mut Context<Self>) {
+    pub fn stop_thread(&mut self, cx: &mut Context<Self>) {
         let Some(thread_id) = self.thread_id else {
             return;
         };
 
+        // Without thread termination support the whole session is shut down.
+        if !self
+            .capabilities(cx)
+            .supports_terminate_threads_request
+            .unwrap_or_default()
+        {
+            self.terminated_by_user = true;
+        }
+
         self.workspace
             .update(cx, |workspace, cx| {
                 workspace
@@ -664,7 +1659,8 @@ impl RunningState {
         });
     }
 
-    pub fn disconnect_client(&self, cx: &mut Context<Self>) {
+    pub fn disconnect_client(&mut self, cx: &mut Context<Self>) {
+        self.terminated_by_user = true;
         self.session().update(cx, |state, cx| {
             state.disconnect_client(cx);
         });
@@ -677,6 +1673,36 @@ impl RunningState {
     }
 }
 
+/// The color of the indicator shown next to a thread in the thread list.
+fn thread_status_color(status: ThreadStatus) -> Color {
+    match status {
+        ThreadStatus::Running | ThreadStatus::Stepping => Color::Success,
+        ThreadStatus::Stopped => Color::Warning,
+        ThreadStatus::Exited | ThreadStatus::Ended => Color::Muted,
+    }
+}
+
+/// The heading shown while the debuggee is stopped, saying why it stopped.
+fn stop_reason_label(reason: &StoppedEventReason) -> &'static str {
+    match reason {
+        StoppedEventReason::Breakpoint => "Paused on breakpoint",
+        StoppedEventReason::Exception => "Paused on exception",
+        StoppedEventReason::Step => "Paused after step",
+        StoppedEventReason::Pause => "Paused",
+        StoppedEventReason::Entry => "Paused on entry",
+        _ => "Paused",
+    }
+}
+
+/// The name of a stepping granularity in the granularity dropdown.
+fn stepping_granularity_label(granularity: SteppingGranularity) -> &'static str {
+    match granularity {
+        SteppingGranularity::Line => "Line",
+        SteppingGranularity::Statement => "Statement",
+        SteppingGranularity::Instruction => "Instruction",
+    }
+}
+
 impl EventEmitter<DebugPanelItemEvent> for RunningState {}
 
 impl Focusable for RunningState {
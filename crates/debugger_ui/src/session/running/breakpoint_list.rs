@@ -0,0 +1,500 @@
+use std::{path::Path, sync::Arc};
+
+use dap::DataBreakpointAccessType;
+use editor::Editor;
+use gpui::{Entity, FocusHandle, Focusable, Subscription, WeakEntity};
+use language::Point;
+use menu::{Cancel, Confirm};
+use project::debugger::{
+    breakpoint_store::{BreakpointEditAction, BreakpointKind, SerializedBreakpoint},
+    session::{DataBreakpoint, Session, SessionEvent},
+};
+use ui::{prelude::*, Checkbox, Indicator, Tooltip};
+use workspace::{OpenOptions, Workspace};
+
+/// An expression that decides whether a breakpoint stops.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConditionKind {
+    /// The breakpoint only stops when the expression is true.
+    Condition,
+    /// The breakpoint ignores hits until the expression, e.g. `>= 5`, is satisfied.
+    HitCondition,
+}
+
+impl ConditionKind {
+    fn placeholder(&self) -> &'static str {
+        match self {
+            ConditionKind::Condition => "Stop when this expression is true",
+            ConditionKind::HitCondition => "Stop after this many hits, e.g. >= 5",
+        }
+    }
+}
+
+/// The inline editor for a condition of the breakpoint on `row`.
+struct ConditionEditor {
+    abs_path: Arc<Path>,
+    row: u32,
+    kind: ConditionKind,
+    editor: Entity<Editor>,
+}
+
+pub struct BreakpointList {
+    session: Entity<Session>,
+    workspace: WeakEntity<Workspace>,
+    condition_editor: Option<ConditionEditor>,
+    focus_handle: FocusHandle,
+    _subscription: Subscription,
+}
+
+impl BreakpointList {
+    pub fn new(
+        session: Entity<Session>,
+        workspace: WeakEntity<Workspace>,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let focus_handle = cx.focus_handle();
+
+        let _subscription = cx.subscribe(&session, |_, _, event, cx| match event {
+            SessionEvent::Breakpoints => cx.notify(),
+            _ => {}
+        });
+
+        Self {
+            session,
+            workspace,
+            condition_editor: None,
+            focus_handle,
+            _subscription,
+        }
+    }
+
+    /// Opens the inline editor for a condition of the breakpoint on `row`, filled with its
+    /// current value.
+    pub fn edit_condition(
+        &mut self,
+        abs_path: Arc<Path>,
+        row: u32,
+        kind: ConditionKind,
+        current: Option<Arc<str>>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let editor = cx.new(|cx| {
+            let mut editor = Editor::single_line(window, cx);
+            editor.set_placeholder_text(kind.placeholder(), cx);
+            if let Some(current) = current {
+                editor.set_text(current.as_ref(), window, cx);
+            }
+            editor
+        });
+        window.focus(&editor.focus_handle(cx));
+
+        self.condition_editor = Some(ConditionEditor {
+            abs_path,
+            row,
+            kind,
+            editor,
+        });
+        cx.notify();
+    }
+
+    /// Sets the edited condition on the breakpoint. An empty condition removes it.
+    fn confirm_condition(&mut self, _: &Confirm, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(condition_editor) = self.condition_editor.take() else {
+            return;
+        };
+        let condition: Arc<str> = condition_editor
+            .editor
+            .read(cx)
+            .text(cx)
+            .trim()
+            .to_string()
+            .into();
+        let edit_action = match condition_editor.kind {
+            ConditionKind::Condition => BreakpointEditAction::EditCondition(condition),
+            ConditionKind::HitCondition => BreakpointEditAction::EditHitCondition(condition),
+        };
+
+        self.workspace
+            .update(cx, |workspace, cx| {
+                workspace
+                    .project()
+                    .read(cx)
+                    .breakpoint_store()
+                    .update(cx, |store, cx| {
+                        store.edit_breakpoint_at_row(
+                            &condition_editor.abs_path,
+                            condition_editor.row,
+                            edit_action,
+                            cx,
+                        );
+                    });
+            })
+            .ok();
+
+        window.focus(&self.focus_handle);
+        cx.notify();
+    }
+
+    fn cancel_condition(&mut self, _: &Cancel, window: &mut Window, cx: &mut Context<Self>) {
+        if self.condition_editor.take().is_some() {
+            window.focus(&self.focus_handle);
+            cx.notify();
+        } else {
+            cx.propagate();
+        }
+    }
+
+    /// Every breakpoint in the project, ordered by file and then by row.
+    pub fn breakpoints(&self, cx: &App) -> Vec<SerializedBreakpoint> {
+        let Some(workspace) = self.workspace.upgrade() else {
+            return Vec::new();
+        };
+
+        workspace
+            .read(cx)
+            .project()
+            .read(cx)
+            .breakpoint_store()
+            .read(cx)
+            .all_breakpoints(cx)
+            .into_values()
+            .flat_map(|mut breakpoints| {
+                breakpoints.sort_by_key(|breakpoint| breakpoint.position);
+                breakpoints
+            })
+            .collect()
+    }
+
+    fn toggle_enabled(&mut self, abs_path: Arc<Path>, row: u32, cx: &mut Context<Self>) {
+        self.workspace
+            .update(cx, |workspace, cx| {
+                workspace
+                    .project()
+                    .read(cx)
+                    .breakpoint_store()
+                    .update(cx, |store, cx| {
+                        store.edit_breakpoint_at_row(
+                            &abs_path,
+                            row,
+                            BreakpointEditAction::InvertState,
+                            cx,
+                        );
+                    });
+            })
+            .ok();
+    }
+
+    fn open_breakpoint(
+        &mut self,
+        abs_path: Arc<Path>,
+        row: u32,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let task = self.workspace.update(cx, |workspace, cx| {
+            workspace.open_abs_path(abs_path.to_path_buf(), OpenOptions::default(), window, cx)
+        });
+
+        cx.spawn_in(window, move |_, mut cx| async move {
+            let item = task?.await?;
+            if let Some(editor) = item.downcast::<Editor>() {
+                editor.update_in(&mut cx, |editor, window, cx| {
+                    editor.go_to_singleton_buffer_point(Point::new(row, 0), window, cx);
+                })?;
+            }
+            anyhow::Ok(())
+        })
+        .detach_and_log_err(cx);
+    }
+
+    fn render_entry(
+        &self,
+        breakpoint: SerializedBreakpoint,
+        ix: usize,
+        cx: &mut Context<Self>,
+    ) -> AnyElement {
+        let session = self.session.read(cx);
+        let abs_path = breakpoint.path.clone();
+        let row = breakpoint.position;
+        let is_enabled = breakpoint.state.is_enabled();
+        let supports_conditions = session
+            .capabilities()
+            .supports_conditional_breakpoints
+            .unwrap_or_default();
+        let supports_hit_conditions = session
+            .capabilities()
+            .supports_hit_conditional_breakpoints
+            .unwrap_or_default();
+        let (indicator_color, indicator_tooltip) = match session.breakpoint_verified(&abs_path, row)
+        {
+            Some(true) => (Color::Success, "Verified by the debug adapter"),
+            Some(false) => (
+                Color::Warning,
+                "Not verified by the debug adapter, so it may not be hit",
+            ),
+            None => (Color::Muted, "Not sent to the debug adapter"),
+        };
+        let file_name = abs_path
+            .file_name()
+            .map(|file_name| file_name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| abs_path.to_string_lossy().into_owned());
+
+        let condition_editor = self
+            .condition_editor
+            .as_ref()
+            .filter(|editor| editor.abs_path == abs_path && editor.row == row)
+            .map(|editor| editor.editor.clone());
+
+        let entry = h_flex()
+            .id(("breakpoint-list", ix))
+            .w_full()
+            .gap_2()
+            .p_1()
+            .rounded_md()
+            .hover(|s| s.bg(cx.theme().colors().element_hover))
+            .child(
+                Checkbox::new(("breakpoint-enabled", ix), is_enabled.into()).on_click(cx.listener(
+                    {
+                        let abs_path = abs_path.clone();
+                        move |this, _, _, cx| {
+                            // Toggling shouldn't also jump to the breakpoint.
+                            cx.stop_propagation();
+                            this.toggle_enabled(abs_path.clone(), row, cx);
+                        }
+                    },
+                )),
+            )
+            .child(
+                div()
+                    .id(("breakpoint-verified", ix))
+                    .child(Indicator::dot().color(indicator_color))
+                    .tooltip(Tooltip::text(indicator_tooltip)),
+            )
+            .child(
+                v_flex()
+                    .flex_1()
+                    .overflow_hidden()
+                    .child(
+                        h_flex()
+                            .gap_1()
+                            .text_ui_sm(cx)
+                            .child(format!("{file_name}:{}", row + 1))
+                            .when_some(
+                                match &breakpoint.kind {
+                                    BreakpointKind::Log(message) => Some(message.clone()),
+                                    BreakpointKind::Standard => None,
+                                },
+                                |this, message| {
+                                    this.child(
+                                        Label::new(format!("logs \"{message}\""))
+                                            .size(LabelSize::Small)
+                                            .color(Color::Muted),
+                                    )
+                                },
+                            )
+                            .when_some(breakpoint.condition.clone(), |this, condition| {
+                                this.child(
+                                    Label::new(format!("when {condition}"))
+                                        .size(LabelSize::Small)
+                                        .color(Color::Muted),
+                                )
+                            })
+                            .when_some(breakpoint.hit_condition.clone(), |this, hit_condition| {
+                                this.child(
+                                    Label::new(format!("hits {hit_condition}"))
+                                        .size(LabelSize::Small)
+                                        .color(Color::Muted),
+                                )
+                            }),
+                    )
+                    .child(
+                        div()
+                            .text_ui_xs(cx)
+                            .text_color(cx.theme().colors().text_muted)
+                            .child(abs_path.to_string_lossy().into_owned()),
+                    ),
+            )
+            .child(self.render_condition_button(
+                ix,
+                &breakpoint,
+                ConditionKind::Condition,
+                supports_conditions,
+                cx,
+            ))
+            .child(self.render_condition_button(
+                ix,
+                &breakpoint,
+                ConditionKind::HitCondition,
+                supports_hit_conditions,
+                cx,
+            ))
+            .on_click(cx.listener(move |this, _, window, cx| {
+                this.open_breakpoint(abs_path.clone(), row, window, cx);
+            }));
+
+        v_flex()
+            .w_full()
+            .child(entry)
+            .when_some(condition_editor, |this, editor| {
+                this.child(div().pl_8().pr_1().pb_1().child(editor))
+            })
+            .into_any()
+    }
+
+    fn remove_data_breakpoint(&mut self, data_id: String, cx: &mut Context<Self>) {
+        self.session.update(cx, |session, cx| {
+            session
+                .remove_data_breakpoint(&data_id, cx)
+                .detach_and_log_err(cx);
+        });
+    }
+
+    fn render_data_breakpoint(
+        &self,
+        breakpoint: &DataBreakpoint,
+        ix: usize,
+        cx: &mut Context<Self>,
+    ) -> AnyElement {
+        let (indicator_color, indicator_tooltip) = match breakpoint.verified {
+            Some(true) => (Color::Success, "Verified by the debug adapter"),
+            Some(false) => (
+                Color::Warning,
+                "Not verified by the debug adapter, so it may not be hit",
+            ),
+            None => (Color::Muted, "Not sent to the debug adapter"),
+        };
+        let access = match breakpoint.access_type {
+            Some(DataBreakpointAccessType::Read) => "breaks on read",
+            Some(DataBreakpointAccessType::Write) => "breaks on write",
+            Some(DataBreakpointAccessType::ReadWrite) => "breaks on read or write",
+            None => "breaks on change",
+        };
+        let data_id = breakpoint.data_id.clone();
+
+        h_flex()
+            .id(("data-breakpoint-list", ix))
+            .w_full()
+            .gap_2()
+            .p_1()
+            .rounded_md()
+            .hover(|s| s.bg(cx.theme().colors().element_hover))
+            .child(
+                div()
+                    .id(("data-breakpoint-verified", ix))
+                    .child(Indicator::dot().color(indicator_color))
+                    .tooltip(Tooltip::text(indicator_tooltip)),
+            )
+            .child(
+                h_flex()
+                    .flex_1()
+                    .gap_1()
+                    .overflow_hidden()
+                    .text_ui_sm(cx)
+                    .child(breakpoint.description.clone())
+                    .child(
+                        Label::new(access)
+                            .size(LabelSize::Small)
+                            .color(Color::Muted),
+                    ),
+            )
+            .child(
+                IconButton::new(("data-breakpoint-remove", ix), IconName::Close)
+                    .icon_size(IconSize::Small)
+                    .tooltip(Tooltip::text("Remove Data Breakpoint"))
+                    .on_click(cx.listener(move |this, _, _, cx| {
+                        this.remove_data_breakpoint(data_id.clone(), cx);
+                    })),
+            )
+            .into_any()
+    }
+
+    /// A button opening the inline editor for a condition, grayed out when the adapter can't
+    /// evaluate that kind of condition.
+    fn render_condition_button(
+        &self,
+        ix: usize,
+        breakpoint: &SerializedBreakpoint,
+        kind: ConditionKind,
+        is_supported: bool,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let (id, icon, current, label) = match kind {
+            ConditionKind::Condition => (
+                "breakpoint-condition",
+                IconName::Code,
+                breakpoint.condition.clone(),
+                "Condition",
+            ),
+            ConditionKind::HitCondition => (
+                "breakpoint-hit-condition",
+                IconName::Hash,
+                breakpoint.hit_condition.clone(),
+                "Hit Count",
+            ),
+        };
+        let abs_path = breakpoint.path.clone();
+        let row = breakpoint.position;
+
+        IconButton::new((id, ix), icon)
+            .icon_size(IconSize::Small)
+            .toggle_state(current.is_some())
+            .disabled(!is_supported)
+            .tooltip(if is_supported {
+                Tooltip::text(format!("Edit {label}"))
+            } else {
+                Tooltip::text(format!(
+                    "The debug adapter doesn't support {label} breakpoints"
+                ))
+            })
+            .on_click(cx.listener(move |this, _, window, cx| {
+                cx.stop_propagation();
+                this.edit_condition(abs_path.clone(), row, kind, current.clone(), window, cx);
+            }))
+    }
+}
+
+impl Focusable for BreakpointList {
+    fn focus_handle(&self, _: &gpui::App) -> gpui::FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Render for BreakpointList {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let breakpoints = self.breakpoints(cx);
+        let data_breakpoints = self.session.read(cx).data_breakpoints().to_vec();
+
+        v_flex()
+            .id("breakpoint-list")
+            .key_context("DebugBreakpointList")
+            .track_focus(&self.focus_handle)
+            .on_action(cx.listener(Self::confirm_condition))
+            .on_action(cx.listener(Self::cancel_condition))
+            .size_full()
+            .p_1()
+            .overflow_y_scroll()
+            .when(
+                breakpoints.is_empty() && data_breakpoints.is_empty(),
+                |this| {
+                    this.child(
+                        Label::new("No breakpoints set")
+                            .size(LabelSize::Small)
+                            .color(Color::Muted),
+                    )
+                },
+            )
+            .children(
+                breakpoints
+                    .into_iter()
+                    .enumerate()
+                    .map(|(ix, breakpoint)| self.render_entry(breakpoint, ix, cx)),
+            )
+            .children(
+                data_breakpoints
+                    .iter()
+                    .enumerate()
+                    .map(|(ix, breakpoint)| self.render_data_breakpoint(breakpoint, ix, cx)),
+            )
+    }
+}
@@ -4,35 +4,132 @@ use super::{
 };
 use anyhow::Result;
 use collections::HashMap;
-use dap::OutputEvent;
-use editor::{CompletionProvider, Editor, EditorElement, EditorStyle};
+use dap::{debugger_settings::DebuggerSettings, OutputEvent, OutputEventCategory};
+use editor::{
+    actions::{MoveDown, MoveToEnd, MoveUp},
+    scroll::ScrollAnchor,
+    Anchor, CompletionProvider, Editor, EditorElement, EditorStyle, ToOffset as _,
+};
 use fuzzy::StringMatchCandidate;
-use gpui::{Context, Entity, Render, Subscription, Task, TextStyle, WeakEntity};
-use language::{Buffer, CodeLabel};
+use gpui::{
+    actions, Context, Entity, FontWeight, HighlightStyle, MouseButton, MouseUpEvent, Render,
+    Subscription, Task, TextStyle, UnderlineStyle, WeakEntity,
+};
+use language::{Buffer, CodeLabel, Point};
 use menu::Confirm;
 use project::{
     debugger::session::{CompletionsQuery, OutputToken, Session},
+    search_history::{QueryInsertionBehavior, SearchHistory, SearchHistoryCursor},
     Completion,
 };
+use regex::Regex;
 use settings::Settings;
-use std::{cell::RefCell, rc::Rc, usize};
+use std::{
+    cell::RefCell,
+    ops::Range,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::LazyLock,
+    usize,
+};
 use theme::ThemeSettings;
-use ui::prelude::*;
+use ui::{prelude::*, Tooltip};
+use workspace::{OpenOptions, Workspace};
+
+actions!(console, [Clear]);
+
+const MAX_EVALUATION_HISTORY_LEN: usize = 100;
+
+/// Highlights console lines the adapter wrote to stderr.
+enum StderrOutput {}
+
+/// Highlights console lines the adapter marked as important.
+enum ImportantOutput {}
+
+/// Highlights the file paths in the output that open the file when clicked.
+enum FileLinkOutput {}
+
+/// Matches `path:line` and `path:line:column`, as printed in stack traces and compiler errors.
+static FILE_LINK_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"((?:[A-Za-z]:)?[^\s:"'`()\[\]{}<>,;=]+\.\w+):(\d+)(?::(\d+))?"#).unwrap()
+});
+
+/// A file position referenced in the console output.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct FileLink {
+    /// The path as written in the output, which may be relative to the session's working
+    /// directory.
+    pub path: PathBuf,
+    /// The zero-based row.
+    pub row: u32,
+    /// The zero-based column.
+    pub column: u32,
+}
+
+/// Finds the file positions referenced in a line of output, along with their ranges in `line`.
+pub(crate) fn file_links(line: &str) -> Vec<(Range<usize>, FileLink)> {
+    FILE_LINK_REGEX
+        .captures_iter(line)
+        .filter_map(|captures| {
+            let link = captures.get(0)?;
+            // Skip the host and port of URLs, such as `http://localhost.local:8080`.
+            let preceding = line[..link.start()].chars().next_back();
+            if preceding.is_some_and(|c| c.is_alphanumeric() || c == '/' || c == ':') {
+                return None;
+            }
+
+            let row = captures.get(2)?.as_str().parse::<u32>().ok()?;
+            let column = captures
+                .get(3)
+                .and_then(|column| column.as_str().parse::<u32>().ok())
+                .unwrap_or(1);
+            Some((
+                link.range(),
+                FileLink {
+                    path: PathBuf::from(captures.get(1)?.as_str()),
+                    row: row.saturating_sub(1),
+                    column: column.saturating_sub(1),
+                },
+            ))
+        })
+        .collect()
+}
+
+/// Where the console was scrolled to when switching to another tab.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum ConsoleScroll {
+    /// Scrolled to the last line, so it keeps following new output.
+    Tail,
+    /// Scrolled away from the last line, to a place in the scrollback that is kept.
+    Offset(ScrollAnchor),
+}
 
 pub struct Console {
     console: Entity<Editor>,
     query_bar: Entity<Editor>,
     session: Entity<Session>,
+    workspace: WeakEntity<Workspace>,
     _subscriptions: Vec<Subscription>,
     variable_list: Entity<VariableList>,
     stack_frame_list: Entity<StackFrameList>,
     last_token: OutputToken,
     update_output_task: Task<()>,
+    /// Whether older output was dropped to stay within the console's line limit.
+    trimmed: bool,
+    /// The evaluated expressions, browsed with the up and down keys in the query bar.
+    evaluation_history: SearchHistory,
+    evaluation_history_cursor: SearchHistoryCursor,
+    /// The scroll position to return to once the output that arrived while another tab was
+    /// shown has been added.
+    pending_scroll: Option<ConsoleScroll>,
+    /// The file positions referenced in the output, opened when clicked.
+    file_links: Vec<(Range<Anchor>, FileLink)>,
 }
 
 impl Console {
     pub fn new(
         session: Entity<Session>,
+        workspace: WeakEntity<Workspace>,
         stack_frame_list: Entity<StackFrameList>,
         variable_list: Entity<VariableList>,
         window: &mut Window,
@@ -75,6 +172,7 @@ impl Console {
 
         Self {
             session,
+            workspace,
             console,
             query_bar,
             variable_list,
@@ -82,6 +180,14 @@ impl Console {
             stack_frame_list,
             update_output_task: Task::ready(()),
             last_token: OutputToken(0),
+            trimmed: false,
+            evaluation_history: SearchHistory::new(
+                Some(MAX_EVALUATION_HISTORY_LEN),
+                QueryInsertionBehavior::AlwaysInsert,
+            ),
+            evaluation_history_cursor: SearchHistoryCursor::default(),
+            pending_scroll: None,
+            file_links: Vec::new(),
         }
     }
 
@@ -95,6 +201,38 @@ impl Console {
         &self.query_bar
     }
 
+    /// Where the console is scrolled to. A console that was never shown counts as following its
+    /// output.
+    pub(crate) fn scroll_state(&self, cx: &mut App) -> ConsoleScroll {
+        self.console.update(cx, |console, cx| {
+            let Some(visible_lines) = console.visible_line_count() else {
+                return ConsoleScroll::Tail;
+            };
+            let last_row = console.max_point(cx).row().0 as f32;
+            if console.scroll_position(cx).y + visible_lines >= last_row {
+                ConsoleScroll::Tail
+            } else {
+                ConsoleScroll::Offset(console.scroll_manager.anchor())
+            }
+        })
+    }
+
+    /// Scrolls back to `scroll` the next time the console is rendered, after adding the output
+    /// that arrived in the meantime.
+    pub(crate) fn restore_scroll(&mut self, scroll: ConsoleScroll) {
+        self.pending_scroll = Some(scroll);
+    }
+
+    fn apply_pending_scroll(&mut self, window: &mut Window, cx: &mut App) {
+        let Some(scroll) = self.pending_scroll.take() else {
+            return;
+        };
+        self.console.update(cx, |console, cx| match scroll {
+            ConsoleScroll::Tail => console.move_to_end(&MoveToEnd, window, cx),
+            ConsoleScroll::Offset(anchor) => console.set_scroll_anchor(anchor, window, cx),
+        });
+    }
+
     fn is_local(&self, cx: &Context<Self>) -> bool {
         self.session.read(cx).is_local()
     }
@@ -116,23 +254,266 @@ impl Console {
         window: &mut Window,
         cx: &mut App,
     ) {
-        self.console.update(cx, |console, cx| {
+        let settings = DebuggerSettings::get_global(cx);
+        let show_telemetry_output = settings.show_telemetry_output;
+        let max_lines = settings.console_max_lines;
+
+        let (trimmed, new_links) = self.console.update(cx, |console, cx| {
             let mut to_insert = String::default();
+            let mut stderr_lines = Vec::new();
+            let mut important_lines = Vec::new();
+            let mut links = Vec::new();
             for event in events {
                 use std::fmt::Write;
 
-                _ = write!(to_insert, "{}\n", event.output.trim_end());
+                let lines = match event.category {
+                    Some(OutputEventCategory::Telemetry) if !show_telemetry_output => continue,
+                    Some(OutputEventCategory::Stderr) => Some(&mut stderr_lines),
+                    Some(OutputEventCategory::Important) => Some(&mut important_lines),
+                    _ => None,
+                };
+
+                let start = to_insert.len();
+                let output = event.output.trim_end();
+                _ = write!(to_insert, "{}", output);
+                links.extend(
+                    file_links(output)
+                        .into_iter()
+                        .map(|(range, link)| (start + range.start..start + range.end, link)),
+                );
+                if let Some(lines) = lines {
+                    lines.push(start..to_insert.len());
+                }
+                to_insert.push('\n');
             }
 
             console.set_read_only(false);
-            console.move_to_end(&editor::actions::MoveToEnd, window, cx);
+            console.move_to_end(&MoveToEnd, window, cx);
+            let insertion_offset = console.buffer().read(cx).len(cx);
             console.insert(&to_insert, window, cx);
+
+            Self::highlight_lines::<StderrOutput>(
+                console,
+                insertion_offset,
+                stderr_lines,
+                HighlightStyle {
+                    color: Some(cx.theme().status().error),
+                    ..Default::default()
+                },
+                cx,
+            );
+            Self::highlight_lines::<ImportantOutput>(
+                console,
+                insertion_offset,
+                important_lines,
+                HighlightStyle {
+                    color: Some(cx.theme().colors().text),
+                    font_weight: Some(FontWeight::BOLD),
+                    ..Default::default()
+                },
+                cx,
+            );
+            Self::highlight_lines::<FileLinkOutput>(
+                console,
+                insertion_offset,
+                links.iter().map(|(range, _)| range.clone()).collect(),
+                HighlightStyle {
+                    color: Some(cx.theme().colors().text_accent),
+                    underline: Some(UnderlineStyle {
+                        thickness: px(1.),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                },
+                cx,
+            );
+
+            let snapshot = console.buffer().read(cx).snapshot(cx);
+            let new_links = links
+                .into_iter()
+                .map(|(range, link)| {
+                    (
+                        snapshot.anchor_after(insertion_offset + range.start)
+                            ..snapshot.anchor_before(insertion_offset + range.end),
+                        link,
+                    )
+                })
+                .collect::<Vec<_>>();
+
+            let trimmed = Self::trim_to_max_lines(console, max_lines, cx);
             console.set_read_only(true);
 
             cx.notify();
+            (trimmed, new_links)
+        });
+        self.trimmed |= trimmed;
+
+        self.file_links.extend(new_links);
+        if trimmed {
+            // The links in the dropped lines collapse to empty ranges.
+            let snapshot = self.console.read(cx).buffer().read(cx).snapshot(cx);
+            self.file_links.retain(|(range, _)| {
+                range.start.to_offset(&snapshot) < range.end.to_offset(&snapshot)
+            });
+        }
+    }
+
+    /// Opens the file position referenced at `offset` in the console output, returning whether
+    /// there was one.
+    pub(crate) fn open_file_link_at(
+        &mut self,
+        offset: usize,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> bool {
+        let snapshot = self.console.read(cx).buffer().read(cx).snapshot(cx);
+        let Some(link) = self
+            .file_links
+            .iter()
+            .find(|(range, _)| {
+                (range.start.to_offset(&snapshot)..range.end.to_offset(&snapshot)).contains(&offset)
+            })
+            .map(|(_, link)| link.clone())
+        else {
+            return false;
+        };
+        let Some(abs_path) = self.resolve_link_path(&link.path, cx) else {
+            return false;
+        };
+
+        let Ok(task) = self.workspace.update(cx, |workspace, cx| {
+            workspace.open_abs_path(abs_path, OpenOptions::default(), window, cx)
+        }) else {
+            return false;
+        };
+        cx.spawn_in(window, move |_, mut cx| async move {
+            let item = task.await?;
+            if let Some(editor) = item.downcast::<Editor>() {
+                editor.update_in(&mut cx, |editor, window, cx| {
+                    editor.go_to_singleton_buffer_point(
+                        Point::new(link.row, link.column),
+                        window,
+                        cx,
+                    );
+                })?;
+            }
+            anyhow::Ok(())
+        })
+        .detach_and_log_err(cx);
+        true
+    }
+
+    /// Resolves relative paths against the session's working directory, or the first worktree's
+    /// root when the session has none.
+    fn resolve_link_path(&self, path: &Path, cx: &App) -> Option<PathBuf> {
+        if path.is_absolute() {
+            return Some(path.to_path_buf());
+        }
+        let cwd = self
+            .session
+            .read(cx)
+            .configuration()
+            .and_then(|config| config.cwd)
+            .or_else(|| {
+                let workspace = self.workspace.upgrade()?;
+                let project = workspace.read(cx).project().read(cx);
+                let worktree = project.visible_worktrees(cx).next()?;
+                Some(worktree.read(cx).abs_path().to_path_buf())
+            })?;
+        Some(cwd.join(path))
+    }
+
+    fn open_clicked_file_link(
+        &mut self,
+        _: &MouseUpEvent,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let selection = self
+            .console
+            .update(cx, |console, cx| console.selections.newest::<usize>(cx));
+        // Selecting text within a link shouldn't open it.
+        if selection.is_empty() {
+            self.open_file_link_at(selection.head(), window, cx);
+        }
+    }
+
+    /// Drops the oldest lines so that at most `max_lines` lines of output remain, returning
+    /// whether anything was dropped.
+    fn trim_to_max_lines(console: &mut Editor, max_lines: usize, cx: &mut Context<Editor>) -> bool {
+        let snapshot = console.buffer().read(cx).snapshot(cx);
+        // Every line of output ends with a newline, so the last row is always empty.
+        let line_count = snapshot.max_point().row as usize;
+        if line_count <= max_lines {
+            return false;
+        }
+
+        let end = snapshot.point_to_offset(Point::new((line_count - max_lines) as u32, 0));
+        console.edit([(0..end, "")], cx);
+        true
+    }
+
+    pub fn clear(&mut self, _: &Clear, window: &mut Window, cx: &mut Context<Self>) {
+        self.console.update(cx, |console, cx| {
+            console.set_read_only(false);
+            console.clear(window, cx);
+            console.set_read_only(true);
+            console.clear_highlights::<StderrOutput>(cx);
+            console.clear_highlights::<ImportantOutput>(cx);
+            console.clear_highlights::<FileLinkOutput>(cx);
         });
+        self.file_links.clear();
+        self.trimmed = false;
+        cx.notify();
     }
 
+    fn render_header(&self, cx: &Context<Self>) -> impl IntoElement {
+        h_flex()
+            .w_full()
+            .px_1()
+            .justify_between()
+            .child(div().when(self.trimmed, |this| {
+                this.child(
+                    Label::new("… earlier output trimmed")
+                        .size(LabelSize::XSmall)
+                        .color(Color::Muted),
+                )
+            }))
+            .child(
+                IconButton::new("clear-console", IconName::Trash)
+                    .icon_size(IconSize::Small)
+                    .on_click(cx.listener(|this, _, window, cx| this.clear(&Clear, window, cx)))
+                    .tooltip(Tooltip::text("Clear console")),
+            )
+    }
+
+    /// Adds `lines`, given as ranges relative to `insertion_offset`, to the console's existing
+    /// highlights of type `T`.
+    fn highlight_lines<T: 'static>(
+        console: &mut Editor,
+        insertion_offset: usize,
+        lines: Vec<Range<usize>>,
+        style: HighlightStyle,
+        cx: &mut Context<Editor>,
+    ) {
+        if lines.is_empty() {
+            return;
+        }
+
+        let snapshot = console.buffer().read(cx).snapshot(cx);
+        let mut ranges = console
+            .text_highlights::<T>(cx)
+            .map(|(_, ranges)| ranges.to_vec())
+            .unwrap_or_default();
+        ranges.extend(lines.into_iter().map(|line| {
+            snapshot.anchor_after(insertion_offset + line.start)
+                ..snapshot.anchor_before(insertion_offset + line.end)
+        }));
+        console.highlight_text::<T>(ranges, style, cx);
+    }
+
+    /// Evaluates the query bar's expression in the selected stack frame, so that the frame's
+    /// variables are in scope.
     pub fn evaluate(&mut self, _: &Confirm, window: &mut Window, cx: &mut Context<Self>) {
         let expression = self.query_bar.update(cx, |editor, cx| {
             let expression = editor.text(cx);
@@ -141,11 +522,18 @@ impl Console {
 
             expression
         });
+        if expression.trim().is_empty() {
+            return;
+        }
+
+        self.evaluation_history
+            .add(&mut self.evaluation_history_cursor, expression.clone());
+        self.evaluation_history_cursor.reset();
 
         self.session.update(cx, |state, cx| {
             state.evaluate(
                 expression,
-                Some(dap::EvaluateArgumentsContext::Variables),
+                Some(dap::EvaluateArgumentsContext::Repl),
                 self.stack_frame_list.read(cx).current_stack_frame_id(),
                 None,
                 cx,
@@ -153,6 +541,40 @@ impl Console {
         });
     }
 
+    pub fn previous_evaluation(&mut self, _: &MoveUp, window: &mut Window, cx: &mut Context<Self>) {
+        if let Some(expression) = self
+            .evaluation_history
+            .previous(&mut self.evaluation_history_cursor)
+            .map(str::to_string)
+        {
+            self.set_query(&expression, window, cx);
+        }
+    }
+
+    pub fn next_evaluation(&mut self, _: &MoveDown, window: &mut Window, cx: &mut Context<Self>) {
+        let expression = self
+            .evaluation_history
+            .next(&mut self.evaluation_history_cursor)
+            .map(str::to_string);
+        // Moving past the newest expression goes back to an empty query bar.
+        if expression.is_none() {
+            self.evaluation_history_cursor.reset();
+        }
+        self.set_query(&expression.unwrap_or_default(), window, cx);
+    }
+
+    fn set_query(&mut self, query: &str, window: &mut Window, cx: &mut Context<Self>) {
+        self.query_bar.update(cx, |editor, cx| {
+            editor.set_text(query, window, cx);
+            editor.move_to_end(&editor::actions::MoveToEnd, window, cx);
+        });
+    }
+
+    /// Expressions can only be evaluated while the adapter is still running.
+    fn can_evaluate(&self, cx: &Context<Self>) -> bool {
+        self.is_local(cx) && !self.session.read(cx).is_terminated()
+    }
+
     fn render_console(&self, cx: &Context<Self>) -> impl IntoElement {
         let settings = ThemeSettings::get_global(cx);
         let text_style = TextStyle {
@@ -218,12 +640,12 @@ impl Render for Console {
                 let (output, last_processed_token) = session.output(token);
 
                 _ = this.update(cx, |this, cx| {
-                    if last_processed_token == this.last_token {
-                        return;
+                    if last_processed_token != this.last_token {
+                        this.add_messages(output, window, cx);
+                        this.last_token = last_processed_token;
                     }
-                    this.add_messages(output, window, cx);
-
-                    this.last_token = last_processed_token;
+                    // Adding output scrolls to the end, so the previous position is restored after.
+                    this.apply_pending_scroll(window, cx);
                 });
             });
         });
@@ -231,9 +653,18 @@ impl Render for Console {
         v_flex()
             .key_context("DebugConsole")
             .on_action(cx.listener(Self::evaluate))
+            .on_action(cx.listener(Self::previous_evaluation))
+            .on_action(cx.listener(Self::next_evaluation))
+            .on_action(cx.listener(Self::clear))
             .size_full()
-            .child(self.render_console(cx))
-            .when(self.is_local(cx), |this| {
+            .child(self.render_header(cx))
+            .child(
+                div()
+                    .size_full()
+                    .on_mouse_up(MouseButton::Left, cx.listener(Self::open_clicked_file_link))
+                    .child(self.render_console(cx)),
+            )
+            .when(self.can_evaluate(cx), |this| {
                 this.child(self.render_query_bar(cx))
                     .pt(DynamicSpacing::Base04.rems(cx))
             })
@@ -0,0 +1,384 @@
+use dap::DisassembledInstruction;
+use gpui::{
+    list, AnyElement, Empty, Entity, FocusHandle, Focusable, ListState, Subscription, Task,
+    WeakEntity,
+};
+use project::debugger::session::{Session, SessionEvent};
+use ui::{prelude::*, Tooltip};
+
+use super::stack_frame_list::{StackFrameList, StackFrameListEvent};
+use super::RunningState;
+
+/// The number of instructions disassembled before the instruction pointer.
+const INSTRUCTIONS_BEFORE: u64 = 20;
+/// The number of instructions disassembled from the instruction pointer onwards.
+const INSTRUCTIONS_AFTER: u64 = 40;
+
+/// What the view shows in place of the instructions.
+enum DisassemblyStatus {
+    /// The program is running, or no stack frame is selected.
+    Idle,
+    Loading,
+    /// The selected stack frame has no instruction pointer to disassemble around.
+    NoInstructionPointer,
+    Error(SharedString),
+    Loaded,
+}
+
+pub struct DisassemblyView {
+    list: ListState,
+    session: Entity<Session>,
+    stack_frame_list: Entity<StackFrameList>,
+    state: WeakEntity<RunningState>,
+    focus_handle: FocusHandle,
+    instructions: Vec<DisassembledInstruction>,
+    /// The index of the instruction at the selected stack frame's instruction pointer.
+    current_ix: Option<usize>,
+    /// The index of the instruction last gone to by address, highlighted until the next fetch.
+    selected_ix: Option<usize>,
+    status: DisassemblyStatus,
+    fetch_task: Task<()>,
+    _subscriptions: Vec<Subscription>,
+}
+
+impl DisassemblyView {
+    pub fn new(
+        session: Entity<Session>,
+        stack_frame_list: Entity<StackFrameList>,
+        state: WeakEntity<RunningState>,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let weak_entity = cx.weak_entity();
+        let focus_handle = cx.focus_handle();
+
+        let list = ListState::new(
+            0,
+            gpui::ListAlignment::Top,
+            px(1000.),
+            move |ix, _window, cx| {
+                weak_entity
+                    .upgrade()
+                    .map(|view| view.update(cx, |this, cx| this.render_entry(ix, cx)))
+                    .unwrap_or(div().into_any())
+            },
+        );
+
+        let _subscriptions = vec![
+            // The instructions are fetched again once the stack frame list selects a frame.
+            cx.subscribe(&session, |this, _, event, cx| match event {
+                SessionEvent::Stopped(_) | SessionEvent::Continued => {
+                    this.clear(DisassemblyStatus::Idle, cx);
+                }
+                _ => {}
+            }),
+            cx.subscribe(
+                &stack_frame_list,
+                |this, _, event: &StackFrameListEvent, cx| match event {
+                    StackFrameListEvent::SelectedStackFrameChanged(_) => this.fetch(cx),
+                },
+            ),
+        ];
+
+        Self {
+            list,
+            session,
+            stack_frame_list,
+            state,
+            focus_handle,
+            instructions: Vec::new(),
+            current_ix: None,
+            selected_ix: None,
+            status: DisassemblyStatus::Idle,
+            fetch_task: Task::ready(()),
+            _subscriptions,
+        }
+    }
+
+    fn supports_disassembly(&self, cx: &App) -> bool {
+        self.session
+            .read(cx)
+            .capabilities()
+            .supports_disassemble_request
+            .unwrap_or_default()
+    }
+
+    fn clear(&mut self, status: DisassemblyStatus, cx: &mut Context<Self>) {
+        self.fetch_task = Task::ready(());
+        self.instructions.clear();
+        self.current_ix = None;
+        self.selected_ix = None;
+        self.status = status;
+        self.list.reset(0);
+        cx.notify();
+    }
+
+    /// Disassembles the instructions around the selected stack frame's instruction pointer.
+    fn fetch(&mut self, cx: &mut Context<Self>) {
+        if !self.supports_disassembly(cx) {
+            return;
+        }
+        let Some(stack_frame) = self
+            .stack_frame_list
+            .update(cx, |list, cx| list.current_stack_frame(cx))
+        else {
+            self.clear(DisassemblyStatus::Idle, cx);
+            return;
+        };
+        let Some(memory_reference) = stack_frame.instruction_pointer_reference else {
+            self.clear(DisassemblyStatus::NoInstructionPointer, cx);
+            return;
+        };
+
+        self.status = DisassemblyStatus::Loading;
+        let task = self.session.update(cx, |session, cx| {
+            session.disassemble(
+                memory_reference.clone(),
+                -(INSTRUCTIONS_BEFORE as i64),
+                INSTRUCTIONS_BEFORE + INSTRUCTIONS_AFTER,
+                cx,
+            )
+        });
+        self.fetch_task = cx.spawn(|this, mut cx| async move {
+            let result = task.await;
+            this.update(&mut cx, |this, cx| {
+                this.selected_ix = None;
+                match result {
+                    Ok(instructions) => {
+                        this.current_ix = current_instruction_ix(&instructions, &memory_reference);
+                        this.instructions = instructions;
+                        this.status = DisassemblyStatus::Loaded;
+                        this.list.reset(this.instructions.len());
+                        if let Some(current_ix) = this.current_ix {
+                            this.list.scroll_to_reveal_item(current_ix);
+                        }
+                    }
+                    Err(error) => {
+                        this.instructions.clear();
+                        this.current_ix = None;
+                        this.status = DisassemblyStatus::Error(error.to_string().into());
+                        this.list.reset(0);
+                    }
+                }
+                cx.notify();
+            })
+            .ok();
+        });
+        cx.notify();
+    }
+
+    /// Selects and reveals the instruction containing `address`, which must be within the
+    /// disassembled instructions.
+    pub fn go_to_address(
+        &mut self,
+        address: u64,
+        cx: &mut Context<Self>,
+    ) -> Result<(), SharedString> {
+        if !matches!(self.status, DisassemblyStatus::Loaded) {
+            return Err("There are no disassembled instructions to go to".into());
+        }
+
+        let last_address = self
+            .instructions
+            .last()
+            .and_then(|instruction| parse_address(&instruction.address));
+        let ix = self.instructions.iter().rposition(|instruction| {
+            parse_address(&instruction.address).is_some_and(|start| start <= address)
+        });
+        let Some(ix) = ix.filter(|_| last_address.is_some_and(|last| address <= last)) else {
+            return Err(format!("{address:#x} is outside the disassembled instructions").into());
+        };
+
+        self.selected_ix = Some(ix);
+        self.list.scroll_to_reveal_item(ix);
+        cx.notify();
+        Ok(())
+    }
+
+    fn step_instruction(&mut self, step_in: bool, cx: &mut Context<Self>) {
+        self.state
+            .update(cx, |state, cx| state.step_instruction(step_in, cx))
+            .ok();
+    }
+
+    fn render_entry(&mut self, ix: usize, cx: &mut Context<Self>) -> AnyElement {
+        let Some(instruction) = self.instructions.get(ix) else {
+            return Empty.into_any();
+        };
+        let is_current = self.current_ix == Some(ix);
+        let is_selected = self.selected_ix == Some(ix);
+
+        h_flex()
+            .id(("disassembly-instruction", ix))
+            .w_full()
+            .px_1()
+            .gap_2()
+            .font_buffer(cx)
+            .text_ui_sm(cx)
+            .when(is_selected, |this| {
+                this.bg(cx.theme().colors().element_selected)
+            })
+            .when(is_current, |this| {
+                this.bg(cx.theme().colors().editor_active_line_background)
+            })
+            .child(
+                div().w_4().child(
+                    Icon::new(IconName::ArrowRight)
+                        .size(IconSize::XSmall)
+                        .color(if is_current {
+                            Color::Accent
+                        } else {
+                            Color::Hidden
+                        }),
+                ),
+            )
+            .child(
+                Label::new(instruction.address.clone())
+                    .size(LabelSize::Small)
+                    .color(Color::Muted),
+            )
+            .child(
+                Label::new(instruction.instruction.clone())
+                    .size(LabelSize::Small)
+                    .color(if is_current {
+                        Color::Default
+                    } else {
+                        Color::Muted
+                    }),
+            )
+            .when_some(instruction.symbol.clone(), |this, symbol| {
+                this.child(
+                    Label::new(symbol)
+                        .size(LabelSize::Small)
+                        .color(Color::Hint)
+                        .truncate(),
+                )
+            })
+            .into_any()
+    }
+
+    fn render_status(&self) -> Option<Label> {
+        let message: SharedString = match &self.status {
+            DisassemblyStatus::Loaded => return None,
+            DisassemblyStatus::Idle => "Disassembly is shown while the program is paused".into(),
+            DisassemblyStatus::Loading => "Disassembling…".into(),
+            DisassemblyStatus::NoInstructionPointer => {
+                "This stack frame has no instruction pointer".into()
+            }
+            DisassemblyStatus::Error(error) => error.clone(),
+        };
+        let color = match self.status {
+            DisassemblyStatus::Error(_) => Color::Error,
+            _ => Color::Muted,
+        };
+
+        Some(Label::new(message).size(LabelSize::Small).color(color))
+    }
+}
+
+/// Finds the instruction at `memory_reference`. Adapters may format the same address differently
+/// in the instruction pointer and in the instructions, so addresses are compared as numbers when
+/// both parse.
+fn current_instruction_ix(
+    instructions: &[DisassembledInstruction],
+    memory_reference: &str,
+) -> Option<usize> {
+    let target = parse_address(memory_reference);
+    instructions.iter().position(|instruction| {
+        match (target, parse_address(&instruction.address)) {
+            (Some(target), Some(address)) => target == address,
+            _ => instruction.address == memory_reference,
+        }
+    })
+}
+
+/// Parses an address as adapters report it: hexadecimal with a `0x` prefix, or decimal.
+pub(super) fn parse_address(address: &str) -> Option<u64> {
+    let address = address.trim();
+    match address
+        .strip_prefix("0x")
+        .or_else(|| address.strip_prefix("0X"))
+    {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => address.parse().ok(),
+    }
+}
+
+#[cfg(any(test, feature = "test-support"))]
+impl DisassemblyView {
+    /// The disassembled instructions' text, with the current instruction marked with `>` and the
+    /// one gone to by address with `*`.
+    pub fn visible_instructions(&self) -> Vec<String> {
+        self.instructions
+            .iter()
+            .enumerate()
+            .map(|(ix, instruction)| {
+                let marker = if self.current_ix == Some(ix) {
+                    "> "
+                } else if self.selected_ix == Some(ix) {
+                    "* "
+                } else {
+                    "  "
+                };
+                format!(
+                    "{marker}{} {}",
+                    instruction.address, instruction.instruction
+                )
+            })
+            .collect()
+    }
+}
+
+impl Focusable for DisassemblyView {
+    fn focus_handle(&self, _: &gpui::App) -> gpui::FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Render for DisassemblyView {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let supports_instruction_stepping = self
+            .session
+            .read(cx)
+            .capabilities()
+            .supports_stepping_granularity
+            .unwrap_or_default();
+        let can_step = supports_instruction_stepping && self.current_ix.is_some();
+
+        v_flex()
+            .track_focus(&self.focus_handle)
+            .size_full()
+            .p_1()
+            .gap_1()
+            .child(
+                h_flex()
+                    .gap_1()
+                    .child(
+                        IconButton::new("disassembly-step-over", IconName::DebugStepOver)
+                            .icon_size(IconSize::XSmall)
+                            .disabled(!can_step)
+                            .tooltip(Tooltip::text("Step Over Instruction"))
+                            .on_click(cx.listener(|this, _, _window, cx| {
+                                this.step_instruction(false, cx);
+                            })),
+                    )
+                    .child(
+                        IconButton::new("disassembly-step-in", IconName::DebugStepInto)
+                            .icon_size(IconSize::XSmall)
+                            .disabled(!can_step)
+                            .tooltip(Tooltip::text("Step Into Instruction"))
+                            .on_click(cx.listener(|this, _, _window, cx| {
+                                this.step_instruction(true, cx);
+                            })),
+                    )
+                    .when(!supports_instruction_stepping, |this| {
+                        this.child(
+                            Label::new("This debug adapter can't step by instruction")
+                                .size(LabelSize::Small)
+                                .color(Color::Muted),
+                        )
+                    }),
+            )
+            .children(self.render_status())
+            .child(list(self.list.clone()).size_full())
+    }
+}
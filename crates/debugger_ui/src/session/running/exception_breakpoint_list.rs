@@ -0,0 +1,213 @@
+use collections::HashMap;
+use dap::ExceptionBreakpointsFilter;
+use editor::Editor;
+use gpui::{Empty, Entity, FocusHandle, Focusable, Subscription};
+use menu::Confirm;
+use project::debugger::session::{ExceptionFilter, Session, SessionEvent};
+use ui::{prelude::*, Checkbox, Tooltip};
+
+/// The adapter's exception breakpoint filters, such as breaking on uncaught exceptions.
+pub struct ExceptionBreakpointList {
+    session: Entity<Session>,
+    /// Condition editors of the turned on filters that accept a condition, by filter id.
+    condition_editors: HashMap<String, Entity<Editor>>,
+    focus_handle: FocusHandle,
+    _subscription: Subscription,
+}
+
+impl ExceptionBreakpointList {
+    pub fn new(session: Entity<Session>, window: &mut Window, cx: &mut Context<Self>) -> Self {
+        let _subscription = cx.subscribe(&session, |_, _, event, cx| match event {
+            SessionEvent::Breakpoints => cx.notify(),
+            _ => {}
+        });
+
+        let mut this = Self {
+            session,
+            condition_editors: HashMap::default(),
+            focus_handle: cx.focus_handle(),
+            _subscription,
+        };
+        for filter in this.session.read(cx).exception_filters() {
+            if this.accepts_condition(&filter.filter_id, cx) {
+                this.add_condition_editor(
+                    &filter.filter_id,
+                    filter.condition.as_deref(),
+                    window,
+                    cx,
+                );
+            }
+        }
+        this
+    }
+
+    /// Whether a condition can be set on the filter, which needs support from both the filter and
+    /// the adapter.
+    fn accepts_condition(&self, filter_id: &str, cx: &App) -> bool {
+        let session = self.session.read(cx);
+        session
+            .capabilities()
+            .supports_exception_filter_options
+            .unwrap_or_default()
+            && session.exception_breakpoint_filters().iter().any(|filter| {
+                filter.filter == filter_id && filter.supports_condition.unwrap_or_default()
+            })
+    }
+
+    fn add_condition_editor(
+        &mut self,
+        filter_id: &str,
+        condition: Option<&str>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let placeholder = self
+            .session
+            .read(cx)
+            .exception_breakpoint_filters()
+            .iter()
+            .find(|filter| filter.filter == filter_id)
+            .and_then(|filter| filter.condition_description.clone())
+            .unwrap_or_else(|| "Only break when this expression is true".to_string());
+        let editor = cx.new(|cx| {
+            let mut editor = Editor::single_line(window, cx);
+            editor.set_placeholder_text(placeholder, cx);
+            if let Some(condition) = condition {
+                editor.set_text(condition, window, cx);
+            }
+            editor
+        });
+        self.condition_editors.insert(filter_id.to_string(), editor);
+    }
+
+    /// Turns the filter on or off, and sends the filters to the adapter.
+    pub fn toggle_filter(&mut self, filter_id: &str, window: &mut Window, cx: &mut Context<Self>) {
+        let mut filters = self.session.read(cx).exception_filters();
+        if let Some(ix) = filters
+            .iter()
+            .position(|filter| filter.filter_id == filter_id)
+        {
+            filters.remove(ix);
+            self.condition_editors.remove(filter_id);
+        } else {
+            filters.push(ExceptionFilter {
+                filter_id: filter_id.to_string(),
+                condition: None,
+            });
+            if self.accepts_condition(filter_id, cx) {
+                self.add_condition_editor(filter_id, None, window, cx);
+            }
+        }
+        self.set_filters(filters, cx);
+    }
+
+    /// Limits a turned on filter to exceptions `condition` is true for. An empty condition
+    /// removes the limit.
+    pub fn set_condition(&mut self, filter_id: &str, condition: &str, cx: &mut Context<Self>) {
+        let condition = condition.trim();
+        let mut filters = self.session.read(cx).exception_filters();
+        let Some(filter) = filters
+            .iter_mut()
+            .find(|filter| filter.filter_id == filter_id)
+        else {
+            return;
+        };
+        filter.condition = (!condition.is_empty()).then(|| condition.to_string());
+        self.set_filters(filters, cx);
+    }
+
+    fn confirm_condition(&mut self, _: &Confirm, window: &mut Window, cx: &mut Context<Self>) {
+        let focused = self
+            .condition_editors
+            .iter()
+            .find(|(_, editor)| editor.focus_handle(cx).is_focused(window))
+            .map(|(filter_id, editor)| (filter_id.clone(), editor.read(cx).text(cx)));
+        if let Some((filter_id, condition)) = focused {
+            self.set_condition(&filter_id, &condition, cx);
+            window.focus(&self.focus_handle);
+        }
+    }
+
+    fn set_filters(&mut self, filters: Vec<ExceptionFilter>, cx: &mut Context<Self>) {
+        self.session.update(cx, |session, cx| {
+            session
+                .set_exception_filters(filters, cx)
+                .detach_and_log_err(cx);
+        });
+        cx.notify();
+    }
+
+    fn render_filter(
+        &self,
+        filter: &ExceptionBreakpointsFilter,
+        is_enabled: bool,
+        ix: usize,
+        cx: &mut Context<Self>,
+    ) -> AnyElement {
+        let filter_id = filter.filter.clone();
+        let condition_editor = self
+            .condition_editors
+            .get(&filter.filter)
+            .filter(|_| is_enabled)
+            .cloned();
+
+        v_flex()
+            .w_full()
+            .child(
+                h_flex()
+                    .id(("exception-filter", ix))
+                    .w_full()
+                    .gap_2()
+                    .p_1()
+                    .rounded_md()
+                    .hover(|s| s.bg(cx.theme().colors().element_hover))
+                    .child(
+                        Checkbox::new(("exception-filter-enabled", ix), is_enabled.into())
+                            .on_click(cx.listener(move |this, _, window, cx| {
+                                this.toggle_filter(&filter_id, window, cx);
+                            })),
+                    )
+                    .child(Label::new(filter.label.clone()).size(LabelSize::Small))
+                    .when_some(filter.description.clone(), |this, description| {
+                        this.tooltip(Tooltip::text(description))
+                    }),
+            )
+            .when_some(condition_editor, |this, editor| {
+                this.child(div().pl_8().pr_1().pb_1().child(editor))
+            })
+            .into_any()
+    }
+}
+
+impl Focusable for ExceptionBreakpointList {
+    fn focus_handle(&self, _: &gpui::App) -> gpui::FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Render for ExceptionBreakpointList {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let session = self.session.read(cx);
+        let filters = session.exception_breakpoint_filters().to_vec();
+        if filters.is_empty() {
+            return Empty.into_any_element();
+        }
+        let enabled = session.exception_filters();
+
+        v_flex()
+            .id("exception-breakpoint-list")
+            .key_context("DebugExceptionBreakpointList")
+            .track_focus(&self.focus_handle)
+            .on_action(cx.listener(Self::confirm_condition))
+            .size_full()
+            .p_1()
+            .overflow_y_scroll()
+            .children(filters.iter().enumerate().map(|(ix, filter)| {
+                let is_enabled = enabled
+                    .iter()
+                    .any(|enabled| enabled.filter_id == filter.filter);
+                self.render_filter(filter, is_enabled, ix, cx)
+            }))
+            .into_any_element()
+    }
+}
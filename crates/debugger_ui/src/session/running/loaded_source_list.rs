@@ -1,64 +1,94 @@
-use gpui::{list, AnyElement, Empty, Entity, FocusHandle, Focusable, ListState, Subscription};
+use anyhow::anyhow;
+use editor::Editor;
+use gpui::{
+    uniform_list, AnyElement, Entity, FocusHandle, Focusable, Subscription, Task,
+    UniformListScrollHandle, WeakEntity,
+};
+use language::{Capability, Point};
 use project::debugger::session::{Session, SessionEvent};
+use std::{
+    ops::Range,
+    path::{Path, PathBuf},
+};
 use ui::prelude::*;
-use util::maybe;
+use workspace::{OpenOptions, Workspace};
 
 pub struct LoadedSourceList {
-    list: ListState,
-    invalidate: bool,
+    scroll_handle: UniformListScrollHandle,
     focus_handle: FocusHandle,
     _subscription: Subscription,
     session: Entity<Session>,
+    workspace: WeakEntity<Workspace>,
 }
 
 impl LoadedSourceList {
-    pub fn new(session: Entity<Session>, cx: &mut Context<Self>) -> Self {
-        let weak_entity = cx.weak_entity();
+    pub fn new(
+        session: Entity<Session>,
+        workspace: WeakEntity<Workspace>,
+        cx: &mut Context<Self>,
+    ) -> Self {
         let focus_handle = cx.focus_handle();
 
-        let list = ListState::new(
-            0,
-            gpui::ListAlignment::Top,
-            px(1000.),
-            move |ix, _window, cx| {
-                weak_entity
-                    .upgrade()
-                    .map(|loaded_sources| {
-                        loaded_sources.update(cx, |this, cx| this.render_entry(ix, cx))
-                    })
-                    .unwrap_or(div().into_any())
-            },
-        );
-
-        let _subscription = cx.subscribe(&session, |this, _, event, cx| match event {
+        let _subscription = cx.subscribe(&session, |_, _, event, cx| match event {
             SessionEvent::Stopped(_) | SessionEvent::LoadedSources => {
-                this.invalidate = true;
                 cx.notify();
             }
             _ => {}
         });
 
         Self {
-            list,
+            scroll_handle: UniformListScrollHandle::new(),
             session,
+            workspace,
             focus_handle,
             _subscription,
-            invalidate: true,
         }
     }
 
-    fn render_entry(&mut self, ix: usize, cx: &mut Context<Self>) -> AnyElement {
-        let Some(source) = maybe!({
-            self.session
-                .update(cx, |state, cx| state.loaded_sources(cx).get(ix).cloned())
-        }) else {
-            return Empty.into_any();
-        };
+    pub fn open_source(
+        &mut self,
+        source: dap::Source,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        open_source(
+            self.session.clone(),
+            self.workspace.clone(),
+            source,
+            None,
+            window,
+            cx,
+        )
+        .detach_and_log_err(cx);
+    }
+
+    fn render_entries(&mut self, range: Range<usize>, cx: &mut Context<Self>) -> Vec<AnyElement> {
+        let sources = self.session.update(cx, |session, cx| {
+            session
+                .loaded_sources(cx)
+                .get(range.clone())
+                .map(<[_]>::to_vec)
+                .unwrap_or_default()
+        });
 
+        sources
+            .into_iter()
+            .zip(range)
+            .map(|(source, ix)| self.render_entry(source, ix, cx))
+            .collect()
+    }
+
+    fn render_entry(
+        &mut self,
+        source: dap::Source,
+        ix: usize,
+        cx: &mut Context<Self>,
+    ) -> AnyElement {
         v_flex()
             .rounded_md()
             .w_full()
             .group("")
+            .id(("loaded-source-list", ix))
             .p_1()
             .hover(|s| s.bg(cx.theme().colors().element_hover))
             .child(
@@ -73,10 +103,21 @@ impl LoadedSourceList {
                     .text_color(cx.theme().colors().text_muted)
                     .when_some(source.path.clone(), |this, path| this.child(path)),
             )
+            .on_click(cx.listener(move |this, _, window, cx| {
+                this.open_source(source.clone(), window, cx);
+            }))
             .into_any()
     }
 }
 
+#[cfg(any(test, feature = "test-support"))]
+impl LoadedSourceList {
+    pub fn loaded_sources(&self, cx: &mut Context<Self>) -> Vec<dap::Source> {
+        self.session
+            .update(cx, |session, cx| session.loaded_sources(cx).to_vec())
+    }
+}
+
 impl Focusable for LoadedSourceList {
     fn focus_handle(&self, _: &gpui::App) -> gpui::FocusHandle {
         self.focus_handle.clone()
@@ -85,19 +126,136 @@ impl Focusable for LoadedSourceList {
 
 impl Render for LoadedSourceList {
     fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
-        if self.invalidate {
-            let len = self
-                .session
-                .update(cx, |session, cx| session.loaded_sources(cx).len());
-            self.list.reset(len);
-            self.invalidate = false;
-            cx.notify();
-        }
+        let len = self
+            .session
+            .update(cx, |session, cx| session.loaded_sources(cx).len());
 
         div()
             .track_focus(&self.focus_handle)
             .size_full()
             .p_1()
-            .child(list(self.list.clone()).size_full())
+            .child(
+                uniform_list(
+                    cx.entity().clone(),
+                    "loaded-source-list",
+                    len,
+                    |this, range, _window, cx| this.render_entries(range, cx),
+                )
+                .track_scroll(self.scroll_handle.clone())
+                .size_full(),
+            )
+    }
+}
+
+/// Opens a source reported by the debug adapter, optionally at a zero-based row.
+///
+/// Sources on disk are opened like any other file. Sources with a source reference are fetched
+/// from the adapter into a read-only buffer, which is reused when the same source is opened again.
+pub(crate) fn open_source(
+    session: Entity<Session>,
+    workspace: WeakEntity<Workspace>,
+    source: dap::Source,
+    row: Option<u32>,
+    window: &mut Window,
+    cx: &mut App,
+) -> Task<anyhow::Result<()>> {
+    let Some(reference) = source.source_reference.filter(|reference| *reference > 0) else {
+        let Some(path) = source.path else {
+            return Task::ready(Err(anyhow!(
+                "Source has neither a path nor a source reference"
+            )));
+        };
+        let task = workspace.update(cx, |workspace, cx| {
+            workspace.open_abs_path(PathBuf::from(path), OpenOptions::default(), window, cx)
+        });
+
+        return window.spawn(cx, move |mut cx| async move {
+            let item = task?.await?;
+            if let Some((row, editor)) = row.zip(item.downcast::<Editor>()) {
+                editor.update_in(&mut cx, |editor, window, cx| {
+                    go_to_row(editor, row, window, cx)
+                })??;
+            }
+            anyhow::Ok(())
+        });
+    };
+
+    // The session caches the contents, so this only reaches the adapter the first time.
+    let content = session.update(cx, |session, cx| session.source_content(source.clone(), cx));
+
+    window.spawn(cx, move |mut cx| async move {
+        let project = workspace.update(&mut cx, |workspace, _| workspace.project().clone())?;
+        let existing_buffer =
+            session.read_with(&cx, |session, _| session.source_buffer(reference))?;
+        let buffer = match existing_buffer {
+            Some(buffer) => buffer,
+            None => {
+                let content = content.await?;
+                let name = source.name.clone().unwrap_or_default();
+                let (create_buffer, language) = project.update(&mut cx, |project, cx| {
+                    let language = project.languages().language_for_file_path(Path::new(&name));
+                    (project.create_buffer(cx), language)
+                })?;
+                let buffer = create_buffer.await?;
+                // Without a language the buffer would lack outlines and symbol navigation.
+                let language = language.await.ok();
+                buffer.update(&mut cx, |buffer, cx| {
+                    buffer.edit([(0..0, content)], None, cx);
+                    buffer.set_language(language, cx);
+                    buffer.set_capability(Capability::ReadOnly, cx);
+                })?;
+                session.update(&mut cx, |session, _| {
+                    session.set_source_buffer(reference, &buffer)
+                })?;
+                buffer
+            }
+        };
+
+        workspace.update_in(&mut cx, |workspace, window, cx| {
+            let existing_editor = workspace.items_of_type::<Editor>(cx).find(|editor| {
+                editor.read(cx).buffer().read(cx).as_singleton().as_ref() == Some(&buffer)
+            });
+            let editor = if let Some(editor) = existing_editor {
+                workspace.activate_item(&editor, true, true, window, cx);
+                editor
+            } else {
+                let editor = cx.new(|cx| {
+                    let mut editor = Editor::for_buffer(buffer, Some(project), window, cx);
+                    if let Some(name) = source.name.clone() {
+                        editor
+                            .buffer()
+                            .update(cx, |multi_buffer, cx| multi_buffer.set_title(name, cx));
+                    }
+                    editor
+                });
+                workspace.add_item_to_active_pane(Box::new(editor.clone()), None, true, window, cx);
+                editor
+            };
+
+            match row {
+                Some(row) => editor.update(cx, |editor, cx| go_to_row(editor, row, window, cx)),
+                None => Ok(()),
+            }
+        })?
+    })
+}
+
+/// Moves the cursor to the start of `row`, failing if the buffer doesn't have that many rows.
+fn go_to_row(
+    editor: &mut Editor,
+    row: u32,
+    window: &mut Window,
+    cx: &mut Context<Editor>,
+) -> anyhow::Result<()> {
+    let max_row = editor.buffer().read(cx).snapshot(cx).max_point().row;
+    if row > max_row {
+        return Err(anyhow!(
+            "Line {} is past the end of the file, which has {} lines",
+            row + 1,
+            max_row + 1
+        ));
     }
+
+    editor.go_to_singleton_buffer_point(Point::new(row, 0), window, cx);
+    Ok(())
 }
@@ -1,6 +1,9 @@
 use anyhow::anyhow;
+use editor::{Editor, EditorEvent};
+use fuzzy::StringMatchCandidate;
 use gpui::{
-    list, AnyElement, Empty, Entity, FocusHandle, Focusable, ListState, Subscription, WeakEntity,
+    list, AnyElement, Empty, Entity, FocusHandle, Focusable, ListState, Subscription, Task,
+    WeakEntity,
 };
 use project::{
     debugger::session::{Session, SessionEvent},
@@ -17,13 +20,18 @@ pub struct ModuleList {
     session: Entity<Session>,
     workspace: WeakEntity<Workspace>,
     focus_handle: FocusHandle,
-    _subscription: Subscription,
+    filter_editor: Entity<Editor>,
+    /// Indices into the session's modules that match the filter, or `None` when there's no filter.
+    filtered_modules: Option<Vec<usize>>,
+    filter_task: Task<()>,
+    _subscriptions: Vec<Subscription>,
 }
 
 impl ModuleList {
     pub fn new(
         session: Entity<Session>,
         workspace: WeakEntity<Workspace>,
+        window: &mut Window,
         cx: &mut Context<Self>,
     ) -> Self {
         let weak_entity = cx.weak_entity();
@@ -41,24 +49,90 @@ impl ModuleList {
             },
         );
 
-        let _subscription = cx.subscribe(&session, |this, _, event, cx| match event {
-            SessionEvent::Stopped(_) | SessionEvent::Modules => {
-                this.invalidate = true;
-                cx.notify();
-            }
-            _ => {}
+        let filter_editor = cx.new(|cx| {
+            let mut editor = Editor::single_line(window, cx);
+            editor.set_placeholder_text("Filter modules…", cx);
+            editor
         });
 
+        let _subscriptions = vec![
+            cx.subscribe(&session, |this, _, event, cx| match event {
+                SessionEvent::Stopped(_) | SessionEvent::Modules => {
+                    this.invalidate = true;
+                    this.update_filtered_modules(cx);
+                    cx.notify();
+                }
+                _ => {}
+            }),
+            cx.subscribe(&filter_editor, |this, _, event: &EditorEvent, cx| {
+                if let EditorEvent::BufferEdited = event {
+                    this.update_filtered_modules(cx);
+                }
+            }),
+        ];
+
         Self {
             list,
             session,
             workspace,
             focus_handle,
-            _subscription,
+            filter_editor,
+            filtered_modules: None,
+            filter_task: Task::ready(()),
+            _subscriptions,
             invalidate: true,
         }
     }
 
+    fn update_filtered_modules(&mut self, cx: &mut Context<Self>) {
+        let query = self.filter_editor.read(cx).text(cx);
+        if query.is_empty() {
+            self.filter_task = Task::ready(());
+            self.filtered_modules = None;
+            self.invalidate = true;
+            cx.notify();
+            return;
+        }
+
+        let candidates = self.session.update(cx, |session, cx| {
+            session
+                .modules(cx)
+                .iter()
+                .enumerate()
+                .map(|(ix, module)| StringMatchCandidate::new(ix, &module.name))
+                .collect::<Vec<_>>()
+        });
+        let executor = cx.background_executor().clone();
+        self.filter_task = cx.spawn(|this, mut cx| async move {
+            let matches = fuzzy::match_strings(
+                &candidates,
+                &query,
+                false,
+                candidates.len(),
+                &Default::default(),
+                executor,
+            )
+            .await;
+
+            this.update(&mut cx, |this, cx| {
+                this.filtered_modules = Some(
+                    matches
+                        .into_iter()
+                        .map(|string_match| string_match.candidate_id)
+                        .collect(),
+                );
+                this.invalidate = true;
+                cx.notify();
+            })
+            .ok();
+        });
+    }
+
+    fn module_count(&self, cx: &mut Context<Self>) -> usize {
+        self.session
+            .update(cx, |session, cx| session.modules(cx).len())
+    }
+
     fn open_module(&mut self, path: Arc<Path>, window: &mut Window, cx: &mut Context<Self>) {
         cx.spawn_in(window, move |this, mut cx| async move {
             let (worktree, relative_path) = this
@@ -112,9 +186,14 @@ impl ModuleList {
     }
 
     fn render_entry(&mut self, ix: usize, cx: &mut Context<Self>) -> AnyElement {
+        let module_ix = match &self.filtered_modules {
+            Some(filtered_modules) => filtered_modules.get(ix).copied(),
+            None => Some(ix),
+        };
         let Some(module) = maybe!({
+            let module_ix = module_ix?;
             self.session
-                .update(cx, |state, cx| state.modules(cx).get(ix).cloned())
+                .update(cx, |state, cx| state.modules(cx).get(module_ix).cloned())
         }) else {
             return Empty.into_any();
         };
@@ -155,6 +234,21 @@ impl ModuleList {
         self.session
             .update(cx, |session, cx| session.modules(cx).to_vec())
     }
+
+    pub fn filter_editor(&self) -> &Entity<Editor> {
+        &self.filter_editor
+    }
+
+    pub fn filtered_modules(&self, cx: &mut Context<Self>) -> Vec<dap::Module> {
+        let modules = self.modules(cx);
+        match &self.filtered_modules {
+            Some(filtered_modules) => filtered_modules
+                .iter()
+                .filter_map(|ix| modules.get(*ix).cloned())
+                .collect(),
+            None => modules,
+        }
+    }
 }
 
 impl Focusable for ModuleList {
@@ -165,19 +259,35 @@ impl Focusable for ModuleList {
 
 impl Render for ModuleList {
     fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let module_count = self.module_count(cx);
+        let visible_count = self
+            .filtered_modules
+            .as_ref()
+            .map_or(module_count, |filtered_modules| filtered_modules.len());
+
         if self.invalidate {
-            let len = self
-                .session
-                .update(cx, |session, cx| session.modules(cx).len());
-            self.list.reset(len);
+            self.list.reset(visible_count);
             self.invalidate = false;
             cx.notify();
         }
 
-        div()
+        v_flex()
             .track_focus(&self.focus_handle)
             .size_full()
             .p_1()
+            .gap_1()
+            .child(
+                h_flex()
+                    .gap_2()
+                    .child(div().flex_1().child(self.filter_editor.clone()))
+                    .when(self.filtered_modules.is_some(), |this| {
+                        this.child(
+                            Label::new(format!("{visible_count} of {module_count} modules"))
+                                .size(LabelSize::Small)
+                                .color(Color::Muted),
+                        )
+                    }),
+            )
             .child(list(self.list.clone()).size_full())
     }
 }
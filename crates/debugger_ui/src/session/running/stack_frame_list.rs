@@ -15,7 +15,7 @@ use ui::{prelude::*, Tooltip};
 use util::ResultExt;
 use workspace::Workspace;
 
-use super::RunningState;
+use super::{loaded_source_list::open_source, RunningState};
 
 #[derive(Debug)]
 pub enum StackFrameListEvent {
@@ -32,6 +32,8 @@ pub struct StackFrameList {
     entries: Vec<StackFrameEntry>,
     workspace: WeakEntity<Workspace>,
     current_stack_frame_id: Option<StackFrameId>,
+    /// Whether selecting the first stack frame on rebuilding the entries also opens it.
+    go_to_first_stack_frame: bool,
 }
 
 #[allow(clippy::large_enum_variant)]
@@ -84,6 +86,7 @@ impl StackFrameList {
             invalidate: true,
             entries: Default::default(),
             current_stack_frame_id: None,
+            go_to_first_stack_frame: true,
         }
     }
 
@@ -134,6 +137,19 @@ impl StackFrameList {
         self.current_stack_frame_id
     }
 
+    /// The selected stack frame of the selected thread, if it's still loaded.
+    pub(super) fn current_stack_frame(&self, cx: &mut App) -> Option<dap::StackFrame> {
+        let current_stack_frame_id = self.current_stack_frame_id?;
+        self.stack_frames(cx)
+            .into_iter()
+            .find(|stack_frame| stack_frame.dap.id == current_stack_frame_id)
+            .map(|stack_frame| stack_frame.dap)
+    }
+
+    pub(super) fn set_go_to_first_stack_frame(&mut self, go_to_first_stack_frame: bool) {
+        self.go_to_first_stack_frame = go_to_first_stack_frame;
+    }
+
     pub(super) fn refresh(&mut self, cx: &mut Context<Self>) {
         self.invalidate = true;
         self.entries.clear();
@@ -178,8 +194,13 @@ impl StackFrameList {
 
         if let Some(current_stack_frame) = current_stack_frame.filter(|_| select_first_stack_frame)
         {
-            self.select_stack_frame(current_stack_frame, true, window, cx)
-                .detach_and_log_err(cx);
+            self.select_stack_frame(
+                current_stack_frame,
+                self.go_to_first_stack_frame,
+                window,
+                cx,
+            )
+            .detach_and_log_err(cx);
         }
 
         cx.notify();
@@ -187,27 +208,42 @@ impl StackFrameList {
 
     pub fn go_to_selected_stack_frame(&mut self, window: &Window, cx: &mut Context<Self>) {
         if let Some(current_stack_frame_id) = self.current_stack_frame_id {
-            let frame = self
-                .entries
-                .iter()
-                .find_map(|entry| match entry {
-                    StackFrameEntry::Normal(dap) => {
-                        if dap.id == current_stack_frame_id {
-                            Some(dap)
-                        } else {
-                            None
-                        }
-                    }
-                    StackFrameEntry::Collapsed(daps) => {
-                        daps.iter().find(|dap| dap.id == current_stack_frame_id)
+            self.select_stack_frame_by_id(current_stack_frame_id, window, cx);
+        }
+    }
+
+    /// Selects the listed stack frame with the given ID and opens its source. Returns false if
+    /// the frame isn't listed.
+    pub fn select_stack_frame_by_id(
+        &mut self,
+        stack_frame_id: StackFrameId,
+        window: &Window,
+        cx: &mut Context<Self>,
+    ) -> bool {
+        let frame = self
+            .entries
+            .iter()
+            .find_map(|entry| match entry {
+                StackFrameEntry::Normal(dap) => {
+                    if dap.id == stack_frame_id {
+                        Some(dap)
+                    } else {
+                        None
                     }
-                })
-                .cloned();
+                }
+                StackFrameEntry::Collapsed(daps) => {
+                    daps.iter().find(|dap| dap.id == stack_frame_id)
+                }
+            })
+            .cloned();
 
-            if let Some(frame) = frame.as_ref() {
-                self.select_stack_frame(frame, true, window, cx)
+        match frame {
+            Some(frame) => {
+                self.select_stack_frame(&frame, true, window, cx)
                     .detach_and_log_err(cx);
+                true
             }
+            None => false,
         }
     }
 
@@ -232,7 +268,27 @@ impl StackFrameList {
         let row = (stack_frame.line.saturating_sub(1)) as u32;
 
         let Some(abs_path) = self.abs_path_from_stack_frame(&stack_frame) else {
-            return Task::ready(Err(anyhow!("Project path not found")));
+            // Frames in sources that aren't on disk are shown from the adapter's contents.
+            return match stack_frame.source.clone().filter(|source| {
+                source
+                    .source_reference
+                    .is_some_and(|reference| reference > 0)
+            }) {
+                Some(source) => cx.spawn_in(window, move |this, mut cx| async move {
+                    this.update_in(&mut cx, |this, window, cx| {
+                        open_source(
+                            this.session.clone(),
+                            this.workspace.clone(),
+                            source,
+                            Some(row),
+                            window,
+                            cx,
+                        )
+                    })?
+                    .await
+                }),
+                None => Task::ready(Err(anyhow!("Project path not found"))),
+            };
         };
 
         cx.spawn_in(window, move |this, mut cx| async move {
@@ -517,3 +573,95 @@ impl Focusable for StackFrameList {
 }
 
 impl EventEmitter<StackFrameListEvent> for StackFrameList {}
+
+/// Formats a thread's stack trace as text, for pasting into issues and chats. The format is
+/// stable so it can be parsed: a `Thread <id> "<name>"` line, followed by one line per frame,
+/// innermost first, of `  #<index> <function> at <path>:<line>:<column>`. The location is
+/// `<unknown>` for frames without a source, and the column is left out when it isn't known.
+pub(crate) fn format_stack_trace(thread: &dap::Thread, stack_frames: &[StackFrame]) -> String {
+    use std::fmt::Write as _;
+
+    let mut text = format!("Thread {} \"{}\"\n", thread.id, thread.name);
+    for (ix, stack_frame) in stack_frames.iter().enumerate() {
+        let stack_frame = &stack_frame.dap;
+        let path = stack_frame
+            .source
+            .as_ref()
+            .and_then(|source| source.path.clone().or_else(|| source.name.clone()));
+        _ = match path {
+            Some(path) if stack_frame.column > 0 => writeln!(
+                text,
+                "  #{ix} {} at {path}:{}:{}",
+                stack_frame.name, stack_frame.line, stack_frame.column
+            ),
+            Some(path) => writeln!(
+                text,
+                "  #{ix} {} at {path}:{}",
+                stack_frame.name, stack_frame.line
+            ),
+            None => writeln!(text, "  #{ix} {} at <unknown>", stack_frame.name),
+        };
+    }
+    text
+}
+
+/// The most stack frames [`StackFrameHistory`] remembers.
+const MAX_STACK_FRAME_HISTORY_LEN: usize = 50;
+
+/// The stack frames selected since the program last stopped, for navigating back and forth
+/// between them.
+#[derive(Debug, Default)]
+pub(crate) struct StackFrameHistory {
+    stack_frame_ids: Vec<StackFrameId>,
+    /// The index of the current frame in `stack_frame_ids`.
+    current: usize,
+}
+
+impl StackFrameHistory {
+    /// Records a newly selected frame, dropping the frames that could be navigated forward to.
+    /// Selecting the current frame again is ignored, as happens when navigating the history.
+    pub(crate) fn push(&mut self, stack_frame_id: StackFrameId) {
+        if self.stack_frame_ids.get(self.current) == Some(&stack_frame_id) {
+            return;
+        }
+
+        self.stack_frame_ids
+            .truncate((self.current + 1).min(self.stack_frame_ids.len()));
+        self.stack_frame_ids.push(stack_frame_id);
+        if self.stack_frame_ids.len() > MAX_STACK_FRAME_HISTORY_LEN {
+            self.stack_frame_ids.remove(0);
+        }
+        self.current = self.stack_frame_ids.len() - 1;
+    }
+
+    pub(crate) fn can_go_back(&self) -> bool {
+        self.current > 0
+    }
+
+    pub(crate) fn can_go_forward(&self) -> bool {
+        self.current + 1 < self.stack_frame_ids.len()
+    }
+
+    /// Moves to the previously selected frame, returning it.
+    pub(crate) fn go_back(&mut self) -> Option<StackFrameId> {
+        if !self.can_go_back() {
+            return None;
+        }
+        self.current -= 1;
+        Some(self.stack_frame_ids[self.current])
+    }
+
+    /// Moves to the frame that was selected after the current one, returning it.
+    pub(crate) fn go_forward(&mut self) -> Option<StackFrameId> {
+        if !self.can_go_forward() {
+            return None;
+        }
+        self.current += 1;
+        Some(self.stack_frame_ids[self.current])
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.stack_frame_ids.clear();
+        self.current = 0;
+    }
+}
@@ -1,18 +1,38 @@
 use super::stack_frame_list::{StackFrameList, StackFrameListEvent};
 use dap::{ScopePresentationHint, StackFrameId, VariablePresentationHintKind, VariableReference};
-use editor::Editor;
+use editor::{Editor, EditorEvent};
+use fuzzy::StringMatchCandidate;
 use gpui::{
     actions, anchored, deferred, uniform_list, AnyElement, ClickEvent, ClipboardItem, Context,
     DismissEvent, Entity, FocusHandle, Focusable, Hsla, MouseButton, MouseDownEvent, Point,
-    Stateful, Subscription, TextStyleRefinement, UniformListScrollHandle,
+    Stateful, Subscription, Task, TextStyleRefinement, UniformListScrollHandle,
 };
 use menu::{SelectFirst, SelectLast, SelectNext, SelectPrevious};
 use project::debugger::session::{Session, SessionEvent};
-use std::{collections::HashMap, ops::Range, sync::Arc};
-use ui::{prelude::*, ContextMenu, ListItem, Scrollbar, ScrollbarState};
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Range,
+    sync::Arc,
+};
+use ui::{
+    prelude::*, Checkbox, ContextMenu, ContextMenuEntry, ListItem, Scrollbar, ScrollbarState,
+    ToggleState, Tooltip,
+};
 use util::{debug_panic, maybe};
 
-actions!(variable_list, [ExpandSelectedEntry, CollapseSelectedEntry]);
+actions!(
+    variable_list,
+    [
+        ExpandSelectedEntry,
+        CollapseSelectedEntry,
+        ExpandAllEntries,
+        CollapseAllEntries
+    ]
+);
+
+/// How deep "expand all" expands the tree, counting scopes as depth 1. Entries at this depth are
+/// left collapsed, so expanding a large structure doesn't fetch all of its descendants.
+const EXPAND_ALL_MAX_DEPTH: usize = 4;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub(crate) struct EntryState {
@@ -56,6 +76,27 @@ impl EntryPath {
     }
 }
 
+/// Builds an expression like `foo.bar[2]` from the names of a variable and its ancestors, skipping
+/// the scope that the path starts with.
+pub(crate) fn expression_for_path(path: &[SharedString]) -> String {
+    let mut expression = String::new();
+    for name in path.iter().skip(1) {
+        if name.starts_with('[') {
+            expression.push_str(name);
+        } else if !name.is_empty() && name.chars().all(|c| c.is_ascii_digit()) {
+            expression.push('[');
+            expression.push_str(name);
+            expression.push(']');
+        } else {
+            if !expression.is_empty() {
+                expression.push('.');
+            }
+            expression.push_str(name);
+        }
+    }
+    expression
+}
+
 #[derive(Debug, Clone, PartialEq)]
 enum EntryKind {
     Variable(dap::Variable),
@@ -77,6 +118,14 @@ impl EntryKind {
         }
     }
 
+    /// Whether the entry has children to show when expanded.
+    fn is_expandable(&self) -> bool {
+        match self {
+            EntryKind::Variable(dap) => dap.variables_reference > 0,
+            EntryKind::Scope(_) => true,
+        }
+    }
+
     #[allow(dead_code)]
     fn name(&self) -> &str {
         match self {
@@ -127,8 +176,25 @@ impl ListEntry {
     }
 }
 
+/// The paths of the matched variables and of their ancestors, which stay visible so that the tree
+/// keeps its structure.
+fn paths_with_ancestors<'a>(
+    matched: impl IntoIterator<Item = &'a Arc<[SharedString]>>,
+) -> HashSet<Arc<[SharedString]>> {
+    let mut paths = HashSet::default();
+    for indices in matched {
+        for len in 1..=indices.len() {
+            paths.insert(Arc::from(&indices[..len]));
+        }
+    }
+    paths
+}
+
 pub struct VariableList {
+    /// The visible entries, i.e. those matching the filter, if there is one.
     entries: Vec<ListEntry>,
+    /// The entries of the expanded tree, before filtering.
+    unfiltered_entries: Vec<ListEntry>,
     entry_states: HashMap<EntryPath, EntryState>,
     selected_stack_frame_id: Option<StackFrameId>,
     list_handle: UniformListScrollHandle,
@@ -138,7 +204,20 @@ pub struct VariableList {
     open_context_menu: Option<(Entity<ContextMenu>, Point<Pixels>, Subscription)>,
     focus_handle: FocusHandle,
     edited_path: Option<(EntryPath, Entity<Editor>)>,
+    /// The adapter's error for the last rejected edit or data breakpoint, shown next to the
+    /// variable's value.
+    edit_error: Option<(EntryPath, SharedString)>,
     disabled: bool,
+    filter_editor: Entity<Editor>,
+    /// Whether the filter also matches the values of variables, not only their names.
+    filter_values: bool,
+    /// The indices of the paths of the variables matching the filter and of their ancestors, or
+    /// `None` when there's no filter.
+    filter_matches: Option<HashSet<Arc<[SharedString]>>>,
+    filter_task: Task<()>,
+    /// Whether entries are expanded as their children are fetched, up to
+    /// [`EXPAND_ALL_MAX_DEPTH`]. Set by "expand all" until an entry is toggled by hand.
+    expanding_all: bool,
     _subscriptions: Vec<Subscription>,
 }
 
@@ -151,13 +230,26 @@ impl VariableList {
     ) -> Self {
         let focus_handle = cx.focus_handle();
 
+        let filter_editor = cx.new(|cx| {
+            let mut editor = Editor::single_line(window, cx);
+            editor.set_placeholder_text("Filter variables…", cx);
+            editor
+        });
+
         let _subscriptions = vec![
             cx.subscribe(&stack_frame_list, Self::handle_stack_frame_list_events),
+            cx.subscribe(&filter_editor, |this, _, event: &EditorEvent, cx| {
+                if let EditorEvent::BufferEdited = event {
+                    this.update_filter(cx);
+                }
+            }),
             cx.subscribe(&session, |this, _, event, _| match event {
                 SessionEvent::Stopped(_) => {
                     this.selection.take();
                     this.edited_path.take();
+                    this.edit_error.take();
                     this.selected_stack_frame_id.take();
+                    this.expanding_all = false;
                 }
                 _ => {}
             }),
@@ -180,7 +272,14 @@ impl VariableList {
             open_context_menu: None,
             disabled: false,
             edited_path: None,
+            edit_error: None,
+            filter_editor,
+            filter_values: false,
+            filter_matches: None,
+            filter_task: Task::ready(()),
+            expanding_all: false,
             entries: Default::default(),
+            unfiltered_entries: Default::default(),
             entry_states: Default::default(),
         }
     }
@@ -241,22 +340,27 @@ impl VariableList {
                 EntryKind::Scope(dap) => path = path.with_child(dap.name.clone().into()),
             }
 
+            let expand = self.expanding_all
+                && path.indices.len() < EXPAND_ALL_MAX_DEPTH
+                && dap_kind.is_expandable();
             let var_state = self
                 .entry_states
                 .entry(path.clone())
                 .and_modify(|state| {
                     state.parent_reference = container_reference;
+                    state.is_expanded |= expand;
                 })
                 .or_insert(EntryState {
                     depth: path.indices.len(),
-                    is_expanded: dap_kind.as_scope().is_some_and(|scope| {
-                        (scopes_count == 1 && !contains_local_scope)
-                            || scope
-                                .presentation_hint
-                                .as_ref()
-                                .map(|hint| *hint == ScopePresentationHint::Locals)
-                                .unwrap_or(scope.name.to_lowercase().starts_with("local"))
-                    }),
+                    is_expanded: expand
+                        || dap_kind.as_scope().is_some_and(|scope| {
+                            (scopes_count == 1 && !contains_local_scope)
+                                || scope
+                                    .presentation_hint
+                                    .as_ref()
+                                    .map(|hint| *hint == ScopePresentationHint::Locals)
+                                    .unwrap_or(scope.name.to_lowercase().starts_with("local"))
+                        }),
                     parent_reference: container_reference,
                 });
 
@@ -280,10 +384,74 @@ impl VariableList {
             }
         }
 
-        self.entries = entries;
+        if entries != self.unfiltered_entries {
+            self.unfiltered_entries = entries;
+            if !self.filter_editor.read(cx).text(cx).is_empty() {
+                self.update_filter(cx);
+            }
+        }
+        self.entries = match &self.filter_matches {
+            Some(matches) => self
+                .unfiltered_entries
+                .iter()
+                .filter(|entry| matches.contains(&entry.path.indices))
+                .cloned()
+                .collect(),
+            None => self.unfiltered_entries.clone(),
+        };
         cx.notify();
     }
 
+    /// Fuzzy-matches the filter against the variables of the expanded tree. Clearing the filter
+    /// shows the whole tree again, as it was expanded before.
+    fn update_filter(&mut self, cx: &mut Context<Self>) {
+        let query = self.filter_editor.read(cx).text(cx);
+        if query.is_empty() {
+            self.filter_task = Task::ready(());
+            self.filter_matches = None;
+            cx.notify();
+            return;
+        }
+
+        let mut paths = Vec::new();
+        let mut candidates = Vec::new();
+        for entry in &self.unfiltered_entries {
+            let Some(variable) = entry.as_variable() else {
+                continue;
+            };
+            let string = if self.filter_values {
+                format!("{} {}", variable.name, variable.value)
+            } else {
+                variable.name.clone()
+            };
+            candidates.push(StringMatchCandidate::new(paths.len(), &string));
+            paths.push(entry.path.indices.clone());
+        }
+
+        let executor = cx.background_executor().clone();
+        self.filter_task = cx.spawn(|this, mut cx| async move {
+            let matches = fuzzy::match_strings(
+                &candidates,
+                &query,
+                false,
+                candidates.len(),
+                &Default::default(),
+                executor,
+            )
+            .await;
+
+            this.update(&mut cx, |this, cx| {
+                this.filter_matches = Some(paths_with_ancestors(
+                    matches
+                        .iter()
+                        .map(|string_match| &paths[string_match.candidate_id]),
+                ));
+                cx.notify();
+            })
+            .ok();
+        });
+    }
+
     fn handle_stack_frame_list_events(
         &mut self,
         _: Entity<StackFrameList>,
@@ -299,7 +467,7 @@ impl VariableList {
     }
 
     pub fn completion_variables(&self, _cx: &mut Context<Self>) -> Vec<dap::Variable> {
-        self.entries
+        self.unfiltered_entries
             .iter()
             .filter_map(|entry| match &entry.dap_kind {
                 EntryKind::Variable(dap) => Some(dap.clone()),
@@ -336,9 +504,44 @@ impl VariableList {
         };
 
         entry.is_expanded = !entry.is_expanded;
+        self.expanding_all = false;
         cx.notify();
     }
 
+    /// Expands every scope and variable down to [`EXPAND_ALL_MAX_DEPTH`]. Variables are expanded
+    /// as their parents' children are fetched, rather than all being fetched up front.
+    fn expand_all_entries(
+        &mut self,
+        _: &ExpandAllEntries,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.expanding_all = true;
+        cx.notify();
+    }
+
+    fn collapse_all_entries(
+        &mut self,
+        _: &CollapseAllEntries,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.expanding_all = false;
+        for state in self.entry_states.values_mut() {
+            state.is_expanded = false;
+        }
+        cx.notify();
+    }
+
+    /// Expands an entry that "expand all" left collapsed at [`EXPAND_ALL_MAX_DEPTH`], without
+    /// ending "expand all" for the rest of the tree.
+    fn expand_past_depth_cap(&mut self, path: &EntryPath, cx: &mut Context<Self>) {
+        if let Some(state) = self.entry_states.get_mut(path) {
+            state.is_expanded = true;
+            cx.notify();
+        }
+    }
+
     fn select_first(&mut self, _: &SelectFirst, window: &mut Window, cx: &mut Context<Self>) {
         self.cancel_variable_edit(&Default::default(), window, cx);
         if let Some(variable) = self.entries.first() {
@@ -420,12 +623,24 @@ impl VariableList {
             let (var_path, editor) = self.edited_path.take()?;
             let state = self.entry_states.get(&var_path)?;
             let variables_reference = state.parent_reference;
-            let name = var_path.leaf_name?;
+            let name = var_path.leaf_name.clone()?;
             let value = editor.read(cx).text(cx);
 
-            self.session.update(cx, |session, cx| {
+            let set_value = self.session.update(cx, |session, cx| {
                 session.set_variable_value(variables_reference, name.into(), value, cx)
             });
+            cx.spawn(|this, mut cx| async move {
+                let result = set_value.await;
+                this.update(&mut cx, |this, cx| {
+                    // The cell already shows the previous value again, so only the error is left to surface.
+                    this.edit_error = result
+                        .err()
+                        .map(|error| (var_path, error.to_string().into()));
+                    cx.notify();
+                })
+                .ok();
+            })
+            .detach();
             Some(())
         });
 
@@ -434,6 +649,63 @@ impl VariableList {
         }
     }
 
+    fn supports_set_variable(&self, cx: &App) -> bool {
+        !self.disabled
+            && self
+                .session
+                .read(cx)
+                .capabilities()
+                .supports_set_variable
+                .unwrap_or_default()
+    }
+
+    fn supports_data_breakpoints(&self, cx: &App) -> bool {
+        self.session
+            .read(cx)
+            .capabilities()
+            .supports_data_breakpoints
+            .unwrap_or_default()
+    }
+
+    /// Sets a data breakpoint that stops when the variable's value changes, showing the adapter's
+    /// error next to the variable if it can't be watched.
+    fn add_data_breakpoint(&mut self, path: EntryPath, cx: &mut Context<Self>) {
+        let Some(state) = self.entry_states.get(&path) else {
+            return;
+        };
+        let Some(name) = path.leaf_name.clone() else {
+            return;
+        };
+        let variables_reference = state.parent_reference;
+        let frame_id = self.selected_stack_frame_id;
+
+        let task = self.session.update(cx, |session, cx| {
+            session.add_data_breakpoint(variables_reference, name.to_string(), frame_id, cx)
+        });
+        cx.spawn(|this, mut cx| async move {
+            let result = task.await;
+            this.update(&mut cx, |this, cx| {
+                this.edit_error = result.err().map(|error| (path, error.to_string().into()));
+                cx.notify();
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    fn start_variable_edit(
+        &mut self,
+        path: EntryPath,
+        value: &str,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let editor = Self::create_variable_editor(value, window, cx);
+        self.edit_error.take();
+        self.edited_path = Some((path, editor));
+        cx.notify();
+    }
+
     fn collapse_selected_entry(
         &mut self,
         _: &CollapseSelectedEntry,
@@ -447,6 +719,7 @@ impl VariableList {
             };
 
             entry_state.is_expanded = false;
+            self.expanding_all = false;
             cx.notify();
         }
     }
@@ -464,6 +737,7 @@ impl VariableList {
             };
 
             entry_state.is_expanded = true;
+            self.expanding_all = false;
             cx.notify();
         }
     }
@@ -482,25 +756,61 @@ impl VariableList {
 
         let variable_value = dap_var.value.clone();
         let variable_name = dap_var.name.clone();
+        // Containers may not have a value of their own, so fall back to their type rather than
+        // copying an empty string.
+        let display_value = if variable_value.is_empty() {
+            dap_var.type_.clone().unwrap_or_default()
+        } else {
+            variable_value.clone()
+        };
+        let expression = dap_var
+            .evaluate_name
+            .clone()
+            .unwrap_or_else(|| expression_for_path(&variable.path.indices));
+        let supports_set_variable = self.supports_set_variable(cx);
+        let supports_data_breakpoints = !self.disabled && self.supports_data_breakpoints(cx);
         let this = cx.entity().clone();
 
         let context_menu = ContextMenu::build(window, cx, |menu, _, _| {
             menu.entry("Copy name", None, move |_, cx| {
                 cx.write_to_clipboard(ClipboardItem::new_string(variable_name.clone()))
             })
-            .entry("Copy value", None, {
-                let variable_value = variable_value.clone();
-                move |_, cx| {
-                    cx.write_to_clipboard(ClipboardItem::new_string(variable_value.clone()))
-                }
+            .entry("Copy value", None, move |_, cx| {
+                cx.write_to_clipboard(ClipboardItem::new_string(display_value.clone()))
             })
-            .entry("Set value", None, move |window, cx| {
-                this.update(cx, |variable_list, cx| {
-                    let editor = Self::create_variable_editor(&variable_value, window, cx);
-                    variable_list.edited_path = Some((variable.path.clone(), editor));
-
-                    cx.notify();
-                });
+            .entry("Copy expression", None, move |_, cx| {
+                cx.write_to_clipboard(ClipboardItem::new_string(expression.clone()))
+            })
+            .when(supports_set_variable, |menu| {
+                let this = this.clone();
+                let path = variable.path.clone();
+                menu.entry("Set value", None, move |window, cx| {
+                    this.update(cx, |variable_list, cx| {
+                        variable_list.start_variable_edit(
+                            path.clone(),
+                            &variable_value,
+                            window,
+                            cx,
+                        );
+                    });
+                })
+            })
+            .item({
+                let entry = ContextMenuEntry::new("Break on value change")
+                    .disabled(!supports_data_breakpoints)
+                    .handler(move |_, cx| {
+                        this.update(cx, |variable_list, cx| {
+                            variable_list.add_data_breakpoint(variable.path.clone(), cx);
+                        });
+                    });
+                if supports_data_breakpoints {
+                    entry
+                } else {
+                    entry.documentation_aside(|_| {
+                        Label::new("The debug adapter doesn't support data breakpoints")
+                            .into_any_element()
+                    })
+                }
             })
         });
 
@@ -551,6 +861,46 @@ impl VariableList {
         pretty_assertions::assert_eq!(expected, visual_entries);
     }
 
+    #[cfg(any(test, feature = "test-support"))]
+    pub fn edit_variable(
+        &mut self,
+        name: &str,
+        value: &str,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(entry) = self.entries.iter().find(|entry| {
+            entry
+                .as_variable()
+                .is_some_and(|variable| variable.name == name)
+        }) else {
+            panic!("No variable named {name}");
+        };
+        let old_value = entry.as_variable().unwrap().value.clone();
+
+        self.start_variable_edit(entry.path.clone(), &old_value, window, cx);
+        if let Some((_, editor)) = self.edited_path.as_ref() {
+            editor.update(cx, |editor, cx| editor.set_text(value, window, cx));
+        }
+        self.confirm_variable_edit(&menu::Confirm, window, cx);
+    }
+
+    #[cfg(any(test, feature = "test-support"))]
+    pub fn filter_editor(&self) -> &Entity<Editor> {
+        &self.filter_editor
+    }
+
+    #[cfg(any(test, feature = "test-support"))]
+    pub fn set_filter_values(&mut self, filter_values: bool, cx: &mut Context<Self>) {
+        self.filter_values = filter_values;
+        self.update_filter(cx);
+    }
+
+    #[cfg(any(test, feature = "test-support"))]
+    pub fn edit_error(&self) -> Option<SharedString> {
+        self.edit_error.as_ref().map(|(_, error)| error.clone())
+    }
+
     #[track_caller]
     #[cfg(any(test, feature = "test-support"))]
     pub fn scopes(&self) -> Vec<dap::Scope> {
@@ -798,35 +1148,23 @@ impl VariableList {
                                     this.child(div().size_full().px_2().child(editor.clone()))
                                 } else {
                                     this.text_color(cx.theme().colors().text_muted)
-                                        .when(
-                                            !self.disabled
-                                                && self
-                                                    .session
-                                                    .read(cx)
-                                                    .capabilities()
-                                                    .supports_set_variable
-                                                    .unwrap_or_default(),
-                                            |this| {
-                                                let path = variable.path.clone();
-                                                let variable_value = dap.value.clone();
-                                                this.on_click(cx.listener(
-                                                    move |this, click: &ClickEvent, window, cx| {
-                                                        if click.down.click_count < 2 {
-                                                            return;
-                                                        }
-                                                        let editor = Self::create_variable_editor(
-                                                            &variable_value,
-                                                            window,
-                                                            cx,
-                                                        );
-                                                        this.edited_path =
-                                                            Some((path.clone(), editor));
-
-                                                        cx.notify();
-                                                    },
-                                                ))
-                                            },
-                                        )
+                                        .when(self.supports_set_variable(cx), |this| {
+                                            let path = variable.path.clone();
+                                            let variable_value = dap.value.clone();
+                                            this.on_click(cx.listener(
+                                                move |this, click: &ClickEvent, window, cx| {
+                                                    if click.down.click_count < 2 {
+                                                        return;
+                                                    }
+                                                    this.start_variable_edit(
+                                                        path.clone(),
+                                                        &variable_value,
+                                                        window,
+                                                        cx,
+                                                    );
+                                                },
+                                            ))
+                                        })
                                         .child(
                                             Label::new(format!("=  {}", &dap.value))
                                                 .single_line()
@@ -836,9 +1174,54 @@ impl VariableList {
                                                     this.color(Color::from(color))
                                                 }),
                                         )
+                                        .when_some(
+                                            self.edit_error
+                                                .as_ref()
+                                                .filter(|(path, _)| path == &variable.path),
+                                            |this, (_, error)| {
+                                                this.child(
+                                                    Label::new(error.clone())
+                                                        .single_line()
+                                                        .truncate()
+                                                        .size(LabelSize::Small)
+                                                        .color(Color::Error),
+                                                )
+                                            },
+                                        )
                                 }
                             }))
-                        }),
+                        })
+                        .when(
+                            self.expanding_all
+                                && var_ref > 0
+                                && !state.is_expanded
+                                && state.depth >= EXPAND_ALL_MAX_DEPTH,
+                            |this| {
+                                let child_count = dap.named_variables.unwrap_or_default()
+                                    + dap.indexed_variables.unwrap_or_default();
+                                let path = variable.path.clone();
+                                this.child(
+                                    Button::new(
+                                        SharedString::from(format!(
+                                            "variable-more-{}-{}",
+                                            dap.name, state.depth
+                                        )),
+                                        if child_count > 0 {
+                                            format!("+{child_count} more")
+                                        } else {
+                                            "+ more".to_string()
+                                        },
+                                    )
+                                    .label_size(LabelSize::Small)
+                                    .color(Color::Muted)
+                                    .on_click(cx.listener(
+                                        move |this, _, _, cx| {
+                                            this.expand_past_depth_cap(&path, cx);
+                                        },
+                                    )),
+                                )
+                            },
+                        ),
                 ),
             )
             .into_any()
@@ -901,9 +1284,72 @@ impl Render for VariableList {
             .on_action(cx.listener(Self::select_next))
             .on_action(cx.listener(Self::expand_selected_entry))
             .on_action(cx.listener(Self::collapse_selected_entry))
+            .on_action(cx.listener(Self::expand_all_entries))
+            .on_action(cx.listener(Self::collapse_all_entries))
             .on_action(cx.listener(Self::cancel_variable_edit))
             .on_action(cx.listener(Self::confirm_variable_edit))
-            //
+            .child(
+                h_flex()
+                    .p_1()
+                    .gap_2()
+                    .child(div().flex_1().child(self.filter_editor.clone()))
+                    .child(
+                        Checkbox::new("variable-filter-values", self.filter_values.into())
+                            .label("Match values")
+                            .on_click(cx.listener(|this, state, _, cx| {
+                                this.filter_values = *state == ToggleState::Selected;
+                                this.update_filter(cx);
+                            })),
+                    )
+                    .child(
+                        h_flex()
+                            .child(
+                                IconButton::new("variable-list-expand-all", IconName::SquarePlus)
+                                    .icon_size(IconSize::XSmall)
+                                    .disabled(self.disabled)
+                                    .tooltip({
+                                        let focus_handle = self.focus_handle.clone();
+                                        move |window, cx| {
+                                            Tooltip::for_action_in(
+                                                "Expand All",
+                                                &ExpandAllEntries,
+                                                &focus_handle,
+                                                window,
+                                                cx,
+                                            )
+                                        }
+                                    })
+                                    .on_click(cx.listener(|this, _, window, cx| {
+                                        this.expand_all_entries(&ExpandAllEntries, window, cx);
+                                    })),
+                            )
+                            .child(
+                                IconButton::new(
+                                    "variable-list-collapse-all",
+                                    IconName::SquareMinus,
+                                )
+                                .icon_size(IconSize::XSmall)
+                                .disabled(self.disabled)
+                                .tooltip({
+                                    let focus_handle = self.focus_handle.clone();
+                                    move |window, cx| {
+                                        Tooltip::for_action_in(
+                                            "Collapse All",
+                                            &CollapseAllEntries,
+                                            &focus_handle,
+                                            window,
+                                            cx,
+                                        )
+                                    }
+                                })
+                                .on_click(cx.listener(
+                                    |this, _, window, cx| {
+                                        this.collapse_all_entries(&CollapseAllEntries, window, cx);
+                                    },
+                                )),
+                            ),
+                    ),
+            )
             .child(
                 uniform_list(
                     cx.entity().clone(),
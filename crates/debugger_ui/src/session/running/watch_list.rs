@@ -0,0 +1,249 @@
+use super::stack_frame_list::{StackFrameList, StackFrameListEvent};
+use dap::StackFrameId;
+use editor::Editor;
+use gpui::{Entity, EventEmitter, FocusHandle, Focusable, Subscription, Task};
+use menu::Confirm;
+use project::debugger::session::Session;
+use ui::prelude::*;
+
+struct Watch {
+    expression: SharedString,
+    /// The result of the last evaluation, or the adapter's error if the expression couldn't be
+    /// evaluated. `None` until the program has stopped at least once.
+    value: Option<Result<SharedString, SharedString>>,
+}
+
+pub enum WatchListEvent {
+    /// A watch expression was added or removed.
+    WatchesChanged,
+}
+
+pub struct WatchList {
+    session: Entity<Session>,
+    stack_frame_list: Entity<StackFrameList>,
+    watch_editor: Entity<Editor>,
+    watches: Vec<Watch>,
+    focus_handle: FocusHandle,
+    evaluate_task: Task<()>,
+    _subscriptions: Vec<Subscription>,
+}
+
+impl WatchList {
+    pub fn new(
+        session: Entity<Session>,
+        stack_frame_list: Entity<StackFrameList>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let focus_handle = cx.focus_handle();
+
+        let watch_editor = cx.new(|cx| {
+            let mut editor = Editor::single_line(window, cx);
+            editor.set_placeholder_text("Add a watch expression", cx);
+            editor
+        });
+
+        let _subscriptions = vec![cx.subscribe(
+            &stack_frame_list,
+            |this, _, event: &StackFrameListEvent, cx| match event {
+                StackFrameListEvent::SelectedStackFrameChanged(stack_frame_id) => {
+                    this.evaluate_watches(Some(*stack_frame_id), cx);
+                }
+            },
+        )];
+
+        Self {
+            session,
+            stack_frame_list,
+            watch_editor,
+            watches: Vec::new(),
+            focus_handle,
+            evaluate_task: Task::ready(()),
+            _subscriptions,
+        }
+    }
+
+    fn add_watch(&mut self, _: &Confirm, window: &mut Window, cx: &mut Context<Self>) {
+        let expression = self.watch_editor.update(cx, |editor, cx| {
+            let expression = editor.text(cx);
+            editor.clear(window, cx);
+            expression
+        });
+        let expression = expression.trim();
+        if expression.is_empty() {
+            return;
+        }
+
+        self.watches.push(Watch {
+            expression: expression.to_string().into(),
+            value: None,
+        });
+        cx.emit(WatchListEvent::WatchesChanged);
+
+        let stack_frame_id = self.stack_frame_list.read(cx).current_stack_frame_id();
+        self.evaluate_watches(stack_frame_id, cx);
+    }
+
+    fn remove_watch(&mut self, ix: usize, cx: &mut Context<Self>) {
+        if ix < self.watches.len() {
+            self.watches.remove(ix);
+            cx.emit(WatchListEvent::WatchesChanged);
+            cx.notify();
+        }
+    }
+
+    /// The watch expressions, in the order they were added.
+    pub fn expressions(&self) -> Vec<String> {
+        self.watches
+            .iter()
+            .map(|watch| watch.expression.to_string())
+            .collect()
+    }
+
+    /// Replaces the watches with `expressions`, e.g. the ones restored from a previous run.
+    pub fn set_expressions(&mut self, expressions: Vec<String>, cx: &mut Context<Self>) {
+        self.watches = expressions
+            .into_iter()
+            .map(|expression| Watch {
+                expression: expression.into(),
+                value: None,
+            })
+            .collect();
+        cx.emit(WatchListEvent::WatchesChanged);
+
+        let stack_frame_id = self.stack_frame_list.read(cx).current_stack_frame_id();
+        self.evaluate_watches(stack_frame_id, cx);
+        cx.notify();
+    }
+
+    fn evaluate_watches(&mut self, stack_frame_id: Option<StackFrameId>, cx: &mut Context<Self>) {
+        if stack_frame_id.is_none() || self.watches.is_empty() {
+            return;
+        }
+
+        let evaluations = self
+            .watches
+            .iter()
+            .map(|watch| {
+                let expression = watch.expression.clone();
+                let evaluation = self.session.update(cx, |session, cx| {
+                    session.evaluate_watch(expression.to_string(), stack_frame_id, cx)
+                });
+                (expression, evaluation)
+            })
+            .collect::<Vec<_>>();
+
+        self.evaluate_task = cx.spawn(|this, mut cx| async move {
+            for (expression, evaluation) in evaluations {
+                let value = evaluation
+                    .await
+                    .map(|response| SharedString::from(response.result))
+                    .map_err(|error| SharedString::from(error.to_string()));
+
+                this.update(&mut cx, |this, cx| {
+                    // Watches may have been removed while the evaluation was in flight.
+                    for watch in this
+                        .watches
+                        .iter_mut()
+                        .filter(|watch| watch.expression == expression)
+                    {
+                        watch.value = Some(value.clone());
+                    }
+                    cx.notify();
+                })
+                .ok();
+            }
+        });
+    }
+
+    fn render_watch(&self, ix: usize, watch: &Watch, cx: &mut Context<Self>) -> impl IntoElement {
+        h_flex()
+            .id(("watch", ix))
+            .w_full()
+            .gap_2()
+            .p_1()
+            .rounded_md()
+            .hover(|style| style.bg(cx.theme().colors().element_hover))
+            .child(Label::new(watch.expression.clone()).size(LabelSize::Small))
+            .child(div().flex_1().map(|this| {
+                match &watch.value {
+                    Some(Ok(value)) => this.child(
+                        Label::new(format!("=  {value}"))
+                            .single_line()
+                            .truncate()
+                            .size(LabelSize::Small)
+                            .color(Color::Muted),
+                    ),
+                    Some(Err(error)) => this.child(
+                        Label::new(error.clone())
+                            .single_line()
+                            .truncate()
+                            .size(LabelSize::Small)
+                            .color(Color::Error),
+                    ),
+                    None => this,
+                }
+            }))
+            .child(
+                IconButton::new(("remove-watch", ix), IconName::Close)
+                    .icon_size(IconSize::Small)
+                    .on_click(cx.listener(move |this, _, _window, cx| {
+                        this.remove_watch(ix, cx);
+                    })),
+            )
+    }
+}
+
+#[cfg(any(test, feature = "test-support"))]
+impl WatchList {
+    pub fn watch_editor(&self) -> &Entity<Editor> {
+        &self.watch_editor
+    }
+
+    /// The watch expressions with their last value, or their error prefixed with `Error: `.
+    pub fn watches(&self) -> Vec<(String, Option<String>)> {
+        self.watches
+            .iter()
+            .map(|watch| {
+                let value = watch.value.as_ref().map(|value| match value {
+                    Ok(value) => value.to_string(),
+                    Err(error) => format!("Error: {error}"),
+                });
+                (watch.expression.to_string(), value)
+            })
+            .collect()
+    }
+
+    pub fn confirm(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.add_watch(&Confirm, window, cx);
+    }
+}
+
+impl EventEmitter<WatchListEvent> for WatchList {}
+
+impl Focusable for WatchList {
+    fn focus_handle(&self, _: &App) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Render for WatchList {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let watches = self
+            .watches
+            .iter()
+            .enumerate()
+            .map(|(ix, watch)| self.render_watch(ix, watch, cx).into_any_element())
+            .collect::<Vec<_>>();
+
+        v_flex()
+            .key_context("DebugWatchList")
+            .track_focus(&self.focus_handle)
+            .on_action(cx.listener(Self::add_watch))
+            .size_full()
+            .p_1()
+            .gap_1()
+            .child(self.watch_editor.clone())
+            .children(watches)
+    }
+}
@@ -1,54 +1,111 @@
-use std::time::Duration;
+use std::{io, rc::Rc, time::Duration};
 
 use anyhow::Result;
 
 use dap::client::SessionId;
 use gpui::{
-    percentage, Animation, AnimationExt, Entity, EventEmitter, FocusHandle, Focusable, Task,
+    percentage, Animation, AnimationExt, App, Entity, EventEmitter, FocusHandle, Focusable, Task,
     Transformation,
 };
 use project::debugger::session::Session;
-use ui::{v_flex, Color, Context, Icon, IconName, IntoElement, ParentElement, Render, Styled};
+use ui::{
+    v_flex, Color, Context, FluentBuilder, Icon, IconName, IntoElement, Label, LabelCommon,
+    LabelSize, ParentElement, Render, Styled,
+};
+
+/// How many times an adapter that failed to start transiently is launched again automatically.
+const MAX_AUTOMATIC_RETRIES: u32 = 3;
+/// The delay before the first automatic retry, doubled for every following one.
+const RETRY_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Launches a session's debug adapter again under the same session ID.
+pub(crate) type Relaunch = Rc<dyn Fn(&mut App) -> Task<Result<Entity<Session>>>>;
 
 pub(crate) struct StartingState {
     focus_handle: FocusHandle,
     pub(super) session_id: SessionId,
+    /// How many times the adapter was launched again after failing transiently.
+    retries: u32,
     _notify_parent: Task<()>,
 }
 
 pub(crate) enum StartingEvent {
-    Failed,
+    Failed(anyhow::Error),
     Finished(Entity<Session>),
 }
 
 impl EventEmitter<StartingEvent> for StartingState {}
 
 impl StartingState {
+    /// Waits for the session to start. Failures that may go away by themselves, like the adapter's
+    /// port still being bound, are retried with `relaunch` a few times before being reported.
     pub(crate) fn new(
         session_id: SessionId,
         task: Task<Result<Entity<Session>>>,
+        relaunch: Option<Relaunch>,
         cx: &mut Context<Self>,
     ) -> Self {
         let _notify_parent = cx.spawn(move |this, mut cx| async move {
-            let entity = task.await;
+            let mut task = task;
+            let mut retries = 0;
+            let result = loop {
+                let error = match task.await {
+                    Ok(session) => break Ok(session),
+                    Err(error) => error,
+                };
+                let Some(relaunch) = relaunch
+                    .as_ref()
+                    .filter(|_| retries < MAX_AUTOMATIC_RETRIES && is_transient(&error))
+                else {
+                    break Err(error);
+                };
+
+                log::info!("Retrying debug adapter that failed to start: {error:#}");
+                cx.background_executor()
+                    .timer(RETRY_BACKOFF * 2u32.pow(retries))
+                    .await;
+                retries += 1;
+                let Ok(next_task) = this.update(&mut cx, |this, cx| {
+                    this.retries = retries;
+                    cx.notify();
+                    relaunch(cx)
+                }) else {
+                    return;
+                };
+                task = next_task;
+            };
 
-            this.update(&mut cx, |_, cx| {
-                if let Ok(entity) = entity {
-                    cx.emit(StartingEvent::Finished(entity))
-                } else {
-                    cx.emit(StartingEvent::Failed)
-                }
+            this.update(&mut cx, |_, cx| match result {
+                Ok(session) => cx.emit(StartingEvent::Finished(session)),
+                Err(error) => cx.emit(StartingEvent::Failed(error)),
             })
             .ok();
         });
         Self {
             session_id,
+            retries: 0,
             focus_handle: cx.focus_handle(),
             _notify_parent,
         }
     }
 }
 
+/// Whether a failure to start may go away by itself, e.g. when the port the adapter listens on
+/// is still bound by an adapter that's shutting down.
+fn is_transient(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        cause.downcast_ref::<io::Error>().is_some_and(|error| {
+            matches!(
+                error.kind(),
+                io::ErrorKind::AddrInUse
+                    | io::ErrorKind::ConnectionRefused
+                    | io::ErrorKind::ConnectionReset
+                    | io::ErrorKind::TimedOut
+            )
+        })
+    })
+}
+
 impl Focusable for StartingState {
     fn focus_handle(&self, _: &ui::App) -> FocusHandle {
         self.focus_handle.clone()
@@ -76,5 +133,15 @@ impl Render for StartingState {
                     )
                     .into_any_element(),
             )
+            .when(self.retries > 0, |this| {
+                this.child(
+                    Label::new(format!(
+                        "Retrying after a failure to start ({} of {MAX_AUTOMATIC_RETRIES})",
+                        self.retries
+                    ))
+                    .size(LabelSize::Small)
+                    .color(Color::Muted),
+                )
+            })
     }
 }
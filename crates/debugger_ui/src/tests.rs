@@ -7,11 +7,17 @@ use workspace::Workspace;
 use crate::{debugger_panel::DebugPanel, session::DebugSession};
 
 mod attach_modal;
+mod breakpoint_list;
 mod console;
 mod debugger_panel;
+mod disassembly_view;
+mod exception_breakpoint_list;
+mod hover;
+mod loaded_source_list;
 mod module_list;
 mod stack_frame_list;
 mod variable_list;
+mod watch_list;
 
 pub fn init_test(cx: &mut gpui::TestAppContext) {
     if std::env::var("RUST_LOG").is_ok() {
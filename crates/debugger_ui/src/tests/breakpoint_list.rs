@@ -0,0 +1,493 @@
+use crate::{
+    debugger_panel::DebugPanel,
+    session::{running::breakpoint_list::ConditionKind, ThreadItem},
+    tests::{active_debug_session_panel, init_test, init_test_workspace},
+};
+use dap::{
+    requests::{DataBreakpointInfo, SetBreakpoints, SetDataBreakpoints},
+    DataBreakpointAccessType, DebugRequestType,
+};
+use gpui::{BackgroundExecutor, TestAppContext, VisualTestContext};
+use language::Point;
+use project::{
+    debugger::breakpoint_store::{
+        Breakpoint, BreakpointEditAction, BreakpointKind, BreakpointState,
+    },
+    FakeFs, Project,
+};
+use serde_json::json;
+use std::{
+    path::Path,
+    sync::{Arc, Mutex},
+};
+use util::path;
+
+#[gpui::test]
+async fn test_breakpoint_list_toggles_breakpoints(
+    executor: BackgroundExecutor,
+    cx: &mut TestAppContext,
+) {
+    init_test(cx);
+
+    let fs = FakeFs::new(executor.clone());
+    fs.insert_tree(
+        path!("/project"),
+        json!({
+            "main.rs": "First line\nSecond line\nThird line",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, [path!("/project").as_ref()], cx).await;
+    let workspace = init_test_workspace(&project, cx).await;
+    workspace
+        .update(cx, |workspace, window, cx| {
+            workspace.focus_panel::<DebugPanel>(window, cx);
+        })
+        .unwrap();
+    let cx = &mut VisualTestContext::from_window(*workspace, cx);
+
+    let task = project.update(cx, |project, cx| {
+        project.start_debug_session(dap::test_config(DebugRequestType::Launch, None, None), cx)
+    });
+
+    let session = task.await.unwrap();
+    let client = session.update(cx, |session, _| session.adapter_client().unwrap());
+
+    let sent_breakpoint_lines = Arc::new(Mutex::new(None));
+    client
+        .on_request::<SetBreakpoints, _>({
+            let sent_breakpoint_lines = sent_breakpoint_lines.clone();
+            move |_, args| {
+                let lines = args
+                    .breakpoints
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|breakpoint| breakpoint.line)
+                    .collect::<Vec<_>>();
+                let breakpoints = lines
+                    .iter()
+                    .map(|line| {
+                        serde_json::from_value(json!({ "id": line * 10, "verified": false }))
+                            .unwrap()
+                    })
+                    .collect();
+                *sent_breakpoint_lines.lock().unwrap() = Some(lines);
+
+                Ok(dap::SetBreakpointsResponse { breakpoints })
+            }
+        })
+        .await;
+
+    cx.run_until_parked();
+
+    let buffer = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer(path!("/project/main.rs"), cx)
+        })
+        .await
+        .unwrap();
+    project.update(cx, |project, cx| {
+        let position = buffer.read(cx).snapshot().anchor_before(Point::new(1, 0));
+        project.breakpoint_store().update(cx, |store, cx| {
+            store.toggle_breakpoint(
+                buffer.clone(),
+                (
+                    position,
+                    Breakpoint {
+                        kind: BreakpointKind::Standard,
+                        condition: None,
+                        hit_condition: None,
+                        state: BreakpointState::Enabled,
+                    },
+                ),
+                BreakpointEditAction::Toggle,
+                cx,
+            )
+        });
+    });
+
+    cx.run_until_parked();
+
+    assert_eq!(Some(vec![2]), sent_breakpoint_lines.lock().unwrap().take());
+
+    let running_state = active_debug_session_panel(workspace, cx).update(cx, |item, _| {
+        item.mode()
+            .as_running()
+            .expect("Session should be running by this point")
+            .clone()
+    });
+    running_state.update(cx, |state, cx| {
+        state.set_thread_item(ThreadItem::Breakpoints, cx);
+    });
+    cx.run_until_parked();
+
+    let abs_path = Arc::<Path>::from(Path::new(path!("/project/main.rs")));
+    running_state.update(cx, |state, cx| {
+        let breakpoints = state
+            .breakpoint_list()
+            .update(cx, |list, cx| list.breakpoints(cx));
+        assert_eq!(1, breakpoints.len());
+        assert_eq!(abs_path, breakpoints[0].path);
+        assert_eq!(1, breakpoints[0].position);
+        assert!(breakpoints[0].state.is_enabled());
+    });
+    session.update(cx, |session, _| {
+        assert_eq!(Some(false), session.breakpoint_verified(&abs_path, 1));
+    });
+
+    // The adapter verifies the breakpoint later on, referring to it by id even though it moved
+    // it to the next line.
+    client
+        .fake_event(dap::messages::Events::Breakpoint(
+            serde_json::from_value(json!({
+                "reason": "changed",
+                "breakpoint": {
+                    "id": 20,
+                    "verified": true,
+                    "line": 3,
+                    "source": { "path": path!("/project/main.rs") },
+                },
+            }))
+            .unwrap(),
+        ))
+        .await;
+    cx.run_until_parked();
+
+    session.update(cx, |session, _| {
+        assert_eq!(Some(true), session.breakpoint_verified(&abs_path, 1));
+    });
+
+    // Disabling the breakpoint removes it from the adapter, but not from the list.
+    let toggle_enabled = |cx: &mut VisualTestContext| {
+        project.update(cx, |project, cx| {
+            project.breakpoint_store().update(cx, |store, cx| {
+                store.edit_breakpoint_at_row(&abs_path, 1, BreakpointEditAction::InvertState, cx)
+            });
+        });
+        cx.run_until_parked();
+    };
+    toggle_enabled(cx);
+
+    assert_eq!(Some(vec![]), sent_breakpoint_lines.lock().unwrap().take());
+    session.update(cx, |session, _| {
+        assert_eq!(None, session.breakpoint_verified(&abs_path, 1));
+    });
+    running_state.update(cx, |state, cx| {
+        let breakpoints = state
+            .breakpoint_list()
+            .update(cx, |list, cx| list.breakpoints(cx));
+        assert_eq!(1, breakpoints.len());
+        assert_eq!(BreakpointState::Disabled, breakpoints[0].state);
+    });
+
+    toggle_enabled(cx);
+
+    assert_eq!(Some(vec![2]), sent_breakpoint_lines.lock().unwrap().take());
+    session.update(cx, |session, _| {
+        assert_eq!(Some(false), session.breakpoint_verified(&abs_path, 1));
+    });
+
+    let shutdown_session = project.update(cx, |project, cx| {
+        project.dap_store().update(cx, |dap_store, cx| {
+            dap_store.shutdown_session(session.read(cx).session_id(), cx)
+        })
+    });
+
+    shutdown_session.await.unwrap();
+}
+
+#[gpui::test]
+async fn test_breakpoint_list_edits_conditions(
+    executor: BackgroundExecutor,
+    cx: &mut TestAppContext,
+) {
+    init_test(cx);
+
+    let fs = FakeFs::new(executor.clone());
+    fs.insert_tree(
+        path!("/project"),
+        json!({
+            "main.rs": "First line\nSecond line\nThird line",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, [path!("/project").as_ref()], cx).await;
+    let workspace = init_test_workspace(&project, cx).await;
+    workspace
+        .update(cx, |workspace, window, cx| {
+            workspace.focus_panel::<DebugPanel>(window, cx);
+        })
+        .unwrap();
+    let cx = &mut VisualTestContext::from_window(*workspace, cx);
+
+    // The adapter evaluates conditions, but not hit counts.
+    let task = project.update(cx, |project, cx| {
+        project.start_debug_session(
+            dap::test_config(
+                DebugRequestType::Launch,
+                None,
+                Some(dap::Capabilities {
+                    supports_conditional_breakpoints: Some(true),
+                    ..Default::default()
+                }),
+            ),
+            cx,
+        )
+    });
+
+    let session = task.await.unwrap();
+    let client = session.update(cx, |session, _| session.adapter_client().unwrap());
+
+    let sent_breakpoints = Arc::new(Mutex::new(None));
+    client
+        .on_request::<SetBreakpoints, _>({
+            let sent_breakpoints = sent_breakpoints.clone();
+            move |_, args| {
+                let breakpoints = args.breakpoints.unwrap_or_default();
+                let response = breakpoints
+                    .iter()
+                    .map(|_| serde_json::from_value(json!({ "verified": true })).unwrap())
+                    .collect();
+                *sent_breakpoints.lock().unwrap() = Some(breakpoints);
+
+                Ok(dap::SetBreakpointsResponse {
+                    breakpoints: response,
+                })
+            }
+        })
+        .await;
+
+    cx.run_until_parked();
+
+    let buffer = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer(path!("/project/main.rs"), cx)
+        })
+        .await
+        .unwrap();
+    project.update(cx, |project, cx| {
+        let position = buffer.read(cx).snapshot().anchor_before(Point::new(2, 0));
+        project.breakpoint_store().update(cx, |store, cx| {
+            store.toggle_breakpoint(
+                buffer.clone(),
+                (
+                    position,
+                    Breakpoint {
+                        kind: BreakpointKind::Standard,
+                        condition: None,
+                        hit_condition: None,
+                        state: BreakpointState::Enabled,
+                    },
+                ),
+                BreakpointEditAction::Toggle,
+                cx,
+            )
+        });
+    });
+    cx.run_until_parked();
+    sent_breakpoints.lock().unwrap().take();
+
+    let running_state = active_debug_session_panel(workspace, cx).update(cx, |item, _| {
+        item.mode()
+            .as_running()
+            .expect("Session should be running by this point")
+            .clone()
+    });
+    running_state.update(cx, |state, cx| {
+        state.set_thread_item(ThreadItem::Breakpoints, cx);
+    });
+    cx.run_until_parked();
+
+    let abs_path = Arc::<Path>::from(Path::new(path!("/project/main.rs")));
+    let breakpoint_list = running_state.update(cx, |state, _| state.breakpoint_list().clone());
+    for (kind, condition) in [
+        (ConditionKind::Condition, "x > 1"),
+        (ConditionKind::HitCondition, ">= 3"),
+    ] {
+        breakpoint_list.update_in(cx, |list, window, cx| {
+            list.edit_condition(abs_path.clone(), 2, kind, None, window, cx);
+        });
+        cx.simulate_input(condition);
+        cx.dispatch_action(menu::Confirm);
+        cx.run_until_parked();
+    }
+
+    breakpoint_list.update(cx, |list, cx| {
+        let breakpoints = list.breakpoints(cx);
+        assert_eq!(1, breakpoints.len());
+        assert_eq!(Some("x > 1".into()), breakpoints[0].condition);
+        assert_eq!(Some(">= 3".into()), breakpoints[0].hit_condition);
+    });
+
+    // The hit count is kept, but not sent to an adapter that can't evaluate it.
+    let sent = sent_breakpoints
+        .lock()
+        .unwrap()
+        .take()
+        .expect("Editing a condition should resend the breakpoints");
+    assert_eq!(1, sent.len());
+    assert_eq!(3, sent[0].line);
+    assert_eq!(Some("x > 1".to_string()), sent[0].condition);
+    assert_eq!(None, sent[0].hit_condition);
+
+    // Confirming an empty condition removes it.
+    breakpoint_list.update_in(cx, |list, window, cx| {
+        list.edit_condition(
+            abs_path.clone(),
+            2,
+            ConditionKind::Condition,
+            None,
+            window,
+            cx,
+        );
+    });
+    cx.dispatch_action(menu::Confirm);
+    cx.run_until_parked();
+
+    breakpoint_list.update(cx, |list, cx| {
+        let breakpoints = list.breakpoints(cx);
+        assert_eq!(None, breakpoints[0].condition);
+        assert_eq!(Some(">= 3".into()), breakpoints[0].hit_condition);
+    });
+
+    let shutdown_session = project.update(cx, |project, cx| {
+        project.dap_store().update(cx, |dap_store, cx| {
+            dap_store.shutdown_session(session.read(cx).session_id(), cx)
+        })
+    });
+
+    shutdown_session.await.unwrap();
+}
+
+#[gpui::test]
+async fn test_data_breakpoints(executor: BackgroundExecutor, cx: &mut TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(executor.clone());
+    fs.insert_tree(
+        path!("/project"),
+        json!({
+            "main.rs": "First line\nSecond line\nThird line",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, [path!("/project").as_ref()], cx).await;
+    let workspace = init_test_workspace(&project, cx).await;
+    let cx = &mut VisualTestContext::from_window(*workspace, cx);
+
+    let task = project.update(cx, |project, cx| {
+        project.start_debug_session(
+            dap::test_config(
+                DebugRequestType::Launch,
+                None,
+                Some(dap::Capabilities {
+                    supports_data_breakpoints: Some(true),
+                    ..Default::default()
+                }),
+            ),
+            cx,
+        )
+    });
+
+    let session = task.await.unwrap();
+    let client = session.update(cx, |session, _| session.adapter_client().unwrap());
+
+    // Only `counter` can be watched.
+    client
+        .on_request::<DataBreakpointInfo, _>(move |_, args| {
+            let response = if args.name == "counter" {
+                json!({
+                    "dataId": "counter-id",
+                    "description": "counter",
+                    "accessTypes": ["read", "write"],
+                })
+            } else {
+                json!({
+                    "dataId": null,
+                    "description": "Temporaries can't be watched",
+                })
+            };
+            Ok(serde_json::from_value(response).unwrap())
+        })
+        .await;
+
+    let sent_data_ids = Arc::new(Mutex::new(None));
+    client
+        .on_request::<SetDataBreakpoints, _>({
+            let sent_data_ids = sent_data_ids.clone();
+            move |_, args| {
+                let breakpoints = args
+                    .breakpoints
+                    .iter()
+                    .map(|_| serde_json::from_value(json!({ "verified": true })).unwrap())
+                    .collect();
+                *sent_data_ids.lock().unwrap() = Some(
+                    args.breakpoints
+                        .into_iter()
+                        .map(|breakpoint| (breakpoint.data_id, breakpoint.access_type))
+                        .collect::<Vec<_>>(),
+                );
+
+                Ok(dap::SetDataBreakpointsResponse { breakpoints })
+            }
+        })
+        .await;
+
+    cx.run_until_parked();
+
+    session
+        .update(cx, |session, cx| {
+            session.add_data_breakpoint(1, "counter".into(), None, cx)
+        })
+        .await
+        .unwrap();
+    cx.run_until_parked();
+
+    // Breaking on writes is preferred, as that's when the value changes.
+    assert_eq!(
+        Some(vec![(
+            "counter-id".to_string(),
+            Some(DataBreakpointAccessType::Write)
+        )]),
+        sent_data_ids.lock().unwrap().take()
+    );
+    session.update(cx, |session, _| {
+        let data_breakpoints = session.data_breakpoints();
+        assert_eq!(1, data_breakpoints.len());
+        assert_eq!("counter", data_breakpoints[0].description);
+        assert_eq!(Some(true), data_breakpoints[0].verified);
+    });
+
+    let error = session
+        .update(cx, |session, cx| {
+            session.add_data_breakpoint(1, "temporary".into(), None, cx)
+        })
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("Temporaries can't be watched"));
+    assert_eq!(None, sent_data_ids.lock().unwrap().take());
+
+    session
+        .update(cx, |session, cx| {
+            session.remove_data_breakpoint("counter-id", cx)
+        })
+        .await
+        .unwrap();
+    cx.run_until_parked();
+
+    assert_eq!(Some(vec![]), sent_data_ids.lock().unwrap().take());
+    session.update(cx, |session, _| {
+        assert!(session.data_breakpoints().is_empty());
+    });
+
+    let shutdown_session = project.update(cx, |project, cx| {
+        project.dap_store().update(cx, |dap_store, cx| {
+            dap_store.shutdown_session(session.read(cx).session_id(), cx)
+        })
+    });
+
+    shutdown_session.await.unwrap();
+}
@@ -1,8 +1,13 @@
 use crate::{tests::active_debug_session_panel, *};
-use dap::requests::StackTrace;
+use dap::{
+    debugger_settings::DebuggerSettings,
+    requests::{Evaluate, StackTrace, Threads},
+};
 use gpui::{BackgroundExecutor, TestAppContext, VisualTestContext};
 use project::{FakeFs, Project};
 use serde_json::json;
+use settings::SettingsStore;
+use std::sync::{Arc, Mutex};
 use tests::{init_test, init_test_workspace};
 
 #[gpui::test]
@@ -178,6 +183,376 @@ async fn test_handle_output_event(executor: BackgroundExecutor, cx: &mut TestApp
     shutdown_session.await.unwrap();
 }
 
+#[gpui::test]
+async fn test_telemetry_output_is_hidden_by_default(
+    executor: BackgroundExecutor,
+    cx: &mut TestAppContext,
+) {
+    init_test(cx);
+
+    let fs = FakeFs::new(executor.clone());
+
+    fs.insert_tree(
+        "/project",
+        json!({
+            "main.rs": "First line\nSecond line\nThird line\nFourth line",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/project".as_ref()], cx).await;
+    let workspace = init_test_workspace(&project, cx).await;
+    let cx = &mut VisualTestContext::from_window(*workspace, cx);
+
+    let task = project.update(cx, |project, cx| {
+        project.start_debug_session(
+            dap::test_config(dap::DebugRequestType::Launch, None, None),
+            cx,
+        )
+    });
+
+    let session = task.await.unwrap();
+    let client = session.update(cx, |session, _| session.adapter_client().unwrap());
+
+    for (category, output) in [
+        (dap::OutputEventCategory::Stdout, "Regular output"),
+        (dap::OutputEventCategory::Telemetry, "Telemetry output"),
+        (dap::OutputEventCategory::Stderr, "Error output"),
+        (dap::OutputEventCategory::Important, "Important output"),
+    ] {
+        client
+            .fake_event(dap::messages::Events::Output(dap::OutputEvent {
+                category: Some(category),
+                output: output.to_string(),
+                data: None,
+                variables_reference: None,
+                source: None,
+                line: None,
+                column: None,
+                group: None,
+                location_reference: None,
+            }))
+            .await;
+    }
+
+    cx.run_until_parked();
+
+    let running_state = active_debug_session_panel(workspace, cx).update(cx, |item, _| {
+        item.mode()
+            .as_running()
+            .expect("Session should be running by this point")
+            .clone()
+    });
+
+    running_state.update(cx, |state, cx| {
+        state.set_thread_item(session::ThreadItem::Console, cx);
+        cx.refresh_windows();
+    });
+    cx.run_until_parked();
+
+    running_state.update(cx, |state, cx| {
+        assert_eq!(
+            "Regular output\nError output\nImportant output\n",
+            state.console().read(cx).editor().read(cx).text(cx).as_str()
+        );
+    });
+
+    let shutdown_session = project.update(cx, |project, cx| {
+        project.dap_store().update(cx, |dap_store, cx| {
+            dap_store.shutdown_session(session.read(cx).session_id(), cx)
+        })
+    });
+
+    shutdown_session.await.unwrap();
+}
+
+#[gpui::test]
+async fn test_telemetry_output_is_shown_when_enabled(
+    executor: BackgroundExecutor,
+    cx: &mut TestAppContext,
+) {
+    init_test(cx);
+    cx.update(|cx| {
+        SettingsStore::update_global(cx, |store, cx| {
+            store.update_user_settings::<DebuggerSettings>(cx, |settings| {
+                settings.show_telemetry_output = true;
+            });
+        });
+    });
+
+    let fs = FakeFs::new(executor.clone());
+
+    fs.insert_tree(
+        "/project",
+        json!({
+            "main.rs": "First line\nSecond line\nThird line\nFourth line",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/project".as_ref()], cx).await;
+    let workspace = init_test_workspace(&project, cx).await;
+    let cx = &mut VisualTestContext::from_window(*workspace, cx);
+
+    let task = project.update(cx, |project, cx| {
+        project.start_debug_session(
+            dap::test_config(dap::DebugRequestType::Launch, None, None),
+            cx,
+        )
+    });
+
+    let session = task.await.unwrap();
+    let client = session.update(cx, |session, _| session.adapter_client().unwrap());
+
+    for (category, output) in [
+        (dap::OutputEventCategory::Stdout, "Regular output"),
+        (dap::OutputEventCategory::Telemetry, "Telemetry output"),
+        (dap::OutputEventCategory::Stderr, "Error output"),
+        (dap::OutputEventCategory::Important, "Important output"),
+    ] {
+        client
+            .fake_event(dap::messages::Events::Output(dap::OutputEvent {
+                category: Some(category),
+                output: output.to_string(),
+                data: None,
+                variables_reference: None,
+                source: None,
+                line: None,
+                column: None,
+                group: None,
+                location_reference: None,
+            }))
+            .await;
+    }
+
+    cx.run_until_parked();
+
+    let running_state = active_debug_session_panel(workspace, cx).update(cx, |item, _| {
+        item.mode()
+            .as_running()
+            .expect("Session should be running by this point")
+            .clone()
+    });
+
+    running_state.update(cx, |state, cx| {
+        state.set_thread_item(session::ThreadItem::Console, cx);
+        cx.refresh_windows();
+    });
+    cx.run_until_parked();
+
+    running_state.update(cx, |state, cx| {
+        assert_eq!(
+            "Regular output\nTelemetry output\nError output\nImportant output\n",
+            state.console().read(cx).editor().read(cx).text(cx).as_str()
+        );
+    });
+
+    let shutdown_session = project.update(cx, |project, cx| {
+        project.dap_store().update(cx, |dap_store, cx| {
+            dap_store.shutdown_session(session.read(cx).session_id(), cx)
+        })
+    });
+
+    shutdown_session.await.unwrap();
+}
+
+#[gpui::test]
+async fn test_console_drops_oldest_lines_over_limit(
+    executor: BackgroundExecutor,
+    cx: &mut TestAppContext,
+) {
+    init_test(cx);
+    cx.update(|cx| {
+        SettingsStore::update_global(cx, |store, cx| {
+            store.update_user_settings::<DebuggerSettings>(cx, |settings| {
+                settings.console_max_lines = 2;
+            });
+        });
+    });
+
+    let fs = FakeFs::new(executor.clone());
+
+    fs.insert_tree(
+        "/project",
+        json!({
+            "main.rs": "First line\nSecond line\nThird line\nFourth line",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/project".as_ref()], cx).await;
+    let workspace = init_test_workspace(&project, cx).await;
+    let cx = &mut VisualTestContext::from_window(*workspace, cx);
+
+    let task = project.update(cx, |project, cx| {
+        project.start_debug_session(
+            dap::test_config(dap::DebugRequestType::Launch, None, None),
+            cx,
+        )
+    });
+
+    let session = task.await.unwrap();
+    let client = session.update(cx, |session, _| session.adapter_client().unwrap());
+
+    for output in ["First output", "Second output", "Third output"] {
+        client
+            .fake_event(dap::messages::Events::Output(dap::OutputEvent {
+                category: Some(dap::OutputEventCategory::Stdout),
+                output: output.to_string(),
+                data: None,
+                variables_reference: None,
+                source: None,
+                line: None,
+                column: None,
+                group: None,
+                location_reference: None,
+            }))
+            .await;
+    }
+
+    cx.run_until_parked();
+
+    let running_state = active_debug_session_panel(workspace, cx).update(cx, |item, _| {
+        item.mode()
+            .as_running()
+            .expect("Session should be running by this point")
+            .clone()
+    });
+
+    running_state.update(cx, |state, cx| {
+        state.set_thread_item(session::ThreadItem::Console, cx);
+        cx.refresh_windows();
+    });
+    cx.run_until_parked();
+
+    running_state.update(cx, |state, cx| {
+        assert_eq!(
+            "Second output\nThird output\n",
+            state.console().read(cx).editor().read(cx).text(cx).as_str()
+        );
+    });
+
+    let shutdown_session = project.update(cx, |project, cx| {
+        project.dap_store().update(cx, |dap_store, cx| {
+            dap_store.shutdown_session(session.read(cx).session_id(), cx)
+        })
+    });
+
+    shutdown_session.await.unwrap();
+}
+
+#[gpui::test]
+async fn test_console_evaluates_expressions(executor: BackgroundExecutor, cx: &mut TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(executor.clone());
+
+    let project = Project::test(fs, ["/project".as_ref()], cx).await;
+    let workspace = init_test_workspace(&project, cx).await;
+    let cx = &mut VisualTestContext::from_window(*workspace, cx);
+
+    let task = project.update(cx, |project, cx| {
+        project.start_debug_session(
+            dap::test_config(dap::DebugRequestType::Launch, None, None),
+            cx,
+        )
+    });
+
+    let session = task.await.unwrap();
+    let client = session.update(cx, |session, _| session.adapter_client().unwrap());
+
+    client
+        .on_request::<Evaluate, _>(move |_, args| {
+            assert!(matches!(
+                args.context,
+                Some(dap::EvaluateArgumentsContext::Repl)
+            ));
+            if args.expression == "answer" {
+                Ok(dap::EvaluateResponse {
+                    result: "42".into(),
+                    type_: None,
+                    presentation_hint: None,
+                    variables_reference: 0,
+                    named_variables: None,
+                    indexed_variables: None,
+                    memory_reference: None,
+                    value_location_reference: None,
+                })
+            } else {
+                Err(dap::ErrorResponse {
+                    error: Some(dap::Message {
+                        id: 1,
+                        format: "name is not defined".into(),
+                        variables: None,
+                        send_telemetry: None,
+                        show_user: None,
+                        url: None,
+                        url_label: None,
+                    }),
+                })
+            }
+        })
+        .await;
+
+    cx.run_until_parked();
+
+    let running_state = active_debug_session_panel(workspace, cx).update(cx, |item, _| {
+        item.mode()
+            .as_running()
+            .expect("Session should be running by this point")
+            .clone()
+    });
+    running_state.update(cx, |state, cx| {
+        state.set_thread_item(session::ThreadItem::Console, cx);
+        cx.refresh_windows();
+    });
+    let console = running_state.update(cx, |state, _| state.console().clone());
+
+    for expression in ["answer", "missing"] {
+        console.update_in(cx, |console, window, cx| {
+            console.query_bar().update(cx, |query_bar, cx| {
+                query_bar.set_text(expression, window, cx);
+            });
+            console.evaluate(&menu::Confirm, window, cx);
+        });
+        cx.run_until_parked();
+    }
+    cx.update(|_, cx| cx.refresh_windows());
+    cx.run_until_parked();
+
+    console.update(cx, |console, cx| {
+        let text = console.editor().read(cx).text(cx);
+        let lines = text.lines().collect::<Vec<_>>();
+        assert_eq!(lines[..3], ["> answer", "42", "> missing"]);
+        assert_eq!(
+            lines.len(),
+            4,
+            "the failed evaluation should print its error"
+        );
+        assert!(console.query_bar().read(cx).text(cx).is_empty());
+    });
+
+    // The up and down keys browse the evaluated expressions, newest first.
+    console.update_in(cx, |console, window, cx| {
+        console.previous_evaluation(&editor::actions::MoveUp, window, cx);
+        assert_eq!(console.query_bar().read(cx).text(cx), "missing");
+        console.previous_evaluation(&editor::actions::MoveUp, window, cx);
+        assert_eq!(console.query_bar().read(cx).text(cx), "answer");
+        console.next_evaluation(&editor::actions::MoveDown, window, cx);
+        assert_eq!(console.query_bar().read(cx).text(cx), "missing");
+        console.next_evaluation(&editor::actions::MoveDown, window, cx);
+        assert_eq!(console.query_bar().read(cx).text(cx), "");
+    });
+
+    let shutdown_session = project.update(cx, |project, cx| {
+        project.dap_store().update(cx, |dap_store, cx| {
+            dap_store.shutdown_session(session.read(cx).session_id(), cx)
+        })
+    });
+
+    shutdown_session.await.unwrap();
+}
+
 // #[gpui::test]
 // async fn test_grouped_output(executor: BackgroundExecutor, cx: &mut TestAppContext) {
 //     init_test(cx);
@@ -914,3 +1289,480 @@ async fn test_handle_output_event(executor: BackgroundExecutor, cx: &mut TestApp
 
 //     shutdown_session.await.unwrap();
 // }
+
+#[gpui::test]
+async fn test_evaluate_selection_in_debugger(
+    executor: BackgroundExecutor,
+    cx: &mut TestAppContext,
+) {
+    init_test(cx);
+
+    let fs = FakeFs::new(executor.clone());
+
+    fs.insert_tree(
+        "/project",
+        json!({
+            "main.rs": "fn main() {\n    let answer = 42;\n    missing();\n}",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/project".as_ref()], cx).await;
+    let workspace = init_test_workspace(&project, cx).await;
+    let cx = &mut VisualTestContext::from_window(*workspace, cx);
+
+    let task = project.update(cx, |project, cx| {
+        project.start_debug_session(
+            dap::test_config(dap::DebugRequestType::Launch, None, None),
+            cx,
+        )
+    });
+
+    let session = task.await.unwrap();
+    let client = session.update(cx, |session, _| session.adapter_client().unwrap());
+
+    client
+        .on_request::<Threads, _>(move |_, _| {
+            Ok(dap::ThreadsResponse {
+                threads: vec![dap::Thread {
+                    id: 1,
+                    name: "Thread 1".into(),
+                }],
+            })
+        })
+        .await;
+
+    client
+        .on_request::<StackTrace, _>(move |_, _| {
+            Ok(dap::StackTraceResponse {
+                stack_frames: vec![dap::StackFrame {
+                    id: 7,
+                    name: "main".into(),
+                    source: None,
+                    line: 2,
+                    column: 1,
+                    end_line: None,
+                    end_column: None,
+                    can_restart: None,
+                    instruction_pointer_reference: None,
+                    module_id: None,
+                    presentation_hint: None,
+                }],
+                total_frames: None,
+            })
+        })
+        .await;
+
+    let evaluations = Arc::new(Mutex::new(Vec::new()));
+    client
+        .on_request::<Evaluate, _>({
+            let evaluations = evaluations.clone();
+            move |_, args| {
+                evaluations
+                    .lock()
+                    .unwrap()
+                    .push((args.expression.clone(), args.frame_id));
+                if args.expression == "answer" {
+                    Ok(dap::EvaluateResponse {
+                        result: "42".into(),
+                        type_: None,
+                        presentation_hint: None,
+                        variables_reference: 0,
+                        named_variables: None,
+                        indexed_variables: None,
+                        memory_reference: None,
+                        value_location_reference: None,
+                    })
+                } else {
+                    Err(dap::ErrorResponse {
+                        error: Some(dap::Message {
+                            id: 1,
+                            format: "missing is not a valid expression".into(),
+                            variables: None,
+                            send_telemetry: None,
+                            show_user: None,
+                            url: None,
+                            url_label: None,
+                        }),
+                    })
+                }
+            }
+        })
+        .await;
+
+    let worktree_id = project.update(cx, |project, cx| {
+        project.worktrees(cx).next().unwrap().read(cx).id()
+    });
+    let editor = workspace
+        .update(cx, |workspace, window, cx| {
+            workspace.open_path((worktree_id, "main.rs"), None, true, window, cx)
+        })
+        .unwrap()
+        .await
+        .unwrap()
+        .downcast::<editor::Editor>()
+        .unwrap();
+    let select = |text: &str, cx: &mut VisualTestContext| {
+        editor.update_in(cx, |editor, window, cx| {
+            let start = editor.text(cx).find(text).unwrap();
+            editor.change_selections(None, window, cx, |selections| {
+                selections.select_ranges([start..start + text.len()]);
+            });
+        });
+    };
+
+    // Nothing is evaluated while the program is running.
+    select("answer", cx);
+    cx.dispatch_action(workspace::EvaluateSelection);
+    cx.run_until_parked();
+    assert!(evaluations.lock().unwrap().is_empty());
+
+    client
+        .fake_event(dap::messages::Events::Stopped(dap::StoppedEvent {
+            reason: dap::StoppedEventReason::Pause,
+            description: None,
+            thread_id: Some(1),
+            preserve_focus_hint: None,
+            text: None,
+            all_threads_stopped: None,
+            hit_breakpoint_ids: None,
+        }))
+        .await;
+    cx.run_until_parked();
+
+    let running_state = active_debug_session_panel(workspace, cx).update(cx, |item, _| {
+        item.mode()
+            .as_running()
+            .expect("Session should be running by this point")
+            .clone()
+    });
+    running_state.update(cx, |state, cx| {
+        let threads = state
+            .session()
+            .update(cx, |session, cx| session.threads(cx));
+        state.select_current_thread(&threads, cx);
+    });
+    cx.run_until_parked();
+
+    // The selection is evaluated in the selected stack frame, and the error of an invalid
+    // expression is shown as well.
+    select("answer", cx);
+    cx.dispatch_action(workspace::EvaluateSelection);
+    cx.run_until_parked();
+    select("missing", cx);
+    cx.dispatch_action(workspace::EvaluateSelection);
+    cx.run_until_parked();
+
+    assert_eq!(
+        *evaluations.lock().unwrap(),
+        [
+            ("answer".to_string(), Some(7)),
+            ("missing".to_string(), Some(7))
+        ]
+    );
+
+    running_state.update(cx, |state, cx| {
+        assert!(
+            state.active_thread_item() == session::ThreadItem::Console,
+            "the console should be shown to reveal the result"
+        );
+        cx.refresh_windows();
+    });
+    cx.run_until_parked();
+
+    let console = running_state.update(cx, |state, _| state.console().clone());
+    console.update(cx, |console, cx| {
+        let text = console.editor().read(cx).text(cx);
+        let lines = text.lines().collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            [
+                "> answer",
+                "42",
+                "> missing",
+                "missing is not a valid expression"
+            ]
+        );
+    });
+
+    let shutdown_session = project.update(cx, |project, cx| {
+        project.dap_store().update(cx, |dap_store, cx| {
+            dap_store.shutdown_session(session.read(cx).session_id(), cx)
+        })
+    });
+
+    shutdown_session.await.unwrap();
+}
+
+#[gpui::test]
+async fn test_console_scroll_is_kept_across_tabs(
+    executor: BackgroundExecutor,
+    cx: &mut TestAppContext,
+) {
+    init_test(cx);
+
+    let fs = FakeFs::new(executor.clone());
+
+    fs.insert_tree(
+        "/project",
+        json!({
+            "main.rs": "First line\nSecond line\nThird line\nFourth line",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/project".as_ref()], cx).await;
+    let workspace = init_test_workspace(&project, cx).await;
+    let cx = &mut VisualTestContext::from_window(*workspace, cx);
+    workspace
+        .update(cx, |workspace, window, cx| {
+            workspace.focus_panel::<DebugPanel>(window, cx);
+        })
+        .unwrap();
+
+    let task = project.update(cx, |project, cx| {
+        project.start_debug_session(
+            dap::test_config(dap::DebugRequestType::Launch, None, None),
+            cx,
+        )
+    });
+
+    let session = task.await.unwrap();
+    let client = session.update(cx, |session, _| session.adapter_client().unwrap());
+
+    let output = |output: String| {
+        dap::messages::Events::Output(dap::OutputEvent {
+            category: Some(dap::OutputEventCategory::Stdout),
+            output,
+            data: None,
+            variables_reference: None,
+            source: None,
+            line: None,
+            column: None,
+            group: None,
+            location_reference: None,
+        })
+    };
+    let lines = |range: std::ops::Range<usize>| {
+        range
+            .map(|ix| format!("line {ix}"))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    client.fake_event(output(lines(0..200))).await;
+    cx.run_until_parked();
+
+    let running_state = active_debug_session_panel(workspace, cx).update(cx, |item, _| {
+        item.mode()
+            .as_running()
+            .expect("Session should be running by this point")
+            .clone()
+    });
+    let show_tab = |thread_item: session::ThreadItem, cx: &mut VisualTestContext| {
+        running_state.update(cx, |state, cx| {
+            state.set_thread_item(thread_item, cx);
+            cx.refresh_windows();
+        });
+        cx.run_until_parked();
+    };
+    show_tab(session::ThreadItem::Console, cx);
+
+    let editor = running_state.update(cx, |state, cx| state.console().read(cx).editor().clone());
+
+    // Scrolled away from the end, the place in the scrollback is kept while output arrives.
+    editor.update_in(cx, |editor, window, cx| {
+        editor.set_scroll_position(gpui::point(0., 20.), window, cx);
+    });
+    cx.run_until_parked();
+
+    show_tab(session::ThreadItem::Variables, cx);
+    client.fake_event(output("new output".into())).await;
+    cx.run_until_parked();
+    show_tab(session::ThreadItem::Console, cx);
+
+    editor.update(cx, |editor, cx| {
+        assert!(editor.text(cx).ends_with("new output\n"));
+        assert_eq!(20., editor.scroll_position(cx).y);
+    });
+
+    // Scrolled to the end, the console keeps following the output.
+    editor.update_in(cx, |editor, window, cx| {
+        editor.move_to_end(&editor::actions::MoveToEnd, window, cx);
+    });
+    cx.run_until_parked();
+    let tail_position = editor.update(cx, |editor, cx| editor.scroll_position(cx).y);
+    assert!(tail_position > 20.);
+
+    show_tab(session::ThreadItem::Variables, cx);
+    client.fake_event(output(lines(200..300))).await;
+    cx.run_until_parked();
+    show_tab(session::ThreadItem::Console, cx);
+
+    editor.update(cx, |editor, cx| {
+        assert!(editor.scroll_position(cx).y >= tail_position + 100.);
+    });
+
+    let shutdown_session = project.update(cx, |project, cx| {
+        project.dap_store().update(cx, |dap_store, cx| {
+            dap_store.shutdown_session(session.read(cx).session_id(), cx)
+        })
+    });
+
+    shutdown_session.await.unwrap();
+}
+
+#[test]
+fn test_file_links_in_output() {
+    use crate::session::running::console::{file_links, FileLink};
+    use std::path::PathBuf;
+
+    let line = "panicked at src/main.rs:12:5 (called from /project/lib.rs:3)";
+    assert_eq!(
+        vec![
+            (
+                12..28,
+                FileLink {
+                    path: PathBuf::from("src/main.rs"),
+                    row: 11,
+                    column: 4,
+                }
+            ),
+            (
+                42..59,
+                FileLink {
+                    path: PathBuf::from("/project/lib.rs"),
+                    row: 2,
+                    column: 0,
+                }
+            ),
+        ],
+        file_links(line)
+    );
+
+    assert!(file_links("listening on http://localhost.local:8080").is_empty());
+    assert!(file_links("no paths here: 42").is_empty());
+}
+
+#[gpui::test]
+async fn test_console_opens_clicked_file_links(
+    executor: BackgroundExecutor,
+    cx: &mut TestAppContext,
+) {
+    init_test(cx);
+
+    let fs = FakeFs::new(executor.clone());
+
+    fs.insert_tree(
+        "/project",
+        json!({
+            "main.rs": "fn main() {\n    run();\n}",
+            "src": {
+                "lib.rs": "pub fn run() {\n    panic!();\n}",
+            },
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/project".as_ref()], cx).await;
+    let workspace = init_test_workspace(&project, cx).await;
+    let cx = &mut VisualTestContext::from_window(*workspace, cx);
+
+    let task = project.update(cx, |project, cx| {
+        project.start_debug_session(
+            dap::DebugAdapterConfig {
+                cwd: Some("/project/src".into()),
+                ..dap::test_config(dap::DebugRequestType::Launch, None, None)
+            },
+            cx,
+        )
+    });
+
+    let session = task.await.unwrap();
+    let client = session.update(cx, |session, _| session.adapter_client().unwrap());
+
+    client
+        .fake_event(dap::messages::Events::Output(dap::OutputEvent {
+            category: Some(dap::OutputEventCategory::Stderr),
+            output: "panicked at lib.rs:2:5\nnote: called from /project/main.rs:2".to_string(),
+            data: None,
+            variables_reference: None,
+            source: None,
+            line: None,
+            column: None,
+            group: None,
+            location_reference: None,
+        }))
+        .await;
+
+    cx.run_until_parked();
+
+    let running_state = active_debug_session_panel(workspace, cx).update(cx, |item, _| {
+        item.mode()
+            .as_running()
+            .expect("Session should be running by this point")
+            .clone()
+    });
+    running_state.update(cx, |state, cx| {
+        state.set_thread_item(session::ThreadItem::Console, cx);
+        cx.refresh_windows();
+    });
+    cx.run_until_parked();
+
+    let console = running_state.update(cx, |state, _| state.console().clone());
+    let text = console.update(cx, |console, cx| console.editor().read(cx).text(cx));
+    let offset_of = |needle: &str| text.find(needle).unwrap();
+
+    let active_editor_position = |cx: &mut VisualTestContext| {
+        workspace
+            .update(cx, |workspace, _, cx| {
+                let editor = workspace.active_item_as::<editor::Editor>(cx).unwrap();
+                editor.update(cx, |editor, cx| {
+                    (
+                        editor.text(cx),
+                        editor.selections.newest::<language::Point>(cx).head(),
+                    )
+                })
+            })
+            .unwrap()
+    };
+
+    // Relative paths are resolved against the session's working directory.
+    console.update_in(cx, |console, window, cx| {
+        assert!(console.open_file_link_at(offset_of("lib.rs") + 2, window, cx));
+    });
+    cx.run_until_parked();
+    assert_eq!(
+        (
+            "pub fn run() {\n    panic!();\n}".to_string(),
+            language::Point::new(1, 4)
+        ),
+        active_editor_position(cx)
+    );
+
+    console.update_in(cx, |console, window, cx| {
+        assert!(console.open_file_link_at(offset_of("/project/main.rs"), window, cx));
+    });
+    cx.run_until_parked();
+    assert_eq!(
+        (
+            "fn main() {\n    run();\n}".to_string(),
+            language::Point::new(1, 0)
+        ),
+        active_editor_position(cx)
+    );
+
+    // Text around the links isn't clickable.
+    console.update_in(cx, |console, window, cx| {
+        assert!(!console.open_file_link_at(offset_of("panicked"), window, cx));
+        assert!(!console.open_file_link_at(offset_of("note"), window, cx));
+    });
+
+    let shutdown_session = project.update(cx, |project, cx| {
+        project.dap_store().update(cx, |dap_store, cx| {
+            dap_store.shutdown_session(session.read(cx).session_id(), cx)
+        })
+    });
+
+    shutdown_session.await.unwrap();
+}
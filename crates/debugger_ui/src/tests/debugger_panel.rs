@@ -5,6 +5,7 @@ use dap::{
         Continue, Disconnect, Launch, Next, RunInTerminal, SetBreakpoints, StackTrace,
         StartDebugging, StepBack, StepIn, StepOut, Threads,
     },
+    transport::AdapterStartError,
     DebugRequestType, ErrorResponse, RunInTerminalRequestArguments, SourceBreakpoint,
     StartDebuggingRequestArguments, StartDebuggingRequestArgumentsRequest,
 };
@@ -12,23 +13,30 @@ use editor::{
     actions::{self},
     Editor, EditorMode, MultiBuffer,
 };
-use gpui::{BackgroundExecutor, TestAppContext, VisualTestContext};
+use gpui::{BackgroundExecutor, Task, TestAppContext, VisualTestContext};
 use project::{
-    debugger::session::{ThreadId, ThreadStatus},
+    debugger::{
+        dap_store::DapStoreEvent,
+        session::{ThreadId, ThreadStatus},
+    },
     FakeFs, Project,
 };
+use rpc::proto;
 use serde_json::json;
+use settings::SettingsStore;
 use std::{
     path::Path,
+    rc::Rc,
     sync::{
-        atomic::{AtomicBool, Ordering},
+        atomic::{AtomicBool, AtomicUsize, Ordering},
         Arc,
     },
+    time::Duration,
 };
 use terminal_view::{terminal_panel::TerminalPanel, TerminalView};
 use tests::{active_debug_session_panel, init_test, init_test_workspace};
 use util::path;
-use workspace::{dock::Panel, Item};
+use workspace::{dock::Panel, Item, SaveIntent};
 
 #[gpui::test]
 async fn test_basic_show_debug_panel(executor: BackgroundExecutor, cx: &mut TestAppContext) {
@@ -179,6 +187,277 @@ async fn test_basic_show_debug_panel(executor: BackgroundExecutor, cx: &mut Test
         .unwrap();
 }
 
+#[gpui::test]
+async fn test_starting_session_has_session_id(
+    executor: BackgroundExecutor,
+    cx: &mut TestAppContext,
+) {
+    init_test(cx);
+
+    let fs = FakeFs::new(executor.clone());
+
+    fs.insert_tree(
+        "/project",
+        json!({
+            "main.rs": "First line\nSecond line\nThird line\nFourth line",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/project".as_ref()], cx).await;
+    let workspace = init_test_workspace(&project, cx).await;
+    let cx = &mut VisualTestContext::from_window(*workspace, cx);
+
+    let session_id = SessionId(1);
+    let debug_session = workspace
+        .update(cx, |workspace, window, cx| {
+            DebugSession::starting(
+                project.clone(),
+                cx.entity().downgrade(),
+                session_id,
+                Task::ready(Err(anyhow::anyhow!("adapter failed to start"))),
+                None,
+                window,
+                cx,
+            )
+        })
+        .unwrap();
+
+    debug_session.update(cx, |debug_session, cx| {
+        assert!(matches!(
+            debug_session.mode(),
+            session::DebugSessionState::Starting(_)
+        ));
+        assert_eq!(Some(session_id), debug_session.session_id(cx));
+    });
+
+    cx.run_until_parked();
+
+    debug_session.update(cx, |debug_session, cx| {
+        assert!(matches!(
+            debug_session.mode(),
+            session::DebugSessionState::Failed(_)
+        ));
+        assert_eq!(None, debug_session.session_id(cx));
+    });
+}
+
+#[gpui::test]
+async fn test_failed_session_shows_adapter_stderr_once(
+    executor: BackgroundExecutor,
+    cx: &mut TestAppContext,
+) {
+    init_test(cx);
+
+    let fs = FakeFs::new(executor.clone());
+
+    fs.insert_tree(
+        "/project",
+        json!({
+            "main.rs": "First line\nSecond line\nThird line\nFourth line",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/project".as_ref()], cx).await;
+    let workspace = init_test_workspace(&project, cx).await;
+    let cx = &mut VisualTestContext::from_window(*workspace, cx);
+
+    let error = AdapterStartError::attach(
+        anyhow::anyhow!("failed to spawn command."),
+        "adapter: missing runtime\n".into(),
+    )
+    .context("couldn't start the debugger");
+    let debug_session = workspace
+        .update(cx, |workspace, window, cx| {
+            DebugSession::starting(
+                project.clone(),
+                cx.entity().downgrade(),
+                SessionId(1),
+                Task::ready(Err(error)),
+                None,
+                window,
+                cx,
+            )
+        })
+        .unwrap();
+
+    cx.run_until_parked();
+
+    debug_session.update(cx, |debug_session, cx| {
+        let session::DebugSessionState::Failed(failed) = debug_session.mode() else {
+            panic!("session should have failed to start");
+        };
+        let failed = failed.read(cx);
+        assert_eq!(
+            "couldn't start the debugger: failed to spawn command.",
+            failed.message().as_ref()
+        );
+        assert_eq!(
+            Some("adapter: missing runtime"),
+            failed.stderr().map(|stderr| stderr.as_ref())
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_starting_session_retries_transient_failures(
+    executor: BackgroundExecutor,
+    cx: &mut TestAppContext,
+) {
+    init_test(cx);
+
+    let fs = FakeFs::new(executor.clone());
+
+    fs.insert_tree(
+        "/project",
+        json!({
+            "main.rs": "First line\nSecond line\nThird line\nFourth line",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/project".as_ref()], cx).await;
+    let workspace = init_test_workspace(&project, cx).await;
+    let cx = &mut VisualTestContext::from_window(*workspace, cx);
+
+    let worktree = project.update(cx, |project, cx| project.worktrees(cx).next().unwrap());
+    let session_id = SessionId(100);
+    let relaunches = Arc::new(AtomicUsize::new(0));
+    let relaunch: session::Relaunch = Rc::new({
+        let project = project.clone();
+        let relaunches = relaunches.clone();
+        move |cx| {
+            relaunches.fetch_add(1, Ordering::SeqCst);
+            project.update(cx, |project, cx| {
+                project.dap_store().update(cx, |dap_store, cx| {
+                    dap_store.start_session(
+                        session_id,
+                        dap::test_config(DebugRequestType::Launch, None, None),
+                        &worktree,
+                        None,
+                        cx,
+                    )
+                })
+            })
+        }
+    });
+
+    // The adapter's port is still taken the first time around.
+    let debug_session = workspace
+        .update(cx, |workspace, window, cx| {
+            DebugSession::starting(
+                project.clone(),
+                cx.entity().downgrade(),
+                session_id,
+                Task::ready(Err(
+                    std::io::Error::from(std::io::ErrorKind::AddrInUse).into()
+                )),
+                Some(relaunch),
+                window,
+                cx,
+            )
+        })
+        .unwrap();
+
+    cx.run_until_parked();
+    debug_session.update(cx, |debug_session, cx| {
+        assert!(matches!(
+            debug_session.mode(),
+            session::DebugSessionState::Starting(_)
+        ));
+        assert_eq!(Some(session_id), debug_session.session_id(cx));
+    });
+
+    executor.advance_clock(Duration::from_secs(1));
+    cx.run_until_parked();
+
+    assert_eq!(1, relaunches.load(Ordering::SeqCst));
+    debug_session.update(cx, |debug_session, cx| {
+        assert!(debug_session.mode().as_running().is_some());
+        assert_eq!(Some(session_id), debug_session.session_id(cx));
+    });
+
+    let shutdown_session = project.update(cx, |project, cx| {
+        project.dap_store().update(cx, |dap_store, cx| {
+            dap_store.shutdown_session(session_id, cx)
+        })
+    });
+
+    shutdown_session.await.unwrap();
+}
+
+#[gpui::test]
+async fn test_starting_session_notifies_once_retries_run_out(
+    executor: BackgroundExecutor,
+    cx: &mut TestAppContext,
+) {
+    init_test(cx);
+
+    let fs = FakeFs::new(executor.clone());
+
+    fs.insert_tree(
+        "/project",
+        json!({
+            "main.rs": "First line\nSecond line\nThird line\nFourth line",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/project".as_ref()], cx).await;
+    let workspace = init_test_workspace(&project, cx).await;
+    let cx = &mut VisualTestContext::from_window(*workspace, cx);
+
+    let notifications = Arc::new(AtomicUsize::new(0));
+    let _subscription = project.update(cx, |project, cx| {
+        let notifications = notifications.clone();
+        cx.subscribe(project.dap_store(), move |_, _, event, _| {
+            if let DapStoreEvent::Notification(_) = event {
+                notifications.fetch_add(1, Ordering::SeqCst);
+            }
+        })
+    });
+
+    // The adapter's port stays taken.
+    let relaunches = Arc::new(AtomicUsize::new(0));
+    let relaunch: session::Relaunch = Rc::new({
+        let relaunches = relaunches.clone();
+        move |_| {
+            relaunches.fetch_add(1, Ordering::SeqCst);
+            Task::ready(Err(
+                std::io::Error::from(std::io::ErrorKind::AddrInUse).into()
+            ))
+        }
+    });
+    let debug_session = workspace
+        .update(cx, |workspace, window, cx| {
+            DebugSession::starting(
+                project.clone(),
+                cx.entity().downgrade(),
+                SessionId(100),
+                Task::ready(Err(
+                    std::io::Error::from(std::io::ErrorKind::AddrInUse).into()
+                )),
+                Some(relaunch),
+                window,
+                cx,
+            )
+        })
+        .unwrap();
+
+    executor.advance_clock(Duration::from_secs(10));
+    cx.run_until_parked();
+
+    assert_eq!(3, relaunches.load(Ordering::SeqCst));
+    debug_session.update(cx, |debug_session, _| {
+        assert!(matches!(
+            debug_session.mode(),
+            session::DebugSessionState::Failed(_)
+        ));
+    });
+    assert_eq!(1, notifications.load(Ordering::SeqCst));
+}
+
 #[gpui::test]
 async fn test_we_can_only_have_one_panel_per_debug_session(
     executor: BackgroundExecutor,
@@ -1082,3 +1361,1392 @@ async fn test_debug_session_is_shutdown_when_attach_and_launch_request_fails(
         );
     });
 }
+
+#[gpui::test]
+async fn test_restart_relaunches_session_when_adapter_cannot_restart(
+    executor: BackgroundExecutor,
+    cx: &mut TestAppContext,
+) {
+    init_test(cx);
+
+    let fs = FakeFs::new(executor.clone());
+
+    fs.insert_tree(
+        "/project",
+        json!({
+            "main.rs": "First line\nSecond line\nThird line\nFourth line",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/project".as_ref()], cx).await;
+    let workspace = init_test_workspace(&project, cx).await;
+    let cx = &mut VisualTestContext::from_window(*workspace, cx);
+
+    let task = project.update(cx, |project, cx| {
+        project.start_debug_session(dap::test_config(DebugRequestType::Launch, None, None), cx)
+    });
+
+    let session = task.await.unwrap();
+    let session_id = session.read_with(cx, |session, _| session.session_id());
+    let client = session.update(cx, |session, _| session.adapter_client().unwrap());
+
+    client
+        .on_request::<Threads, _>(move |_, _| {
+            Ok(dap::ThreadsResponse {
+                threads: Vec::default(),
+            })
+        })
+        .await;
+
+    cx.run_until_parked();
+
+    let running_state = active_debug_session_panel(workspace, cx).update(cx, |item, _| {
+        item.mode()
+            .as_running()
+            .expect("Session should be running by this point")
+            .clone()
+    });
+
+    running_state.update(cx, |running_state, cx| {
+        assert!(
+            running_state.can_restart(cx),
+            "Local sessions can be relaunched even without restart support"
+        );
+        running_state.restart_session(cx);
+    });
+
+    cx.run_until_parked();
+
+    project.update(cx, |project, cx| {
+        assert!(
+            project
+                .dap_store()
+                .read(cx)
+                .sessions()
+                .any(|session| session.read(cx).session_id() != session_id),
+            "Restarting should have launched a new session"
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_stopped_thread_is_not_selected_when_disabled(
+    executor: BackgroundExecutor,
+    cx: &mut TestAppContext,
+) {
+    init_test(cx);
+    cx.update(|cx| {
+        SettingsStore::update_global(cx, |store, cx| {
+            store.update_user_settings::<DebuggerSettings>(cx, |settings| {
+                settings.select_stopped_thread = false;
+            });
+        });
+    });
+
+    let fs = FakeFs::new(executor.clone());
+
+    fs.insert_tree(
+        "/project",
+        json!({
+            "main.rs": "First line\nSecond line\nThird line\nFourth line",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/project".as_ref()], cx).await;
+    let workspace = init_test_workspace(&project, cx).await;
+    let cx = &mut VisualTestContext::from_window(*workspace, cx);
+
+    let task = project.update(cx, |project, cx| {
+        project.start_debug_session(dap::test_config(DebugRequestType::Launch, None, None), cx)
+    });
+
+    let session = task.await.unwrap();
+    let client = session.update(cx, |session, _| session.adapter_client().unwrap());
+
+    client
+        .on_request::<Threads, _>(move |_, _| {
+            Ok(dap::ThreadsResponse {
+                threads: vec![
+                    dap::Thread {
+                        id: 1,
+                        name: "Thread 1".into(),
+                    },
+                    dap::Thread {
+                        id: 2,
+                        name: "Thread 2".into(),
+                    },
+                ],
+            })
+        })
+        .await;
+
+    client
+        .on_request::<StackTrace, _>(move |_, _| {
+            Ok(dap::StackTraceResponse {
+                stack_frames: Vec::default(),
+                total_frames: None,
+            })
+        })
+        .await;
+
+    for thread_id in [1, 2] {
+        client
+            .fake_event(dap::messages::Events::Stopped(dap::StoppedEvent {
+                reason: dap::StoppedEventReason::Breakpoint,
+                description: None,
+                thread_id: Some(thread_id),
+                preserve_focus_hint: None,
+                text: None,
+                all_threads_stopped: None,
+                hit_breakpoint_ids: None,
+            }))
+            .await;
+
+        cx.run_until_parked();
+    }
+
+    let running_state = active_debug_session_panel(workspace, cx).update(cx, |item, _| {
+        item.mode()
+            .as_running()
+            .expect("Session should be running by this point")
+            .clone()
+    });
+
+    // The first stopped thread is selected because nothing was selected yet, but the second one
+    // doesn't take over.
+    running_state.update(cx, |running_state, _| {
+        assert_eq!(Some(ThreadId(1)), running_state.selected_thread_id());
+    });
+
+    let shutdown_session = project.update(cx, |project, cx| {
+        project.dap_store().update(cx, |dap_store, cx| {
+            dap_store.shutdown_session(session.read(cx).session_id(), cx)
+        })
+    });
+
+    shutdown_session.await.unwrap();
+}
+
+#[gpui::test]
+async fn test_stopped_frame_is_not_opened_when_selecting_stopped_thread_is_disabled(
+    executor: BackgroundExecutor,
+    cx: &mut TestAppContext,
+) {
+    init_test(cx);
+    cx.update(|cx| {
+        SettingsStore::update_global(cx, |store, cx| {
+            store.update_user_settings::<DebuggerSettings>(cx, |settings| {
+                settings.select_stopped_thread = false;
+            });
+        });
+    });
+
+    let fs = FakeFs::new(executor.clone());
+
+    fs.insert_tree(
+        path!("/project"),
+        json!({
+            "main.rs": "First line\nSecond line\nThird line\nFourth line",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, [path!("/project").as_ref()], cx).await;
+    let workspace = init_test_workspace(&project, cx).await;
+    // The panel is already open, so the stack frame list is shown when the thread stops.
+    let _ = workspace.update(cx, |workspace, window, cx| {
+        workspace.toggle_dock(workspace::dock::DockPosition::Bottom, window, cx);
+    });
+    let cx = &mut VisualTestContext::from_window(*workspace, cx);
+
+    let task = project.update(cx, |project, cx| {
+        project.start_debug_session(dap::test_config(DebugRequestType::Launch, None, None), cx)
+    });
+
+    let session = task.await.unwrap();
+    let client = session.update(cx, |session, _| session.adapter_client().unwrap());
+
+    client
+        .on_request::<Threads, _>(move |_, _| {
+            Ok(dap::ThreadsResponse {
+                threads: vec![dap::Thread {
+                    id: 1,
+                    name: "Thread 1".into(),
+                }],
+            })
+        })
+        .await;
+
+    client
+        .on_request::<StackTrace, _>(move |_, _| {
+            Ok(dap::StackTraceResponse {
+                stack_frames: vec![dap::StackFrame {
+                    id: 1,
+                    name: "main".into(),
+                    source: Some(dap::Source {
+                        name: Some("main.rs".into()),
+                        path: Some(path!("/project/main.rs").into()),
+                        source_reference: None,
+                        presentation_hint: None,
+                        origin: None,
+                        sources: None,
+                        adapter_data: None,
+                        checksums: None,
+                    }),
+                    line: 2,
+                    column: 1,
+                    end_line: None,
+                    end_column: None,
+                    can_restart: None,
+                    instruction_pointer_reference: None,
+                    module_id: None,
+                    presentation_hint: None,
+                }],
+                total_frames: None,
+            })
+        })
+        .await;
+
+    client
+        .fake_event(dap::messages::Events::Stopped(dap::StoppedEvent {
+            reason: dap::StoppedEventReason::Breakpoint,
+            description: None,
+            thread_id: Some(1),
+            preserve_focus_hint: None,
+            text: None,
+            all_threads_stopped: None,
+            hit_breakpoint_ids: None,
+        }))
+        .await;
+
+    cx.run_until_parked();
+
+    let running_state = active_debug_session_panel(workspace, cx).update(cx, |item, _| {
+        item.mode()
+            .as_running()
+            .expect("Session should be running by this point")
+            .clone()
+    });
+
+    // The stopped frame is selected, but not opened in an editor.
+    running_state.update(cx, |running_state, cx| {
+        assert_eq!(Some(ThreadId(1)), running_state.selected_thread_id());
+        assert_eq!(
+            Some(1),
+            running_state
+                .stack_frame_list()
+                .read(cx)
+                .current_stack_frame_id()
+        );
+    });
+    workspace
+        .update(cx, |workspace, _window, cx| {
+            assert_eq!(0, workspace.items_of_type::<Editor>(cx).count());
+        })
+        .unwrap();
+
+    let shutdown_session = project.update(cx, |project, cx| {
+        project.dap_store().update(cx, |dap_store, cx| {
+            dap_store.shutdown_session(session.read(cx).session_id(), cx)
+        })
+    });
+
+    shutdown_session.await.unwrap();
+}
+
+#[gpui::test]
+async fn test_stop_on_entry_selects_the_stopped_thread_and_frame(
+    executor: BackgroundExecutor,
+    cx: &mut TestAppContext,
+) {
+    init_test(cx);
+
+    let fs = FakeFs::new(executor.clone());
+
+    fs.insert_tree(
+        "/project",
+        json!({
+            "main.rs": "First line\nSecond line\nThird line\nFourth line",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/project".as_ref()], cx).await;
+    let workspace = init_test_workspace(&project, cx).await;
+    let cx = &mut VisualTestContext::from_window(*workspace, cx);
+
+    let task = project.update(cx, |project, cx| {
+        project.start_debug_session(
+            dap::DebugAdapterConfig {
+                stop_on_entry: true,
+                ..dap::test_config(DebugRequestType::Launch, None, None)
+            },
+            cx,
+        )
+    });
+
+    let session = task.await.unwrap();
+    let client = session.update(cx, |session, _| session.adapter_client().unwrap());
+
+    client
+        .on_request::<Threads, _>(move |_, _| {
+            Ok(dap::ThreadsResponse {
+                threads: vec![dap::Thread {
+                    id: 1,
+                    name: "Thread 1".into(),
+                }],
+            })
+        })
+        .await;
+
+    client
+        .on_request::<StackTrace, _>(move |_, _| {
+            Ok(dap::StackTraceResponse {
+                stack_frames: vec![dap::StackFrame {
+                    id: 1,
+                    name: "main".into(),
+                    source: None,
+                    line: 1,
+                    column: 1,
+                    end_line: None,
+                    end_column: None,
+                    can_restart: None,
+                    instruction_pointer_reference: None,
+                    module_id: None,
+                    presentation_hint: None,
+                }],
+                total_frames: None,
+            })
+        })
+        .await;
+
+    client
+        .fake_event(dap::messages::Events::Stopped(dap::StoppedEvent {
+            reason: dap::StoppedEventReason::Entry,
+            description: None,
+            thread_id: Some(1),
+            preserve_focus_hint: None,
+            text: None,
+            all_threads_stopped: None,
+            hit_breakpoint_ids: None,
+        }))
+        .await;
+
+    cx.run_until_parked();
+
+    let running_state = active_debug_session_panel(workspace, cx).update(cx, |item, _| {
+        item.mode()
+            .as_running()
+            .expect("Session should be running by this point")
+            .clone()
+    });
+
+    // Stopping at the entry point is handled like any other stop.
+    running_state.update(cx, |running_state, cx| {
+        assert_eq!(Some(ThreadId(1)), running_state.selected_thread_id());
+        assert_eq!(
+            Some(1),
+            running_state
+                .stack_frame_list()
+                .read(cx)
+                .current_stack_frame_id()
+        );
+    });
+
+    let shutdown_session = project.update(cx, |project, cx| {
+        project.dap_store().update(cx, |dap_store, cx| {
+            dap_store.shutdown_session(session.read(cx).session_id(), cx)
+        })
+    });
+
+    shutdown_session.await.unwrap();
+}
+
+#[gpui::test]
+async fn test_thread_items_are_gated_by_adapter_capabilities(
+    executor: BackgroundExecutor,
+    cx: &mut TestAppContext,
+) {
+    init_test(cx);
+
+    let fs = FakeFs::new(executor.clone());
+
+    let project = Project::test(fs, ["/project".as_ref()], cx).await;
+    let workspace = init_test_workspace(&project, cx).await;
+    let cx = &mut VisualTestContext::from_window(*workspace, cx);
+
+    let task = project.update(cx, |project, cx| {
+        project.start_debug_session(
+            dap::test_config(
+                DebugRequestType::Launch,
+                None,
+                Some(dap::Capabilities {
+                    supports_modules_request: Some(true),
+                    ..Default::default()
+                }),
+            ),
+            cx,
+        )
+    });
+
+    let session = task.await.unwrap();
+    let client = session.update(cx, |session, _| session.adapter_client().unwrap());
+
+    client
+        .on_request::<Threads, _>(move |_, _| {
+            Ok(dap::ThreadsResponse {
+                threads: vec![dap::Thread {
+                    id: 1,
+                    name: "Thread 1".into(),
+                }],
+            })
+        })
+        .await;
+
+    cx.run_until_parked();
+
+    active_debug_session_panel(workspace, cx).update(cx, |item, cx| {
+        let running_state = item.mode().as_running().unwrap().read(cx);
+
+        assert!(running_state.supports_thread_item(session::ThreadItem::Modules, cx));
+        assert!(!running_state.supports_thread_item(session::ThreadItem::LoadedSource, cx));
+        assert!(running_state.supports_thread_item(session::ThreadItem::Variables, cx));
+        assert!(running_state.supports_thread_item(session::ThreadItem::Console, cx));
+    });
+
+    let shutdown_session = project.update(cx, |project, cx| {
+        project.dap_store().update(cx, |dap_store, cx| {
+            dap_store.shutdown_session(session.read(cx).session_id(), cx)
+        })
+    });
+
+    shutdown_session.await.unwrap();
+}
+
+#[gpui::test]
+async fn test_closing_running_session_asks_for_confirmation(
+    executor: BackgroundExecutor,
+    cx: &mut TestAppContext,
+) {
+    init_test(cx);
+
+    let fs = FakeFs::new(executor.clone());
+
+    let project = Project::test(fs, ["/project".as_ref()], cx).await;
+    let workspace = init_test_workspace(&project, cx).await;
+    let cx = &mut VisualTestContext::from_window(*workspace, cx);
+
+    let task = project.update(cx, |project, cx| {
+        project.start_debug_session(dap::test_config(DebugRequestType::Launch, None, None), cx)
+    });
+
+    let session = task.await.unwrap();
+    let client = session.update(cx, |session, _| session.adapter_client().unwrap());
+
+    client
+        .on_request::<Threads, _>(move |_, _| {
+            Ok(dap::ThreadsResponse {
+                threads: vec![dap::Thread {
+                    id: 1,
+                    name: "Thread 1".into(),
+                }],
+            })
+        })
+        .await;
+
+    cx.run_until_parked();
+
+    let debug_session = active_debug_session_panel(workspace, cx);
+    let pane = workspace
+        .update(cx, |workspace, _window, cx| {
+            let debug_panel = workspace.panel::<DebugPanel>(cx).unwrap();
+            debug_panel.read(cx).pane().unwrap()
+        })
+        .unwrap();
+
+    let close = pane.update_in(cx, |pane, window, cx| {
+        pane.close_item_by_id(debug_session.entity_id(), SaveIntent::Close, window, cx)
+    });
+    cx.run_until_parked();
+    assert!(cx.has_pending_prompt());
+    cx.simulate_prompt_answer("Cancel");
+    close.await.unwrap();
+
+    // we still have the running session and the inert item
+    pane.update(cx, |pane, _| assert_eq!(2, pane.items_len()));
+
+    cx.update(|_, cx| {
+        SettingsStore::update_global(cx, |store, cx| {
+            store.update_user_settings::<DebuggerSettings>(cx, |settings| {
+                settings.confirm_close_running_session = false;
+            });
+        });
+    });
+
+    debug_session.update(cx, |debug_session, cx| {
+        assert!(!debug_session.should_confirm_close(cx));
+    });
+
+    let shutdown_session = project.update(cx, |project, cx| {
+        project.dap_store().update(cx, |dap_store, cx| {
+            dap_store.shutdown_session(session.read(cx).session_id(), cx)
+        })
+    });
+
+    shutdown_session.await.unwrap();
+}
+
+#[gpui::test]
+async fn test_thread_item_is_kept_when_switching_sessions(
+    executor: BackgroundExecutor,
+    cx: &mut TestAppContext,
+) {
+    init_test(cx);
+
+    let fs = FakeFs::new(executor.clone());
+
+    let project = Project::test(fs, ["/project".as_ref()], cx).await;
+    let workspace = init_test_workspace(&project, cx).await;
+    let cx = &mut VisualTestContext::from_window(*workspace, cx);
+
+    let task = project.update(cx, |project, cx| {
+        project.start_debug_session(dap::test_config(DebugRequestType::Launch, None, None), cx)
+    });
+
+    let session = task.await.unwrap();
+    let client = session.update(cx, |session, _| session.adapter_client().unwrap());
+
+    client
+        .on_request::<Threads, _>(move |_, _| {
+            Ok(dap::ThreadsResponse {
+                threads: vec![dap::Thread {
+                    id: 1,
+                    name: "Thread 1".into(),
+                }],
+            })
+        })
+        .await;
+
+    cx.run_until_parked();
+
+    let debug_session = active_debug_session_panel(workspace, cx);
+    let running_state = debug_session.update(cx, |debug_session, _| {
+        debug_session.mode().as_running().unwrap().clone()
+    });
+    running_state.update(cx, |running_state, cx| {
+        running_state.set_thread_item(session::ThreadItem::Console, cx);
+    });
+
+    let pane = workspace
+        .update(cx, |workspace, _window, cx| {
+            let debug_panel = workspace.panel::<DebugPanel>(cx).unwrap();
+            debug_panel.read(cx).pane().unwrap()
+        })
+        .unwrap();
+    let session_ix = pane.update(cx, |pane, _| pane.active_item_index());
+    pane.update_in(cx, |pane, window, cx| {
+        let other_ix = if session_ix == 0 { 1 } else { 0 };
+        pane.activate_item(other_ix, true, true, window, cx);
+        pane.activate_item(session_ix, true, true, window, cx);
+    });
+
+    cx.run_until_parked();
+
+    assert_eq!(
+        session::ThreadItem::Console,
+        active_debug_session_panel(workspace, cx).update(cx, |debug_session, cx| {
+            debug_session
+                .mode()
+                .as_running()
+                .unwrap()
+                .read(cx)
+                .active_thread_item()
+        })
+    );
+
+    let shutdown_session = project.update(cx, |project, cx| {
+        project.dap_store().update(cx, |dap_store, cx| {
+            dap_store.shutdown_session(session.read(cx).session_id(), cx)
+        })
+    });
+
+    shutdown_session.await.unwrap();
+}
+
+#[gpui::test]
+async fn test_other_sessions_do_not_take_over_a_stopped_session(
+    executor: BackgroundExecutor,
+    cx: &mut TestAppContext,
+) {
+    init_test(cx);
+
+    let fs = FakeFs::new(executor.clone());
+
+    let project = Project::test(fs, ["/project".as_ref()], cx).await;
+    let workspace = init_test_workspace(&project, cx).await;
+    let cx = &mut VisualTestContext::from_window(*workspace, cx);
+
+    let mut sessions = Vec::new();
+    let mut clients = Vec::new();
+    for _ in 0..2 {
+        let task = project.update(cx, |project, cx| {
+            project.start_debug_session(dap::test_config(DebugRequestType::Launch, None, None), cx)
+        });
+        let session = task.await.unwrap();
+        let client = session.update(cx, |session, _| session.adapter_client().unwrap());
+
+        client
+            .on_request::<Threads, _>(move |_, _| {
+                Ok(dap::ThreadsResponse {
+                    threads: vec![dap::Thread {
+                        id: 1,
+                        name: "Thread 1".into(),
+                    }],
+                })
+            })
+            .await;
+        client
+            .on_request::<StackTrace, _>(move |_, _| {
+                Ok(dap::StackTraceResponse {
+                    stack_frames: Vec::default(),
+                    total_frames: None,
+                })
+            })
+            .await;
+
+        if sessions.is_empty() {
+            cx.run_until_parked();
+            // The user is inspecting the first session when the second one starts.
+            client
+                .fake_event(dap::messages::Events::Stopped(dap::StoppedEvent {
+                    reason: dap::StoppedEventReason::Pause,
+                    description: None,
+                    thread_id: Some(1),
+                    preserve_focus_hint: None,
+                    text: None,
+                    all_threads_stopped: None,
+                    hit_breakpoint_ids: None,
+                }))
+                .await;
+        }
+        cx.run_until_parked();
+
+        sessions.push(session);
+        clients.push(client);
+    }
+
+    let first_session_id = sessions[0].read_with(cx, |session, _| session.session_id());
+    let assert_first_session_is_active = |cx: &mut VisualTestContext| {
+        let active_session = active_debug_session_panel(workspace, cx);
+        assert_eq!(
+            Some(first_session_id),
+            active_session.read_with(cx, |session, cx| session.session_id(cx))
+        );
+    };
+
+    // Both sessions have their own item, next to the inert one.
+    workspace
+        .update(cx, |workspace, _window, cx| {
+            let debug_panel = workspace.panel::<DebugPanel>(cx).unwrap();
+            assert_eq!(3, debug_panel.read(cx).pane().unwrap().read(cx).items_len());
+        })
+        .unwrap();
+    assert_first_session_is_active(cx);
+
+    // The second session stopping doesn't switch away from the first one either.
+    clients[1]
+        .fake_event(dap::messages::Events::Stopped(dap::StoppedEvent {
+            reason: dap::StoppedEventReason::Breakpoint,
+            description: None,
+            thread_id: Some(1),
+            preserve_focus_hint: None,
+            text: None,
+            all_threads_stopped: None,
+            hit_breakpoint_ids: None,
+        }))
+        .await;
+    cx.run_until_parked();
+    assert_first_session_is_active(cx);
+
+    for session in sessions {
+        let shutdown_session = project.update(cx, |project, cx| {
+            project.dap_store().update(cx, |dap_store, cx| {
+                dap_store.shutdown_session(session.read(cx).session_id(), cx)
+            })
+        });
+        shutdown_session.await.unwrap();
+    }
+}
+
+#[test]
+fn test_thread_item_proto_round_trip() {
+    for thread_item in [
+        session::ThreadItem::Breakpoints,
+        session::ThreadItem::Console,
+        session::ThreadItem::Disassembly,
+        session::ThreadItem::ExceptionBreakpoints,
+        session::ThreadItem::LoadedSource,
+        session::ThreadItem::Modules,
+        session::ThreadItem::Variables,
+        session::ThreadItem::Watches,
+    ] {
+        assert_eq!(
+            thread_item,
+            session::ThreadItem::from_proto(thread_item.to_proto())
+        );
+    }
+
+    // Tabs added by newer peers aren't recognized, so the follower's tab stays as it was.
+    assert_eq!(None, proto::DebuggerThreadItem::from_i32(i32::MAX));
+}
+
+#[gpui::test]
+async fn test_steps_use_the_selected_granularity(
+    executor: BackgroundExecutor,
+    cx: &mut TestAppContext,
+) {
+    init_test(cx);
+
+    let fs = FakeFs::new(executor.clone());
+
+    let project = Project::test(fs, ["/project".as_ref()], cx).await;
+    let workspace = init_test_workspace(&project, cx).await;
+    let cx = &mut VisualTestContext::from_window(*workspace, cx);
+
+    let task = project.update(cx, |project, cx| {
+        project.start_debug_session(
+            dap::test_config(
+                DebugRequestType::Launch,
+                None,
+                Some(dap::Capabilities {
+                    supports_stepping_granularity: Some(true),
+                    ..Default::default()
+                }),
+            ),
+            cx,
+        )
+    });
+
+    let session = task.await.unwrap();
+    let client = session.update(cx, |session, _| session.adapter_client().unwrap());
+
+    client
+        .on_request::<Threads, _>(move |_, _| {
+            Ok(dap::ThreadsResponse {
+                threads: vec![dap::Thread {
+                    id: 1,
+                    name: "Thread 1".into(),
+                }],
+            })
+        })
+        .await;
+
+    client
+        .on_request::<StackTrace, _>(move |_, _| {
+            Ok(dap::StackTraceResponse {
+                stack_frames: Vec::default(),
+                total_frames: None,
+            })
+        })
+        .await;
+
+    let requested_granularities = Arc::new(std::sync::Mutex::new(Vec::new()));
+    client
+        .on_request::<Next, _>({
+            let requested_granularities = requested_granularities.clone();
+            move |_, args| {
+                requested_granularities
+                    .lock()
+                    .unwrap()
+                    .push(args.granularity);
+                Ok(())
+            }
+        })
+        .await;
+    client
+        .on_request::<StepIn, _>({
+            let requested_granularities = requested_granularities.clone();
+            move |_, args| {
+                requested_granularities
+                    .lock()
+                    .unwrap()
+                    .push(args.granularity);
+                Ok(())
+            }
+        })
+        .await;
+
+    client
+        .fake_event(dap::messages::Events::Stopped(dap::StoppedEvent {
+            reason: dap::StoppedEventReason::Pause,
+            description: None,
+            thread_id: Some(1),
+            preserve_focus_hint: None,
+            text: None,
+            all_threads_stopped: None,
+            hit_breakpoint_ids: None,
+        }))
+        .await;
+
+    cx.run_until_parked();
+
+    let running_state = active_debug_session_panel(workspace, cx).update(cx, |item, _| {
+        item.mode()
+            .as_running()
+            .expect("Session should be running by this point")
+            .clone()
+    });
+
+    running_state.update(cx, |running_state, cx| {
+        assert_eq!(
+            running_state.stepping_granularity(),
+            dap::SteppingGranularity::Line
+        );
+        running_state.step_over(cx);
+    });
+    cx.run_until_parked();
+
+    client
+        .fake_event(dap::messages::Events::Stopped(dap::StoppedEvent {
+            reason: dap::StoppedEventReason::Step,
+            description: None,
+            thread_id: Some(1),
+            preserve_focus_hint: None,
+            text: None,
+            all_threads_stopped: None,
+            hit_breakpoint_ids: None,
+        }))
+        .await;
+    cx.run_until_parked();
+
+    running_state.update(cx, |running_state, cx| {
+        running_state.set_stepping_granularity(dap::SteppingGranularity::Instruction, cx);
+        running_state.step_in(cx);
+    });
+    cx.run_until_parked();
+
+    assert_eq!(
+        *requested_granularities.lock().unwrap(),
+        vec![
+            Some(dap::SteppingGranularity::Line),
+            Some(dap::SteppingGranularity::Instruction),
+        ]
+    );
+
+    let shutdown_session = project.update(cx, |project, cx| {
+        project.dap_store().update(cx, |dap_store, cx| {
+            dap_store.shutdown_session(session.read(cx).session_id(), cx)
+        })
+    });
+
+    shutdown_session.await.unwrap();
+}
+
+#[gpui::test]
+async fn test_session_exit_is_reported(executor: BackgroundExecutor, cx: &mut TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(executor.clone());
+
+    let project = Project::test(fs, ["/project".as_ref()], cx).await;
+    let workspace = init_test_workspace(&project, cx).await;
+    let cx = &mut VisualTestContext::from_window(*workspace, cx);
+
+    let task = project.update(cx, |project, cx| {
+        project.start_debug_session(dap::test_config(DebugRequestType::Launch, None, None), cx)
+    });
+
+    let session = task.await.unwrap();
+    let client = session.update(cx, |session, _| session.adapter_client().unwrap());
+
+    client
+        .on_request::<Threads, _>(move |_, _| {
+            Ok(dap::ThreadsResponse {
+                threads: vec![dap::Thread {
+                    id: 1,
+                    name: "Thread 1".into(),
+                }],
+            })
+        })
+        .await;
+
+    client.on_request::<Disconnect, _>(move |_, _| Ok(())).await;
+
+    cx.run_until_parked();
+
+    let running_state = active_debug_session_panel(workspace, cx).update(cx, |item, _| {
+        item.mode()
+            .as_running()
+            .expect("Session should be running by this point")
+            .clone()
+    });
+    running_state.update(cx, |running_state, _| {
+        assert_eq!(running_state.exit(), None);
+    });
+
+    client
+        .fake_event(dap::messages::Events::Exited(dap::ExitedEvent {
+            exit_code: 3,
+        }))
+        .await;
+    cx.run_until_parked();
+
+    running_state.update(cx, |running_state, _| {
+        let exit = running_state.exit().unwrap();
+        assert_eq!(exit, session::running::SessionExit::Exited { exit_code: 3 });
+        assert_eq!(exit.message().as_ref(), "Process exited with code 3");
+        assert!(exit.is_error());
+    });
+
+    // Exits after the user disconnected aren't reported as failures.
+    running_state.update(cx, |running_state, cx| running_state.disconnect_client(cx));
+    client
+        .fake_event(dap::messages::Events::Exited(dap::ExitedEvent {
+            exit_code: 1,
+        }))
+        .await;
+    cx.run_until_parked();
+
+    running_state.update(cx, |running_state, _| {
+        assert_eq!(
+            running_state.exit(),
+            Some(session::running::SessionExit::TerminatedByUser)
+        );
+    });
+
+    let shutdown_session = project.update(cx, |project, cx| {
+        project.dap_store().update(cx, |dap_store, cx| {
+            dap_store.shutdown_session(session.read(cx).session_id(), cx)
+        })
+    });
+
+    shutdown_session.await.unwrap();
+}
+
+#[gpui::test]
+async fn test_session_timers(executor: BackgroundExecutor, cx: &mut TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(executor.clone());
+
+    let project = Project::test(fs, ["/project".as_ref()], cx).await;
+    let workspace = init_test_workspace(&project, cx).await;
+    let cx = &mut VisualTestContext::from_window(*workspace, cx);
+
+    let task = project.update(cx, |project, cx| {
+        project.start_debug_session(dap::test_config(DebugRequestType::Launch, None, None), cx)
+    });
+
+    let session = task.await.unwrap();
+    let client = session.update(cx, |session, _| session.adapter_client().unwrap());
+
+    client
+        .on_request::<Threads, _>(move |_, _| {
+            Ok(dap::ThreadsResponse {
+                threads: vec![dap::Thread {
+                    id: 1,
+                    name: "Thread 1".into(),
+                }],
+            })
+        })
+        .await;
+
+    client
+        .on_request::<StackTrace, _>(move |_, _| {
+            Ok(dap::StackTraceResponse {
+                stack_frames: Vec::default(),
+                total_frames: None,
+            })
+        })
+        .await;
+
+    client
+        .on_request::<Continue, _>(move |_, _| {
+            Ok(dap::ContinueResponse {
+                all_threads_continued: Some(true),
+            })
+        })
+        .await;
+
+    client.on_request::<Next, _>(move |_, _| Ok(())).await;
+
+    cx.run_until_parked();
+
+    let running_state = active_debug_session_panel(workspace, cx).update(cx, |item, _| {
+        item.mode()
+            .as_running()
+            .expect("Session should be running by this point")
+            .clone()
+    });
+    running_state.update(cx, |running_state, _| {
+        assert_eq!(running_state.elapsed_since_stop(), None);
+    });
+
+    client
+        .fake_event(dap::messages::Events::Stopped(dap::StoppedEvent {
+            reason: dap::StoppedEventReason::Pause,
+            description: None,
+            thread_id: Some(1),
+            preserve_focus_hint: None,
+            text: None,
+            all_threads_stopped: None,
+            hit_breakpoint_ids: None,
+        }))
+        .await;
+    cx.run_until_parked();
+
+    running_state.update(cx, |running_state, _| {
+        assert!(running_state.elapsed_since_stop().is_some());
+    });
+
+    client
+        .fake_event(dap::messages::Events::Continued(dap::ContinuedEvent {
+            thread_id: 1,
+            all_threads_continued: Some(true),
+        }))
+        .await;
+    cx.run_until_parked();
+
+    running_state.update(cx, |running_state, _| {
+        assert_eq!(running_state.elapsed_since_stop(), None);
+    });
+
+    // Adapters don't send `continued` after a continue or step the client requested.
+    client
+        .fake_event(dap::messages::Events::Stopped(dap::StoppedEvent {
+            reason: dap::StoppedEventReason::Pause,
+            description: None,
+            thread_id: Some(1),
+            preserve_focus_hint: None,
+            text: None,
+            all_threads_stopped: None,
+            hit_breakpoint_ids: None,
+        }))
+        .await;
+    cx.run_until_parked();
+    running_state.update(cx, |running_state, cx| {
+        assert!(running_state.elapsed_since_stop().is_some());
+        running_state.continue_thread(cx);
+        assert_eq!(running_state.elapsed_since_stop(), None);
+    });
+    cx.run_until_parked();
+
+    client
+        .fake_event(dap::messages::Events::Stopped(dap::StoppedEvent {
+            reason: dap::StoppedEventReason::Pause,
+            description: None,
+            thread_id: Some(1),
+            preserve_focus_hint: None,
+            text: None,
+            all_threads_stopped: None,
+            hit_breakpoint_ids: None,
+        }))
+        .await;
+    cx.run_until_parked();
+    running_state.update(cx, |running_state, cx| {
+        assert!(running_state.elapsed_since_stop().is_some());
+        running_state.step_over(cx);
+        assert_eq!(running_state.elapsed_since_stop(), None);
+    });
+    cx.run_until_parked();
+
+    // The timers stop once the process exits.
+    client
+        .fake_event(dap::messages::Events::Exited(dap::ExitedEvent {
+            exit_code: 0,
+        }))
+        .await;
+    cx.run_until_parked();
+
+    let elapsed = running_state.update(cx, |running_state, _| running_state.elapsed_since_launch());
+    std::thread::sleep(Duration::from_millis(10));
+    running_state.update(cx, |running_state, _| {
+        assert_eq!(running_state.elapsed_since_launch(), elapsed);
+    });
+
+    let shutdown_session = project.update(cx, |project, cx| {
+        project.dap_store().update(cx, |dap_store, cx| {
+            dap_store.shutdown_session(session.read(cx).session_id(), cx)
+        })
+    });
+
+    shutdown_session.await.unwrap();
+}
+
+#[gpui::test]
+async fn test_exception_stop_shows_its_reason_and_variables(
+    executor: BackgroundExecutor,
+    cx: &mut TestAppContext,
+) {
+    init_test(cx);
+
+    let fs = FakeFs::new(executor.clone());
+
+    fs.insert_tree(
+        "/project",
+        json!({
+            "main.rs": "First line\nSecond line\nThird line\nFourth line",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/project".as_ref()], cx).await;
+    let workspace = init_test_workspace(&project, cx).await;
+    let cx = &mut VisualTestContext::from_window(*workspace, cx);
+
+    let task = project.update(cx, |project, cx| {
+        project.start_debug_session(dap::test_config(DebugRequestType::Launch, None, None), cx)
+    });
+
+    let session = task.await.unwrap();
+    let client = session.update(cx, |session, _| session.adapter_client().unwrap());
+
+    client
+        .on_request::<Threads, _>(move |_, _| {
+            Ok(dap::ThreadsResponse {
+                threads: vec![dap::Thread {
+                    id: 1,
+                    name: "Thread 1".into(),
+                }],
+            })
+        })
+        .await;
+
+    client
+        .on_request::<StackTrace, _>(move |_, _| {
+            Ok(dap::StackTraceResponse {
+                stack_frames: Vec::default(),
+                total_frames: None,
+            })
+        })
+        .await;
+
+    client
+        .on_request::<Continue, _>(move |_, _| {
+            Ok(dap::ContinueResponse {
+                all_threads_continued: Some(true),
+            })
+        })
+        .await;
+
+    client
+        .fake_event(dap::messages::Events::Stopped(dap::StoppedEvent {
+            reason: dap::StoppedEventReason::Breakpoint,
+            description: None,
+            thread_id: Some(1),
+            preserve_focus_hint: None,
+            text: None,
+            all_threads_stopped: None,
+            hit_breakpoint_ids: None,
+        }))
+        .await;
+
+    cx.run_until_parked();
+
+    let running_state = active_debug_session_panel(workspace, cx).update(cx, |item, _| {
+        item.mode()
+            .as_running()
+            .expect("Session should be running by this point")
+            .clone()
+    });
+
+    running_state.update(cx, |running_state, cx| {
+        assert_eq!(
+            Some(("Paused on breakpoint".into(), None)),
+            running_state.stop_reason(cx)
+        );
+        running_state.set_thread_item(session::ThreadItem::Console, cx);
+    });
+
+    // Adapters don't send `continued` after a continue the client requested.
+    running_state.update(cx, |running_state, cx| {
+        running_state.continue_thread(cx);
+        assert_eq!(None, running_state.stop_reason(cx));
+    });
+
+    cx.run_until_parked();
+
+    running_state.update(cx, |running_state, cx| {
+        assert_eq!(None, running_state.stop_reason(cx));
+    });
+
+    client
+        .fake_event(dap::messages::Events::Stopped(dap::StoppedEvent {
+            reason: dap::StoppedEventReason::Exception,
+            description: Some("Panic".into()),
+            thread_id: Some(1),
+            preserve_focus_hint: None,
+            text: Some("attempt to divide by zero".into()),
+            all_threads_stopped: None,
+            hit_breakpoint_ids: None,
+        }))
+        .await;
+
+    cx.run_until_parked();
+
+    running_state.update(cx, |running_state, cx| {
+        assert_eq!(
+            Some((
+                "Paused on exception".into(),
+                Some("Panic: attempt to divide by zero".into())
+            )),
+            running_state.stop_reason(cx)
+        );
+        assert_eq!(
+            session::ThreadItem::Variables,
+            running_state.active_thread_item(),
+            "Stopping on an exception should show the variables"
+        );
+    });
+
+    let shutdown_session = project.update(cx, |project, cx| {
+        project.dap_store().update(cx, |dap_store, cx| {
+            dap_store.shutdown_session(session.read(cx).session_id(), cx)
+        })
+    });
+
+    shutdown_session.await.unwrap();
+}
+
+#[gpui::test]
+async fn test_debug_sessions_are_restored_inert(
+    executor: BackgroundExecutor,
+    cx: &mut TestAppContext,
+) {
+    use crate::persistence::{SerializedDebugPanel, SerializedDebugSession};
+    use crate::session::{DebugSessionState, ThreadItem};
+
+    init_test(cx);
+
+    let fs = FakeFs::new(executor.clone());
+
+    fs.insert_tree(
+        "/project",
+        json!({
+            "main.rs": "First line\nSecond line\nThird line",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/project".as_ref()], cx).await;
+    let workspace = init_test_workspace(&project, cx).await;
+    let cx = &mut VisualTestContext::from_window(*workspace, cx);
+
+    let config = dap::DebugAdapterConfig {
+        program: Some("/project/main".into()),
+        cwd: Some("/project".into()),
+        ..dap::test_config(DebugRequestType::Launch, None, None)
+    };
+    let task = project.update(cx, |project, cx| {
+        project.start_debug_session(config.clone(), cx)
+    });
+
+    let session = task.await.unwrap();
+    cx.run_until_parked();
+
+    let running_state = active_debug_session_panel(workspace, cx).update(cx, |item, _| {
+        item.mode()
+            .as_running()
+            .expect("Session should be running by this point")
+            .clone()
+    });
+    running_state.update(cx, |state, cx| {
+        state.set_thread_item(ThreadItem::Watches, cx);
+    });
+    let watch_list = running_state.update(cx, |state, _| state.watch_list().clone());
+    cx.update(|window, cx| {
+        watch_list.update(cx, |watch_list, cx| {
+            watch_list
+                .watch_editor()
+                .update(cx, |editor, cx| editor.set_text("counter", window, cx));
+            watch_list.confirm(window, cx);
+        });
+    });
+    cx.run_until_parked();
+
+    let serialized = workspace
+        .update(cx, |workspace, _, cx| {
+            let debug_panel = workspace.panel::<DebugPanel>(cx).unwrap();
+            debug_panel.read(cx).serialized_sessions(cx)
+        })
+        .unwrap();
+
+    // The initial session was never launched, so only the running one is persisted.
+    assert_eq!(
+        SerializedDebugPanel {
+            sessions: vec![SerializedDebugSession {
+                config: config.clone(),
+                thread_item: Some(proto::DebuggerThreadItem::Watches.into()),
+                watches: vec!["counter".to_string()],
+            }],
+            active_session: Some(0),
+        },
+        serialized
+    );
+    assert_eq!(
+        serialized,
+        serde_json::from_str(&serde_json::to_string(&serialized).unwrap()).unwrap()
+    );
+
+    let shutdown_session = project.update(cx, |project, cx| {
+        project.dap_store().update(cx, |dap_store, cx| {
+            dap_store.shutdown_session(session.read(cx).session_id(), cx)
+        })
+    });
+    shutdown_session.await.unwrap();
+    cx.run_until_parked();
+
+    // A workspace reopened with the persisted sessions shows them inert, ready to relaunch.
+    let restored_workspace = init_test_workspace(&project, cx).await;
+    let debug_panel = restored_workspace
+        .update(cx, |workspace, _, cx| {
+            workspace.panel::<DebugPanel>(cx).unwrap()
+        })
+        .unwrap();
+    restored_workspace
+        .update(cx, |_, window, cx| {
+            debug_panel.update(cx, |debug_panel, cx| {
+                debug_panel.restore(serialized, window, cx);
+            });
+        })
+        .unwrap();
+    cx.run_until_parked();
+
+    let restored_session = debug_panel.update(cx, |debug_panel, cx| {
+        assert_eq!(1, debug_panel.pane().unwrap().read(cx).items_len());
+        debug_panel.active_session(cx).unwrap()
+    });
+    let inert = restored_session.update(cx, |restored_session, _| match restored_session.mode() {
+        DebugSessionState::Inert(inert) => inert.clone(),
+        _ => panic!("Restored sessions should be inert"),
+    });
+
+    inert.update(cx, |inert, cx| inert.launch(cx));
+    cx.run_until_parked();
+
+    let running_state = restored_session.update(cx, |restored_session, _| {
+        restored_session
+            .mode()
+            .as_running()
+            .expect("Relaunched session should be running by this point")
+            .clone()
+    });
+    let relaunched_session = running_state.update(cx, |state, cx| {
+        assert_eq!(ThreadItem::Watches, state.active_thread_item());
+        assert_eq!(
+            vec!["counter".to_string()],
+            state.watch_list().read(cx).expressions()
+        );
+        assert_eq!(Some(config), state.session().read(cx).configuration());
+        state.session().clone()
+    });
+
+    let shutdown_session = project.update(cx, |project, cx| {
+        project.dap_store().update(cx, |dap_store, cx| {
+            dap_store.shutdown_session(relaunched_session.read(cx).session_id(), cx)
+        })
+    });
+    shutdown_session.await.unwrap();
+}
@@ -0,0 +1,324 @@
+use crate::{
+    debugger_panel::DebugPanel,
+    session::ThreadItem,
+    tests::{active_debug_session_panel, init_test, init_test_workspace},
+};
+use dap::{
+    requests::{Disassemble, StackTrace, Threads},
+    DebugRequestType, StackFrame, StoppedEvent,
+};
+use gpui::{BackgroundExecutor, TestAppContext, VisualTestContext};
+use project::{FakeFs, Project};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+fn instruction(address: &str, instruction: &str) -> dap::DisassembledInstruction {
+    dap::DisassembledInstruction {
+        address: address.into(),
+        instruction_bytes: None,
+        instruction: instruction.into(),
+        symbol: None,
+        location: None,
+        line: None,
+        column: None,
+        end_line: None,
+        end_column: None,
+        presentation_hint: None,
+    }
+}
+
+#[gpui::test]
+async fn test_disassembly_marks_instruction_pointer(
+    executor: BackgroundExecutor,
+    cx: &mut TestAppContext,
+) {
+    init_test(cx);
+
+    let fs = FakeFs::new(executor.clone());
+
+    let project = Project::test(fs, ["/project".as_ref()], cx).await;
+    let workspace = init_test_workspace(&project, cx).await;
+    workspace
+        .update(cx, |workspace, window, cx| {
+            workspace.focus_panel::<DebugPanel>(window, cx);
+        })
+        .unwrap();
+    let cx = &mut VisualTestContext::from_window(*workspace, cx);
+
+    let task = project.update(cx, |project, cx| {
+        project.start_debug_session(
+            dap::test_config(
+                DebugRequestType::Launch,
+                None,
+                Some(dap::Capabilities {
+                    supports_disassemble_request: Some(true),
+                    ..Default::default()
+                }),
+            ),
+            cx,
+        )
+    });
+
+    let session = task.await.unwrap();
+    let client = session.update(cx, |session, _| session.adapter_client().unwrap());
+
+    client
+        .on_request::<Threads, _>(move |_, _| {
+            Ok(dap::ThreadsResponse {
+                threads: vec![dap::Thread {
+                    id: 1,
+                    name: "Thread 1".into(),
+                }],
+            })
+        })
+        .await;
+
+    client
+        .on_request::<StackTrace, _>(move |_, args| {
+            assert!(args.thread_id == 1);
+            Ok(dap::StackTraceResponse {
+                stack_frames: vec![StackFrame {
+                    id: 1,
+                    name: "main".into(),
+                    source: None,
+                    line: 1,
+                    column: 1,
+                    end_line: None,
+                    end_column: None,
+                    can_restart: None,
+                    instruction_pointer_reference: Some("0x1004".into()),
+                    module_id: None,
+                    presentation_hint: None,
+                }],
+                total_frames: None,
+            })
+        })
+        .await;
+
+    let disassemble_count = Arc::new(AtomicUsize::new(0));
+    client
+        .on_request::<Disassemble, _>({
+            let disassemble_count = disassemble_count.clone();
+            move |_, args| {
+                assert_eq!("0x1004", args.memory_reference);
+                assert_eq!(Some(-20), args.instruction_offset);
+                disassemble_count.fetch_add(1, Ordering::SeqCst);
+
+                Ok(dap::DisassembleResponse {
+                    instructions: vec![
+                        instruction("0x0000000000001000", "push rbp"),
+                        instruction("0x0000000000001004", "mov rbp, rsp"),
+                        instruction("0x0000000000001008", "ret"),
+                    ],
+                })
+            }
+        })
+        .await;
+
+    let stopped_event = dap::messages::Events::Stopped(StoppedEvent {
+        reason: dap::StoppedEventReason::Pause,
+        description: None,
+        thread_id: Some(1),
+        preserve_focus_hint: None,
+        text: None,
+        all_threads_stopped: None,
+        hit_breakpoint_ids: None,
+    });
+
+    client.fake_event(stopped_event.clone()).await;
+
+    cx.run_until_parked();
+
+    let running_state =
+        active_debug_session_panel(workspace, cx).update_in(cx, |item, window, cx| {
+            cx.focus_self(window);
+            item.mode()
+                .as_running()
+                .expect("Session should be running by this point")
+                .clone()
+        });
+
+    running_state.update(cx, |state, cx| {
+        assert!(state.supports_thread_item(ThreadItem::Disassembly, cx));
+        state.set_thread_item(ThreadItem::Disassembly, cx);
+        cx.refresh_windows();
+    });
+
+    cx.run_until_parked();
+
+    running_state.update(cx, |state, cx| {
+        assert_eq!(
+            vec![
+                "  0x0000000000001000 push rbp",
+                "> 0x0000000000001004 mov rbp, rsp",
+                "  0x0000000000001008 ret",
+            ],
+            state.disassembly_view().read(cx).visible_instructions()
+        );
+    });
+    let disassemble_count_after_first_stop = disassemble_count.load(Ordering::SeqCst);
+
+    // Every stop disassembles around the new instruction pointer.
+    client.fake_event(stopped_event).await;
+
+    cx.run_until_parked();
+
+    assert!(disassemble_count.load(Ordering::SeqCst) > disassemble_count_after_first_stop);
+
+    let shutdown_session = project.update(cx, |project, cx| {
+        project.dap_store().update(cx, |dap_store, cx| {
+            dap_store.shutdown_session(session.read(cx).session_id(), cx)
+        })
+    });
+
+    shutdown_session.await.unwrap();
+}
+
+#[gpui::test]
+async fn test_go_to_address_selects_instruction(
+    executor: BackgroundExecutor,
+    cx: &mut TestAppContext,
+) {
+    init_test(cx);
+
+    let fs = FakeFs::new(executor.clone());
+
+    let project = Project::test(fs, ["/project".as_ref()], cx).await;
+    let workspace = init_test_workspace(&project, cx).await;
+    workspace
+        .update(cx, |workspace, window, cx| {
+            workspace.focus_panel::<DebugPanel>(window, cx);
+        })
+        .unwrap();
+    let cx = &mut VisualTestContext::from_window(*workspace, cx);
+
+    let task = project.update(cx, |project, cx| {
+        project.start_debug_session(
+            dap::test_config(
+                DebugRequestType::Launch,
+                None,
+                Some(dap::Capabilities {
+                    supports_disassemble_request: Some(true),
+                    ..Default::default()
+                }),
+            ),
+            cx,
+        )
+    });
+
+    let session = task.await.unwrap();
+    let client = session.update(cx, |session, _| session.adapter_client().unwrap());
+
+    client
+        .on_request::<Threads, _>(move |_, _| {
+            Ok(dap::ThreadsResponse {
+                threads: vec![dap::Thread {
+                    id: 1,
+                    name: "Thread 1".into(),
+                }],
+            })
+        })
+        .await;
+
+    client
+        .on_request::<StackTrace, _>(move |_, _| {
+            Ok(dap::StackTraceResponse {
+                stack_frames: vec![StackFrame {
+                    id: 1,
+                    name: "main".into(),
+                    source: None,
+                    line: 1,
+                    column: 1,
+                    end_line: None,
+                    end_column: None,
+                    can_restart: None,
+                    instruction_pointer_reference: Some("0x1004".into()),
+                    module_id: None,
+                    presentation_hint: None,
+                }],
+                total_frames: None,
+            })
+        })
+        .await;
+
+    client
+        .on_request::<Disassemble, _>(move |_, _| {
+            Ok(dap::DisassembleResponse {
+                instructions: vec![
+                    instruction("0x0000000000001000", "push rbp"),
+                    instruction("0x0000000000001004", "mov rbp, rsp"),
+                    instruction("0x0000000000001008", "ret"),
+                ],
+            })
+        })
+        .await;
+
+    client
+        .fake_event(dap::messages::Events::Stopped(StoppedEvent {
+            reason: dap::StoppedEventReason::Pause,
+            description: None,
+            thread_id: Some(1),
+            preserve_focus_hint: None,
+            text: None,
+            all_threads_stopped: None,
+            hit_breakpoint_ids: None,
+        }))
+        .await;
+
+    cx.run_until_parked();
+
+    let running_state =
+        active_debug_session_panel(workspace, cx).update_in(cx, |item, window, cx| {
+            cx.focus_self(window);
+            item.mode()
+                .as_running()
+                .expect("Session should be running by this point")
+                .clone()
+        });
+
+    // An address within an instruction goes to that instruction and shows the disassembly.
+    running_state.update_in(cx, |state, window, cx| {
+        state.go_to("0x1001", window, cx);
+        assert_eq!(None, state.go_to_error());
+        assert_eq!(ThreadItem::Disassembly, state.active_thread_item());
+        assert_eq!(
+            vec![
+                "* 0x0000000000001000 push rbp",
+                "> 0x0000000000001004 mov rbp, rsp",
+                "  0x0000000000001008 ret",
+            ],
+            state.disassembly_view().read(cx).visible_instructions()
+        );
+    });
+
+    running_state.update_in(cx, |state, window, cx| {
+        state.go_to("0x2000", window, cx);
+        assert_eq!(
+            Some("0x2000 is outside the disassembled instructions"),
+            state.go_to_error().map(|error| error.as_ref())
+        );
+
+        state.go_to("0", window, cx);
+        assert_eq!(
+            Some("Lines start at 1"),
+            state.go_to_error().map(|error| error.as_ref())
+        );
+
+        // The selected stack frame has no source to go to a line in.
+        state.go_to("12", window, cx);
+        assert_eq!(
+            Some("The selected stack frame has no source to go to"),
+            state.go_to_error().map(|error| error.as_ref())
+        );
+    });
+
+    let shutdown_session = project.update(cx, |project, cx| {
+        project.dap_store().update(cx, |dap_store, cx| {
+            dap_store.shutdown_session(session.read(cx).session_id(), cx)
+        })
+    });
+
+    shutdown_session.await.unwrap();
+}
@@ -0,0 +1,297 @@
+use crate::{
+    session::ThreadItem,
+    tests::{active_debug_session_panel, init_test, init_test_workspace},
+};
+use dap::{requests::SetExceptionBreakpoints, DebugRequestType};
+use gpui::{BackgroundExecutor, TestAppContext, VisualTestContext};
+use project::{debugger::session::ExceptionFilter, FakeFs, Project};
+use serde_json::json;
+use std::sync::{Arc, Mutex};
+
+fn exception_capabilities() -> dap::Capabilities {
+    dap::Capabilities {
+        exception_breakpoint_filters: Some(vec![
+            serde_json::from_value(json!({
+                "filter": "uncaught",
+                "label": "Uncaught Exceptions",
+                "default": true,
+            }))
+            .unwrap(),
+            serde_json::from_value(json!({
+                "filter": "raised",
+                "label": "Raised Exceptions",
+                "supportsCondition": true,
+            }))
+            .unwrap(),
+        ]),
+        supports_exception_filter_options: Some(true),
+        ..Default::default()
+    }
+}
+
+#[gpui::test]
+async fn test_exception_breakpoint_list_sends_and_remembers_filters(
+    executor: BackgroundExecutor,
+    cx: &mut TestAppContext,
+) {
+    init_test(cx);
+
+    let fs = FakeFs::new(executor.clone());
+    fs.insert_tree(
+        "/project",
+        json!({
+            "main.rs": "First line\nSecond line\nThird line",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/project".as_ref()], cx).await;
+    let workspace = init_test_workspace(&project, cx).await;
+    let cx = &mut VisualTestContext::from_window(*workspace, cx);
+
+    let task = project.update(cx, |project, cx| {
+        project.start_debug_session(
+            dap::test_config(
+                DebugRequestType::Launch,
+                None,
+                Some(exception_capabilities()),
+            ),
+            cx,
+        )
+    });
+
+    let session = task.await.unwrap();
+    let client = session.update(cx, |session, _| session.adapter_client().unwrap());
+
+    let sent_filters = Arc::new(Mutex::new(None));
+    client
+        .on_request::<SetExceptionBreakpoints, _>({
+            let sent_filters = sent_filters.clone();
+            move |_, args| {
+                *sent_filters.lock().unwrap() = args.filter_options.map(|options| {
+                    options
+                        .into_iter()
+                        .map(|option| (option.filter_id, option.condition))
+                        .collect::<Vec<_>>()
+                });
+                Ok(serde_json::from_value(json!({})).unwrap())
+            }
+        })
+        .await;
+
+    cx.run_until_parked();
+    sent_filters.lock().unwrap().take();
+
+    let running_state = active_debug_session_panel(workspace, cx).update(cx, |item, _| {
+        item.mode()
+            .as_running()
+            .expect("Session should be running by this point")
+            .clone()
+    });
+    running_state.update(cx, |state, cx| {
+        assert!(state.supports_thread_item(ThreadItem::ExceptionBreakpoints, cx));
+        state.set_thread_item(ThreadItem::ExceptionBreakpoints, cx);
+    });
+    session.update(cx, |session, _| {
+        assert_eq!(
+            vec![ExceptionFilter {
+                filter_id: "uncaught".into(),
+                condition: None,
+            }],
+            session.exception_filters(),
+            "The adapter's default filters should be on until others are chosen"
+        );
+    });
+
+    let exception_breakpoint_list =
+        running_state.update(cx, |state, _| state.exception_breakpoint_list().clone());
+    exception_breakpoint_list.update_in(cx, |list, window, cx| {
+        list.toggle_filter("raised", window, cx);
+    });
+    cx.run_until_parked();
+
+    assert_eq!(
+        Some(vec![
+            ("uncaught".to_string(), None),
+            ("raised".to_string(), None)
+        ]),
+        sent_filters.lock().unwrap().take()
+    );
+
+    exception_breakpoint_list.update(cx, |list, cx| {
+        list.set_condition("raised", " error.is_fatal ", cx);
+    });
+    cx.run_until_parked();
+
+    assert_eq!(
+        Some(vec![
+            ("uncaught".to_string(), None),
+            ("raised".to_string(), Some("error.is_fatal".to_string()))
+        ]),
+        sent_filters.lock().unwrap().take()
+    );
+
+    exception_breakpoint_list.update_in(cx, |list, window, cx| {
+        list.toggle_filter("uncaught", window, cx);
+    });
+    cx.run_until_parked();
+
+    assert_eq!(
+        Some(vec![(
+            "raised".to_string(),
+            Some("error.is_fatal".to_string())
+        )]),
+        sent_filters.lock().unwrap().take()
+    );
+
+    let shutdown_session = project.update(cx, |project, cx| {
+        project.dap_store().update(cx, |dap_store, cx| {
+            dap_store.shutdown_session(session.read(cx).session_id(), cx)
+        })
+    });
+    shutdown_session.await.unwrap();
+    cx.run_until_parked();
+
+    // A new session of the same configuration starts with the filters chosen last time.
+    let task = project.update(cx, |project, cx| {
+        project.start_debug_session(
+            dap::test_config(
+                DebugRequestType::Launch,
+                None,
+                Some(exception_capabilities()),
+            ),
+            cx,
+        )
+    });
+
+    let session = task.await.unwrap();
+    cx.run_until_parked();
+
+    session.update(cx, |session, _| {
+        assert_eq!(
+            vec![ExceptionFilter {
+                filter_id: "raised".into(),
+                condition: Some("error.is_fatal".into()),
+            }],
+            session.exception_filters()
+        );
+    });
+
+    let shutdown_session = project.update(cx, |project, cx| {
+        project.dap_store().update(cx, |dap_store, cx| {
+            dap_store.shutdown_session(session.read(cx).session_id(), cx)
+        })
+    });
+    shutdown_session.await.unwrap();
+}
+
+#[gpui::test]
+async fn test_exception_breakpoints_are_hidden_without_filters(
+    executor: BackgroundExecutor,
+    cx: &mut TestAppContext,
+) {
+    init_test(cx);
+
+    let fs = FakeFs::new(executor.clone());
+    fs.insert_tree(
+        "/project",
+        json!({
+            "main.rs": "First line\nSecond line\nThird line",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/project".as_ref()], cx).await;
+    let workspace = init_test_workspace(&project, cx).await;
+    let cx = &mut VisualTestContext::from_window(*workspace, cx);
+
+    let task = project.update(cx, |project, cx| {
+        project.start_debug_session(dap::test_config(DebugRequestType::Launch, None, None), cx)
+    });
+
+    let session = task.await.unwrap();
+    cx.run_until_parked();
+
+    let running_state = active_debug_session_panel(workspace, cx).update(cx, |item, _| {
+        item.mode()
+            .as_running()
+            .expect("Session should be running by this point")
+            .clone()
+    });
+    running_state.update(cx, |state, cx| {
+        assert!(!state.supports_thread_item(ThreadItem::ExceptionBreakpoints, cx));
+    });
+    session.update(cx, |session, _| {
+        assert!(session.exception_filters().is_empty());
+    });
+
+    let shutdown_session = project.update(cx, |project, cx| {
+        project.dap_store().update(cx, |dap_store, cx| {
+            dap_store.shutdown_session(session.read(cx).session_id(), cx)
+        })
+    });
+    shutdown_session.await.unwrap();
+}
+
+#[gpui::test]
+async fn test_exception_filters_are_restored_when_a_session_starts(
+    executor: BackgroundExecutor,
+    cx: &mut TestAppContext,
+) {
+    init_test(cx);
+
+    let fs = FakeFs::new(executor.clone());
+    fs.insert_tree(
+        "/project",
+        json!({
+            "main.rs": "First line\nSecond line\nThird line",
+        }),
+    )
+    .await;
+
+    // No workspace, so no exception breakpoint list is ever opened.
+    let project = Project::test(fs, ["/project".as_ref()], cx).await;
+    let config = dap::DebugAdapterConfig {
+        label: "restored exception filters".into(),
+        ..dap::test_config(
+            DebugRequestType::Launch,
+            None,
+            Some(exception_capabilities()),
+        )
+    };
+    let filters = vec![ExceptionFilter {
+        filter_id: "raised".into(),
+        condition: Some("error.is_fatal".into()),
+    }];
+
+    let task = project.update(cx, |project, cx| {
+        project.start_debug_session(config.clone(), cx)
+    });
+    let session = task.await.unwrap();
+    session.update(cx, |session, cx| {
+        session.set_exception_filters(filters.clone(), cx).detach();
+    });
+    cx.run_until_parked();
+
+    let shutdown_session = project.update(cx, |project, cx| {
+        project.dap_store().update(cx, |dap_store, cx| {
+            dap_store.shutdown_session(session.read(cx).session_id(), cx)
+        })
+    });
+    shutdown_session.await.unwrap();
+    cx.run_until_parked();
+
+    // The filters are in place once the session has started, so they were sent while it started.
+    let task = project.update(cx, |project, cx| project.start_debug_session(config, cx));
+    let session = task.await.unwrap();
+    session.update(cx, |session, _| {
+        assert_eq!(filters, session.exception_filters());
+    });
+
+    let shutdown_session = project.update(cx, |project, cx| {
+        project.dap_store().update(cx, |dap_store, cx| {
+            dap_store.shutdown_session(session.read(cx).session_id(), cx)
+        })
+    });
+    shutdown_session.await.unwrap();
+}
@@ -0,0 +1,298 @@
+use crate::{
+    hover::{expression_at, DebuggerHoverAddon},
+    tests::{active_debug_session_panel, init_test, init_test_workspace},
+};
+use dap::requests::{Evaluate, StackTrace, Threads};
+use editor::{Addon as _, Editor};
+use gpui::{BackgroundExecutor, Entity, Task, TestAppContext, VisualTestContext, WindowHandle};
+use project::{debugger::session::Session, FakeFs, Hover, Project};
+use serde_json::json;
+use std::sync::{Arc, Mutex};
+use workspace::Workspace;
+
+fn project_files() -> serde_json::Value {
+    json!({
+        "main.rs": "fn main() {\n    let answer = 42;\n    missing();\n}",
+    })
+}
+
+/// Starts a session with the given capabilities, stops it in stack frame 7 and opens `main.rs`.
+async fn start_stopped_session(
+    project: &Entity<Project>,
+    workspace: WindowHandle<Workspace>,
+    capabilities: Option<dap::Capabilities>,
+    cx: &mut VisualTestContext,
+) -> (Entity<Session>, Entity<Editor>) {
+    let task = project.update(cx, |project, cx| {
+        project.start_debug_session(
+            dap::test_config(dap::DebugRequestType::Launch, None, capabilities),
+            cx,
+        )
+    });
+
+    let session = task.await.unwrap();
+    let client = session.update(cx, |session, _| session.adapter_client().unwrap());
+
+    client
+        .on_request::<Threads, _>(move |_, _| {
+            Ok(dap::ThreadsResponse {
+                threads: vec![dap::Thread {
+                    id: 1,
+                    name: "Thread 1".into(),
+                }],
+            })
+        })
+        .await;
+
+    client
+        .on_request::<StackTrace, _>(move |_, _| {
+            Ok(dap::StackTraceResponse {
+                stack_frames: vec![dap::StackFrame {
+                    id: 7,
+                    name: "main".into(),
+                    source: None,
+                    line: 2,
+                    column: 1,
+                    end_line: None,
+                    end_column: None,
+                    can_restart: None,
+                    instruction_pointer_reference: None,
+                    module_id: None,
+                    presentation_hint: None,
+                }],
+                total_frames: None,
+            })
+        })
+        .await;
+
+    let worktree_id = project.update(cx, |project, cx| {
+        project.worktrees(cx).next().unwrap().read(cx).id()
+    });
+    let editor = workspace
+        .update(cx, |workspace, window, cx| {
+            workspace.open_path((worktree_id, "main.rs"), None, true, window, cx)
+        })
+        .unwrap()
+        .await
+        .unwrap()
+        .downcast::<Editor>()
+        .unwrap();
+
+    client
+        .fake_event(dap::messages::Events::Stopped(dap::StoppedEvent {
+            reason: dap::StoppedEventReason::Pause,
+            description: None,
+            thread_id: Some(1),
+            preserve_focus_hint: None,
+            text: None,
+            all_threads_stopped: None,
+            hit_breakpoint_ids: None,
+        }))
+        .await;
+    cx.run_until_parked();
+
+    let running_state = active_debug_session_panel(workspace, cx).update(cx, |item, _| {
+        item.mode()
+            .as_running()
+            .expect("Session should be running by this point")
+            .clone()
+    });
+    running_state.update(cx, |state, cx| {
+        let threads = state
+            .session()
+            .update(cx, |session, cx| session.threads(cx));
+        state.select_current_thread(&threads, cx);
+    });
+    cx.run_until_parked();
+
+    (session, editor)
+}
+
+/// Asks the editor's debugger addon for the hover at the start of `text`.
+fn hover_at(
+    editor: &Entity<Editor>,
+    text: &str,
+    cx: &mut VisualTestContext,
+) -> Option<Task<Vec<Hover>>> {
+    editor.update(cx, |editor, cx| {
+        let editor = &*editor;
+        let buffer = editor.buffer().read(cx).as_singleton().unwrap();
+        let offset = editor.text(cx).find(text).unwrap();
+        let position = buffer.read(cx).anchor_before(offset);
+        editor
+            .addon::<DebuggerHoverAddon>()
+            .expect("Editors in the workspace should have the debugger hover addon")
+            .hover(editor, &buffer, position, cx)
+    })
+}
+
+#[gpui::test]
+async fn test_hover_evaluates_hovered_expression(
+    executor: BackgroundExecutor,
+    cx: &mut TestAppContext,
+) {
+    init_test(cx);
+
+    let fs = FakeFs::new(executor.clone());
+    fs.insert_tree("/project", project_files()).await;
+
+    let project = Project::test(fs, ["/project".as_ref()], cx).await;
+    let workspace = init_test_workspace(&project, cx).await;
+    let cx = &mut VisualTestContext::from_window(*workspace, cx);
+
+    let (session, editor) = start_stopped_session(
+        &project,
+        workspace,
+        Some(dap::Capabilities {
+            supports_evaluate_for_hovers: Some(true),
+            ..Default::default()
+        }),
+        cx,
+    )
+    .await;
+    let client = session.update(cx, |session, _| session.adapter_client().unwrap());
+
+    let evaluations = Arc::new(Mutex::new(Vec::new()));
+    client
+        .on_request::<Evaluate, _>({
+            let evaluations = evaluations.clone();
+            move |_, args| {
+                evaluations.lock().unwrap().push((
+                    args.expression.clone(),
+                    args.context,
+                    args.frame_id,
+                ));
+                if args.expression == "answer" {
+                    Ok(dap::EvaluateResponse {
+                        result: "42".into(),
+                        type_: Some("i32".into()),
+                        presentation_hint: None,
+                        variables_reference: 0,
+                        named_variables: None,
+                        indexed_variables: None,
+                        memory_reference: None,
+                        value_location_reference: None,
+                    })
+                } else {
+                    Err(dap::ErrorResponse {
+                        error: Some(dap::Message {
+                            id: 1,
+                            format: "missing is not a valid expression".into(),
+                            variables: None,
+                            send_telemetry: None,
+                            show_user: None,
+                            url: None,
+                            url_label: None,
+                        }),
+                    })
+                }
+            }
+        })
+        .await;
+
+    let hover =
+        hover_at(&editor, "answer", cx).expect("the stopped session should evaluate hovers");
+    cx.run_until_parked();
+    let hovers = hover.await;
+    assert_eq!(
+        hovers
+            .iter()
+            .flat_map(|hover| hover.contents.iter().map(|block| block.text.as_str()))
+            .collect::<Vec<_>>(),
+        ["answer: i32 = 42"]
+    );
+
+    // Expressions the adapter can't evaluate show nothing, and neither does whitespace.
+    let hover = hover_at(&editor, "missing", cx).unwrap();
+    cx.run_until_parked();
+    assert!(hover.await.is_empty());
+    assert!(hover_at(&editor, " = 42", cx).is_none());
+    cx.run_until_parked();
+
+    assert_eq!(
+        *evaluations.lock().unwrap(),
+        [
+            (
+                "answer".to_string(),
+                Some(dap::EvaluateArgumentsContext::Hover),
+                Some(7)
+            ),
+            (
+                "missing".to_string(),
+                Some(dap::EvaluateArgumentsContext::Hover),
+                Some(7)
+            )
+        ]
+    );
+
+    let shutdown_session = project.update(cx, |project, cx| {
+        project.dap_store().update(cx, |dap_store, cx| {
+            dap_store.shutdown_session(session.read(cx).session_id(), cx)
+        })
+    });
+    shutdown_session.await.unwrap();
+}
+
+#[gpui::test]
+async fn test_hover_requires_evaluate_for_hovers_capability(
+    executor: BackgroundExecutor,
+    cx: &mut TestAppContext,
+) {
+    init_test(cx);
+
+    let fs = FakeFs::new(executor.clone());
+    fs.insert_tree("/project", project_files()).await;
+
+    let project = Project::test(fs, ["/project".as_ref()], cx).await;
+    let workspace = init_test_workspace(&project, cx).await;
+    let cx = &mut VisualTestContext::from_window(*workspace, cx);
+
+    let (session, editor) = start_stopped_session(&project, workspace, None, cx).await;
+    let client = session.update(cx, |session, _| session.adapter_client().unwrap());
+
+    let evaluated = Arc::new(Mutex::new(false));
+    client
+        .on_request::<Evaluate, _>({
+            let evaluated = evaluated.clone();
+            move |_, _| {
+                *evaluated.lock().unwrap() = true;
+                Err(dap::ErrorResponse { error: None })
+            }
+        })
+        .await;
+
+    assert!(hover_at(&editor, "answer", cx).is_none());
+    cx.run_until_parked();
+    assert!(!*evaluated.lock().unwrap());
+
+    let shutdown_session = project.update(cx, |project, cx| {
+        project.dap_store().update(cx, |dap_store, cx| {
+            dap_store.shutdown_session(session.read(cx).session_id(), cx)
+        })
+    });
+    shutdown_session.await.unwrap();
+}
+
+#[test]
+fn test_expression_at() {
+    let expression = |line: &str, column: usize| {
+        expression_at(line, column).map(|range| line[range].to_string())
+    };
+
+    assert_eq!(expression("let total = count;", 12), Some("count".into()));
+    assert_eq!(expression("let total = count;", 16), Some("count".into()));
+    assert_eq!(expression("let total = count;", 17), None);
+    assert_eq!(expression("let total = count;", 9), None);
+    assert_eq!(
+        expression("self.items.len()", 6),
+        Some("self.items".into()),
+        "Hovering a field includes the values it's a member of"
+    );
+    assert_eq!(expression("self.items.len()", 2), Some("self".into()));
+    assert_eq!(expression("(a).b", 4), Some("b".into()));
+    assert_eq!(expression("$scope_1", 3), Some("$scope_1".into()));
+    assert_eq!(expression("x = 1.5", 6), None);
+    assert_eq!(expression("x = 42", 4), None);
+    assert_eq!(expression("größe + 1", 4), Some("größe".into()));
+    assert_eq!(expression("", 0), None);
+}
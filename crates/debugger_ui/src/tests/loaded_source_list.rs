@@ -0,0 +1,275 @@
+use crate::{
+    debugger_panel::DebugPanel,
+    session::ThreadItem,
+    tests::{active_debug_session_panel, init_test, init_test_workspace},
+};
+use dap::{
+    requests::{LoadedSources, Source, StackTrace, Threads},
+    DebugRequestType, StoppedEvent,
+};
+use editor::Editor;
+use gpui::{BackgroundExecutor, TestAppContext, VisualTestContext};
+use project::{FakeFs, Project};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+fn source(path: &str) -> dap::Source {
+    dap::Source {
+        name: path.rsplit('/').next().map(ToOwned::to_owned),
+        path: Some(path.into()),
+        source_reference: None,
+        presentation_hint: None,
+        origin: None,
+        sources: None,
+        adapter_data: None,
+        checksums: None,
+    }
+}
+
+#[gpui::test]
+async fn test_loaded_source_events_are_deduplicated(
+    executor: BackgroundExecutor,
+    cx: &mut TestAppContext,
+) {
+    init_test(cx);
+
+    let fs = FakeFs::new(executor.clone());
+
+    let project = Project::test(fs, ["/project".as_ref()], cx).await;
+    let workspace = init_test_workspace(&project, cx).await;
+    workspace
+        .update(cx, |workspace, window, cx| {
+            workspace.focus_panel::<DebugPanel>(window, cx);
+        })
+        .unwrap();
+    let cx = &mut VisualTestContext::from_window(*workspace, cx);
+
+    let task = project.update(cx, |project, cx| {
+        project.start_debug_session(
+            dap::test_config(
+                DebugRequestType::Launch,
+                None,
+                Some(dap::Capabilities {
+                    supports_loaded_sources_request: Some(true),
+                    ..Default::default()
+                }),
+            ),
+            cx,
+        )
+    });
+
+    let session = task.await.unwrap();
+    let client = session.update(cx, |session, _| session.adapter_client().unwrap());
+
+    client
+        .on_request::<StackTrace, _>(move |_, _| {
+            Ok(dap::StackTraceResponse {
+                stack_frames: Vec::default(),
+                total_frames: None,
+            })
+        })
+        .await;
+
+    client
+        .on_request::<Threads, _>(move |_, _| {
+            Ok(dap::ThreadsResponse {
+                threads: vec![dap::Thread {
+                    id: 1,
+                    name: "Thread 1".into(),
+                }],
+            })
+        })
+        .await;
+
+    client
+        .on_request::<LoadedSources, _>(move |_, _| {
+            Ok(dap::LoadedSourcesResponse {
+                sources: vec![source("/project/main.rs"), source("/project/main.rs")],
+            })
+        })
+        .await;
+
+    client
+        .fake_event(dap::messages::Events::Stopped(StoppedEvent {
+            reason: dap::StoppedEventReason::Pause,
+            description: None,
+            thread_id: Some(1),
+            preserve_focus_hint: None,
+            text: None,
+            all_threads_stopped: None,
+            hit_breakpoint_ids: None,
+        }))
+        .await;
+
+    cx.run_until_parked();
+
+    let running_state = active_debug_session_panel(workspace, cx).update(cx, |item, _| {
+        item.mode()
+            .as_running()
+            .expect("Session should be running by this point")
+            .clone()
+    });
+
+    running_state.update(cx, |state, cx| {
+        state.set_thread_item(ThreadItem::LoadedSource, cx);
+        cx.refresh_windows();
+    });
+
+    cx.run_until_parked();
+
+    let loaded_source_list =
+        running_state.update(cx, |state, _| state.loaded_source_list().clone());
+    let loaded_sources = |cx: &mut VisualTestContext| {
+        loaded_source_list.update(cx, |list, cx| list.loaded_sources(cx))
+    };
+
+    assert_eq!(vec![source("/project/main.rs")], loaded_sources(cx));
+
+    for reason in [
+        dap::LoadedSourceEventReason::New,
+        dap::LoadedSourceEventReason::New,
+    ] {
+        client
+            .fake_event(dap::messages::Events::LoadedSource(
+                dap::LoadedSourceEvent {
+                    reason,
+                    source: source("/project/lib.rs"),
+                },
+            ))
+            .await;
+    }
+
+    cx.run_until_parked();
+
+    assert_eq!(
+        vec![source("/project/main.rs"), source("/project/lib.rs")],
+        loaded_sources(cx)
+    );
+
+    let changed_source = dap::Source {
+        origin: Some("changed".into()),
+        ..source("/project/lib.rs")
+    };
+    client
+        .fake_event(dap::messages::Events::LoadedSource(
+            dap::LoadedSourceEvent {
+                reason: dap::LoadedSourceEventReason::Changed,
+                source: changed_source.clone(),
+            },
+        ))
+        .await;
+
+    cx.run_until_parked();
+
+    assert_eq!(
+        vec![source("/project/main.rs"), changed_source],
+        loaded_sources(cx)
+    );
+
+    client
+        .fake_event(dap::messages::Events::LoadedSource(
+            dap::LoadedSourceEvent {
+                reason: dap::LoadedSourceEventReason::Removed,
+                source: source("/project/main.rs"),
+            },
+        ))
+        .await;
+
+    cx.run_until_parked();
+
+    assert_eq!(1, loaded_sources(cx).len());
+
+    let shutdown_session = project.update(cx, |project, cx| {
+        project.dap_store().update(cx, |dap_store, cx| {
+            dap_store.shutdown_session(session.read(cx).session_id(), cx)
+        })
+    });
+
+    shutdown_session.await.unwrap();
+}
+
+#[gpui::test]
+async fn test_fetched_sources_are_cached(executor: BackgroundExecutor, cx: &mut TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(executor.clone());
+
+    let project = Project::test(fs, ["/project".as_ref()], cx).await;
+    let workspace = init_test_workspace(&project, cx).await;
+    workspace
+        .update(cx, |workspace, window, cx| {
+            workspace.focus_panel::<DebugPanel>(window, cx);
+        })
+        .unwrap();
+    let cx = &mut VisualTestContext::from_window(*workspace, cx);
+
+    let task = project.update(cx, |project, cx| {
+        project.start_debug_session(dap::test_config(DebugRequestType::Launch, None, None), cx)
+    });
+
+    let session = task.await.unwrap();
+    let client = session.update(cx, |session, _| session.adapter_client().unwrap());
+
+    let source_requests = Arc::new(AtomicUsize::new(0));
+    client
+        .on_request::<Source, _>({
+            let source_requests = source_requests.clone();
+            move |_, args| {
+                source_requests.fetch_add(1, Ordering::SeqCst);
+                assert_eq!(7, args.source_reference);
+                Ok(dap::SourceResponse {
+                    content: "fn generated() {}\n".into(),
+                    mime_type: None,
+                })
+            }
+        })
+        .await;
+
+    cx.run_until_parked();
+
+    let running_state = active_debug_session_panel(workspace, cx).update(cx, |item, _| {
+        item.mode()
+            .as_running()
+            .expect("Session should be running by this point")
+            .clone()
+    });
+    let loaded_source_list =
+        running_state.update(cx, |state, _| state.loaded_source_list().clone());
+
+    let fetched_source = dap::Source {
+        name: Some("generated.rs".into()),
+        path: None,
+        source_reference: Some(7),
+        ..source("/project/generated.rs")
+    };
+    for _ in 0..2 {
+        loaded_source_list.update_in(cx, |list, window, cx| {
+            list.open_source(fetched_source.clone(), window, cx);
+        });
+        cx.run_until_parked();
+    }
+
+    assert_eq!(1, source_requests.load(Ordering::SeqCst));
+
+    // Opening the source again reuses its read-only editor.
+    let editors = workspace
+        .update(cx, |workspace, _, cx| {
+            workspace.items_of_type::<Editor>(cx).collect::<Vec<_>>()
+        })
+        .unwrap();
+    assert_eq!(1, editors.len());
+    editors[0].update(cx, |editor, cx| {
+        assert_eq!("fn generated() {}\n", editor.text(cx));
+        assert!(editor.read_only(cx));
+    });
+
+    let shutdown_session = project.update(cx, |project, cx| {
+        project.dap_store().update(cx, |dap_store, cx| {
+            dap_store.shutdown_session(session.read(cx).session_id(), cx)
+        })
+    });
+
+    shutdown_session.await.unwrap();
+}
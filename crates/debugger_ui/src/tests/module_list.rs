@@ -252,6 +252,30 @@ async fn test_module_list(executor: BackgroundExecutor, cx: &mut TestAppContext)
         assert!(!actual_modules.contains(&changed_module));
     });
 
+    let module_list = running_state.update(cx, |state, _| state.module_list().clone());
+    module_list.update_in(cx, |list, window, cx| {
+        list.filter_editor()
+            .update(cx, |editor, cx| editor.set_text("third", window, cx));
+    });
+
+    cx.run_until_parked();
+
+    module_list.update(cx, |list, cx| {
+        assert_eq!(vec![new_module.clone()], list.filtered_modules(cx));
+        assert_eq!(2, list.modules(cx).len());
+    });
+
+    module_list.update_in(cx, |list, window, cx| {
+        list.filter_editor()
+            .update(cx, |editor, cx| editor.set_text("", window, cx));
+    });
+
+    cx.run_until_parked();
+
+    module_list.update(cx, |list, cx| {
+        assert_eq!(list.modules(cx), list.filtered_modules(cx));
+    });
+
     let shutdown_session = project.update(cx, |project, cx| {
         project.dap_store().update(cx, |dap_store, cx| {
             dap_store.shutdown_session(session.read(cx).session_id(), cx)
@@ -1,6 +1,9 @@
 use crate::{
     debugger_panel::DebugPanel,
-    session::running::stack_frame_list::StackFrameEntry,
+    session::running::{
+        stack_frame_list::{StackFrameEntry, StackFrameHistory},
+        CopyAllStackTraces, CopyStackTrace,
+    },
     tests::{active_debug_session_panel, init_test, init_test_workspace},
 };
 use dap::{
@@ -843,3 +846,230 @@ async fn test_collapsed_entries(executor: BackgroundExecutor, cx: &mut TestAppCo
 
     shutdown_session.await.unwrap();
 }
+
+#[gpui::test]
+async fn test_copy_stack_traces(executor: BackgroundExecutor, cx: &mut TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(executor.clone());
+
+    fs.insert_tree(
+        path!("/project"),
+        json!({
+           "src": {
+               "test.js": "console.log('test');",
+           }
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, [path!("/project").as_ref()], cx).await;
+    let workspace = init_test_workspace(&project, cx).await;
+    let cx = &mut VisualTestContext::from_window(*workspace, cx);
+
+    let task = project.update(cx, |project, cx| {
+        project.start_debug_session(
+            dap::test_config(dap::DebugRequestType::Launch, None, None),
+            cx,
+        )
+    });
+
+    let session = task.await.unwrap();
+    let client = session.update(cx, |session, _| session.adapter_client().unwrap());
+
+    client
+        .on_request::<Threads, _>(move |_, _| {
+            Ok(dap::ThreadsResponse {
+                threads: vec![
+                    dap::Thread {
+                        id: 1,
+                        name: "Thread 1".into(),
+                    },
+                    dap::Thread {
+                        id: 2,
+                        name: "Thread 2".into(),
+                    },
+                ],
+            })
+        })
+        .await;
+
+    client
+        .on_request::<StackTrace, _>(move |_, args| {
+            let stack_frames = if args.thread_id == 1 {
+                vec![
+                    StackFrame {
+                        id: 1,
+                        name: "main".into(),
+                        source: Some(dap::Source {
+                            name: Some("test.js".into()),
+                            path: Some(path!("/project/src/test.js").into()),
+                            source_reference: None,
+                            presentation_hint: None,
+                            origin: None,
+                            sources: None,
+                            adapter_data: None,
+                            checksums: None,
+                        }),
+                        line: 1,
+                        column: 9,
+                        end_line: None,
+                        end_column: None,
+                        can_restart: None,
+                        instruction_pointer_reference: None,
+                        module_id: None,
+                        presentation_hint: None,
+                    },
+                    StackFrame {
+                        id: 2,
+                        name: "<anonymous>".into(),
+                        source: None,
+                        line: 0,
+                        column: 0,
+                        end_line: None,
+                        end_column: None,
+                        can_restart: None,
+                        instruction_pointer_reference: None,
+                        module_id: None,
+                        presentation_hint: None,
+                    },
+                ]
+            } else {
+                vec![StackFrame {
+                    id: 3,
+                    name: "worker".into(),
+                    source: Some(dap::Source {
+                        name: Some("worker.js".into()),
+                        path: None,
+                        source_reference: Some(1),
+                        presentation_hint: None,
+                        origin: None,
+                        sources: None,
+                        adapter_data: None,
+                        checksums: None,
+                    }),
+                    line: 4,
+                    column: 0,
+                    end_line: None,
+                    end_column: None,
+                    can_restart: None,
+                    instruction_pointer_reference: None,
+                    module_id: None,
+                    presentation_hint: None,
+                }]
+            };
+
+            Ok(dap::StackTraceResponse {
+                stack_frames,
+                total_frames: None,
+            })
+        })
+        .await;
+
+    client
+        .fake_event(dap::messages::Events::Stopped(dap::StoppedEvent {
+            reason: dap::StoppedEventReason::Pause,
+            description: None,
+            thread_id: Some(1),
+            preserve_focus_hint: None,
+            text: None,
+            all_threads_stopped: Some(true),
+            hit_breakpoint_ids: None,
+        }))
+        .await;
+
+    cx.run_until_parked();
+
+    let running_state = active_debug_session_panel(workspace, cx).update(cx, |item, _| {
+        item.mode()
+            .as_running()
+            .expect("Session should be running by this point")
+            .clone()
+    });
+    running_state.update(cx, |running_state, cx| {
+        let threads = running_state
+            .session()
+            .update(cx, |session, cx| session.threads(cx));
+        running_state.select_current_thread(&threads, cx);
+    });
+
+    cx.run_until_parked();
+
+    running_state.update_in(cx, |running_state, window, cx| {
+        running_state.copy_stack_trace(&CopyStackTrace, window, cx);
+    });
+
+    assert_eq!(
+        Some(format!(
+            "Thread 1 \"Thread 1\"\n  #0 main at {}:1:9\n  #1 <anonymous> at <unknown>\n",
+            path!("/project/src/test.js")
+        )),
+        cx.read_from_clipboard().and_then(|item| item.text())
+    );
+
+    running_state.update_in(cx, |running_state, window, cx| {
+        running_state.copy_all_stack_traces(&CopyAllStackTraces, window, cx);
+    });
+
+    cx.run_until_parked();
+
+    assert_eq!(
+        Some(format!(
+            "Thread 1 \"Thread 1\"\n  #0 main at {}:1:9\n  #1 <anonymous> at <unknown>\n\n\
+            Thread 2 \"Thread 2\"\n  #0 worker at worker.js:4\n",
+            path!("/project/src/test.js")
+        )),
+        cx.read_from_clipboard().and_then(|item| item.text()),
+        "Copying all threads should fetch the stack frames of the threads that weren't selected"
+    );
+
+    let shutdown_session = project.update(cx, |project, cx| {
+        project.dap_store().update(cx, |dap_store, cx| {
+            dap_store.shutdown_session(session.read(cx).session_id(), cx)
+        })
+    });
+
+    shutdown_session.await.unwrap();
+}
+
+#[test]
+fn test_stack_frame_history() {
+    let mut history = StackFrameHistory::default();
+    assert!(!history.can_go_back());
+    assert!(!history.can_go_forward());
+    assert_eq!(None, history.go_back());
+
+    history.push(1);
+    history.push(2);
+    // Reselecting the current frame doesn't add an entry.
+    history.push(2);
+    history.push(3);
+
+    assert_eq!(Some(2), history.go_back());
+    assert_eq!(Some(1), history.go_back());
+    assert_eq!(None, history.go_back());
+    assert_eq!(Some(2), history.go_forward());
+
+    // Navigating the history reselects frames, which must not be recorded again.
+    history.push(2);
+    assert_eq!(Some(3), history.go_forward());
+    assert_eq!(None, history.go_forward());
+
+    // Selecting a new frame drops the frames ahead of the current one.
+    history.go_back();
+    history.go_back();
+    history.push(4);
+    assert!(!history.can_go_forward());
+    assert_eq!(Some(1), history.go_back());
+
+    // The history is bounded, dropping the oldest frames first.
+    history.clear();
+    for stack_frame_id in 0..100 {
+        history.push(stack_frame_id);
+    }
+    let mut oldest = None;
+    while let Some(stack_frame_id) = history.go_back() {
+        oldest = Some(stack_frame_id);
+    }
+    assert_eq!(Some(50), oldest);
+}
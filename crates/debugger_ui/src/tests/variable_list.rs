@@ -4,16 +4,19 @@ use std::sync::{
 };
 
 use crate::{
-    session::running::variable_list::{CollapseSelectedEntry, ExpandSelectedEntry},
+    session::running::variable_list::{
+        expression_for_path, CollapseAllEntries, CollapseSelectedEntry, ExpandAllEntries,
+        ExpandSelectedEntry,
+    },
     tests::{active_debug_session_panel, init_test, init_test_workspace},
     DebugPanel,
 };
 use collections::HashMap;
 use dap::{
-    requests::{Initialize, Launch, Scopes, StackTrace, Variables},
+    requests::{Initialize, Launch, Scopes, SetVariable, StackTrace, Variables},
     Scope, StackFrame, Variable,
 };
-use gpui::{BackgroundExecutor, TestAppContext, VisualTestContext};
+use gpui::{BackgroundExecutor, SharedString, TestAppContext, VisualTestContext};
 use menu::{SelectFirst, SelectNext, SelectPrevious};
 use project::{FakeFs, Project};
 use serde_json::json;
@@ -1757,3 +1760,756 @@ async fn test_it_fetches_scopes_variables_when_you_select_a_stack_frame(
 
     shutdown_session.await.unwrap();
 }
+
+#[gpui::test]
+async fn test_rejected_variable_edit_keeps_value_and_shows_error(
+    executor: BackgroundExecutor,
+    cx: &mut TestAppContext,
+) {
+    init_test(cx);
+
+    let fs = FakeFs::new(executor.clone());
+
+    fs.insert_tree(
+        path!("/project"),
+        json!({
+           "src": {
+               "test.js": "const variable1 = \"Value 1\";",
+           }
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, [path!("/project").as_ref()], cx).await;
+    let workspace = init_test_workspace(&project, cx).await;
+    workspace
+        .update(cx, |workspace, window, cx| {
+            workspace.focus_panel::<DebugPanel>(window, cx);
+        })
+        .unwrap();
+    let cx = &mut VisualTestContext::from_window(*workspace, cx);
+
+    let task = project.update(cx, |project, cx| {
+        project.start_debug_session(
+            dap::test_config(
+                dap::DebugRequestType::Launch,
+                None,
+                Some(dap::Capabilities {
+                    supports_set_variable: Some(true),
+                    ..Default::default()
+                }),
+            ),
+            cx,
+        )
+    });
+
+    let session = task.await.unwrap();
+    let client = session.update(cx, |session, _| session.adapter_client().unwrap());
+
+    client
+        .on_request::<dap::requests::Threads, _>(move |_, _| {
+            Ok(dap::ThreadsResponse {
+                threads: vec![dap::Thread {
+                    id: 1,
+                    name: "Thread 1".into(),
+                }],
+            })
+        })
+        .await;
+
+    client
+        .on_request::<StackTrace, _>(move |_, _| {
+            Ok(dap::StackTraceResponse {
+                stack_frames: vec![StackFrame {
+                    id: 1,
+                    name: "Stack Frame 1".into(),
+                    source: None,
+                    line: 1,
+                    column: 1,
+                    end_line: None,
+                    end_column: None,
+                    can_restart: None,
+                    instruction_pointer_reference: None,
+                    module_id: None,
+                    presentation_hint: None,
+                }],
+                total_frames: None,
+            })
+        })
+        .await;
+
+    client
+        .on_request::<Scopes, _>(move |_, _| {
+            Ok(dap::ScopesResponse {
+                scopes: vec![Scope {
+                    name: "Scope 1".into(),
+                    presentation_hint: None,
+                    variables_reference: 2,
+                    named_variables: None,
+                    indexed_variables: None,
+                    expensive: false,
+                    source: None,
+                    line: None,
+                    column: None,
+                    end_line: None,
+                    end_column: None,
+                }],
+            })
+        })
+        .await;
+
+    let variables = vec![Variable {
+        name: "variable1".into(),
+        value: "value 1".into(),
+        type_: None,
+        presentation_hint: None,
+        evaluate_name: None,
+        variables_reference: 0,
+        named_variables: None,
+        indexed_variables: None,
+        memory_reference: None,
+        declaration_location_reference: None,
+        value_location_reference: None,
+    }];
+
+    client
+        .on_request::<Variables, _>({
+            let variables = Arc::new(variables.clone());
+            move |_, _| {
+                Ok(dap::VariablesResponse {
+                    variables: (*variables).clone(),
+                })
+            }
+        })
+        .await;
+
+    let called_set_variable = Arc::new(AtomicBool::new(false));
+    client
+        .on_request::<SetVariable, _>({
+            let called_set_variable = called_set_variable.clone();
+            move |_, args| {
+                assert_eq!("variable1", args.name);
+                assert_eq!("value 2", args.value);
+                called_set_variable.store(true, Ordering::SeqCst);
+
+                Err(dap::ErrorResponse {
+                    error: Some(dap::Message {
+                        id: 1,
+                        format: "variable1 is read-only".into(),
+                        variables: None,
+                        send_telemetry: None,
+                        show_user: None,
+                        url: None,
+                        url_label: None,
+                    }),
+                })
+            }
+        })
+        .await;
+
+    client
+        .fake_event(dap::messages::Events::Stopped(dap::StoppedEvent {
+            reason: dap::StoppedEventReason::Pause,
+            description: None,
+            thread_id: Some(1),
+            preserve_focus_hint: None,
+            text: None,
+            all_threads_stopped: None,
+            hit_breakpoint_ids: None,
+        }))
+        .await;
+
+    cx.run_until_parked();
+
+    let running_state =
+        active_debug_session_panel(workspace, cx).update_in(cx, |item, window, cx| {
+            cx.focus_self(window);
+            item.mode()
+                .as_running()
+                .expect("Session should be running by this point")
+                .clone()
+        });
+
+    cx.run_until_parked();
+
+    let variable_list = running_state.update(cx, |state, _| state.variable_list().clone());
+    variable_list.update_in(cx, |variable_list, window, cx| {
+        variable_list.edit_variable("variable1", "value 2", window, cx);
+    });
+
+    cx.run_until_parked();
+
+    assert!(called_set_variable.load(Ordering::SeqCst));
+    variable_list.update(cx, |variable_list, _| {
+        assert_eq!(variables, variable_list.variables());
+        assert!(variable_list
+            .edit_error()
+            .is_some_and(|error| error.contains("variable1 is read-only")));
+    });
+
+    let shutdown_session = project.update(cx, |project, cx| {
+        project.dap_store().update(cx, |dap_store, cx| {
+            dap_store.shutdown_session(session.read(cx).session_id(), cx)
+        })
+    });
+
+    shutdown_session.await.unwrap();
+}
+
+#[test]
+fn test_expression_for_path() {
+    let path = |names: &[&str]| {
+        names
+            .iter()
+            .map(|name| SharedString::from(name.to_string()))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!("foo", expression_for_path(&path(&["Locals", "foo"])));
+    assert_eq!(
+        "foo.bar[2].baz",
+        expression_for_path(&path(&["Locals", "foo", "bar", "2", "baz"]))
+    );
+    assert_eq!(
+        "items[0]",
+        expression_for_path(&path(&["Locals", "items", "[0]"]))
+    );
+}
+
+#[gpui::test]
+async fn test_filter_keeps_ancestors_and_expanded_state(
+    executor: BackgroundExecutor,
+    cx: &mut TestAppContext,
+) {
+    init_test(cx);
+
+    let fs = FakeFs::new(executor.clone());
+
+    let test_file_content = r#"
+        const variable1 = {
+            nested1: "Nested 1",
+            nested2: "Nested 2",
+        };
+        const variable2 = "Value 2";
+        const variable3 = "Value 3";
+    "#
+    .unindent();
+
+    fs.insert_tree(
+        path!("/project"),
+        json!({
+           "src": {
+               "test.js": test_file_content,
+           }
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, [path!("/project").as_ref()], cx).await;
+    let workspace = init_test_workspace(&project, cx).await;
+    workspace
+        .update(cx, |workspace, window, cx| {
+            workspace.focus_panel::<DebugPanel>(window, cx);
+        })
+        .unwrap();
+    let cx = &mut VisualTestContext::from_window(*workspace, cx);
+
+    let task = project.update(cx, |project, cx| {
+        project.start_debug_session(
+            dap::test_config(dap::DebugRequestType::Launch, None, None),
+            cx,
+        )
+    });
+
+    let session = task.await.unwrap();
+    let client = session.update(cx, |session, _| session.adapter_client().unwrap());
+
+    client
+        .on_request::<dap::requests::Threads, _>(move |_, _| {
+            Ok(dap::ThreadsResponse {
+                threads: vec![dap::Thread {
+                    id: 1,
+                    name: "Thread 1".into(),
+                }],
+            })
+        })
+        .await;
+
+    client
+        .on_request::<Initialize, _>(move |_, _| {
+            Ok(dap::Capabilities {
+                supports_step_back: Some(false),
+                ..Default::default()
+            })
+        })
+        .await;
+
+    client.on_request::<Launch, _>(move |_, _| Ok(())).await;
+
+    let stack_frames = vec![StackFrame {
+        id: 1,
+        name: "Stack Frame 1".into(),
+        source: Some(dap::Source {
+            name: Some("test.js".into()),
+            path: Some(path!("/project/src/test.js").into()),
+            source_reference: None,
+            presentation_hint: None,
+            origin: None,
+            sources: None,
+            adapter_data: None,
+            checksums: None,
+        }),
+        line: 1,
+        column: 1,
+        end_line: None,
+        end_column: None,
+        can_restart: None,
+        instruction_pointer_reference: None,
+        module_id: None,
+        presentation_hint: None,
+    }];
+
+    client
+        .on_request::<StackTrace, _>({
+            let stack_frames = Arc::new(stack_frames.clone());
+            move |_, args| {
+                assert_eq!(1, args.thread_id);
+
+                Ok(dap::StackTraceResponse {
+                    stack_frames: (*stack_frames).clone(),
+                    total_frames: None,
+                })
+            }
+        })
+        .await;
+
+    let scopes = vec![
+        Scope {
+            name: "Scope 1".into(),
+            presentation_hint: Some(dap::ScopePresentationHint::Locals),
+            variables_reference: 2,
+            named_variables: None,
+            indexed_variables: None,
+            expensive: false,
+            source: None,
+            line: None,
+            column: None,
+            end_line: None,
+            end_column: None,
+        },
+        Scope {
+            name: "Scope 2".into(),
+            presentation_hint: None,
+            variables_reference: 4,
+            named_variables: None,
+            indexed_variables: None,
+            expensive: false,
+            source: None,
+            line: None,
+            column: None,
+            end_line: None,
+            end_column: None,
+        },
+    ];
+
+    client
+        .on_request::<Scopes, _>({
+            let scopes = Arc::new(scopes.clone());
+            move |_, args| {
+                assert_eq!(1, args.frame_id);
+
+                Ok(dap::ScopesResponse {
+                    scopes: (*scopes).clone(),
+                })
+            }
+        })
+        .await;
+
+    let scope1_variables = vec![
+        Variable {
+            name: "variable1".into(),
+            value: "{nested1: \"Nested 1\", nested2: \"Nested 2\"}".into(),
+            type_: None,
+            presentation_hint: None,
+            evaluate_name: None,
+            variables_reference: 3,
+            named_variables: None,
+            indexed_variables: None,
+            memory_reference: None,
+            declaration_location_reference: None,
+            value_location_reference: None,
+        },
+        Variable {
+            name: "variable2".into(),
+            value: "Value 2".into(),
+            type_: None,
+            presentation_hint: None,
+            evaluate_name: None,
+            variables_reference: 0,
+            named_variables: None,
+            indexed_variables: None,
+            memory_reference: None,
+            declaration_location_reference: None,
+            value_location_reference: None,
+        },
+    ];
+
+    let nested_variables = vec![
+        Variable {
+            name: "nested1".into(),
+            value: "Nested 1".into(),
+            type_: None,
+            presentation_hint: None,
+            evaluate_name: None,
+            variables_reference: 0,
+            named_variables: None,
+            indexed_variables: None,
+            memory_reference: None,
+            declaration_location_reference: None,
+            value_location_reference: None,
+        },
+        Variable {
+            name: "nested2".into(),
+            value: "Nested 2".into(),
+            type_: None,
+            presentation_hint: None,
+            evaluate_name: None,
+            variables_reference: 0,
+            named_variables: None,
+            indexed_variables: None,
+            memory_reference: None,
+            declaration_location_reference: None,
+            value_location_reference: None,
+        },
+    ];
+
+    let scope2_variables = vec![Variable {
+        name: "variable3".into(),
+        value: "Value 3".into(),
+        type_: None,
+        presentation_hint: None,
+        evaluate_name: None,
+        variables_reference: 0,
+        named_variables: None,
+        indexed_variables: None,
+        memory_reference: None,
+        declaration_location_reference: None,
+        value_location_reference: None,
+    }];
+
+    client
+        .on_request::<Variables, _>({
+            let scope1_variables = Arc::new(scope1_variables.clone());
+            let nested_variables = Arc::new(nested_variables.clone());
+            let scope2_variables = Arc::new(scope2_variables.clone());
+            move |_, args| match args.variables_reference {
+                4 => Ok(dap::VariablesResponse {
+                    variables: (*scope2_variables).clone(),
+                }),
+                3 => Ok(dap::VariablesResponse {
+                    variables: (*nested_variables).clone(),
+                }),
+                2 => Ok(dap::VariablesResponse {
+                    variables: (*scope1_variables).clone(),
+                }),
+                id => unreachable!("unexpected variables reference {id}"),
+            }
+        })
+        .await;
+
+    client
+        .fake_event(dap::messages::Events::Stopped(dap::StoppedEvent {
+            reason: dap::StoppedEventReason::Pause,
+            description: None,
+            thread_id: Some(1),
+            preserve_focus_hint: None,
+            text: None,
+            all_threads_stopped: None,
+            hit_breakpoint_ids: None,
+        }))
+        .await;
+
+    cx.run_until_parked();
+    let running_state =
+        active_debug_session_panel(workspace, cx).update_in(cx, |item, window, cx| {
+            cx.focus_self(window);
+            let running = item
+                .mode()
+                .as_running()
+                .expect("Session should be running by this point")
+                .clone();
+
+            let variable_list = running.read_with(cx, |state, _| state.variable_list().clone());
+            variable_list.update(cx, |_, cx| cx.focus_self(window));
+            running
+        });
+
+    cx.dispatch_action(SelectFirst);
+    cx.dispatch_action(SelectNext);
+    cx.dispatch_action(ExpandSelectedEntry);
+    cx.run_until_parked();
+
+    let variable_list = running_state.update(cx, |state, _| state.variable_list().clone());
+    let set_filter = |query: &str, cx: &mut VisualTestContext| {
+        variable_list.update_in(cx, |list, window, cx| {
+            list.filter_editor()
+                .update(cx, |editor, cx| editor.set_text(query, window, cx));
+        });
+        cx.run_until_parked();
+    };
+
+    variable_list.update(cx, |list, _| {
+        list.assert_visual_entries(vec![
+            "v Scope 1",
+            "    v variable1 <=== selected",
+            "        > nested1",
+            "        > nested2",
+            "    > variable2",
+            "> Scope 2",
+        ]);
+    });
+
+    // The containers of a match stay visible.
+    set_filter("nested1", cx);
+    variable_list.update(cx, |list, _| {
+        list.assert_visual_entries(vec![
+            "v Scope 1",
+            "    v variable1 <=== selected",
+            "        > nested1",
+        ]);
+    });
+
+    // Values are only matched when asked to.
+    set_filter("Value 2", cx);
+    variable_list.update(cx, |list, _| {
+        list.assert_visual_entries(vec![]);
+    });
+    variable_list.update(cx, |list, cx| list.set_filter_values(true, cx));
+    cx.run_until_parked();
+    variable_list.update(cx, |list, _| {
+        list.assert_visual_entries(vec!["v Scope 1", "    > variable2"]);
+    });
+
+    // Clearing the filter shows the tree as it was expanded before.
+    set_filter("", cx);
+    variable_list.update(cx, |list, _| {
+        list.assert_visual_entries(vec![
+            "v Scope 1",
+            "    v variable1 <=== selected",
+            "        > nested1",
+            "        > nested2",
+            "    > variable2",
+            "> Scope 2",
+        ]);
+    });
+
+    let shutdown_session = project.update(cx, |project, cx| {
+        project.dap_store().update(cx, |dap_store, cx| {
+            dap_store.shutdown_session(session.read(cx).session_id(), cx)
+        })
+    });
+
+    shutdown_session.await.unwrap();
+}
+
+#[gpui::test]
+async fn test_expand_all_stops_at_depth_cap(executor: BackgroundExecutor, cx: &mut TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(executor.clone());
+    fs.insert_tree(
+        path!("/project"),
+        json!({
+           "src": {
+               "test.js": "const variable1 = { inner: { deep: { leaf: 1 } } };",
+           }
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, [path!("/project").as_ref()], cx).await;
+    let workspace = init_test_workspace(&project, cx).await;
+    workspace
+        .update(cx, |workspace, window, cx| {
+            workspace.focus_panel::<DebugPanel>(window, cx);
+        })
+        .unwrap();
+    let cx = &mut VisualTestContext::from_window(*workspace, cx);
+
+    let task = project.update(cx, |project, cx| {
+        project.start_debug_session(
+            dap::test_config(dap::DebugRequestType::Launch, None, None),
+            cx,
+        )
+    });
+
+    let session = task.await.unwrap();
+    let client = session.update(cx, |session, _| session.adapter_client().unwrap());
+
+    client
+        .on_request::<dap::requests::Threads, _>(move |_, _| {
+            Ok(dap::ThreadsResponse {
+                threads: vec![dap::Thread {
+                    id: 1,
+                    name: "Thread 1".into(),
+                }],
+            })
+        })
+        .await;
+
+    client.on_request::<Launch, _>(move |_, _| Ok(())).await;
+
+    client
+        .on_request::<StackTrace, _>(move |_, _| {
+            Ok(dap::StackTraceResponse {
+                stack_frames: vec![StackFrame {
+                    id: 1,
+                    name: "Stack Frame 1".into(),
+                    source: Some(dap::Source {
+                        name: Some("test.js".into()),
+                        path: Some(path!("/project/src/test.js").into()),
+                        source_reference: None,
+                        presentation_hint: None,
+                        origin: None,
+                        sources: None,
+                        adapter_data: None,
+                        checksums: None,
+                    }),
+                    line: 1,
+                    column: 1,
+                    end_line: None,
+                    end_column: None,
+                    can_restart: None,
+                    instruction_pointer_reference: None,
+                    module_id: None,
+                    presentation_hint: None,
+                }],
+                total_frames: None,
+            })
+        })
+        .await;
+
+    let scope = |name: &str, variables_reference: u64| Scope {
+        name: name.into(),
+        presentation_hint: None,
+        variables_reference,
+        named_variables: None,
+        indexed_variables: None,
+        expensive: false,
+        source: None,
+        line: None,
+        column: None,
+        end_line: None,
+        end_column: None,
+    };
+    let scopes = vec![scope("Scope 1", 2), scope("Scope 2", 6)];
+    client
+        .on_request::<Scopes, _>(move |_, _| {
+            Ok(dap::ScopesResponse {
+                scopes: scopes.clone(),
+            })
+        })
+        .await;
+
+    let variable = |name: &str, variables_reference: u64| Variable {
+        name: name.into(),
+        value: "value".into(),
+        type_: None,
+        presentation_hint: None,
+        evaluate_name: None,
+        variables_reference,
+        named_variables: None,
+        indexed_variables: None,
+        memory_reference: None,
+        declaration_location_reference: None,
+        value_location_reference: None,
+    };
+    let fetched_past_cap = Arc::new(AtomicBool::new(false));
+    client
+        .on_request::<Variables, _>({
+            let fetched_past_cap = fetched_past_cap.clone();
+            move |_, args| {
+                let variables = match args.variables_reference {
+                    2 => vec![variable("variable1", 3)],
+                    3 => vec![variable("inner", 4)],
+                    4 => vec![Variable {
+                        named_variables: Some(1),
+                        ..variable("deep", 5)
+                    }],
+                    5 => {
+                        fetched_past_cap.store(true, Ordering::SeqCst);
+                        vec![variable("leaf", 0)]
+                    }
+                    6 => vec![variable("variable2", 0)],
+                    id => unreachable!("unexpected variables reference {id}"),
+                };
+                Ok(dap::VariablesResponse { variables })
+            }
+        })
+        .await;
+
+    client
+        .fake_event(dap::messages::Events::Stopped(dap::StoppedEvent {
+            reason: dap::StoppedEventReason::Pause,
+            description: None,
+            thread_id: Some(1),
+            preserve_focus_hint: None,
+            text: None,
+            all_threads_stopped: None,
+            hit_breakpoint_ids: None,
+        }))
+        .await;
+
+    cx.run_until_parked();
+
+    let variable_list =
+        active_debug_session_panel(workspace, cx).update_in(cx, |item, window, cx| {
+            cx.focus_self(window);
+            let variable_list = item
+                .mode()
+                .as_running()
+                .expect("Session should be running by this point")
+                .read(cx)
+                .variable_list()
+                .clone();
+            variable_list.update(cx, |_, cx| cx.focus_self(window));
+            variable_list
+        });
+
+    variable_list.update(cx, |list, _| {
+        list.assert_visual_entries(vec!["> Scope 1", "> Scope 2"]);
+    });
+
+    cx.dispatch_action(ExpandAllEntries);
+    cx.run_until_parked();
+
+    variable_list.update(cx, |list, _| {
+        list.assert_visual_entries(vec![
+            "v Scope 1",
+            "    v variable1",
+            "        v inner",
+            "            > deep",
+            "v Scope 2",
+            "    > variable2",
+        ]);
+    });
+    assert!(
+        !fetched_past_cap.load(Ordering::SeqCst),
+        "Expanding all shouldn't fetch the children of variables at the depth cap"
+    );
+
+    cx.dispatch_action(CollapseAllEntries);
+    cx.run_until_parked();
+
+    variable_list.update(cx, |list, _| {
+        list.assert_visual_entries(vec!["> Scope 1", "> Scope 2"]);
+    });
+
+    let shutdown_session = project.update(cx, |project, cx| {
+        project.dap_store().update(cx, |dap_store, cx| {
+            dap_store.shutdown_session(session.read(cx).session_id(), cx)
+        })
+    });
+
+    shutdown_session.await.unwrap();
+}
@@ -0,0 +1,207 @@
+use crate::{
+    session::ThreadItem,
+    tests::{active_debug_session_panel, init_test, init_test_workspace},
+    DebugPanel,
+};
+use dap::{
+    requests::{Evaluate, Scopes, StackTrace, Threads},
+    StackFrame,
+};
+use gpui::{BackgroundExecutor, TestAppContext, VisualTestContext};
+use project::{FakeFs, Project};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+use util::path;
+
+#[gpui::test]
+async fn test_watches_are_evaluated_on_each_stop(
+    executor: BackgroundExecutor,
+    cx: &mut TestAppContext,
+) {
+    init_test(cx);
+
+    let fs = FakeFs::new(executor.clone());
+
+    let project = Project::test(fs, [path!("/project").as_ref()], cx).await;
+    let workspace = init_test_workspace(&project, cx).await;
+    workspace
+        .update(cx, |workspace, window, cx| {
+            workspace.focus_panel::<DebugPanel>(window, cx);
+        })
+        .unwrap();
+    let cx = &mut VisualTestContext::from_window(*workspace, cx);
+
+    let task = project.update(cx, |project, cx| {
+        project.start_debug_session(
+            dap::test_config(dap::DebugRequestType::Launch, None, None),
+            cx,
+        )
+    });
+
+    let session = task.await.unwrap();
+    let client = session.update(cx, |session, _| session.adapter_client().unwrap());
+
+    client
+        .on_request::<Threads, _>(move |_, _| {
+            Ok(dap::ThreadsResponse {
+                threads: vec![dap::Thread {
+                    id: 1,
+                    name: "Thread 1".into(),
+                }],
+            })
+        })
+        .await;
+
+    client
+        .on_request::<StackTrace, _>(move |_, _| {
+            Ok(dap::StackTraceResponse {
+                stack_frames: vec![StackFrame {
+                    id: 1,
+                    name: "Stack Frame 1".into(),
+                    source: None,
+                    line: 1,
+                    column: 1,
+                    end_line: None,
+                    end_column: None,
+                    can_restart: None,
+                    instruction_pointer_reference: None,
+                    module_id: None,
+                    presentation_hint: None,
+                }],
+                total_frames: None,
+            })
+        })
+        .await;
+
+    client
+        .on_request::<Scopes, _>(move |_, _| Ok(dap::ScopesResponse { scopes: vec![] }))
+        .await;
+
+    let stop_count = Arc::new(AtomicUsize::new(0));
+
+    client
+        .on_request::<Evaluate, _>({
+            let stop_count = stop_count.clone();
+            move |_, args| {
+                assert_eq!(Some(dap::EvaluateArgumentsContext::Watch), args.context);
+                assert_eq!(Some(1), args.frame_id);
+
+                if args.expression != "counter" {
+                    return Err(dap::ErrorResponse {
+                        error: Some(dap::Message {
+                            id: 1,
+                            format: format!("{} is not defined", args.expression),
+                            variables: None,
+                            send_telemetry: None,
+                            show_user: None,
+                            url: None,
+                            url_label: None,
+                        }),
+                    });
+                }
+
+                Ok(dap::EvaluateResponse {
+                    result: stop_count.load(Ordering::SeqCst).to_string(),
+                    type_: None,
+                    presentation_hint: None,
+                    variables_reference: 0,
+                    named_variables: None,
+                    indexed_variables: None,
+                    memory_reference: None,
+                    value_location_reference: None,
+                })
+            }
+        })
+        .await;
+
+    let stop = || {
+        dap::messages::Events::Stopped(dap::StoppedEvent {
+            reason: dap::StoppedEventReason::Pause,
+            description: None,
+            thread_id: Some(1),
+            preserve_focus_hint: None,
+            text: None,
+            all_threads_stopped: None,
+            hit_breakpoint_ids: None,
+        })
+    };
+
+    stop_count.store(1, Ordering::SeqCst);
+    client.fake_event(stop()).await;
+
+    cx.run_until_parked();
+
+    let running_state = active_debug_session_panel(workspace, cx).update(cx, |item, _| {
+        item.mode()
+            .as_running()
+            .expect("Session should be running by this point")
+            .clone()
+    });
+
+    running_state.update(cx, |state, cx| {
+        state.set_thread_item(ThreadItem::Watches, cx);
+        cx.refresh_windows();
+    });
+
+    cx.run_until_parked();
+
+    let watch_list = running_state.update(cx, |state, _| state.watch_list().clone());
+
+    for expression in ["counter", "missing"] {
+        cx.update(|window, cx| {
+            watch_list.update(cx, |watch_list, cx| {
+                watch_list
+                    .watch_editor()
+                    .update(cx, |editor, cx| editor.set_text(expression, window, cx));
+                watch_list.confirm(window, cx);
+            });
+        });
+    }
+
+    cx.run_until_parked();
+
+    watch_list.update(cx, |watch_list, _| {
+        let watches = watch_list.watches();
+        assert_eq!(2, watches.len());
+        assert_eq!(("counter".to_string(), Some("1".to_string())), watches[0]);
+        assert_eq!("missing", watches[1].0);
+        assert!(watches[1].1.as_ref().is_some_and(
+            |value| value.starts_with("Error: ") && value.contains("missing is not defined")
+        ));
+    });
+
+    // Switching tabs must not drop the watches, and the next stop re-evaluates them.
+    running_state.update(cx, |state, cx| {
+        state.set_thread_item(ThreadItem::Variables, cx);
+    });
+
+    stop_count.store(2, Ordering::SeqCst);
+    client.fake_event(stop()).await;
+
+    cx.run_until_parked();
+
+    running_state.update(cx, |state, cx| {
+        state.set_thread_item(ThreadItem::Watches, cx);
+    });
+
+    watch_list.update(cx, |watch_list, _| {
+        assert_eq!(
+            Some(Some("2".to_string())),
+            watch_list
+                .watches()
+                .into_iter()
+                .find(|(expression, _)| expression == "counter")
+                .map(|(_, value)| value)
+        );
+    });
+
+    let shutdown_session = project.update(cx, |project, cx| {
+        project.dap_store().update(cx, |dap_store, cx| {
+            dap_store.shutdown_session(session.read(cx).session_id(), cx)
+        })
+    });
+
+    shutdown_session.await.unwrap();
+}
@@ -144,7 +144,7 @@ use multi_buffer::{
 };
 use parking_lot::Mutex;
 use project::{
-    debugger::breakpoint_store::{Breakpoint, BreakpointKind},
+    debugger::breakpoint_store::{Breakpoint, BreakpointKind, BreakpointState},
     lsp_store::{CompletionDocumentation, FormatTrigger, LspFormatTarget, OpenLspBufferHandle},
     project_settings::{GitGutterSetting, ProjectSettings},
     CodeAction, Completion, CompletionIntent, CompletionSource, DocumentHighlight, InlayHint,
@@ -615,6 +615,19 @@ pub trait Addon: 'static {
         None
     }
 
+    /// Content shown in the hover popover along with the language servers' hovers, for the
+    /// symbol at `position` in `buffer`. Only requested once the hover delay passed, and the task
+    /// is dropped when the pointer moves on before it completes.
+    fn hover(
+        &self,
+        _: &Editor,
+        _: &Entity<Buffer>,
+        _: text::Anchor,
+        _: &mut App,
+    ) -> Option<Task<Vec<project::Hover>>> {
+        None
+    }
+
     fn to_any(&self) -> &dyn std::any::Any;
 }
 
@@ -8478,6 +8491,9 @@ impl Editor {
                     breakpoint_position,
                     Breakpoint {
                         kind: BreakpointKind::Standard,
+                        condition: None,
+                        hit_condition: None,
+                        state: BreakpointState::Enabled,
                     },
                 )
             });
@@ -8545,7 +8561,15 @@ impl Editor {
         breakpoint_store.update(cx, |breakpoint_store, cx| {
             breakpoint_store.toggle_breakpoint(
                 buffer,
-                (breakpoint_position.text_anchor, Breakpoint { kind }),
+                (
+                    breakpoint_position.text_anchor,
+                    Breakpoint {
+                        kind,
+                        condition: None,
+                        hit_condition: None,
+                        state: BreakpointState::Enabled,
+                    },
+                ),
                 edit_action,
                 cx,
             );
@@ -57,7 +57,7 @@ use multi_buffer::{
     MultiBufferRow, RowInfo,
 };
 use project::{
-    debugger::breakpoint_store::{Breakpoint, BreakpointKind},
+    debugger::breakpoint_store::{Breakpoint, BreakpointKind, BreakpointState},
     project_settings::{self, GitGutterSetting, GitHunkStyleSetting, ProjectSettings},
 };
 use settings::Settings;
@@ -7022,6 +7022,9 @@ impl Element for EditorElement {
                                     );
                                     let breakpoint = Breakpoint {
                                         kind: BreakpointKind::Standard,
+                                        condition: None,
+                                        hit_condition: None,
+                                        state: BreakpointState::Enabled,
                                     };
 
                                     (position, breakpoint)
@@ -281,6 +281,18 @@ fn show_hover(
                 delay.await;
             }
 
+            // Addons may do more work per hover than a language server, e.g. evaluating the
+            // hovered expression in a debugger, so they're only asked once the pointer rested.
+            // Their tasks are held by this one, and dropped with it when the hover moves on.
+            let addon_hover_requests = this.update(&mut cx, |editor, cx| {
+                let editor = &*editor;
+                editor
+                    .addons
+                    .values()
+                    .filter_map(|addon| addon.hover(editor, &buffer, buffer_position, cx))
+                    .collect::<Vec<_>>()
+            })?;
+
             let offset = anchor.to_offset(&snapshot.buffer_snapshot);
             let local_diagnostic = snapshot
                 .buffer_snapshot
@@ -404,11 +416,14 @@ fn show_hover(
                 None
             };
 
-            let hovers_response = if let Some(hover_request) = hover_request {
+            let mut hovers_response = if let Some(hover_request) = hover_request {
                 hover_request.await
             } else {
                 Vec::new()
             };
+            for addon_hover_request in addon_hover_requests {
+                hovers_response.extend(addon_hover_request.await);
+            }
             let snapshot = this.update_in(&mut cx, |this, window, cx| this.snapshot(window, cx))?;
             let mut hover_highlights = Vec::with_capacity(hovers_response.len());
             let mut info_popovers = Vec::with_capacity(
@@ -922,7 +937,7 @@ mod tests {
         hover_links::update_inlay_link_and_hover_points,
         inlay_hint_cache::tests::{cached_hint_labels, visible_hint_labels},
         test::editor_lsp_test_context::EditorLspTestContext,
-        InlayId, PointForPosition,
+        Addon, InlayId, PointForPosition,
     };
     use collections::BTreeSet;
     use gpui::App;
@@ -960,6 +975,78 @@ mod tests {
         }
     }
 
+    #[gpui::test]
+    async fn test_addon_hovers_are_requested_after_delay(cx: &mut gpui::TestAppContext) {
+        init_test(cx, |_| {});
+
+        struct CountingHoverAddon(Arc<AtomicUsize>);
+
+        impl Addon for CountingHoverAddon {
+            fn hover(
+                &self,
+                _: &Editor,
+                _: &Entity<language::Buffer>,
+                _: text::Anchor,
+                _: &mut App,
+            ) -> Option<Task<Vec<project::Hover>>> {
+                self.0.fetch_add(1, atomic::Ordering::SeqCst);
+                Some(Task::ready(vec![project::Hover {
+                    contents: vec![HoverBlock {
+                        text: "addon docs".to_string(),
+                        kind: HoverBlockKind::PlainText,
+                    }],
+                    range: None,
+                    language: None,
+                }]))
+            }
+
+            fn to_any(&self) -> &dyn std::any::Any {
+                self
+            }
+        }
+
+        let mut cx = EditorLspTestContext::new_rust(lsp::ServerCapabilities::default(), cx).await;
+        let requests = Arc::new(AtomicUsize::new(0));
+        cx.update_editor(|editor, _, _| {
+            editor.register_addon(CountingHoverAddon(requests.clone()));
+        });
+        cx.set_state(indoc! {"
+            fn ˇtest() { println!(); }
+        "});
+
+        let hover = |marked_text: &str, cx: &mut EditorLspTestContext| {
+            let hover_point = cx.display_point(marked_text);
+            cx.update_editor(|editor, window, cx| {
+                let snapshot = editor.snapshot(window, cx);
+                let anchor = snapshot
+                    .buffer_snapshot
+                    .anchor_before(hover_point.to_offset(&snapshot, Bias::Left));
+                hover_at(editor, Some(anchor), window, cx)
+            });
+        };
+
+        // Passing over a symbol on the way to another doesn't ask the addon about it.
+        hover("fn teˇst() { println!(); }", &mut cx);
+        cx.background_executor
+            .advance_clock(Duration::from_millis(HOVER_REQUEST_DELAY_MILLIS + 10));
+        cx.run_until_parked();
+        assert_eq!(requests.load(atomic::Ordering::SeqCst), 0);
+
+        hover("fn test() { priˇntln!(); }", &mut cx);
+        cx.background_executor
+            .advance_clock(Duration::from_millis(get_hover_popover_delay(&cx) + 100));
+        cx.run_until_parked();
+
+        assert_eq!(requests.load(atomic::Ordering::SeqCst), 1);
+        cx.editor(|editor, _window, cx| {
+            assert_eq!(editor.hover_state.info_popovers.len(), 1);
+            assert_eq!(
+                editor.hover_state.info_popovers[0].get_rendered_text(cx),
+                "addon docs"
+            );
+        });
+    }
+
     #[gpui::test]
     async fn test_mouse_hover_info_popover_with_autocomplete_popover(
         cx: &mut gpui::TestAppContext,
@@ -248,9 +248,10 @@ impl BreakpointStore {
         match edit_action {
             BreakpointEditAction::Toggle => {
                 let len_before = breakpoint_set.breakpoints.len();
-                breakpoint_set
-                    .breakpoints
-                    .retain(|value| &breakpoint != value);
+                // Conditions don't matter here, so a conditional breakpoint is toggled off too.
+                breakpoint_set.breakpoints.retain(|(position, other)| {
+                    *position != breakpoint.0 || other.kind != breakpoint.1.kind
+                });
                 if len_before == breakpoint_set.breakpoints.len() {
                     // We did not remove any breakpoint, hence let's toggle one.
                     breakpoint_set.breakpoints.push(breakpoint.clone());
@@ -287,6 +288,25 @@ impl BreakpointStore {
                         });
                 }
             }
+            BreakpointEditAction::EditCondition(condition) => {
+                let condition = (!condition.is_empty()).then_some(condition);
+                breakpoint.1.condition = condition.clone();
+                Self::edit_breakpoint_at(breakpoint_set, &breakpoint, |other| {
+                    other.condition = condition
+                });
+            }
+            BreakpointEditAction::EditHitCondition(hit_condition) => {
+                let hit_condition = (!hit_condition.is_empty()).then_some(hit_condition);
+                breakpoint.1.hit_condition = hit_condition.clone();
+                Self::edit_breakpoint_at(breakpoint_set, &breakpoint, |other| {
+                    other.hit_condition = hit_condition
+                });
+            }
+            BreakpointEditAction::InvertState => {
+                let state = breakpoint.1.state.invert();
+                breakpoint.1.state = state;
+                Self::edit_breakpoint_at(breakpoint_set, &breakpoint, |other| other.state = state);
+            }
         }
 
         if breakpoint_set.breakpoints.is_empty() {
@@ -328,6 +348,49 @@ impl BreakpointStore {
         cx.notify();
     }
 
+    /// Edits the breakpoint on `row` of a file, for when there's no editor to take its position
+    /// from, e.g. in a list of all breakpoints.
+    pub fn edit_breakpoint_at_row(
+        &mut self,
+        abs_path: &Arc<Path>,
+        row: u32,
+        edit_action: BreakpointEditAction,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(breakpoint_set) = self.breakpoints.get(abs_path) else {
+            return;
+        };
+        let buffer = breakpoint_set.buffer.clone();
+        let snapshot = buffer.read(cx).snapshot();
+        let Some(breakpoint) = breakpoint_set
+            .breakpoints
+            .iter()
+            .find(|(position, _)| snapshot.summary_for_anchor::<PointUtf16>(position).row == row)
+            .cloned()
+        else {
+            return;
+        };
+
+        self.toggle_breakpoint(buffer, breakpoint, edit_action, cx);
+    }
+
+    /// Edits the breakpoint at the position of `breakpoint`, or adds `breakpoint` if there's none.
+    fn edit_breakpoint_at(
+        breakpoint_set: &mut BreakpointsInFile,
+        breakpoint: &(text::Anchor, Breakpoint),
+        edit: impl FnOnce(&mut Breakpoint),
+    ) {
+        if let Some((_, other)) = breakpoint_set
+            .breakpoints
+            .iter_mut()
+            .find(|(position, _)| *position == breakpoint.0)
+        {
+            edit(other);
+        } else {
+            breakpoint_set.breakpoints.push(breakpoint.clone());
+        }
+    }
+
     pub fn on_file_rename(
         &mut self,
         old_path: Arc<Path>,
@@ -411,6 +474,9 @@ impl BreakpointStore {
                             position,
                             path: path.clone(),
                             kind: breakpoint.kind.clone(),
+                            condition: breakpoint.condition.clone(),
+                            hit_condition: breakpoint.hit_condition.clone(),
+                            state: breakpoint.state,
                         }
                     })
                     .collect()
@@ -433,6 +499,9 @@ impl BreakpointStore {
                                 position,
                                 path: path.clone(),
                                 kind: breakpoint.kind.clone(),
+                                condition: breakpoint.condition.clone(),
+                                hit_condition: breakpoint.hit_condition.clone(),
+                                state: breakpoint.state,
                             }
                         })
                         .collect(),
@@ -481,9 +550,15 @@ impl BreakpointStore {
 
                     for bp in bps {
                         let position = snapshot.anchor_before(PointUtf16::new(bp.position, 0));
-                        breakpoints_for_file
-                            .breakpoints
-                            .push((position, Breakpoint { kind: bp.kind }))
+                        breakpoints_for_file.breakpoints.push((
+                            position,
+                            Breakpoint {
+                                kind: bp.kind,
+                                condition: bp.condition,
+                                hit_condition: bp.hit_condition,
+                                state: bp.state,
+                            },
+                        ))
                     }
                     new_breakpoints.insert(path, breakpoints_for_file);
                 }
@@ -519,6 +594,13 @@ type LogMessage = Arc<str>;
 pub enum BreakpointEditAction {
     Toggle,
     EditLogMessage(LogMessage),
+    /// Sets the expression that must hold for the breakpoint to be hit, or clears it when empty.
+    EditCondition(Arc<str>),
+    /// Sets how many hits the breakpoint ignores, e.g. `>= 5`, or clears it when empty.
+    EditHitCondition(Arc<str>),
+    /// Turns the breakpoint off, or back on. A disabled breakpoint stays in the editor, but isn't
+    /// sent to debug adapters.
+    InvertState,
 }
 
 #[derive(Clone, Debug)]
@@ -557,9 +639,41 @@ impl Hash for BreakpointKind {
     }
 }
 
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq)]
+pub enum BreakpointState {
+    #[default]
+    Enabled,
+    Disabled,
+}
+
+impl BreakpointState {
+    pub fn is_enabled(&self) -> bool {
+        matches!(self, BreakpointState::Enabled)
+    }
+
+    pub fn invert(&self) -> Self {
+        match self {
+            BreakpointState::Enabled => BreakpointState::Disabled,
+            BreakpointState::Disabled => BreakpointState::Enabled,
+        }
+    }
+
+    pub fn to_int(&self) -> i32 {
+        match self {
+            BreakpointState::Enabled => 0,
+            BreakpointState::Disabled => 1,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
 pub struct Breakpoint {
     pub kind: BreakpointKind,
+    /// An expression the adapter evaluates on each hit, stopping only when it's true.
+    pub condition: Option<Arc<str>>,
+    /// An expression controlling how many hits are ignored, interpreted by the adapter.
+    pub hit_condition: Option<Arc<str>>,
+    pub state: BreakpointState,
 }
 
 impl Breakpoint {
@@ -576,6 +690,12 @@ impl Breakpoint {
             } else {
                 None
             },
+            condition: self.condition.as_ref().map(ToString::to_string),
+            hit_condition: self.hit_condition.as_ref().map(ToString::to_string),
+            state: match self.state {
+                BreakpointState::Enabled => proto::BreakpointState::Enabled.into(),
+                BreakpointState::Disabled => proto::BreakpointState::Disabled.into(),
+            },
         })
     }
 
@@ -587,6 +707,12 @@ impl Breakpoint {
                 }
                 None | Some(proto::BreakpointKind::Standard) => BreakpointKind::Standard,
             },
+            condition: breakpoint.condition.map(Into::into),
+            hit_condition: breakpoint.hit_condition.map(Into::into),
+            state: match proto::BreakpointState::from_i32(breakpoint.state) {
+                Some(proto::BreakpointState::Disabled) => BreakpointState::Disabled,
+                None | Some(proto::BreakpointState::Enabled) => BreakpointState::Enabled,
+            },
         })
     }
 }
@@ -596,6 +722,9 @@ pub struct SerializedBreakpoint {
     pub position: u32,
     pub path: Arc<Path>,
     pub kind: BreakpointKind,
+    pub condition: Option<Arc<str>>,
+    pub hit_condition: Option<Arc<str>>,
+    pub state: BreakpointState,
 }
 
 impl From<SerializedBreakpoint> for dap::SourceBreakpoint {
@@ -603,8 +732,8 @@ impl From<SerializedBreakpoint> for dap::SourceBreakpoint {
         Self {
             line: bp.position as u64 + 1,
             column: None,
-            condition: None,
-            hit_condition: None,
+            condition: bp.condition.as_deref().map(Into::into),
+            hit_condition: bp.hit_condition.as_deref().map(Into::into),
             log_message: bp.kind.log_message().as_deref().map(Into::into),
             mode: None,
         }
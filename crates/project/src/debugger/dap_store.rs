@@ -20,6 +20,7 @@ use dap::{
         Completions, Evaluate, Request as _, RunInTerminal, SetExpression, SetVariable,
         StartDebugging,
     },
+    transport::AdapterStartError,
     Capabilities, CompletionItem, CompletionsArguments, ErrorResponse, EvaluateArguments,
     EvaluateArgumentsContext, EvaluateResponse, RunInTerminalRequestArguments,
     SetExpressionArguments, SetVariableArguments, Source, StartDebuggingRequestArguments,
@@ -333,6 +334,60 @@ impl DapStore {
         let Some(local_store) = self.as_local() else {
             unimplemented!("Starting session on remote side");
         };
+        let session_id = local_store.next_session_id();
+        let task = self.start_session(session_id, config, worktree, parent_session, cx);
+        let task = cx.spawn(|this, mut cx| async move {
+            let result = task.await;
+            if let Err(error) = &result {
+                this.update(&mut cx, |this, cx| this.notify_start_failure(error, cx))
+                    .log_err();
+            }
+            result
+        });
+        (session_id, task)
+    }
+
+    /// Allocates the ID of a session started with [`Self::start_session`].
+    pub fn next_session_id(&self) -> Result<SessionId> {
+        let Some(local_store) = self.as_local() else {
+            return Err(anyhow!(
+                "Starting sessions on the remote side isn't supported"
+            ));
+        };
+        Ok(local_store.next_session_id())
+    }
+
+    /// Reports a session that failed to start. Callers of [`Self::start_session`] do this once
+    /// they stop retrying.
+    pub fn notify_start_failure(&mut self, error: &anyhow::Error, cx: &mut Context<Self>) {
+        // The adapter's stderr is shown with the failed session rather than in the notification.
+        let message = error
+            .chain()
+            .find(|cause| !cause.is::<AdapterStartError>())
+            .map_or_else(|| error.to_string(), ToString::to_string);
+        cx.emit(DapStoreEvent::Notification(message));
+    }
+
+    /// Starts a session under an ID that isn't in use, e.g. to retry a session that failed to
+    /// start under the same ID, so views keyed by it keep pointing at it. Unlike
+    /// [`Self::new_session`], failures aren't notified, so retrying them doesn't notify each time.
+    pub fn start_session(
+        &mut self,
+        session_id: SessionId,
+        config: DebugAdapterConfig,
+        worktree: &Entity<Worktree>,
+        parent_session: Option<Entity<Session>>,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<Entity<Session>>> {
+        let Some(local_store) = self.as_local() else {
+            return Task::ready(Err(anyhow!(
+                "Starting sessions on the remote side isn't supported"
+            )));
+        };
+        debug_assert!(
+            !self.sessions.contains_key(&session_id),
+            "session {session_id:?} is already running"
+        );
 
         let delegate = DapAdapterDelegate::new(
             local_store.fs.clone(),
@@ -346,7 +401,6 @@ impl DapStore {
                 env.get_environment(Some(worktree.id()), Some(worktree.abs_path()), cx)
             }),
         );
-        let session_id = local_store.next_session_id();
 
         let (initialized_tx, initialized_rx) = oneshot::channel();
 
@@ -361,18 +415,8 @@ impl DapStore {
             cx,
         );
 
-        let task = cx.spawn(|this, mut cx| async move {
-            let session = match start_client_task.await {
-                Ok(session) => session,
-                Err(error) => {
-                    this.update(&mut cx, |_, cx| {
-                        cx.emit(DapStoreEvent::Notification(error.to_string()));
-                    })
-                    .log_err();
-
-                    return Err(error);
-                }
-            };
+        cx.spawn(|this, mut cx| async move {
+            let session = start_client_task.await?;
 
             // we have to insert the session early, so we can handle reverse requests
             // that need the session to be available
@@ -390,21 +434,17 @@ impl DapStore {
             {
                 Ok(_) => {}
                 Err(error) => {
-                    this.update(&mut cx, |this, cx| {
-                        cx.emit(DapStoreEvent::Notification(error.to_string()));
+                    let stderr = session.read_with(&cx, |session, _| session.adapter_stderr())?;
+                    this.update(&mut cx, |this, cx| this.shutdown_session(session_id, cx))?
+                        .await
+                        .log_err();
 
-                        this.shutdown_session(session_id, cx)
-                    })?
-                    .await
-                    .log_err();
-
-                    return Err(error);
+                    return Err(AdapterStartError::attach(error, stderr));
                 }
             }
 
             Ok(session)
-        });
-        (session_id, task)
+        })
     }
 
     fn handle_start_debugging_request(
@@ -449,6 +489,7 @@ impl DapStore {
                 cwd: config.cwd,
                 initialize_args: Some(args.configuration),
                 supports_attach: config.supports_attach,
+                stop_on_entry: false,
             },
             &worktree,
             Some(parent_session.clone()),
@@ -1,34 +1,38 @@
 use crate::project_settings::ProjectSettings;
 
-use super::breakpoint_store::{BreakpointStore, BreakpointStoreEvent, BreakpointUpdatedReason};
+use super::breakpoint_store::{
+    BreakpointStore, BreakpointStoreEvent, BreakpointUpdatedReason, SerializedBreakpoint,
+};
 use super::dap_command::{
     self, Attach, ConfigurationDone, ContinueCommand, DapCommand, DisconnectCommand,
     EvaluateCommand, Initialize, Launch, LoadedSourcesCommand, LocalDapCommand, LocationsCommand,
     ModulesCommand, NextCommand, PauseCommand, RestartCommand, RestartStackFrameCommand,
-    ScopesCommand, SetVariableValueCommand, StackTraceCommand, StepBackCommand, StepCommand,
-    StepInCommand, StepOutCommand, TerminateCommand, TerminateThreadsCommand, ThreadsCommand,
-    VariablesCommand,
+    ScopesCommand, SetVariableValueCommand, SourceContentCommand, StackTraceCommand,
+    StepBackCommand, StepCommand, StepInCommand, StepOutCommand, TerminateCommand,
+    TerminateThreadsCommand, ThreadsCommand, VariablesCommand,
 };
 use super::dap_store::DapAdapterDelegate;
 use anyhow::{anyhow, Result};
 use collections::{HashMap, IndexMap, IndexSet};
 use dap::adapters::{DebugAdapter, DebugAdapterBinary};
 use dap::messages::Response;
-use dap::OutputEventCategory;
 use dap::{
     adapters::{DapDelegate, DapStatus},
     client::{DebugAdapterClient, SessionId},
     messages::{Events, Message},
-    Capabilities, ContinueArguments, EvaluateArgumentsContext, Module, Source, StackFrameId,
-    SteppingGranularity, StoppedEvent, VariableReference,
+    Capabilities, ContinueArguments, DataBreakpointAccessType, EvaluateArgumentsContext, Module,
+    Source, StackFrameId, SteppingGranularity, StoppedEvent, VariableReference,
 };
 use dap_adapters::build_adapter;
+use db::kvp::KEY_VALUE_STORE;
 use futures::channel::oneshot;
 use futures::{future::Shared, FutureExt};
 use gpui::{
     App, AppContext, AsyncApp, BackgroundExecutor, Context, Entity, EventEmitter, Task, WeakEntity,
 };
+use language::Buffer;
 use rpc::AnyProtoClient;
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use settings::Settings;
 use smol::stream::StreamExt;
@@ -166,6 +170,23 @@ pub struct LocalMode {
     breakpoint_store: Entity<BreakpointStore>,
 }
 
+/// Adapters may report the same source several times, so sources are matched by their reference
+/// when they have one and by their path otherwise.
+fn is_same_source(a: &Source, b: &Source) -> bool {
+    match (
+        a.source_reference.filter(|reference| *reference > 0),
+        b.source_reference.filter(|reference| *reference > 0),
+    ) {
+        (Some(a), Some(b)) => a == b,
+        (None, None) => match (&a.path, &b.path) {
+            (Some(a), Some(b)) => a == b,
+            (None, None) => a.name == b.name,
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
 fn client_source(abs_path: &Path) -> dap::Source {
     dap::Source {
         name: abs_path
@@ -307,70 +328,23 @@ impl LocalMode {
         })
     }
 
-    fn send_breakpoints_from_path(
+    /// Replaces the breakpoints the adapter has for a file, resolving to the breakpoints as the
+    /// adapter reports them, in the order they were sent.
+    fn send_breakpoints(
         &self,
-        abs_path: Arc<Path>,
-        reason: BreakpointUpdatedReason,
-        cx: &mut App,
-    ) -> Task<()> {
-        let breakpoints = self
-            .breakpoint_store
-            .read_with(cx, |store, cx| store.breakpoints_from_path(&abs_path, cx))
-            .into_iter()
-            .map(Into::into)
-            .collect();
-
-        let task = self.request(
+        abs_path: &Path,
+        breakpoints: Vec<SerializedBreakpoint>,
+        source_modified: bool,
+        cx: &App,
+    ) -> Task<Result<Vec<dap::Breakpoint>>> {
+        self.request(
             dap_command::SetBreakpoints {
-                source: client_source(&abs_path),
-                source_modified: Some(matches!(reason, BreakpointUpdatedReason::FileSaved)),
-                breakpoints,
+                source: client_source(abs_path),
+                source_modified: Some(source_modified),
+                breakpoints: breakpoints.into_iter().map(Into::into).collect(),
             },
             cx.background_executor().clone(),
-        );
-
-        cx.background_spawn(async move {
-            match task.await {
-                Ok(_) => {}
-                Err(err) => log::warn!("Set breakpoints request failed for path: {}", err),
-            }
-        })
-    }
-
-    fn send_all_breakpoints(&self, ignore_breakpoints: bool, cx: &App) -> Task<()> {
-        let mut breakpoint_tasks = Vec::new();
-        let breakpoints = self
-            .breakpoint_store
-            .read_with(cx, |store, cx| store.all_breakpoints(cx));
-
-        for (path, breakpoints) in breakpoints {
-            let breakpoints = if ignore_breakpoints {
-                vec![]
-            } else {
-                breakpoints.into_iter().map(Into::into).collect()
-            };
-
-            breakpoint_tasks.push(self.request(
-                dap_command::SetBreakpoints {
-                    source: client_source(&path),
-                    source_modified: Some(false),
-                    breakpoints,
-                },
-                cx.background_executor().clone(),
-            ));
-        }
-
-        cx.background_spawn(async move {
-            futures::future::join_all(breakpoint_tasks)
-                .await
-                .iter()
-                .for_each(|res| match res {
-                    Ok(_) => {}
-                    Err(err) => {
-                        log::warn!("Set breakpoints request failed: {}", err);
-                    }
-                });
-        })
+        )
     }
 
     async fn get_adapter_binary(
@@ -417,6 +391,7 @@ impl LocalMode {
         &self,
         capabilities: &Capabilities,
         initialized_rx: oneshot::Receiver<()>,
+        session: WeakEntity<Session>,
         cx: &App,
     ) -> Task<Result<()>> {
         let mut raw = self.adapter.request_args(&self.config);
@@ -424,6 +399,11 @@ impl LocalMode {
             self.config.initialize_args.clone().unwrap_or(json!({})),
             &mut raw,
         );
+        if self.config.stop_on_entry {
+            if let Some(raw) = raw.as_object_mut() {
+                raw.insert("stopOnEntry".into(), json!(true));
+            }
+        }
 
         // Of relevance: https://github.com/microsoft/vscode/issues/4902#issuecomment-368583522
         let launch = match &self.config.request {
@@ -437,13 +417,32 @@ impl LocalMode {
 
         let configuration_done_supported = ConfigurationDone::is_supported(capabilities);
 
+        let key = exception_filters_key(&self.config);
+        let saved_exception_filters = cx.background_spawn(async move {
+            KEY_VALUE_STORE
+                .read_kvp(&key)
+                .log_err()
+                .flatten()
+                .and_then(|value| serde_json::from_str::<Vec<ExceptionFilter>>(&value).log_err())
+        });
+
         let configuration_sequence = cx.spawn({
             let this = self.clone();
-            move |cx| async move {
+            move |mut cx| async move {
                 initialized_rx.await?;
+                let saved_exception_filters = saved_exception_filters.await;
                 // todo(debugger) figure out if we want to handle a breakpoint response error
                 // This will probably consist of letting a user know that breakpoints failed to be set
-                cx.update(|cx| this.send_all_breakpoints(false, cx))?.await;
+                session
+                    .update(&mut cx, |session, cx| {
+                        // The exception filters chosen last time apply to exceptions thrown while
+                        // the debuggee starts, so they're sent with the breakpoints.
+                        if let Some(filters) = saved_exception_filters {
+                            session.exception_filters = Some(filters);
+                        }
+                        session.send_all_breakpoints(cx)
+                    })?
+                    .await;
 
                 if configuration_done_supported {
                     this.request(ConfigurationDone, cx.background_executor().clone())
@@ -579,6 +578,14 @@ pub struct Session {
     id: SessionId,
     parent_id: Option<SessionId>,
     ignore_breakpoints: bool,
+    /// Whether the adapter verified each breakpoint it was sent, by file and row.
+    breakpoint_verification: HashMap<(Arc<Path>, u32), bool>,
+    /// The file and row of each breakpoint the adapter identified, by the adapter's id, for
+    /// updating its verification when the adapter reports a change to it.
+    breakpoint_ids: HashMap<u64, (Arc<Path>, u32)>,
+    data_breakpoints: Vec<DataBreakpoint>,
+    /// The exception filters chosen for this session, or `None` to use the adapter's defaults.
+    exception_filters: Option<Vec<ExceptionFilter>>,
     modules: Vec<dap::Module>,
     loaded_sources: Vec<dap::Source>,
     output_token: OutputToken,
@@ -588,7 +595,13 @@ pub struct Session {
     variables: HashMap<VariableReference, Vec<dap::Variable>>,
     stack_frames: IndexMap<StackFrameId, StackFrame>,
     locations: HashMap<u64, dap::LocationsResponse>,
+    /// Contents of sources that aren't available on disk, by source reference.
+    fetched_sources: HashMap<u64, Shared<Task<Option<Arc<str>>>>>,
+    /// Read-only buffers showing fetched sources, by source reference.
+    source_buffers: HashMap<u64, WeakEntity<Buffer>>,
     is_session_terminated: bool,
+    /// The event the debuggee last stopped with, cleared when it continues.
+    last_stop: Option<StoppedEvent>,
     requests: HashMap<TypeId, HashMap<RequestSlot, Shared<Task<Option<()>>>>>,
     _background_tasks: Vec<Task<()>>,
 }
@@ -670,13 +683,69 @@ impl CompletionsQuery {
     }
 }
 
+/// An exception breakpoint filter turned on for a session, such as breaking on uncaught
+/// exceptions.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExceptionFilter {
+    /// The id of one of the adapter's `exceptionBreakpointFilters`.
+    pub filter_id: String,
+    /// Only break on exceptions this expression is true for. Sent when the adapter supports
+    /// exception filter options.
+    pub condition: Option<String>,
+}
+
+/// The exception filters chosen for an adapter and debug configuration are remembered under this
+/// key, for sessions of the same configuration to start with.
+fn exception_filters_key(config: &DebugAdapterConfig) -> String {
+    format!(
+        "debugger_exception_filters_{}_{}",
+        config.kind.display_name(),
+        config.label
+    )
+}
+
+/// A breakpoint that stops when a variable is accessed rather than when a line is reached, also
+/// known as a watchpoint.
+#[derive(Clone, Debug)]
+pub struct DataBreakpoint {
+    pub data_id: String,
+    /// The adapter's description of what is watched, usually the variable's name.
+    pub description: String,
+    /// The kind of access that stops, or `None` for the adapter's default.
+    pub access_type: Option<DataBreakpointAccessType>,
+    /// Whether the adapter verified the breakpoint, or `None` before it responded.
+    pub verified: Option<bool>,
+}
+
+/// Prefers stopping on writes, i.e. when the value changes, if the adapter offers a choice.
+fn preferred_access_type(
+    access_types: Option<&[DataBreakpointAccessType]>,
+) -> Option<DataBreakpointAccessType> {
+    let access_types = access_types?;
+    access_types
+        .iter()
+        .find(|access_type| matches!(access_type, DataBreakpointAccessType::Write))
+        .or(access_types.first())
+        .cloned()
+}
+
 pub enum SessionEvent {
     Modules,
     LoadedSources,
     Stopped(Option<ThreadId>),
+    /// The adapter resumed the debuggee.
+    Continued,
     StackTrace,
     Variables,
     Threads,
+    /// Breakpoints were added or removed, sent to the adapter, or changed by it.
+    Breakpoints,
+    /// The debuggee exited with this exit code.
+    Exited {
+        exit_code: u64,
+    },
+    /// The adapter ended the debug session.
+    Terminated,
 }
 
 impl EventEmitter<SessionEvent> for Session {}
@@ -738,14 +807,11 @@ impl Session {
 
                 cx.subscribe(&breakpoint_store, |this, _, event, cx| match event {
                     BreakpointStoreEvent::BreakpointsUpdated(path, reason) => {
-                        if let Some(local) = (!this.ignore_breakpoints)
-                            .then(|| this.as_local_mut())
-                            .flatten()
-                        {
-                            local
-                                .send_breakpoints_from_path(path.clone(), *reason, cx)
+                        if !this.ignore_breakpoints {
+                            this.send_breakpoints_from_path(path.clone(), *reason, cx)
                                 .detach();
-                        };
+                        }
+                        cx.emit(SessionEvent::Breakpoints);
                     }
                     BreakpointStoreEvent::ActiveDebugLineChanged => {}
                 })
@@ -760,6 +826,10 @@ impl Session {
                     thread_states: ThreadStates::default(),
                     output_token: OutputToken(0),
                     ignore_breakpoints: false,
+                    breakpoint_verification: HashMap::default(),
+                    breakpoint_ids: HashMap::default(),
+                    data_breakpoints: Vec::new(),
+                    exception_filters: None,
                     output: circular_buffer::CircularBuffer::boxed(),
                     requests: HashMap::default(),
                     modules: Vec::default(),
@@ -767,8 +837,11 @@ impl Session {
                     threads: IndexMap::default(),
                     stack_frames: IndexMap::default(),
                     locations: Default::default(),
+                    fetched_sources: HashMap::default(),
+                    source_buffers: HashMap::default(),
                     _background_tasks,
                     is_session_terminated: false,
+                    last_stop: None,
                 }
             })
         })
@@ -789,6 +862,10 @@ impl Session {
             parent_id: None,
             capabilities: Capabilities::default(),
             ignore_breakpoints,
+            breakpoint_verification: HashMap::default(),
+            breakpoint_ids: HashMap::default(),
+            data_breakpoints: Vec::new(),
+            exception_filters: None,
             variables: Default::default(),
             stack_frames: Default::default(),
             thread_states: ThreadStates::default(),
@@ -801,7 +878,10 @@ impl Session {
             threads: IndexMap::default(),
             _background_tasks: Vec::default(),
             locations: Default::default(),
+            fetched_sources: HashMap::default(),
+            source_buffers: HashMap::default(),
             is_session_terminated: false,
+            last_stop: None,
         }
     }
 
@@ -829,6 +909,11 @@ impl Session {
         self.is_session_terminated
     }
 
+    /// Why the debuggee last stopped, e.g. on a breakpoint or an exception, while it's stopped.
+    pub fn last_stop(&self) -> Option<&StoppedEvent> {
+        self.last_stop.as_ref()
+    }
+
     pub fn is_local(&self) -> bool {
         matches!(self.mode, Mode::Local(_))
     }
@@ -853,9 +938,12 @@ impl Session {
         cx: &mut Context<Self>,
     ) -> Task<Result<()>> {
         match &self.mode {
-            Mode::Local(local_mode) => {
-                local_mode.initialize_sequence(&self.capabilities, initialize_rx, cx)
-            }
+            Mode::Local(local_mode) => local_mode.initialize_sequence(
+                &self.capabilities,
+                initialize_rx,
+                cx.weak_entity(),
+                cx,
+            ),
             Mode::Remote(_) => Task::ready(Err(anyhow!("cannot initialize remote session"))),
         }
     }
@@ -930,6 +1018,7 @@ impl Session {
         self.invalidate_generic();
         self.threads.clear();
         self.variables.clear();
+        self.last_stop = Some(event.clone());
         cx.emit(SessionEvent::Stopped(
             event
                 .thread_id
@@ -957,13 +1046,21 @@ impl Session {
                 }
                 // todo(debugger): We should be able to get away with only invalidating generic if all threads were continued
                 self.invalidate_generic();
+                self.last_stop = None;
+                cx.emit(SessionEvent::Continued);
+                cx.notify();
             }
-            Events::Exited(_event) => {
+            Events::Exited(event) => {
                 self.clear_active_debug_line(cx);
+                cx.emit(SessionEvent::Exited {
+                    exit_code: event.exit_code,
+                });
             }
             Events::Terminated(_) => {
                 self.is_session_terminated = true;
                 self.clear_active_debug_line(cx);
+                cx.emit(SessionEvent::Terminated);
+                cx.notify();
             }
             Events::Thread(event) => {
                 let thread_id = ThreadId(event.thread_id);
@@ -983,19 +1080,22 @@ impl Session {
                 cx.notify();
             }
             Events::Output(event) => {
-                if event
-                    .category
-                    .as_ref()
-                    .is_some_and(|category| *category == OutputEventCategory::Telemetry)
-                {
-                    return;
+                self.push_output(event, cx);
+            }
+            Events::Breakpoint(event) => {
+                // Adapters may only verify a breakpoint later on, e.g. once its code is loaded.
+                // They refer to it by id, since they may also have moved it to another line.
+                let breakpoint = event.breakpoint;
+                let verified = breakpoint
+                    .id
+                    .and_then(|id| self.breakpoint_ids.get(&id))
+                    .and_then(|key| self.breakpoint_verification.get_mut(key));
+                if let Some(verified) = verified {
+                    *verified = breakpoint.verified;
+                    cx.emit(SessionEvent::Breakpoints);
+                    cx.notify();
                 }
-
-                self.output.push_back(event);
-                self.output_token.0 += 1;
-                cx.notify();
             }
-            Events::Breakpoint(_) => {}
             Events::Module(event) => {
                 match event.reason {
                     dap::ModuleEventReason::New => {
@@ -1018,8 +1118,34 @@ impl Session {
                 // todo(debugger): We should only send the invalidate command to downstream clients.
                 // self.invalidate_state(&ModulesCommand.into());
             }
-            Events::LoadedSource(_) => {
-                self.invalidate_state(&LoadedSourcesCommand.into());
+            Events::LoadedSource(event) => {
+                match event.reason {
+                    dap::LoadedSourceEventReason::New => {
+                        if !self
+                            .loaded_sources
+                            .iter()
+                            .any(|other| is_same_source(&event.source, other))
+                        {
+                            self.loaded_sources.push(event.source);
+                        }
+                    }
+                    dap::LoadedSourceEventReason::Changed => {
+                        if let Some(source) = self
+                            .loaded_sources
+                            .iter_mut()
+                            .find(|other| is_same_source(&event.source, other))
+                        {
+                            *source = event.source;
+                        }
+                    }
+                    dap::LoadedSourceEventReason::Removed => {
+                        self.loaded_sources
+                            .retain(|other| !is_same_source(&event.source, other));
+                    }
+                }
+
+                cx.emit(SessionEvent::LoadedSources);
+                cx.notify();
             }
             Events::Capabilities(event) => {
                 self.capabilities = self.capabilities.merge(event.capabilities);
@@ -1210,19 +1336,23 @@ impl Session {
         self.ignore_breakpoints
     }
 
-    pub fn toggle_ignore_breakpoints(&mut self, cx: &mut App) -> Task<()> {
+    pub fn toggle_ignore_breakpoints(&mut self, cx: &mut Context<Self>) -> Task<()> {
         self.set_ignore_breakpoints(!self.ignore_breakpoints, cx)
     }
 
-    pub(crate) fn set_ignore_breakpoints(&mut self, ignore: bool, cx: &mut App) -> Task<()> {
+    pub(crate) fn set_ignore_breakpoints(
+        &mut self,
+        ignore: bool,
+        cx: &mut Context<Self>,
+    ) -> Task<()> {
         if self.ignore_breakpoints == ignore {
             return Task::ready(());
         }
 
         self.ignore_breakpoints = ignore;
 
-        if let Some(local) = self.as_local() {
-            local.send_all_breakpoints(ignore, cx)
+        if self.as_local().is_some() {
+            self.send_all_breakpoints(cx)
         } else {
             // todo(debugger): We need to propagate this change to downstream sessions and send a message to upstream sessions
             unimplemented!()
@@ -1233,12 +1363,401 @@ impl Session {
         self.ignore_breakpoints
     }
 
+    /// Whether the adapter verified the breakpoint at `row`, or `None` if the adapter doesn't
+    /// have it, e.g. because it's disabled.
+    pub fn breakpoint_verified(&self, abs_path: &Arc<Path>, row: u32) -> Option<bool> {
+        self.breakpoint_verification
+            .get(&(abs_path.clone(), row))
+            .copied()
+    }
+
+    /// Sends the enabled breakpoints of a file to the adapter, or none while breakpoints are
+    /// ignored, and records which of them the adapter verified.
+    fn send_breakpoints_from_path(
+        &mut self,
+        abs_path: Arc<Path>,
+        reason: BreakpointUpdatedReason,
+        cx: &mut Context<Self>,
+    ) -> Task<()> {
+        let Some(local) = self.as_local() else {
+            return Task::ready(());
+        };
+
+        let breakpoints = if self.ignore_breakpoints {
+            Vec::new()
+        } else {
+            local
+                .breakpoint_store
+                .read(cx)
+                .breakpoints_from_path(&abs_path, cx)
+                .into_iter()
+                .filter(|breakpoint| breakpoint.state.is_enabled())
+                .map(|mut breakpoint| {
+                    // Adapters may reject conditions they don't support, so they're left out.
+                    if !self
+                        .capabilities
+                        .supports_conditional_breakpoints
+                        .unwrap_or_default()
+                    {
+                        breakpoint.condition = None;
+                    }
+                    if !self
+                        .capabilities
+                        .supports_hit_conditional_breakpoints
+                        .unwrap_or_default()
+                    {
+                        breakpoint.hit_condition = None;
+                    }
+                    breakpoint
+                })
+                .collect::<Vec<_>>()
+        };
+        let rows = breakpoints
+            .iter()
+            .map(|breakpoint| breakpoint.position)
+            .collect::<Vec<_>>();
+        let task = local.send_breakpoints(
+            &abs_path,
+            breakpoints,
+            matches!(reason, BreakpointUpdatedReason::FileSaved),
+            cx,
+        );
+
+        cx.spawn(|this, mut cx| async move {
+            let result = task.await;
+            this.update(&mut cx, |this, cx| {
+                this.breakpoint_verification
+                    .retain(|(path, _), _| *path != abs_path);
+                this.breakpoint_ids.retain(|_, (path, _)| *path != abs_path);
+                match result {
+                    Ok(breakpoints) => {
+                        for (row, breakpoint) in rows.into_iter().zip(breakpoints) {
+                            let key = (abs_path.clone(), row);
+                            if let Some(id) = breakpoint.id {
+                                this.breakpoint_ids.insert(id, key.clone());
+                            }
+                            this.breakpoint_verification
+                                .insert(key, breakpoint.verified);
+                        }
+                    }
+                    Err(err) => log::warn!("Set breakpoints request failed for path: {}", err),
+                }
+                cx.emit(SessionEvent::Breakpoints);
+                cx.notify();
+            })
+            .ok();
+        })
+    }
+
+    fn send_all_breakpoints(&mut self, cx: &mut Context<Self>) -> Task<()> {
+        let Some(local) = self.as_local() else {
+            return Task::ready(());
+        };
+
+        let paths = local
+            .breakpoint_store
+            .read(cx)
+            .all_breakpoints(cx)
+            .into_keys()
+            .collect::<Vec<_>>();
+        let tasks = paths
+            .into_iter()
+            .map(|path| self.send_breakpoints_from_path(path, BreakpointUpdatedReason::Toggled, cx))
+            .collect::<Vec<_>>();
+
+        let data_breakpoints =
+            (!self.data_breakpoints.is_empty()).then(|| self.send_data_breakpoints(cx));
+        let exception_breakpoints = (!self.exception_breakpoint_filters().is_empty())
+            .then(|| self.send_exception_breakpoints(cx));
+
+        cx.spawn(|_, _| async move {
+            futures::future::join_all(tasks).await;
+            if let Some(data_breakpoints) = data_breakpoints {
+                data_breakpoints.await.log_err();
+            }
+            if let Some(exception_breakpoints) = exception_breakpoints {
+                exception_breakpoints.await.log_err();
+            }
+        })
+    }
+
+    /// Disassembles `instruction_count` instructions starting `instruction_offset` instructions
+    /// away from `memory_reference`, such as a stack frame's instruction pointer.
+    pub fn disassemble(
+        &mut self,
+        memory_reference: String,
+        instruction_offset: i64,
+        instruction_count: u64,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<Vec<dap::DisassembledInstruction>>> {
+        if !self
+            .capabilities
+            .supports_disassemble_request
+            .unwrap_or_default()
+        {
+            return Task::ready(Err(anyhow!(
+                "This debug adapter doesn't support disassembly"
+            )));
+        }
+        let Some(local) = self.as_local() else {
+            return Task::ready(Err(anyhow!(
+                "Disassembly isn't supported for remote sessions yet"
+            )));
+        };
+
+        local.request(
+            dap_command::DisassembleCommand {
+                memory_reference,
+                instruction_offset,
+                instruction_count,
+            },
+            cx.background_executor().clone(),
+        )
+    }
+
+    pub fn data_breakpoints(&self) -> &[DataBreakpoint] {
+        &self.data_breakpoints
+    }
+
+    /// Sets a data breakpoint that stops when the variable `name` in the container
+    /// `variables_reference` changes, resolving to the adapter's explanation if the variable
+    /// can't be watched.
+    pub fn add_data_breakpoint(
+        &mut self,
+        variables_reference: u64,
+        name: String,
+        frame_id: Option<u64>,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<()>> {
+        if !self
+            .capabilities
+            .supports_data_breakpoints
+            .unwrap_or_default()
+        {
+            return Task::ready(Err(anyhow!(
+                "This debug adapter doesn't support data breakpoints"
+            )));
+        }
+        let Some(local) = self.as_local() else {
+            return Task::ready(Err(anyhow!(
+                "Data breakpoints aren't supported for remote sessions yet"
+            )));
+        };
+
+        let info = local.request(
+            dap_command::DataBreakpointInfoCommand {
+                variables_reference,
+                name: name.clone(),
+                frame_id,
+            },
+            cx.background_executor().clone(),
+        );
+        cx.spawn(|this, mut cx| async move {
+            let info = info.await?;
+            let Some(data_id) = info.data_id else {
+                return Err(anyhow!("{name} can't be watched: {}", info.description));
+            };
+
+            let result = this
+                .update(&mut cx, |this, cx| {
+                    if this
+                        .data_breakpoints
+                        .iter()
+                        .any(|breakpoint| breakpoint.data_id == data_id)
+                    {
+                        return Task::ready(Ok(()));
+                    }
+                    this.data_breakpoints.push(DataBreakpoint {
+                        data_id: data_id.clone(),
+                        description: info.description,
+                        access_type: preferred_access_type(info.access_types.as_deref()),
+                        verified: None,
+                    });
+                    this.send_data_breakpoints(cx)
+                })?
+                .await;
+
+            // Don't list a breakpoint that the adapter refused to set.
+            if result.is_err() {
+                this.update(&mut cx, |this, cx| {
+                    this.data_breakpoints
+                        .retain(|breakpoint| breakpoint.data_id != data_id);
+                    cx.emit(SessionEvent::Breakpoints);
+                    cx.notify();
+                })
+                .ok();
+            }
+            result
+        })
+    }
+
+    pub fn remove_data_breakpoint(
+        &mut self,
+        data_id: &str,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<()>> {
+        self.data_breakpoints
+            .retain(|breakpoint| breakpoint.data_id != data_id);
+        self.send_data_breakpoints(cx)
+    }
+
+    /// Replaces the adapter's data breakpoints with this session's, or with none while breakpoints
+    /// are ignored, and records which of them the adapter verified.
+    fn send_data_breakpoints(&mut self, cx: &mut Context<Self>) -> Task<Result<()>> {
+        cx.emit(SessionEvent::Breakpoints);
+        cx.notify();
+        let Some(local) = self.as_local() else {
+            return Task::ready(Ok(()));
+        };
+
+        let breakpoints = if self.ignore_breakpoints {
+            Vec::new()
+        } else {
+            self.data_breakpoints
+                .iter()
+                .map(|breakpoint| dap::DataBreakpoint {
+                    data_id: breakpoint.data_id.clone(),
+                    access_type: breakpoint.access_type.clone(),
+                    condition: None,
+                    hit_condition: None,
+                })
+                .collect::<Vec<_>>()
+        };
+        let data_ids = breakpoints
+            .iter()
+            .map(|breakpoint| breakpoint.data_id.clone())
+            .collect::<Vec<_>>();
+        let task = local.request(
+            dap_command::SetDataBreakpoints { breakpoints },
+            cx.background_executor().clone(),
+        );
+
+        cx.spawn(|this, mut cx| async move {
+            let result = task.await;
+            this.update(&mut cx, |this, cx| {
+                if let Ok(responses) = &result {
+                    for (data_id, response) in data_ids.iter().zip(responses) {
+                        if let Some(breakpoint) = this
+                            .data_breakpoints
+                            .iter_mut()
+                            .find(|breakpoint| &breakpoint.data_id == data_id)
+                        {
+                            breakpoint.verified = Some(response.verified);
+                        }
+                    }
+                }
+                cx.emit(SessionEvent::Breakpoints);
+                cx.notify();
+            })?;
+            result.map(|_| ())
+        })
+    }
+
+    /// The kinds of exceptions the adapter can break on.
+    pub fn exception_breakpoint_filters(&self) -> &[dap::ExceptionBreakpointsFilter] {
+        self.capabilities
+            .exception_breakpoint_filters
+            .as_deref()
+            .unwrap_or_default()
+    }
+
+    /// The exception filters turned on for this session, which are the adapter's defaults until
+    /// others are chosen.
+    pub fn exception_filters(&self) -> Vec<ExceptionFilter> {
+        match &self.exception_filters {
+            Some(filters) => filters.clone(),
+            None => self
+                .exception_breakpoint_filters()
+                .iter()
+                .filter(|filter| filter.default.unwrap_or_default())
+                .map(|filter| ExceptionFilter {
+                    filter_id: filter.filter.clone(),
+                    condition: None,
+                })
+                .collect(),
+        }
+    }
+
+    /// Turns on exactly these exception filters, replacing the adapter's, and remembers them for
+    /// later sessions of the same configuration.
+    pub fn set_exception_filters(
+        &mut self,
+        filters: Vec<ExceptionFilter>,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<()>> {
+        if let Some(local) = self.as_local() {
+            let key = exception_filters_key(&local.config);
+            let value = serde_json::to_string(&filters);
+            cx.background_spawn(async move {
+                KEY_VALUE_STORE.write_kvp(key, value?).await?;
+                anyhow::Ok(())
+            })
+            .detach_and_log_err(cx);
+        }
+        self.exception_filters = Some(filters);
+        self.send_exception_breakpoints(cx)
+    }
+
+    /// Replaces the exception filters the adapter breaks on with this session's, or with none
+    /// while breakpoints are ignored.
+    fn send_exception_breakpoints(&mut self, cx: &mut Context<Self>) -> Task<Result<()>> {
+        cx.emit(SessionEvent::Breakpoints);
+        cx.notify();
+        if self.exception_breakpoint_filters().is_empty() {
+            return Task::ready(Ok(()));
+        }
+        let Some(local) = self.as_local() else {
+            return Task::ready(Ok(()));
+        };
+
+        let filters = if self.ignore_breakpoints {
+            Vec::new()
+        } else {
+            self.exception_filters()
+        };
+        let supports_filter_options = self
+            .capabilities
+            .supports_exception_filter_options
+            .unwrap_or_default();
+        let command = if supports_filter_options {
+            dap_command::SetExceptionBreakpoints {
+                filters: Vec::new(),
+                filter_options: Some(
+                    filters
+                        .into_iter()
+                        .map(|filter| dap::ExceptionFilterOptions {
+                            filter_id: filter.filter_id,
+                            condition: filter.condition,
+                            mode: None,
+                        })
+                        .collect(),
+                ),
+            }
+        } else {
+            dap_command::SetExceptionBreakpoints {
+                filters: filters.into_iter().map(|filter| filter.filter_id).collect(),
+                filter_options: None,
+            }
+        };
+
+        local.request(command, cx.background_executor().clone())
+    }
+
     pub fn loaded_sources(&mut self, cx: &mut Context<Self>) -> &[Source] {
         self.fetch(
             dap_command::LoadedSourcesCommand,
             |this, result, cx| {
                 let result = result.log_err()?;
-                this.loaded_sources = result.iter().cloned().collect();
+                this.loaded_sources.clear();
+                for source in result.iter() {
+                    if !this
+                        .loaded_sources
+                        .iter()
+                        .any(|other| is_same_source(source, other))
+                    {
+                        this.loaded_sources.push(source.clone());
+                    }
+                }
                 cx.emit(SessionEvent::LoadedSources);
                 cx.notify();
                 Some(result)
@@ -1249,6 +1768,64 @@ impl Session {
         &self.loaded_sources
     }
 
+    /// Fetches the contents of a source that isn't available on disk, such as one the adapter
+    /// generated or decompiled. Sources with a source reference are only fetched once per session.
+    pub fn source_content(
+        &mut self,
+        source: Source,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<Arc<str>>> {
+        let Some(reference) = source.source_reference.filter(|reference| *reference > 0) else {
+            let task = self.request(
+                SourceContentCommand { source },
+                |_, result, _| result.log_err(),
+                cx,
+            );
+            return cx.background_executor().spawn(async move {
+                task.await
+                    .map(|response| Arc::from(response.content))
+                    .ok_or_else(|| anyhow!("failed to fetch source content"))
+            });
+        };
+
+        let task = if let Some(task) = self.fetched_sources.get(&reference) {
+            task.clone()
+        } else {
+            let request = self.request(
+                SourceContentCommand { source },
+                |_, result, _| result.log_err(),
+                cx,
+            );
+            let task = cx
+                .spawn(|this, mut cx| async move {
+                    let content = request.await.map(|response| Arc::from(response.content));
+                    if content.is_none() {
+                        // Let the next attempt fetch it again.
+                        this.update(&mut cx, |this, _| this.fetched_sources.remove(&reference))
+                            .ok();
+                    }
+                    content
+                })
+                .shared();
+            self.fetched_sources.insert(reference, task.clone());
+            task
+        };
+
+        cx.background_executor().spawn(async move {
+            task.await
+                .ok_or_else(|| anyhow!("failed to fetch source content"))
+        })
+    }
+
+    /// The buffer previously opened for the source with the given reference, if it's still alive.
+    pub fn source_buffer(&self, reference: u64) -> Option<Entity<Buffer>> {
+        self.source_buffers.get(&reference)?.upgrade()
+    }
+
+    pub fn set_source_buffer(&mut self, reference: u64, buffer: &Entity<Buffer>) {
+        self.source_buffers.insert(reference, buffer.downgrade());
+    }
+
     fn empty_response(&mut self, res: Result<()>, _cx: &mut Context<Self>) -> Option<()> {
         res.log_err()?;
         Some(())
@@ -1379,6 +1956,8 @@ impl Session {
 
     pub fn continue_thread(&mut self, thread_id: ThreadId, cx: &mut Context<Self>) {
         self.thread_states.continue_thread(thread_id);
+        // Adapters don't send `continued` for requests the client made.
+        self.last_stop = None;
         self.request(
             ContinueCommand {
                 args: ContinueArguments {
@@ -1392,6 +1971,14 @@ impl Session {
         .detach();
     }
 
+    /// What the debug adapter wrote to stderr, up to a limit. Empty for remote sessions.
+    pub fn adapter_stderr(&self) -> String {
+        match &self.mode {
+            Mode::Local(local) => local.client.stderr(),
+            Mode::Remote(_) => String::new(),
+        }
+    }
+
     pub fn adapter_client(&self) -> Option<Arc<DebugAdapterClient>> {
         match self.mode {
             Mode::Local(ref local) => Some(local.client.clone()),
@@ -1421,6 +2008,7 @@ impl Session {
         };
 
         self.thread_states.process_step(thread_id);
+        self.last_stop = None;
         self.request(
             command,
             Self::on_step_response::<NextCommand>(thread_id),
@@ -1451,6 +2039,7 @@ impl Session {
         };
 
         self.thread_states.process_step(thread_id);
+        self.last_stop = None;
         self.request(
             command,
             Self::on_step_response::<StepInCommand>(thread_id),
@@ -1481,6 +2070,7 @@ impl Session {
         };
 
         self.thread_states.process_step(thread_id);
+        self.last_stop = None;
         self.request(
             command,
             Self::on_step_response::<StepOutCommand>(thread_id),
@@ -1511,6 +2101,7 @@ impl Session {
         };
 
         self.thread_states.process_step(thread_id);
+        self.last_stop = None;
 
         self.request(
             command,
@@ -1578,6 +2169,45 @@ impl Session {
             .unwrap_or_default()
     }
 
+    /// The stack frames of every thread, once the ones that weren't fetched yet have loaded.
+    /// Frames already fetched, e.g. for the selected thread, aren't requested again.
+    pub fn all_stack_frames(
+        &mut self,
+        cx: &mut Context<Self>,
+    ) -> Task<Vec<(dap::Thread, Vec<StackFrame>)>> {
+        let thread_ids = self.threads.keys().copied().collect::<Vec<_>>();
+        let mut pending_requests = Vec::new();
+        for thread_id in &thread_ids {
+            self.stack_frames(*thread_id, cx);
+            let request = RequestSlot::from(StackTraceCommand {
+                thread_id: thread_id.0,
+                start_frame: None,
+                levels: None,
+            });
+            if let Some(task) = self
+                .requests
+                .get(&TypeId::of::<StackTraceCommand>())
+                .and_then(|requests| requests.get(&request))
+            {
+                pending_requests.push(task.clone());
+            }
+        }
+
+        cx.spawn(|this, mut cx| async move {
+            futures::future::join_all(pending_requests).await;
+            this.update(&mut cx, |this, cx| {
+                thread_ids
+                    .into_iter()
+                    .filter_map(|thread_id| {
+                        let thread = this.threads.get(&thread_id)?.dap.clone();
+                        Some((thread, this.stack_frames(thread_id, cx)))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+        })
+    }
+
     pub fn scopes(&mut self, stack_frame_id: u64, cx: &mut Context<Self>) -> &[dap::Scope] {
         if self.requests.contains_key(&TypeId::of::<ThreadsCommand>())
             && self
@@ -1651,32 +2281,75 @@ impl Session {
             .unwrap_or_default()
     }
 
+    /// Changes the value of a variable, resolving to the adapter's error if it rejects the new
+    /// value (e.g. because the variable is read-only).
     pub fn set_variable_value(
         &mut self,
         variables_reference: u64,
         name: String,
         value: String,
         cx: &mut Context<Self>,
-    ) {
-        if self.capabilities.supports_set_variable.unwrap_or_default() {
-            self.request(
-                SetVariableValueCommand {
-                    name,
-                    value,
-                    variables_reference,
-                },
-                move |this, response, cx| {
-                    let response = response.log_err()?;
+    ) -> Task<Result<()>> {
+        if !self.capabilities.supports_set_variable.unwrap_or_default() {
+            return Task::ready(Err(anyhow!(
+                "This debug adapter doesn't support setting variables"
+            )));
+        }
+
+        let (tx, rx) = oneshot::channel();
+        let request = self.request(
+            SetVariableValueCommand {
+                name,
+                value,
+                variables_reference,
+            },
+            move |this, response, cx| match response {
+                Ok(response) => {
                     this.invalidate_command_type::<VariablesCommand>();
                     cx.notify();
+                    tx.send(Ok(())).ok();
                     Some(response)
-                },
-                cx,
-            )
-            .detach()
+                }
+                Err(error) => {
+                    tx.send(Err(error)).ok();
+                    None
+                }
+            },
+            cx,
+        );
+
+        cx.background_executor().spawn(async move {
+            request.await;
+            rx.await?
+        })
+    }
+
+    fn push_output(&mut self, event: dap::OutputEvent, cx: &mut Context<Self>) {
+        self.output.push_back(event);
+        self.output_token.0 += 1;
+        cx.notify();
+    }
+
+    fn console_output(
+        output: String,
+        category: Option<dap::OutputEventCategory>,
+        variables_reference: Option<u64>,
+    ) -> dap::OutputEvent {
+        dap::OutputEvent {
+            category,
+            output,
+            group: None,
+            variables_reference,
+            source: None,
+            line: None,
+            column: None,
+            data: None,
+            location_reference: None,
         }
     }
 
+    /// Evaluates an expression typed into the console. The expression is echoed to the console,
+    /// followed by its result or, if the evaluation failed, the error.
     pub fn evaluate(
         &mut self,
         expression: String,
@@ -1685,6 +2358,15 @@ impl Session {
         source: Option<Source>,
         cx: &mut Context<Self>,
     ) {
+        self.push_output(
+            Self::console_output(
+                format!("> {expression}"),
+                Some(dap::OutputEventCategory::Console),
+                None,
+            ),
+            cx,
+        );
+
         self.request(
             EvaluateCommand {
                 expression,
@@ -1692,29 +2374,97 @@ impl Session {
                 frame_id,
                 source,
             },
-            |this, response, cx| {
-                let response = response.log_err()?;
-                this.output.push_back(dap::OutputEvent {
-                    category: None,
-                    output: response.result.clone(),
-                    group: None,
-                    variables_reference: Some(response.variables_reference),
-                    source: None,
-                    line: None,
-                    column: None,
-                    data: None,
-                    location_reference: None,
-                });
-
-                this.invalidate_command_type::<ScopesCommand>();
-                cx.notify();
-                Some(response)
+            |this, response, cx| match response {
+                Ok(response) => {
+                    this.push_output(
+                        Self::console_output(
+                            response.result.clone(),
+                            None,
+                            Some(response.variables_reference),
+                        ),
+                        cx,
+                    );
+                    this.invalidate_command_type::<ScopesCommand>();
+                    Some(response)
+                }
+                Err(error) => {
+                    this.push_output(
+                        Self::console_output(
+                            error.to_string(),
+                            Some(dap::OutputEventCategory::Stderr),
+                            None,
+                        ),
+                        cx,
+                    );
+                    None
+                }
             },
             cx,
         )
         .detach();
     }
 
+    /// Evaluates a watch expression in the given stack frame. Unlike [`Self::evaluate`], the result
+    /// isn't written to the console and errors are returned to the caller.
+    pub fn evaluate_watch(
+        &mut self,
+        expression: String,
+        frame_id: Option<u64>,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<dap::EvaluateResponse>> {
+        self.evaluate_quietly(expression, EvaluateArgumentsContext::Watch, frame_id, cx)
+    }
+
+    /// Evaluates an expression hovered in an editor, in the given stack frame. Like
+    /// [`Self::evaluate_watch`], the result isn't written to the console.
+    pub fn evaluate_hover(
+        &mut self,
+        expression: String,
+        frame_id: u64,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<dap::EvaluateResponse>> {
+        self.evaluate_quietly(
+            expression,
+            EvaluateArgumentsContext::Hover,
+            Some(frame_id),
+            cx,
+        )
+    }
+
+    fn evaluate_quietly(
+        &mut self,
+        expression: String,
+        context: EvaluateArgumentsContext,
+        frame_id: Option<u64>,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<dap::EvaluateResponse>> {
+        let (tx, rx) = oneshot::channel();
+        let request = self.request(
+            EvaluateCommand {
+                expression,
+                context: Some(context),
+                frame_id,
+                source: None,
+            },
+            move |_, response, _| match response {
+                Ok(response) => {
+                    tx.send(Ok(response.clone())).ok();
+                    Some(response)
+                }
+                Err(error) => {
+                    tx.send(Err(error)).ok();
+                    None
+                }
+            },
+            cx,
+        );
+
+        cx.background_executor().spawn(async move {
+            request.await;
+            rx.await?
+        })
+    }
+
     pub fn location(
         &mut self,
         reference: u64,
@@ -134,6 +134,9 @@ pub struct DebugAdapterConfig {
     pub initialize_args: Option<serde_json::Value>,
     /// Whether the debug adapter supports attaching to a running process.
     pub supports_attach: bool,
+    /// Whether the debuggee should stop at its entry point after launching or attaching
+    #[serde(default)]
+    pub stop_on_entry: bool,
 }
 
 /// Represents the type of the debugger adapter connection
@@ -164,6 +167,9 @@ pub struct DebugTaskDefinition {
     cwd: Option<String>,
     /// Additional initialization arguments to be sent on DAP initialization
     initialize_args: Option<serde_json::Value>,
+    /// Whether the debuggee should stop at its entry point after launching or attaching
+    #[serde(default)]
+    stop_on_entry: bool,
 }
 
 impl DebugTaskDefinition {
@@ -180,6 +186,7 @@ impl DebugTaskDefinition {
             cwd: cwd.clone(),
             initialize_args: self.initialize_args,
             supports_attach: true,
+            stop_on_entry: self.stop_on_entry,
         });
 
         let args: Vec<String> = Vec::new();
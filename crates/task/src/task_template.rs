@@ -113,6 +113,7 @@ mod deserialization_tests {
             supports_attach: false,
             cwd: None,
             initialize_args: None,
+            stop_on_entry: false,
         };
         let json = json!({
             "label": "test config",
@@ -299,6 +299,7 @@ pub struct Pane {
     >,
     can_split_predicate:
         Option<Arc<dyn Fn(&mut Self, &dyn Any, &mut Window, &mut Context<Self>) -> bool>>,
+    close_confirmation: Option<Arc<dyn Fn(&dyn ItemHandle, &App) -> Option<SharedString>>>,
     should_display_tab_bar: Rc<dyn Fn(&Window, &mut Context<Pane>) -> bool>,
     render_tab_bar_buttons: Rc<
         dyn Fn(
@@ -430,6 +431,7 @@ impl Pane {
             can_drop_predicate,
             custom_drop_handle: None,
             can_split_predicate: None,
+            close_confirmation: None,
             should_display_tab_bar: Rc::new(|_, cx| TabBarSettings::get_global(cx).show),
             render_tab_bar_buttons: Rc::new(move |pane, window, cx| {
                 if !pane.has_focus(window, cx) && !pane.context_menu_focused(window, cx) {
@@ -708,6 +710,15 @@ impl Pane {
         self.can_split_predicate = can_split_predicate;
     }
 
+    /// Sets a check that runs before an item is closed. When it returns a message, the user is
+    /// asked to confirm with that message and the item stays open if they cancel.
+    pub fn set_close_confirmation(
+        &mut self,
+        close_confirmation: Option<Arc<dyn Fn(&dyn ItemHandle, &App) -> Option<SharedString>>>,
+    ) {
+        self.close_confirmation = close_confirmation;
+    }
+
     pub fn set_close_pane_if_empty(&mut self, close_pane_if_empty: bool, cx: &mut Context<Self>) {
         self.close_pane_if_empty = close_pane_if_empty;
         cx.notify();
@@ -1623,6 +1634,29 @@ impl Pane {
                     })?;
                 }
 
+                if save_intent == SaveIntent::Close {
+                    let answer = pane.update_in(&mut cx, |pane, window, cx| {
+                        let message = pane
+                            .close_confirmation
+                            .as_ref()
+                            .and_then(|confirmation| confirmation(item_to_close.as_ref(), cx))?;
+                        let item_ix = pane.index_for_item(item_to_close.as_ref())?;
+                        pane.activate_item(item_ix, true, true, window, cx);
+                        Some(window.prompt(
+                            PromptLevel::Warning,
+                            &message,
+                            None,
+                            &["Close", "Cancel"],
+                            cx,
+                        ))
+                    })?;
+                    if let Some(answer) = answer {
+                        if !matches!(answer.await, Ok(0)) {
+                            break;
+                        }
+                    }
+                }
+
                 if should_save {
                     if !Self::save_item(
                         project.clone(),
@@ -12,7 +12,7 @@ use anyhow::{anyhow, bail, Context, Result};
 use client::DevServerProjectId;
 use db::{define_connection, query, sqlez::connection::Connection, sqlez_macros::sql};
 use gpui::{point, size, Axis, Bounds, WindowBounds, WindowId};
-use project::debugger::breakpoint_store::{BreakpointKind, SerializedBreakpoint};
+use project::debugger::breakpoint_store::{BreakpointKind, BreakpointState, SerializedBreakpoint};
 
 use language::{LanguageName, Toolchain};
 use project::WorktreeId;
@@ -147,6 +147,31 @@ impl Column for SerializedWindowBounds {
 pub struct Breakpoint {
     pub position: u32,
     pub kind: BreakpointKind,
+    pub condition: Option<Arc<str>>,
+    pub hit_condition: Option<Arc<str>>,
+    pub state: BreakpointState,
+}
+
+/// Wrapper for DB type of a breakpoint's state
+struct BreakpointStateWrapper(BreakpointState);
+
+impl StaticColumnCount for BreakpointStateWrapper {}
+
+impl Bind for BreakpointStateWrapper {
+    fn bind(&self, statement: &Statement, start_index: i32) -> anyhow::Result<i32> {
+        statement.bind(&self.0.to_int(), start_index)
+    }
+}
+
+impl Column for BreakpointStateWrapper {
+    fn column(statement: &mut Statement, start_index: i32) -> anyhow::Result<(Self, i32)> {
+        let state = match statement.column_int(start_index)? {
+            0 => BreakpointState::Enabled,
+            1 => BreakpointState::Disabled,
+            _ => return Err(anyhow::anyhow!("Invalid BreakpointState discriminant")),
+        };
+        Ok((BreakpointStateWrapper(state), start_index + 1))
+    }
 }
 
 /// Wrapper for DB type of a breakpoint
@@ -159,7 +184,7 @@ impl From<BreakpointKind> for BreakpointKindWrapper<'static> {
 }
 impl StaticColumnCount for BreakpointKindWrapper<'_> {
     fn column_count() -> usize {
-        1
+        2
     }
 }
 
@@ -184,8 +209,8 @@ impl Column for BreakpointKindWrapper<'_> {
         match kind {
             0 => Ok((BreakpointKind::Standard.into(), start_index + 2)),
             1 => {
-                let message = statement.column_text(start_index)?.to_string();
-                Ok((BreakpointKind::Log(message.into()).into(), start_index + 1))
+                let message = statement.column_text(start_index + 1)?.to_string();
+                Ok((BreakpointKind::Log(message.into()).into(), start_index + 2))
             }
             _ => Err(anyhow::anyhow!("Invalid BreakpointKind discriminant")),
         }
@@ -199,7 +224,7 @@ struct Breakpoints(Vec<Breakpoint>);
 
 impl sqlez::bindable::StaticColumnCount for Breakpoint {
     fn column_count() -> usize {
-        1 + BreakpointKindWrapper::column_count()
+        1 + BreakpointKindWrapper::column_count() + 2 + BreakpointStateWrapper::column_count()
     }
 }
 
@@ -210,10 +235,13 @@ impl sqlez::bindable::Bind for Breakpoint {
         start_index: i32,
     ) -> anyhow::Result<i32> {
         let next_index = statement.bind(&self.position, start_index)?;
-        statement.bind(
+        let next_index = statement.bind(
             &BreakpointKindWrapper(Cow::Borrowed(&self.kind)),
             next_index,
-        )
+        )?;
+        let next_index = statement.bind(&self.condition, next_index)?;
+        let next_index = statement.bind(&self.hit_condition, next_index)?;
+        statement.bind(&BreakpointStateWrapper(self.state), next_index)
     }
 }
 
@@ -224,11 +252,17 @@ impl Column for Breakpoint {
             .with_context(|| format!("Failed to read BreakPoint at index {start_index}"))?
             as u32;
         let (kind, next_index) = BreakpointKindWrapper::column(statement, start_index + 1)?;
+        let (condition, next_index) = Option::<Arc<str>>::column(statement, next_index)?;
+        let (hit_condition, next_index) = Option::<Arc<str>>::column(statement, next_index)?;
+        let (state, next_index) = BreakpointStateWrapper::column(statement, next_index)?;
 
         Ok((
             Breakpoint {
                 position,
                 kind: kind.0.into_owned(),
+                condition,
+                hit_condition,
+                state: state.0,
             },
             next_index,
         ))
@@ -249,10 +283,19 @@ impl Column for Breakpoints {
                         .with_context(|| format!("Failed to read BreakPoint at index {index}"))?
                         as u32;
                     let (kind, next_index) = BreakpointKindWrapper::column(statement, index + 1)?;
+                    let (condition, next_index) =
+                        Option::<Arc<str>>::column(statement, next_index)?;
+                    let (hit_condition, next_index) =
+                        Option::<Arc<str>>::column(statement, next_index)?;
+                    let (state, next_index) =
+                        BreakpointStateWrapper::column(statement, next_index)?;
 
                     breakpoints.push(Breakpoint {
                         position,
                         kind: kind.0.into_owned(),
+                        condition,
+                        hit_condition,
+                        state: state.0,
                     });
                     index = next_index;
                 }
@@ -338,6 +381,8 @@ define_connection! {
     //      breakpoint_location: Vec<u32>, // A list of the locations of breakpoints
     //      kind: int, // The kind of breakpoint (standard, log)
     //      log_message: String, // log message for log breakpoints, otherwise it's Null
+    //      condition: String, // The expression that must hold for the breakpoint to stop, or Null
+    //      hit_condition: String, // The hit count expression of the breakpoint, or Null
     // )
     pub static ref DB: WorkspaceDb<()> =
     &[
@@ -529,6 +574,13 @@ define_connection! {
                 ON UPDATE CASCADE
             );
         ),
+    sql!(
+        ALTER TABLE breakpoints ADD COLUMN condition TEXT;
+        ALTER TABLE breakpoints ADD COLUMN hit_condition TEXT;
+    ),
+    sql!(
+        ALTER TABLE breakpoints ADD COLUMN state INTEGER DEFAULT 0 NOT NULL;
+    ),
     ];
 }
 
@@ -684,7 +736,7 @@ impl WorkspaceDb {
     ) -> BTreeMap<Arc<Path>, Vec<SerializedBreakpoint>> {
         let breakpoints: Result<Vec<(PathBuf, Breakpoint)>> = self
             .select_bound(sql! {
-                SELECT path, breakpoint_location, kind
+                SELECT path, breakpoint_location, kind, log_message, condition, hit_condition, state
                 FROM breakpoints
                 WHERE workspace_id = ?
             })
@@ -706,6 +758,9 @@ impl WorkspaceDb {
                             position: breakpoint.position,
                             path,
                             kind: breakpoint.kind,
+                            condition: breakpoint.condition,
+                            hit_condition: breakpoint.hit_condition,
+                            state: breakpoint.state,
                         });
                 }
 
@@ -734,14 +789,17 @@ impl WorkspaceDb {
                     for bp in breakpoints {
                         let kind = BreakpointKindWrapper::from(bp.kind);
                         match conn.exec_bound(sql!(
-                            INSERT INTO breakpoints (workspace_id, path, breakpoint_location, kind, log_message)
-                            VALUES (?1, ?2, ?3, ?4, ?5);))?
+                            INSERT INTO breakpoints (workspace_id, path, breakpoint_location, kind, log_message, condition, hit_condition, state)
+                            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8);))?
 
                         ((
                             workspace.id,
                             path.as_ref(),
                             bp.position,
                             kind,
+                            bp.condition,
+                            bp.hit_condition,
+                            BreakpointStateWrapper(bp.state),
                         )) {
                             Ok(_) => {}
                             Err(err) => {
@@ -1405,11 +1463,25 @@ mod tests {
         let breakpoint = Breakpoint {
             position: 123,
             kind: BreakpointKind::Standard,
+            condition: None,
+            hit_condition: None,
+            state: BreakpointState::Enabled,
         };
 
         let log_breakpoint = Breakpoint {
             position: 456,
             kind: BreakpointKind::Log("Test log message".into()),
+            condition: None,
+            hit_condition: None,
+            state: BreakpointState::Disabled,
+        };
+
+        let conditional_breakpoint = Breakpoint {
+            position: 789,
+            kind: BreakpointKind::Standard,
+            condition: Some("count > 3".into()),
+            hit_condition: Some(">= 2".into()),
+            state: BreakpointState::Enabled,
         };
 
         let workspace = SerializedWorkspace {
@@ -1429,11 +1501,25 @@ mod tests {
                             position: breakpoint.position,
                             path: Arc::from(path),
                             kind: breakpoint.kind.clone(),
+                            condition: None,
+                            hit_condition: None,
+                            state: breakpoint.state,
                         },
                         SerializedBreakpoint {
                             position: log_breakpoint.position,
                             path: Arc::from(path),
                             kind: log_breakpoint.kind.clone(),
+                            condition: None,
+                            hit_condition: None,
+                            state: log_breakpoint.state,
+                        },
+                        SerializedBreakpoint {
+                            position: conditional_breakpoint.position,
+                            path: Arc::from(path),
+                            kind: conditional_breakpoint.kind.clone(),
+                            condition: conditional_breakpoint.condition.clone(),
+                            hit_condition: conditional_breakpoint.hit_condition.clone(),
+                            state: conditional_breakpoint.state,
                         },
                     ],
                 );
@@ -1448,13 +1534,32 @@ mod tests {
         let loaded = db.workspace_for_roots(&["/tmp"]).unwrap();
         let loaded_breakpoints = loaded.breakpoints.get(&Arc::from(path)).unwrap();
 
-        assert_eq!(loaded_breakpoints.len(), 2);
+        assert_eq!(loaded_breakpoints.len(), 3);
         assert_eq!(loaded_breakpoints[0].position, breakpoint.position);
         assert_eq!(loaded_breakpoints[0].kind, breakpoint.kind);
         assert_eq!(loaded_breakpoints[1].position, log_breakpoint.position);
         assert_eq!(loaded_breakpoints[1].kind, log_breakpoint.kind);
+        assert_eq!(
+            loaded_breakpoints[1].kind.log_message(),
+            log_breakpoint.kind.log_message()
+        );
         assert_eq!(loaded_breakpoints[0].path, Arc::from(path));
         assert_eq!(loaded_breakpoints[1].path, Arc::from(path));
+        assert_eq!(loaded_breakpoints[0].condition, None);
+        assert_eq!(loaded_breakpoints[0].state, breakpoint.state);
+        assert_eq!(loaded_breakpoints[1].state, log_breakpoint.state);
+        assert_eq!(
+            loaded_breakpoints[2].position,
+            conditional_breakpoint.position
+        );
+        assert_eq!(
+            loaded_breakpoints[2].condition,
+            conditional_breakpoint.condition
+        );
+        assert_eq!(
+            loaded_breakpoints[2].hit_condition,
+            conditional_breakpoint.hit_condition
+        );
     }
 
     #[gpui::test]